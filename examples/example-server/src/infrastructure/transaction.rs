@@ -2,7 +2,7 @@ use async_trait::async_trait;
 use meshestra::error::MeshestraError;
 use meshestra::prelude::Injectable;
 use meshestra::transactional::{Transaction, TransactionManager, TransactionOptions};
-use sea_orm::{DatabaseConnection, DatabaseTransaction, TransactionTrait};
+use sea_orm::{DatabaseConnection, DatabaseTransaction, DbErr, RuntimeErr, TransactionTrait};
 use std::any::Any;
 use std::sync::Arc;
 
@@ -13,6 +13,18 @@ pub struct SeaOrmTransaction {
     pub inner: Option<DatabaseTransaction>,
 }
 
+impl SeaOrmTransaction {
+    /// The underlying SeaORM transaction, to run queries on.
+    /// Fails if the transaction has already been committed or rolled back.
+    pub fn connection(&self) -> Result<&DatabaseTransaction, DbErr> {
+        self.inner.as_ref().ok_or_else(|| {
+            DbErr::Conn(RuntimeErr::Internal(
+                "Transaction already finalized".to_string(),
+            ))
+        })
+    }
+}
+
 #[async_trait]
 impl Transaction for SeaOrmTransaction {
     /// Commits the transaction to the database.
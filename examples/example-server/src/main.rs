@@ -1,4 +1,3 @@
-use axum::Router;
 use meshestra::prelude::*;
 use std::sync::Arc;
 use std::time::Duration;
@@ -11,11 +10,8 @@ mod infrastructure;
 mod modules;
 
 use app_module::AppModule;
-use infrastructure::transaction::SeaOrmTransactionManager;
-use modules::{
-    product::{product_entity, ProductController},
-    user::{domain::user_entity, UserController},
-};
+use infrastructure::database::PersistenceModule;
+use modules::{product::product_entity, user::domain::user_entity};
 
 #[derive(Clone)]
 struct AppState {
@@ -54,58 +50,43 @@ async fn main() {
         .expect("Failed to synchronize database schema");
     tracing::info!("✅ Database schema synchronized.");
 
-    // 3. Container 구축 (E0382 Move 에러 해결)
-    // .register()가 self를 소모하므로 체이닝을 끝까지 이어가거나 변수를 갱신해야 합니다.
-    let mut container = ContainerBuilder::new().register(db.clone()).build(); // 여기서 소유권 흐름이 깔끔하게 마무리됩니다.
-
-    // 4. 의존성 주입으로 TransactionManager 생성 및 등록
-    // 이미 빌드된 container에서 DatabaseConnection을 찾아 SeaOrmTransactionManager를 만듭니다.
-    let transaction_manager = SeaOrmTransactionManager::inject(&container)
-        .expect("Failed to inject SeaOrmTransactionManager");
-
-    // 주입된 매니저를 컨테이너에 다시 등록
-    container.register(transaction_manager);
-
-    // 5. AppModule 등록 (Interface Binding)
-    // 이제 컨테이너 안에 SeaOrmTransactionManager가 들어있으므로 바인딩이 성공합니다.
-    AppModule::register(&mut container).expect("Failed to register AppModule");
-
-    let shared_container = Arc::new(container);
-
-    // 6. Application 빌드
+    // 3. Container 구축
+    // 연결된 DatabaseConnection을 설정으로 PersistenceModule을 등록한 뒤
+    // AppModule을 등록합니다. AppModule은 dyn TransactionManager를 주입받을 수 있습니다.
+    let container = ContainerBuilder::new()
+        .register(EventBus::new())
+        .module_dyn(PersistenceModule::for_root(db.clone()))
+        .expect("Failed to register PersistenceModule")
+        .module::<AppModule>()
+        .expect("Failed to register AppModule")
+        .try_build()
+        .expect("Failed to build the container");
+
+    // 4. Application 빌드
     let app = Application::builder()
-        .container((*shared_container).clone())
+        .container(container)
+        .module::<AppModule>()
         .init_timeout(Duration::from_secs(30))
+        .openapi(OpenApiConfig::new("Example Server", "0.1.0"))
         .build()
         .await
         .expect("Failed to initialize application");
 
-    // 7. Axum 설정 및 컨트롤러 주입
+    // 5. AppModule의 컨트롤러로 라우터 구성
     let state = AppState {
-        container: shared_container.clone(),
+        container: app.container().clone(),
     };
 
-    let user_controller =
-        Arc::new(UserController::inject(state.get_container()).expect("User injection failed"));
-    let product_controller = Arc::new(
-        ProductController::inject(state.get_container()).expect("Product injection failed"),
-    );
-
-    let router = Router::new()
-        .nest(
-            UserController::base_path(),
-            UserController::router(user_controller),
-        )
-        .nest(
-            ProductController::base_path(),
-            ProductController::router(product_controller),
-        )
+    let router = app
+        .router::<AppModule, AppState>()
+        .expect("Failed to build the router")
         .with_state(state);
 
-    // 8. 서버 실행
+    // 6. 서버 실행
     let addr = "0.0.0.0:3000";
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
     tracing::info!("✅ Server running on http://127.0.0.1:3000");
+    tracing::info!("📖 API docs on http://127.0.0.1:3000/docs");
 
     axum::serve(listener, router)
         .with_graceful_shutdown(async move {
@@ -1,2 +1,3 @@
-pub mod user;
 pub mod product;
+pub mod upload;
+pub mod user;
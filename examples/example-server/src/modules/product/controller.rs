@@ -10,18 +10,21 @@ pub struct ProductController {
 
 #[routes(ProductController)]
 impl ProductController {
+    /// Create a product
     #[post("/")]
     pub async fn create(&self, #[body] req: CreateProductRequest) -> Result<Json<Product>> {
         let product = self.service.create(req).await?;
         Ok(Json(product))
     }
 
+    /// Get a product by id
     #[get("/{id}")]
     pub async fn get_one(&self, #[param] id: String) -> Result<Json<Product>> {
         let product = self.service.get(id).await?;
         Ok(Json(product))
     }
 
+    /// List every product
     #[get("/all")]
     pub async fn list(&self) -> Result<Json<Vec<Product>>> {
         let products = self.service.list().await?;
@@ -1,13 +1,14 @@
+use meshestra::ApiSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ApiSchema)]
 pub struct Product {
     pub id: String,
     pub name: String,
     pub price: f64,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ApiSchema)]
 pub struct CreateProductRequest {
     pub name: String,
     pub price: f64,
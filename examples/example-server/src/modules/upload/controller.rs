@@ -0,0 +1,40 @@
+use meshestra::prelude::*;
+use serde::Serialize;
+
+/// Avatars are small; anything bigger is refused with a 413
+const MAX_AVATAR_BYTES: usize = 512 * 1024;
+
+#[derive(FromMultipart)]
+pub struct AvatarUpload {
+    user_id: String,
+    #[part(name = "file", limit = MAX_AVATAR_BYTES)]
+    avatar: UploadedFile,
+    caption: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct UploadReceipt {
+    user_id: String,
+    file_name: Option<String>,
+    content_type: Option<String>,
+    size: usize,
+    caption: Option<String>,
+}
+
+#[controller(path = "/uploads")]
+pub struct UploadController {}
+
+#[routes(UploadController)]
+impl UploadController {
+    /// `curl -F user_id=1 -F file=@avatar.png http://127.0.0.1:3000/uploads/avatar`
+    #[post("/avatar")]
+    pub async fn avatar(&self, #[multipart] upload: AvatarUpload) -> Json<UploadReceipt> {
+        Json(UploadReceipt {
+            user_id: upload.user_id,
+            file_name: upload.avatar.file_name,
+            content_type: upload.avatar.content_type,
+            size: upload.avatar.bytes.len(),
+            caption: upload.caption,
+        })
+    }
+}
@@ -0,0 +1,8 @@
+use meshestra::prelude::*;
+
+pub mod controller;
+
+pub use controller::UploadController;
+
+#[module(controllers = [UploadController])]
+pub struct UploadModule;
@@ -1,4 +1,4 @@
-use crate::modules::user::domain::{CreateUserRequest, User};
+use crate::modules::user::domain::{CreateUserRequest, User, UserCreatedEvent};
 use crate::modules::user::service::UserService;
 use meshestra::prelude::*;
 use std::sync::Arc;
@@ -6,10 +6,12 @@ use std::sync::Arc;
 #[controller(path = "/users")]
 pub struct UserController {
     service: Arc<UserService>,
+    events: Arc<EventBus>,
 }
 
 #[routes(UserController)]
 impl UserController {
+    /// Create a user
     #[post("/")]
     pub async fn create(&self, #[body] req: CreateUserRequest) -> Result<Json<User>> {
         // [수정] state.container를 쓸 필요 없이 주입된 self.service를 바로 사용합니다.
@@ -17,18 +19,27 @@ impl UserController {
         Ok(Json(user))
     }
 
+    /// Server-sent events for every user created from now on
+    #[get("/events")]
+    pub async fn events(&self) -> Sse<KeepAliveStream<EventStream>> {
+        self.events.sse_stream::<UserCreatedEvent>()
+    }
+
+    /// Get a user by id
     #[get("/{id}")]
     pub async fn get_one(&self, #[param] id: String) -> Result<Json<User>> {
         let user = self.service.get(id).await?;
         Ok(Json(user))
     }
 
+    /// List every user
     #[get("/all")]
     pub async fn list(&self) -> Result<Json<Vec<User>>> {
         let users = self.service.list().await?;
         Ok(Json(users))
     }
 
+    /// Create a user in a transaction of its own
     #[post("/transaction-test")]
     pub async fn create_transaction_test(
         &self,
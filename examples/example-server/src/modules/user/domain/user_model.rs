@@ -1,13 +1,21 @@
+use meshestra::ApiSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ApiSchema)]
 pub struct User {
     pub id: String,
     pub name: String,
     pub email: String,
 }
 
-#[derive(Debug, Deserialize)]
+/// Published on the `EventBus` when a user is created
+#[derive(Debug, Clone, Serialize)]
+pub struct UserCreatedEvent {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize, ApiSchema)]
 pub struct CreateUserRequest {
     pub name: String,
     pub email: String,
@@ -1,4 +1,4 @@
-use crate::modules::user::domain::{CreateUserRequest, User};
+use crate::modules::user::domain::{CreateUserRequest, User, UserCreatedEvent};
 use crate::modules::user::repository::UserRepository;
 use meshestra::prelude::*;
 use meshestra::transactional::TransactionManager;
@@ -8,10 +8,11 @@ use std::sync::Arc;
 pub struct UserService {
     repository: Arc<dyn UserRepository>, // TODO: 여기가 Inject 가 안되는 이유를 설명해봐
     transaction_manager: Arc<dyn TransactionManager>,
+    events: Arc<EventBus>,
 }
 
 impl UserService {
-    #[transactional]
+    #[transactional(manager = self.transaction_manager)]
     pub async fn create_user(&self, req: CreateUserRequest) -> Result<User> {
         // 1. [수정] ActiveModel이 아닌 일반 User 구조체를 생성합니다.
         let user = User {
@@ -24,6 +25,10 @@ impl UserService {
         // 이제 "expected &User, found ActiveModel" 에러가 사라집니다.
         let saved_user = self.repository.save(&user).await?;
 
+        self.events.publish(UserCreatedEvent {
+            id: saved_user.id.clone(),
+            name: saved_user.name.clone(),
+        });
         Ok(saved_user)
     }
 
@@ -0,0 +1,256 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::punctuated::Punctuated;
+use syn::{parse_macro_input, parse_quote, Attribute, Data, DeriveInput, Fields, Token};
+
+pub fn derive_api_schema(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    generate_api_schema_impl(&input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// What a type's or a field's `#[serde(...)]` attributes change in its
+/// schema
+#[derive(Default)]
+struct SerdeAttrs {
+    rename: Option<String>,
+    rename_all: Option<String>,
+    skip: bool,
+    default: bool,
+}
+
+fn generate_api_schema_impl(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let type_name = name.to_string();
+    let container = serde_attrs(&input.attrs)?;
+    let description = option_tokens(doc_text(&input.attrs));
+
+    // Generic types are inlined: one component per instantiation would need
+    // a name for each.
+    let mut generics = input.generics.clone();
+    for param in generics.type_params_mut() {
+        param
+            .bounds
+            .push(parse_quote!(::meshestra::openapi::ApiSchema));
+    }
+    let generic = input.generics.type_params().next().is_some();
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let schema = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => {
+                let mut properties = Vec::new();
+                for field in &fields.named {
+                    let serde = serde_attrs(&field.attrs)?;
+                    if serde.skip || is_hidden(&field.attrs)? {
+                        continue;
+                    }
+                    let ident = field.ident.as_ref().unwrap().to_string();
+                    let ident = ident.strip_prefix("r#").unwrap_or(&ident);
+                    let property = match (serde.rename, &container.rename_all) {
+                        (Some(rename), _) => rename,
+                        (None, Some(rule)) => {
+                            rename_case(ident, rule).ok_or_else(|| unknown_case(input, rule))?
+                        }
+                        (None, None) => ident.to_string(),
+                    };
+                    let ty = &field.ty;
+                    let required = !serde.default && !container.default && !is_option_type(ty);
+                    let field_description = option_tokens(doc_text(&field.attrs));
+                    properties.push(quote! {
+                        ::meshestra::openapi::Property {
+                            name: #property,
+                            schema: <#ty as ::meshestra::openapi::ApiSchema>::schema(__components),
+                            description: #field_description,
+                            required: #required,
+                        }
+                    });
+                }
+                quote! {
+                    ::meshestra::openapi::object_schema(#description, vec![#(#properties),*])
+                }
+            }
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                let ty = &fields.unnamed[0].ty;
+                return Ok(quote! {
+                    impl #impl_generics ::meshestra::openapi::ApiSchema for #name #ty_generics #where_clause {
+                        fn schema(__components: &mut ::meshestra::openapi::Components) -> ::meshestra::openapi::Value {
+                            <#ty as ::meshestra::openapi::ApiSchema>::schema(__components)
+                        }
+                    }
+                });
+            }
+            _ => return Err(unsupported(input)),
+        },
+        Data::Enum(data) => {
+            let mut variants = Vec::new();
+            for variant in &data.variants {
+                if !matches!(variant.fields, Fields::Unit) {
+                    return Err(syn::Error::new_spanned(
+                        variant,
+                        "#[derive(ApiSchema)] only supports enums of unit variants",
+                    ));
+                }
+                let serde = serde_attrs(&variant.attrs)?;
+                if serde.skip || is_hidden(&variant.attrs)? {
+                    continue;
+                }
+                let ident = variant.ident.to_string();
+                variants.push(match (serde.rename, &container.rename_all) {
+                    (Some(rename), _) => rename,
+                    (None, Some(rule)) => {
+                        rename_case(&ident, rule).ok_or_else(|| unknown_case(input, rule))?
+                    }
+                    (None, None) => ident,
+                });
+            }
+            quote! {
+                ::meshestra::openapi::enum_schema(#description, &[#(#variants),*])
+            }
+        }
+        Data::Union(_) => return Err(unsupported(input)),
+    };
+
+    let body = if generic {
+        schema
+    } else {
+        quote! {
+            __components.define(#type_name, |__components| #schema)
+        }
+    };
+    Ok(quote! {
+        impl #impl_generics ::meshestra::openapi::ApiSchema for #name #ty_generics #where_clause {
+            fn schema(__components: &mut ::meshestra::openapi::Components) -> ::meshestra::openapi::Value {
+                #body
+            }
+        }
+    })
+}
+
+fn unsupported(input: &DeriveInput) -> syn::Error {
+    syn::Error::new_spanned(
+        input,
+        "#[derive(ApiSchema)] supports structs with named fields, newtypes and enums of unit variants",
+    )
+}
+
+fn unknown_case(input: &DeriveInput, rule: &str) -> syn::Error {
+    syn::Error::new_spanned(input, format!("unknown `rename_all` rule `{}`", rule))
+}
+
+/// Whether the attributes have `#[api(hidden)]`
+fn is_hidden(attrs: &[Attribute]) -> syn::Result<bool> {
+    let Some(attr) = attrs.iter().find(|attr| attr.path().is_ident("api")) else {
+        return Ok(false);
+    };
+    match attr.parse_args::<syn::Ident>() {
+        Ok(flag) if flag == "hidden" => Ok(true),
+        _ => Err(syn::Error::new_spanned(attr, "expected `#[api(hidden)]`")),
+    }
+}
+
+/// The `#[serde(...)]` settings the schema follows; the others are ignored
+fn serde_attrs(attrs: &[Attribute]) -> syn::Result<SerdeAttrs> {
+    let mut serde = SerdeAttrs::default();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+        let metas = attr.parse_args_with(Punctuated::<syn::Meta, Token![,]>::parse_terminated)?;
+        for meta in metas {
+            let Some(name) = meta.path().get_ident().map(ToString::to_string) else {
+                continue;
+            };
+            match (name.as_str(), &meta) {
+                ("rename", syn::Meta::NameValue(value)) => {
+                    serde.rename = string_value(&value.value)
+                }
+                ("rename_all", syn::Meta::NameValue(value)) => {
+                    serde.rename_all = string_value(&value.value)
+                }
+                ("skip" | "skip_serializing", _) => serde.skip = true,
+                ("default", _) => serde.default = true,
+                _ => {}
+            }
+        }
+    }
+    Ok(serde)
+}
+
+fn string_value(expr: &syn::Expr) -> Option<String> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(lit),
+            ..
+        }) => Some(lit.value()),
+        _ => None,
+    }
+}
+
+/// `ident` renamed as serde's `rename_all = rule` would
+fn rename_case(ident: &str, rule: &str) -> Option<String> {
+    let mut words: Vec<String> = Vec::new();
+    for part in ident.split('_').filter(|part| !part.is_empty()) {
+        let mut word = String::new();
+        for c in part.chars() {
+            if c.is_uppercase() && !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            word.extend(c.to_lowercase());
+        }
+        words.push(word);
+    }
+    let capitalized = |word: &String| {
+        let mut chars = word.chars();
+        chars
+            .next()
+            .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+            .unwrap_or_default()
+    };
+    Some(match rule {
+        "lowercase" => words.concat(),
+        "UPPERCASE" => words.concat().to_uppercase(),
+        "PascalCase" => words.iter().map(capitalized).collect(),
+        "camelCase" => words
+            .iter()
+            .enumerate()
+            .map(|(i, word)| {
+                if i == 0 {
+                    word.clone()
+                } else {
+                    capitalized(word)
+                }
+            })
+            .collect(),
+        "snake_case" => words.join("_"),
+        "SCREAMING_SNAKE_CASE" => words.join("_").to_uppercase(),
+        "kebab-case" => words.join("-"),
+        "SCREAMING-KEBAB-CASE" => words.join("-").to_uppercase(),
+        _ => return None,
+    })
+}
+
+/// The doc comment of an item, without the space after `///`
+fn doc_text(attrs: &[Attribute]) -> Option<String> {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(value) if value.path.is_ident("doc") => string_value(&value.value),
+            _ => None,
+        })
+        .map(|line| line.strip_prefix(' ').unwrap_or(&line).to_string())
+        .collect();
+    let text = lines.join("\n").trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+fn option_tokens(value: Option<String>) -> TokenStream2 {
+    match value {
+        Some(value) => quote! { Some(#value) },
+        None => quote! { None },
+    }
+}
+
+/// Whether `ty` is an `Option`
+fn is_option_type(ty: &syn::Type) -> bool {
+    matches!(ty, syn::Type::Path(type_path) if type_path.path.segments.last().is_some_and(|segment| segment.ident == "Option"))
+}
@@ -5,6 +5,17 @@ use syn::{parse_macro_input, Item};
 pub fn aspect_attribute(_attr: TokenStream, item: TokenStream) -> TokenStream {
     let input = parse_macro_input!(item as Item);
 
+    // `#[controller]` consumes controller-level aspects and `#[routes]` the
+    // method-level ones.
+    if let Item::Struct(item) = &input {
+        return syn::Error::new_spanned(
+            &item.ident,
+            "#[aspect] on a controller must be placed below #[controller]",
+        )
+        .to_compile_error()
+        .into();
+    }
+
     quote! {
         #input
     }
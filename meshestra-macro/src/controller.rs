@@ -1,6 +1,8 @@
 use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TokenStream2;
-use quote::quote;
+use quote::{quote, quote_spanned, ToTokens};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
 use syn::{
     parse::Parse, parse::ParseStream, parse_macro_input, Attribute, FnArg, ImplItem, ItemImpl,
     ItemStruct, LitStr, Token,
@@ -8,67 +10,476 @@ use syn::{
 
 struct ControllerArgs {
     path: String,
+    /// `auto_head = true`: answer HEAD requests with the GET routes
+    auto_head: bool,
+    /// `version = "v1"`: the API version every route belongs to, normalized
+    version: Option<String>,
+    /// `constructor = "new"`: inject through this associated function
+    constructor: Option<LitStr>,
 }
 
 impl Parse for ControllerArgs {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         let mut path = None;
+        let mut auto_head = false;
+        let mut version = None;
+        let mut constructor = None;
         while !input.is_empty() {
             let name: syn::Ident = input.parse()?;
             input.parse::<Token![=]>()?;
             if name == "path" {
                 let lit: LitStr = input.parse()?;
                 path = Some(lit.value());
+            } else if name == "auto_head" {
+                let lit: syn::LitBool = input.parse()?;
+                auto_head = lit.value;
+            } else if name == "version" {
+                let lit: LitStr = input.parse()?;
+                version = Some(normalize_version(&lit)?);
+            } else if name == "constructor" {
+                constructor = Some(input.parse::<LitStr>()?);
             } else {
-                let _: syn::Expr = input.parse()?;
+                return Err(syn::Error::new(
+                    name.span(),
+                    "expected one of `path`, `auto_head`, `version`, or `constructor`",
+                ));
             }
             if input.peek(Token![,]) {
                 input.parse::<Token![,]>()?;
             }
         }
-        Ok(ControllerArgs { path: path.unwrap_or_else(|| "/".to_string()) })
+        Ok(ControllerArgs {
+            path: path.unwrap_or_else(|| "/".to_string()),
+            auto_head,
+            version,
+            constructor,
+        })
     }
 }
 
 pub fn controller_attribute(attr: TokenStream, item: TokenStream) -> TokenStream {
     let args = parse_macro_input!(attr as ControllerArgs);
-    let input = parse_macro_input!(item as ItemStruct);
-    let expanded = generate_controller_impl(&args, &input);
-    TokenStream::from(expanded)
+    let item = parse_macro_input!(item as syn::Item);
+
+    let expanded = match item {
+        syn::Item::Struct(mut input) => take_controller_attrs(&mut input.attrs).and_then(|attrs| {
+            let struct_name = &input.ident;
+            let self_ty: syn::Type = syn::parse_quote!(#struct_name);
+            let injectable_impl = match &args.constructor {
+                Some(constructor) => generate_constructor_injectable(struct_name, constructor),
+                None => generate_injectable_for_controller(&input)?,
+            };
+            let controller_impl = generate_controller_impl(&args, &self_ty, &attrs);
+            Ok(quote! {
+                #input
+                #injectable_impl
+                #controller_impl
+            })
+        }),
+        // `#[controller]` on the impl block stands for `#[routes]` too; the
+        // struct derives `Injectable` itself, since its fields aren't visible
+        // from here.
+        syn::Item::Impl(_) if args.constructor.is_some() => Err(syn::Error::new_spanned(
+            &args.constructor,
+            "on the impl block, put `#[injectable(constructor = \"...\")]` next to `#[derive(Injectable)]`",
+        )),
+        syn::Item::Impl(mut input) => take_controller_attrs(&mut input.attrs).map(|attrs| {
+            let controller_impl = generate_controller_impl(&args, &input.self_ty, &attrs);
+            let routes_impl = generate_routes_impl(input);
+            quote! {
+                #controller_impl
+                #routes_impl
+            }
+        }),
+        other => Err(syn::Error::new_spanned(other, "`#[controller]` goes on a struct or on its impl block")),
+    };
+    TokenStream::from(expanded.unwrap_or_else(|e| e.to_compile_error()))
 }
 
-fn generate_controller_impl(args: &ControllerArgs, input: &ItemStruct) -> TokenStream2 {
-    let struct_name = &input.ident;
+/// The controller-level attributes below `#[controller]`
+struct ControllerAttrs {
+    guards: Vec<syn::Type>,
+    roles: Vec<LitStr>,
+    aspects: Vec<syn::Type>,
+    /// `#[interceptor(...)]`s: types resolved from the container, or
+    /// expressions building one
+    interceptors: Vec<syn::Expr>,
+    /// `#[middleware(...)]` layers, outermost first
+    middleware: Vec<syn::Expr>,
+    /// `#[cors(...)]`, as a `CorsOverride` expression
+    cors: Option<TokenStream2>,
+}
+
+/// Take the controller-level `#[guard(...)]`, `#[roles(...)]`,
+/// `#[aspect(...)]`, `#[interceptor(...)]`, `#[middleware(...)]` and
+/// `#[cors(...)]` below `#[controller]` off the item, so they are not
+/// expanded on their own; they apply to every route.
+fn take_controller_attrs(attrs: &mut Vec<Attribute>) -> syn::Result<ControllerAttrs> {
+    let mut taken = ControllerAttrs {
+        guards: Vec::new(),
+        roles: Vec::new(),
+        aspects: Vec::new(),
+        interceptors: Vec::new(),
+        middleware: Vec::new(),
+        cors: None,
+    };
+    for attr in attrs.iter() {
+        if attr.path().is_ident("guard") {
+            taken.guards.extend(parse_guard_list(attr)?);
+        } else if attr.path().is_ident("roles") {
+            taken.roles.extend(parse_roles_list(attr)?);
+        } else if attr.path().is_ident("aspect") {
+            taken.aspects.push(attr.parse_args::<syn::Type>()?);
+        } else if attr.path().is_ident("interceptor") {
+            taken.interceptors.extend(
+                attr.parse_args_with(Punctuated::<syn::Expr, Token![,]>::parse_terminated)?,
+            );
+        } else if attr.path().is_ident("middleware") {
+            taken.middleware.push(attr.parse_args::<syn::Expr>()?);
+        } else if attr.path().is_ident("cors") {
+            taken.cors = Some(parse_cors(attr)?);
+        }
+    }
+    attrs.retain(|attr| {
+        ![
+            "guard",
+            "roles",
+            "aspect",
+            "interceptor",
+            "middleware",
+            "cors",
+        ]
+        .iter()
+        .any(|name| attr.path().is_ident(name))
+    });
+    Ok(taken)
+}
+
+/// Parse `#[cors(origins = "https://a.example.com, https://*.example.com",
+/// methods = "GET, POST", headers = "X-Token", credentials = true,
+/// max_age = 600)]` into a `CorsOverride`; each part is optional
+fn parse_cors(attr: &Attribute) -> syn::Result<TokenStream2> {
+    let list = |lit: &LitStr| -> Vec<String> {
+        lit.value()
+            .split(',')
+            .map(|item| item.trim().to_string())
+            .filter(|item| !item.is_empty())
+            .collect()
+    };
+    let mut origins = None;
+    let mut methods = None;
+    let mut headers = None;
+    let mut credentials = None;
+    let mut max_age = None;
+    attr.parse_nested_meta(|meta| {
+        let name = meta
+            .path
+            .get_ident()
+            .map(|ident| ident.to_string())
+            .unwrap_or_default();
+        match name.as_str() {
+            "origins" => {
+                let lit: LitStr = meta.value()?.parse()?;
+                let values = list(&lit);
+                if values.is_empty() {
+                    return Err(syn::Error::new_spanned(lit, "expected at least one origin"));
+                }
+                origins = Some((lit, values));
+            }
+            "methods" => {
+                let lit: LitStr = meta.value()?.parse()?;
+                let values = list(&lit);
+                if values
+                    .iter()
+                    .any(|method| !method.chars().all(|c| c.is_ascii_alphabetic()))
+                {
+                    return Err(syn::Error::new_spanned(
+                        lit,
+                        "expected HTTP methods, e.g. `\"GET, POST\"`",
+                    ));
+                }
+                methods = Some(
+                    values
+                        .iter()
+                        .map(|method| method.to_ascii_uppercase())
+                        .collect::<Vec<_>>(),
+                );
+            }
+            "headers" => {
+                let lit: LitStr = meta.value()?.parse()?;
+                let values = list(&lit);
+                let token = |c: char| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c);
+                if values.iter().any(|header| !header.chars().all(token)) {
+                    return Err(syn::Error::new_spanned(
+                        lit,
+                        "not a list of valid header names",
+                    ));
+                }
+                headers = Some(
+                    values
+                        .iter()
+                        .map(|header| header.to_ascii_lowercase())
+                        .collect::<Vec<_>>(),
+                );
+            }
+            "credentials" => {
+                let lit: syn::LitBool = meta.value()?.parse()?;
+                credentials = Some(lit.value);
+            }
+            "max_age" => {
+                let lit: syn::LitInt = meta.value()?.parse()?;
+                max_age = Some(lit.base10_parse::<u64>()?);
+            }
+            _ => {
+                return Err(meta
+                    .error("expected `origins`, `methods`, `headers`, `credentials` or `max_age`"))
+            }
+        }
+        Ok(())
+    })?;
+    if let (Some((lit, values)), Some(true)) = (&origins, credentials) {
+        if values.iter().any(|origin| origin == "*") {
+            return Err(syn::Error::new_spanned(
+                lit,
+                "`credentials = true` needs the origins listed; browsers refuse credentials for any origin (`*`)",
+            ));
+        }
+    }
+    let strings = |values: Option<Vec<String>>| match values {
+        Some(values) => quote! { Some(&[#(#values),*]) },
+        None => quote! { None },
+    };
+    let origins = strings(origins.map(|(_, values)| values));
+    let methods = strings(methods);
+    let headers = strings(headers);
+    let credentials = match credentials {
+        Some(credentials) => quote! { Some(#credentials) },
+        None => quote! { None },
+    };
+    let max_age = match max_age {
+        Some(max_age) => quote! { Some(#max_age) },
+        None => quote! { None },
+    };
+    Ok(quote! {
+        ::meshestra::cors::CorsOverride {
+            origins: #origins,
+            methods: #methods,
+            headers: #headers,
+            credentials: #credentials,
+            max_age: #max_age,
+        }
+    })
+}
+
+/// Parse `#[guard(AuthGuard, RolesGuard)]` into its guard types
+pub(crate) fn parse_guard_list(attr: &Attribute) -> syn::Result<Vec<syn::Type>> {
+    let types = attr.parse_args_with(Punctuated::<syn::Type, Token![,]>::parse_terminated)?;
+    Ok(types.into_iter().collect())
+}
+
+/// Parse `#[roles("admin", "editor")]` into its role names
+pub(crate) fn parse_roles_list(attr: &Attribute) -> syn::Result<Vec<LitStr>> {
+    let names = attr.parse_args_with(Punctuated::<LitStr, Token![,]>::parse_terminated)?;
+    Ok(names.into_iter().collect())
+}
+
+fn generate_controller_impl(
+    args: &ControllerArgs,
+    struct_name: &syn::Type,
+    attrs: &ControllerAttrs,
+) -> TokenStream2 {
+    let ControllerAttrs {
+        guards,
+        roles,
+        aspects,
+        interceptors,
+        middleware,
+        cors,
+    } = attrs;
     let base_path = &args.path;
-    let injectable_impl = generate_injectable_for_controller(input);
-    let router_method = quote! {
+    let auto_head = args.auto_head;
+    let version = match &args.version {
+        Some(version) => quote! { Some(#version) },
+        None => quote! { None },
+    };
+    let cors = match cors {
+        Some(cors) => quote! {
+            static CORS: ::meshestra::cors::CorsOverride = #cors;
+            Some(&CORS)
+        },
+        None => quote! { None },
+    };
+    let guard_names = guards.iter().map(type_name);
+    let aspect_names = aspects.iter().map(type_name);
+
+    // A type is resolved from the container per request, a missing one
+    // answered like a missing `#[service]`; anything else is built once per
+    // router and shared by its routes.
+    let mut built = Vec::new();
+    let controller_interceptors: Vec<_> = interceptors
+        .iter()
+        .map(|interceptor| match interceptor {
+            syn::Expr::Path(path) if path.qself.is_none() => quote! {
+                container.resolve::<#path>()?
+                    as ::std::sync::Arc<dyn ::meshestra::interceptor::Interceptor>
+            },
+            expr => {
+                let index = built.len();
+                built.push(expr);
+                quote! { built[#index].clone() }
+            }
+        })
+        .collect();
+    // The last layer added is the outermost.
+    let middleware = middleware.iter().rev().map(|layer| {
+        quote_spanned! {layer.span()=>
+            .layer(::meshestra::controller::routing::middleware(#layer))
+        }
+    });
+    quote! {
         impl #struct_name {
             pub fn base_path() -> &'static str { #base_path }
+
+            /// Runs the controller-level `#[guard(...)]`s; called by `#[routes]`
+            #[doc(hidden)]
+            pub async fn __meshestra_controller_guards(
+                ctx: &::meshestra::guard::GuardContext<'_>,
+            ) -> ::meshestra::guard::GuardResult {
+                #( ::meshestra::guard::activate_guard::<#guards>(ctx).await?; )*
+                Ok(())
+            }
+
+            /// Whether GET routes answer HEAD requests; read by `#[routes]`
+            #[doc(hidden)]
+            pub fn __meshestra_controller_auto_head() -> bool {
+                #auto_head
+            }
+
+            /// The `version = "..."` the routes belong to, unless they override it
+            #[doc(hidden)]
+            pub fn __meshestra_controller_version() -> Option<&'static str> {
+                #version
+            }
+
+            /// The controller-level `#[cors(...)]`; read by `#[routes]`
+            #[doc(hidden)]
+            pub fn __meshestra_controller_cors() -> Option<&'static ::meshestra::cors::CorsOverride> {
+                #cors
+            }
+
+            /// Names of the controller-level `#[guard(...)]`s, for `route_table()`
+            #[doc(hidden)]
+            pub fn __meshestra_controller_guard_names() -> &'static [&'static str] {
+                &[#(#guard_names),*]
+            }
+
+            /// Names of the controller-level `#[aspect(...)]`s, for `route_table()`
+            #[doc(hidden)]
+            pub fn __meshestra_controller_aspect_names() -> &'static [&'static str] {
+                &[#(#aspect_names),*]
+            }
+
+            /// Roles required by the controller-level `#[roles(...)]`; read by `#[routes]`
+            #[doc(hidden)]
+            pub fn __meshestra_controller_roles() -> &'static [&'static str] {
+                &[#(#roles),*]
+            }
+
+            /// Builds the controller-level `#[interceptor(...)]`s given as
+            /// expressions; called by `#[routes]` once per router
+            #[doc(hidden)]
+            pub fn __meshestra_controller_built_interceptors() -> ::std::sync::Arc<[::std::sync::Arc<dyn ::meshestra::interceptor::Interceptor>]> {
+                let built: Vec<::std::sync::Arc<dyn ::meshestra::interceptor::Interceptor>> = vec![
+                    #(::std::sync::Arc::new(#built)),*
+                ];
+                built.into()
+            }
+
+            /// The controller-level `#[interceptor(...)]`s in listed order, the
+            /// `built` ones and the others resolved; called by `#[routes]`
+            #[doc(hidden)]
+            #[allow(unused_variables)]
+            pub fn __meshestra_controller_interceptors(
+                container: &::meshestra::Container,
+                built: &[::std::sync::Arc<dyn ::meshestra::interceptor::Interceptor>],
+            ) -> ::meshestra::Result<Vec<::std::sync::Arc<dyn ::meshestra::interceptor::Interceptor>>> {
+                Ok(vec![#(#controller_interceptors),*])
+            }
+
+            /// Adds the controller-level `#[middleware(...)]` layers to a
+            /// router of its routes; called by `#[routes]`
+            #[doc(hidden)]
+            pub fn __meshestra_controller_middleware<S>(router: ::axum::Router<S>) -> ::axum::Router<S>
+            where
+                S: Clone + Send + Sync + 'static,
+            {
+                router #(#middleware)*
+            }
+
+            /// Resolves the controller-level `#[aspect(...)]`s; called by `#[routes]`
+            #[doc(hidden)]
+            pub fn __meshestra_controller_aspects(
+                container: &::meshestra::Container,
+            ) -> ::meshestra::Result<Vec<::std::sync::Arc<dyn ::meshestra::interceptor::Interceptor>>> {
+                Ok(vec![
+                    #(
+                        ::std::sync::Arc::new(::meshestra::aspect::AspectInterceptor::from_arc(
+                            container.resolve::<#aspects>()?,
+                        )) as ::std::sync::Arc<dyn ::meshestra::interceptor::Interceptor>,
+                    )*
+                ])
+            }
         }
-    };
-    quote! {
-        #input
-        #injectable_impl
-        #router_method
     }
 }
 
-fn generate_injectable_for_controller(input: &ItemStruct) -> TokenStream2 {
+fn generate_injectable_for_controller(input: &ItemStruct) -> syn::Result<TokenStream2> {
     let struct_name = &input.ident;
     let fields = match &input.fields {
         syn::Fields::Named(fields) => &fields.named,
-        _ => panic!("#[controller] only supports structs with named fields"),
+        syn::Fields::Unnamed(fields) => {
+            return Err(syn::Error::new_spanned(
+                fields,
+                "#[controller] needs named fields, e.g. `struct UserController { service: Arc<UserService> }`",
+            ))
+        }
+        syn::Fields::Unit => {
+            return Err(syn::Error::new_spanned(
+                struct_name,
+                format!("#[controller] needs braces on a struct without fields: `struct {} {{}}`", struct_name),
+            ))
+        }
     };
     let field_injections = fields.iter().map(|field| {
         let field_name = &field.ident;
         let field_type = extract_injectable_type(&field.ty);
-        quote! { #field_name: container.resolve::<#field_type>()? }
+        quote! { #field_name: container.handle::<#field_type>()?.into() }
     });
-    quote! {
+    let dependencies = fields.iter().map(|field| {
+        let field_type = extract_injectable_type(&field.ty);
+        quote! { ::meshestra::di::Dependency::of::<#field_type>() }
+    });
+    Ok(quote! {
         impl ::meshestra::Injectable for #struct_name {
             fn inject(container: &::meshestra::Container) -> ::meshestra::Result<Self> {
                 Ok(Self { #(#field_injections),* })
             }
+
+            fn dependencies() -> ::std::vec::Vec<::meshestra::di::Dependency> {
+                ::std::vec![#(#dependencies),*]
+            }
+        }
+    })
+}
+
+/// `Injectable` through the `constructor = "..."` function
+fn generate_constructor_injectable(struct_name: &syn::Ident, constructor: &LitStr) -> TokenStream2 {
+    let construct = crate::injectable::constructor_injection(constructor);
+    quote! {
+        impl ::meshestra::Injectable for #struct_name {
+            fn inject(container: &::meshestra::Container) -> ::meshestra::Result<Self> {
+                #construct
+            }
         }
     }
 }
@@ -89,19 +500,115 @@ fn extract_injectable_type(ty: &syn::Type) -> syn::Type {
 }
 
 #[derive(Clone)]
-enum ParamKind { Body, Param, Query, Raw }
+enum ParamKind {
+    Body,
+    /// `#[form]`: an `application/x-www-form-urlencoded` body
+    Form,
+    /// `#[multipart]`: a `multipart/form-data` body
+    Multipart,
+    /// `#[param]`, or `#[param("repo_id")]` to pick a placeholder by name
+    Param(Option<LitStr>),
+    Query,
+    /// `#[header("X-Api-Key")]`
+    Header(LitStr),
+    /// `#[cookie("session")]`
+    Cookie(LitStr),
+    /// `#[host_param]`
+    Host,
+    /// `#[ip]`
+    Ip,
+    /// `#[service]`: an `Arc<T>` resolved from the container per request
+    Service,
+    /// `#[session]`: the request's `Session`, from the session layer
+    Session,
+    /// `#[csrf_token]`: the session's `CsrfToken`
+    CsrfToken,
+    /// `#[current_user]`: the `CurrentUser<T>`, or `Option<CurrentUser<T>>`,
+    /// granted by the guards
+    CurrentUser,
+    /// The `WebSocketSession` of a `#[ws]` route, from the upgrade request
+    WebSocket,
+    Raw,
+}
+
+impl ParamKind {
+    /// Read from the request parts (or the container) without an extractor,
+    /// so not a candidate for consuming the body
+    fn is_read_from_parts(&self) -> bool {
+        matches!(
+            self,
+            ParamKind::Header(_)
+                | ParamKind::Cookie(_)
+                | ParamKind::Host
+                | ParamKind::Ip
+                | ParamKind::Service
+                | ParamKind::Session
+                | ParamKind::CsrfToken
+                | ParamKind::CurrentUser
+                | ParamKind::WebSocket
+        )
+    }
+}
 
 struct ParamInfo {
     ty: syn::Type,
     kind: ParamKind,
+    /// `#[body(validate)]`: run `ValidationPipe` on the parsed body
+    validate: bool,
+    /// `#[pipe(...)]`, in the order they run
+    pipes: Vec<syn::Path>,
 }
 
-struct RouteInfo {
+/// A method and path a route answers: `#[get("/users/{id}")]`, or an alias
+struct Endpoint {
     method: String,
-    path: String,
+    path: LitStr,
+    /// The path as written, when it used `:id` for `{id}`
+    colon_path: Option<LitStr>,
+}
+
+struct RouteInfo {
+    /// Every method attribute and `alias`, in order
+    endpoints: Vec<Endpoint>,
     fn_name: syn::Ident,
     params: Vec<ParamInfo>,
     aspects: Vec<syn::Type>,
+    guards: Vec<syn::Type>,
+    /// `#[middleware(...)]` layers, outermost first
+    middleware: Vec<syn::Expr>,
+    /// `#[public]`: skip the controller-level guards
+    public: bool,
+    /// `#[roles(...)]`, replacing the controller-level roles
+    roles: Option<Vec<LitStr>>,
+    /// `#[timeout("2m")]`, in milliseconds
+    timeout_ms: Option<u64>,
+    /// `#[rate_limit(per_minute = 60)]`: requests per window of seconds
+    rate_limit: Option<(u32, u64)>,
+    /// `#[skip_global_pipes]`: don't run the application's global pipes
+    skip_global_pipes: bool,
+    /// `#[status(201)]`, replacing the default 200
+    status: Option<u16>,
+    /// `#[response_header("Cache-Control", "no-store")]`, name lowercased
+    response_headers: Vec<(LitStr, LitStr)>,
+    /// `#[produces("text/csv")]`
+    produces: Option<LitStr>,
+    /// `#[version("2")]`, normalized, replacing the controller's version
+    version: Option<String>,
+    /// The method returns `impl Stream`: served as server-sent events
+    streams: bool,
+    /// `#[ws("/chat")]`: the method runs on the upgraded connection
+    websocket: bool,
+    /// `#[csrf_exempt]`: skipped by the CSRF check
+    csrf_exempt: bool,
+    /// When the `#[param]`s share one `Path` of a tuple: for each
+    /// placeholder, in path order, the parameter bound to it, if any
+    path_slots: Option<Vec<Option<usize>>>,
+    /// `#[api(hidden)]`: left out of the OpenAPI document
+    hidden: bool,
+    /// The lines of the method's doc comment
+    docs: Vec<String>,
+    /// The return type, unless it's `()` or an `impl Trait`
+    output: Option<syn::Type>,
 }
 
 pub fn routes_attribute(_attr: TokenStream, item: TokenStream) -> TokenStream {
@@ -116,10 +623,16 @@ fn generate_routes_impl(input: ItemImpl) -> TokenStream2 {
 
     for item in input.items.iter() {
         if let ImplItem::Fn(method) = item {
-            if let Some(route_info) = extract_route_info(method) {
+            let route_info = match extract_route_info(method) {
+                Ok(route_info) => route_info,
+                Err(e) => return e.to_compile_error(),
+            };
+            if let Some(route_info) = route_info {
                 routes.push(route_info);
                 let mut clean_method = method.clone();
-                clean_method.attrs.retain(|attr| !is_http_method_attr(attr) && !attr.path().is_ident("aspect"));
+                clean_method
+                    .attrs
+                    .retain(|attr| !is_http_method_attr(attr) && !is_route_meta_attr(attr));
                 for input in clean_method.sig.inputs.iter_mut() {
                     if let FnArg::Typed(pat_type) = input {
                         pat_type.attrs.retain(|attr| !is_param_attr(attr));
@@ -134,162 +647,1611 @@ fn generate_routes_impl(input: ItemImpl) -> TokenStream2 {
         }
     }
 
-    let route_registrations = routes.iter().map(|route| {
-        let method_ident = match route.method.as_str() {
-            "GET" => quote! { ::axum::routing::get },
-            "POST" => quote! { ::axum::routing::post },
-            "PUT" => quote! { ::axum::routing::put },
-            "DELETE" => quote! { ::axum::routing::delete },
-            "PATCH" => quote! { ::axum::routing::patch },
-            _ => quote! { ::axum::routing::get },
-        };
-
-        let path = &route.path;
+    let controller_name = type_name(&input.self_ty);
+    let route_handlers: Vec<TokenStream2> = routes.iter().map(|route| {
         let fn_name = &route.fn_name;
         let aspects = &route.aspects;
 
-        let extractor_patterns: Vec<_> = route.params.iter().enumerate().map(|(i, p)| {
+        let guards = &route.guards;
+        let controller_guards = if route.public {
+            quote! {}
+        } else {
+            quote! {
+                if let Err(denied) = Self::__meshestra_controller_guards(&__guard_context).await {
+                    return denied.into_response();
+                }
+            }
+        };
+
+        // Method-level `#[roles]` replace the controller's; public routes
+        // skip the controller's like they skip its guards.
+        let required_roles = match &route.roles {
+            Some(roles) => Some(quote! { &[#(#roles),*] }),
+            None if route.public => None,
+            None => Some(quote! { Self::__meshestra_controller_roles() }),
+        };
+        let roles_guard = required_roles.map(|required_roles| quote! {
+            let __required_roles: &[&str] = #required_roles;
+            if !__required_roles.is_empty() {
+                let __roles_guard = ::meshestra::guard::RolesGuard::new(__required_roles);
+                if let Err(denied) = ::meshestra::guard::Guard::can_activate(&__roles_guard, &__guard_context).await {
+                    return denied.into_response();
+                }
+            }
+        });
+
+        // Extract the way axum does: every parameter but the last from the
+        // request parts, the last one (which may consume the body) from the
+        // whole request. Parameters read without an extractor don't count,
+        // so they may come after the body. Guards run first, so a denied
+        // request is never parsed.
+        let body_param = route.params.iter().rposition(|p| !p.kind.is_read_from_parts());
+        // With pipes, the request yields the first pipe's input.
+        let input_ty = |p: &ParamInfo| match p.pipes.first() {
+            Some(pipe) => quote_spanned! {pipe.span()=> <#pipe as ::meshestra::pipe::Pipe>::Input },
+            None => { let ty = &p.ty; quote! { #ty } }
+        };
+        let first_path_param = route.params.iter().position(|p| matches!(p.kind, ParamKind::Param(_)));
+        let mut extractions: Vec<_> = route.params.iter().enumerate().map(|(i, p)| {
             let temp_ident = quote::format_ident!("__p_{}", i);
-            let ty = &p.ty;
-            match p.kind {
-                ParamKind::Body => quote! { ::axum::Json(#temp_ident): ::axum::Json<#ty> },
-                ParamKind::Param => quote! { ::axum::extract::Path(#temp_ident): ::axum::extract::Path<#ty> },
-                ParamKind::Query => quote! { ::axum::extract::Query(#temp_ident): ::axum::extract::Query<#ty> },
-                ParamKind::Raw => quote! { #temp_ident: #ty },
+            let ty = input_ty(p);
+            // Headers, cookies, the host and the client address are read from
+            // the request parts; a missing required value is a 400.
+            let read = match &p.kind {
+                ParamKind::Header(name) => Some(quote! { header::<#ty>(&__parts, #name) }),
+                ParamKind::Cookie(name) => Some(quote! { cookie::<#ty>(&__parts, #name) }),
+                ParamKind::Host => Some(quote! { host::<#ty>(&__parts) }),
+                ParamKind::Ip => Some(quote! { client_ip::<#ty>(&__parts, __container) }),
+                _ => None,
+            };
+            // Services come from the container, `Arc<dyn Trait>`s through
+            // their registered caster; a missing one is a 500.
+            let service = matches!(p.kind, ParamKind::Service).then(|| match extract_injectable_type(&p.ty) {
+                ty @ syn::Type::TraitObject(_) => quote! { __container.resolve_trait::<#ty>() },
+                ty => quote! { __container.resolve::<#ty>() },
+            });
+            let (pattern, extractor) = match p.kind {
+                ParamKind::Body => (quote! { ::axum::Json(#temp_ident) }, quote! { ::axum::Json<#ty> }),
+                ParamKind::Form => (quote! { ::axum::extract::Form(#temp_ident) }, quote! { ::axum::extract::Form<#ty> }),
+                ParamKind::Param(_) => (quote! { ::axum::extract::Path(#temp_ident) }, quote! { ::axum::extract::Path<#ty> }),
+                ParamKind::Query => (quote! { ::axum::extract::Query(#temp_ident) }, quote! { ::axum::extract::Query<#ty> }),
+                _ => (quote! { #temp_ident }, quote! { #ty }),
+            };
+            let extracted = if matches!(p.kind, ParamKind::WebSocket) {
+                quote! {
+                    <::axum::extract::ws::WebSocketUpgrade as ::axum::extract::FromRequestParts<S>>::from_request_parts(&mut __parts, &__state).await
+                }
+            } else if matches!(p.kind, ParamKind::Session) {
+                quote! { ::meshestra::session::Session::from_parts(&__parts) }
+            } else if matches!(p.kind, ParamKind::CsrfToken) {
+                quote! { ::meshestra::csrf::CsrfToken::from_parts(&__parts) }
+            } else if let Some(read) = read {
+                quote! { ::meshestra::controller::extract::#read }
+            } else if let Some(service) = service {
+                service
+            } else if matches!(p.kind, ParamKind::Multipart) {
+                quote! {
+                    ::meshestra::controller::multipart::from_request::<#ty>(
+                        ::axum::http::Request::from_parts(__parts, __body),
+                    ).await
+                }
+            } else if body_param == Some(i) {
+                quote! {
+                    <#extractor as ::axum::extract::FromRequest<S, _>>::from_request(
+                        ::axum::http::Request::from_parts(__parts, __body),
+                        &__state,
+                    ).await
+                }
+            } else {
+                quote! {
+                    <#extractor as ::axum::extract::FromRequestParts<S>>::from_request_parts(&mut __parts, &__state).await
+                }
+            };
+            // The application's global pipes see bodies and queries first.
+            let global_pipes = (matches!(p.kind, ParamKind::Body | ParamKind::Form | ParamKind::Query) && !route.skip_global_pipes).then(|| quote! {
+                let #temp_ident = match ::meshestra::pipe::apply_global_pipes(__container, #temp_ident).await {
+                    Ok(value) => value,
+                    Err(rejected) => return rejected.into_response(),
+                };
+            });
+            // Invalid bodies are answered with a 422 before the handler runs,
+            // in the request's language.
+            let validation = p.validate.then(|| quote! {
+                let #temp_ident = match ::meshestra::pipe::Pipe::transform(
+                    &::meshestra::pipe::builtins::ValidationPipe::<#ty>::new().with_message_keys({
+                        use ::meshestra::i18n::{DerivedMessageKeys as _, NoMessageKeys as _};
+                        (&::meshestra::i18n::MessageKeysOf::<#ty>::new()).keys()
+                    }),
+                    #temp_ident,
+                ).await {
+                    Ok(value) => value,
+                    Err(invalid) => return invalid.localize(&__localizer).into_response(),
+                };
+            });
+            // Each pipe transforms the previous one's output; rejected
+            // values are answered without running the handler.
+            let pipes = p.pipes.iter().map(|pipe| quote_spanned! {pipe.span()=>
+                let #temp_ident = match ::meshestra::pipe::Pipe::transform(
+                    &*{
+                        // Only one of them applies to a given pipe.
+                        #[allow(unused_imports)]
+                        use ::meshestra::pipe::{ResolveFromContainer as _, ResolveOrDefault as _};
+                        match (&::meshestra::pipe::PipeSource::<#pipe>::new()).resolve(__container) {
+                            Ok(pipe) => pipe,
+                            Err(error) => return error.into_response(),
+                        }
+                    },
+                    #temp_ident,
+                ).await {
+                    Ok(value) => value,
+                    Err(rejected) => return rejected.into_response(),
+                };
+            });
+            let piped = p.pipes.last().map(|pipe| {
+                let param_ty = &p.ty;
+                quote_spanned! {pipe.span()=> let #temp_ident: #param_ty = #temp_ident; }
+            });
+            let extraction = match (&route.path_slots, &p.kind) {
+                // The `#[param]`s are deserialized together, as one tuple in
+                // placeholder order, at the first of them; the rest only run
+                // their pipes. Placeholders no parameter names are skipped.
+                (Some(slots), ParamKind::Param(_)) => (first_path_param == Some(i)).then(|| {
+                    let patterns = slots.iter().map(|slot| match slot {
+                        Some(j) => { let ident = quote::format_ident!("__p_{}", j); quote! { #ident } }
+                        None => quote! { _ },
+                    });
+                    let tys = slots.iter().map(|slot| match slot {
+                        Some(j) => input_ty(&route.params[*j]),
+                        None => quote! { ::std::string::String },
+                    });
+                    quote! {
+                        let ::axum::extract::Path((#(#patterns,)*)) = match <::axum::extract::Path<(#(#tys,)*)> as ::axum::extract::FromRequestParts<S>>::from_request_parts(&mut __parts, &__state).await {
+                            Ok(value) => value,
+                            Err(rejection) => return rejection.into_response(),
+                        };
+                    }
+                }),
+                _ => Some(quote! {
+                    let #pattern = match #extracted {
+                        Ok(value) => value,
+                        Err(rejection) => return rejection.into_response(),
+                    };
+                }),
+            };
+            quote! {
+                #extraction
+                #global_pipes
+                #validation
+                #(#pipes)*
+                #piped
             }
         }).collect();
+        // The parts are moved into the body's request, so it goes last.
+        if let Some(i) = body_param {
+            let body = extractions.remove(i);
+            extractions.push(body);
+        }
 
         let internal_args: Vec<_> = route.params.iter().enumerate().map(|(i, _)| {
             quote::format_ident!("__p_{}", i)
         }).collect();
 
-        if aspects.is_empty() {
+        // Pipes, services and the `#[ip]` settings are resolved per request,
+        // from the state moved into the handler.
+        let uses_container = route.websocket || route.params.iter().any(|p| {
+            !p.pipes.is_empty()
+                || p.validate
+                || matches!(p.kind, ParamKind::Ip | ParamKind::Service)
+                || (matches!(p.kind, ParamKind::Body | ParamKind::Form | ParamKind::Query) && !route.skip_global_pipes)
+        });
+        let pipe_container = uses_container.then(|| quote! {
+            let __container = ::meshestra::di::HasContainer::get_container(&__state);
+        });
+        // Read before the request is taken apart for the extractors.
+        let localizer = route.params.iter().any(|p| p.validate).then(|| quote! {
+            let __localizer = ::meshestra::i18n::Localizer::of(Some(__container), __request.headers());
+        });
+
+        let split_request = if route.params.is_empty() {
+            quote! {}
+        } else {
             quote! {
-                .route(#path, #method_ident({
-                    let controller = controller.clone();
-                    move |#(#extractor_patterns),*| {
-                        let controller = controller.clone();
-                        async move { 
-                            use ::axum::response::IntoResponse;
-                            controller.#fn_name(#(#internal_args),*).await.into_response()
-                        }
-                    }
-                }))
+                #[allow(unused_mut, unused_variables)]
+                let (mut __parts, __body) = __request.into_parts();
             }
-        } else {
+        };
+
+        // Extend (or shorten) the global TimeoutInterceptor's deadline, or
+        // enforce the route's own when there is none.
+        // Depends on the request, so it wraps the kept chain rather than
+        // joining it.
+        let route_timeout = route.timeout_ms.map(|ms| quote! {
+            let __timeout = ::std::time::Duration::from_millis(#ms);
+            if !::meshestra::interceptor::TimeoutOverride::apply(&__request, __timeout) {
+                let __timeout: ::std::sync::Arc<dyn ::meshestra::interceptor::Interceptor> =
+                    ::std::sync::Arc::new(::meshestra::interceptor::TimeoutInterceptor::new(__timeout));
+                __next = ::meshestra::interceptor::Next::chain(::std::sync::Arc::new([__timeout]), __next);
+            }
+        });
+
+        // One limiter per route, shared by all its requests; it wraps the
+        // route's aspects.
+        let (rate_limiter, rate_limiter_clone, rate_limit) = match route.rate_limit {
+            Some((limit, window_secs)) => (
+                quote! {
+                    let __rate_limiter = ::std::sync::Arc::new(::meshestra::interceptor::RateLimitInterceptor::new(
+                        #limit,
+                        ::std::time::Duration::from_secs(#window_secs),
+                    ));
+                },
+                quote! { let __rate_limiter = __rate_limiter.clone(); },
+                quote! { __interceptors.insert(0, __rate_limiter); },
+            ),
+            None => (quote! {}, quote! {}, quote! {}),
+        };
+
+        // `#[status]`, `#[response_header]` and `#[produces]` shape the
+        // handler's own response, before interceptors see it.
+        let status_override = route.status.map(|status| quote! {
+            let __response = ::meshestra::controller::response::with_status(__response, #status);
+        });
+        let response_headers = route.response_headers.iter().map(|(name, value)| quote! {
+            let __response = ::meshestra::controller::response::with_header(__response, #name, #value);
+        });
+        let produces = route.produces.as_ref().map(|content_type| quote! {
+            let __response = ::meshestra::controller::response::produces(__response, #content_type);
+        });
+        let shape_response = quote! {
+            #status_override
+            #(#response_headers)*
+            #produces
+        };
+
+        // A stream may borrow the controller, so it's driven where the
+        // controller lives, for as long as the client listens.
+        let call = if let Some(session) = route.params.iter().position(|p| matches!(p.kind, ParamKind::WebSocket)) {
+            // The upgrade is answered now, and the method runs on the
+            // connection, tracked by the application's registry if any.
+            let upgrade = &internal_args[session];
             quote! {
-                .route(#path, #method_ident({
+                {
+                    let __sessions = __container.resolve::<::meshestra::ws::WsConnectionRegistry>().ok();
+                    let __span = ::meshestra::controller::span::Span::current();
+                    #upgrade.on_upgrade(move |__socket| ::meshestra::controller::span::in_span(__span, async move {
+                        let #upgrade = ::meshestra::ws::WebSocketSession::new(__socket, __sessions.as_deref());
+                        let _ = controller.#fn_name(#(#internal_args),*).await;
+                    })).into_response()
+                }
+            }
+        } else if route.streams {
+            quote! {
+                ::meshestra::controller::sse::stream_response(move |__sender| async move {
+                    __sender.forward(controller.#fn_name(#(#internal_args),*).await).await
+                })
+            }
+        } else {
+            quote! { controller.#fn_name(#(#internal_args),*).await.into_response() }
+        };
+
+        let invoke = quote! {
+            // Controller aspects wrap the method's; within each, `priority`,
+            // then listed order, decides the nesting. Controller interceptors
+            // wrap all of it. The chain is built once per container; a
+            // missing aspect is answered like a missing interceptor.
+            let __interceptors = __chain.get_or_try_init(__container, || {
+                let mut __interceptors = Self::__meshestra_controller_aspects(__container)?;
+                ::meshestra::interceptor::sort_by_priority(&mut __interceptors);
+                let mut __method_aspects: Vec<::std::sync::Arc<dyn ::meshestra::interceptor::Interceptor>> = vec![#(
+                    ::std::sync::Arc::new(::meshestra::aspect::AspectInterceptor::from_arc(
+                        __container.resolve::<#aspects>()?,
+                    )) as ::std::sync::Arc<dyn ::meshestra::interceptor::Interceptor>,
+                )*];
+                ::meshestra::interceptor::sort_by_priority(&mut __method_aspects);
+                __interceptors.extend(__method_aspects);
+                #rate_limit
+                let mut __outer = Self::__meshestra_controller_interceptors(__container, &__built_interceptors)?;
+                ::meshestra::interceptor::sort_by_priority(&mut __outer);
+                __outer.extend(__interceptors);
+                Ok(__outer)
+            });
+            let __interceptors = match __interceptors {
+                Ok(interceptors) => interceptors,
+                Err(error) => return error.into_response(),
+            };
+            // Aspects see the real request, body included; extraction
+            // happens innermost so their changes reach the handler, and
+            // each extracted value is moved into the method once, so it
+            // needn't be `Clone`.
+            let __handler = ::meshestra::interceptor::Next::new(move |__request: ::axum::http::Request<::axum::body::Body>| -> ::std::pin::Pin<Box<dyn ::std::future::Future<Output = ::meshestra::interceptor::InterceptorResult> + Send>> {
+                Box::pin(async move {
+                    // A panic travels the chain as an error too, so
+                    // transactions roll back instead of unwinding past it.
+                    let mut __response = match ::meshestra::exception::catch_unwind(async move {
+                        #pipe_container
+                        #localizer
+                        #split_request
+                        #(#extractions)*
+                        let __response = #call;
+                        #shape_response
+                        __response
+                    }).await {
+                        Ok(__response) => __response,
+                        Err(__panic) => return Err(Box::new(__panic) as ::meshestra::interceptor::InterceptorError),
+                    };
+                    // A returned `Exception` travels the chain as an error,
+                    // so interceptors see the failure.
+                    match ::meshestra::exception::Exception::take(&mut __response) {
+                        Some(__error) => Err(__error),
+                        None => Ok(__response),
+                    }
+                })
+            });
+            #[allow(unused_mut)]
+            let mut __next = ::meshestra::interceptor::Next::chain(__interceptors, __handler);
+            #route_timeout
+            let __filters = ::meshestra::exception::ExceptionFilters::of(&__request);
+            let __host = __filters.host(&__request);
+            __next
+                .run(__request)
+                .await
+                .unwrap_or_else(|e| __filters.handle(e, &__host))
+        };
+
+        // The request's span and `MatchedRoute` take the matched route at
+        // runtime; the controller and handler are known now.
+        let handler_name = fn_name.to_string();
+        quote! {
+            {
+                let controller = controller.clone();
+                let __built_interceptors = __built_interceptors.clone();
+                let __chain = ::std::sync::Arc::new(::meshestra::interceptor::RouteChain::default());
+                #rate_limiter
+                move |::axum::extract::State(__state): ::axum::extract::State<S>, __request: ::axum::http::Request<::axum::body::Body>| {
+                    const __CONTROLLER: &str = #controller_name;
+                    const __HANDLER: &str = #handler_name;
                     let controller = controller.clone();
-                    move |__state: ::axum::extract::State<S>, #(#extractor_patterns,)* __parts: ::axum::http::request::Parts| {
-                        let controller = controller.clone();
-                        async move { 
-                            use ::axum::response::IntoResponse;
-                            let mut execution = {
-                                let controller = controller.clone();
-                                #(let #internal_args = #internal_args.clone();)*
-                                Box::pin(async move {
-                                    controller.#fn_name(#(#internal_args),*).await.into_response()
-                                })
-                            };
+                    let __built_interceptors = __built_interceptors.clone();
+                    let __chain = __chain.clone();
+                    #rate_limiter_clone
+                    let __span = ::meshestra::controller::span::request_span(&__request, __CONTROLLER, __HANDLER);
+                    ::meshestra::controller::routing::MatchedRoute::record(&__request, __CONTROLLER, __HANDLER);
+                    ::meshestra::controller::span::traced(__span, async move {
+                        use ::axum::response::IntoResponse;
+                        let __container = ::meshestra::di::HasContainer::get_container(&__state);
+                        let (__request, __path_params) = ::meshestra::guard::extract_path_params(__request).await;
+                        let __request = {
+                            let __guard_context = ::meshestra::guard::GuardContext::new(__request)
+                                .with_path_params(__path_params)
+                                .with_container(__container);
+                            #controller_guards
                             #(
-                                let container = __state.get_container();
-                                let aspect = container.resolve::<#aspects>().expect("Aspect resolve failed");
-                                let interceptor = ::meshestra::aspect::AspectInterceptor::new(aspect);
-                                let mut req = ::axum::http::Request::builder()
-                                    .method(__parts.method.clone())
-                                    .uri(__parts.uri.clone())
-                                    .version(__parts.version)
-                                    .body(::axum::body::Body::empty()).unwrap();
-                                *req.headers_mut() = __parts.headers.clone();
-                                let next_logic = execution;
-                                let next = ::meshestra::interceptor::Next::new(move |_| next_logic);
-                                execution = Box::pin(async move {
-                                    interceptor.intercept(req, next).await.unwrap_or_else(|e| {
-                                        (::axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
-                                    })
-                                });
+                                if let Err(denied) = ::meshestra::guard::activate_guard::<#guards>(&__guard_context).await {
+                                    return denied.into_response();
+                                }
                             )*
-                            execution.await
-                        }
+                            #roles_guard
+                            __guard_context.into_request()
+                        };
+                        #invoke
+                    })
+                }
+            }
+        }
+    }).collect();
+
+    // Each route's handler is built once and shared by all its endpoints,
+    // so aliases share state such as the route's rate limiter.
+    let route_idents: Vec<_> = (0..routes.len())
+        .map(|i| quote::format_ident!("__route_{}", i))
+        .collect();
+    // So are its `#[middleware(...)]` layers, in the order they're added:
+    // the last one is the outermost.
+    let route_layers: Vec<Vec<_>> = routes
+        .iter()
+        .enumerate()
+        .map(|(i, route)| {
+            (0..route.middleware.len())
+                .rev()
+                .map(|j| quote::format_ident!("__route_{}_layer_{}", i, j))
+                .collect()
+        })
+        .collect();
+    let route_bindings = routes
+        .iter()
+        .zip(&route_idents)
+        .zip(&route_handlers)
+        .zip(&route_layers)
+        .map(|(((route, ident), handler), layers)| {
+            let layer_values = route.middleware.iter().rev().map(|layer| {
+                quote_spanned! {layer.span()=>
+                    ::meshestra::controller::routing::middleware(#layer)
+                }
+            });
+            quote! {
+                let #ident = #handler;
+                #(let #layers = #layer_values;)*
+            }
+        });
+
+    // Routes with a `#[version(...)]` of their own get a router per version;
+    // the rest share the controller's.
+    let mut versions: Vec<Option<&String>> = Vec::new();
+    for route in &routes {
+        if !versions.contains(&route.version.as_ref()) {
+            versions.push(route.version.as_ref());
+        }
+    }
+    let mut version_routers = Vec::new();
+    for version in versions {
+        let endpoints: Vec<_> = routes
+            .iter()
+            .zip(route_idents.iter().zip(&route_layers))
+            .filter(|(route, _)| route.version.as_ref() == version)
+            .flat_map(|(route, (ident, layers))| {
+                route
+                    .endpoints
+                    .iter()
+                    .map(move |endpoint| (endpoint, ident, layers, &route.fn_name))
+            })
+            .collect();
+
+        // One method router per path, so requests with a method the path has
+        // no route for are answered with a 405 listing the ones it has.
+        let mut paths: Vec<String> = Vec::new();
+        for (endpoint, _, _, _) in &endpoints {
+            if !paths.contains(&endpoint.path.value()) {
+                paths.push(endpoint.path.value());
+            }
+        }
+        let mut route_registrations = Vec::new();
+        for path in paths {
+            let on_path: Vec<_> = endpoints
+                .iter()
+                .filter(|(endpoint, _, _, _)| endpoint.path.value() == path)
+                .collect();
+            let mut methods = Vec::new();
+            let mut allowed: Vec<String> = Vec::new();
+            let mut handlers: Vec<String> = Vec::new();
+            for (endpoint, ident, layers, fn_name) in &on_path {
+                let handler = format!("{}::{}", controller_name, fn_name);
+                if !handlers.contains(&handler) {
+                    handlers.push(handler);
+                }
+                let filter = match endpoint.method.as_str() {
+                    "GET" => quote! { ::axum::routing::MethodFilter::GET },
+                    "POST" => quote! { ::axum::routing::MethodFilter::POST },
+                    "PUT" => quote! { ::axum::routing::MethodFilter::PUT },
+                    "DELETE" => quote! { ::axum::routing::MethodFilter::DELETE },
+                    "PATCH" => quote! { ::axum::routing::MethodFilter::PATCH },
+                    "HEAD" => quote! { ::axum::routing::MethodFilter::HEAD },
+                    "OPTIONS" => quote! { ::axum::routing::MethodFilter::OPTIONS },
+                    method => {
+                        return syn::Error::new_spanned(
+                            &endpoint.path,
+                            format!("unsupported HTTP method `{}`", method),
+                        )
+                        .to_compile_error();
                     }
-                }))
+                };
+                if allowed.contains(&endpoint.method) {
+                    return syn::Error::new_spanned(
+                        &endpoint.path,
+                        format!("`{} {}` is routed twice", endpoint.method, path),
+                    )
+                    .to_compile_error();
+                }
+                // A route's layers wrap its handler only, not the path's
+                // other methods.
+                methods.push(if layers.is_empty() {
+                    quote! { .on(#filter, #ident.clone()) }
+                } else {
+                    quote! {
+                        .merge(::axum::routing::MethodRouter::new().on(#filter, #ident.clone()) #(.route_layer(#layers.clone()))*)
+                    }
+                });
+                allowed.push(endpoint.method.clone());
             }
+
+            // axum answers HEAD with the GET route; the controller's `auto_head`
+            // decides whether that happens or HEAD is refused like any other
+            // method the path has no route for.
+            let implicit_head = on_path
+                .iter()
+                .find(|(endpoint, _, _, _)| endpoint.method == "GET")
+                .filter(|_| !allowed.iter().any(|method| method == "HEAD"))
+                .map(|(_, ident, layers, _)| (ident, layers));
+            let (auto_head, head_route) = match implicit_head {
+                Some((get, layers)) => (
+                    quote! { Self::__meshestra_controller_auto_head() },
+                    quote! {
+                        let __router = if __auto_head {
+                            let __get = #get.clone();
+                            __router.merge(::axum::routing::MethodRouter::new().head(move |__state: ::axum::extract::State<S>, __request: ::axum::http::Request<::axum::body::Body>| {
+                                let __get = __get.clone();
+                                async move { ::meshestra::controller::routing::without_body(__get(__state, __request).await) }
+                            }) #(.route_layer(#layers.clone()))*)
+                        } else {
+                            let __allow = __allow.clone();
+                            __router.head(move |__method: ::axum::http::Method| {
+                                let __allow = __allow.clone();
+                                async move { ::meshestra::controller::routing::method_not_allowed(&__method, &__allow) }
+                            })
+                        };
+                    },
+                ),
+                None => (quote! { false }, quote! {}),
+            };
+
+            let handlers = handlers.join(", ");
+            route_registrations.push(quote! {
+                let __routes = ::meshestra::controller::routing::route(__routes, #path, #handlers, {
+                    let __auto_head = #auto_head;
+                    let __allow = {
+                        let mut __allow = vec![#(#allowed),*];
+                        if __auto_head {
+                            __allow.push("HEAD");
+                        }
+                        __allow.join(", ")
+                    };
+                    let __router = ::axum::routing::MethodRouter::new() #(#methods)*;
+                    #head_route
+                    __router.fallback(move |__method: ::axum::http::Method| {
+                        let __allow = __allow.clone();
+                        async move { ::meshestra::controller::routing::method_not_allowed(&__method, &__allow) }
+                    })
+                });
+            });
         }
-    });
+        let version = match version {
+            Some(version) => quote! { Some(#version) },
+            None => quote! { Self::__meshestra_controller_version() },
+        };
+        version_routers.push(quote! {
+            (
+                #version,
+                Self::__meshestra_controller_middleware({
+                    let __routes = ::axum::Router::new();
+                    #(#route_registrations)*
+                    __routes.route_layer(::meshestra::cors::ControllerCorsLayer::new(Self::__meshestra_controller_cors()))
+                }),
+            )
+        });
+    }
 
     let self_ty = &input.self_ty;
     let impl_generics = &input.generics;
+    let impl_attrs = &input.attrs;
+    let colon_path_warnings = colon_path_warnings(routes.iter().flat_map(|route| &route.endpoints));
+
+    // What `route_table()` reports: one descriptor per declared endpoint.
+    let descriptors = routes.iter().flat_map(|route| {
+        let handler = format!("{}::{}", controller_name, route.fn_name);
+        let controller_guards = (!route.public).then(|| quote! {
+            __guards.extend_from_slice(Self::__meshestra_controller_guard_names());
+        });
+        let guards: Vec<_> = route.guards.iter().map(type_name).collect();
+        let aspects: Vec<_> = route.aspects.iter().map(type_name).collect();
+        let version = match &route.version {
+            Some(version) => quote! { Some(#version) },
+            None => quote! { Self::__meshestra_controller_version() },
+        };
+        let csrf_exempt = route.csrf_exempt;
+        let api = match api_operation(route, &controller_name) {
+            Ok(api) => api,
+            Err(e) => e.to_compile_error(),
+        };
+        route.endpoints.iter().map(move |endpoint| {
+            let method = &endpoint.method;
+            let path = &endpoint.path;
+            quote! {
+                {
+                    let mut __guards: Vec<&'static str> = Vec::new();
+                    #controller_guards
+                    __guards.extend_from_slice(&[#(#guards),*]);
+                    let mut __aspects: Vec<&'static str> = Self::__meshestra_controller_aspect_names().to_vec();
+                    __aspects.extend_from_slice(&[#(#aspects),*]);
+                    ::meshestra::controller::routing::RouteDescriptor {
+                        method: #method,
+                        path: ::meshestra::controller::routing::join_path(Self::base_path(), #path),
+                        handler: #handler,
+                        guards: __guards,
+                        aspects: __aspects,
+                        version: #version,
+                        cors: Self::__meshestra_controller_cors(),
+                        csrf_exempt: #csrf_exempt,
+                        api: #api,
+                    }
+                }
+            }
+        })
+    }).collect::<Vec<_>>();
 
     quote! {
+        #(#impl_attrs)*
         impl #impl_generics #self_ty {
             #(#clean_items)*
+
+            /// The routes `router()` serves, with their full paths
+            pub fn route_table() -> Vec<::meshestra::controller::routing::RouteDescriptor> {
+                #(#colon_path_warnings)*
+                vec![#(#descriptors),*]
+            }
+            /// The routes of the controller's own version; the others are
+            /// served by the module router, see `versioned_routers()`
             pub fn router<S>(controller: ::std::sync::Arc<Self>) -> ::axum::Router<S>
             where
                 S: Clone + Send + Sync + ::meshestra::di::HasContainer + 'static,
             {
-                ::axum::Router::new() #(#route_registrations)*
+                let __version = Self::__meshestra_controller_version();
+                Self::versioned_routers(controller)
+                    .into_iter()
+                    .filter(|(version, _)| *version == __version)
+                    .fold(::axum::Router::new(), |router, (_, routes)| router.merge(routes))
+            }
+            /// A router per API version the routes belong to
+            pub fn versioned_routers<S>(controller: ::std::sync::Arc<Self>) -> Vec<(Option<&'static str>, ::axum::Router<S>)>
+            where
+                S: Clone + Send + Sync + ::meshestra::di::HasContainer + 'static,
+            {
+                let __built_interceptors = Self::__meshestra_controller_built_interceptors();
+                #(#route_bindings)*
+                vec![#(#version_routers),*]
+            }
+        }
+
+        impl #impl_generics ::meshestra::controller::routing::Controller for #self_ty {
+            fn base_path() -> &'static str {
+                <#self_ty>::base_path()
+            }
+
+            fn route_table() -> Vec<::meshestra::controller::routing::RouteDescriptor> {
+                <#self_ty>::route_table()
+            }
+
+            fn versioned_routers<S>(controller: ::std::sync::Arc<Self>) -> Vec<(Option<&'static str>, ::axum::Router<S>)>
+            where
+                S: Clone + Send + Sync + ::meshestra::di::HasContainer + 'static,
+            {
+                <#self_ty>::versioned_routers(controller)
             }
         }
     }
 }
 
-fn extract_route_info(method: &syn::ImplItemFn) -> Option<RouteInfo> {
-    let mut http_method = None;
-    let mut path = String::new();
+/// A type as written, without the spaces `quote` puts in paths
+fn type_name(ty: &impl ToTokens) -> String {
+    ty.to_token_stream().to_string().replace(' ', "")
+}
+
+fn extract_route_info(method: &syn::ImplItemFn) -> syn::Result<Option<RouteInfo>> {
+    let mut endpoints = Vec::new();
     let mut aspects = Vec::new();
+    let mut guards = Vec::new();
+    let mut middleware = Vec::new();
+    let mut public = false;
+    let mut roles: Option<Vec<LitStr>> = None;
+    let mut timeout_ms = None;
+    let mut rate_limit = None;
+    let mut skip_global_pipes = false;
+    let mut status = None;
+    let mut response_headers = Vec::new();
+    let mut produces = None;
+    let mut version = None;
+    let mut websocket = false;
+    let mut csrf_exempt = false;
+    let mut hidden = false;
+    let mut docs = Vec::new();
 
     for attr in &method.attrs {
         if let Some(ident) = attr.path().get_ident() {
             let name = ident.to_string();
-            if ["get", "post", "put", "delete", "patch"].contains(&name.as_str()) {
-                http_method = Some(name.to_uppercase());
-                if let syn::Meta::List(meta_list) = &attr.meta {
-                    let tokens = meta_list.tokens.to_string();
-                    path = tokens.trim_matches('"').to_string();
+            if ["get", "post", "put", "delete", "patch", "head", "options"].contains(&name.as_str())
+            {
+                let method = name.to_uppercase();
+                for written in parse_route_paths(attr)? {
+                    let (path, colon_path) = normalize_colon_params(written);
+                    endpoints.push(Endpoint {
+                        method: method.clone(),
+                        path,
+                        colon_path,
+                    });
+                }
+            } else if name == "ws" {
+                // The upgrade request is a GET.
+                for written in parse_route_paths(attr)? {
+                    let (path, colon_path) = normalize_colon_params(written);
+                    endpoints.push(Endpoint {
+                        method: "GET".to_string(),
+                        path,
+                        colon_path,
+                    });
                 }
+                websocket = true;
             } else if name == "aspect" {
-                if let Ok(ty) = attr.parse_args::<syn::Type>() {
-                    aspects.push(ty);
+                aspects.push(attr.parse_args::<syn::Type>()?);
+            } else if name == "guard" {
+                guards.extend(parse_guard_list(attr)?);
+            } else if name == "middleware" {
+                middleware.push(attr.parse_args::<syn::Expr>()?);
+            } else if name == "public" {
+                public = true;
+            } else if name == "csrf_exempt" {
+                csrf_exempt = true;
+            } else if name == "roles" {
+                roles
+                    .get_or_insert_with(Vec::new)
+                    .extend(parse_roles_list(attr)?);
+            } else if name == "timeout" {
+                timeout_ms = Some(parse_timeout(attr)?);
+            } else if name == "rate_limit" {
+                rate_limit = Some(parse_rate_limit(attr)?);
+            } else if name == "skip_global_pipes" {
+                skip_global_pipes = true;
+            } else if name == "status" {
+                status = Some(parse_status(attr)?);
+            } else if name == "response_header" {
+                response_headers.push(parse_response_header(attr)?);
+            } else if name == "produces" {
+                produces = Some(parse_produces(attr)?);
+            } else if name == "version" {
+                version = Some(normalize_version(&attr.parse_args()?)?);
+            } else if name == "api" {
+                hidden = parse_api_hidden(attr)?;
+            } else if name == "doc" {
+                if let syn::Meta::NameValue(syn::MetaNameValue {
+                    value:
+                        syn::Expr::Lit(syn::ExprLit {
+                            lit: syn::Lit::Str(line),
+                            ..
+                        }),
+                    ..
+                }) = &attr.meta
+                {
+                    docs.push(line.value());
                 }
             }
         }
     }
-    let http_method = http_method?;
+    let Some(first) = endpoints.first() else {
+        return Ok(None);
+    };
+    // Aliases hand the handler the same path parameters, in the same order.
+    let expected = parse_path_template(&first.path)?;
+    for endpoint in &endpoints[1..] {
+        if parse_path_template(&endpoint.path)? != expected {
+            return Err(syn::Error::new_spanned(
+                &endpoint.path,
+                format!(
+                    "path parameters must match those of `{}`",
+                    first.path.value()
+                ),
+            ));
+        }
+    }
 
     let mut params = Vec::new();
     for input in method.sig.inputs.iter() {
         if let FnArg::Typed(pat_type) = input {
             let ty = (*pat_type.ty).clone();
-            let kind = get_param_kind(&pat_type.attrs);
-            params.push(ParamInfo { ty, kind });
+            let (kind, validate) = get_param_kind(&pat_type.attrs)?;
+            if matches!(kind, ParamKind::Service) && extract_injectable_type(&ty) == ty {
+                return Err(syn::Error::new_spanned(
+                    &ty,
+                    "`#[service]` parameters are `Arc<T>` or `Arc<dyn Trait>`",
+                ));
+            }
+            let pipes = get_param_pipes(&pat_type.attrs)?;
+            params.push(ParamInfo {
+                ty,
+                kind,
+                validate,
+                pipes,
+            });
+        }
+    }
+    // A `#[ws]` method takes the session as its one unannotated parameter.
+    if websocket {
+        let sessions: Vec<_> = params
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| matches!(p.kind, ParamKind::Raw) && is_session_type(&p.ty))
+            .map(|(i, _)| i)
+            .collect();
+        match sessions[..] {
+            [i] => params[i].kind = ParamKind::WebSocket,
+            [] => {
+                return Err(syn::Error::new_spanned(
+                    &method.sig,
+                    "a `#[ws]` method takes a `WebSocketSession` parameter",
+                ))
+            }
+            [_, i, ..] => {
+                return Err(syn::Error::new_spanned(
+                    &params[i].ty,
+                    "a `#[ws]` method takes one `WebSocketSession`",
+                ))
+            }
+        }
+    }
+    // The multipart stream is read from the whole request.
+    if let Some(i) = params
+        .iter()
+        .position(|p| matches!(p.kind, ParamKind::Multipart))
+    {
+        if params[i + 1..].iter().any(|p| !p.kind.is_read_from_parts()) {
+            return Err(syn::Error::new_spanned(
+                &params[i].ty,
+                "`#[multipart]` consumes the request body, so only header, cookie, host, ip and service parameters may follow it",
+            ));
+        }
+    }
+    let path_slots = bind_path_params(&first.path, &expected, &params)?;
+    // `impl Trait`s can't be named in the generated `SchemaProbe::<T>`.
+    let output = match &method.sig.output {
+        syn::ReturnType::Type(_, ty)
+            if !mentions_impl_trait(ty.to_token_stream())
+                && !matches!(&**ty, syn::Type::Tuple(tuple) if tuple.elems.is_empty()) =>
+        {
+            Some((**ty).clone())
+        }
+        _ => None,
+    };
+    Ok(Some(RouteInfo {
+        endpoints,
+        fn_name: method.sig.ident.clone(),
+        params,
+        aspects,
+        guards,
+        middleware,
+        public,
+        roles,
+        timeout_ms,
+        rate_limit,
+        skip_global_pipes,
+        status,
+        response_headers,
+        produces,
+        version,
+        streams: returns_stream(&method.sig),
+        websocket,
+        csrf_exempt,
+        path_slots,
+        hidden,
+        docs,
+        output,
+    }))
+}
+
+/// The `ApiOperation` of `route` in the OpenAPI document, `None` for hidden
+/// and `#[ws]` routes
+fn api_operation(route: &RouteInfo, controller_name: &str) -> syn::Result<TokenStream2> {
+    if route.hidden || route.websocket {
+        return Ok(quote! { None });
+    }
+    let tag = controller_name
+        .strip_suffix("Controller")
+        .filter(|tag| !tag.is_empty())
+        .unwrap_or(controller_name);
+    let (summary, description) = doc_summary(&route.docs);
+    let summary = option_tokens(summary);
+    let description = option_tokens(description);
+
+    let mut params = Vec::new();
+    let param = |name: Option<String>, location: &str, required: bool, ty: &syn::Type| {
+        let name = option_tokens(name);
+        let location = syn::Ident::new(location, proc_macro2::Span::call_site());
+        let schema = type_schema(ty);
+        quote! {
+            ::meshestra::openapi::ApiParam {
+                name: #name,
+                location: ::meshestra::openapi::ParamLocation::#location,
+                required: #required,
+                schema: #schema,
+            }
+        }
+    };
+    // Path parameters are named after their placeholders; those whose type
+    // a pipe hides are described as strings by the document.
+    let placeholders = parse_path_template(&route.endpoints[0].path)?;
+    match &route.path_slots {
+        Some(slots) => {
+            for (placeholder, slot) in placeholders.iter().zip(slots) {
+                if let Some(p) = slot
+                    .map(|i| &route.params[i])
+                    .filter(|p| p.pipes.is_empty())
+                {
+                    params.push(param(Some(placeholder.clone()), "Path", true, &p.ty));
+                }
+            }
+        }
+        None => {
+            let whole = route
+                .params
+                .iter()
+                .find(|p| matches!(p.kind, ParamKind::Param(_)) && p.pipes.is_empty());
+            match whole.map(|p| &p.ty) {
+                Some(syn::Type::Tuple(tuple)) => {
+                    for (placeholder, ty) in placeholders.iter().zip(&tuple.elems) {
+                        params.push(param(Some(placeholder.clone()), "Path", true, ty));
+                    }
+                }
+                Some(ty) if placeholders.len() == 1 => {
+                    params.push(param(Some(placeholders[0].clone()), "Path", true, ty))
+                }
+                Some(ty) => params.push(param(None, "Path", true, ty)),
+                None => {}
+            }
+        }
+    }
+    let mut body = None;
+    for p in &route.params {
+        match &p.kind {
+            ParamKind::Query => params.push(param(None, "Query", false, &p.ty)),
+            ParamKind::Header(name) => params.push(param(
+                Some(name.value()),
+                "Header",
+                !last_segment_is(&p.ty, "Option"),
+                &p.ty,
+            )),
+            ParamKind::Cookie(name) => params.push(param(
+                Some(name.value()),
+                "Cookie",
+                !last_segment_is(&p.ty, "Option"),
+                &p.ty,
+            )),
+            ParamKind::Body => body = Some(("application/json", &p.ty)),
+            ParamKind::Form => body = Some(("application/x-www-form-urlencoded", &p.ty)),
+            ParamKind::Multipart => body = Some(("multipart/form-data", &p.ty)),
+            _ => {}
+        }
+    }
+    let body = match body {
+        Some((content_type, ty)) => {
+            let schema = type_schema(ty);
+            quote! { Some(::meshestra::openapi::ApiBody { content_type: #content_type, schema: #schema }) }
+        }
+        None => quote! { None },
+    };
+    let status = route.status.unwrap_or(200);
+    let response = match &route.output {
+        Some(ty) => {
+            let schema = type_schema(ty);
+            quote! { Some(#schema) }
+        }
+        None => quote! { None },
+    };
+    let events = route.streams
+        || route
+            .output
+            .as_ref()
+            .is_some_and(|ty| last_segment_is(ty, "Sse"));
+    let produces = match (&route.produces, events) {
+        (Some(produces), _) => quote! { Some(#produces) },
+        (None, true) => quote! { Some("text/event-stream") },
+        (None, false) => quote! { None },
+    };
+    Ok(quote! {
+        Some(::meshestra::openapi::ApiOperation {
+            tag: #tag,
+            summary: #summary,
+            description: #description,
+            params: vec![#(#params),*],
+            body: #body,
+            status: #status,
+            response: #response,
+            produces: #produces,
+        })
+    })
+}
+
+/// The summary, the first paragraph of a doc comment, and the description,
+/// the rest of it
+fn doc_summary(docs: &[String]) -> (Option<String>, Option<String>) {
+    let lines: Vec<&str> = docs
+        .iter()
+        .flat_map(|doc| doc.split('\n'))
+        .map(|line| line.strip_prefix(' ').unwrap_or(line))
+        .collect();
+    let start = lines
+        .iter()
+        .position(|line| !line.trim().is_empty())
+        .unwrap_or(lines.len());
+    let end = lines[start..]
+        .iter()
+        .position(|line| line.trim().is_empty())
+        .map_or(lines.len(), |n| start + n);
+    let summary = lines[start..end]
+        .iter()
+        .map(|line| line.trim())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let description = lines[end..].join("\n").trim().to_string();
+    let non_empty = |text: String| (!text.is_empty()).then_some(text);
+    (non_empty(summary), non_empty(description))
+}
+
+fn option_tokens(value: Option<String>) -> TokenStream2 {
+    match value {
+        Some(value) => quote! { Some(#value) },
+        None => quote! { None },
+    }
+}
+
+/// The `TypeSchema` of `ty`: its schema if it implements `ApiSchema`
+fn type_schema(ty: &syn::Type) -> TokenStream2 {
+    let name = type_name(ty);
+    quote! {
+        ::meshestra::openapi::TypeSchema::new(#name, |__components: &mut ::meshestra::openapi::Components| {
+            #[allow(unused_imports)]
+            use ::meshestra::openapi::{Described as _, Undescribed as _};
+            (&&::meshestra::openapi::SchemaProbe::<#ty>::new()).schema(__components)
+        })
+    }
+}
+
+/// Whether `tokens` contain an `impl Trait`
+fn mentions_impl_trait(tokens: TokenStream2) -> bool {
+    tokens.into_iter().any(|token| match token {
+        proc_macro2::TokenTree::Ident(ident) => ident == "impl",
+        proc_macro2::TokenTree::Group(group) => mentions_impl_trait(group.stream()),
+        _ => false,
+    })
+}
+
+/// Whether `ty` is a path ending in `name`, like `Option` or `Sse`
+fn last_segment_is(ty: &syn::Type, name: &str) -> bool {
+    matches!(ty, syn::Type::Path(type_path) if type_path.path.segments.last().is_some_and(|segment| segment.ident == name))
+}
+
+/// Whether `ty` is `WebSocketSession`, however it's imported
+fn is_session_type(ty: &syn::Type) -> bool {
+    matches!(ty, syn::Type::Path(type_path) if type_path.path.segments.last().is_some_and(|segment| segment.ident == "WebSocketSession"))
+}
+
+/// Whether `sig` returns `impl Stream<Item = T>`
+fn returns_stream(sig: &syn::Signature) -> bool {
+    let syn::ReturnType::Type(_, ty) = &sig.output else {
+        return false;
+    };
+    let syn::Type::ImplTrait(impl_trait) = &**ty else {
+        return false;
+    };
+    impl_trait.bounds.iter().any(|bound| match bound {
+        syn::TypeParamBound::Trait(bound) => bound
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Stream"),
+        _ => false,
+    })
+}
+
+/// Parse `#[get("/users/{id}", alias = "/members/{id}")]` into its paths
+fn parse_route_paths(attr: &Attribute) -> syn::Result<Vec<LitStr>> {
+    if let syn::Meta::Path(path) = &attr.meta {
+        return Ok(vec![LitStr::new("", path.span())]);
+    }
+    attr.parse_args_with(|input: ParseStream| {
+        let mut paths = vec![input.parse::<LitStr>()?];
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            let key: syn::Ident = input.parse()?;
+            if key != "alias" {
+                return Err(syn::Error::new_spanned(key, "expected `alias = \"/path\"`"));
+            }
+            input.parse::<Token![=]>()?;
+            paths.push(input.parse()?);
         }
+        Ok(paths)
+    })
+}
+
+/// Rewrite `:id` segments to `{id}`, returning the rewritten path and, if
+/// it differs, the one written
+///
+/// Segments that aren't a `:` and a name are left for `parse_path_template`
+/// to reject.
+fn normalize_colon_params(lit: LitStr) -> (LitStr, Option<LitStr>) {
+    let path = lit.value();
+    let mut changed = false;
+    let segments: Vec<String> = path
+        .split('/')
+        .map(|segment| match segment.strip_prefix(':') {
+            Some(name)
+                if !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_') =>
+            {
+                changed = true;
+                format!("{{{}}}", name)
+            }
+            _ => segment.to_string(),
+        })
+        .collect();
+    if !changed {
+        return (lit, None);
     }
-    Some(RouteInfo { method: http_method, path, fn_name: method.sig.ident.clone(), params, aspects })
+    (LitStr::new(&segments.join("/"), lit.span()), Some(lit))
 }
 
-fn get_param_kind(attrs: &[Attribute]) -> ParamKind {
+/// Deprecation warnings, at the paths, for each endpoint written with `:id`
+///
+/// A proc macro can't warn on stable, so each one is the use of a
+/// `#[deprecated]` constant; `#![deny(deprecated)]` makes them errors.
+fn colon_path_warnings<'a>(endpoints: impl Iterator<Item = &'a Endpoint>) -> Vec<TokenStream2> {
+    endpoints
+        .filter_map(|endpoint| {
+            let written = endpoint.colon_path.as_ref()?;
+            let note = format!(
+                "`{}` uses `:` path parameters, write `{}`",
+                written.value(),
+                endpoint.path.value()
+            );
+            let warning = quote_spanned! {written.span()=> colon_path_parameter};
+            Some(quote! {
+                {
+                    #[deprecated(note = #note)]
+                    #[allow(non_upper_case_globals)]
+                    const colon_path_parameter: &str = #written;
+                    let _ = #warning;
+                }
+            })
+        })
+        .collect()
+}
+
+/// Check a route path and return its parameter names, `{id}` and `{*rest}`
+/// alike, in path order
+///
+/// axum would only panic on these when the router is built; here they're
+/// compile errors pointing at the path.
+fn parse_path_template(lit: &LitStr) -> syn::Result<Vec<String>> {
+    let path = lit.value();
+    let error = |message: String| Err(syn::Error::new(lit.span(), message));
+    if path.is_empty() {
+        return Ok(Vec::new());
+    }
+    if !path.starts_with('/') {
+        return error(format!("route paths start with `/`, try `/{}`", path));
+    }
+    let segments: Vec<&str> = path[1..].split('/').collect();
+    let mut params: Vec<String> = Vec::new();
+    for (i, segment) in segments.iter().enumerate() {
+        let last = i + 1 == segments.len();
+        // A trailing slash is a route of its own, but `//` matches nothing.
+        if segment.is_empty() && !last {
+            return error("route paths can't have empty segments (`//`)".to_string());
+        }
+        if let Some(name) = segment
+            .strip_prefix(':')
+            .or_else(|| segment.strip_prefix('*'))
+        {
+            let hint = if segment.starts_with('*') {
+                format!("{{*{}}}", name)
+            } else {
+                format!("{{{}}}", name)
+            };
+            return error(format!(
+                "`{}` is not a path parameter, write `{}`",
+                segment, hint
+            ));
+        }
+        let mut rest = *segment;
+        while let Some(at) = rest.find(['{', '}']) {
+            let after = &rest[at..];
+            // `{{` and `}}` are literal braces.
+            if after.starts_with("{{") || after.starts_with("}}") {
+                rest = &after[2..];
+                continue;
+            }
+            if after.starts_with('}') {
+                return error(format!("unmatched `}}` in `{}`", segment));
+            }
+            let Some(close) = after.find('}') else {
+                return error(format!("unclosed `{{` in `{}`", segment));
+            };
+            let name = &after[1..close];
+            let (catch_all, ident) = match name.strip_prefix('*') {
+                Some(ident) => (true, ident),
+                None => (false, name),
+            };
+            if ident.is_empty()
+                || ident.contains('{')
+                || !ident.chars().all(|c| c.is_alphanumeric() || c == '_')
+            {
+                return error(format!("`{{{}}}` is not a valid path parameter name", name));
+            }
+            if catch_all && (!last || *segment != format!("{{{}}}", name)) {
+                return error(format!("`{{{}}}` must be the whole last segment", name));
+            }
+            if params.iter().any(|param| param == ident) {
+                return error(format!("path parameter `{}` appears more than once", ident));
+            }
+            params.push(ident.to_string());
+            rest = &after[close + 1..];
+        }
+    }
+    Ok(params)
+}
+
+/// Check the `#[param]` parameters against the path's placeholders, and
+/// decide how they're extracted
+///
+/// A single unnamed parameter is the whole `Path`: a tuple of one element
+/// per placeholder, or a struct deserialized by name. Otherwise each
+/// parameter takes one placeholder, named ones the placeholder they name and
+/// the others the remaining ones in path order, and the result maps every
+/// placeholder to its parameter.
+fn bind_path_params(
+    path: &LitStr,
+    placeholders: &[String],
+    params: &[ParamInfo],
+) -> syn::Result<Option<Vec<Option<usize>>>> {
+    let path_params: Vec<(usize, &ParamInfo, Option<&LitStr>)> = params
+        .iter()
+        .enumerate()
+        .filter_map(|(i, p)| match &p.kind {
+            ParamKind::Param(name) => Some((i, p, name.as_ref())),
+            _ => None,
+        })
+        .collect();
+    let count = placeholders.len();
+    let plural = |n: usize| if n == 1 { "" } else { "s" };
+    let mismatch = |message: String| Err(syn::Error::new(path.span(), message));
+    // With pipes the extracted type is the first pipe's input, which we can't see.
+    let tuple_arity = |p: &ParamInfo| match &p.ty {
+        syn::Type::Tuple(tuple) if p.pipes.is_empty() => Some(tuple.elems.len()),
+        _ => None,
+    };
+    match path_params.as_slice() {
+        [] => return Ok(None),
+        [(_, param, None)] => {
+            return match tuple_arity(param) {
+                Some(arity) if arity != count => mismatch(format!(
+                    "`#[param]` is a tuple of {} but `{}` has {} path parameter{}",
+                    arity,
+                    path.value(),
+                    count,
+                    plural(count),
+                )),
+                None if count == 0 => mismatch(format!(
+                    "`#[param]` is used but `{}` has no path parameters",
+                    path.value()
+                )),
+                _ => Ok(None),
+            };
+        }
+        _ => {}
+    }
+    if let Some((_, tuple, _)) = path_params
+        .iter()
+        .find(|(_, p, _)| tuple_arity(p).is_some())
+    {
+        return Err(syn::Error::new_spanned(
+            &tuple.ty,
+            "a tuple `#[param]` takes every path parameter, so it must be the only `#[param]`",
+        ));
+    }
+    let named = path_params.iter().any(|(_, _, name)| name.is_some());
+    if (named && path_params.len() > count) || (!named && path_params.len() != count) {
+        return mismatch(format!(
+            "{} `#[param]` parameters but `{}` has {} path parameter{}",
+            path_params.len(),
+            path.value(),
+            count,
+            plural(count),
+        ));
+    }
+    let mut slots: Vec<Option<usize>> = vec![None; count];
+    for (i, _, name) in &path_params {
+        let Some(name) = name else { continue };
+        let Some(slot) = placeholders
+            .iter()
+            .position(|placeholder| *placeholder == name.value())
+        else {
+            return Err(syn::Error::new_spanned(
+                name,
+                format!(
+                    "`{}` has no path parameter `{}`",
+                    path.value(),
+                    name.value()
+                ),
+            ));
+        };
+        if slots[slot].is_some() {
+            return Err(syn::Error::new_spanned(
+                name,
+                format!("path parameter `{}` is already taken", name.value()),
+            ));
+        }
+        slots[slot] = Some(*i);
+    }
+    let mut free = slots.iter_mut().filter(|slot| slot.is_none());
+    for (i, _, name) in &path_params {
+        if name.is_none() {
+            if let Some(slot) = free.next() {
+                *slot = Some(*i);
+            }
+        }
+    }
+    Ok(Some(slots))
+}
+
+/// Parse `#[api(hidden)]`
+fn parse_api_hidden(attr: &Attribute) -> syn::Result<bool> {
+    match attr.parse_args::<syn::Ident>() {
+        Ok(flag) if flag == "hidden" => Ok(true),
+        _ => Err(syn::Error::new_spanned(attr, "expected `#[api(hidden)]`")),
+    }
+}
+
+/// Parse `#[timeout("2m")]` into milliseconds; units are `ms`, `s`, `m` and `h`
+fn parse_timeout(attr: &Attribute) -> syn::Result<u64> {
+    let lit: LitStr = attr.parse_args()?;
+    let value = lit.value();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    let factor = match unit.trim() {
+        "ms" => 1,
+        "s" | "" => 1_000,
+        "m" => 60_000,
+        "h" => 3_600_000,
+        _ => 0,
+    };
+    match amount.parse::<u64>() {
+        Ok(amount) if factor > 0 => Ok(amount * factor),
+        _ => Err(syn::Error::new_spanned(
+            lit,
+            "expected a duration such as \"500ms\", \"30s\", \"2m\" or \"1h\"",
+        )),
+    }
+}
+
+/// Parse `#[rate_limit(per_minute = 60)]` into the limit and window in seconds
+fn parse_rate_limit(attr: &Attribute) -> syn::Result<(u32, u64)> {
+    let args =
+        attr.parse_args_with(Punctuated::<syn::MetaNameValue, Token![,]>::parse_terminated)?;
+    let mut parsed = None;
+    for arg in &args {
+        let window_secs = match arg
+            .path
+            .get_ident()
+            .map(|ident| ident.to_string())
+            .as_deref()
+        {
+            Some("per_second") => 1,
+            Some("per_minute") => 60,
+            Some("per_hour") => 3600,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &arg.path,
+                    "expected `per_second`, `per_minute` or `per_hour`",
+                ))
+            }
+        };
+        let limit = match &arg.value {
+            syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Int(limit),
+                ..
+            }) => limit.base10_parse::<u32>()?,
+            value => return Err(syn::Error::new_spanned(value, "expected a request count")),
+        };
+        if parsed.replace((limit, window_secs)).is_some() {
+            return Err(syn::Error::new_spanned(arg, "only one limit can be given"));
+        }
+    }
+    parsed.ok_or_else(|| {
+        syn::Error::new_spanned(attr, "expected e.g. `#[rate_limit(per_minute = 60)]`")
+    })
+}
+
+/// Parse `#[status(201)]`
+fn parse_status(attr: &Attribute) -> syn::Result<u16> {
+    let lit: syn::LitInt = attr.parse_args()?;
+    match lit.base10_parse::<u16>() {
+        Ok(status @ 100..=599) => Ok(status),
+        _ => Err(syn::Error::new_spanned(
+            lit,
+            "expected an HTTP status between 100 and 599",
+        )),
+    }
+}
+
+/// Parse `#[response_header("Cache-Control", "no-store")]`, lowercasing the name
+fn parse_response_header(attr: &Attribute) -> syn::Result<(LitStr, LitStr)> {
+    let args: Vec<LitStr> = attr
+        .parse_args_with(Punctuated::<LitStr, Token![,]>::parse_terminated)?
+        .into_iter()
+        .collect();
+    let [name, value] = args.as_slice() else {
+        return Err(syn::Error::new_spanned(
+            attr,
+            "expected e.g. `#[response_header(\"Cache-Control\", \"no-store\")]`",
+        ));
+    };
+    let token = |c: char| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c);
+    if name.value().is_empty() || !name.value().chars().all(token) {
+        return Err(syn::Error::new_spanned(name, "not a valid header name"));
+    }
+    check_header_value(value)?;
+    Ok((
+        LitStr::new(&name.value().to_ascii_lowercase(), name.span()),
+        value.clone(),
+    ))
+}
+
+/// Parse `#[produces("text/csv")]`
+fn parse_produces(attr: &Attribute) -> syn::Result<LitStr> {
+    let content_type: LitStr = attr.parse_args()?;
+    check_header_value(&content_type)?;
+    Ok(content_type)
+}
+
+/// An API version as `meshestra::controller::versioning::normalize` spells
+/// it: `"2"`, `"V2"` and `"v2"` are all `"v2"`
+fn normalize_version(lit: &LitStr) -> syn::Result<String> {
+    let value = lit.value();
+    let value = value.trim();
+    let number = value.strip_prefix(['v', 'V']).unwrap_or(value);
+    if number.starts_with(|c: char| c.is_ascii_digit())
+        && number.chars().all(|c| c.is_ascii_digit() || c == '.')
+    {
+        Ok(format!("v{}", number))
+    } else {
+        Err(syn::Error::new_spanned(
+            lit,
+            "expected a version such as `\"2\"` or `\"v1.1\"`",
+        ))
+    }
+}
+
+/// Header values are visible ASCII, spaces and tabs
+fn check_header_value(value: &LitStr) -> syn::Result<()> {
+    if value
+        .value()
+        .chars()
+        .all(|c| c == '\t' || (' '..='~').contains(&c))
+    {
+        Ok(())
+    } else {
+        Err(syn::Error::new_spanned(
+            value,
+            "header values must be visible ASCII",
+        ))
+    }
+}
+
+/// The kind of a parameter, and whether `#[body(validate)]` asked for validation
+fn get_param_kind(attrs: &[Attribute]) -> syn::Result<(ParamKind, bool)> {
     for attr in attrs {
         if let Some(ident) = attr.path().get_ident() {
             let name = ident.to_string();
             match name.as_str() {
-                "body" => return ParamKind::Body,
-                "param" => return ParamKind::Param,
-                "query" => return ParamKind::Query,
+                "body" => {
+                    let validate = match &attr.meta {
+                        syn::Meta::Path(_) => false,
+                        _ => {
+                            let flag: syn::Ident = attr.parse_args()?;
+                            if flag != "validate" {
+                                return Err(syn::Error::new_spanned(
+                                    flag,
+                                    "expected `#[body]` or `#[body(validate)]`",
+                                ));
+                            }
+                            true
+                        }
+                    };
+                    return Ok((ParamKind::Body, validate));
+                }
+                "form" => return Ok((ParamKind::Form, false)),
+                "multipart" => return Ok((ParamKind::Multipart, false)),
+                "param" => {
+                    let name = match &attr.meta {
+                        syn::Meta::Path(_) => None,
+                        _ => Some(attr.parse_args().map_err(|_| {
+                            syn::Error::new_spanned(
+                                attr,
+                                "expected `#[param]` or e.g. `#[param(\"repo_id\")]`",
+                            )
+                        })?),
+                    };
+                    return Ok((ParamKind::Param(name), false));
+                }
+                "query" => return Ok((ParamKind::Query, false)),
+                "header" => {
+                    let named = attr.parse_args().map_err(|_| {
+                        syn::Error::new_spanned(attr, "expected e.g. `#[header(\"X-Api-Key\")]`")
+                    })?;
+                    return Ok((ParamKind::Header(named), false));
+                }
+                "cookie" => {
+                    let named = attr.parse_args().map_err(|_| {
+                        syn::Error::new_spanned(attr, "expected e.g. `#[cookie(\"session\")]`")
+                    })?;
+                    return Ok((ParamKind::Cookie(named), false));
+                }
+                "host_param" => return Ok((ParamKind::Host, false)),
+                "ip" => return Ok((ParamKind::Ip, false)),
+                "service" => return Ok((ParamKind::Service, false)),
+                "session" => return Ok((ParamKind::Session, false)),
+                "csrf_token" => return Ok((ParamKind::CsrfToken, false)),
+                "current_user" => return Ok((ParamKind::CurrentUser, false)),
                 _ => {}
             }
         }
     }
-    ParamKind::Raw
+    Ok((ParamKind::Raw, false))
+}
+
+/// The pipes of `#[pipe(A, B)]` attributes, in order; attributes stack
+fn get_param_pipes(attrs: &[Attribute]) -> syn::Result<Vec<syn::Path>> {
+    let mut pipes = Vec::new();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("pipe")) {
+        let listed = attr.parse_args_with(Punctuated::<syn::Path, Token![,]>::parse_terminated)?;
+        if listed.is_empty() {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "expected e.g. `#[pipe(ParseIntPipe)]`",
+            ));
+        }
+        pipes.extend(listed);
+    }
+    Ok(pipes)
 }
 
 fn is_http_method_attr(attr: &Attribute) -> bool {
-    attr.path().get_ident().map_or(false, |ident| {
-        ["get", "post", "put", "delete", "patch"].contains(&ident.to_string().as_str())
+    attr.path().get_ident().is_some_and(|ident| {
+        [
+            "get", "post", "put", "delete", "patch", "head", "options", "ws",
+        ]
+        .contains(&ident.to_string().as_str())
+    })
+}
+
+/// Route attributes consumed by `#[routes]`
+fn is_route_meta_attr(attr: &Attribute) -> bool {
+    attr.path().get_ident().is_some_and(|ident| {
+        [
+            "aspect",
+            "guard",
+            "middleware",
+            "public",
+            "csrf_exempt",
+            "roles",
+            "timeout",
+            "rate_limit",
+            "skip_global_pipes",
+            "status",
+            "response_header",
+            "produces",
+            "version",
+            "api",
+        ]
+        .contains(&ident.to_string().as_str())
     })
 }
 
 fn is_param_attr(attr: &Attribute) -> bool {
-    attr.path().get_ident().map_or(false, |ident| {
-        ["body", "param", "query"].contains(&ident.to_string().as_str())
+    attr.path().get_ident().is_some_and(|ident| {
+        [
+            "body",
+            "form",
+            "multipart",
+            "param",
+            "query",
+            "header",
+            "cookie",
+            "host_param",
+            "ip",
+            "service",
+            "session",
+            "csrf_token",
+            "current_user",
+            "pipe",
+        ]
+        .contains(&ident.to_string().as_str())
     })
-}
\ No newline at end of file
+}
@@ -1,35 +1,165 @@
 use proc_macro::TokenStream;
-use quote::quote;
-use syn::{parse_macro_input, ItemFn, ItemStruct};
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{quote, quote_spanned};
+use syn::{parse_macro_input, spanned::Spanned, FnArg, ImplItem, Item, ItemImpl, ItemStruct, Type};
 
 pub fn exception_filter_attribute(_attr: TokenStream, item: TokenStream) -> TokenStream {
-    let input = parse_macro_input!(item as ItemStruct);
-
-    // We just mark it for now, or we could generate the 'ExceptionFilter' trait impl
-    // if we wanted to enforce structure.
-    // For now, it mostly acts as a marker or Injectable.
+    match parse_macro_input!(item as Item) {
+        Item::Struct(input) => filter_struct(input),
+        Item::Impl(input) => match filter_impl(input) {
+            Ok(tokens) => tokens,
+            Err(e) => e.to_compile_error(),
+        },
+        other => syn::Error::new_spanned(
+            other,
+            "#[exception_filter] can be placed on a struct or its impl block",
+        )
+        .to_compile_error(),
+    }
+    .into()
+}
 
+fn filter_struct(input: ItemStruct) -> TokenStream2 {
     quote! {
-        #[derive(::meshestra::Injectable)]
+        #[derive(::meshestra::DeriveInjectable)]
         #input
     }
-    .into()
 }
 
-pub fn handle_attribute(_attr: TokenStream, item: TokenStream) -> TokenStream {
-    // This is used on methods inside an exception filter.
-    // It is primarily metadata. The real registration logic usually happens in
-    // the filters 'new' or 'init' or via reflection if we had it.
-    // In Rust, we might rely on the user to register these handlers,
-    // or generate a 'register_handlers' method.
+/// A `#[handle]` method of a filter
+struct Handler {
+    method: syn::Ident,
+    /// `None` for the catch-all
+    error: Option<Type>,
+    takes_host: bool,
+}
 
-    // For this implementation, we'll let it pass through but ensure it's valid.
-    let input = parse_macro_input!(item as ItemFn);
+/// Generates `ExceptionFilter` from the `#[handle]` methods, tried in
+/// declaration order
+fn filter_impl(mut input: ItemImpl) -> syn::Result<TokenStream2> {
+    let mut handlers = Vec::new();
+    let mut catch_all = None;
+    let mut assertions = Vec::new();
 
-    // We could parse 'attr' to get the exception type: #[handle(UserError)]
+    for item in &mut input.items {
+        let ImplItem::Fn(method) = item else { continue };
+        let Some(index) = method
+            .attrs
+            .iter()
+            .position(|attr| attr.path().is_ident("handle"))
+        else {
+            continue;
+        };
+        let attr = method.attrs.remove(index);
 
-    quote! {
-        #input
+        let params: Vec<_> = method.sig.inputs.iter().collect();
+        let error_param = match params.as_slice() {
+            [FnArg::Receiver(_), FnArg::Typed(error), rest @ ..] if rest.len() <= 1 => error,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &method.sig,
+                    "#[handle] methods take `&self`, the error and optionally `&ArgumentsHost`",
+                ))
+            }
+        };
+        let handler = Handler {
+            method: method.sig.ident.clone(),
+            error: None,
+            takes_host: params.len() == 3,
+        };
+
+        if matches!(attr.meta, syn::Meta::Path(_)) {
+            if catch_all.is_some() {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "only one catch-all #[handle] is allowed",
+                ));
+            }
+            catch_all = Some(handler);
+            continue;
+        }
+
+        let error: Type = attr.parse_args()?;
+        let Type::Reference(param) = &*error_param.ty else {
+            return Err(syn::Error::new_spanned(
+                &error_param.ty,
+                "the error is borrowed, e.g. `err: &UserError`",
+            ));
+        };
+        let param_ty = &param.elem;
+        assertions.push(quote_spanned! {param_ty.span()=>
+            const _: fn() = || {
+                fn handled_error<E: ::std::error::Error + Send + Sync + 'static>() {}
+                handled_error::<#param_ty>();
+            };
+        });
+        handlers.push(Handler {
+            error: Some(error),
+            ..handler
+        });
     }
+
+    let call = |handler: &Handler, error: TokenStream2| {
+        let method = &handler.method;
+        if handler.takes_host {
+            quote! { self.#method(#error, __host) }
+        } else {
+            quote! { self.#method(#error) }
+        }
+    };
+
+    let dispatch = handlers.iter().map(|handler| {
+        let error = &handler.error;
+        let call = call(handler, quote!(__error));
+        quote! {
+            if let Some(__error) = error.downcast_ref::<#error>() {
+                return #call;
+            }
+        }
+    });
+    let fallback = match &catch_all {
+        Some(handler) => call(handler, quote!(error.as_ref())),
+        None => quote! { ::meshestra::exception::default_response(error.as_ref(), __host) },
+    };
+    let catches = if catch_all.is_some() {
+        quote! { true }
+    } else {
+        let errors = handlers.iter().map(|handler| &handler.error);
+        quote! { false #(|| error.is::<#errors>())* }
+    };
+
+    let self_ty = &input.self_ty;
+    let (impl_generics, _, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        #input
+
+        #(#assertions)*
+
+        impl #impl_generics ::meshestra::exception::ExceptionFilter for #self_ty #where_clause {
+            fn catch(
+                &self,
+                error: Box<dyn ::std::error::Error + Send + Sync>,
+                __host: &::meshestra::exception::ArgumentsHost,
+            ) -> ::axum::response::Response {
+                #(#dispatch)*
+                #fallback
+            }
+
+            fn catches(&self, error: &(dyn ::std::error::Error + Send + Sync + 'static)) -> bool {
+                #catches
+            }
+        }
+    })
+}
+
+pub fn handle_attribute(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    // `#[exception_filter]` on the impl block consumes these.
+    let input = parse_macro_input!(item as ImplItem);
+    syn::Error::new_spanned(
+        input,
+        "#[handle] methods must be in an impl block annotated with #[exception_filter]",
+    )
+    .to_compile_error()
     .into()
 }
@@ -0,0 +1,43 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Item};
+
+pub fn guard_attribute(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as Item);
+
+    // `#[controller]` consumes controller-level guards and `#[routes]` the
+    // method-level ones. Reaching here on a struct means the guards would be
+    // silently dropped.
+    if let Item::Struct(item) = &input {
+        return syn::Error::new_spanned(
+            &item.ident,
+            "#[guard] on a controller must be placed below #[controller]",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    quote! {
+        #input
+    }
+    .into()
+}
+
+pub fn roles_attribute(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as Item);
+
+    // Consumed by `#[controller]` and `#[routes]` like `#[guard]`.
+    if let Item::Struct(item) = &input {
+        return syn::Error::new_spanned(
+            &item.ident,
+            "#[roles] on a controller must be placed below #[controller]",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    quote! {
+        #input
+    }
+    .into()
+}
@@ -1,7 +1,10 @@
 use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TokenStream2;
-use quote::quote;
-use syn::{parse_macro_input, Data, DeriveInput, Fields, GenericArgument, PathArguments, Type};
+use quote::{quote, quote_spanned};
+use syn::{
+    parse_macro_input, Attribute, Data, DeriveInput, Fields, GenericArgument, LitStr,
+    PathArguments, Type,
+};
 
 pub fn derive_injectable(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
@@ -13,12 +16,47 @@ fn generate_injectable_impl(input: &DeriveInput) -> TokenStream2 {
     let struct_name = &input.ident;
     let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
 
+    let constructor = match parse_injectable_constructor(&input.attrs) {
+        Ok(constructor) => constructor,
+        Err(e) => return e.to_compile_error(),
+    };
+    if let Some(constructor) = constructor {
+        let construct = constructor_injection(&constructor);
+        return quote! {
+            impl #impl_generics ::meshestra::Injectable for #struct_name #ty_generics #where_clause {
+                fn inject(container: &::meshestra::Container) -> ::meshestra::Result<Self> {
+                    #construct
+                }
+            }
+        };
+    }
+
     let fields = match &input.data {
         Data::Struct(s) => match &s.fields {
             Fields::Named(f) => &f.named,
-            _ => panic!("#[derive(Injectable)] only supports structs with named fields."),
+            Fields::Unnamed(f) => {
+                return syn::Error::new_spanned(
+                    f,
+                    "#[derive(Injectable)] needs named fields, e.g. `struct Service { repository: Arc<Repository> }`",
+                )
+                .to_compile_error()
+            }
+            Fields::Unit => {
+                return syn::Error::new_spanned(
+                    struct_name,
+                    format!("#[derive(Injectable)] needs braces on a struct without fields: `struct {} {{}}`", struct_name),
+                )
+                .to_compile_error()
+            }
         },
-        _ => panic!("#[derive(Injectable)] can only be used on structs."),
+        Data::Enum(e) => {
+            return syn::Error::new_spanned(e.enum_token, "#[derive(Injectable)] can only be used on structs, not enums")
+                .to_compile_error()
+        }
+        Data::Union(u) => {
+            return syn::Error::new_spanned(u.union_token, "#[derive(Injectable)] can only be used on structs, not unions")
+                .to_compile_error()
+        }
     };
 
     let field_injections = fields.iter().map(|field| {
@@ -52,6 +90,14 @@ fn generate_injectable_impl(input: &DeriveInput) -> TokenStream2 {
         }
     });
 
+    // What each field resolves, now or when a `Lazy` is first used
+    let dependencies = fields.iter().map(|field| {
+        let dependency = get_generic_type(&field.ty, "Lazy")
+            .or_else(|| get_generic_type(&field.ty, "Arc"))
+            .unwrap_or(&field.ty);
+        quote! { ::meshestra::di::Dependency::of::<#dependency>() }
+    });
+
     quote! {
         impl #impl_generics ::meshestra::Injectable for #struct_name #ty_generics #where_clause {
             fn inject(container: &::meshestra::Container) -> ::meshestra::Result<Self> {
@@ -59,10 +105,54 @@ fn generate_injectable_impl(input: &DeriveInput) -> TokenStream2 {
                     #(#field_injections),*
                 })
             }
+
+            fn dependencies() -> ::std::vec::Vec<::meshestra::di::Dependency> {
+                ::std::vec![#(#dependencies),*]
+            }
         }
     }
 }
 
+/// The `constructor = "new"` of `#[injectable(...)]`, if any
+fn parse_injectable_constructor(attrs: &[Attribute]) -> syn::Result<Option<LitStr>> {
+    let mut constructor = None;
+    for attr in attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("injectable"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("constructor") {
+                constructor = Some(meta.value()?.parse::<LitStr>()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `constructor = \"...\"`"))
+            }
+        })?;
+    }
+    Ok(constructor)
+}
+
+/// The body of an `inject` calling the associated function `constructor`
+/// names, with its parameters resolved from the container
+///
+/// The call is spanned at the name, so a parameter the container can't
+/// provide is reported there.
+pub(crate) fn constructor_injection(constructor: &LitStr) -> TokenStream2 {
+    let ident = match constructor.parse::<syn::Ident>() {
+        Ok(ident) => ident,
+        Err(_) => {
+            return syn::Error::new_spanned(
+                constructor,
+                "expected the name of an associated function",
+            )
+            .to_compile_error()
+        }
+    };
+    quote_spanned! {constructor.span()=>
+        ::meshestra::di::construct(container, Self::#ident)
+    }
+}
+
 /// Helper to extract the inner type from a generic wrapper like `Arc<T>` or `Lazy<T>`.
 /// Returns `Some(T)` if `ty` matches `wrapper_name<T>`, otherwise `None`.
 fn get_generic_type<'a>(ty: &'a Type, wrapper_name: &str) -> Option<&'a Type> {
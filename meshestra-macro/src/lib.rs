@@ -1,12 +1,19 @@
 use proc_macro::TokenStream;
 
+mod api_schema;
 mod aspect;
 mod controller;
 mod exception;
+mod guard;
 mod http_methods;
 mod injectable;
 mod interceptor;
+mod message_keys;
+mod middleware;
+mod mock;
 mod module;
+mod multipart;
+mod test;
 mod transactional;
 
 /// Derive macro for making a struct injectable into the DI container
@@ -20,29 +27,152 @@ mod transactional;
 ///     repository: Arc<dyn UserRepository>,
 /// }
 /// ```
-#[proc_macro_derive(Injectable)]
+///
+/// `#[injectable(constructor = "new")]` builds the struct with its own
+/// associated function instead, resolving each parameter (`Arc<T>`,
+/// `Arc<dyn Trait>` or `Lazy<T>`) from the container. A constructor
+/// returning a `Result` fails the injection with its error.
+///
+/// ```ignore
+/// #[derive(Injectable)]
+/// #[injectable(constructor = "new")]
+/// pub struct SlugService {
+///     pattern: Regex,
+/// }
+///
+/// impl SlugService {
+///     fn new(config: Arc<ConfigService>) -> Result<Self> {
+///         // ...
+///     }
+/// }
+/// ```
+#[proc_macro_derive(Injectable, attributes(injectable))]
 pub fn derive_injectable(input: TokenStream) -> TokenStream {
     injectable::derive_injectable(input)
 }
 
-/// Attribute macro for defining a controller with automatic DI registration
+/// Derive macro reading a struct from a `multipart/form-data` body
+///
+/// Each field is read from the part of the same name: `String`, `Vec<u8>`,
+/// `Bytes` or `UploadedFile` (with the client's file name and content
+/// type) parts are required, `Option`s of them optional. Requires
+/// meshestra's `multipart` feature.
 ///
 /// # Example
+/// ```ignore
+/// #[derive(FromMultipart)]
+/// pub struct AvatarUpload {
+///     user_id: String,
+///     #[part(name = "file", limit = 1024 * 1024)]
+///     avatar: UploadedFile,
+///     caption: Option<String>,
+/// }
+/// ```
+#[proc_macro_derive(FromMultipart, attributes(part))]
+pub fn derive_from_multipart(input: TokenStream) -> TokenStream {
+    multipart::derive_from_multipart(input)
+}
+
+/// Derive macro describing a type in the OpenAPI document
+///
+/// Structs with named fields become an object schema under
+/// `components/schemas`, newtypes the schema of what they wrap and enums of
+/// unit variants string enums. Doc comments become descriptions,
+/// `#[serde(rename, rename_all, skip, default)]` are followed, `Option`
+/// fields aren't required and `#[api(hidden)]` leaves a field out.
+///
+/// # Example
+/// ```ignore
+/// /// A registered user
+/// #[derive(Serialize, ApiSchema)]
+/// #[serde(rename_all = "camelCase")]
+/// pub struct User {
+///     pub id: String,
+///     /// Shown to other users
+///     pub display_name: String,
+///     pub bio: Option<String>,
+///     #[api(hidden)]
+///     pub password_hash: String,
+/// }
+/// ```
+#[proc_macro_derive(ApiSchema, attributes(api))]
+pub fn derive_api_schema(input: TokenStream) -> TokenStream {
+    api_schema::derive_api_schema(input)
+}
+
+/// Derive macro naming the message keys of a validated type's fields
+///
+/// `#[body(validate)]` answers the failures of a field with
+/// `#[message(key = "...")]` with that key's message, in the request's
+/// language, instead of the validator's message or code. Keys apply to
+/// the type's own fields, not to those of nested types.
+///
+/// # Example
+/// ```ignore
+/// #[derive(Deserialize, Validate, MessageKeys)]
+/// pub struct CreateUser {
+///     #[validate(email)]
+///     #[message(key = "user.email.invalid")]
+///     email: String,
+/// }
+/// ```
+#[proc_macro_derive(MessageKeys, attributes(message))]
+pub fn derive_message_keys(input: TokenStream) -> TokenStream {
+    message_keys::derive_message_keys(input)
+}
+
+/// Attribute macro for defining a controller with automatic DI registration
+///
+/// `auto_head = true` answers HEAD requests to every GET route with the
+/// GET response's headers and an empty body; otherwise HEAD is refused
+/// with a 405 like any method a path has no route for.
+///
+/// On the impl block, `#[controller]` also does the work of `#[routes]`,
+/// generating `router()`; the struct then derives `Injectable`:
+///
+/// ```ignore
+/// #[derive(Injectable)]
+/// pub struct UserController {
+///     user_service: Arc<UserService>,
+/// }
+///
+/// #[controller(path = "/users")]
+/// impl UserController {
+///     #[get("/{id}")]
+///     async fn get_user(&self, #[param] id: String) -> Response {
+///         // ...
+///     }
+/// }
 /// ```
-/// use meshestra::controller;
 ///
+/// On the struct, it generates `Injectable` instead, and the impl block
+/// takes `#[routes]`:
+///
+/// ```ignore
 /// #[controller(path = "/users")]
 /// pub struct UserController {
 ///     user_service: Arc<UserService>,
 /// }
 ///
+/// #[routes(UserController)]
 /// impl UserController {
 ///     #[get("/{id}")]
-///     async fn get_user(&self, Path(id): Path<String>) -> Response {
+///     async fn get_user(&self, #[param] id: String) -> Response {
 ///         // ...
 ///     }
 /// }
 /// ```
+///
+/// Controller-level `#[guard]`, `#[roles]`, `#[aspect]` and `#[cors]` go
+/// below `#[controller]` in either form.
+///
+/// `constructor = "new"` injects the struct form through its own
+/// associated function, as `#[injectable(constructor = "new")]` does for
+/// `#[derive(Injectable)]`; in the impl form, put that on the derive.
+///
+/// `version = "v1"` puts every route in API version `v1`, served under
+/// `/v1` or picked with the `X-Api-Version` header, as the module router's
+/// `VersioningConfig` says. A route's `#[version("2")]` overrides it.
 #[proc_macro_attribute]
 pub fn controller(attr: TokenStream, item: TokenStream) -> TokenStream {
     controller::controller_attribute(attr, item)
@@ -50,11 +180,21 @@ pub fn controller(attr: TokenStream, item: TokenStream) -> TokenStream {
 
 /// Attribute macro for defining routes in an impl block
 ///
+/// Not needed when `#[controller]` is on the impl block itself.
+///
+/// A handler returning `impl Stream<Item = T>`, with `T: Serialize`, is
+/// served as server-sent events, one JSON `data` event per item; the stream
+/// may borrow `self`. One returning `Sse<...>` is a response like any other.
+///
+/// Each route is described for the OpenAPI document: the first paragraph
+/// of the handler's doc comment is its summary and the rest its
+/// description. `#[api(hidden)]` leaves a route out.
+///
 /// # Example
-/// ```
+/// ```ignore
 /// #[routes(UserController)]
 /// impl UserController {
-///     #[get("/:id")]
+///     #[get("/{id}")]
 ///     async fn get_user(&self, Path(id): Path<String>) -> Json<User> {
 ///         // ...
 ///     }
@@ -67,6 +207,13 @@ pub fn routes(attr: TokenStream, item: TokenStream) -> TokenStream {
 
 /// Attribute macro for defining a module with providers and controllers
 ///
+/// Providers are registered once the ones they inject are, so they can be
+/// listed in any order; registration fails naming the provider and the type
+/// it's missing, or the cycle its dependencies make.
+///
+/// `prefix = "/admin"` serves the module's controllers, and those of its
+/// imports, under that segment, after the application's global prefix.
+///
 /// # Example
 /// ```
 /// use meshestra::module;
@@ -84,9 +231,16 @@ pub fn module(attr: TokenStream, item: TokenStream) -> TokenStream {
 
 /// Attribute macro for defining interceptors on a controller
 ///
+/// Placed below `#[controller]`, the interceptors wrap every route, outside
+/// its aspects; among themselves, listed order, then `priority`, decides the
+/// nesting. A type is resolved from the container per request; an
+/// expression such as `CacheInterceptor::new(60)` is built once per router
+/// and shared by its routes.
+///
 /// # Example
-/// ```
-/// #[interceptor(LoggingInterceptor)]
+/// ```ignore
+/// #[controller(path = "/users")]
+/// #[interceptor(LoggingInterceptor, CacheInterceptor::new(60))]
 /// pub struct UserController { ... }
 /// ```
 #[proc_macro_attribute]
@@ -94,11 +248,44 @@ pub fn interceptor(attr: TokenStream, item: TokenStream) -> TokenStream {
     interceptor::interceptor_attribute(attr, item)
 }
 
+/// Applies a tower layer to a route, or to every route of a controller
+///
+/// Takes any expression evaluating to a `Layer`, so configured layers work.
+/// On a method it's added with `route_layer` and wraps that route's handler
+/// only; below `#[controller]` it's added to the controller's routers with
+/// `Router::layer`. Several stack in declaration order, the first outermost.
+///
+/// # Example
+/// ```ignore
+/// #[controller(path = "/uploads")]
+/// #[middleware(TraceLayer::new_for_http())]
+/// pub struct UploadController {}
+///
+/// #[routes(UploadController)]
+/// impl UploadController {
+///     #[post("/")]
+///     #[middleware(RequestBodyLimitLayer::new(1024 * 1024))]
+///     async fn upload(&self, body: Bytes) -> StatusCode { ... }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn middleware(attr: TokenStream, item: TokenStream) -> TokenStream {
+    middleware::middleware_attribute(attr, item)
+}
+
 /// Wrapped an async function to execute within a transaction
 ///
+/// # Transaction manager
+/// - `manager = <expr>` uses that manager, e.g. `manager = self.tx`.
+/// - `manager_name = "..."` uses the manager registered under that name.
+/// - `manager = ambient` uses the one installed with `with_manager` or
+///   `set_global_manager`.
+/// - Without either, a free function uses the ambient manager. Methods must
+///   name theirs: the macro cannot see the fields of `Self`.
+///
 /// # Example
 /// ```
-/// #[transactional]
+/// #[transactional(manager = self.transaction_manager)]
 /// async fn create_user(&self, user: User) -> Result<User> { ... }
 /// ```
 #[proc_macro_attribute]
@@ -106,12 +293,69 @@ pub fn transactional(attr: TokenStream, item: TokenStream) -> TokenStream {
     transactional::transactional_attribute(attr, item)
 }
 
+/// Runs an async test on a Tokio runtime, like `#[tokio::test]`
+///
+/// With `transactional`, the body runs in a transaction of the global
+/// transaction manager (or of `transactional(manager = <expr>)`) that is
+/// rolled back at the end whatever the outcome, see
+/// `meshestra::testing::TransactionalTest`.
+///
+/// # Example
+/// ```ignore
+/// #[meshestra::test(transactional)]
+/// async fn users_are_saved() {
+///     let user = service.create_user(new_user()).await.unwrap();
+///     assert!(repository.find(user.id).await.unwrap().is_some());
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn test(attr: TokenStream, item: TokenStream) -> TokenStream {
+    test::test_attribute(attr, item)
+}
+
+/// Generates a mock of a trait, for binding it in tests
+///
+/// Each method is answered by a closure set with `on_<method>`; calling one
+/// that isn't set panics. Calls are counted for `assert_called`. The mock
+/// implements `meshestra::testing::MockProvider`, so it can replace the
+/// trait's binding with `TestAppBuilder::mock` or `MockProvider::override_in`.
+///
+/// # Example
+/// ```ignore
+/// mock_trait! {
+///     pub MockUserRepository: UserRepository {
+///         async fn find_by_id(&self, id: &str) -> Result<Option<User>>;
+///         async fn save(&self, user: User) -> Result<User>;
+///     }
+/// }
+///
+/// let repository = MockUserRepository::new().on_find_by_id(|_| Ok(Some(user())));
+/// ```
+#[proc_macro]
+pub fn mock_trait(input: TokenStream) -> TokenStream {
+    mock::mock_trait(input)
+}
+
 /// Attribute macro for defining an exception filter
 ///
+/// On the struct it derives `Injectable`; on its impl block it implements
+/// `ExceptionFilter` from the `#[handle]` methods. Errors are downcast to
+/// each handled type in declaration order, then go to the catch-all
+/// `#[handle]` method, if any, or get a generic 500.
+///
 /// # Example
 /// ```
 /// #[exception_filter]
 /// pub struct GlobalExceptionFilter;
+///
+/// #[exception_filter]
+/// impl GlobalExceptionFilter {
+///     #[handle(UserError)]
+///     fn handle_user(&self, err: &UserError, host: &ArgumentsHost) -> Response { ... }
+///
+///     #[handle]
+///     fn handle_any(&self, err: &(dyn Error + Send + Sync), host: &ArgumentsHost) -> Response { ... }
+/// }
 /// ```
 #[proc_macro_attribute]
 pub fn exception_filter(attr: TokenStream, item: TokenStream) -> TokenStream {
@@ -120,10 +364,13 @@ pub fn exception_filter(attr: TokenStream, item: TokenStream) -> TokenStream {
 
 /// Attribute macro for defining an exception handler method
 ///
+/// Only valid inside an `#[exception_filter]` impl block. The handled type
+/// must implement `std::error::Error`.
+///
 /// # Example
 /// ```
 /// #[handle(UserError)]
-/// fn handle_user(&self, err: UserError) -> Response { ... }
+/// fn handle_user(&self, err: &UserError, host: &ArgumentsHost) -> Response { ... }
 /// ```
 #[proc_macro_attribute]
 pub fn handle(attr: TokenStream, item: TokenStream) -> TokenStream {
@@ -131,6 +378,15 @@ pub fn handle(attr: TokenStream, item: TokenStream) -> TokenStream {
 }
 
 /// HTTP GET method attribute for controller methods
+///
+/// Like every method attribute, it can be repeated, or take aliases as in
+/// `#[get("/users/{id}", alias = "/members/{id}")]`, to serve one handler
+/// on several paths; all of them must have the same path parameters, in
+/// the same order.
+///
+/// Path parameters are written `{id}`. The older `:id` form is rewritten
+/// to it, with a deprecation warning at the path; deny the `deprecated`
+/// lint to make it an error instead.
 #[proc_macro_attribute]
 pub fn get(attr: TokenStream, item: TokenStream) -> TokenStream {
     http_methods::http_method_attribute("GET", attr, item)
@@ -160,8 +416,49 @@ pub fn patch(attr: TokenStream, item: TokenStream) -> TokenStream {
     http_methods::http_method_attribute("PATCH", attr, item)
 }
 
+/// HTTP HEAD method attribute for controller methods
+///
+/// Takes precedence over the controller's `auto_head` for its path.
+#[proc_macro_attribute]
+pub fn head(attr: TokenStream, item: TokenStream) -> TokenStream {
+    http_methods::http_method_attribute("HEAD", attr, item)
+}
+
+/// HTTP OPTIONS method attribute for controller methods
+#[proc_macro_attribute]
+pub fn options(attr: TokenStream, item: TokenStream) -> TokenStream {
+    http_methods::http_method_attribute("OPTIONS", attr, item)
+}
+
+/// WebSocket route attribute for controller methods
+///
+/// The method takes a `WebSocketSession` and, like other routes, `#[param]`,
+/// `#[query]` and `#[service]` parameters. Guards, roles and aspects run on
+/// the upgrade request; once it's accepted, the method runs on the
+/// connection. Needs the `websocket` feature.
+///
+/// # Example
+/// ```ignore
+/// #[ws("/rooms/{room}")]
+/// async fn chat(&self, mut session: WebSocketSession, #[param] room: String) {
+///     while let Some(Ok(message)) = session.recv_json::<ChatMessage>().await {
+///         session.send_json(&self.rooms.post(&room, message)).ok();
+///     }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn ws(attr: TokenStream, item: TokenStream) -> TokenStream {
+    http_methods::http_method_attribute("GET", attr, item)
+}
+
 /// Parameter attribute for request body (JSON)
 /// Wraps the parameter with axum::Json extractor
+///
+/// `#[body(validate)]` also runs `validator::Validate` on the parsed body
+/// (requires meshestra's `validation` feature), answering invalid bodies
+/// with a 422 listing the offending fields. Their messages are looked up
+/// by the container's `MessageResolver` in the request's `Accept-Language`;
+/// see `#[derive(MessageKeys)]` for naming their keys.
 #[proc_macro_attribute]
 pub fn body(_attr: TokenStream, item: TokenStream) -> TokenStream {
     // Pass-through, actual handling is done by #[routes] macro
@@ -170,6 +467,20 @@ pub fn body(_attr: TokenStream, item: TokenStream) -> TokenStream {
 
 /// Parameter attribute for path parameters
 /// Wraps the parameter with axum::extract::Path extractor
+///
+/// A route with several placeholders takes one `#[param]` per placeholder,
+/// in path order, or a single tuple or struct holding them all:
+///
+/// ```ignore
+/// #[get("/users/{user_id}/posts/{post_id}")]
+/// async fn post(&self, #[param] user_id: u64, #[param] post_id: u64) -> String { ... }
+/// ```
+///
+/// `#[param("repo_id")]` picks a placeholder by name instead; unnamed
+/// parameters then take the remaining ones in order. Either way the values
+/// are deserialized by axum's `Path`, so `i64`s and `Uuid`s work as well as
+/// `String`s. The count is checked at compile time, as are the paths
+/// themselves.
 #[proc_macro_attribute]
 pub fn param(_attr: TokenStream, item: TokenStream) -> TokenStream {
     // Pass-through, actual handling is done by #[routes] macro
@@ -184,33 +495,110 @@ pub fn query(_attr: TokenStream, item: TokenStream) -> TokenStream {
     item
 }
 
-/// Parameter attribute for request headers
-/// Wraps the parameter with axum::extract::Header extractor
+/// Parameter attribute for an HTML form body
+/// Wraps the parameter with axum::extract::Form extractor
+///
+/// Requests that aren't `application/x-www-form-urlencoded` are answered
+/// with a 415.
+#[proc_macro_attribute]
+pub fn form(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    // Pass-through, actual handling is done by #[routes] macro
+    item
+}
+
+/// Parameter attribute for a `multipart/form-data` body
+///
+/// The parameter is axum's raw `Multipart` stream or a type implementing
+/// `FromMultipart`, usually derived; it must be the last parameter.
+/// Requests that aren't `multipart/form-data` are answered with a 415.
+/// Requires meshestra's `multipart` feature.
 ///
 /// # Example
+/// ```ignore
+/// #[routes(AvatarController)]
+/// impl AvatarController {
+///     #[post("/")]
+///     async fn upload(&self, #[multipart] upload: AvatarUpload) -> Json<Avatar> {
+///         // ...
+///     }
+/// }
 /// ```
-/// #[headers(param?: string)]
+#[proc_macro_attribute]
+pub fn multipart(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    // Pass-through, actual handling is done by #[routes] macro
+    item
+}
+
+/// Parameter attribute running pipes on the extracted value
+///
+/// `#[param] #[pipe(TrimPipe, ParseIntPipe)] id: i32` extracts the first
+/// pipe's `Input`, runs the pipes in order and hands the last one's
+/// `Output` to the handler. Pipes come from the container, or are built
+/// with `Default` when not registered; a rejected value is answered with
+/// the `PipeError` (a 400 for invalid input) without running the handler.
+#[proc_macro_attribute]
+pub fn pipe(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    // Pass-through, actual handling is done by #[routes] macro
+    item
+}
+
+/// Parameter attribute for a request header
+///
+/// `#[header("X-Api-Key")] key: String` requires the header and answers
+/// requests without it with a 400 naming it; `Option<String>` makes it
+/// optional. Other `FromValue` types (numbers, `bool`, `IpAddr`, ...) are
+/// parsed from the value, a value that doesn't parse is a 400 as well.
+///
+/// # Example
+/// ```ignore
+/// #[routes(UserController)]
 /// impl UserController {
 ///     #[get("/")]
-///     async fn get_user(&self, #[header] h: Header<String>) -> Response {
+///     async fn list(&self, #[header("X-Api-Key")] key: Option<String>) -> Response {
 ///         // ...
 ///     }
 /// }
 /// ```
 #[proc_macro_attribute]
-pub fn headers(_attr: TokenStream, item: TokenStream) -> TokenStream {
+pub fn header(_attr: TokenStream, item: TokenStream) -> TokenStream {
     // Pass-through, actual handling is done by #[routes] macro
     item
 }
 
-/// Parameter attribute for request IP address
-/// Wraps the parameter with axum::extract::ConnectInfo extractor
+/// Parameter attribute for a cookie of the `Cookie` header
+///
+/// Works like `#[header]`: `String` requires the cookie, `Option<String>`
+/// makes it optional.
 ///
 /// # Example
+/// ```ignore
+/// #[routes(SessionController)]
+/// impl SessionController {
+///     #[get("/me")]
+///     async fn me(&self, #[cookie("session")] sid: Option<String>) -> Response {
+///         // ...
+///     }
+/// }
 /// ```
+#[proc_macro_attribute]
+pub fn cookie(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    // Pass-through, actual handling is done by #[routes] macro
+    item
+}
+
+/// Parameter attribute for the client's IP address
+///
+/// Reads the peer address from `ConnectInfo<SocketAddr>`, so serve the
+/// router with `into_make_service_with_connect_info::<SocketAddr>()`.
+/// Behind a proxy, `ApplicationBuilder::trust_forwarded_for(true)` takes
+/// the leftmost `X-Forwarded-For` address instead.
+///
+/// # Example
+/// ```ignore
+/// #[routes(UserController)]
 /// impl UserController {
 ///     #[get("/")]
-///     async fn get_user(&self, #[ip] ip: String) -> Response {
+///     async fn get_user(&self, #[ip] ip: IpAddr) -> Response {
 ///         // ...
 ///     }
 /// }
@@ -221,11 +609,110 @@ pub fn ip(_attr: TokenStream, item: TokenStream) -> TokenStream {
     item
 }
 
-/// Parameter attribute for request host
-/// Wraps the parameter with axum::extract::ConnectInfo extractor
+/// Parameter attribute for a service resolved from the container per request
+///
+/// For dependencies only one endpoint needs, instead of a controller
+/// field. The parameter is an `Arc<T>`, or an `Arc<dyn Trait>` registered
+/// with `register_trait`; a missing service is a 500 `DependencyNotFound`.
+///
+/// # Example
+/// ```ignore
+/// #[routes(ReportController)]
+/// impl ReportController {
+///     #[get("/export")]
+///     async fn export(&self, #[service] reporter: Arc<ReportService>) -> String {
+///         reporter.csv().await
+///     }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn service(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    // Pass-through, actual handling is done by #[routes] macro
+    item
+}
+
+/// Parameter attribute for the request's `Session`
+///
+/// Needs the `session` feature and `ApplicationBuilder::sessions`; without
+/// the session layer the request fails with a 500. Changes are saved once
+/// the handler returns.
 ///
 /// # Example
+/// ```ignore
+/// #[routes(CartController)]
+/// impl CartController {
+///     #[post("/items")]
+///     async fn add(&self, #[session] session: Session, #[body] item: Item) -> Result<()> {
+///         let mut cart: Vec<Item> = session.get("cart").unwrap_or_default();
+///         cart.push(item);
+///         session.insert("cart", cart)?;
+///         Ok(())
+///     }
+/// }
 /// ```
+#[proc_macro_attribute]
+pub fn session(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    // Pass-through, actual handling is done by #[routes] macro
+    item
+}
+
+/// Parameter attribute for the `CurrentUser<T>` the route's guards
+/// authenticated
+///
+/// Requests no guard authenticated are answered with 401; take an
+/// `Option<CurrentUser<T>>` on routes open to anonymous users too. Asking
+/// for a `T` other than the one the guard inserted is answered with 500.
+///
+/// # Example
+/// ```ignore
+/// #[routes(ProfileController)]
+/// impl ProfileController {
+///     #[get("/")]
+///     #[guard(JwtAuthGuard<UserClaims>)]
+///     async fn show(&self, #[current_user] user: CurrentUser<UserClaims>) -> String {
+///         user.sub.clone()
+///     }
+///
+///     #[get("/greeting")]
+///     async fn greet(&self, #[current_user] user: Option<CurrentUser>) -> String {
+///         format!("hello {}", user.map_or("stranger".to_string(), |user| user.id.clone()))
+///     }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn current_user(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    // Pass-through, actual handling is done by #[routes] macro
+    item
+}
+
+/// Parameter attribute for the session's `CsrfToken`, to embed in forms
+///
+/// The token is created with the session's first one and replaced by
+/// `Session::regenerate_id`. Needs the `session` feature.
+///
+/// # Example
+/// ```ignore
+/// #[routes(ProfileController)]
+/// impl ProfileController {
+///     #[get("/edit")]
+///     async fn edit(&self, #[csrf_token] token: CsrfToken) -> Html<String> {
+///         Html(format!(r#"<form method="post"><input type="hidden" name="_csrf" value="{}">...</form>"#, token))
+///     }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn csrf_token(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    // Pass-through, actual handling is done by #[routes] macro
+    item
+}
+
+/// Parameter attribute for the request's host
+///
+/// Reads the `Host` header, or the URI's authority for HTTP/2 requests.
+///
+/// # Example
+/// ```ignore
+/// #[routes(UserController)]
 /// impl UserController {
 ///     #[get("/")]
 ///     async fn get_user(&self, #[host_param] host: String) -> Response {
@@ -258,6 +745,22 @@ pub fn host_param(_attr: TokenStream, item: TokenStream) -> TokenStream {
 /// 3. `Aspect::after`: Runs after successful handler execution.
 /// 4. `Aspect::on_error`: Runs if the handler or interceptors fail.
 ///
+/// # Ordering
+/// A request to a route passes through, outermost first:
+/// 1. Exception filters, answering whatever fails inside them.
+/// 2. Global interceptors, which wrap the whole router.
+/// 3. Guards: the controller's, the method's, then `#[roles]`.
+/// 4. Controller-level `#[interceptor(...)]`s.
+/// 5. Controller-level aspects, in declaration order.
+/// 6. Method-level aspects, in declaration order.
+/// 7. The handler.
+///
+/// The first aspect listed is the outermost: its `before` runs first and
+/// its `after` last. Among the controller's aspects, and among the
+/// method's, a lower `priority` moves an aspect outwards; it never moves a
+/// method aspect outside the controller's. `meshestra::testing::ExecutionTrace`
+/// records the order for tests to assert.
+///
 /// # Example
 /// ```rust
 /// // Apply to all methods in the controller
@@ -266,8 +769,8 @@ pub fn host_param(_attr: TokenStream, item: TokenStream) -> TokenStream {
 /// pub struct UserController { ... }
 ///
 /// impl UserController {
-///     // Apply to a specific method (runs after controller-level aspects)
-///     #[get("/:id")]
+///     // Apply to a specific method (nested inside controller-level aspects)
+///     #[get("/{id}")]
 ///     #[aspect(LoggingAspect)]
 ///     async fn get_user(&self, #[param] id: String) -> ApiResponse<User> {
 ///         // ...
@@ -278,3 +781,244 @@ pub fn host_param(_attr: TokenStream, item: TokenStream) -> TokenStream {
 pub fn aspect(attr: TokenStream, item: TokenStream) -> TokenStream {
     aspect::aspect_attribute(attr, item)
 }
+
+/// Attribute macro for protecting routes with Guards
+///
+/// # Description
+/// Place it below `#[controller]` to guard every route of the controller, or on a
+/// handler method inside `#[routes]` to guard that route. Each guard is resolved
+/// from the DI container and its `can_activate` runs before the handler; the first
+/// denial answers the request with the guard's error response and the handler body
+/// never runs.
+///
+/// # Execution Order
+/// 1. Controller-level guards, in the order listed.
+/// 2. Method-level guards, in the order listed.
+///
+/// Mark a method `#[public]` to skip the controller-level guards for that route.
+///
+/// # Example
+/// ```rust
+/// #[controller(path = "/articles")]
+/// #[guard(AuthGuard)]
+/// pub struct ArticleController { ... }
+///
+/// #[routes(ArticleController)]
+/// impl ArticleController {
+///     #[delete("/{id}")]
+///     #[guard(RolesGuard)]
+///     async fn remove(&self, #[param] id: String) -> Result<()> { ... }
+///
+///     #[get("/")]
+///     #[public]
+///     async fn list(&self) -> Json<Vec<Article>> { ... }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn guard(attr: TokenStream, item: TokenStream) -> TokenStream {
+    guard::guard_attribute(attr, item)
+}
+
+/// Restricts a controller or route to users holding one of the listed roles
+///
+/// Runs the built-in `RolesGuard` after all `#[guard(...)]`s, so an
+/// authentication guard must insert the `CurrentUser` first. Users lacking
+/// every listed role get a 403 naming the required roles. Method-level roles
+/// replace the controller's, and `#[public]` routes skip the controller's.
+/// Role implications (an admin is also an editor) come from a `RoleHierarchy`
+/// registered in the container.
+///
+/// # Example
+/// ```rust
+/// #[controller(path = "/articles")]
+/// #[guard(AuthGuard)]
+/// #[roles("editor")]
+/// pub struct ArticleController { ... }
+///
+/// #[routes(ArticleController)]
+/// impl ArticleController {
+///     #[delete("/{id}")]
+///     #[roles("admin")]
+///     async fn remove(&self, #[param] id: String) -> Result<()> { ... }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn roles(attr: TokenStream, item: TokenStream) -> TokenStream {
+    guard::roles_attribute(attr, item)
+}
+
+/// Gives a route its own request deadline, e.g. `#[timeout("2m")]`
+///
+/// Replaces the deadline of a global `TimeoutInterceptor` for this route, or
+/// enforces it on its own when none is installed. Units are `ms`, `s`, `m`
+/// and `h`.
+///
+/// # Example
+/// ```rust
+/// #[routes(ReportController)]
+/// impl ReportController {
+///     #[get("/yearly")]
+///     #[timeout("2m")]
+///     async fn yearly(&self) -> Json<Report> { ... }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn timeout(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    // Pass-through, actual handling is done by #[routes] macro
+    item
+}
+
+/// Limits how often each client may call a route
+///
+/// Takes one of `per_second`, `per_minute` or `per_hour`. The route gets its
+/// own `RateLimitInterceptor`, keyed by client IP, whose budget is shared by
+/// all requests to the route; excess requests are answered with 429.
+///
+/// # Example
+/// ```rust
+/// #[routes(AuthController)]
+/// impl AuthController {
+///     #[post("/login")]
+///     #[rate_limit(per_minute = 5)]
+///     async fn login(&self, #[body] credentials: Credentials) -> Json<Token> { ... }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn rate_limit(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    // Pass-through, actual handling is done by #[routes] macro
+    item
+}
+
+/// Marks a route as exempt from the CSRF check of `ApplicationBuilder::csrf`
+///
+/// For requests that can't carry the token, such as webhooks from other
+/// services; authenticate those some other way.
+///
+/// # Example
+/// ```ignore
+/// #[routes(WebhookController)]
+/// impl WebhookController {
+///     #[post("/payments")]
+///     #[csrf_exempt]
+///     async fn payment(&self, #[header("Stripe-Signature")] signature: String, #[body] event: Event) -> StatusCode { ... }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn csrf_exempt(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    // Pass-through, actual handling is done by #[routes] macro
+    item
+}
+
+/// Marks a route as exempt from its controller's `#[guard(...)]`s and `#[roles(...)]`
+///
+/// Method-level guards and roles on the same route still run.
+#[proc_macro_attribute]
+pub fn public(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    // Pass-through, actual handling is done by #[routes] macro
+    item
+}
+
+/// Exempts a route from the pipes registered with `ApplicationBuilder::global_pipe`
+///
+/// `#[pipe(...)]` attributes on its parameters still run.
+#[proc_macro_attribute]
+pub fn skip_global_pipes(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    // Pass-through, actual handling is done by #[routes] macro
+    item
+}
+
+/// Answers a route with its own success status, e.g. `#[status(201)]`
+///
+/// Only replaces the default `200 OK`: error statuses, and statuses the
+/// handler set itself (such as `ApiResponse::with_status`), win.
+///
+/// # Example
+/// ```rust
+/// #[routes(UserController)]
+/// impl UserController {
+///     #[post("/")]
+///     #[status(201)]
+///     async fn create(&self, #[body] user: CreateUser) -> Json<User> { ... }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn status(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    // Pass-through, actual handling is done by #[routes] macro
+    item
+}
+
+/// Appends a header to a route's responses, e.g.
+/// `#[response_header("Cache-Control", "no-store")]`
+///
+/// Attributes stack, one header each.
+#[proc_macro_attribute]
+pub fn response_header(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    // Pass-through, actual handling is done by #[routes] macro
+    item
+}
+
+/// Sets the `Content-Type` of a route returning text or bytes, e.g.
+/// `#[produces("text/csv")]`
+///
+/// Replaces the `text/plain` of a `String` and the
+/// `application/octet-stream` of a `Vec<u8>`; JSON bodies keep theirs.
+///
+/// # Example
+/// ```rust
+/// #[routes(ReportController)]
+/// impl ReportController {
+///     #[get("/export")]
+///     #[produces("text/csv")]
+///     async fn export(&self) -> String { ... }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn produces(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    // Pass-through, actual handling is done by #[routes] macro
+    item
+}
+
+/// Overrides parts of the application's `CorsConfig` for a controller's
+/// routes
+///
+/// `origins`, `methods` and `headers` are comma-separated lists; with
+/// `credentials` and `max_age` (seconds), each replaces what the application
+/// configured. `credentials = true` with `origins = "*"` is a compile error,
+/// as browsers refuse it.
+///
+/// # Example
+/// ```ignore
+/// #[controller(path = "/admin")]
+/// #[cors(origins = "https://admin.example.com", credentials = true)]
+/// pub struct AdminController { ... }
+/// ```
+#[proc_macro_attribute]
+pub fn cors(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    // Pass-through, actual handling is done by #[controller] macro
+    item
+}
+
+/// Puts a route in another API version than its controller's, e.g.
+/// `#[version("2")]`
+///
+/// `"2"`, `"V2"` and `"v2"` are the same version. The route is left out
+/// of `router()`, which serves the controller's version; the module
+/// router serves every version.
+///
+/// # Example
+/// ```rust
+/// #[controller(path = "/users", version = "v1")]
+/// impl UserController {
+///     #[get("/")]
+///     async fn list(&self) -> Json<Vec<UserV1>> { ... }
+///
+///     #[get("/")]
+///     #[version("2")]
+///     async fn list_v2(&self) -> Json<Vec<UserV2>> { ... }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn version(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    // Pass-through, actual handling is done by #[routes] macro
+    item
+}
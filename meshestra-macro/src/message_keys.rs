@@ -0,0 +1,71 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitStr};
+
+pub fn derive_message_keys(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    generate_message_keys_impl(&input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+fn generate_message_keys_impl(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    name,
+                    "#[derive(MessageKeys)] needs a struct with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                name,
+                "#[derive(MessageKeys)] can only be used on structs",
+            ))
+        }
+    };
+
+    let mut arms = Vec::new();
+    for field in fields {
+        let Some(key) = message_key(&field.attrs)? else {
+            continue;
+        };
+        let ident = field.ident.as_ref().unwrap().to_string();
+        let ident = ident.strip_prefix("r#").unwrap_or(&ident).to_string();
+        arms.push(quote! { #ident => Some(#key), });
+    }
+
+    Ok(quote! {
+        impl #impl_generics ::meshestra::i18n::MessageKeys for #name #ty_generics #where_clause {
+            fn message_key(field: &str) -> Option<&'static str> {
+                match field {
+                    #(#arms)*
+                    _ => None,
+                }
+            }
+        }
+    })
+}
+
+/// The key of a field's `#[message(key = "...")]`, if it has one
+fn message_key(attrs: &[syn::Attribute]) -> syn::Result<Option<LitStr>> {
+    let mut key = None;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("message")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("key") {
+                key = Some(meta.value()?.parse::<LitStr>()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `key = \"...\"`"))
+            }
+        })?;
+    }
+    Ok(key)
+}
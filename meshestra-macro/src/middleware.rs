@@ -0,0 +1,23 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Item};
+
+pub fn middleware_attribute(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as Item);
+
+    // `#[controller]` consumes controller-level layers and `#[routes]` the
+    // method-level ones.
+    if let Item::Struct(item) = &input {
+        return syn::Error::new_spanned(
+            &item.ident,
+            "#[middleware] on a controller must be placed below #[controller]",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    quote! {
+        #input
+    }
+    .into()
+}
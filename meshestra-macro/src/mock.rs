@@ -0,0 +1,180 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::{
+    braced, parse_macro_input, FnArg, Ident, Path, ReturnType, Signature, Token, Visibility,
+};
+
+/// `<vis> MockName: Trait { <method signatures>; ... }`
+struct MockArgs {
+    vis: Visibility,
+    name: Ident,
+    trait_path: Path,
+    methods: Vec<Signature>,
+}
+
+impl Parse for MockArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let vis = input.parse()?;
+        let name = input.parse()?;
+        input.parse::<Token![:]>()?;
+        let trait_path = input.parse()?;
+        let content;
+        braced!(content in input);
+        let mut methods = Vec::new();
+        while !content.is_empty() {
+            methods.push(content.parse()?);
+            content.parse::<Token![;]>()?;
+        }
+        Ok(MockArgs {
+            vis,
+            name,
+            trait_path,
+            methods,
+        })
+    }
+}
+
+pub fn mock_trait(input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(input as MockArgs);
+    generate_mock(&args)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+fn generate_mock(args: &MockArgs) -> syn::Result<TokenStream2> {
+    let MockArgs {
+        vis,
+        name,
+        trait_path,
+        methods,
+    } = args;
+    let mock_name = name.to_string();
+
+    let mut fields = Vec::new();
+    let mut inits = Vec::new();
+    let mut setters = Vec::new();
+    let mut impls = Vec::new();
+    let mut method_names = Vec::new();
+    for sig in methods {
+        if !sig.generics.params.is_empty() {
+            return Err(syn::Error::new_spanned(
+                &sig.generics,
+                "mock_trait! doesn't support generic methods",
+            ));
+        }
+        match sig.inputs.first() {
+            Some(FnArg::Receiver(receiver))
+                if receiver.reference.is_some() && receiver.mutability.is_none() => {}
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    sig,
+                    "mock_trait! methods must take `&self`",
+                ))
+            }
+        }
+
+        let method = &sig.ident;
+        let method_name = method.to_string();
+        let setter = format_ident!("on_{}", method);
+        let mut arg_types = Vec::new();
+        let mut arg_names = Vec::new();
+        for (i, arg) in sig.inputs.iter().skip(1).enumerate() {
+            let FnArg::Typed(arg) = arg else {
+                unreachable!("only the first argument can be a receiver")
+            };
+            if let syn::Type::ImplTrait(ty) = &*arg.ty {
+                return Err(syn::Error::new_spanned(
+                    ty,
+                    "mock_trait! doesn't support `impl Trait` arguments",
+                ));
+            }
+            arg_types.push(&arg.ty);
+            arg_names.push(format_ident!("__arg{}", i));
+        }
+        let output = match &sig.output {
+            ReturnType::Default => quote! { () },
+            ReturnType::Type(_, ty) => quote! { #ty },
+        };
+        let closure = quote! { dyn Fn(#(#arg_types),*) -> #output + Send + Sync };
+        let unprogrammed = format!(
+            "{}::{} was called, but isn't programmed; set it with `{}`",
+            mock_name, method_name, setter
+        );
+
+        fields.push(quote! { #method: Option<Box<#closure>> });
+        inits.push(quote! { #method: None });
+        setters.push(quote! {
+            /// Answer the calls of this method with `f`
+            pub fn #setter(
+                mut self,
+                f: impl Fn(#(#arg_types),*) -> #output + Send + Sync + 'static,
+            ) -> Self {
+                self.#method = Some(Box::new(f));
+                self
+            }
+        });
+        let asyncness = &sig.asyncness;
+        impls.push(quote! {
+            #asyncness fn #method(&self, #(#arg_names: #arg_types),*) -> #output {
+                self.__calls.record(#method_name);
+                let f = self.#method.as_ref().expect(#unprogrammed);
+                f(#(#arg_names),*)
+            }
+        });
+        method_names.push(method_name);
+    }
+
+    let async_trait = methods
+        .iter()
+        .any(|sig| sig.asyncness.is_some())
+        .then(|| quote! { #[::meshestra::async_trait] });
+
+    Ok(quote! {
+        #vis struct #name {
+            __calls: ::meshestra::testing::MockCalls,
+            #(#fields,)*
+        }
+
+        impl #name {
+            pub fn new() -> Self {
+                Self {
+                    __calls: ::meshestra::testing::MockCalls::new(#mock_name, &[#(#method_names),*]),
+                    #(#inits,)*
+                }
+            }
+
+            #(#setters)*
+
+            /// The calls made to this mock so far, also once it's registered
+            pub fn calls(&self) -> ::meshestra::testing::MockCalls {
+                self.__calls.clone()
+            }
+
+            /// Assert `method` has been called `times` times
+            pub fn assert_called(&self, method: &str, times: usize) {
+                self.__calls.assert_called(method, times);
+            }
+        }
+
+        impl Default for #name {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        #async_trait
+        impl #trait_path for #name {
+            #(#impls)*
+        }
+
+        impl ::meshestra::testing::MockProvider for #name {
+            type Trait = dyn #trait_path;
+
+            fn into_binding(mock: ::std::sync::Arc<Self>) -> ::std::sync::Arc<dyn #trait_path> {
+                mock
+            }
+        }
+    })
+}
@@ -3,8 +3,8 @@ use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
 use syn::{
     parse::{Parse, ParseStream},
-    parse_macro_input, Expr, ExprMethodCall, ExprPath, GenericArgument, ItemStruct, Path, Token,
-    Type,
+    parse_macro_input, Expr, ExprMethodCall, ExprPath, GenericArgument, ItemStruct, LitBool,
+    LitStr, Path, Token, Type,
 };
 
 // Simplified parsing for items like `UserService` or `AppModule`
@@ -19,12 +19,29 @@ impl Parse for ModuleItem {
     }
 }
 
+// An import: a module type, or an expression building a `DynamicModule`
+enum Import {
+    Module(Path),
+    Dynamic(Expr),
+}
+
+impl Parse for Import {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let expr: Expr = input.parse()?;
+
+        match expr {
+            Expr::Path(path) if path.qself.is_none() => Ok(Import::Module(path.path)),
+            expr => Ok(Import::Dynamic(expr)),
+        }
+    }
+}
+
 // Parses a provider expression, which can be a simple type or a trait binding.
 enum Provider {
     Struct(ExprPath),
     Trait {
         impl_path: ExprPath,
-        trait_path: Type,
+        trait_path: Box<Type>,
     },
 }
 
@@ -95,15 +112,22 @@ fn parse_for_trait_call(method_call: ExprMethodCall) -> syn::Result<Provider> {
 
     Ok(Provider::Trait {
         impl_path,
-        trait_path,
+        trait_path: Box::new(trait_path),
     })
 }
 
 // Main struct to parse the macro arguments: `imports = [...], providers = [...]`
 struct ModuleArgs {
-    imports: Vec<ModuleItem>,
+    imports: Vec<Import>,
     controllers: Vec<ModuleItem>,
     providers: Vec<Provider>,
+    // Types, traits (`dyn Trait`) and imported modules
+    exports: Vec<Type>,
+    global: bool,
+    // What `for_root` takes
+    config: Option<Type>,
+    // The segment the module's controllers and imports are served under
+    prefix: Option<LitStr>,
 }
 
 impl Parse for ModuleArgs {
@@ -111,17 +135,43 @@ impl Parse for ModuleArgs {
         let mut imports = Vec::new();
         let mut controllers = Vec::new();
         let mut providers = Vec::new();
+        let mut exports = Vec::new();
+        let mut global = false;
+        let mut config = None;
+        let mut prefix = None;
 
         while !input.is_empty() {
             let name: syn::Ident = input.parse()?;
             input.parse::<Token![=]>()?;
 
+            if name == "global" {
+                global = input.parse::<LitBool>()?.value;
+                if input.peek(Token![,]) {
+                    input.parse::<Token![,]>()?;
+                }
+                continue;
+            }
+            if name == "config" {
+                config = Some(input.parse::<Type>()?);
+                if input.peek(Token![,]) {
+                    input.parse::<Token![,]>()?;
+                }
+                continue;
+            }
+            if name == "prefix" {
+                prefix = Some(input.parse::<LitStr>()?);
+                if input.peek(Token![,]) {
+                    input.parse::<Token![,]>()?;
+                }
+                continue;
+            }
+
             let content;
             syn::bracketed!(content in input);
 
             if name == "imports" {
                 imports = content
-                    .parse_terminated(ModuleItem::parse, Token![,])?
+                    .parse_terminated(Import::parse, Token![,])?
                     .into_iter()
                     .collect();
             } else if name == "controllers" {
@@ -134,10 +184,15 @@ impl Parse for ModuleArgs {
                     .parse_terminated(Provider::parse, Token![,])?
                     .into_iter()
                     .collect();
+            } else if name == "exports" {
+                exports = content
+                    .parse_terminated(Type::parse, Token![,])?
+                    .into_iter()
+                    .collect();
             } else {
                 return Err(syn::Error::new(
                     name.span(),
-                    "Expected `imports`, `controllers`, or `providers`",
+                    "Expected `imports`, `controllers`, `providers`, `exports`, `global`, `config`, or `prefix`",
                 ));
             }
 
@@ -150,6 +205,10 @@ impl Parse for ModuleArgs {
             imports,
             controllers,
             providers,
+            exports,
+            global,
+            config,
+            prefix,
         })
     }
 }
@@ -162,21 +221,43 @@ pub fn module_attribute(attr: TokenStream, item: TokenStream) -> TokenStream {
     TokenStream::from(expanded)
 }
 
-// Generates the `impl Module for ...` block
+// Generates the `impl Module for ...` block and the inherent helpers
 fn generate_module_impl(args: &ModuleArgs, input: &ItemStruct) -> TokenStream2 {
     let module_name = &input.ident;
 
-    let import_registrations = args.imports.iter().map(|item| {
-        let path = &item.path;
-        quote! { #path::register(container)?; }
+    let exports = &args.exports;
+
+    let import_registrations = args.imports.iter().map(|import| match import {
+        Import::Module(path) => quote! { #path::register(container)?; },
+        // Its exports count as imported ones, see `export_check`.
+        Import::Dynamic(expr) if !exports.is_empty() => quote! {
+            {
+                let module: ::meshestra::module::DynamicModule = #expr;
+                imported.extend_from_slice(module.exported_types());
+                module.register_into(container)?;
+            }
+        },
+        Import::Dynamic(expr) => quote! {
+            ::meshestra::module::DynamicModule::register_into(#expr, container)?;
+        },
     });
 
-    let provider_registrations = args.providers.iter().map(|provider| match provider {
+    // Providers are registered once their dependencies are, whatever order
+    // they're listed in.
+    let provider_steps = args.providers.iter().map(|provider| match provider {
         Provider::Struct(path) => {
             quote! {
-                {
-                    let instance = <#path as ::meshestra::Injectable>::inject(container)?;
-                    container.register(instance);
+                ::meshestra::module::ProviderStep {
+                    provides: ::std::vec![::std::any::type_name::<#path>()],
+                    register: |container| {
+                        // Overridden providers, by a test say, aren't injected at all.
+                        if !container.is_overridden::<#path>() {
+                            let instance = <#path as ::meshestra::Injectable>::inject(container)?;
+                            container.try_register(instance)?;
+                            container.declare::<#path>();
+                        }
+                        Ok(())
+                    },
                 }
             }
         }
@@ -185,13 +266,23 @@ fn generate_module_impl(args: &ModuleArgs, input: &ItemStruct) -> TokenStream2 {
             trait_path,
         } => {
             quote! {
-                {
-                    // First, register the concrete implementation so it can be injected elsewhere if needed
-                    let instance = <#impl_path as ::meshestra::Injectable>::inject(container)?;
-                    container.register(instance);
+                ::meshestra::module::ProviderStep {
+                    provides: ::std::vec![
+                        ::std::any::type_name::<#impl_path>(),
+                        ::std::any::type_name::<#trait_path>(),
+                    ],
+                    register: |container| {
+                        if !container.is_overridden::<#trait_path>() {
+                            // First, register the concrete implementation so it can be injected elsewhere if needed
+                            let instance = <#impl_path as ::meshestra::Injectable>::inject(container)?;
+                            container.try_register(instance)?;
+                            container.declare::<#impl_path>();
 
-                    // Then, register the trait binding
-                    container.register_trait::<#trait_path, #impl_path, _>(|i| i as std::sync::Arc<#trait_path>);
+                            // Then, register the trait binding
+                            container.register_trait::<#trait_path, #impl_path, _>(|i| i as std::sync::Arc<#trait_path>);
+                        }
+                        Ok(())
+                    },
                 }
             }
         }
@@ -202,29 +293,258 @@ fn generate_module_impl(args: &ModuleArgs, input: &ItemStruct) -> TokenStream2 {
         quote! {
             {
                 let instance = <#path as ::meshestra::Injectable>::inject(container)?;
-                container.register(instance);
+                container.try_register(instance)?;
+                container.declare::<#path>();
+            }
+        }
+    });
+
+    let import_paths: Vec<&Path> = args
+        .imports
+        .iter()
+        .filter_map(|import| match import {
+            Import::Module(path) => Some(path),
+            Import::Dynamic(_) => None,
+        })
+        .collect();
+    let has_dynamic_imports = import_paths.len() < args.imports.len();
+
+    // What the module exports, imported modules standing for their exports
+    let exported_types = if exports.is_empty() {
+        quote! {}
+    } else {
+        quote! {
+            fn exported_types() -> ::std::vec::Vec<(::std::any::TypeId, &'static str)> {
+                ::meshestra::module::resolve_exports(
+                    &[#((
+                        ::std::any::TypeId::of::<#import_paths>(),
+                        <#import_paths as ::meshestra::Module>::exported_types,
+                    )),*],
+                    &[#(::meshestra::module::export::<#exports>()),*],
+                )
+            }
+        }
+    };
+
+    // Every export must be provided here or exported by an import
+    let provided_types = args.providers.iter().map(|provider| match provider {
+        Provider::Struct(path) => quote! { ::std::any::TypeId::of::<#path>() },
+        Provider::Trait {
+            impl_path,
+            trait_path,
+        } => quote! {
+            ::std::any::TypeId::of::<#impl_path>(), ::std::any::TypeId::of::<#trait_path>()
+        },
+    });
+    let (imported_decl, export_check) = if exports.is_empty() {
+        (quote! {}, quote! {})
+    } else {
+        (
+            quote! {
+                #[allow(unused_mut)]
+                let mut imported: ::std::vec::Vec<(::std::any::TypeId, &'static str)> = ::std::vec::Vec::new();
+            },
+            quote! {
+                #(imported.extend(<#import_paths as ::meshestra::Module>::exported_types());)*
+                ::meshestra::module::check_exports(
+                    stringify!(#module_name),
+                    &<#module_name as ::meshestra::Module>::exported_types(),
+                    &[#(#provided_types),*],
+                    &imported,
+                )?;
+            },
+        )
+    };
+
+    // A global module registers once, and before anything else; see
+    // `register_globals`.
+    let (global_const, global_registration, global_check) = if args.global {
+        (
+            quote! { const GLOBAL: bool = true; },
+            quote! { #module_name::register(container)?; },
+            quote! {
+                let first = container.enter_global(
+                    ::std::any::TypeId::of::<#module_name>(),
+                    stringify!(#module_name),
+                    &<#module_name as ::meshestra::Module>::exported_types(),
+                )?;
+                if !first {
+                    return Ok(());
+                }
+            },
+        )
+    } else {
+        (quote! {}, quote! {}, quote! {})
+    };
+
+    // `for_root(config)` and `for_feature(items)`, for `imports`
+    let configured = match &args.config {
+        Some(config) => quote! {
+            /// The module, registered after `config`, for the `imports` of
+            /// the module setting it up.
+            pub fn for_root(config: #config) -> ::meshestra::module::DynamicModule {
+                ::meshestra::module::DynamicModule::builder()
+                    .provider(config)
+                    .module::<Self>()
+                    .build()
+            }
+
+            /// Registrations of a feature, for its module's `imports`; they
+            /// must come after `for_root`'s.
+            pub fn for_feature(
+                items: impl ::std::iter::IntoIterator<Item = ::meshestra::module::DynamicProvider>,
+            ) -> ::meshestra::module::DynamicModule {
+                ::meshestra::module::DynamicModule::builder()
+                    .registration(|container| {
+                        ::meshestra::module::require_root::<#config>(container, stringify!(#module_name))
+                    })
+                    .providers(items)
+                    .build()
             }
+        },
+        None => quote! {},
+    };
+
+    let import_routers = import_paths.iter().map(|path| {
+        quote! {
+            <#path as ::meshestra::Module>::mount_router::<S>(container, state, routers)?;
+        }
+    });
+
+    // The controllers of dynamic modules are found in the container.
+    let dynamic_routers = if has_dynamic_imports {
+        quote! { ::meshestra::module::mount_dynamic::<S>(container, state, routers)?; }
+    } else {
+        quote! {}
+    };
+
+    let controller_mounts = args.controllers.iter().map(|item| {
+        let path = &item.path;
+        quote! {
+            state.add_routes(#path::route_table())?;
+            routers.mount(
+                &state.path(#path::base_path()),
+                #path::versioned_routers(container.resolve::<#path>()?),
+            );
         }
     });
 
+    // The controllers and imports of `prefix = "..."` are served under it.
+    let (enter_prefix, exit_prefix) = match &args.prefix {
+        Some(prefix) => (
+            quote! { let __outer_prefix = state.enter_prefix(#prefix); },
+            quote! { state.exit_prefix(__outer_prefix); },
+        ),
+        None => (quote! {}, quote! {}),
+    };
+
+    let import_routes = import_paths.iter().map(|path| {
+        quote! { <#path as ::meshestra::Module>::collect_routes(state)?; }
+    });
+
+    let controller_routes = args.controllers.iter().map(|item| {
+        let path = &item.path;
+        quote! { state.add_routes(#path::route_table())?; }
+    });
+
     quote! {
         #input
 
+        impl ::meshestra::Module for #module_name {
+            #global_const
+
+            fn register(container: &mut ::meshestra::Container) -> ::meshestra::Result<()> {
+                #module_name::register(container)
+            }
+
+            fn register_globals(container: &mut ::meshestra::Container) -> ::meshestra::Result<()> {
+                #(<#import_paths as ::meshestra::Module>::register_globals(container)?;)*
+                #global_registration
+                Ok(())
+            }
+
+            fn mount_router<S>(
+                container: &::meshestra::Container,
+                state: &mut ::meshestra::module::MountState,
+                routers: &mut ::meshestra::controller::versioning::VersionedRouters<S>,
+            ) -> ::meshestra::Result<()>
+            where
+                S: Clone + Send + Sync + ::meshestra::di::HasContainer + 'static,
+            {
+                if !state.enter::<Self>() {
+                    return Ok(());
+                }
+                #enter_prefix
+                #(#import_routers)*
+                #dynamic_routers
+                #(#controller_mounts)*
+                #exit_prefix
+                Ok(())
+            }
+
+            fn collect_routes(state: &mut ::meshestra::module::MountState) -> ::meshestra::Result<()> {
+                if !state.enter::<Self>() {
+                    return Ok(());
+                }
+                #enter_prefix
+                #(#import_routes)*
+                #(#controller_routes)*
+                #exit_prefix
+                Ok(())
+            }
+
+            #exported_types
+        }
+
         impl #module_name {
             /// Registers the module's imports, providers, and controllers.
             pub fn register(container: &mut ::meshestra::Container) -> ::meshestra::Result<()> {
-                #(#import_registrations)*
-                #(#provider_registrations)*
-                #(#controller_registrations)*
-                Ok(())
+                #global_check
+
+                fn register_all(container: &mut ::meshestra::Container) -> ::meshestra::Result<()> {
+                    #imported_decl
+                    #(<#import_paths as ::meshestra::Module>::register_globals(container)?;)*
+                    #(#import_registrations)*
+                    ::meshestra::module::register_providers(
+                        container,
+                        stringify!(#module_name),
+                        ::std::vec![#(#provider_steps),*],
+                    )?;
+                    #(#controller_registrations)*
+                    #export_check
+                    Ok(())
+                }
+
+                // Registrations are attributed to the module, for the
+                // duplicate provider warning.
+                let parent = container.enter_module(stringify!(#module_name));
+                let result = register_all(container);
+                container.exit_module(parent);
+                result
             }
 
+            /// The names of the types and traits this module exports.
+            pub fn exports() -> ::std::vec::Vec<&'static str> {
+                <Self as ::meshestra::Module>::exports()
+            }
+
+            #configured
+
             /// Creates a new DI container and registers this module.
             pub fn create_container() -> ::meshestra::Result<::meshestra::Container> {
                 let mut container = ::meshestra::Container::new();
                 Self::register(&mut container)?;
                 Ok(container)
             }
+
+            /// Builds a router serving the module's controllers and those of
+            /// its imports, each nested at its `base_path()`.
+            pub fn build_router<S>(container: &::meshestra::Container) -> ::meshestra::Result<::axum::Router<S>>
+            where
+                S: Clone + Send + Sync + ::meshestra::di::HasContainer + 'static,
+            {
+                <Self as ::meshestra::Module>::build_router(container)
+            }
         }
     }
 }
@@ -0,0 +1,127 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitStr};
+
+pub fn derive_from_multipart(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    generate_from_multipart_impl(&input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// A field's `#[part(name = "...", limit = ...)]` settings
+struct PartInfo {
+    ident: syn::Ident,
+    ty: syn::Type,
+    name: LitStr,
+    limit: Option<syn::Expr>,
+}
+
+fn generate_from_multipart_impl(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let struct_name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fields = match &input.data {
+        Data::Struct(s) => match &s.fields {
+            Fields::Named(f) => &f.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    input,
+                    "#[derive(FromMultipart)] only supports structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                input,
+                "#[derive(FromMultipart)] can only be used on structs",
+            ))
+        }
+    };
+
+    let mut parts = Vec::new();
+    for field in fields {
+        let ident = field.ident.clone().unwrap();
+        let mut name = LitStr::new(&ident.to_string(), ident.span());
+        let mut limit = None;
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("part"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("name") {
+                    name = meta.value()?.parse()?;
+                } else if meta.path.is_ident("limit") {
+                    limit = Some(meta.value()?.parse()?);
+                } else {
+                    return Err(meta.error("expected `name = \"...\"` or `limit = <bytes>`"));
+                }
+                Ok(())
+            })?;
+        }
+        parts.push(PartInfo {
+            ident,
+            ty: field.ty.clone(),
+            name,
+            limit,
+        });
+    }
+
+    let slots = parts.iter().map(|part| {
+        let ident = &part.ident;
+        let ty = &part.ty;
+        quote! {
+            let mut #ident: ::std::option::Option<<#ty as __multipart::FromMaybePart>::Part> = None;
+        }
+    });
+    let arms = parts.iter().map(|part| {
+        let ident = &part.ident;
+        let ty = &part.ty;
+        let name = &part.name;
+        let limit = match &part.limit {
+            Some(limit) => quote! { Some((#limit) as usize) },
+            None => quote! { None },
+        };
+        quote! {
+            Some(#name) => {
+                #ident = Some(<<#ty as __multipart::FromMaybePart>::Part as __multipart::FromPart>::from_part(__field, #limit).await?);
+            }
+        }
+    });
+    let finish = parts.iter().map(|part| {
+        let ident = &part.ident;
+        let ty = &part.ty;
+        let name = &part.name;
+        quote! {
+            #ident: <#ty as __multipart::FromMaybePart>::from_maybe_part(#ident, #name)?
+        }
+    });
+
+    Ok(quote! {
+        const _: () = {
+            use ::meshestra::controller::multipart as __multipart;
+
+            #[::meshestra::async_trait]
+            impl #impl_generics __multipart::FromMultipart for #struct_name #ty_generics #where_clause {
+                async fn from_multipart(
+                    mut __stream: ::meshestra::axum::extract::Multipart,
+                ) -> ::std::result::Result<Self, ::meshestra::exception::HttpException> {
+                    #(#slots)*
+                    // Parts are matched by name; unknown ones are skipped.
+                    while let Some(__field) = __multipart::next_part(&mut __stream).await? {
+                        let __name = __field.name().map(::std::string::ToString::to_string);
+                        match __name.as_deref() {
+                            #(#arms)*
+                            _ => {}
+                        }
+                    }
+                    Ok(Self {
+                        #(#finish),*
+                    })
+                }
+            }
+        };
+    })
+}
@@ -0,0 +1,90 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::{parenthesized, parse_macro_input, Expr, ItemFn, ReturnType, Token};
+
+/// `#[meshestra::test]`, `#[meshestra::test(transactional)]` or
+/// `#[meshestra::test(transactional(manager = <expr>))]`
+struct TestArgs {
+    /// `Some` for a transactional test, with its `manager` expression if given
+    transactional: Option<Option<Expr>>,
+}
+
+impl Parse for TestArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.is_empty() {
+            return Ok(TestArgs {
+                transactional: None,
+            });
+        }
+
+        let key: syn::Ident = input.parse()?;
+        if key != "transactional" {
+            return Err(syn::Error::new_spanned(key, "expected `transactional`"));
+        }
+        let mut manager = None;
+        if input.peek(syn::token::Paren) {
+            let content;
+            parenthesized!(content in input);
+            let key: syn::Ident = content.parse()?;
+            if key != "manager" {
+                return Err(syn::Error::new_spanned(key, "expected `manager = <expr>`"));
+            }
+            content.parse::<Token![=]>()?;
+            manager = Some(content.parse()?);
+        }
+        Ok(TestArgs {
+            transactional: Some(manager),
+        })
+    }
+}
+
+pub fn test_attribute(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(attr as TestArgs);
+    let input = parse_macro_input!(item as ItemFn);
+
+    if input.sig.asyncness.is_none() {
+        return syn::Error::new_spanned(input.sig.fn_token, "#[meshestra::test] needs an async fn")
+            .to_compile_error()
+            .into();
+    }
+
+    let Some(manager) = args.transactional else {
+        return quote! {
+            #[::tokio::test]
+            #input
+        }
+        .into();
+    };
+
+    let attrs = &input.attrs;
+    let vis = &input.vis;
+    let sig = &input.sig;
+    let block = &input.block;
+    let output = match &sig.output {
+        ReturnType::Default => quote! { () },
+        ReturnType::Type(_, ty) => quote! { #ty },
+    };
+    let manager = match manager {
+        Some(expr) => quote! { #expr },
+        None => quote! {
+            ::meshestra::transactional::current_manager()
+                .expect("#[meshestra::test(transactional)] needs a global transaction manager")
+        },
+    };
+
+    quote! {
+        #(#attrs)*
+        #[::tokio::test]
+        #vis #sig {
+            let __manager: ::std::sync::Arc<dyn ::meshestra::transactional::TransactionManager> = #manager;
+            ::meshestra::testing::TransactionalTest::wrap(__manager, async move {
+                let __output: #output = #block;
+                __output
+            })
+            .await
+            .expect("the test transaction begins and rolls back")
+        }
+    }
+    .into()
+}
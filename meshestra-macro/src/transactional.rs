@@ -1,11 +1,50 @@
 use proc_macro::TokenStream;
-use quote::quote;
-use syn::{parse::Parse, parse::ParseStream, parse_macro_input, ItemFn, LitBool, Path, Token};
+use proc_macro2::{TokenStream as TokenStream2, TokenTree};
+use quote::{quote, quote_spanned};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{
+    bracketed, parse::Parse, parse::ParseStream, parse_macro_input, Expr, FnArg, ItemFn, LitBool,
+    LitStr, Path, Token, Type,
+};
+
+/// Where the generated code gets its `TransactionManager` from
+enum ManagerSource {
+    /// `manager = <expr>`, e.g. `manager = self.tx`
+    Expr(Expr),
+    /// `manager = ambient`: the handle installed with `with_manager` or
+    /// `set_global_manager`
+    Ambient,
+    /// `manager_name = "..."` without `manager`: the manager registered
+    /// under that name
+    Named(LitStr),
+}
 
 struct TransactionArgs {
     isolation: Option<Path>,
     propagation: Option<Path>,
     read_only: Option<bool>,
+    rollback_for: Vec<Type>,
+    no_rollback_for: Vec<Type>,
+    manager: Option<ManagerSource>,
+    manager_name: Option<LitStr>,
+}
+
+/// Whether `tokens` mention `self` anywhere
+fn mentions_self(tokens: TokenStream2) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(ident) => ident == "self",
+        TokenTree::Group(group) => mentions_self(group.stream()),
+        _ => false,
+    })
+}
+
+/// Parse a bracketed list of error types, e.g. `[ValidationError, io::Error]`
+fn parse_type_list(input: ParseStream) -> syn::Result<Vec<Type>> {
+    let content;
+    bracketed!(content in input);
+    let types = Punctuated::<Type, Token![,]>::parse_terminated(&content)?;
+    Ok(types.into_iter().collect())
 }
 
 impl Parse for TransactionArgs {
@@ -13,6 +52,10 @@ impl Parse for TransactionArgs {
         let mut isolation = None;
         let mut propagation = None;
         let mut read_only = None;
+        let mut rollback_for = Vec::new();
+        let mut no_rollback_for = Vec::new();
+        let mut manager = None;
+        let mut manager_name = None;
 
         while !input.is_empty() {
             let key: syn::Ident = input.parse()?;
@@ -28,8 +71,24 @@ impl Parse for TransactionArgs {
             } else if key == "read_only" {
                 let b: LitBool = input.parse()?;
                 read_only = Some(b.value);
+            } else if key == "rollback_for" {
+                rollback_for = parse_type_list(input)?;
+            } else if key == "no_rollback_for" {
+                no_rollback_for = parse_type_list(input)?;
+            } else if key == "manager" {
+                let expr: Expr = input.parse()?;
+                manager = Some(match &expr {
+                    Expr::Path(path) if path.path.is_ident("ambient") => ManagerSource::Ambient,
+                    _ => ManagerSource::Expr(expr),
+                });
+            } else if key == "manager_name" {
+                let name: LitStr = input.parse()?;
+                manager_name = Some(name);
             } else {
-                // Ignore or error
+                return Err(syn::Error::new(
+                    key.span(),
+                    "expected one of `isolation`, `propagation`, `read_only`, `rollback_for`, `no_rollback_for`, `manager`, or `manager_name`",
+                ));
             }
 
             if input.peek(Token![,]) {
@@ -41,6 +100,10 @@ impl Parse for TransactionArgs {
             isolation,
             propagation,
             read_only,
+            rollback_for,
+            no_rollback_for,
+            manager,
+            manager_name,
         })
     }
 }
@@ -67,90 +130,130 @@ pub fn transactional_attribute(attr: TokenStream, item: TokenStream) -> TokenStr
     // But for "Enum" request, allowing full path is standard Rust way.
     // Let's support intelligent mapping: if it's a single identifier, we map it to our enum.
     let isolation_code = match args.isolation {
-        Some(path) => {
-            if path.segments.len() == 1 {
-                let ident = &path.segments.first().unwrap().ident;
-                quote! { Some(::meshestra::transactional::IsolationLevel::#ident) }
-            } else {
-                quote! { Some(#path) }
-            }
-        }
+        Some(path) => match path.get_ident() {
+            Some(ident) => quote! { Some(::meshestra::transactional::IsolationLevel::#ident) },
+            None => quote! { Some(#path) },
+        },
         None => quote! { None },
     };
 
     let propagation_code = match args.propagation {
-        Some(path) => {
-            if path.segments.len() == 1 {
-                let ident = &path.segments.first().unwrap().ident;
-                quote! { ::meshestra::transactional::Propagation::#ident }
-            } else {
-                quote! { #path }
-            }
-        }
+        Some(path) => match path.get_ident() {
+            Some(ident) => quote! { ::meshestra::transactional::Propagation::#ident },
+            None => quote! { #path },
+        },
         None => quote! { ::meshestra::transactional::Propagation::Required },
     };
 
     let read_only_code = args.read_only.unwrap_or(false);
 
+    let manager_name_code = match &args.manager_name {
+        Some(name) => quote! { Some(::std::sync::Arc::from(#name)) },
+        None => quote! { None },
+    };
+
     let options_expr = quote! {
         ::meshestra::transactional::TransactionOptions {
             isolation: #isolation_code,
             propagation: #propagation_code,
             read_only: #read_only_code,
+            manager_name: #manager_name_code,
         }
     };
 
-    let new_block = quote! {
-        {
-            use ::meshestra::transactional::{get_current_transaction, ACTIVE_TRANSACTION, Propagation, Transaction, TransactionManager};
-            use ::meshestra::MeshestraError;
-            use ::std::sync::Arc;
-            use ::tokio::sync::Mutex;
-
-            let options = #options_expr;
-
-            // This logic handles Propagation::Required
-            if options.propagation == Propagation::Required {
-                if let Some(_existing_tx) = get_current_transaction() {
-                    // A transaction is already active. Just run the function body.
-                    // The outer transactional scope will handle commit/rollback.
-                    (async move { #block }).await
-                } else {
-                    // No active transaction. We need to start one.
-                    let tx_manager = &self.transaction_manager;
-                    let tx_box = tx_manager.begin(options).await.map_err(|e| MeshestraError::Internal(e.to_string()))?;
-
-                    let tx_arc = Arc::new(Mutex::new(tx_box));
-
-                    // Set the transaction in the task local for the scope of the function
-                    let result = ACTIVE_TRANSACTION.scope(Some(tx_arc.clone()), async {
-                        (async move { #block }).await
-                    }).await;
-
-                    // After the function runs, commit or rollback.
-                    let mut guard = tx_arc.lock().await;
-                    match &result {
-                        Ok(_) => {
-                            if let Err(e) = guard.commit().await {
-                                 return Err(MeshestraError::Internal(format!("Failed to commit transaction: {}", e)).into());
-                            }
-                        },
-                        Err(_) => {
-                            if let Err(e) = guard.rollback().await {
-                                // Log rollback failure? For now, the original error is more important.
-                            }
-                        }
-                    }
-
-                    result
-                }
-            } else {
-                 panic!("Only Propagation::Required is currently supported by #[transactional]");
+    // Resolve the manager: an explicit expression, the named one, or the
+    // ambient handle for free functions. The fields of `Self` are unknown
+    // here, so methods have to say which manager they use.
+    let has_receiver = matches!(input.sig.inputs.first(), Some(FnArg::Receiver(_)));
+    let manager_source = match (args.manager, args.manager_name) {
+        (Some(source), _) => source,
+        (None, Some(name)) => ManagerSource::Named(name),
+        (None, None) if has_receiver => {
+            return syn::Error::new(
+                proc_macro2::Span::call_site(),
+                "#[transactional] on a method needs a transaction manager; \
+                 use `manager = ambient`, `manager = self.<field>` or `manager_name = \"...\"`",
+            )
+            .to_compile_error()
+            .into();
+        }
+        (None, None) => ManagerSource::Ambient,
+    };
+    let (manager_setup, manager_ref) = match manager_source {
+        ManagerSource::Expr(expr) => {
+            if !has_receiver && mentions_self(quote!(#expr)) {
+                return syn::Error::new(
+                    expr.span(),
+                    "`manager` refers to `self`, but this function has no `self` receiver; \
+                     use `manager = ambient` or pass a path to a manager",
+                )
+                .to_compile_error()
+                .into();
+            }
+            // Point "no field" errors at the attribute rather than the expansion.
+            (quote! {}, quote_spanned! {expr.span()=> &*#expr })
+        }
+        ManagerSource::Ambient => (
+            quote! {
+                let __transaction_manager = ::meshestra::transactional::current_manager()
+                    .map_err(::meshestra::MeshestraError::from)?;
+            },
+            quote! { &*__transaction_manager },
+        ),
+        ManagerSource::Named(name) => (
+            quote! {
+                let __transaction_manager = ::meshestra::transactional::named_manager(#name)
+                    .map_err(::meshestra::MeshestraError::from)?;
+            },
+            quote! { &*__transaction_manager },
+        ),
+    };
+
+    // Propagation handling lives in the runtime so it can be tested without the macro.
+    let new_block = if args.rollback_for.is_empty() && args.no_rollback_for.is_empty() {
+        quote! {
+            {
+                let options = #options_expr;
+                #manager_setup
+
+                ::meshestra::transactional::run_transactional(
+                    #manager_ref,
+                    options,
+                    async move #block,
+                )
+                .await
+            }
+        }
+    } else {
+        // `rollback_for` wins over `no_rollback_for`; unlisted errors roll back.
+        let rollback_for = &args.rollback_for;
+        let no_rollback_for = &args.no_rollback_for;
+        quote! {
+            {
+                let options = #options_expr;
+                #manager_setup
+
+                ::meshestra::transactional::run_transactional_with(
+                    #manager_ref,
+                    options,
+                    |error| {
+                        let rollback_for = false
+                            #(|| ::meshestra::transactional::error_matches::<#rollback_for, _>(error))*;
+                        let no_rollback_for = false
+                            #(|| ::meshestra::transactional::error_matches::<#no_rollback_for, _>(error))*;
+                        rollback_for || !no_rollback_for
+                    },
+                    async move #block,
+                )
+                .await
             }
         }
     };
 
-    input.block = syn::parse2(new_block).expect("Failed to generate transactional wrapper");
+    input.block = match syn::parse2(new_block) {
+        Ok(block) => block,
+        Err(e) => return e.to_compile_error().into(),
+    };
 
     TokenStream::from(quote! {
         #input
@@ -1,7 +1,13 @@
 use crate::error::MeshestraError;
 use crate::interceptor::{Interceptor, InterceptorResult, Next};
 use async_trait::async_trait;
-use axum::{body::Body, http::Request, response::Response};
+use axum::{
+    body::{Body, Bytes, HttpBody},
+    http::{Extensions, HeaderMap, Method, Request, Uri, response::Parts},
+    response::Response,
+};
+use futures_util::{StreamExt, stream};
+use std::error::Error;
 use std::sync::Arc;
 
 /// Result type for Aspect hooks.
@@ -48,10 +54,84 @@ pub trait Aspect: Send + Sync + 'static {
         Ok(())
     }
 
+    /// Whether [`after_with_body`](Self::after_with_body) is called, the
+    /// response body being buffered for it
+    fn wants_response_body(&self) -> bool {
+        false
+    }
+
+    /// Largest response body buffered for
+    /// [`after_with_body`](Self::after_with_body), in bytes (1 MiB by
+    /// default); larger ones skip the hook and are sent as they come
+    fn response_body_limit(&self) -> usize {
+        1024 * 1024
+    }
+
+    /// Executed after [`after`](Self::after) with the whole response body,
+    /// when [`wants_response_body`](Self::wants_response_body)
+    ///
+    /// The response is sent with the parts and body as the hook leaves them.
+    async fn after_with_body(&self, _parts: &mut Parts, _body: &mut Bytes) -> AspectResult {
+        Ok(())
+    }
+
     /// Executed when an error occurs during the handler or interceptor execution.
-    async fn on_error(&self, _error: &(dyn std::error::Error + Send + Sync)) {
+    async fn on_error(&self, _error: &(dyn Error + Send + Sync)) {
         // Default: No-op for error logging or metrics
     }
+
+    /// [`on_error`](Self::on_error), knowing the request that failed;
+    /// returning a response answers the request with it instead of the
+    /// error
+    ///
+    /// ```rust,ignore
+    /// async fn on_error_ctx(
+    ///     &self,
+    ///     error: &(dyn Error + Send + Sync + 'static),
+    ///     request: &ErrorContext,
+    /// ) -> Option<Response> {
+    ///     let missing = error.downcast_ref::<UserMissing>()?;
+    ///     tracing::info!(path = request.uri.path(), "{}", missing);
+    ///     Some(StatusCode::NOT_FOUND.into_response())
+    /// }
+    /// ```
+    ///
+    /// Calls `on_error` and keeps the error by default.
+    async fn on_error_ctx(
+        &self,
+        error: &(dyn Error + Send + Sync + 'static),
+        _request: &ErrorContext,
+    ) -> Option<Response> {
+        self.on_error(error).await;
+        None
+    }
+
+    /// Position among the route's aspects and interceptors, see
+    /// [`Interceptor::priority`]
+    fn priority(&self) -> i32 {
+        0
+    }
+}
+
+/// The request an [`Aspect::on_error_ctx`] is called for, as it was after
+/// the aspect's [`before`](Aspect::before) hook
+#[derive(Debug, Clone)]
+pub struct ErrorContext {
+    pub method: Method,
+    pub uri: Uri,
+    pub headers: HeaderMap,
+    pub extensions: Extensions,
+}
+
+impl ErrorContext {
+    fn of(request: &Request<Body>) -> Self {
+        Self {
+            method: request.method().clone(),
+            uri: request.uri().clone(),
+            headers: request.headers().clone(),
+            extensions: request.extensions().clone(),
+        }
+    }
 }
 
 /// Adapter that wraps an [`Aspect`] to work within the [`Interceptor`] system.
@@ -62,9 +142,56 @@ pub struct AspectInterceptor<A: Aspect> {
 impl<A: Aspect> AspectInterceptor<A> {
     /// Creates a new adapter for the given aspect.
     pub fn new(aspect: A) -> Self {
-        Self {
-            aspect: Arc::new(aspect),
+        Self::from_arc(Arc::new(aspect))
+    }
+
+    /// Creates a new adapter for an aspect shared with the container.
+    pub fn from_arc(aspect: Arc<A>) -> Self {
+        Self { aspect }
+    }
+}
+
+impl<A: Aspect> AspectInterceptor<A> {
+    /// Buffer the body of `response` for [`Aspect::after_with_body`] and
+    /// reassemble it, or pass it on as it is when it's too large
+    async fn after_with_body(&self, response: Response) -> InterceptorResult {
+        let limit = self.aspect.response_body_limit();
+        let (mut parts, body) = response.into_parts();
+        if body.size_hint().lower() > limit as u64 {
+            self.warn_too_large();
+            return Ok(Response::from_parts(parts, body));
         }
+
+        let mut chunks = body.into_data_stream();
+        let mut buffered = Vec::new();
+        let mut size = 0;
+        while let Some(chunk) = chunks.next().await {
+            let chunk = chunk?;
+            size += chunk.len();
+            buffered.push(chunk);
+            if size > limit {
+                // Send what was read, then the rest as it comes
+                let read = stream::iter(buffered.into_iter().map(Ok));
+                self.warn_too_large();
+                let body = Body::from_stream(read.chain(chunks));
+                return Ok(Response::from_parts(parts, body));
+            }
+        }
+
+        let mut body = Bytes::from(buffered.concat());
+        if let Err(e) = self.aspect.after_with_body(&mut parts, &mut body).await {
+            return Err(Box::new(e));
+        }
+        parts.headers.remove(axum::http::header::CONTENT_LENGTH);
+        Ok(Response::from_parts(parts, Body::from(body)))
+    }
+
+    fn warn_too_large(&self) {
+        tracing::warn!(
+            aspect = std::any::type_name::<A>(),
+            limit = self.aspect.response_body_limit(),
+            "Response body too large for after_with_body, skipping the hook"
+        );
     }
 }
 
@@ -78,6 +205,7 @@ impl<A: Aspect> Interceptor for AspectInterceptor<A> {
         }
 
         // 2. Proceed to the next interceptor or handler
+        let context = ErrorContext::of(&request);
         let result = next.run(request).await;
 
         match result {
@@ -86,13 +214,26 @@ impl<A: Aspect> Interceptor for AspectInterceptor<A> {
                 if let Err(e) = self.aspect.after(&mut response).await {
                     return Err(Box::new(e));
                 }
+                if self.aspect.wants_response_body() {
+                    return self.after_with_body(response).await;
+                }
                 Ok(response)
             }
             Err(e) => {
-                // 4. Run Error hook on failure
-                self.aspect.on_error(e.as_ref()).await;
-                Err(e)
+                // 4. Run Error hook on failure, which may answer instead
+                match self.aspect.on_error_ctx(e.as_ref(), &context).await {
+                    Some(response) => Ok(response),
+                    None => Err(e),
+                }
             }
         }
     }
+
+    fn priority(&self) -> i32 {
+        self.aspect.priority()
+    }
+
+    fn name(&self) -> &'static str {
+        std::any::type_name::<A>()
+    }
 }
@@ -0,0 +1,166 @@
+use crate::common::response::ApiError;
+use axum::{
+    Json,
+    body::Body,
+    http::{
+        Request, StatusCode as HttpStatusCode,
+        header::{CONTENT_LENGTH, CONTENT_TYPE},
+    },
+    response::{IntoResponse, Response},
+};
+use serde_json::json;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+/// Renders the body of every error response the framework produces
+///
+/// `ApiResponse::error`, `MeshestraError`, `HttpException`, `GuardError`,
+/// `PipeError` and the exception-filter fallback all render through the
+/// application's format, so clients see one shape for every failure. The
+/// default is [`ApiEnvelope`]; pick another with
+/// `ApplicationBuilder::error_body_format`, which [`ErrorBodyLayer`] applies.
+///
+/// Closures taking the status and the error implement it too.
+///
+/// # Example
+/// ```
+/// use meshestra::common::error_body::ErrorBodyFormat;
+/// use meshestra::common::response::ApiError;
+/// use axum::{http::{header, StatusCode}, response::{IntoResponse, Response}};
+/// use serde_json::json;
+///
+/// /// RFC 7807 problem details
+/// struct ProblemDetails;
+///
+/// impl ErrorBodyFormat for ProblemDetails {
+///     fn render(&self, status: StatusCode, error: &ApiError) -> Response {
+///         let body = json!({
+///             "type": "about:blank",
+///             "title": error.code,
+///             "status": status.as_u16(),
+///             "detail": error.message,
+///         });
+///         (status, [(header::CONTENT_TYPE, "application/problem+json")], body.to_string())
+///             .into_response()
+///     }
+/// }
+/// ```
+pub trait ErrorBodyFormat: Send + Sync + 'static {
+    fn render(&self, status: HttpStatusCode, error: &ApiError) -> Response;
+}
+
+impl<F> ErrorBodyFormat for F
+where
+    F: Fn(HttpStatusCode, &ApiError) -> Response + Send + Sync + 'static,
+{
+    fn render(&self, status: HttpStatusCode, error: &ApiError) -> Response {
+        self(status, error)
+    }
+}
+
+/// The default format: the `ApiResponse` envelope,
+/// `{"error": {"code", "message", "details"?}, "success": false}`
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ApiEnvelope;
+
+impl ErrorBodyFormat for ApiEnvelope {
+    fn render(&self, status: HttpStatusCode, error: &ApiError) -> Response {
+        let body = json!({ "error": error, "success": false });
+        (status, Json(body)).into_response()
+    }
+}
+
+/// The error an error response was rendered from, for [`ErrorBodyLayer`]
+#[derive(Clone)]
+struct RenderedError(ApiError);
+
+/// Render `error` with [`ApiEnvelope`]
+///
+/// An [`ErrorBodyLayer`] around the response renders it again with its
+/// format.
+pub fn render_error(status: HttpStatusCode, error: &ApiError) -> Response {
+    let mut response = ApiEnvelope.render(status, error);
+    response
+        .extensions_mut()
+        .insert(RenderedError(error.clone()));
+    response
+}
+
+/// Tower layer rendering the error responses inside it with a format
+///
+/// [`Application::router`] adds it, with the format given to
+/// [`ApplicationBuilder::error_body_format`], around everything it serves.
+/// Layers added around that router answer their errors with
+/// [`ApiEnvelope`]; wrap them in [`Application::error_body_layer`] too.
+///
+/// The other headers of a response, such as CORS ones, are kept.
+///
+/// [`Application::router`]: crate::lifecycle::Application::router
+/// [`Application::error_body_layer`]: crate::lifecycle::Application::error_body_layer
+/// [`ApplicationBuilder::error_body_format`]: crate::lifecycle::ApplicationBuilder::error_body_format
+#[derive(Clone)]
+pub struct ErrorBodyLayer {
+    format: Arc<dyn ErrorBodyFormat>,
+}
+
+impl ErrorBodyLayer {
+    pub fn new(format: Arc<dyn ErrorBodyFormat>) -> Self {
+        Self { format }
+    }
+}
+
+impl<S> Layer<S> for ErrorBodyLayer {
+    type Service = ErrorBodyMiddleware<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ErrorBodyMiddleware {
+            inner,
+            format: self.format.clone(),
+        }
+    }
+}
+
+/// Service produced by [`ErrorBodyLayer`]
+#[derive(Clone)]
+pub struct ErrorBodyMiddleware<S> {
+    inner: S,
+    format: Arc<dyn ErrorBodyFormat>,
+}
+
+impl<S> Service<Request<Body>> for ErrorBodyMiddleware<S>
+where
+    S: Service<Request<Body>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        let format = self.format.clone();
+        let future = self.inner.call(request);
+
+        Box::pin(async move { Ok(render_again(&*format, future.await?)) })
+    }
+}
+
+/// `response` rendered with `format`, if it's an error response
+fn render_again(format: &dyn ErrorBodyFormat, mut response: Response) -> Response {
+    let Some(RenderedError(error)) = response.extensions_mut().remove() else {
+        return response;
+    };
+    let (mut parts, _) = response.into_parts();
+    let (rendered, body) = format.render(parts.status, &error).into_parts();
+    parts.headers.remove(CONTENT_TYPE);
+    parts.headers.remove(CONTENT_LENGTH);
+    parts.headers.extend(rendered.headers);
+    parts.status = rendered.status;
+    Response::from_parts(parts, body)
+}
@@ -0,0 +1,366 @@
+use crate::common::StatusCode as ApiStatus;
+use crate::exception::HttpException;
+use axum::{
+    body::{Body, Bytes},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use futures_util::stream;
+use std::io::SeekFrom;
+use std::path::{Component, Path, PathBuf};
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+/// The size of the chunks a file is read and sent in
+const CHUNK_SIZE: u64 = 64 * 1024;
+
+/// A file on disk as a response, streamed in chunks
+///
+/// The response has the file's `Content-Length` and a `Content-Type` guessed
+/// from its extension, and accepts byte ranges when given the request's
+/// headers with [`range`](Self::range): a satisfiable `Range` answers 206
+/// with that part of the file, and one starting past its end 416. Lists of
+/// ranges, other units and malformed headers are ignored: the whole file is
+/// sent with 200. A missing file answers 404.
+///
+/// When the path comes from the request, confine it to a directory with
+/// [`open_within`](Self::open_within); it's resolved there, and a path
+/// leaving the directory answers 404 as if it didn't exist.
+///
+/// Opening reads the file's metadata with `tokio::fs`; the file itself is
+/// opened and read as the body is sent.
+///
+/// # Example
+/// ```rust,ignore
+/// #[get("/reports/{name}")]
+/// async fn download(&self, #[param] name: String, headers: HeaderMap) -> FileResponse {
+///     FileResponse::open_within(&self.config.reports_dir, name)
+///         .await
+///         .attachment("report.csv")
+///         .range(&headers)
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct FileResponse {
+    /// The file's path and length; `None` answers 404
+    file: Option<(PathBuf, u64)>,
+    content_type: Option<String>,
+    disposition: Option<String>,
+    range: Option<HeaderValue>,
+}
+
+impl FileResponse {
+    /// The file at `path`
+    pub async fn open(path: impl Into<PathBuf>) -> Self {
+        Self::with_file(stat(path.into()).await)
+    }
+
+    /// The file at `path` in `root`, which it must not leave
+    ///
+    /// The path is joined to `root` and canonicalized, so `..` components
+    /// and symbolic links pointing elsewhere are refused.
+    pub async fn open_within(root: impl AsRef<Path>, path: impl AsRef<Path>) -> Self {
+        let file = match resolve(root.as_ref(), path.as_ref()).await {
+            Some(path) => stat(path).await,
+            None => None,
+        };
+        Self::with_file(file)
+    }
+
+    fn with_file(file: Option<(PathBuf, u64)>) -> Self {
+        Self {
+            file,
+            content_type: None,
+            disposition: None,
+            range: None,
+        }
+    }
+
+    /// Have the client save the file as `filename` rather than display it
+    pub fn attachment(mut self, filename: &str) -> Self {
+        self.disposition = Some(attachment(filename));
+        self
+    }
+
+    /// Answer with `content_type` rather than the one of the extension
+    pub fn content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+
+    /// Serve the byte range the request's `Range` header asks for, if any
+    pub fn range(mut self, headers: &HeaderMap) -> Self {
+        self.range = headers.get(header::RANGE).cloned();
+        self
+    }
+}
+
+/// `path` in `root`, unless it leaves it or doesn't exist
+async fn resolve(root: &Path, path: &Path) -> Option<PathBuf> {
+    // Refused before touching the filesystem, so that it can't be probed
+    // for paths outside the root.
+    let escapes = path
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir));
+    if escapes {
+        return None;
+    }
+    let root = tokio::fs::canonicalize(root).await.ok()?;
+    let path = tokio::fs::canonicalize(root.join(path)).await.ok()?;
+    path.starts_with(&root).then_some(path)
+}
+
+/// `path` and the length of the file there, unless it isn't a file
+async fn stat(path: PathBuf) -> Option<(PathBuf, u64)> {
+    match tokio::fs::metadata(&path).await {
+        Ok(metadata) if metadata.is_file() => Some((path, metadata.len())),
+        _ => None,
+    }
+}
+
+impl IntoResponse for FileResponse {
+    fn into_response(self) -> Response {
+        let Some((path, len)) = self.file else {
+            return HttpException::not_found("File not found").into_response();
+        };
+
+        let range = self
+            .range
+            .as_ref()
+            .map_or(ByteRange::Ignored, |range| parse_range(range, len));
+        let (status, start, end) = match range {
+            ByteRange::Ignored => (StatusCode::OK, 0, len),
+            ByteRange::Part(start, end) => (StatusCode::PARTIAL_CONTENT, start, end),
+            ByteRange::NotSatisfiable => return range_not_satisfiable(len),
+        };
+
+        let content_type = self
+            .content_type
+            .unwrap_or_else(|| content_type_of(&path).to_string());
+        let mut response = Response::new(Body::from_stream(read_file(path, start, end)));
+        *response.status_mut() = status;
+        let headers = response.headers_mut();
+        if let Ok(content_type) = HeaderValue::from_str(&content_type) {
+            headers.insert(header::CONTENT_TYPE, content_type);
+        }
+        headers.insert(header::CONTENT_LENGTH, HeaderValue::from(end - start));
+        headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+        if status == StatusCode::PARTIAL_CONTENT {
+            let content_range = format!("bytes {}-{}/{}", start, end - 1, len);
+            if let Ok(content_range) = HeaderValue::from_str(&content_range) {
+                headers.insert(header::CONTENT_RANGE, content_range);
+            }
+        }
+        if let Some(disposition) = self
+            .disposition
+            .and_then(|d| HeaderValue::from_str(&d).ok())
+        {
+            headers.insert(header::CONTENT_DISPOSITION, disposition);
+        }
+        response
+    }
+}
+
+/// The bytes `start..end` of the file at `path`, in chunks
+fn read_file(
+    path: PathBuf,
+    start: u64,
+    end: u64,
+) -> impl futures_util::Stream<Item = std::io::Result<Bytes>> + Send + 'static {
+    stream::try_unfold((None::<File>, start), move |(file, position)| {
+        let path = path.clone();
+        async move {
+            if position >= end {
+                return Ok(None);
+            }
+            let mut file = match file {
+                Some(file) => file,
+                None => {
+                    let mut file = File::open(&path).await?;
+                    file.seek(SeekFrom::Start(position)).await?;
+                    file
+                }
+            };
+            let mut chunk = vec![0; CHUNK_SIZE.min(end - position) as usize];
+            let read = file.read(&mut chunk).await?;
+            if read == 0 {
+                return Err(std::io::ErrorKind::UnexpectedEof.into());
+            }
+            chunk.truncate(read);
+            Ok(Some((
+                Bytes::from(chunk),
+                (Some(file), position + read as u64),
+            )))
+        }
+    })
+}
+
+/// What a `Range` header asks of a file
+#[derive(Debug, PartialEq, Eq)]
+enum ByteRange {
+    /// Not a single byte range: the whole file is sent
+    Ignored,
+    /// The bytes `start..end`
+    Part(u64, u64),
+    /// A range starting past the end of the file
+    NotSatisfiable,
+}
+
+/// What a `Range` header asks of `len` bytes
+///
+/// A single byte range is served; lists of ranges, other units and
+/// malformed ranges are ignored, as RFC 9110 allows.
+fn parse_range(range: &HeaderValue, len: u64) -> ByteRange {
+    let Some(spec) = range
+        .to_str()
+        .ok()
+        .and_then(|range| range.trim().strip_prefix("bytes="))
+    else {
+        return ByteRange::Ignored;
+    };
+    if spec.contains(',') {
+        return ByteRange::Ignored;
+    }
+    let Some((first, last)) = spec.split_once('-') else {
+        return ByteRange::Ignored;
+    };
+    let (first, last) = (first.trim(), last.trim());
+    if first.is_empty() {
+        // A suffix: the last `last` bytes.
+        return match last.parse::<u64>() {
+            Ok(0) => ByteRange::NotSatisfiable,
+            Ok(_) if len == 0 => ByteRange::NotSatisfiable,
+            Ok(suffix) => ByteRange::Part(len.saturating_sub(suffix), len),
+            Err(_) => ByteRange::Ignored,
+        };
+    }
+    let Ok(start) = first.parse::<u64>() else {
+        return ByteRange::Ignored;
+    };
+    let end = match last {
+        "" => len,
+        last => match last.parse::<u64>() {
+            Ok(last) if last >= start => last.saturating_add(1).min(len),
+            _ => return ByteRange::Ignored,
+        },
+    };
+    if start >= len {
+        ByteRange::NotSatisfiable
+    } else {
+        ByteRange::Part(start, end)
+    }
+}
+
+/// The 416 of a range outside `len` bytes
+fn range_not_satisfiable(len: u64) -> Response {
+    let mut response = HttpException::new(
+        ApiStatus::RangeNotSatisfiable,
+        "Requested range not satisfiable",
+    )
+    .into_response();
+    if let Ok(content_range) = HeaderValue::from_str(&format!("bytes */{}", len)) {
+        response
+            .headers_mut()
+            .insert(header::CONTENT_RANGE, content_range);
+    }
+    response
+}
+
+/// The `Content-Disposition` of an attachment saved as `filename`
+pub(crate) fn attachment(filename: &str) -> String {
+    let filename: String = filename
+        .chars()
+        .map(|c| match c {
+            '"' | '\\' => '_',
+            c if c.is_ascii() && !c.is_ascii_control() => c,
+            _ => '_',
+        })
+        .collect();
+    format!("attachment; filename=\"{}\"", filename)
+}
+
+/// The media type of a file, from its extension
+fn content_type_of(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+        Some("txt") => "text/plain; charset=utf-8",
+        Some("html" | "htm") => "text/html; charset=utf-8",
+        Some("css") => "text/css",
+        Some("csv") => "text/csv",
+        Some("js" | "mjs") => "text/javascript",
+        Some("json") => "application/json",
+        Some("xml") => "application/xml",
+        Some("pdf") => "application/pdf",
+        Some("zip") => "application/zip",
+        Some("gz") => "application/gzip",
+        Some("wasm") => "application/wasm",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("svg") => "image/svg+xml",
+        Some("webp") => "image/webp",
+        Some("ico") => "image/x-icon",
+        Some("mp3") => "audio/mpeg",
+        Some("mp4") => "video/mp4",
+        Some("webm") => "video/webm",
+        Some("woff") => "font/woff",
+        Some("woff2") => "font/woff2",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(value: &'static str, len: u64) -> ByteRange {
+        parse_range(&HeaderValue::from_static(value), len)
+    }
+
+    #[test]
+    fn ranges_are_clamped_to_the_file() {
+        assert_eq!(range("bytes=0-9", 100), ByteRange::Part(0, 10));
+        assert_eq!(range("bytes=90-", 100), ByteRange::Part(90, 100));
+        assert_eq!(range("bytes=-10", 100), ByteRange::Part(90, 100));
+        assert_eq!(range("bytes=90-200", 100), ByteRange::Part(90, 100));
+        assert_eq!(range("bytes=-200", 100), ByteRange::Part(0, 100));
+    }
+
+    #[test]
+    fn ranges_past_the_end_are_refused() {
+        assert_eq!(range("bytes=100-", 100), ByteRange::NotSatisfiable);
+        assert_eq!(range("bytes=150-200", 100), ByteRange::NotSatisfiable);
+        assert_eq!(range("bytes=-0", 100), ByteRange::NotSatisfiable);
+        assert_eq!(range("bytes=0-", 0), ByteRange::NotSatisfiable);
+    }
+
+    #[test]
+    fn other_ranges_are_ignored() {
+        assert_eq!(range("bytes=0-1,5-9", 100), ByteRange::Ignored);
+        assert_eq!(range("items=0-9", 100), ByteRange::Ignored);
+        assert_eq!(range("bytes=9-0", 100), ByteRange::Ignored);
+        assert_eq!(range("bytes=a-b", 100), ByteRange::Ignored);
+        assert_eq!(range("bytes=10", 100), ByteRange::Ignored);
+    }
+
+    #[test]
+    fn content_types_follow_the_extension() {
+        assert_eq!(content_type_of(Path::new("report.CSV")), "text/csv");
+        assert_eq!(content_type_of(Path::new("logo.png")), "image/png");
+        assert_eq!(
+            content_type_of(Path::new("data")),
+            "application/octet-stream"
+        );
+    }
+
+    #[test]
+    fn attachment_filenames_are_quoted_safely() {
+        assert_eq!(
+            attachment("report \"2024\".csv"),
+            "attachment; filename=\"report _2024_.csv\""
+        );
+    }
+}
@@ -1,5 +1,13 @@
+pub mod error_body;
+pub mod file;
+pub mod pagination;
 pub mod response;
 pub mod status_code;
+pub mod stream;
 
-pub use response::ApiResponse;
+pub use error_body::ErrorBodyFormat;
+pub use file::FileResponse;
+pub use pagination::{PageInfo, Pagination};
+pub use response::{ApiResponse, ResponseMeta, ResultExt};
 pub use status_code::StatusCode;
+pub use stream::StreamResponse;
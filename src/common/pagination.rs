@@ -0,0 +1,235 @@
+use crate::openapi::{ApiSchema, Components, Property, object_schema};
+use axum::http::Uri;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+/// Where a page sits in a paginated list
+///
+/// [`ApiResponse::paginated`](crate::common::ApiResponse::paginated)
+/// serializes it under `meta.pagination`:
+///
+/// ```json
+/// { "page": 2, "per_page": 20, "total_items": 45, "total_pages": 3 }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageInfo {
+    /// The page number, from 1
+    pub page: u64,
+    pub per_page: u64,
+    pub total_items: u64,
+    pub total_pages: u64,
+}
+
+impl PageInfo {
+    /// Page `page` of `total_items` items, `per_page` to a page
+    ///
+    /// A `page` or `per_page` of 0 counts as 1. An empty list has no pages.
+    pub fn new(page: u64, per_page: u64, total_items: u64) -> Self {
+        let per_page = per_page.max(1);
+        Self {
+            page: page.max(1),
+            per_page,
+            total_items,
+            total_pages: total_items.div_ceil(per_page),
+        }
+    }
+
+    pub fn has_next(&self) -> bool {
+        self.page < self.total_pages
+    }
+
+    pub fn has_prev(&self) -> bool {
+        self.page > 1
+    }
+
+    /// The RFC 8288 `Link` header of the page requested at `uri`, with
+    /// `rel="next"` and `rel="prev"` links; `None` for a single page
+    ///
+    /// The links are `uri` with its `page` query parameter replaced, so the
+    /// other parameters, `per_page` included, are kept.
+    pub fn link_header(&self, uri: &Uri) -> Option<String> {
+        let mut links = Vec::new();
+        if self.has_next() {
+            links.push(format!("<{}>; rel=\"next\"", page_uri(uri, self.page + 1)));
+        }
+        if self.has_prev() {
+            links.push(format!("<{}>; rel=\"prev\"", page_uri(uri, self.page - 1)));
+        }
+        (!links.is_empty()).then(|| links.join(", "))
+    }
+}
+
+/// `uri` with its `page` query parameter set to `page`
+fn page_uri(uri: &Uri, page: u64) -> String {
+    let mut params: Vec<String> = uri
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .filter(|param| !param.is_empty())
+        .filter(|param| param.split('=').next() != Some("page"))
+        .map(str::to_string)
+        .collect();
+    params.push(format!("page={}", page));
+
+    let origin = match (uri.scheme_str(), uri.authority()) {
+        (Some(scheme), Some(authority)) => format!("{}://{}", scheme, authority),
+        _ => String::new(),
+    };
+    format!("{}{}?{}", origin, uri.path(), params.join("&"))
+}
+
+/// The `page` and `per_page` query parameters of a list endpoint, for
+/// `#[query]`
+///
+/// `page` starts at 1 and defaults to it. `per_page` defaults to
+/// `DEFAULT_PER_PAGE` and is capped at `MAX_PER_PAGE` rather than rejected.
+///
+/// # Example
+/// ```rust,ignore
+/// #[get("/")]
+/// async fn list(
+///     &self,
+///     #[query] pagination: Pagination<25, 50>,
+///     OriginalUri(uri): OriginalUri,
+/// ) -> Result<ApiResponse<Vec<User>>> {
+///     let total = self.repository.count().await?;
+///     let users = self
+///         .repository
+///         .find_page(pagination.offset(), pagination.limit())
+///         .await?;
+///     Ok(ApiResponse::paginated(users, pagination.page_info(total)).with_links(&uri))
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub struct Pagination<const DEFAULT_PER_PAGE: u64 = 20, const MAX_PER_PAGE: u64 = 100> {
+    page: Option<u64>,
+    per_page: Option<u64>,
+}
+
+impl<const DEFAULT_PER_PAGE: u64, const MAX_PER_PAGE: u64>
+    Pagination<DEFAULT_PER_PAGE, MAX_PER_PAGE>
+{
+    pub fn new(page: u64, per_page: u64) -> Self {
+        Self {
+            page: Some(page),
+            per_page: Some(per_page),
+        }
+    }
+
+    /// The page number, from 1
+    pub fn page(&self) -> u64 {
+        self.page.unwrap_or(1).max(1)
+    }
+
+    /// The page size, from 1 to `MAX_PER_PAGE`
+    pub fn per_page(&self) -> u64 {
+        self.per_page
+            .unwrap_or(DEFAULT_PER_PAGE)
+            .clamp(1, MAX_PER_PAGE.max(1))
+    }
+
+    /// The number of items before the page
+    pub fn offset(&self) -> u64 {
+        (self.page() - 1).saturating_mul(self.per_page())
+    }
+
+    /// The number of items on a page, [`per_page`](Self::per_page)
+    pub fn limit(&self) -> u64 {
+        self.per_page()
+    }
+
+    /// The [`PageInfo`] of this page of `total_items` items
+    pub fn page_info(&self, total_items: u64) -> PageInfo {
+        PageInfo::new(self.page(), self.per_page(), total_items)
+    }
+}
+
+/// Described as its query parameters, with their defaults and bounds
+impl<const DEFAULT_PER_PAGE: u64, const MAX_PER_PAGE: u64> ApiSchema
+    for Pagination<DEFAULT_PER_PAGE, MAX_PER_PAGE>
+{
+    fn schema(_components: &mut Components) -> Value {
+        object_schema(
+            None,
+            vec![
+                Property {
+                    name: "page",
+                    schema: json!({ "type": "integer", "format": "int64", "minimum": 1, "default": 1 }),
+                    description: Some("The page number, from 1"),
+                    required: false,
+                },
+                Property {
+                    name: "per_page",
+                    schema: json!({
+                        "type": "integer",
+                        "format": "int64",
+                        "minimum": 1,
+                        "maximum": MAX_PER_PAGE,
+                        "default": DEFAULT_PER_PAGE,
+                    }),
+                    description: Some("The page size"),
+                    required: false,
+                },
+            ],
+        )
+    }
+}
+
+impl ApiSchema for PageInfo {
+    fn schema(components: &mut Components) -> Value {
+        components.define("PageInfo", |_| {
+            json!({
+                "type": "object",
+                "properties": {
+                    "page": { "type": "integer", "format": "int64", "minimum": 1 },
+                    "per_page": { "type": "integer", "format": "int64", "minimum": 1 },
+                    "total_items": { "type": "integer", "format": "int64", "minimum": 0 },
+                    "total_pages": { "type": "integer", "format": "int64", "minimum": 0 },
+                },
+                "required": ["page", "per_page", "total_items", "total_pages"],
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pages_are_counted_from_the_total() {
+        assert_eq!(PageInfo::new(1, 20, 45).total_pages, 3);
+        assert_eq!(PageInfo::new(1, 20, 40).total_pages, 2);
+        assert_eq!(PageInfo::new(1, 20, 0).total_pages, 0);
+        assert_eq!(PageInfo::new(0, 0, 3), PageInfo::new(1, 1, 3));
+    }
+
+    #[test]
+    fn per_page_defaults_and_is_capped() {
+        let default: Pagination = Pagination::default();
+        assert_eq!((default.page(), default.per_page()), (1, 20));
+        assert_eq!(default.offset(), 0);
+
+        let capped: Pagination<10, 50> = Pagination::new(3, 500);
+        assert_eq!(capped.per_page(), 50);
+        assert_eq!(capped.offset(), 100);
+        assert_eq!(Pagination::<10, 50>::new(0, 0).per_page(), 1);
+    }
+
+    #[test]
+    fn links_replace_the_page_and_keep_other_parameters() {
+        let uri: Uri = "/users?sort=name&page=2&per_page=20".parse().unwrap();
+        assert_eq!(
+            PageInfo::new(2, 20, 45).link_header(&uri).unwrap(),
+            "</users?sort=name&per_page=20&page=3>; rel=\"next\", \
+             </users?sort=name&per_page=20&page=1>; rel=\"prev\""
+        );
+
+        let uri: Uri = "https://api.example.com/users".parse().unwrap();
+        assert_eq!(
+            PageInfo::new(1, 20, 45).link_header(&uri).unwrap(),
+            "<https://api.example.com/users?page=2>; rel=\"next\""
+        );
+        assert_eq!(PageInfo::new(1, 20, 5).link_header(&uri), None);
+    }
+}
@@ -1,9 +1,14 @@
+use crate::common::error_body::render_error;
+use crate::common::pagination::PageInfo;
+use crate::error::MeshestraError;
+use crate::exception::HttpException;
 use axum::{
     Json,
-    http::StatusCode as HttpStatusCode,
+    http::{HeaderValue, StatusCode as HttpStatusCode, Uri, header},
     response::{IntoResponse, Response},
 };
 use serde::Serialize;
+use std::fmt::Display;
 
 /// Standard API response wrapper
 ///
@@ -35,6 +40,9 @@ pub struct ApiResponse<T: Serialize> {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub data: Option<T>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<ResponseMeta>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<ApiError>,
 
@@ -42,12 +50,27 @@ pub struct ApiResponse<T: Serialize> {
 
     #[serde(skip)]
     pub http_status: HttpStatusCode,
+
+    /// The `Link` header, see [`ApiResponse::with_links`]
+    #[serde(skip)]
+    pub link: Option<HeaderValue>,
 }
 
-#[derive(Debug, Serialize)]
+/// What a response says about its `data`, under `meta`
+#[derive(Debug, Default, Serialize)]
+pub struct ResponseMeta {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pagination: Option<PageInfo>,
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct ApiError {
     pub code: String,
     pub message: String,
+
+    /// Structured context, e.g. the offending fields of a request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
 }
 
 impl<T: Serialize> ApiResponse<T> {
@@ -57,9 +80,11 @@ impl<T: Serialize> ApiResponse<T> {
     pub fn success(data: T) -> Self {
         Self {
             data: Some(data),
+            meta: None,
             error: None,
             success: true,
             http_status: HttpStatusCode::OK,
+            link: None,
         }
     }
 
@@ -79,19 +104,242 @@ impl<T: Serialize> ApiResponse<T> {
     pub fn error(status: crate::common::StatusCode, message: impl Into<String>) -> ApiResponse<T> {
         ApiResponse {
             data: None,
+            meta: None,
             error: Some(ApiError {
                 code: status.to_string(),
                 message: message.into(),
+                details: None,
             }),
             success: false,
             http_status: status.into(),
+            link: None,
         }
     }
+
+    /// Create an error response with an explicit `code`, for errors whose
+    /// code isn't the name of their status
+    pub fn error_with_code(
+        status: HttpStatusCode,
+        code: impl Into<String>,
+        message: impl Into<String>,
+    ) -> ApiResponse<T> {
+        ApiResponse {
+            data: None,
+            meta: None,
+            error: Some(ApiError {
+                code: code.into(),
+                message: message.into(),
+                details: None,
+            }),
+            success: false,
+            http_status: status,
+            link: None,
+        }
+    }
+
+    /// Answer with `status` instead of the default
+    ///
+    /// An explicit status takes precedence over a route's `#[status]`,
+    /// which only replaces the default 200.
+    pub fn with_status(mut self, status: HttpStatusCode) -> Self {
+        self.http_status = status;
+        self
+    }
+
+    /// Attach structured `details` to an error response
+    ///
+    /// Has no effect on successful responses.
+    pub fn with_details(mut self, details: serde_json::Value) -> Self {
+        if let Some(error) = self.error.as_mut() {
+            error.details = Some(details);
+        }
+        self
+    }
+
+    /// A success with the value of `result`, or its error
+    ///
+    /// Errors are converted by the `From` impls: an [`HttpException`]
+    /// answers with its status, a [`MeshestraError`] as it would on its own.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// #[get("/{id}")]
+    /// async fn get_one(&self, #[param] id: u64) -> ApiResponse<User> {
+    ///     ApiResponse::from_result(self.service.find(id).await)
+    /// }
+    /// ```
+    pub fn from_result<E>(result: Result<T, E>) -> Self
+    where
+        Self: From<E>,
+    {
+        result.into()
+    }
+}
+
+impl<T: Serialize> ApiResponse<Vec<T>> {
+    /// Create a successful response with a page of `items`, described by
+    /// `page` under `meta.pagination`
+    ///
+    /// # Example
+    /// ```
+    /// use meshestra::common::{ApiResponse, Pagination};
+    ///
+    /// let pagination: Pagination = Pagination::new(2, 2);
+    /// let response = ApiResponse::paginated(vec!["c", "d"], pagination.page_info(5));
+    /// assert_eq!(
+    ///     serde_json::to_string(&response).unwrap(),
+    ///     r#"{"data":["c","d"],"meta":{"pagination":{"page":2,"per_page":2,"total_items":5,"total_pages":3}},"success":true}"#
+    /// );
+    /// ```
+    pub fn paginated(items: Vec<T>, page: PageInfo) -> Self {
+        Self {
+            meta: Some(ResponseMeta {
+                pagination: Some(page),
+            }),
+            ..Self::success(items)
+        }
+    }
+
+    /// Add a `Link` header to the next and previous pages, for the page
+    /// requested at `uri`
+    ///
+    /// Use the `OriginalUri` of the request, as `Uri` lacks the path the
+    /// router was nested under. Has no effect without `meta.pagination`.
+    pub fn with_links(mut self, uri: &Uri) -> Self {
+        self.link = self
+            .meta
+            .as_ref()
+            .and_then(|meta| meta.pagination)
+            .and_then(|page| page.link_header(uri))
+            .and_then(|link| HeaderValue::from_str(&link).ok());
+        self
+    }
 }
 
 impl<T: Serialize> IntoResponse for ApiResponse<T> {
+    /// Errors render through
+    /// [`render_error`](crate::common::error_body::render_error)
     fn into_response(self) -> Response {
+        if let (Some(error), None) = (&self.error, &self.data) {
+            return render_error(self.http_status, error);
+        }
         // Use the stored http_status to provide accurate HTTP semantics
-        (self.http_status, Json(self)).into_response()
+        let link = self.link.clone();
+        let mut response = (self.http_status, Json(self)).into_response();
+        if let Some(link) = link {
+            response.headers_mut().insert(header::LINK, link);
+        }
+        response
+    }
+}
+
+impl<T: Serialize> From<HttpException> for ApiResponse<T> {
+    fn from(exception: HttpException) -> Self {
+        let response = ApiResponse::error(exception.status(), exception.message());
+        match exception.details() {
+            Some(details) => response.with_details(details.clone()),
+            None => response,
+        }
+    }
+}
+
+/// A 500 with the error's code, or the status of a wrapped
+/// [`HttpException`]
+impl<T: Serialize> From<MeshestraError> for ApiResponse<T> {
+    fn from(error: MeshestraError) -> Self {
+        let code = error.code();
+        let message = match error {
+            MeshestraError::Http(exception) => return exception.into(),
+            MeshestraError::Internal(message) => message,
+            error => error.to_string(),
+        };
+        ApiResponse::error_with_code(HttpStatusCode::INTERNAL_SERVER_ERROR, code, message)
+    }
+}
+
+impl<T: Serialize, E> From<Result<T, E>> for ApiResponse<T>
+where
+    ApiResponse<T>: From<E>,
+{
+    fn from(result: Result<T, E>) -> Self {
+        match result {
+            Ok(data) => ApiResponse::success(data),
+            Err(error) => error.into(),
+        }
+    }
+}
+
+/// Adapters from a service's `Result` to what a handler answers
+///
+/// # Example
+/// ```rust,ignore
+/// #[get("/{id}")]
+/// async fn get_one(&self, #[param] id: u64) -> ApiResponse<User> {
+///     self.service
+///         .find(id)
+///         .await
+///         .not_found_when(|e| matches!(e, UserError::Missing(_)))
+/// }
+///
+/// #[post("/")]
+/// async fn create(&self, #[body] user: NewUser) -> Result<Json<User>, HttpException> {
+///     let user = self
+///         .service
+///         .create(user)
+///         .await
+///         .or_status(meshestra::common::StatusCode::Conflict)?;
+///     Ok(Json(user))
+/// }
+/// ```
+pub trait ResultExt<T, E> {
+    /// [`ApiResponse::from_result`]
+    fn into_api_response(self) -> ApiResponse<T>
+    where
+        T: Serialize,
+        ApiResponse<T>: From<E>;
+
+    /// Like [`into_api_response`](Self::into_api_response), but errors
+    /// `is_not_found` picks answer 404 with their message
+    fn not_found_when(self, is_not_found: impl FnOnce(&E) -> bool) -> ApiResponse<T>
+    where
+        T: Serialize,
+        E: Display,
+        ApiResponse<T>: From<E>;
+
+    /// Turn the error into an [`HttpException`] of `status`, with the
+    /// error's message
+    fn or_status(self, status: crate::common::StatusCode) -> Result<T, HttpException>
+    where
+        E: Display;
+}
+
+impl<T, E> ResultExt<T, E> for Result<T, E> {
+    fn into_api_response(self) -> ApiResponse<T>
+    where
+        T: Serialize,
+        ApiResponse<T>: From<E>,
+    {
+        self.into()
+    }
+
+    fn not_found_when(self, is_not_found: impl FnOnce(&E) -> bool) -> ApiResponse<T>
+    where
+        T: Serialize,
+        E: Display,
+        ApiResponse<T>: From<E>,
+    {
+        match self {
+            Err(error) if is_not_found(&error) => {
+                ApiResponse::error(crate::common::StatusCode::NotFound, error.to_string())
+            }
+            result => result.into(),
+        }
+    }
+
+    fn or_status(self, status: crate::common::StatusCode) -> Result<T, HttpException>
+    where
+        E: Display,
+    {
+        self.map_err(|error| HttpException::new(status, error.to_string()))
     }
 }
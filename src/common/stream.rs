@@ -0,0 +1,66 @@
+use crate::common::file::attachment;
+use axum::{
+    BoxError,
+    body::{Body, Bytes},
+    http::{HeaderValue, header},
+    response::{IntoResponse, Response},
+};
+use futures_util::Stream;
+
+/// A response whose body is a stream of chunks, sent as they're produced
+///
+/// Useful for exports generated on the fly. An error from the stream aborts
+/// the response, which the client sees as a truncated body.
+///
+/// # Example
+/// ```rust,ignore
+/// #[get("/export")]
+/// async fn export(&self) -> StreamResponse {
+///     let rows = self.repository.stream_all().map_ok(|user| Bytes::from(user.to_csv_row()));
+///     StreamResponse::from_stream(rows, "text/csv").attachment("users.csv")
+/// }
+/// ```
+pub struct StreamResponse {
+    body: Body,
+    content_type: HeaderValue,
+    disposition: Option<String>,
+}
+
+impl StreamResponse {
+    /// Send the chunks of `stream` as a body of `content_type`
+    ///
+    /// An invalid `content_type` is sent as `application/octet-stream`.
+    pub fn from_stream<S, E>(stream: S, content_type: &str) -> Self
+    where
+        S: Stream<Item = Result<Bytes, E>> + Send + 'static,
+        E: Into<BoxError>,
+    {
+        Self {
+            body: Body::from_stream(stream),
+            content_type: HeaderValue::from_str(content_type)
+                .unwrap_or(HeaderValue::from_static("application/octet-stream")),
+            disposition: None,
+        }
+    }
+
+    /// Have the client save the body as `filename` rather than display it
+    pub fn attachment(mut self, filename: &str) -> Self {
+        self.disposition = Some(attachment(filename));
+        self
+    }
+}
+
+impl IntoResponse for StreamResponse {
+    fn into_response(self) -> Response {
+        let mut response = Response::new(self.body);
+        let headers = response.headers_mut();
+        headers.insert(header::CONTENT_TYPE, self.content_type);
+        if let Some(disposition) = self
+            .disposition
+            .and_then(|d| HeaderValue::from_str(&d).ok())
+        {
+            headers.insert(header::CONTENT_DISPOSITION, disposition);
+        }
+        response
+    }
+}
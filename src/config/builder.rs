@@ -0,0 +1,138 @@
+use crate::config::{ConfigError, ConfigService, ConfigSource};
+use std::env;
+use std::path::PathBuf;
+
+/// Builder assembling a [`ConfigService`] from layered sources
+///
+/// Sources are applied in the order they are added, so later sources
+/// override keys set by earlier ones. A `{profile}` placeholder in a file
+/// path is replaced with the configured profile name.
+///
+/// # Example
+/// ```no_run
+/// use meshestra::config::ConfigService;
+///
+/// let config = ConfigService::builder()
+///     .profile("production")
+///     .add_toml_file("config/default.toml")
+///     .add_toml_file("config/{profile}.toml")
+///     .add_env(Some("APP"))
+///     .build()
+///     .unwrap();
+///
+/// let pool_size: u32 = config.get_as("database.pool_size").unwrap();
+/// ```
+#[derive(Default)]
+pub struct ConfigBuilder {
+    profile: Option<String>,
+    sources: Vec<ConfigSource>,
+}
+
+impl ConfigBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the active profile name (e.g. `"development"`, `"production"`)
+    pub fn profile(mut self, name: impl Into<String>) -> Self {
+        self.profile = Some(name.into());
+        self
+    }
+
+    /// Read the active profile name from an environment variable, if set
+    pub fn profile_from_env(mut self, var: &str) -> Self {
+        if let Ok(name) = env::var(var) {
+            self.profile = Some(name);
+        }
+        self
+    }
+
+    /// Add a TOML file; nested tables are flattened to dotted keys
+    ///
+    /// A missing file is skipped, but a file that exists and fails to parse
+    /// makes [`build`](Self::build) return an error.
+    pub fn add_toml_file(self, path: impl Into<PathBuf>) -> Self {
+        self.add_source(ConfigSource::toml_file(path))
+    }
+
+    /// Add environment variables
+    ///
+    /// With a prefix, only variables starting with `{PREFIX}_` are loaded;
+    /// the prefix is stripped, the rest is lowercased and `__` becomes `.`,
+    /// so `APP_DATABASE__POOL_SIZE` sets `database.pool_size`. Without a
+    /// prefix every variable is loaded under its own name.
+    pub fn add_env(self, prefix: Option<&str>) -> Self {
+        self.add_source(ConfigSource::env(prefix))
+    }
+
+    /// Add explicit key/value pairs
+    pub fn add_values<K, V>(self, values: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.add_source(ConfigSource::Values(
+            values
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        ))
+    }
+
+    /// Add a source
+    pub fn add_source(mut self, source: ConfigSource) -> Self {
+        self.sources.push(source);
+        self
+    }
+
+    pub fn build(self) -> Result<ConfigService, ConfigError> {
+        let service = ConfigService {
+            profile: self.profile.as_deref().map(Into::into),
+            ..Default::default()
+        };
+
+        for source in &self.sources {
+            if let Some(values) = source.load(self.profile.as_deref())? {
+                for (key, value) in values {
+                    service.set(&key, &value);
+                }
+            }
+        }
+
+        Ok(service)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::source::write_temp_file;
+
+    #[test]
+    fn test_later_sources_override_earlier() {
+        let default = write_temp_file(
+            "default.toml",
+            "[database]\nurl = \"postgres://localhost/app\"\npool_size = 5\n\n[server]\nhosts = [\"a\", \"b\"]\n",
+        );
+        let production = write_temp_file("production.toml", "[database]\npool_size = 20\n");
+        let profile_path = default.with_file_name("{profile}.toml");
+
+        let config = ConfigService::builder()
+            .profile("production")
+            .add_toml_file(&default)
+            .add_toml_file(profile_path)
+            .add_toml_file(default.with_file_name("missing.toml"))
+            .add_values([("database.url", "postgres://override/app")])
+            .build()
+            .unwrap();
+
+        assert_eq!(config.profile(), Some("production"));
+        assert_eq!(config.get_as::<u32>("database.pool_size").unwrap(), 20);
+        assert_eq!(
+            config.get("database.url").unwrap(),
+            "postgres://override/app"
+        );
+        assert_eq!(config.get("server.hosts").unwrap(), "a,b");
+        std::fs::remove_file(production).ok();
+    }
+}
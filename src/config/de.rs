@@ -0,0 +1,426 @@
+//! Deserialization of flat string configuration values into typed structs
+
+use serde::de::value::{
+    MapAccessDeserializer, MapDeserializer, SeqDeserializer, StringDeserializer,
+};
+use serde::de::{self, DeserializeSeed, Deserializer, IntoDeserializer, MapAccess, Visitor};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::time::Duration;
+
+/// A deserialization error, keeping the field serde reported missing
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Error {
+    MissingField(&'static str),
+    Message(String),
+}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::Message(msg.to_string())
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        Error::MissingField(field)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::MissingField(field) => write!(f, "missing field `{}`", field),
+            Error::Message(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// A single raw configuration value
+///
+/// Scalars are parsed on demand from the string representation, `Option`
+/// fields treat an empty string as `None`, and sequences are read as
+/// comma-separated lists.
+pub(crate) struct StrValue(pub(crate) String);
+
+impl<'de> IntoDeserializer<'de, Error> for StrValue {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident: $ty:ty),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                let parsed = self.0.trim().parse::<$ty>().map_err(|e| {
+                    <Error as de::Error>::custom(format!("expected {}: {}", stringify!($ty), e))
+                })?;
+                visitor.$visit(parsed)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for StrValue {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_string(self.0)
+    }
+
+    deserialize_parsed! {
+        deserialize_bool => visit_bool: bool,
+        deserialize_i8 => visit_i8: i8,
+        deserialize_i16 => visit_i16: i16,
+        deserialize_i32 => visit_i32: i32,
+        deserialize_i64 => visit_i64: i64,
+        deserialize_u8 => visit_u8: u8,
+        deserialize_u16 => visit_u16: u16,
+        deserialize_u32 => visit_u32: u32,
+        deserialize_u64 => visit_u64: u64,
+        deserialize_f32 => visit_f32: f32,
+        deserialize_f64 => visit_f64: f64,
+        deserialize_char => visit_char: char,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.0.is_empty() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let items = self
+            .0
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(|item| StrValue(item.to_string()));
+        visitor.visit_seq(SeqDeserializer::new(items))
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_enum(self.0.into_deserializer())
+    }
+
+    serde::forward_to_deserialize_any! {
+        i128 u128 str string bytes byte_buf unit unit_struct tuple tuple_struct
+        map struct identifier ignored_any
+    }
+}
+
+/// Deserialize a struct from `field -> raw value` pairs
+pub(crate) fn from_pairs<T, I>(pairs: I) -> Result<T, Error>
+where
+    T: de::DeserializeOwned,
+    I: IntoIterator<Item = (String, String)>,
+{
+    let deserializer = MapDeserializer::new(
+        pairs
+            .into_iter()
+            .map(|(field, value)| (field, StrValue(value))),
+    );
+    T::deserialize(deserializer)
+}
+
+/// A problem found while binding a section field
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum FieldIssue {
+    Missing(String),
+    Invalid { field: String, message: String },
+    Other(String),
+}
+
+/// Deserialize a struct from `field -> raw value` pairs, collecting every
+/// missing or invalid field instead of stopping at the first one
+///
+/// Serde aborts on the first error, so this retries: an invalid value is
+/// recorded and dropped, and a missing field is recorded and filled with a
+/// type-directed placeholder, until deserialization succeeds or no further
+/// progress can be made.
+pub(crate) fn from_pairs_collecting<T>(
+    mut pairs: BTreeMap<String, String>,
+) -> Result<T, Vec<FieldIssue>>
+where
+    T: de::DeserializeOwned,
+{
+    let mut issues = Vec::new();
+    let mut placeholders = BTreeSet::new();
+
+    loop {
+        let failed = RefCell::new(None);
+        let entries = pairs
+            .iter()
+            .map(|(field, value)| (field.clone(), Raw::Value(value.clone())))
+            .chain(
+                placeholders
+                    .iter()
+                    .map(|field: &String| (field.clone(), Raw::Placeholder)),
+            );
+        let result = T::deserialize(MapAccessDeserializer::new(KeyedMap {
+            entries,
+            pending: None,
+            failed: &failed,
+        }));
+
+        let error = match result {
+            Ok(value) if issues.is_empty() => return Ok(value),
+            Ok(_) => return Err(issues),
+            Err(error) => error,
+        };
+
+        match failed.into_inner() {
+            Some((field, message)) if !placeholders.contains(&field) => {
+                pairs.remove(&field);
+                issues.push(FieldIssue::Invalid { field, message });
+            }
+            Some(_) => return Err(issues),
+            None => match error {
+                Error::MissingField(field) if !placeholders.contains(field) => {
+                    let field = field.to_string();
+                    let already_reported = issues.iter().any(
+                        |issue| matches!(issue, FieldIssue::Invalid { field: f, .. } if *f == field),
+                    );
+                    if !already_reported {
+                        issues.push(FieldIssue::Missing(field.clone()));
+                    }
+                    placeholders.insert(field);
+                }
+                _ => {
+                    issues.push(FieldIssue::Other(error.to_string()));
+                    return Err(issues);
+                }
+            },
+        }
+    }
+}
+
+enum Raw {
+    Value(String),
+    Placeholder,
+}
+
+/// Map access remembering which field a value error belongs to
+struct KeyedMap<'a, I> {
+    entries: I,
+    pending: Option<(String, Raw)>,
+    failed: &'a RefCell<Option<(String, String)>>,
+}
+
+impl<'de, I> MapAccess<'de> for KeyedMap<'_, I>
+where
+    I: Iterator<Item = (String, Raw)>,
+{
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        let Some((field, raw)) = self.entries.next() else {
+            return Ok(None);
+        };
+        let key: StringDeserializer<Error> = field.clone().into_deserializer();
+        let key = seed.deserialize(key)?;
+        self.pending = Some((field, raw));
+        Ok(Some(key))
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        let (field, raw) = self
+            .pending
+            .take()
+            .ok_or_else(|| <Error as de::Error>::custom("value requested before key"))?;
+        let result = match raw {
+            Raw::Value(value) => seed.deserialize(StrValue(value)),
+            Raw::Placeholder => seed.deserialize(Placeholder),
+        };
+        result.inspect_err(|e| {
+            self.failed.replace(Some((field, e.to_string())));
+        })
+    }
+}
+
+/// Stand-in for a missing field that produces a default for whatever type is requested
+struct Placeholder;
+
+impl<'de> IntoDeserializer<'de, Error> for Placeholder {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+macro_rules! deserialize_placeholder {
+    ($($method:ident => $visit:ident($value:expr)),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                visitor.$visit($value)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for Placeholder {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    deserialize_placeholder! {
+        deserialize_bool => visit_bool(false),
+        deserialize_i8 => visit_i8(0),
+        deserialize_i16 => visit_i16(0),
+        deserialize_i32 => visit_i32(0),
+        deserialize_i64 => visit_i64(0),
+        deserialize_i128 => visit_i128(0),
+        deserialize_u8 => visit_u8(0),
+        deserialize_u16 => visit_u16(0),
+        deserialize_u32 => visit_u32(0),
+        deserialize_u64 => visit_u64(0),
+        deserialize_u128 => visit_u128(0),
+        deserialize_f32 => visit_f32(0.0),
+        deserialize_f64 => visit_f64(0.0),
+        deserialize_char => visit_char('\0'),
+        deserialize_str => visit_str(""),
+        deserialize_string => visit_str(""),
+        deserialize_bytes => visit_bytes(&[]),
+        deserialize_byte_buf => visit_bytes(&[]),
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_none()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_seq(SeqDeserializer::new(std::iter::empty::<Placeholder>()))
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_map(MapDeserializer::new(std::iter::empty::<(
+            String,
+            Placeholder,
+        )>()))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let first = variants
+            .first()
+            .ok_or_else(|| <Error as de::Error>::custom("enum has no variants"))?;
+        visitor.visit_enum((*first).into_deserializer())
+    }
+
+    serde::forward_to_deserialize_any! {
+        unit unit_struct tuple tuple_struct identifier ignored_any
+    }
+}
+
+/// Parse a human-friendly duration such as `"30s"`, `"5m"`, `"1h30m"` or `"250ms"`
+///
+/// Supported units are `ms`, `s`, `m`, `h` and `d`. A bare number is
+/// interpreted as seconds.
+pub fn parse_duration(input: &str) -> Result<Duration, String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err("empty duration".to_string());
+    }
+    if let Ok(secs) = input.parse::<u64>() {
+        return Ok(Duration::from_secs(secs));
+    }
+
+    let mut total = Duration::ZERO;
+    let mut rest = input;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        if digits == 0 {
+            return Err(format!("invalid duration '{}'", input));
+        }
+        let amount: u64 = rest[..digits]
+            .parse()
+            .map_err(|_| format!("invalid duration '{}'", input))?;
+        rest = &rest[digits..];
+
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let part = match &rest[..unit_len] {
+            "ms" => Some(Duration::from_millis(amount)),
+            "s" => Some(Duration::from_secs(amount)),
+            "m" => amount.checked_mul(60).map(Duration::from_secs),
+            "h" => amount.checked_mul(60 * 60).map(Duration::from_secs),
+            "d" => amount.checked_mul(60 * 60 * 24).map(Duration::from_secs),
+            unit => return Err(format!("unknown duration unit '{}' in '{}'", unit, input)),
+        };
+        total = part
+            .and_then(|part| total.checked_add(part))
+            .ok_or_else(|| format!("duration '{}' is too long", input))?;
+        rest = &rest[unit_len..];
+    }
+    Ok(total)
+}
+
+/// Serde helper accepting human-friendly durations in bound config structs
+///
+/// # Example
+/// ```
+/// use serde::Deserialize;
+/// use std::time::Duration;
+///
+/// #[derive(Deserialize)]
+/// struct HttpConfig {
+///     #[serde(deserialize_with = "meshestra::config::deserialize_duration")]
+///     timeout: Duration,
+/// }
+/// ```
+pub fn deserialize_duration<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw = <String as serde::Deserialize>::deserialize(deserializer)?;
+    parse_duration(&raw).map_err(de::Error::custom)
+}
@@ -1,11 +1,67 @@
+use crate::di::{Container, Injectable};
 use dashmap::DashMap;
+use serde::de::DeserializeOwned;
+use std::collections::BTreeMap;
 use std::env;
-use std::sync::Arc;
+use std::fmt::Display;
+use std::str::FromStr;
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::Duration;
+use tokio::sync::watch;
+
+mod builder;
+mod de;
+mod secret;
+mod source;
+mod watcher;
+
+pub use builder::ConfigBuilder;
+pub use de::{deserialize_duration, parse_duration};
+pub use secret::{DEFAULT_SECRET_PATTERNS, Secret};
+pub use source::ConfigSource;
+pub use watcher::ConfigWatcher;
+
+use de::FieldIssue;
+use secret::MASK;
+
+/// Errors raised when reading typed configuration values
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("Missing configuration key: {key}")]
+    Missing { key: String },
+
+    #[error("Invalid value for configuration key '{key}': {message}")]
+    Invalid { key: String, message: String },
+
+    #[error("Failed to bind configuration section '{prefix}': {message}")]
+    Bind { prefix: String, message: String },
+
+    #[error("Failed to load configuration from '{path}': {message}")]
+    Source { path: String, message: String },
+
+    #[error("Invalid configuration: {}", .issues.join("; "))]
+    Validation { issues: Vec<String> },
+}
+
+/// Validation hook for configuration sections bound with
+/// [`ConfigService::bind_validated`]
+///
+/// The default implementation accepts any value, so sections without extra
+/// rules only need an empty `impl Validate for MyConfig {}`.
+pub trait Validate {
+    /// Return a description of every rule the value violates
+    fn validate(&self) -> Result<(), Vec<String>> {
+        Ok(())
+    }
+}
 
 /// Configuration service
 #[derive(Clone, Default)]
 pub struct ConfigService {
     config: Arc<DashMap<String, String>>,
+    profile: Option<Arc<str>>,
+    watchers: Arc<DashMap<String, watch::Sender<Option<String>>>>,
+    secret_patterns: Arc<RwLock<Vec<String>>>,
 }
 
 impl ConfigService {
@@ -18,11 +74,561 @@ impl ConfigService {
         service
     }
 
+    /// Create a builder for layered configuration sources
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::new()
+    }
+
+    /// The active profile name, if one was set when building
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
     pub fn get(&self, key: &str) -> Option<String> {
         self.config.get(key).map(|v| v.clone())
     }
 
     pub fn set(&self, key: &str, value: &str) {
-        self.config.insert(key.to_string(), value.to_string());
+        let previous = self.config.insert(key.to_string(), value.to_string());
+        if previous.as_deref() != Some(value) {
+            self.notify(key, Some(value.to_string()));
+        }
+    }
+
+    /// Subscribe to changes of a single key
+    ///
+    /// The receiver starts with the current value and is notified whenever
+    /// [`set`](Self::set) or [`reload_from`](Self::reload_from) changes it.
+    ///
+    /// # Example
+    /// ```
+    /// use meshestra::config::ConfigService;
+    ///
+    /// let config = ConfigService::default();
+    /// let mut log_level = config.watch("log.level");
+    ///
+    /// config.set("log.level", "debug");
+    /// assert!(log_level.has_changed().unwrap());
+    /// assert_eq!(log_level.borrow_and_update().as_deref(), Some("debug"));
+    /// ```
+    pub fn watch(&self, key: &str) -> watch::Receiver<Option<String>> {
+        self.watchers
+            .entry(key.to_string())
+            .or_insert_with(|| watch::channel(self.get(key)).0)
+            .subscribe()
+    }
+
+    /// Re-read `source` and apply the values that differ from the current ones
+    ///
+    /// Watchers of changed keys are notified; keys no longer present in the
+    /// source keep their current value. Returns the keys that changed.
+    pub fn reload_from(&self, source: &ConfigSource) -> Result<Vec<String>, ConfigError> {
+        let Some(values) = source.load(self.profile())? else {
+            return Ok(Vec::new());
+        };
+
+        let changed: Vec<String> = values
+            .into_iter()
+            .filter(|(key, value)| self.get(key).as_deref() != Some(value.as_str()))
+            .map(|(key, value)| {
+                self.set(&key, &value);
+                key
+            })
+            .collect();
+
+        if !changed.is_empty() {
+            tracing::debug!("Configuration reloaded, changed keys: {:?}", changed);
+        }
+        Ok(changed)
+    }
+
+    /// Treat keys matching any of `patterns` as secret
+    ///
+    /// Patterns are case-insensitive globs where `*` matches any run of
+    /// characters; a pattern without `*` matches keys containing it. They
+    /// extend [`DEFAULT_SECRET_PATTERNS`], which always apply.
+    pub fn mark_secret(&self, patterns: &[&str]) {
+        self.secret_patterns
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .extend(patterns.iter().map(|p| p.to_string()));
+    }
+
+    /// Whether values of `key` are masked in dumps and errors
+    pub fn is_secret(&self, key: &str) -> bool {
+        DEFAULT_SECRET_PATTERNS
+            .iter()
+            .any(|pattern| secret::matches_pattern(pattern, key))
+            || self
+                .secret_patterns
+                .read()
+                .unwrap_or_else(PoisonError::into_inner)
+                .iter()
+                .any(|pattern| secret::matches_pattern(pattern, key))
+    }
+
+    /// Get a value wrapped so it cannot be logged by accident
+    pub fn get_secret(&self, key: &str) -> Option<Secret<String>> {
+        self.get(key).map(Secret::new)
+    }
+
+    /// All key/value pairs, sorted by key, with secret values replaced by `"***"`
+    ///
+    /// # Example
+    /// ```
+    /// use meshestra::config::ConfigService;
+    ///
+    /// let config = ConfigService::default();
+    /// config.set("database.url", "postgres://localhost/app");
+    /// config.set("database.password", "hunter2");
+    ///
+    /// let dump = config.dump();
+    /// assert_eq!(dump["database.password"], "***");
+    /// assert_eq!(dump["database.url"], "postgres://localhost/app");
+    /// ```
+    pub fn dump(&self) -> BTreeMap<String, String> {
+        self.config
+            .iter()
+            .map(|entry| {
+                let value = if self.is_secret(entry.key()) {
+                    MASK.to_string()
+                } else {
+                    entry.value().clone()
+                };
+                (entry.key().clone(), value)
+            })
+            .collect()
+    }
+
+    fn notify(&self, key: &str, value: Option<String>) {
+        if let Some(sender) = self.watchers.get(key) {
+            sender.send_replace(value);
+        }
+    }
+
+    /// Get a value parsed into `T`
+    ///
+    /// # Errors
+    /// Returns `ConfigError::Missing` if the key is not set, or
+    /// `ConfigError::Invalid` naming the key if parsing fails.
+    pub fn get_as<T>(&self, key: &str) -> Result<T, ConfigError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        let raw = self.require_key(key)?;
+        raw.trim().parse::<T>().map_err(|e| {
+            let message = if self.is_secret(key) {
+                format!("cannot parse value: {}", e)
+            } else {
+                format!("cannot parse {:?}: {}", raw, e)
+            };
+            self.invalid(key, &raw, message)
+        })
+    }
+
+    /// Get a value parsed into `T`, falling back to `default` if it is missing or invalid
+    pub fn get_or<T>(&self, key: &str, default: T) -> T
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        match self.get_as(key) {
+            Ok(value) => value,
+            Err(ConfigError::Missing { .. }) => default,
+            Err(e) => {
+                tracing::warn!("{}; using default", e);
+                default
+            }
+        }
+    }
+
+    /// Get a duration written in a human-friendly form such as `"30s"` or `"5m"`
+    ///
+    /// See [`parse_duration`] for the accepted formats.
+    pub fn get_duration(&self, key: &str) -> Result<Duration, ConfigError> {
+        let raw = self.require_key(key)?;
+        parse_duration(&raw).map_err(|message| self.invalid(key, &raw, message))
+    }
+
+    /// Deserialize every key under `prefix` into `T`
+    ///
+    /// Keys are matched as `{prefix}.{field}` or `{prefix}_{field}`
+    /// (case-insensitive) and the remainder is lowercased to form the field
+    /// name, so `database.url` and `DATABASE_POOL_SIZE` bind to `url` and
+    /// `pool_size` under prefix `database`. Scalars are parsed from their
+    /// string form and sequences are read as comma-separated lists.
+    ///
+    /// # Example
+    /// ```
+    /// use meshestra::config::ConfigService;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct DatabaseConfig {
+    ///     url: String,
+    ///     pool_size: u32,
+    /// }
+    ///
+    /// let config = ConfigService::default();
+    /// config.set("DATABASE_URL", "postgres://localhost/app");
+    /// config.set("DATABASE_POOL_SIZE", "16");
+    ///
+    /// let database: DatabaseConfig = config.bind("DATABASE").unwrap();
+    /// assert_eq!(database.pool_size, 16);
+    /// ```
+    pub fn bind<T: DeserializeOwned>(&self, prefix: &str) -> Result<T, ConfigError> {
+        let section = self.section(prefix);
+        de::from_pairs(section.clone()).map_err(|e| ConfigError::Bind {
+            prefix: prefix.to_string(),
+            message: self.redact_section(prefix, &section, e.to_string()),
+        })
+    }
+
+    /// Like [`bind`](Self::bind), but reports every missing or invalid key
+    /// at once and then runs the [`Validate`] hook
+    ///
+    /// # Example
+    /// ```
+    /// use meshestra::config::{ConfigError, ConfigService, Validate};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct JwtConfig {
+    ///     secret: String,
+    ///     ttl_secs: u64,
+    /// }
+    ///
+    /// impl Validate for JwtConfig {
+    ///     fn validate(&self) -> Result<(), Vec<String>> {
+    ///         if self.secret.len() < 32 {
+    ///             return Err(vec!["jwt.secret must be at least 32 characters".into()]);
+    ///         }
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let config = ConfigService::default();
+    /// config.set("JWT_TTL_SECS", "soon");
+    ///
+    /// let Err(ConfigError::Validation { issues }) = config.bind_validated::<JwtConfig>("JWT") else {
+    ///     panic!("expected validation error");
+    /// };
+    /// assert_eq!(issues.len(), 2);
+    /// ```
+    pub fn bind_validated<T>(&self, prefix: &str) -> Result<T, ConfigError>
+    where
+        T: DeserializeOwned + Validate,
+    {
+        let section = self.section(prefix);
+        let value = de::from_pairs_collecting::<T>(section.clone()).map_err(|issues| {
+            ConfigError::Validation {
+                issues: issues
+                    .into_iter()
+                    .map(|issue| match issue {
+                        FieldIssue::Missing(field) => {
+                            format!("missing key '{}.{}'", prefix, field)
+                        }
+                        FieldIssue::Invalid { field, message } => {
+                            format!("invalid value for '{}.{}': {}", prefix, field, message)
+                        }
+                        FieldIssue::Other(message) => format!("'{}': {}", prefix, message),
+                    })
+                    .map(|issue| self.redact_section(prefix, &section, issue))
+                    .collect(),
+            }
+        })?;
+
+        value
+            .validate()
+            .map_err(|issues| ConfigError::Validation { issues })?;
+        Ok(value)
+    }
+
+    /// Check that every key in `keys` is set
+    ///
+    /// # Errors
+    /// Returns `ConfigError::Validation` listing all missing keys.
+    pub fn require(&self, keys: &[&str]) -> Result<(), ConfigError> {
+        let issues: Vec<String> = keys
+            .iter()
+            .filter(|key| !self.config.contains_key(**key))
+            .map(|key| format!("missing key '{}'", key))
+            .collect();
+
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Validation { issues })
+        }
+    }
+
+    /// Collect `field -> value` pairs for all keys under `prefix`
+    fn section(&self, prefix: &str) -> BTreeMap<String, String> {
+        let prefix = prefix.to_lowercase();
+        self.config
+            .iter()
+            .filter_map(|entry| {
+                let key = entry.key().to_lowercase();
+                let field = key
+                    .strip_prefix(&prefix)?
+                    .strip_prefix(['.', '_'])
+                    .filter(|field| !field.is_empty())?;
+                Some((field.to_string(), entry.value().clone()))
+            })
+            .collect()
+    }
+
+    fn invalid(&self, key: &str, raw: &str, message: String) -> ConfigError {
+        let message = if self.is_secret(key) && !raw.is_empty() {
+            message.replace(raw, MASK)
+        } else {
+            message
+        };
+        ConfigError::Invalid {
+            key: key.to_string(),
+            message,
+        }
+    }
+
+    /// Mask the values of secret fields of a section in an error message
+    fn redact_section(
+        &self,
+        prefix: &str,
+        section: &BTreeMap<String, String>,
+        message: String,
+    ) -> String {
+        section
+            .iter()
+            .filter(|(field, value)| {
+                !value.is_empty() && self.is_secret(&format!("{}.{}", prefix, field))
+            })
+            .fold(message, |message, (_, value)| {
+                message.replace(value.as_str(), MASK)
+            })
+    }
+
+    fn require_key(&self, key: &str) -> Result<String, ConfigError> {
+        self.get(key).ok_or_else(|| ConfigError::Missing {
+            key: key.to_string(),
+        })
+    }
+}
+
+/// Resolves the `ConfigService` already registered in the container, or
+/// loads a new one from the environment.
+impl Injectable for ConfigService {
+    fn inject(container: &Container) -> crate::error::Result<Self> {
+        Ok(container
+            .resolve::<ConfigService>()
+            .map(|existing| existing.as_ref().clone())
+            .unwrap_or_else(|_| ConfigService::new()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lifecycle::OnApplicationShutdown;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct ServerConfig {
+        host: String,
+        port: u16,
+        debug: bool,
+        #[serde(deserialize_with = "deserialize_duration")]
+        timeout: Duration,
+        tags: Vec<String>,
+        workers: Option<u32>,
+    }
+
+    #[test]
+    fn test_get_as_reports_key_on_parse_failure() {
+        let config = ConfigService::default();
+        config.set("PORT", "80a");
+
+        let err = config.get_as::<u16>("PORT").unwrap_err();
+        assert!(err.to_string().contains("PORT"));
+        assert!(matches!(
+            config.get_as::<u16>("MISSING"),
+            Err(ConfigError::Missing { .. })
+        ));
+        assert_eq!(config.get_or("MISSING", 8080u16), 8080);
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("5m").unwrap(), Duration::from_secs(300));
+        assert_eq!(parse_duration("1h30m").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse_duration("250ms").unwrap(), Duration::from_millis(250));
+        assert_eq!(parse_duration("10").unwrap(), Duration::from_secs(10));
+        assert!(parse_duration("5 parsecs").is_err());
+        assert!(parse_duration("999999999999999d").is_err());
+        assert!(parse_duration("18446744073709551615s1s").is_err());
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct DatabaseConfig {
+        url: String,
+        pool_size: u32,
+        timeout_secs: u64,
+        replica: Option<String>,
+    }
+
+    impl Validate for DatabaseConfig {
+        fn validate(&self) -> Result<(), Vec<String>> {
+            if self.pool_size == 0 {
+                return Err(vec!["database.pool_size must be positive".to_string()]);
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_require_lists_all_missing_keys() {
+        let config = ConfigService::default();
+        config.set("DATABASE_URL", "postgres://localhost/app");
+
+        let Err(ConfigError::Validation { issues }) =
+            config.require(&["DATABASE_URL", "JWT_SECRET", "REDIS_URL"])
+        else {
+            panic!("expected validation error");
+        };
+        assert_eq!(
+            issues,
+            vec!["missing key 'JWT_SECRET'", "missing key 'REDIS_URL'"]
+        );
+    }
+
+    #[test]
+    fn test_bind_validated_aggregates_errors() {
+        let config = ConfigService::default();
+        config.set("database.pool_size", "many");
+
+        let Err(ConfigError::Validation { issues }) =
+            config.bind_validated::<DatabaseConfig>("database")
+        else {
+            panic!("expected validation error");
+        };
+        assert_eq!(issues.len(), 3);
+        assert!(issues[0].contains("database.pool_size"));
+        assert!(issues.iter().any(|i| i == "missing key 'database.url'"));
+        assert!(
+            issues
+                .iter()
+                .any(|i| i == "missing key 'database.timeout_secs'")
+        );
+
+        config.set("database.url", "postgres://localhost/app");
+        config.set("database.timeout_secs", "30");
+        config.set("database.pool_size", "0");
+        let Err(ConfigError::Validation { issues }) =
+            config.bind_validated::<DatabaseConfig>("database")
+        else {
+            panic!("expected validation error");
+        };
+        assert_eq!(issues, vec!["database.pool_size must be positive"]);
+
+        config.set("database.pool_size", "8");
+        assert!(config.bind_validated::<DatabaseConfig>("database").is_ok());
+    }
+
+    #[test]
+    fn test_reload_notifies_only_changed_keys() {
+        let config = ConfigService::default();
+        config.set("log.level", "info");
+        config.set("rate_limit.rps", "100");
+
+        let mut level = config.watch("log.level");
+        let mut rps = config.watch("rate_limit.rps");
+
+        let changed = config
+            .reload_from(&ConfigSource::Values(BTreeMap::from([
+                ("log.level".to_string(), "debug".to_string()),
+                ("rate_limit.rps".to_string(), "100".to_string()),
+            ])))
+            .unwrap();
+
+        assert_eq!(changed, vec!["log.level"]);
+        assert!(level.has_changed().unwrap());
+        assert_eq!(level.borrow_and_update().as_deref(), Some("debug"));
+        assert!(!rps.has_changed().unwrap());
+        assert_eq!(rps.borrow_and_update().as_deref(), Some("100"));
+    }
+
+    #[tokio::test]
+    async fn test_file_watcher_reloads_on_change() {
+        let path = source::write_temp_file("watched.toml", "[features]\nbeta = false\n");
+        let config = ConfigService::builder()
+            .add_toml_file(&path)
+            .build()
+            .unwrap();
+        let mut beta = config.watch("features.beta");
+
+        let mut watcher = config.spawn_file_watcher(&path, Duration::from_millis(10));
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        std::fs::write(&path, "[features]\nbeta = true # rollout\n").unwrap();
+
+        tokio::time::timeout(Duration::from_secs(2), beta.changed())
+            .await
+            .expect("watcher did not pick up the change")
+            .unwrap();
+        assert_eq!(beta.borrow().as_deref(), Some("true"));
+
+        watcher.on_application_shutdown().await.unwrap();
+        assert!(!watcher.is_running());
+    }
+
+    #[test]
+    fn test_secret_values_are_masked() {
+        let config = ConfigService::default();
+        config.mark_secret(&["*dsn"]);
+        config.set("DATABASE_PASSWORD", "hunter2");
+        config.set("sentry.dsn", "https://abc@sentry.io/1");
+        config.set("stripe.api_key", "sk_live_42");
+        config.set("server.port", "8080");
+
+        let dump = config.dump();
+        assert_eq!(dump["DATABASE_PASSWORD"], "***");
+        assert_eq!(dump["sentry.dsn"], "***");
+        assert_eq!(dump["stripe.api_key"], "***");
+        assert_eq!(dump["server.port"], "8080");
+
+        let err = config.get_as::<u32>("stripe.api_key").unwrap_err();
+        assert!(!err.to_string().contains("sk_live_42"));
+        let err = config.get_duration("DATABASE_PASSWORD").unwrap_err();
+        assert!(!err.to_string().contains("hunter2"));
+
+        let secret = config.get_secret("DATABASE_PASSWORD").unwrap();
+        assert_eq!(format!("{:?}", secret), "Secret(***)");
+        assert_eq!(secret.expose(), "hunter2");
+    }
+
+    #[test]
+    fn test_bind_section() {
+        let config = ConfigService::default();
+        config.set("SERVER_HOST", "0.0.0.0");
+        config.set("SERVER_PORT", "3000");
+        config.set("SERVER_DEBUG", "true");
+        config.set("SERVER_TIMEOUT", "5m");
+        config.set("SERVER_TAGS", "api, internal");
+        config.set("OTHER_PORT", "1");
+        config.set("server.workers", "4");
+
+        let server: ServerConfig = config.bind("SERVER").unwrap();
+        assert_eq!(
+            server,
+            ServerConfig {
+                host: "0.0.0.0".to_string(),
+                port: 3000,
+                debug: true,
+                timeout: Duration::from_secs(300),
+                tags: vec!["api".to_string(), "internal".to_string()],
+                workers: Some(4),
+            }
+        );
     }
 }
@@ -0,0 +1,88 @@
+use std::fmt;
+
+/// Key patterns treated as secret unless overridden
+pub const DEFAULT_SECRET_PATTERNS: &[&str] = &["*PASSWORD*", "*SECRET*", "*TOKEN*", "*KEY*"];
+
+/// Replacement shown instead of secret values
+pub(crate) const MASK: &str = "***";
+
+/// A value that must not end up in logs
+///
+/// `Debug` is redacted and there is no `Display` impl, so the inner value
+/// can only be read through [`expose`](Self::expose).
+///
+/// # Example
+/// ```
+/// use meshestra::config::Secret;
+///
+/// let password = Secret::new("hunter2".to_string());
+/// assert_eq!(format!("{:?}", password), "Secret(***)");
+/// assert_eq!(password.expose(), "hunter2");
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct Secret<T>(T);
+
+impl<T> Secret<T> {
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    /// Access the secret value
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secret({})", MASK)
+    }
+}
+
+/// Case-insensitive match of `key` against a glob pattern
+///
+/// `*` matches any run of characters. A pattern without `*` matches any key
+/// containing it.
+pub(crate) fn matches_pattern(pattern: &str, key: &str) -> bool {
+    let pattern = pattern.to_lowercase();
+    let key = key.to_lowercase();
+
+    if !pattern.contains('*') {
+        return key.contains(&pattern);
+    }
+
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let (first, rest) = parts.split_first().expect("split yields at least one part");
+    let Some(mut remaining) = key.strip_prefix(first) else {
+        return false;
+    };
+
+    let (last, middle) = rest.split_last().expect("pattern contains '*'");
+    for part in middle {
+        match remaining.find(part) {
+            Some(index) => remaining = &remaining[index + part.len()..],
+            None => return false,
+        }
+    }
+    remaining.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches_pattern("*PASSWORD*", "database.password"));
+        assert!(matches_pattern("*_KEY", "STRIPE_API_KEY"));
+        assert!(!matches_pattern("*_KEY", "KEY_ROTATION_DAYS"));
+        assert!(matches_pattern("jwt.*", "JWT.signing"));
+        assert!(matches_pattern("a*b*c", "axxbyyc"));
+        assert!(!matches_pattern("a*b*c", "axxcyyb"));
+        assert!(matches_pattern("credential", "aws.credentials"));
+    }
+}
@@ -0,0 +1,166 @@
+use crate::config::ConfigError;
+use std::collections::BTreeMap;
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// A single layer of configuration
+#[derive(Debug, Clone)]
+pub enum ConfigSource {
+    /// A TOML file; nested tables are flattened to dotted keys
+    TomlFile(PathBuf),
+    /// Environment variables, optionally restricted to a prefix
+    Env { prefix: Option<String> },
+    /// Explicit key/value pairs
+    Values(BTreeMap<String, String>),
+}
+
+impl ConfigSource {
+    pub fn toml_file(path: impl Into<PathBuf>) -> Self {
+        Self::TomlFile(path.into())
+    }
+
+    pub fn env(prefix: Option<&str>) -> Self {
+        Self::Env {
+            prefix: prefix.map(str::to_string),
+        }
+    }
+
+    /// Read the key/value pairs of this source
+    ///
+    /// A `{profile}` placeholder in a file path is replaced with `profile`.
+    /// Returns `None` if the source is a file that does not exist.
+    pub(crate) fn load(
+        &self,
+        profile: Option<&str>,
+    ) -> Result<Option<BTreeMap<String, String>>, ConfigError> {
+        match self {
+            ConfigSource::TomlFile(path) => {
+                let path = resolve_path(path, profile);
+                let values = load_toml_file(&path)?;
+                if values.is_none() {
+                    tracing::debug!("Config file {} not found, skipping", path.display());
+                }
+                Ok(values)
+            }
+            ConfigSource::Env { prefix } => Ok(Some(env_values(env::vars(), prefix.as_deref()))),
+            ConfigSource::Values(values) => Ok(Some(values.clone())),
+        }
+    }
+}
+
+pub(crate) fn resolve_path(path: &Path, profile: Option<&str>) -> PathBuf {
+    match (profile, path.to_str()) {
+        (Some(profile), Some(raw)) if raw.contains("{profile}") => {
+            PathBuf::from(raw.replace("{profile}", profile))
+        }
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Load and flatten a TOML file, returning `None` if it does not exist
+fn load_toml_file(path: &Path) -> Result<Option<BTreeMap<String, String>>, ConfigError> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(ConfigError::Source {
+                path: path.display().to_string(),
+                message: e.to_string(),
+            });
+        }
+    };
+
+    let table: toml::Table =
+        contents
+            .parse()
+            .map_err(|e: toml::de::Error| ConfigError::Source {
+                path: path.display().to_string(),
+                message: e.to_string(),
+            })?;
+
+    let mut values = BTreeMap::new();
+    flatten_table("", &table, &mut values);
+    Ok(Some(values))
+}
+
+fn flatten_table(prefix: &str, table: &toml::Table, out: &mut BTreeMap<String, String>) {
+    for (key, value) in table {
+        let key = if prefix.is_empty() {
+            key.to_lowercase()
+        } else {
+            format!("{}.{}", prefix, key.to_lowercase())
+        };
+
+        match value {
+            toml::Value::Table(nested) => flatten_table(&key, nested, out),
+            other => {
+                out.insert(key, scalar_to_string(other));
+            }
+        }
+    }
+}
+
+fn scalar_to_string(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(s) => s.clone(),
+        toml::Value::Array(items) => items
+            .iter()
+            .map(scalar_to_string)
+            .collect::<Vec<_>>()
+            .join(","),
+        other => other.to_string(),
+    }
+}
+
+fn env_values(
+    vars: impl IntoIterator<Item = (String, String)>,
+    prefix: Option<&str>,
+) -> BTreeMap<String, String> {
+    let Some(prefix) = prefix else {
+        return vars.into_iter().collect();
+    };
+
+    let prefix = format!("{}_", prefix.to_uppercase());
+    vars.into_iter()
+        .filter_map(|(key, value)| {
+            let rest = key.strip_prefix(&prefix)?;
+            (!rest.is_empty()).then(|| (rest.to_lowercase().replace("__", "."), value))
+        })
+        .collect()
+}
+
+/// Write `contents` to a per-process temporary file for config tests
+#[cfg(test)]
+pub(crate) fn write_temp_file(name: &str, contents: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("meshestra-config-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_toml_is_an_error() {
+        let path = write_temp_file("broken.toml", "[database\npool_size = ");
+        let result = ConfigSource::toml_file(&path).load(None);
+        assert!(matches!(result, Err(ConfigError::Source { .. })));
+    }
+
+    #[test]
+    fn test_env_prefix_maps_to_dotted_keys() {
+        let vars = vec![
+            ("APP_DATABASE__POOL_SIZE".to_string(), "8".to_string()),
+            ("APP_PORT".to_string(), "3000".to_string()),
+            ("OTHER_PORT".to_string(), "1".to_string()),
+        ];
+        let values = env_values(vars, Some("app"));
+
+        assert_eq!(values.get("database.pool_size").unwrap(), "8");
+        assert_eq!(values.get("port").unwrap(), "3000");
+        assert_eq!(values.len(), 2);
+    }
+}
@@ -0,0 +1,111 @@
+use crate::config::source::resolve_path;
+use crate::config::{ConfigService, ConfigSource};
+use crate::lifecycle::{LifecycleError, OnApplicationShutdown};
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tokio::task::JoinHandle;
+
+/// Handle to a background task reloading a config file when it changes
+///
+/// Created by [`ConfigService::spawn_file_watcher`]. Register it with
+/// `ApplicationBuilder::on_shutdown` so polling stops with the application.
+pub struct ConfigWatcher {
+    path: PathBuf,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl ConfigWatcher {
+    /// The watched file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.handle
+            .as_ref()
+            .is_some_and(|handle| !handle.is_finished())
+    }
+
+    /// Stop polling the file
+    pub fn stop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            handle.abort();
+            tracing::debug!("Stopped watching config file {}", self.path.display());
+        }
+    }
+}
+
+#[async_trait]
+impl OnApplicationShutdown for ConfigWatcher {
+    async fn on_application_shutdown(&mut self) -> Result<(), LifecycleError> {
+        self.stop();
+        Ok(())
+    }
+}
+
+impl ConfigService {
+    /// Poll a TOML file every `interval` and reload it when it changes
+    ///
+    /// Changes are detected by modification time and size. Reload errors
+    /// (e.g. a half-written file) are logged and the previous values kept.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// let watcher = config.spawn_file_watcher("config/runtime.toml", Duration::from_secs(5));
+    ///
+    /// let app = Application::builder()
+    ///     .container(container)
+    ///     .on_shutdown(Arc::new(RwLock::new(watcher)), "ConfigWatcher")
+    ///     .build()
+    ///     .await?;
+    /// ```
+    pub fn spawn_file_watcher(
+        &self,
+        path: impl Into<PathBuf>,
+        interval: Duration,
+    ) -> ConfigWatcher {
+        let path = path.into();
+        let resolved = resolve_path(&path, self.profile());
+        let source = ConfigSource::TomlFile(path);
+        let config = self.clone();
+        let watched = resolved.clone();
+
+        let handle = tokio::spawn(async move {
+            let mut last_seen = fingerprint(&watched);
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+
+            loop {
+                ticker.tick().await;
+                let current = fingerprint(&watched);
+                if current == last_seen {
+                    continue;
+                }
+                last_seen = current;
+
+                match config.reload_from(&source) {
+                    Ok(changed) if !changed.is_empty() => {
+                        tracing::info!(
+                            "Reloaded {} ({} keys changed)",
+                            watched.display(),
+                            changed.len()
+                        );
+                    }
+                    Ok(_) => {}
+                    Err(e) => tracing::warn!("Failed to reload config: {}", e),
+                }
+            }
+        });
+
+        ConfigWatcher {
+            path: resolved,
+            handle: Some(handle),
+        }
+    }
+}
+
+fn fingerprint(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
@@ -0,0 +1,244 @@
+//! Runtime side of the `#[header]`, `#[cookie]`, `#[host_param]` and `#[ip]`
+//! parameter attributes
+//!
+//! `#[routes]` calls these from generated handlers; a required value the
+//! request doesn't carry, or one that doesn't parse, is answered with a 400
+//! naming the header or cookie.
+
+use crate::di::Container;
+use crate::exception::HttpException;
+use axum::extract::ConnectInfo;
+use axum::http::header::{COOKIE, HOST};
+use axum::http::request::Parts;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+/// How `#[ip]` finds the client's address
+///
+/// By default it's the peer address from `ConnectInfo<SocketAddr>`. Behind
+/// a proxy, set `trust_forwarded_for` (or call
+/// `ApplicationBuilder::trust_forwarded_for`) to use the leftmost
+/// `X-Forwarded-For` address instead. Only do so when the proxy overwrites
+/// the header, as clients can send any value.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ClientIpConfig {
+    pub trust_forwarded_for: bool,
+}
+
+/// Why a value couldn't be handed to the handler
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueRejection {
+    /// The request doesn't carry it
+    Missing,
+    /// It doesn't parse as the parameter's type
+    Invalid,
+}
+
+/// A type a header, cookie or host value parses into
+pub trait FromValue: Sized {
+    fn from_value(raw: &str) -> Option<Self>;
+}
+
+macro_rules! from_str_value {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl FromValue for $ty {
+                fn from_value(raw: &str) -> Option<Self> {
+                    raw.trim().parse().ok()
+                }
+            }
+        )*
+    };
+}
+
+from_str_value!(
+    bool, char, i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64, IpAddr,
+    Ipv4Addr, Ipv6Addr, SocketAddr,
+);
+
+#[cfg(feature = "uuid")]
+from_str_value!(uuid::Uuid);
+
+impl FromValue for String {
+    fn from_value(raw: &str) -> Option<Self> {
+        Some(raw.to_string())
+    }
+}
+
+/// The type of an extracted parameter: a [`FromValue`] is required, an
+/// `Option` of one is `None` when the request doesn't carry it
+pub trait FromMaybeValue: Sized {
+    fn from_maybe_value(raw: Option<&str>) -> Result<Self, ValueRejection>;
+}
+
+impl<T: FromValue> FromMaybeValue for T {
+    fn from_maybe_value(raw: Option<&str>) -> Result<Self, ValueRejection> {
+        T::from_value(raw.ok_or(ValueRejection::Missing)?).ok_or(ValueRejection::Invalid)
+    }
+}
+
+impl<T: FromValue> FromMaybeValue for Option<T> {
+    fn from_maybe_value(raw: Option<&str>) -> Result<Self, ValueRejection> {
+        raw.map(|raw| T::from_value(raw).ok_or(ValueRejection::Invalid))
+            .transpose()
+    }
+}
+
+fn bad_request(rejection: ValueRejection, source: &str) -> HttpException {
+    match rejection {
+        ValueRejection::Missing => HttpException::bad_request(format!("missing {}", source)),
+        ValueRejection::Invalid => HttpException::bad_request(format!("invalid {}", source)),
+    }
+}
+
+/// The `name` header, for `#[header("name")]`
+///
+/// A value that isn't visible ASCII counts as invalid.
+pub fn header<T: FromMaybeValue>(parts: &Parts, name: &str) -> Result<T, HttpException> {
+    let source = format!("header {:?}", name);
+    let raw = match parts.headers.get(name) {
+        Some(value) => Some(
+            value
+                .to_str()
+                .map_err(|_| bad_request(ValueRejection::Invalid, &source))?,
+        ),
+        None => None,
+    };
+    T::from_maybe_value(raw).map_err(|rejection| bad_request(rejection, &source))
+}
+
+/// The `name` cookie from the `Cookie` headers, for `#[cookie("name")]`
+pub fn cookie<T: FromMaybeValue>(parts: &Parts, name: &str) -> Result<T, HttpException> {
+    let raw = parts
+        .headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value.trim_matches('"'));
+    T::from_maybe_value(raw)
+        .map_err(|rejection| bad_request(rejection, &format!("cookie {:?}", name)))
+}
+
+/// The `Host` header, or the URI's authority for HTTP/2, for `#[host_param]`
+pub fn host<T: FromMaybeValue>(parts: &Parts) -> Result<T, HttpException> {
+    let raw = parts
+        .headers
+        .get(HOST)
+        .and_then(|value| value.to_str().ok())
+        .or_else(|| parts.uri.authority().map(|authority| authority.as_str()));
+    T::from_maybe_value(raw).map_err(|rejection| bad_request(rejection, "header \"Host\""))
+}
+
+/// The client's address, for `#[ip]`; see [`ClientIpConfig`]
+///
+/// Without `ConnectInfo` (the router wasn't served with
+/// `into_make_service_with_connect_info`) or a trusted `X-Forwarded-For`,
+/// a required address is a server error rather than the client's.
+pub fn client_ip<T: FromMaybeValue>(
+    parts: &Parts,
+    container: &Container,
+) -> Result<T, HttpException> {
+    let trust_forwarded_for = container
+        .resolve::<ClientIpConfig>()
+        .is_ok_and(|config| config.trust_forwarded_for);
+    let forwarded = trust_forwarded_for
+        .then(|| parts.headers.get("x-forwarded-for")?.to_str().ok())
+        .flatten()
+        .and_then(|value| value.split(',').next())
+        .and_then(|client| client.trim().parse::<IpAddr>().ok());
+    let ip = forwarded.or_else(|| {
+        parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip())
+    });
+    let raw = ip.map(|ip| ip.to_string());
+    T::from_maybe_value(raw.as_deref()).map_err(|rejection| match rejection {
+        ValueRejection::Missing => HttpException::internal("the client address is not available"),
+        ValueRejection::Invalid => bad_request(rejection, "client address"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Request;
+
+    fn parts(request: Request<()>) -> Parts {
+        request.into_parts().0
+    }
+
+    #[test]
+    fn header_is_required_unless_optional() {
+        let parts = parts(
+            Request::get("/")
+                .header("X-Api-Key", "secret")
+                .body(())
+                .unwrap(),
+        );
+
+        assert_eq!(header::<String>(&parts, "x-api-key").unwrap(), "secret");
+        assert_eq!(header::<Option<String>>(&parts, "X-Trace").unwrap(), None);
+        let missing = header::<String>(&parts, "X-Trace").unwrap_err();
+        assert_eq!(missing.message(), "missing header \"X-Trace\"");
+        let invalid = header::<u32>(&parts, "X-Api-Key").unwrap_err();
+        assert_eq!(invalid.message(), "invalid header \"X-Api-Key\"");
+    }
+
+    #[test]
+    fn cookie_is_found_among_several() {
+        let parts = parts(
+            Request::get("/")
+                .header(COOKIE, "theme=dark; session=\"abc\"")
+                .header(COOKIE, "lang=en")
+                .body(())
+                .unwrap(),
+        );
+
+        assert_eq!(cookie::<String>(&parts, "session").unwrap(), "abc");
+        assert_eq!(
+            cookie::<Option<String>>(&parts, "lang").unwrap().as_deref(),
+            Some("en")
+        );
+        assert_eq!(
+            cookie::<String>(&parts, "sid").unwrap_err().message(),
+            "missing cookie \"sid\""
+        );
+    }
+
+    #[test]
+    fn host_falls_back_to_the_authority() {
+        let parts = parts(Request::get("https://example.com/").body(()).unwrap());
+
+        assert_eq!(host::<String>(&parts).unwrap(), "example.com");
+    }
+
+    #[test]
+    fn forwarded_for_is_only_used_when_trusted() {
+        let mut parts = parts(
+            Request::get("/")
+                .header("X-Forwarded-For", "203.0.113.7, 10.0.0.1")
+                .body(())
+                .unwrap(),
+        );
+        parts
+            .extensions
+            .insert(ConnectInfo(SocketAddr::from(([10, 0, 0, 1], 4000))));
+        let mut container = Container::new();
+
+        assert_eq!(
+            client_ip::<IpAddr>(&parts, &container).unwrap(),
+            IpAddr::from([10, 0, 0, 1])
+        );
+
+        container.register(ClientIpConfig {
+            trust_forwarded_for: true,
+        });
+        assert_eq!(
+            client_ip::<IpAddr>(&parts, &container).unwrap(),
+            IpAddr::from([203, 0, 113, 7])
+        );
+    }
+}
@@ -1,7 +1,17 @@
 // Controller functionality is primarily provided through macros:
 // - #[controller(path = "...")] for defining controllers
-// - #[get], #[post], #[put], #[delete], #[patch] for defining routes
+// - #[get], #[post], #[put], #[delete], #[patch], #[head], #[options] for defining routes
 //
 // The macros generate:
 // 1. Injectable trait implementation for DI
 // 2. router() method for Axum integration
+
+pub mod extract;
+#[cfg(feature = "multipart")]
+pub mod multipart;
+pub mod response;
+pub mod routing;
+pub mod routing_options;
+pub mod span;
+pub mod sse;
+pub mod versioning;
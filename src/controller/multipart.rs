@@ -0,0 +1,184 @@
+//! Runtime side of the `#[multipart]` parameter attribute
+//!
+//! A `#[multipart]` parameter is either axum's raw [`Multipart`] stream or
+//! a type implementing [`FromMultipart`], usually derived:
+//!
+//! ```ignore
+//! #[derive(FromMultipart)]
+//! pub struct AvatarUpload {
+//!     user_id: String,
+//!     #[part(limit = 1024 * 1024)]
+//!     avatar: UploadedFile,
+//!     caption: Option<String>,
+//! }
+//! ```
+//!
+//! Fields are matched to parts by name (`#[part(name = "...")]` renames
+//! them). A missing required part is a 400, one over its `limit` a 413, and
+//! a request that isn't `multipart/form-data` a 415. The whole body is
+//! still bounded by axum's `DefaultBodyLimit`.
+
+use crate::common::StatusCode;
+use crate::exception::HttpException;
+use async_trait::async_trait;
+use axum::body::{Body, Bytes};
+use axum::extract::multipart::{Field, MultipartError};
+use axum::extract::{FromRequest, Multipart};
+use axum::http::Request;
+use axum::http::header::CONTENT_TYPE;
+use axum::response::{IntoResponse, Response};
+
+/// A value built from a whole `multipart/form-data` body
+#[async_trait]
+pub trait FromMultipart: Sized {
+    async fn from_multipart(multipart: Multipart) -> Result<Self, HttpException>;
+}
+
+/// The raw stream, for handlers reading the parts themselves
+#[async_trait]
+impl FromMultipart for Multipart {
+    async fn from_multipart(multipart: Multipart) -> Result<Self, HttpException> {
+        Ok(multipart)
+    }
+}
+
+/// A file part, with the metadata the client sent along
+#[derive(Debug, Clone)]
+pub struct UploadedFile {
+    pub file_name: Option<String>,
+    pub content_type: Option<String>,
+    pub bytes: Bytes,
+}
+
+/// A type a single part is read into: `String`, `Vec<u8>`, `Bytes` or
+/// [`UploadedFile`]
+#[async_trait]
+pub trait FromPart: Sized {
+    /// Read `field`, refusing more than `limit` bytes
+    async fn from_part(field: Field<'_>, limit: Option<usize>) -> Result<Self, HttpException>;
+}
+
+#[async_trait]
+impl FromPart for Bytes {
+    async fn from_part(field: Field<'_>, limit: Option<usize>) -> Result<Self, HttpException> {
+        read_part(field, limit).await
+    }
+}
+
+#[async_trait]
+impl FromPart for Vec<u8> {
+    async fn from_part(field: Field<'_>, limit: Option<usize>) -> Result<Self, HttpException> {
+        Ok(read_part(field, limit).await?.to_vec())
+    }
+}
+
+#[async_trait]
+impl FromPart for String {
+    async fn from_part(field: Field<'_>, limit: Option<usize>) -> Result<Self, HttpException> {
+        let name = field.name().unwrap_or_default().to_string();
+        String::from_utf8(read_part(field, limit).await?.to_vec())
+            .map_err(|_| HttpException::bad_request(format!("part {:?} is not UTF-8 text", name)))
+    }
+}
+
+#[async_trait]
+impl FromPart for UploadedFile {
+    async fn from_part(field: Field<'_>, limit: Option<usize>) -> Result<Self, HttpException> {
+        let file_name = field.file_name().map(str::to_string);
+        let content_type = field.content_type().map(str::to_string);
+        Ok(Self {
+            file_name,
+            content_type,
+            bytes: read_part(field, limit).await?,
+        })
+    }
+}
+
+/// The type of a `FromMultipart` field: a [`FromPart`] is required, an
+/// `Option` of one is `None` when the request has no such part
+pub trait FromMaybePart: Sized {
+    type Part: FromPart;
+
+    fn from_maybe_part(part: Option<Self::Part>, name: &str) -> Result<Self, HttpException>;
+}
+
+impl<T: FromPart> FromMaybePart for T {
+    type Part = T;
+
+    fn from_maybe_part(part: Option<T>, name: &str) -> Result<Self, HttpException> {
+        part.ok_or_else(|| HttpException::bad_request(format!("missing part {:?}", name)))
+    }
+}
+
+impl<T: FromPart> FromMaybePart for Option<T> {
+    type Part = T;
+
+    fn from_maybe_part(part: Option<T>, _name: &str) -> Result<Self, HttpException> {
+        Ok(part)
+    }
+}
+
+/// Collect a part's chunks, stopping as soon as it exceeds `limit`
+async fn read_part(mut field: Field<'_>, limit: Option<usize>) -> Result<Bytes, HttpException> {
+    let Some(limit) = limit else {
+        return field.bytes().await.map_err(malformed);
+    };
+    let mut bytes = Vec::new();
+    while let Some(chunk) = field.chunk().await.map_err(malformed)? {
+        if bytes.len() + chunk.len() > limit {
+            return Err(HttpException::new(
+                StatusCode::PayloadTooLarge,
+                format!(
+                    "part {:?} exceeds {} bytes",
+                    field.name().unwrap_or_default(),
+                    limit
+                ),
+            ));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(bytes.into())
+}
+
+/// The next part of `multipart`, for `#[derive(FromMultipart)]`
+#[doc(hidden)]
+pub async fn next_part(multipart: &mut Multipart) -> Result<Option<Field<'_>>, HttpException> {
+    multipart.next_field().await.map_err(malformed)
+}
+
+fn malformed(error: MultipartError) -> HttpException {
+    HttpException::bad_request(error.body_text())
+}
+
+/// Read `request`'s body into `T`, for `#[multipart]`
+///
+/// Requests that aren't `multipart/form-data` are answered with a 415.
+pub async fn from_request<T: FromMultipart>(request: Request<Body>) -> Result<T, Response> {
+    let content_type = request
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    let essence = content_type.split(';').next().unwrap_or_default().trim();
+    if !essence.eq_ignore_ascii_case("multipart/form-data") {
+        let received = if essence.is_empty() {
+            "no content type".to_string()
+        } else {
+            format!("{:?}", essence)
+        };
+        return Err(HttpException::new(
+            StatusCode::UnsupportedMediaType,
+            format!(
+                "expected `Content-Type: multipart/form-data`, got {}",
+                received
+            ),
+        )
+        .into_response());
+    }
+    let multipart = Multipart::from_request(request, &())
+        .await
+        .map_err(IntoResponse::into_response)?;
+    T::from_multipart(multipart)
+        .await
+        .map_err(IntoResponse::into_response)
+}
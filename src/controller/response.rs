@@ -0,0 +1,74 @@
+//! Runtime side of the `#[status]`, `#[response_header]` and `#[produces]`
+//! route attributes
+//!
+//! `#[routes]` applies these to the handler's response, before interceptors
+//! and exception filters see it. The macro checks the literals (and
+//! lowercases header names), so the conversions here can't fail for
+//! generated code.
+
+use axum::http::StatusCode;
+use axum::http::header::{CONTENT_TYPE, HeaderName, HeaderValue};
+use axum::response::Response;
+
+/// Replace the default `200 OK` with `status`, for `#[status(201)]`
+///
+/// Any other status was set on purpose and wins: error statuses, an
+/// `ApiResponse::with_status`, or a `(StatusCode, body)` tuple.
+pub fn with_status(mut response: Response, status: u16) -> Response {
+    if response.status() == StatusCode::OK {
+        *response.status_mut() = StatusCode::from_u16(status).unwrap_or(StatusCode::OK);
+    }
+    response
+}
+
+/// Append a header, for `#[response_header("Cache-Control", "no-store")]`
+///
+/// `name` must be lowercase.
+pub fn with_header(mut response: Response, name: &'static str, value: &'static str) -> Response {
+    response.headers_mut().append(
+        HeaderName::from_static(name),
+        HeaderValue::from_static(value),
+    );
+    response
+}
+
+/// Label a text or byte body as `content_type`, for `#[produces("text/csv")]`
+///
+/// Only the content types axum gives `String`s and `Vec<u8>`s are
+/// replaced, so JSON bodies, error bodies included, keep theirs.
+pub fn produces(mut response: Response, content_type: &'static str) -> Response {
+    let is_plain = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|essence| matches!(essence.trim(), "text/plain" | "application/octet-stream"));
+    if is_plain {
+        response
+            .headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::response::IntoResponse;
+
+    #[test]
+    fn status_only_replaces_the_default() {
+        assert_eq!(with_status("created".into_response(), 201).status(), 201);
+        let rejected = (StatusCode::BAD_REQUEST, "no").into_response();
+        assert_eq!(with_status(rejected, 201).status(), 400);
+    }
+
+    #[test]
+    fn produces_leaves_json_alone() {
+        let csv = produces("a,b\n".into_response(), "text/csv");
+        assert_eq!(csv.headers()[CONTENT_TYPE], "text/csv");
+
+        let json = produces(axum::Json(1).into_response(), "text/csv");
+        assert_eq!(json.headers()[CONTENT_TYPE], "application/json");
+    }
+}
@@ -0,0 +1,332 @@
+//! Responses `#[routes]`-generated routers give outside the handlers, and
+//! mounting those routers for `#[module]`
+
+use crate::common::{ApiResponse, StatusCode};
+use crate::di::HasContainer;
+use axum::Router;
+use axum::body::Body;
+use axum::extract::MatchedPath;
+use axum::http::header::ALLOW;
+use axum::http::{HeaderValue, Method, Request};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{MethodRouter, Route};
+use std::convert::Infallible;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, OnceLock};
+use tower::{Layer, Service};
+
+/// A 405 for a method the path has no route for, listing the ones it has
+/// in `Allow`
+pub fn method_not_allowed(method: &Method, allow: &str) -> Response {
+    let mut response = ApiResponse::<()>::error(
+        StatusCode::MethodNotAllowed,
+        format!("{} is not allowed here", method),
+    )
+    .into_response();
+    if let Ok(allow) = HeaderValue::from_str(allow) {
+        response.headers_mut().insert(ALLOW, allow);
+    }
+    response
+}
+
+/// The GET response with its body dropped, for HEAD requests
+pub fn without_body(response: Response) -> Response {
+    let (parts, _) = response.into_parts();
+    Response::from_parts(parts, Body::empty())
+}
+
+/// The layer of a `#[middleware(...)]`, as is; the bounds are those of
+/// `Router::route_layer`, checked where the attribute is written
+#[doc(hidden)]
+pub fn middleware<L>(layer: L) -> L
+where
+    L: Layer<Route> + Clone + Send + Sync + 'static,
+    L::Service: Service<Request<Body>> + Clone + Send + Sync + 'static,
+    <L::Service as Service<Request<Body>>>::Response: IntoResponse + 'static,
+    <L::Service as Service<Request<Body>>>::Error: Into<Infallible> + 'static,
+    <L::Service as Service<Request<Body>>>::Future: Send + 'static,
+{
+    layer
+}
+
+/// A route a controller serves, as listed by its generated `route_table()`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteDescriptor {
+    /// `"GET"`, `"POST"`, ...
+    pub method: &'static str,
+    /// The full path, base path included
+    pub path: String,
+    /// `UserController::get_user`
+    pub handler: &'static str,
+    /// The guards the route runs, controller-level ones first
+    pub guards: Vec<&'static str>,
+    /// The aspects wrapping the handler, controller-level ones first
+    pub aspects: Vec<&'static str>,
+    /// The API version, `v1`, the route belongs to, if any
+    pub version: Option<&'static str>,
+    /// The controller's `#[cors(...)]`, if any
+    pub cors: Option<&'static crate::cors::CorsOverride>,
+    /// `#[csrf_exempt]`: skipped by the CSRF check
+    pub csrf_exempt: bool,
+    /// What the OpenAPI document says of the route; `None` for
+    /// `#[api(hidden)]` and `#[ws]` routes, and those not generated by
+    /// `#[routes]`
+    pub api: Option<crate::openapi::ApiOperation>,
+}
+
+impl RouteDescriptor {
+    /// Whether `other` would be routed the same requests: same method and
+    /// version, and the same path up to the names of its parameters
+    pub fn conflicts_with(&self, other: &RouteDescriptor) -> bool {
+        self.method == other.method
+            && self.version == other.version
+            && route_shape(&self.path) == route_shape(&other.path)
+    }
+}
+
+/// Where a `#[routes]` handler notes the route it serves, for layers
+/// around the router
+///
+/// Layers added to the router see the route template in [`MatchedPath`];
+/// those around it don't, as routing hasn't happened yet. They insert an
+/// empty `MatchedRoute` into the request extensions and read it once the
+/// response is back, as the `metrics` feature's `MetricsInterceptor` does.
+#[derive(Clone, Default)]
+pub struct MatchedRoute(Arc<OnceLock<HandledRoute>>);
+
+/// The route that handled a request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HandledRoute {
+    /// The full route template, `/users/{id}`
+    pub route: String,
+    /// `UserController`
+    pub controller: &'static str,
+    /// `get_user`
+    pub handler: &'static str,
+}
+
+impl MatchedRoute {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The route, once the request was handled by one
+    pub fn get(&self) -> Option<&HandledRoute> {
+        self.0.get()
+    }
+
+    /// Note in the `MatchedRoute` of `request`, if it has one, that
+    /// `controller`'s `handler` serves it; generated `#[routes]` handlers
+    /// call this
+    #[doc(hidden)]
+    pub fn record(request: &Request<Body>, controller: &'static str, handler: &'static str) {
+        if let Some(slot) = request.extensions().get::<MatchedRoute>() {
+            let route = request
+                .extensions()
+                .get::<MatchedPath>()
+                .map_or(request.uri().path(), MatchedPath::as_str);
+            let _ = slot.0.set(HandledRoute {
+                route: route.to_string(),
+                controller,
+                handler,
+            });
+        }
+    }
+}
+
+/// `path` with its parameter names blanked, `/users/{}/{*}`
+fn route_shape(path: &str) -> String {
+    let mut shape = String::with_capacity(path.len());
+    let mut rest = path;
+    while let Some(open) = rest.find('{') {
+        let Some(close) = rest[open..].find('}') else {
+            break;
+        };
+        let catch_all = rest[open + 1..].starts_with('*');
+        shape.push_str(&rest[..open]);
+        shape.push_str(if catch_all { "{*}" } else { "{}" });
+        rest = &rest[open + close + 1..];
+    }
+    shape.push_str(rest);
+    shape
+}
+
+/// The path a route at `path` has once its controller is mounted at
+/// `base_path`, as [`mount`] does it
+pub fn join_path(base_path: &str, path: &str) -> String {
+    let base_path = base_path.trim_end_matches('/');
+    match path {
+        "" | "/" if base_path.is_empty() => "/".to_string(),
+        "" | "/" => base_path.to_string(),
+        path => format!("{}{}", base_path, path),
+    }
+}
+
+/// `routes` as an aligned table, one route per line
+pub fn format_route_table(routes: &[RouteDescriptor]) -> String {
+    let method_width = routes.iter().map(|r| r.method.len()).max().unwrap_or(0);
+    let path_width = routes.iter().map(|r| r.path.len()).max().unwrap_or(0);
+    routes
+        .iter()
+        .map(|route| {
+            let mut line = format!(
+                "{:method_width$}  {:path_width$}  {}",
+                route.method, route.path, route.handler
+            );
+            if !route.guards.is_empty() {
+                line.push_str(&format!("  guards: {}", route.guards.join(", ")));
+            }
+            if !route.aspects.is_empty() {
+                line.push_str(&format!("  aspects: {}", route.aspects.join(", ")));
+            }
+            if let Some(version) = route.version {
+                line.push_str(&format!("  version: {}", version));
+            }
+            line.trim_end().to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The routing half of a `#[controller]`, implemented by `#[routes]`, for
+/// code generic over controllers such as
+/// [`DynamicModuleBuilder::controller`](crate::module::DynamicModuleBuilder::controller)
+pub trait Controller {
+    /// Where the routes are nested
+    fn base_path() -> &'static str;
+
+    /// The routes, with their full paths
+    fn route_table() -> Vec<RouteDescriptor>;
+
+    /// A router per API version the routes belong to
+    fn versioned_routers<S>(controller: Arc<Self>) -> Vec<(Option<&'static str>, Router<S>)>
+    where
+        S: Clone + Send + Sync + HasContainer + 'static;
+}
+
+/// Nest a controller's router at its base path; one at `/` is merged, as
+/// axum doesn't nest at the root
+pub fn mount<S>(router: Router<S>, base_path: &str, controller: Router<S>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    match base_path.trim_end_matches('/') {
+        "" => router.merge(controller),
+        path => router.nest(path, controller),
+    }
+}
+
+/// `router.route(path, method_router)`, for `#[routes]`; if axum rejects
+/// the route, the panic names the handlers as well as the path
+#[doc(hidden)]
+pub fn route<S>(
+    router: Router<S>,
+    path: &str,
+    handlers: &str,
+    method_router: MethodRouter<S>,
+) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    panic::catch_unwind(AssertUnwindSafe(|| router.route(path, method_router))).unwrap_or_else(
+        |payload| {
+            let reason = payload
+                .downcast_ref::<String>()
+                .map(String::as_str)
+                .or_else(|| payload.downcast_ref::<&str>().copied())
+                .unwrap_or("the route is invalid");
+            panic!("can't route `{}` to {}: {}", path, handlers, reason)
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Request;
+    use axum::routing::get;
+    use tower::ServiceExt;
+
+    async fn status(router: Router, uri: &str) -> u16 {
+        let request = Request::get(uri).body(Body::empty()).unwrap();
+        router.oneshot(request).await.unwrap().status().as_u16()
+    }
+
+    fn route(method: &'static str, path: &str) -> RouteDescriptor {
+        RouteDescriptor {
+            method,
+            path: path.to_string(),
+            handler: "UserController::show",
+            guards: vec!["AuthGuard"],
+            aspects: Vec::new(),
+            version: None,
+            cors: None,
+            csrf_exempt: false,
+            api: None,
+        }
+    }
+
+    #[test]
+    fn paths_join_like_mount_nests() {
+        assert_eq!(join_path("/users", "/"), "/users");
+        assert_eq!(join_path("/users/", "/{id}"), "/users/{id}");
+        assert_eq!(join_path("/", ""), "/");
+        assert_eq!(join_path("/", "/health"), "/health");
+    }
+
+    #[test]
+    fn parameter_names_dont_tell_routes_apart() {
+        let show = route("GET", "/users/{id}");
+
+        assert!(show.conflicts_with(&route("GET", "/users/{name}")));
+        assert!(!show.conflicts_with(&route("DELETE", "/users/{id}")));
+        assert!(!show.conflicts_with(&route("GET", "/users/{*rest}")));
+        let v2 = RouteDescriptor {
+            version: Some("v2"),
+            ..route("GET", "/users/{id}")
+        };
+        assert!(!show.conflicts_with(&v2));
+    }
+
+    #[test]
+    fn route_table_is_aligned() {
+        let table = format_route_table(&[route("GET", "/users/{id}"), route("DELETE", "/")]);
+
+        assert_eq!(
+            table,
+            "GET     /users/{id}  UserController::show  guards: AuthGuard\n\
+             DELETE  /            UserController::show  guards: AuthGuard"
+        );
+    }
+
+    #[tokio::test]
+    async fn controllers_at_the_root_are_merged() {
+        let health = Router::new().route("/health", get(|| async {}));
+        let users = Router::new().route("/{id}", get(|| async {}));
+        let router = mount(mount(Router::new(), "/", health), "/users/", users);
+
+        assert_eq!(status(router.clone(), "/health").await, 200);
+        assert_eq!(status(router, "/users/7").await, 200);
+    }
+
+    #[tokio::test]
+    async fn handlers_fill_the_matched_route_of_layers_around_the_router() {
+        let users = Router::new().route(
+            "/{id}",
+            get(|request: Request<Body>| async move {
+                MatchedRoute::record(&request, "UserController", "show");
+            }),
+        );
+        let router = mount(Router::new(), "/users", users);
+        let slot = MatchedRoute::new();
+        let mut request = Request::get("/users/7").body(Body::empty()).unwrap();
+        request.extensions_mut().insert(slot.clone());
+
+        router.oneshot(request).await.unwrap();
+
+        let handled = slot.get().unwrap();
+        assert_eq!(handled.route, "/users/{id}");
+        assert_eq!(handled.controller, "UserController");
+        assert_eq!(handled.handler, "show");
+    }
+}
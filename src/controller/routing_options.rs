@@ -0,0 +1,232 @@
+//! Lenient path matching: trailing slashes and letter case
+//!
+//! A request no route matches is compared with the route table once more,
+//! ignoring what [`RoutingOptions`] says to, and handed to the route it
+//! matches, or redirected there. The defaults match paths exactly, as axum
+//! does.
+//!
+//! ```ignore
+//! let app = Application::builder()
+//!     .module::<AppModule>()
+//!     .routing_options(RoutingOptions {
+//!         trailing_slash: TrailingSlash::Redirect308,
+//!         case_insensitive: true,
+//!     })
+//!     .build()
+//!     .await?;
+//! ```
+
+use crate::controller::routing::RouteDescriptor;
+use crate::controller::versioning::VersioningConfig;
+use axum::Router;
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::header::LOCATION;
+use axum::http::{HeaderValue, Request, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use std::sync::{Arc, OnceLock};
+use tower::ServiceExt;
+
+/// What a path differing from a route's by a trailing slash gets
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TrailingSlash {
+    /// A 404, like any path without a route
+    #[default]
+    Strict,
+    /// A `308 Permanent Redirect` to the route's path, so the method and
+    /// body are sent again; the query string is kept
+    Redirect308,
+    /// The route's response, as if the path were the route's
+    Merge,
+}
+
+/// How leniently request paths are matched against the routes
+#[derive(Debug, Clone, Default)]
+pub struct RoutingOptions {
+    /// `/users/` for `/users`, and `/users` for `/users/` (strict by default)
+    pub trailing_slash: TrailingSlash,
+    /// Match the literal segments of the routes regardless of case;
+    /// path parameters keep the case they were sent in (off by default)
+    pub case_insensitive: bool,
+}
+
+impl RoutingOptions {
+    fn is_strict(&self) -> bool {
+        self.trailing_slash == TrailingSlash::Strict && !self.case_insensitive
+    }
+
+    /// `router` with the requests it has no route for matched again, against
+    /// `routes` served as `versioning` says
+    ///
+    /// [`Application::router`](crate::lifecycle::Application::router) does
+    /// this with the module's routes.
+    pub fn apply<S>(
+        &self,
+        router: Router<S>,
+        routes: &[RouteDescriptor],
+        versioning: &VersioningConfig,
+    ) -> Router<S>
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        if self.is_strict() || routes.is_empty() {
+            return router;
+        }
+        let templates: Arc<Vec<String>> = Arc::new(
+            routes
+                .iter()
+                .map(|route| served_path(route, versioning))
+                .collect(),
+        );
+        let options = self.clone();
+        // The fallback hands every request on to `router`, whose own
+        // fallback answers those matching no route even leniently.
+        let inner = router.clone();
+        let with_state: Arc<OnceLock<Router>> = Arc::default();
+        router.fallback(move |State(state): State<S>, mut request: Request<Body>| {
+            let (options, templates) = (options.clone(), templates.clone());
+            let (inner, with_state) = (inner.clone(), with_state.clone());
+            async move {
+                let path = request.uri().path();
+                let matched = templates
+                    .iter()
+                    .find_map(|template| options.canonical_path(path, template));
+                if let Some(canonical) = matched {
+                    let query = request.uri().query().map(|query| format!("?{}", query));
+                    let target = format!("{}{}", canonical, query.unwrap_or_default());
+                    let slash_differs = path.ends_with('/') != canonical.ends_with('/');
+                    if slash_differs && options.trailing_slash == TrailingSlash::Redirect308 {
+                        return redirect(&target);
+                    }
+                    if let Ok(uri) = target.parse::<Uri>() {
+                        *request.uri_mut() = uri;
+                    }
+                }
+                let router = with_state.get_or_init(|| inner.with_state(state));
+                match router.clone().oneshot(request).await {
+                    Ok(response) => response,
+                    Err(never) => match never {},
+                }
+            }
+        })
+    }
+
+    /// Whether the route at `template` is served `path` under these options
+    #[cfg(feature = "session")]
+    pub(crate) fn matches(&self, path: &str, template: &str) -> bool {
+        self.canonical_path(path, template).is_some()
+    }
+
+    /// `path` spelled as the route at `template` spells it, if the route
+    /// matches it under these options
+    fn canonical_path(&self, path: &str, template: &str) -> Option<String> {
+        let slash_lenient = self.trailing_slash != TrailingSlash::Strict;
+        let (path_body, template_body) = if slash_lenient {
+            (trim_slash(path), trim_slash(template))
+        } else if path.ends_with('/') == template.ends_with('/') {
+            (path, template)
+        } else {
+            return None;
+        };
+        let mut segments = path_body.split('/');
+        let mut canonical = Vec::new();
+        for expected in template_body.split('/') {
+            if expected.starts_with("{*") && expected.ends_with('}') {
+                canonical.extend(segments.by_ref());
+                break;
+            }
+            let segment = segments.next()?;
+            let is_param = expected.starts_with('{') && expected.ends_with('}');
+            if is_param && !segment.is_empty() {
+                canonical.push(segment);
+            } else if segment == expected
+                || (self.case_insensitive && segment.eq_ignore_ascii_case(expected))
+            {
+                canonical.push(expected);
+            } else {
+                return None;
+            }
+        }
+        if segments.next().is_some() {
+            return None;
+        }
+        let mut canonical = canonical.join("/");
+        if template.ends_with('/') && template != "/" {
+            canonical.push('/');
+        }
+        if canonical.is_empty() {
+            canonical.push('/');
+        }
+        Some(canonical)
+    }
+}
+
+/// The path `route` is served at, its version included for URI versioning
+pub(crate) fn served_path(route: &RouteDescriptor, versioning: &VersioningConfig) -> String {
+    match (versioning, route.version) {
+        (VersioningConfig::Uri, Some(version)) => format!("/{}{}", version, route.path),
+        _ => route.path.clone(),
+    }
+}
+
+/// `path` without its trailing slash, unless it is the root
+fn trim_slash(path: &str) -> &str {
+    match path.strip_suffix('/') {
+        Some("") | None => path,
+        Some(trimmed) => trimmed,
+    }
+}
+
+fn redirect(target: &str) -> Response {
+    match HeaderValue::from_str(target) {
+        Ok(location) => (StatusCode::PERMANENT_REDIRECT, [(LOCATION, location)]).into_response(),
+        Err(_) => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(trailing_slash: TrailingSlash, case_insensitive: bool) -> RoutingOptions {
+        RoutingOptions {
+            trailing_slash,
+            case_insensitive,
+        }
+    }
+
+    #[test]
+    fn trailing_slashes_match_unless_strict() {
+        let merge = options(TrailingSlash::Merge, false);
+        let strict = options(TrailingSlash::Strict, false);
+
+        assert_eq!(
+            merge.canonical_path("/users/", "/users").as_deref(),
+            Some("/users")
+        );
+        assert_eq!(
+            merge.canonical_path("/items", "/items/").as_deref(),
+            Some("/items/")
+        );
+        assert_eq!(strict.canonical_path("/users/", "/users"), None);
+    }
+
+    #[test]
+    fn case_is_ignored_for_literal_segments_only() {
+        let lenient = options(TrailingSlash::Strict, true);
+
+        assert_eq!(
+            lenient
+                .canonical_path("/Users/Ada", "/users/{name}")
+                .as_deref(),
+            Some("/users/Ada")
+        );
+        assert_eq!(
+            lenient
+                .canonical_path("/FILES/a/B", "/files/{*path}")
+                .as_deref(),
+            Some("/files/a/B")
+        );
+        assert_eq!(lenient.canonical_path("/users", "/users/{name}"), None);
+    }
+}
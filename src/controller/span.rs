@@ -0,0 +1,96 @@
+//! The `tracing` span each `#[routes]` handler runs in
+//!
+//! Every request gets a `request` span with these fields, so the logs of
+//! the services it calls are grouped under it:
+//!
+//! - `otel.name` and `route`: the matched route, `GET /users/{id}`
+//! - `controller` and `handler`: `UserController` and `get_user`, fixed
+//!   when the macro expands
+//! - `request_id`: the `X-Request-Id` header, or a new id
+//! - `status` and `latency_ms`, recorded once the response is ready
+//!
+//! Span names must be known at compile time, hence `request` and the
+//! route in `otel.name`, which OpenTelemetry exporters use as the name.
+//!
+//! The generated handler is instrumented with the span, so everything
+//! awaited in it, `#[transactional]` bodies included, runs inside it. Work
+//! spawned onto other tasks doesn't inherit it: pass the span along as
+//! [`EventBus::on`] does for its handlers.
+//!
+//! ```rust,ignore
+//! let span = tracing::Span::current();
+//! tokio::spawn(async move { send_welcome_email(user).await }.instrument(span));
+//! ```
+//!
+//! [`EventBus::on`]: crate::messaging::EventBus::on
+
+pub use tracing::Span;
+
+use axum::body::Body;
+use axum::extract::MatchedPath;
+use axum::http::Request;
+use axum::response::Response;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+use tracing::Instrument;
+use tracing::field::Empty;
+use tracing::instrument::Instrumented;
+
+/// The header a request's id is read from
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// The span of `request`, handled by `controller`'s `handler`
+pub fn request_span(
+    request: &Request<Body>,
+    controller: &'static str,
+    handler: &'static str,
+) -> Span {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or(request.uri().path(), MatchedPath::as_str);
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|id| id.to_str().ok())
+        .map_or_else(next_request_id, str::to_string);
+    tracing::info_span!(
+        "request",
+        otel.name = %format_args!("{} {}", request.method(), route),
+        method = %request.method(),
+        route,
+        controller,
+        handler,
+        request_id = %request_id,
+        status = Empty,
+        latency_ms = Empty,
+    )
+}
+
+/// Run `handler` in `span`, recording the response's status and the
+/// latency on it; generated `#[routes]` handlers call this
+#[doc(hidden)]
+pub async fn traced<F>(span: Span, handler: F) -> Response
+where
+    F: Future<Output = Response>,
+{
+    let start = Instant::now();
+    let response = handler.instrument(span.clone()).await;
+    span.record("status", response.status().as_u16());
+    span.record("latency_ms", start.elapsed().as_millis() as u64);
+    response
+}
+
+/// `future` in `span`, for work the handler hands to another task, such
+/// as `#[ws]` connections; generated by `#[routes]`
+#[doc(hidden)]
+pub fn in_span<F: Future>(span: Span, future: F) -> Instrumented<F> {
+    future.instrument(span)
+}
+
+/// An id for a request that came without one, unique to this process
+fn next_request_id() -> String {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+    format!("req-{}", NEXT_ID.fetch_add(1, Ordering::Relaxed))
+}
@@ -0,0 +1,164 @@
+//! Server-sent events
+//!
+//! A `#[routes]` handler returning `impl Stream<Item = T>`, with `T:
+//! Serialize`, is served as an event stream, each item a JSON `data` event.
+//! The stream may borrow the controller: it is driven next to the controller
+//! it keeps alive, and both are dropped when the client goes away. Handlers
+//! returning [`Sse`] are responses like any other, so their stream must own
+//! what it uses; [`EventBus::sse_stream`] returns one streaming the events
+//! published on the bus.
+//!
+//! ```rust,ignore
+//! #[routes(TickController)]
+//! impl TickController {
+//!     #[get("/ticks")]
+//!     async fn ticks(&self) -> impl Stream<Item = Tick> {
+//!         futures_util::stream::iter(self.ticks.recent())
+//!     }
+//!
+//!     #[get("/users/events")]
+//!     async fn user_events(&self) -> Sse<KeepAliveStream<EventStream>> {
+//!         self.events.sse_stream::<UserCreatedEvent>()
+//!     }
+//! }
+//! ```
+//!
+//! [`EventBus::sse_stream`]: crate::messaging::EventBus::sse_stream
+
+pub use axum::response::sse::{Event, KeepAlive, KeepAliveStream, Sse};
+pub use futures_util::Stream;
+
+use axum::BoxError;
+use axum::response::{IntoResponse, Response};
+use futures_util::StreamExt;
+use serde::Serialize;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// How an event stream is kept open, and what a client that falls behind
+/// gets
+#[derive(Debug, Clone)]
+pub struct SseOptions {
+    /// Interval of the comments keeping an idle connection open, 15 seconds
+    /// by default
+    pub keep_alive: Duration,
+    /// What a subscriber that fell behind the bus gets
+    pub lag: LagPolicy,
+}
+
+impl Default for SseOptions {
+    fn default() -> Self {
+        Self {
+            keep_alive: Duration::from_secs(15),
+            lag: LagPolicy::default(),
+        }
+    }
+}
+
+impl SseOptions {
+    /// `stream` as an event stream response with these options
+    pub fn sse<S, E>(&self, stream: S) -> Sse<KeepAliveStream<S>>
+    where
+        S: Stream<Item = Result<Event, E>> + Send + 'static,
+        E: Into<BoxError>,
+    {
+        Sse::new(stream).keep_alive(KeepAlive::new().interval(self.keep_alive))
+    }
+}
+
+/// What a client gets when it reads slower than events are published and
+/// misses some, see [`EventBus::with_capacity`]
+///
+/// [`EventBus::with_capacity`]: crate::messaging::EventBus::with_capacity
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LagPolicy {
+    /// Carry on with the next events, the default
+    #[default]
+    Skip,
+    /// Send a `lagged` event whose data is the number of events missed, then
+    /// carry on
+    Notify,
+    /// End the stream; browsers reconnect to it
+    Close,
+}
+
+/// The event stream of a handler returning `impl Stream`; generated by
+/// `#[routes]`
+///
+/// `produce` forwards the handler's stream to the [`StreamSender`]; it
+/// runs as the response is read, and is dropped with it.
+#[doc(hidden)]
+pub fn stream_response<T, F, Fut>(produce: F) -> Response
+where
+    T: Serialize + Send + 'static,
+    F: FnOnce(StreamSender<T>) -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel(1);
+    let stream = Driven {
+        driver: Some(Box::pin(produce(StreamSender(sender)))),
+        receiver,
+    };
+    SseOptions::default()
+        .sse(stream.map(|item| Event::default().json_data(item)))
+        .into_response()
+}
+
+/// Where a handler's stream is forwarded to the client
+#[doc(hidden)]
+pub struct StreamSender<T>(mpsc::Sender<T>);
+
+impl<T> StreamSender<T> {
+    /// Send the items of `stream` until it ends or the client goes away
+    pub async fn forward<S: Stream<Item = T>>(self, stream: S) {
+        let mut stream = std::pin::pin!(stream);
+        while let Some(item) = stream.next().await {
+            if self.0.send(item).await.is_err() {
+                break;
+            }
+        }
+    }
+}
+
+/// The items a driver future sends, the driver being polled along
+struct Driven<T> {
+    driver: Option<Pin<Box<dyn Future<Output = ()> + Send>>>,
+    receiver: mpsc::Receiver<T>,
+}
+
+impl<T> Stream for Driven<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let finished = self
+            .driver
+            .as_mut()
+            .is_some_and(|driver| driver.as_mut().poll(cx).is_ready());
+        if finished {
+            // Dropping the driver closes the channel once it's drained.
+            self.driver = None;
+        }
+        self.receiver.poll_recv(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::to_bytes;
+    use futures_util::stream;
+
+    #[tokio::test]
+    async fn items_are_sent_as_json_events() {
+        let response = stream_response(|sender| async move {
+            sender.forward(stream::iter([1, 2, 3])).await;
+        });
+
+        assert_eq!(response.headers()["content-type"], "text/event-stream");
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body, "data: 1\n\ndata: 2\n\ndata: 3\n\n");
+    }
+}
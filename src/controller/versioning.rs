@@ -0,0 +1,220 @@
+//! API versions, for `#[controller(version = "v1")]` and `#[version("2")]`
+//!
+//! A module's router serves each version's routes either under a URI
+//! prefix, `/v1/users`, or at the bare path, `/users`, picking the version
+//! from a request header:
+//!
+//! ```ignore
+//! let router = AppModule::build_versioned_router::<AppState>(
+//!     &container,
+//!     &VersioningConfig::header(),
+//! )?;
+//! ```
+//!
+//! Without the header the highest version answers. A version no route has
+//! is a 404 listing the available ones.
+
+use crate::common::{ApiResponse, StatusCode};
+use crate::controller::routing::mount;
+use axum::Router;
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{HeaderName, Request, Uri};
+use axum::response::{IntoResponse, Response};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, OnceLock};
+use tower::ServiceExt;
+
+/// How requests choose an API version
+#[derive(Debug, Clone, Default)]
+pub enum VersioningConfig {
+    /// A path prefix, `/v1/users`
+    #[default]
+    Uri,
+    /// A header, `X-Api-Version: 1`, with the routes at their bare paths
+    Header(HeaderName),
+}
+
+impl VersioningConfig {
+    /// Versions chosen with the `X-Api-Version` header
+    pub fn header() -> Self {
+        Self::Header(HeaderName::from_static("x-api-version"))
+    }
+}
+
+/// A version as the macros spell it: `2`, `V2` and `v2` are all `v2`
+pub fn normalize(version: &str) -> String {
+    let version = version.trim();
+    let number = version.strip_prefix(['v', 'V']).unwrap_or(version);
+    format!("v{}", number)
+}
+
+/// Sort key putting `v2` before `v10`
+fn version_order(version: &str) -> (Vec<u64>, &str) {
+    let numbers = version[1..]
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect();
+    (numbers, version)
+}
+
+/// The controllers' routers, by version, while a module tree is mounted
+#[doc(hidden)]
+pub struct VersionedRouters<S> {
+    unversioned: Router<S>,
+    versions: BTreeMap<&'static str, Router<S>>,
+}
+
+impl<S> Default for VersionedRouters<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    fn default() -> Self {
+        Self {
+            unversioned: Router::new(),
+            versions: BTreeMap::new(),
+        }
+    }
+}
+
+impl<S> VersionedRouters<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    /// Add a controller's `versioned_routers()`, mounted at `base_path`
+    pub fn mount(&mut self, base_path: &str, routers: Vec<(Option<&'static str>, Router<S>)>) {
+        for (version, router) in routers {
+            let target = match version {
+                Some(version) => self.versions.entry(version).or_default(),
+                None => &mut self.unversioned,
+            };
+            *target = mount(std::mem::take(target), base_path, router);
+        }
+    }
+
+    /// The routers of the versions, `None`'s first, without serving them
+    pub(crate) fn into_routers(self) -> Vec<(Option<&'static str>, Router<S>)> {
+        let mut routers = vec![(None, self.unversioned)];
+        for (version, router) in self.versions {
+            routers.push((Some(version), router));
+        }
+        routers
+    }
+
+    /// One router serving every version the way `config` says
+    pub fn finish(self, config: &VersioningConfig) -> Router<S> {
+        if self.versions.is_empty() {
+            return self.unversioned;
+        }
+        let mut available: Vec<&'static str> = self.versions.keys().copied().collect();
+        available.sort_by(|a, b| version_order(a).cmp(&version_order(b)));
+        match config {
+            VersioningConfig::Uri => {
+                let mut router = self.unversioned;
+                for (version, routes) in self.versions {
+                    router = router.nest(&format!("/{}", version), routes);
+                }
+                router.fallback(move |uri: Uri| {
+                    let available = available.clone();
+                    async move { unknown_prefix(&uri, &available) }
+                })
+            }
+            VersioningConfig::Header(header) => {
+                dispatch_by_header(self.unversioned, self.versions, header.clone(), available)
+            }
+        }
+    }
+}
+
+/// A 404 for a `/v9/...` request, listing the versions when `v9` isn't one
+fn unknown_prefix(uri: &Uri, available: &[&'static str]) -> Response {
+    let segment = uri.path().trim_start_matches('/').split('/').next();
+    match segment {
+        Some(segment) if looks_like_version(segment) && !available.contains(&segment) => {
+            unknown_version(segment, available)
+        }
+        _ => axum::http::StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+fn looks_like_version(segment: &str) -> bool {
+    segment.strip_prefix('v').is_some_and(|number| {
+        number.starts_with(|c: char| c.is_ascii_digit())
+            && number.chars().all(|c| c.is_ascii_digit() || c == '.')
+    })
+}
+
+fn unknown_version(requested: &str, available: &[&'static str]) -> Response {
+    ApiResponse::<()>::error(
+        StatusCode::NotFound,
+        format!("API version {:?} does not exist", requested),
+    )
+    .with_details(serde_json::json!({ "available_versions": available }))
+    .into_response()
+}
+
+/// Requests no unversioned route matches go to the version their `header`
+/// names, or the highest one
+///
+/// The version routers get the state of the first request; an application
+/// has one.
+fn dispatch_by_header<S>(
+    unversioned: Router<S>,
+    versions: BTreeMap<&'static str, Router<S>>,
+    header: HeaderName,
+    available: Vec<&'static str>,
+) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    let versions = Arc::new(versions);
+    let with_state: Arc<OnceLock<HashMap<&'static str, Router>>> = Arc::default();
+    unversioned.fallback(move |State(state): State<S>, request: Request<Body>| {
+        let (versions, with_state) = (versions.clone(), with_state.clone());
+        let (header, available) = (header.clone(), available.clone());
+        async move {
+            let requested = request
+                .headers()
+                .get(&header)
+                .and_then(|value| value.to_str().ok())
+                .map(normalize);
+            let version = match requested {
+                Some(requested) => match available.iter().find(|v| **v == requested) {
+                    Some(version) => *version,
+                    None => return unknown_version(&requested, &available),
+                },
+                None => available[available.len() - 1],
+            };
+            let routers = with_state.get_or_init(|| {
+                versions
+                    .iter()
+                    .map(|(version, router)| (*version, router.clone().with_state(state.clone())))
+                    .collect()
+            });
+            match routers[version].clone().oneshot(request).await {
+                Ok(response) => response,
+                Err(never) => match never {},
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_are_normalized() {
+        assert_eq!(normalize("2"), "v2");
+        assert_eq!(normalize(" V2 "), "v2");
+        assert_eq!(normalize("v1.1"), "v1.1");
+    }
+
+    #[test]
+    fn versions_sort_numerically() {
+        let mut versions = vec!["v10", "v2", "v1.1"];
+        versions.sort_by(|a, b| version_order(a).cmp(&version_order(b)));
+
+        assert_eq!(versions, ["v1.1", "v2", "v10"]);
+    }
+}
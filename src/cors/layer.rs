@@ -0,0 +1,262 @@
+use crate::common::{ApiResponse, StatusCode};
+use crate::cors::{CorsConfig, CorsOverride};
+use axum::http::header::{
+    ACCESS_CONTROL_ALLOW_CREDENTIALS, ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS,
+    ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_MAX_AGE, ACCESS_CONTROL_REQUEST_METHOD, ORIGIN,
+    VARY,
+};
+use axum::http::{HeaderValue, Method};
+use axum::response::IntoResponse;
+use axum::{body::Body, http::Request, response::Response};
+use std::borrow::Cow;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+/// Marks a preflight request for the controller's [`ControllerCorsLayer`]
+#[derive(Clone, Copy)]
+struct Preflight;
+
+/// The `#[cors(...)]` of the controller that answered, on its response
+#[derive(Clone, Copy)]
+struct ControllerCors(Option<&'static CorsOverride>);
+
+/// Tower layer answering preflight requests and adding the CORS headers to
+/// responses, as [`CorsConfig`] says
+///
+/// Requests without an `Origin` pass through untouched. A controller's
+/// `#[cors(...)]` applies to its routes; [`Application::router`] adds this
+/// layer when [`ApplicationBuilder::cors`] was given a configuration.
+///
+/// [`Application::router`]: crate::lifecycle::Application::router
+/// [`ApplicationBuilder::cors`]: crate::lifecycle::ApplicationBuilder::cors
+#[derive(Clone)]
+pub struct CorsLayer {
+    config: Arc<CorsConfig>,
+}
+
+impl CorsLayer {
+    pub fn new(config: CorsConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+        }
+    }
+}
+
+impl<S> Layer<S> for CorsLayer {
+    type Service = CorsMiddleware<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CorsMiddleware {
+            inner,
+            config: self.config.clone(),
+        }
+    }
+}
+
+/// Service produced by [`CorsLayer`]
+#[derive(Clone)]
+pub struct CorsMiddleware<S> {
+    inner: S,
+    config: Arc<CorsConfig>,
+}
+
+impl<S> Service<Request<Body>> for CorsMiddleware<S>
+where
+    S: Service<Request<Body>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<Body>) -> Self::Future {
+        let origin = request
+            .headers()
+            .get(ORIGIN)
+            .and_then(|origin| origin.to_str().ok())
+            .map(str::to_string);
+        let Some(origin) = origin else {
+            return Box::pin(self.inner.call(request));
+        };
+        let preflight = request.method() == Method::OPTIONS
+            && request
+                .headers()
+                .contains_key(ACCESS_CONTROL_REQUEST_METHOD);
+        if preflight {
+            request.extensions_mut().insert(Preflight);
+        }
+        let config = self.config.clone();
+        let future = self.inner.call(request);
+
+        Box::pin(async move {
+            let response = future.await?;
+            // The controller that answered, if any, may override the config.
+            let config = match response.extensions().get::<ControllerCors>() {
+                Some(ControllerCors(Some(cors))) => Cow::Owned(config.with_override(cors)),
+                _ => Cow::Borrowed(&*config),
+            };
+            if preflight {
+                Ok(preflight_response(&config, &origin))
+            } else {
+                Ok(with_cors_headers(response, &config, &origin))
+            }
+        })
+    }
+}
+
+/// The answer to a preflight request from `origin`
+fn preflight_response(config: &CorsConfig, origin: &str) -> Response {
+    if !config.allows(origin) {
+        return ApiResponse::<()>::error(
+            StatusCode::Forbidden,
+            format!("Origin {} is not allowed", origin),
+        )
+        .into_response();
+    }
+    let mut response = axum::http::StatusCode::NO_CONTENT.into_response();
+    let headers = response.headers_mut();
+    let methods: Vec<&str> = config.methods.iter().map(Method::as_str).collect();
+    if let Ok(methods) = HeaderValue::from_str(&methods.join(", ")) {
+        headers.insert(ACCESS_CONTROL_ALLOW_METHODS, methods);
+    }
+    if !config.headers.is_empty() {
+        let names: Vec<&str> = config.headers.iter().map(|name| name.as_str()).collect();
+        if let Ok(names) = HeaderValue::from_str(&names.join(", ")) {
+            headers.insert(ACCESS_CONTROL_ALLOW_HEADERS, names);
+        }
+    }
+    if let Some(max_age) = config.max_age {
+        headers.insert(ACCESS_CONTROL_MAX_AGE, max_age.as_secs().into());
+    }
+    with_cors_headers(response, config, origin)
+}
+
+/// `response` with the headers allowing `origin` to read it, if it may
+fn with_cors_headers(mut response: Response, config: &CorsConfig, origin: &str) -> Response {
+    if !config.allows(origin) {
+        return response;
+    }
+    let headers = response.headers_mut();
+    if config.is_public() {
+        headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue::from_static("*"));
+        return response;
+    }
+    if let Ok(origin) = HeaderValue::from_str(origin) {
+        headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+        headers.append(VARY, HeaderValue::from_static("origin"));
+    }
+    if config.credentials {
+        headers.insert(
+            ACCESS_CONTROL_ALLOW_CREDENTIALS,
+            HeaderValue::from_static("true"),
+        );
+    }
+    response
+}
+
+/// Route layer telling [`CorsLayer`] about a controller's `#[cors(...)]`;
+/// `#[routes]` adds it to every controller router
+///
+/// It also answers the preflight requests `CorsLayer` passes on, so they
+/// don't reach the handlers.
+#[derive(Clone, Copy)]
+pub struct ControllerCorsLayer {
+    cors: Option<&'static CorsOverride>,
+}
+
+impl ControllerCorsLayer {
+    pub fn new(cors: Option<&'static CorsOverride>) -> Self {
+        Self { cors }
+    }
+}
+
+impl<S> Layer<S> for ControllerCorsLayer {
+    type Service = ControllerCorsMiddleware<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ControllerCorsMiddleware {
+            inner,
+            cors: self.cors,
+        }
+    }
+}
+
+/// Service produced by [`ControllerCorsLayer`]
+#[derive(Clone)]
+pub struct ControllerCorsMiddleware<S> {
+    inner: S,
+    cors: Option<&'static CorsOverride>,
+}
+
+impl<S> Service<Request<Body>> for ControllerCorsMiddleware<S>
+where
+    S: Service<Request<Body>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        let cors = ControllerCors(self.cors);
+        if request.extensions().get::<Preflight>().is_some() {
+            let mut response = Response::default();
+            response.extensions_mut().insert(cors);
+            return Box::pin(async move { Ok(response) });
+        }
+        let future = self.inner.call(request);
+        Box::pin(async move {
+            let mut response = future.await?;
+            response.extensions_mut().insert(cors);
+            Ok(response)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, routing::get};
+    use tower::ServiceExt;
+
+    fn app(config: CorsConfig) -> Router {
+        Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(CorsLayer::new(config))
+    }
+
+    #[tokio::test]
+    async fn requests_without_an_origin_pass_through() {
+        let config = CorsConfig::new().allow_origin("https://app.example.com");
+        let request = Request::get("/").body(Body::empty()).unwrap();
+
+        let response = app(config).oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), 200);
+        assert!(!response.headers().contains_key(ACCESS_CONTROL_ALLOW_ORIGIN));
+    }
+
+    #[tokio::test]
+    async fn any_origin_is_answered_with_a_star() {
+        let config = CorsConfig::new().allow_origin("*");
+        let request = Request::get("/")
+            .header(ORIGIN, "https://app.example.com")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app(config).oneshot(request).await.unwrap();
+
+        assert_eq!(response.headers()[ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+    }
+}
@@ -0,0 +1,317 @@
+//! Cross-origin resource sharing
+//!
+//! [`ApplicationBuilder::cors`](crate::lifecycle::ApplicationBuilder::cors)
+//! takes a [`CorsConfig`] for every route; a controller's
+//! `#[cors(origins = "...", credentials = true)]` overrides parts of it for
+//! the controller's routes. The configuration and every override are checked
+//! when the application is built.
+//!
+//! ```rust,ignore
+//! let app = Application::builder()
+//!     .module::<AppModule>()
+//!     .cors(
+//!         CorsConfig::new()
+//!             .allow_origin("https://app.example.com")
+//!             .allow_origin("https://*.preview.example.com")
+//!             .allow_header("content-type")
+//!             .max_age(Duration::from_secs(600)),
+//!     )
+//!     .build()
+//!     .await?;
+//! ```
+
+mod layer;
+
+pub use layer::{ControllerCorsLayer, ControllerCorsMiddleware, CorsLayer, CorsMiddleware};
+
+use axum::http::{HeaderName, Method};
+use regex::Regex;
+use std::time::Duration;
+use thiserror::Error;
+
+/// An origin requests may come from
+#[derive(Debug, Clone)]
+pub enum AllowedOrigin {
+    /// Any origin, `*`
+    Any,
+    /// One origin, `https://app.example.com`
+    Exact(String),
+    /// Origins matching a pattern where `*` stands for one or more
+    /// subdomain labels, `https://*.example.com`
+    Wildcard(String),
+    /// Origins the regex matches, anchor it with `^...$`
+    Regex(Regex),
+}
+
+impl AllowedOrigin {
+    /// `*` is any origin, a pattern with a `*` a wildcard, anything else
+    /// one origin
+    pub fn parse(origin: &str) -> Self {
+        match origin.trim() {
+            "*" => AllowedOrigin::Any,
+            origin if origin.contains('*') => AllowedOrigin::Wildcard(origin.to_string()),
+            origin => AllowedOrigin::Exact(origin.to_string()),
+        }
+    }
+
+    /// Whether requests from `origin` are allowed
+    pub fn matches(&self, origin: &str) -> bool {
+        match self {
+            AllowedOrigin::Any => true,
+            AllowedOrigin::Exact(allowed) => allowed.eq_ignore_ascii_case(origin),
+            AllowedOrigin::Wildcard(pattern) => wildcard_matches(pattern, origin),
+            AllowedOrigin::Regex(regex) => regex.is_match(origin),
+        }
+    }
+}
+
+/// Whether `origin` matches `pattern`, each `*` standing for a non-empty run
+/// of subdomain labels
+fn wildcard_matches(pattern: &str, origin: &str) -> bool {
+    let pattern = pattern.to_ascii_lowercase();
+    let origin = origin.to_ascii_lowercase();
+    let mut parts = pattern.split('*');
+    let Some(first) = parts.next() else {
+        return false;
+    };
+    let Some(mut rest) = origin.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts = parts.peekable();
+    while let Some(part) = parts.next() {
+        let found = if parts.peek().is_none() {
+            rest.strip_suffix(part).map(|label| (label, ""))
+        } else {
+            rest.find(part)
+                .map(|at| (&rest[..at], &rest[at + part.len()..]))
+        };
+        let Some((labels, after)) = found else {
+            return false;
+        };
+        let is_labels = !labels.is_empty()
+            && labels
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.');
+        if !is_labels {
+            return false;
+        }
+        rest = after;
+    }
+    rest.is_empty()
+}
+
+/// Which cross-origin requests are allowed, and how browsers are told
+#[derive(Debug, Clone)]
+pub struct CorsConfig {
+    /// Origins requests may come from (none by default)
+    pub origins: Vec<AllowedOrigin>,
+    /// Methods preflight requests may ask for (`GET`, `HEAD`, `POST`, `PUT`,
+    /// `PATCH` and `DELETE` by default)
+    pub methods: Vec<Method>,
+    /// Request headers beyond the CORS-safelisted ones (none by default)
+    pub headers: Vec<HeaderName>,
+    /// Whether requests may carry cookies and credentials (off by default)
+    pub credentials: bool,
+    /// How long browsers may cache a preflight answer
+    pub max_age: Option<Duration>,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            origins: Vec::new(),
+            methods: vec![
+                Method::GET,
+                Method::HEAD,
+                Method::POST,
+                Method::PUT,
+                Method::PATCH,
+                Method::DELETE,
+            ],
+            headers: Vec::new(),
+            credentials: false,
+            max_age: None,
+        }
+    }
+}
+
+impl CorsConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow an origin: `https://app.example.com`, a wildcard such as
+    /// `https://*.example.com`, or `*` for any
+    pub fn allow_origin(mut self, origin: &str) -> Self {
+        self.origins.push(AllowedOrigin::parse(origin));
+        self
+    }
+
+    /// Allow the origins `pattern` matches
+    ///
+    /// # Errors
+    /// Returns an error if `pattern` is not a valid regex.
+    pub fn allow_origin_regex(mut self, pattern: &str) -> Result<Self, CorsConfigError> {
+        let regex = Regex::new(pattern).map_err(|source| CorsConfigError::InvalidOriginRegex {
+            pattern: pattern.to_string(),
+            source,
+        })?;
+        self.origins.push(AllowedOrigin::Regex(regex));
+        Ok(self)
+    }
+
+    /// Replace the allowed methods
+    pub fn allow_methods(mut self, methods: impl IntoIterator<Item = Method>) -> Self {
+        self.methods = methods.into_iter().collect();
+        self
+    }
+
+    /// Allow a request header, `content-type`
+    pub fn allow_header(mut self, header: &'static str) -> Self {
+        self.headers.push(HeaderName::from_static(header));
+        self
+    }
+
+    pub fn allow_credentials(mut self, credentials: bool) -> Self {
+        self.credentials = credentials;
+        self
+    }
+
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Refuse combinations browsers reject
+    ///
+    /// # Errors
+    /// Returns an error if credentials are allowed for any origin: browsers
+    /// ignore `Access-Control-Allow-Origin: *` on credentialed requests.
+    pub fn validate(&self) -> Result<(), CorsConfigError> {
+        let any_origin = self
+            .origins
+            .iter()
+            .any(|origin| matches!(origin, AllowedOrigin::Any));
+        if self.credentials && any_origin {
+            return Err(CorsConfigError::CredentialsWithAnyOrigin);
+        }
+        Ok(())
+    }
+
+    /// This configuration with a controller's `#[cors(...)]` applied
+    pub fn with_override(&self, cors: &CorsOverride) -> CorsConfig {
+        let mut config = self.clone();
+        if let Some(origins) = cors.origins {
+            config.origins = origins.iter().map(|o| AllowedOrigin::parse(o)).collect();
+        }
+        if let Some(methods) = cors.methods {
+            config.methods = methods
+                .iter()
+                .filter_map(|method| Method::from_bytes(method.as_bytes()).ok())
+                .collect();
+        }
+        if let Some(headers) = cors.headers {
+            config.headers = headers.iter().map(|h| HeaderName::from_static(h)).collect();
+        }
+        if let Some(credentials) = cors.credentials {
+            config.credentials = credentials;
+        }
+        if let Some(max_age) = cors.max_age {
+            config.max_age = Some(Duration::from_secs(max_age));
+        }
+        config
+    }
+
+    /// Whether requests from `origin` are allowed
+    pub(crate) fn allows(&self, origin: &str) -> bool {
+        self.origins.iter().any(|allowed| allowed.matches(origin))
+    }
+
+    /// Whether `Access-Control-Allow-Origin: *` may answer every origin
+    pub(crate) fn is_public(&self) -> bool {
+        !self.credentials
+            && self
+                .origins
+                .iter()
+                .any(|origin| matches!(origin, AllowedOrigin::Any))
+    }
+}
+
+/// What a controller's `#[cors(...)]` replaces in the application's
+/// [`CorsConfig`]; generated by `#[controller]`
+///
+/// Header names are lowercase and methods uppercase; the macro checks them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorsOverride {
+    pub origins: Option<&'static [&'static str]>,
+    pub methods: Option<&'static [&'static str]>,
+    pub headers: Option<&'static [&'static str]>,
+    pub credentials: Option<bool>,
+    /// In seconds
+    pub max_age: Option<u64>,
+}
+
+/// A [`CorsConfig`] browsers would reject, or couldn't be built
+#[derive(Debug, Error)]
+pub enum CorsConfigError {
+    #[error(
+        "CORS credentials can't be allowed for any origin (`*`); list the allowed origins instead"
+    )]
+    CredentialsWithAnyOrigin,
+
+    #[error("invalid CORS origin regex `{pattern}`: {source}")]
+    InvalidOriginRegex {
+        pattern: String,
+        #[source]
+        source: regex::Error,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wildcards_stand_for_subdomains() {
+        let origin = AllowedOrigin::parse("https://*.example.com");
+
+        assert!(origin.matches("https://app.example.com"));
+        assert!(origin.matches("https://a.b.example.com"));
+        assert!(!origin.matches("https://example.com"));
+        assert!(!origin.matches("https://evil.com/.example.com"));
+        assert!(!origin.matches("http://app.example.com"));
+    }
+
+    #[test]
+    fn credentials_for_any_origin_are_refused() {
+        let config = CorsConfig::new().allow_origin("*").allow_credentials(true);
+
+        assert!(matches!(
+            config.validate(),
+            Err(CorsConfigError::CredentialsWithAnyOrigin)
+        ));
+        assert!(config.allow_credentials(false).validate().is_ok());
+    }
+
+    #[test]
+    fn overrides_replace_what_they_name() {
+        let config = CorsConfig::new()
+            .allow_origin("https://app.example.com")
+            .allow_header("content-type");
+        let cors = CorsOverride {
+            origins: Some(&["https://admin.example.com"]),
+            methods: None,
+            headers: None,
+            credentials: Some(true),
+            max_age: Some(60),
+        };
+
+        let merged = config.with_override(&cors);
+
+        assert!(merged.allows("https://admin.example.com"));
+        assert!(!merged.allows("https://app.example.com"));
+        assert_eq!(merged.headers, config.headers);
+        assert!(merged.credentials);
+        assert_eq!(merged.max_age, Some(Duration::from_secs(60)));
+    }
+}
@@ -0,0 +1,169 @@
+use crate::common::StatusCode as ApiStatus;
+use crate::controller::routing_options::RoutingOptions;
+use crate::csrf::{CsrfConfig, token_matches};
+use crate::exception::HttpException;
+use crate::session::{CSRF_TOKEN_KEY, Session};
+use axum::http::header::{CONTENT_LENGTH, CONTENT_TYPE};
+use axum::http::{Method, Request};
+use axum::response::IntoResponse;
+use axum::{body::Body, response::Response};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+/// Tower layer rejecting state-changing requests without the session's
+/// CSRF token, as [`CsrfConfig`] says
+///
+/// Goes inside the session layer, whose [`Session`] it reads;
+/// [`Application::router`] adds it when [`ApplicationBuilder::csrf`] was
+/// given a configuration, with the `#[csrf_exempt]` routes exempted.
+///
+/// [`Application::router`]: crate::lifecycle::Application::router
+/// [`ApplicationBuilder::csrf`]: crate::lifecycle::ApplicationBuilder::csrf
+#[derive(Clone)]
+pub struct CsrfLayer {
+    config: Arc<CsrfConfig>,
+    routing: RoutingOptions,
+}
+
+impl CsrfLayer {
+    pub fn new(config: CsrfConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+            routing: RoutingOptions::default(),
+        }
+    }
+
+    /// Match the exempt paths as the router matches them
+    pub(crate) fn routing_options(mut self, routing: RoutingOptions) -> Self {
+        self.routing = routing;
+        self
+    }
+}
+
+impl<S> Layer<S> for CsrfLayer {
+    type Service = CsrfMiddleware<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CsrfMiddleware {
+            inner,
+            config: self.config.clone(),
+            routing: self.routing.clone(),
+        }
+    }
+}
+
+/// Service produced by [`CsrfLayer`]
+#[derive(Clone)]
+pub struct CsrfMiddleware<S> {
+    inner: S,
+    config: Arc<CsrfConfig>,
+    routing: RoutingOptions,
+}
+
+impl<S> CsrfMiddleware<S> {
+    /// Whether `request` may go through unchecked
+    fn skips(&self, request: &Request<Body>) -> bool {
+        let method = request.method();
+        if matches!(
+            *method,
+            Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE
+        ) {
+            return true;
+        }
+        let path = request.uri().path();
+        self.config.exempt.iter().any(|route| {
+            route.method.as_ref().is_none_or(|exempt| exempt == method)
+                && self.routing.matches(path, &route.path)
+        })
+    }
+}
+
+impl<S> Service<Request<Body>> for CsrfMiddleware<S>
+where
+    S: Service<Request<Body>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        if self.skips(&request) {
+            return Box::pin(self.inner.call(request));
+        }
+        let mut inner = self.inner.clone();
+        let config = self.config.clone();
+
+        Box::pin(async move {
+            let Some(session) = request.extensions().get::<Session>().cloned() else {
+                return Ok(HttpException::internal(
+                    "CSRF protection needs the session layer, see ApplicationBuilder::sessions",
+                )
+                .into_response());
+            };
+            let expected = session.get::<String>(CSRF_TOKEN_KEY);
+            let (provided, request) = match provided_token(request, &config).await {
+                Ok(read) => read,
+                Err(rejected) => return Ok(rejected.into_response()),
+            };
+            if !token_matches(expected.as_deref(), provided.as_deref()) {
+                return Ok(HttpException::forbidden("invalid CSRF token").into_response());
+            }
+            inner.call(request).await
+        })
+    }
+}
+
+/// The token `request` carries, in the header or else the form field,
+/// and the request, its body read back in if it was a form
+async fn provided_token(
+    request: Request<Body>,
+    config: &CsrfConfig,
+) -> Result<(Option<String>, Request<Body>), HttpException> {
+    if let Some(token) = request.headers().get(&config.header) {
+        return Ok((token.to_str().ok().map(str::to_string), request));
+    }
+    let is_form = request
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/x-www-form-urlencoded"));
+    if !is_form {
+        return Ok((None, request));
+    }
+    // The token is checked before anyone is authenticated, so only read
+    // as much of the form as the configuration allows.
+    let too_large = || {
+        HttpException::new(
+            ApiStatus::PayloadTooLarge,
+            format!("Form body exceeds the limit of {} bytes", config.body_limit),
+        )
+    };
+    let declared = request
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok());
+    if declared.is_some_and(|length| length > config.body_limit as u64) {
+        return Err(too_large());
+    }
+    let (parts, body) = request.into_parts();
+    let bytes = axum::body::to_bytes(body, config.body_limit)
+        .await
+        .map_err(|_| too_large())?;
+    // Tokens are hex, so they need no percent-decoding.
+    let token = std::str::from_utf8(&bytes).ok().and_then(|form| {
+        form.split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(name, _)| *name == config.field)
+            .map(|(_, value)| value.to_string())
+    });
+    Ok((token, Request::from_parts(parts, Body::from(bytes))))
+}
@@ -0,0 +1,181 @@
+//! Cross-site request forgery protection, on top of the sessions
+//!
+//! [`ApplicationBuilder::csrf`] rejects POST, PUT, PATCH and DELETE requests
+//! unless they carry the session's [`CsrfToken`], in the `X-CSRF-Token`
+//! header or the `_csrf` field of a urlencoded form; other sites can't read
+//! it, so they can't forge such requests. Routes marked `#[csrf_exempt]`
+//! are skipped. The token is handed out with `#[csrf_token]` parameters and
+//! replaced when [`Session::regenerate_id`] is called, on login.
+//!
+//! ```rust,ignore
+//! let app = Application::builder()
+//!     .module::<AppModule>()
+//!     .sessions(SessionModule::new(SessionConfig::new(secret)))
+//!     .csrf(CsrfConfig::new())
+//!     .build()
+//!     .await?;
+//!
+//! #[get("/settings")]
+//! async fn settings(&self, #[csrf_token] token: CsrfToken) -> Html<String> {
+//!     Html(format!(r#"<form method="post"><input type="hidden" name="_csrf" value="{}">"#, token))
+//! }
+//! ```
+//!
+//! [`ApplicationBuilder::csrf`]: crate::lifecycle::ApplicationBuilder::csrf
+//! [`Session::regenerate_id`]: crate::session::Session::regenerate_id
+
+mod layer;
+
+pub use layer::{CsrfLayer, CsrfMiddleware};
+
+use crate::controller::routing::RouteDescriptor;
+use crate::controller::routing_options::served_path;
+use crate::controller::versioning::VersioningConfig;
+use crate::exception::HttpException;
+use crate::session::Session;
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use axum::http::{HeaderName, Method};
+use std::fmt;
+use subtle::ConstantTimeEq;
+
+/// Where requests carry the CSRF token, and which paths don't need it
+#[derive(Debug, Clone)]
+pub struct CsrfConfig {
+    /// `x-csrf-token` by default
+    pub header: HeaderName,
+    /// The urlencoded form field, `_csrf` by default
+    pub field: String,
+    /// Largest form body read looking for the token, 64 KiB by default;
+    /// larger ones are answered with 413
+    pub body_limit: usize,
+    exempt: Vec<ExemptRoute>,
+}
+
+/// A route the check skips; `method` `None` for all of them
+#[derive(Debug, Clone)]
+struct ExemptRoute {
+    method: Option<Method>,
+    path: String,
+}
+
+impl Default for CsrfConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CsrfConfig {
+    pub fn new() -> Self {
+        Self {
+            header: HeaderName::from_static("x-csrf-token"),
+            field: "_csrf".to_string(),
+            body_limit: 64 * 1024,
+            exempt: Vec::new(),
+        }
+    }
+
+    /// Read the token from the header `header`
+    pub fn header(mut self, header: HeaderName) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// Read the token from the form field `field`
+    pub fn field(mut self, field: impl Into<String>) -> Self {
+        self.field = field.into();
+        self
+    }
+
+    /// Read form bodies of up to `bytes` looking for the token
+    pub fn body_limit(mut self, bytes: usize) -> Self {
+        self.body_limit = bytes;
+        self
+    }
+
+    /// Skip the check for requests to `path`, a route path such as
+    /// `/hooks/{provider}`, whatever their method
+    ///
+    /// For routes outside `#[routes]`; those inside take `#[csrf_exempt]`.
+    pub fn exempt(mut self, path: impl Into<String>) -> Self {
+        self.exempt.push(ExemptRoute {
+            method: None,
+            path: path.into(),
+        });
+        self
+    }
+
+    /// With the `#[csrf_exempt]` routes among `routes` exempted, at the
+    /// paths `versioning` serves them
+    pub(crate) fn exempting(
+        mut self,
+        routes: &[RouteDescriptor],
+        versioning: &VersioningConfig,
+    ) -> Self {
+        for route in routes.iter().filter(|route| route.csrf_exempt) {
+            if let Ok(method) = route.method.parse() {
+                self.exempt.push(ExemptRoute {
+                    method: Some(method),
+                    path: served_path(route, versioning),
+                });
+            }
+        }
+        self
+    }
+}
+
+/// The session's CSRF token, for `#[csrf_token]` parameters
+///
+/// Displays as the token itself, ready to go into a form field or a
+/// `<meta>` tag scripts send back in the header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsrfToken(String);
+
+impl CsrfToken {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The token of the request's session, created if it has none yet;
+    /// generated `#[csrf_token]` parameters call this
+    #[doc(hidden)]
+    pub fn from_parts(parts: &Parts) -> Result<CsrfToken, HttpException> {
+        Ok(CsrfToken(Session::from_parts(parts)?.csrf_token()))
+    }
+}
+
+impl fmt::Display for CsrfToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for CsrfToken {
+    type Rejection = HttpException;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        CsrfToken::from_parts(parts)
+    }
+}
+
+/// Whether `provided` is the session's token, compared in constant time
+fn token_matches(expected: Option<&str>, provided: Option<&str>) -> bool {
+    match (expected, provided) {
+        (Some(expected), Some(provided)) => expected.as_bytes().ct_eq(provided.as_bytes()).into(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_match_only_exactly() {
+        assert!(token_matches(Some("abc"), Some("abc")));
+        assert!(!token_matches(Some("abc"), Some("abd")));
+        assert!(!token_matches(Some("abc"), Some("ab")));
+        assert!(!token_matches(Some("abc"), None));
+        assert!(!token_matches(None, Some("")));
+    }
+}
@@ -1,6 +1,12 @@
-use crate::di::Container;
+use crate::di::{Container, Registration, RegistrationPolicy};
+use crate::error::Result;
+use crate::module::{DynamicModule, Module};
 use std::sync::Arc;
 
+/// Resolves a trait bound with [`ContainerBuilder::bind`], see
+/// [`ContainerBuilder::try_build`]
+type BindingCheck = Box<dyn Fn(&Container) -> Result<()> + Send + Sync>;
+
 /// Builder for constructing a dependency injection container
 ///
 /// Use this to configure and register services before building the final immutable container.
@@ -33,6 +39,7 @@ use std::sync::Arc;
 /// ```
 pub struct ContainerBuilder {
     container: Container,
+    bindings: Vec<BindingCheck>,
 }
 
 impl ContainerBuilder {
@@ -40,12 +47,37 @@ impl ContainerBuilder {
     pub fn new() -> Self {
         Self {
             container: Container::new(),
+            bindings: Vec::new(),
         }
     }
 
+    /// Set what registering a type twice does; it replaces the first
+    /// instance by default
+    ///
+    /// ```
+    /// use meshestra::di::{ContainerBuilder, RegistrationPolicy};
+    ///
+    /// let container = ContainerBuilder::new()
+    ///     .on_duplicate(RegistrationPolicy::Skip)
+    ///     .register(1u32)
+    ///     .register(2u32)
+    ///     .build();
+    /// assert_eq!(*container.resolve::<u32>().unwrap(), 1);
+    /// ```
+    pub fn on_duplicate(mut self, policy: RegistrationPolicy) -> Self {
+        self.container.set_registration_policy(policy);
+        self
+    }
+
     /// Register a service instance
+    ///
+    /// # Panics
+    /// Panics if `T` is registered already and the policy is
+    /// [`RegistrationPolicy::Error`].
     pub fn register<T: 'static + Send + Sync>(mut self, instance: T) -> Self {
-        self.container.register(instance);
+        if self.container.register(instance) == Registration::Rejected {
+            panic!("{} is registered twice", std::any::type_name::<T>());
+        }
         self
     }
 
@@ -60,13 +92,70 @@ impl ContainerBuilder {
         F: Fn(Arc<Impl>) -> Arc<Trait> + 'static + Send + Sync,
     {
         self.container.register_trait::<Trait, Impl, F>(caster);
+        self.bindings.push(Box::new(|container: &Container| {
+            container.resolve_trait::<Trait>().map(|_| ())
+        }));
         self
     }
 
+    /// Register the module `M`, its imports and their providers
+    ///
+    /// ```rust,ignore
+    /// let container = ContainerBuilder::new()
+    ///     .register(EventBus::new())
+    ///     .module::<AppModule>()?
+    ///     .build();
+    /// ```
+    ///
+    /// # Errors
+    /// Returns the error of [`Module::register`], a provider whose
+    /// dependencies aren't registered say.
+    pub fn module<M: Module>(mut self) -> Result<Self> {
+        M::register(&mut self.container)?;
+        Ok(self)
+    }
+
+    /// Register the providers of `module`, a `for_root(...)` of a
+    /// `#[module(config = ...)]` say
+    ///
+    /// # Errors
+    /// Returns the error of [`DynamicModule::register_into`].
+    pub fn module_dyn(mut self, module: DynamicModule) -> Result<Self> {
+        module.register_into(&mut self.container)?;
+        Ok(self)
+    }
+
     /// Build the container
     pub fn build(self) -> Container {
         self.container
     }
+
+    /// Build the container, checking that every trait given to
+    /// [`bind`](Self::bind) resolves to a registered implementation
+    ///
+    /// ```
+    /// use meshestra::di::ContainerBuilder;
+    /// use std::sync::Arc;
+    ///
+    /// trait Clock: Send + Sync {}
+    /// struct SystemClock;
+    /// impl Clock for SystemClock {}
+    ///
+    /// let result = ContainerBuilder::new()
+    ///     .bind::<dyn Clock, SystemClock, _>(|c| c as Arc<dyn Clock>)
+    ///     .try_build();
+    /// assert!(result.is_err());
+    /// ```
+    ///
+    /// # Errors
+    /// Returns [`DependencyNotFound`](crate::error::MeshestraError::DependencyNotFound)
+    /// for the first trait whose implementation isn't registered.
+    pub fn try_build(self) -> Result<Container> {
+        for check in &self.bindings {
+            check(&self.container)?;
+        }
+        Ok(self.container)
+    }
 }
 
 impl Default for ContainerBuilder {
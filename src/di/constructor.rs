@@ -0,0 +1,114 @@
+//! Injecting through a user-defined constructor, for
+//! `#[injectable(constructor = "new")]` and
+//! `#[controller(constructor = "new")]`
+//!
+//! The macros can't see the constructor's signature, so they hand the
+//! function itself to [`construct`], which resolves each parameter by its
+//! type and calls it. A parameter that can't be resolved is a compile error
+//! at that call.
+
+use crate::di::{Container, Lazy};
+use crate::error::{MeshestraError, Result};
+use std::sync::Arc;
+
+/// A constructor parameter resolved from the container: `Arc<T>`,
+/// `Arc<dyn Trait>` or `Lazy<T>`
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` can't be resolved from the container",
+    note = "constructor parameters must be `Arc<T>`, `Arc<dyn Trait>` or `Lazy<T>`"
+)]
+pub trait FromContainer: Sized {
+    fn from_container(container: &Container) -> Result<Self>;
+}
+
+impl<T: ?Sized + Send + Sync + 'static> FromContainer for Arc<T> {
+    fn from_container(container: &Container) -> Result<Self> {
+        container.resolve_arc::<T>()
+    }
+}
+
+impl<T: Send + Sync + 'static> FromContainer for Lazy<T> {
+    fn from_container(container: &Container) -> Result<Self> {
+        Ok(Lazy::new(container))
+    }
+}
+
+/// What a constructor may return: `T`, or a `Result` whose error converts
+/// into [`MeshestraError`]
+pub trait Constructed<T> {
+    fn into_result(self) -> Result<T>;
+}
+
+impl<T> Constructed<T> for T {
+    fn into_result(self) -> Result<T> {
+        Ok(self)
+    }
+}
+
+impl<T, E: Into<MeshestraError>> Constructed<T> for std::result::Result<T, E> {
+    fn into_result(self) -> Result<T> {
+        self.map_err(Into::into)
+    }
+}
+
+/// A function building a `T` from parameters the container resolves;
+/// `Args` is the tuple of its parameter types
+pub trait Constructor<T, Args> {
+    fn construct(self, container: &Container) -> Result<T>;
+}
+
+macro_rules! impl_constructor {
+    ($($arg:ident),*) => {
+        impl<F, R, T, $($arg,)*> Constructor<T, ($($arg,)*)> for F
+        where
+            F: FnOnce($($arg),*) -> R,
+            R: Constructed<T>,
+            $($arg: FromContainer,)*
+        {
+            #[allow(unused_variables)]
+            fn construct(self, container: &Container) -> Result<T> {
+                self($(<$arg as FromContainer>::from_container(container)?),*).into_result()
+            }
+        }
+    };
+}
+
+impl_constructor!();
+impl_constructor!(A1);
+impl_constructor!(A1, A2);
+impl_constructor!(A1, A2, A3);
+impl_constructor!(A1, A2, A3, A4);
+impl_constructor!(A1, A2, A3, A4, A5);
+impl_constructor!(A1, A2, A3, A4, A5, A6);
+impl_constructor!(A1, A2, A3, A4, A5, A6, A7);
+impl_constructor!(A1, A2, A3, A4, A5, A6, A7, A8);
+
+/// Call `constructor` with its parameters resolved from `container`
+///
+/// ```
+/// use meshestra::di::{Container, construct};
+/// use std::sync::Arc;
+///
+/// struct Prefix(&'static str);
+///
+/// struct Greeter {
+///     greeting: String,
+/// }
+///
+/// impl Greeter {
+///     fn new(prefix: Arc<Prefix>) -> Self {
+///         Greeter { greeting: format!("{}, world", prefix.0) }
+///     }
+/// }
+///
+/// let mut container = Container::new();
+/// container.register(Prefix("hello"));
+/// let greeter: Greeter = construct(&container, Greeter::new).unwrap();
+/// assert_eq!(greeter.greeting, "hello, world");
+/// ```
+pub fn construct<T, Args>(
+    container: &Container,
+    constructor: impl Constructor<T, Args>,
+) -> Result<T> {
+    constructor.construct(container)
+}
@@ -1,17 +1,87 @@
+use crate::di::{Dependency, Injectable, MissingDependency, ServiceHandle, ValidationReport};
 use crate::error::{MeshestraError, Result};
-use dashmap::DashMap;
+use crate::transactional::{NamedManagers, TransactionManager};
+use dashmap::{DashMap, DashSet};
 use std::any::{Any, TypeId};
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 /// Type alias for a function that can cast an `Arc<dyn Any>` to another `Arc<dyn Any>`.
 /// The inner value is usually an `Arc<dyn Trait>`.
 type CasterFn = Arc<dyn Fn(Arc<dyn Any + Send + Sync>) -> Arc<dyn Any + Send + Sync> + Send + Sync>;
 
+/// Source of [`Container::revision`]s
+static REVISIONS: AtomicU64 = AtomicU64::new(0);
+
+fn next_revision() -> u64 {
+    REVISIONS.fetch_add(1, Ordering::Relaxed)
+}
+
 /// Thread-safe dependency injection container.
 pub struct Container {
     services: DashMap<TypeId, ServiceEntry>,
     trait_mappings: DashMap<TypeId, TypeId>,
     casters: DashMap<TypeId, CasterFn>,
+    /// Types and traits whose registration is fixed, see
+    /// [`Container::override_with`]
+    overrides: DashSet<TypeId>,
+    /// What registering a registered type again does
+    policy: RegistrationPolicy,
+    /// The module each type was registered by, for the duplicate warning
+    origins: DashMap<TypeId, &'static str>,
+    /// The module being registered, see [`Container::enter_module`]
+    module: Option<&'static str>,
+    /// The global modules registered, see [`Container::enter_global`]
+    global_modules: DashSet<TypeId>,
+    /// The global module exporting each type
+    global_exports: DashMap<TypeId, &'static str>,
+    /// The name and dependencies of the services declared with
+    /// [`Container::declare`]
+    dependencies: DashMap<TypeId, (&'static str, Vec<Dependency>)>,
+    /// The registrations frozen by [`Container::seal`], resolved from
+    /// instead of the maps above until the next registration
+    sealed: Option<Arc<Sealed>>,
+    /// Identifies the registrations, see [`Container::revision`]
+    revision: u64,
+}
+
+/// The registrations of a sealed [`Container`], read without locking
+struct Sealed {
+    services: HashMap<TypeId, ServiceEntry>,
+    trait_mappings: HashMap<TypeId, TypeId>,
+    casters: HashMap<TypeId, CasterFn>,
+}
+
+/// Who registered a type outside of `#[module]` registration, in messages
+const DIRECT: &str = "a direct registration";
+
+/// What [`Container::register`] does with a type that's registered already
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RegistrationPolicy {
+    /// Replace the instance, the default
+    #[default]
+    Replace,
+    /// Keep the first instance
+    Skip,
+    /// Keep the first instance, and have [`Container::try_register`] and
+    /// `#[module]` registration fail
+    Error,
+}
+
+/// What became of an instance given to [`Container::register`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Registration {
+    /// The type wasn't registered
+    Added,
+    /// The instance replaced the registered one
+    Replaced,
+    /// The type is overridden, or registered and the policy is
+    /// [`RegistrationPolicy::Skip`]; the instance was dropped
+    Skipped,
+    /// The type is registered and the policy is [`RegistrationPolicy::Error`];
+    /// the instance was dropped
+    Rejected,
 }
 
 impl Clone for Container {
@@ -20,6 +90,15 @@ impl Clone for Container {
             services: self.services.clone(),
             trait_mappings: self.trait_mappings.clone(),
             casters: self.casters.clone(),
+            overrides: self.overrides.clone(),
+            policy: self.policy,
+            origins: self.origins.clone(),
+            module: self.module,
+            global_modules: self.global_modules.clone(),
+            global_exports: self.global_exports.clone(),
+            dependencies: self.dependencies.clone(),
+            sealed: self.sealed.clone(),
+            revision: next_revision(),
         }
     }
 }
@@ -27,6 +106,8 @@ impl Clone for Container {
 #[derive(Clone)]
 struct ServiceEntry {
     instance: Arc<dyn Any + Send + Sync>,
+    /// `instance` as an `Arc<T>`, for [`Container::resolve_arc`]
+    shared: Arc<dyn Any + Send + Sync>,
 }
 
 impl Container {
@@ -35,16 +116,148 @@ impl Container {
             services: DashMap::new(),
             trait_mappings: DashMap::new(),
             casters: DashMap::new(),
+            overrides: DashSet::new(),
+            policy: RegistrationPolicy::default(),
+            origins: DashMap::new(),
+            module: None,
+            global_modules: DashSet::new(),
+            global_exports: DashMap::new(),
+            dependencies: DashMap::new(),
+            sealed: None,
+            revision: next_revision(),
         }
     }
 
-    pub fn register<T: 'static + Send + Sync>(&mut self, instance: T) -> &mut Self {
+    /// Set what registering a registered type again does, see
+    /// [`ContainerBuilder::on_duplicate`](crate::di::ContainerBuilder::on_duplicate)
+    pub fn set_registration_policy(&mut self, policy: RegistrationPolicy) -> &mut Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn registration_policy(&self) -> RegistrationPolicy {
+        self.policy
+    }
+
+    /// Register `instance` as the `T`; ignored if `T` is overridden
+    ///
+    /// When `T` is registered already, the [`RegistrationPolicy`] decides
+    /// whether `instance` replaces it. During `#[module]` registration, a
+    /// duplicate is logged as a warning naming both modules.
+    pub fn register<T: 'static + Send + Sync>(&mut self, instance: T) -> Registration {
         let type_id = TypeId::of::<T>();
+        if self.overrides.contains(&type_id) {
+            return Registration::Skipped;
+        }
+        let outcome = if !self.services.contains_key(&type_id) {
+            Registration::Added
+        } else if self.module.is_some() && self.origins.get(&type_id).map(|m| *m) == self.module {
+            // A module imported along several paths registers its providers
+            // again.
+            Registration::Replaced
+        } else {
+            if let Some(module) = self.module {
+                tracing::warn!(
+                    type_name = std::any::type_name::<T>(),
+                    registered_by = self.origin(type_id),
+                    module,
+                    policy = ?self.policy,
+                    "A provider is registered twice"
+                );
+            }
+            match self.policy {
+                RegistrationPolicy::Replace => Registration::Replaced,
+                RegistrationPolicy::Skip => return Registration::Skipped,
+                RegistrationPolicy::Error => return Registration::Rejected,
+            }
+        };
+        self.insert(instance);
+        outcome
+    }
+
+    /// [`register`](Self::register), failing when the instance is
+    /// [`Rejected`](Registration::Rejected)
+    pub fn try_register<T: 'static + Send + Sync>(&mut self, instance: T) -> Result<Registration> {
+        match self.register(instance) {
+            Registration::Rejected => Err(MeshestraError::ModuleRegistrationFailed {
+                message: format!(
+                    "{} is registered by both {} and {}",
+                    std::any::type_name::<T>(),
+                    self.origin(TypeId::of::<T>()),
+                    self.module.unwrap_or(DIRECT)
+                ),
+            }),
+            outcome => Ok(outcome),
+        }
+    }
+
+    /// Store `instance` as the `T`, whatever is registered
+    fn insert<T: 'static + Send + Sync>(&mut self, instance: T) {
+        let type_id = TypeId::of::<T>();
+        let instance = Arc::new(instance);
         let entry = ServiceEntry {
-            instance: Arc::new(instance),
+            instance: instance.clone(),
+            shared: Arc::new(instance),
         };
         self.services.insert(type_id, entry);
-        self
+        self.sealed = None;
+        self.revision = next_revision();
+        if let Some(module) = self.module {
+            self.origins.insert(type_id, module);
+        } else {
+            self.origins.remove(&type_id);
+        }
+    }
+
+    /// The module `type_id` was registered by
+    fn origin(&self, type_id: TypeId) -> &'static str {
+        self.origins.get(&type_id).map_or(DIRECT, |module| *module)
+    }
+
+    /// Attribute the registrations from now on to `module`, returning the
+    /// module they were attributed to; called by `#[module]`
+    #[doc(hidden)]
+    pub fn enter_module(&mut self, module: &'static str) -> Option<&'static str> {
+        self.module.replace(module)
+    }
+
+    /// Attribute the registrations from now on to `module` again, the value
+    /// [`enter_module`](Self::enter_module) returned
+    #[doc(hidden)]
+    pub fn exit_module(&mut self, module: Option<&'static str>) {
+        self.module = module;
+    }
+
+    /// Record the global `module`, whose id is `module_id`, returning
+    /// whether it's registered for the first time; called by
+    /// `#[module(global = true)]`
+    ///
+    /// Fails when another global module exports one of its `exports`.
+    #[doc(hidden)]
+    pub fn enter_global(
+        &mut self,
+        module_id: TypeId,
+        module: &'static str,
+        exports: &[(TypeId, &'static str)],
+    ) -> Result<bool> {
+        if self.global_modules.contains(&module_id) {
+            return Ok(false);
+        }
+        for (type_id, name) in exports {
+            if let Some(exporter) = self.global_exports.get(type_id) {
+                return Err(MeshestraError::ModuleRegistrationFailed {
+                    message: format!(
+                        "{} is exported by both global modules {} and {}",
+                        name, *exporter, module
+                    ),
+                });
+            }
+        }
+        self.global_modules.insert(module_id);
+        for (type_id, _) in exports {
+            self.global_exports.insert(*type_id, module);
+        }
+        Ok(true)
     }
 
     pub fn register_trait<Trait, Impl, F>(&mut self, caster_fn: F) -> &mut Self
@@ -55,6 +268,9 @@ impl Container {
     {
         let trait_id = TypeId::of::<Trait>();
         let impl_id = TypeId::of::<Impl>();
+        if self.overrides.contains(&trait_id) {
+            return self;
+        }
 
         self.trait_mappings.insert(trait_id, impl_id);
 
@@ -67,19 +283,62 @@ impl Container {
         });
 
         self.casters.insert(trait_id, caster);
+        self.sealed = None;
+        self.revision = next_revision();
+        self
+    }
+
+    /// Register `instance` as the `T` for good: later registrations of `T`
+    /// are ignored, and `#[module]` skips injecting its own provider
+    ///
+    /// For tests replacing a provider before the modules are registered:
+    ///
+    /// ```rust,ignore
+    /// let mut container = Container::new();
+    /// container.override_with(FakeClock::at(noon));
+    /// AppModule::register(&mut container)?;
+    /// ```
+    pub fn override_with<T: 'static + Send + Sync>(&mut self, instance: T) -> &mut Self {
+        self.insert(instance);
+        self.overrides.insert(TypeId::of::<T>());
         self
     }
 
+    /// [`override_with`](Self::override_with) for the trait `Trait`: bind it
+    /// to `instance` for good
+    ///
+    /// ```rust,ignore
+    /// container.override_trait::<dyn UserRepository, InMemoryUserRepository, _>(
+    ///     InMemoryUserRepository::default(),
+    ///     |repository| repository as Arc<dyn UserRepository>,
+    /// );
+    /// ```
+    pub fn override_trait<Trait, Impl, F>(&mut self, instance: Impl, caster_fn: F) -> &mut Self
+    where
+        Trait: ?Sized + 'static + Send + Sync,
+        Impl: 'static + Send + Sync,
+        F: Fn(Arc<Impl>) -> Arc<Trait> + 'static + Send + Sync,
+    {
+        self.overrides.remove(&TypeId::of::<Trait>());
+        self.override_with(instance);
+        self.register_trait::<Trait, Impl, F>(caster_fn);
+        self.overrides.insert(TypeId::of::<Trait>());
+        self
+    }
+
+    /// Whether `T`, a type or a trait, is overridden
+    pub fn is_overridden<T: ?Sized + 'static>(&self) -> bool {
+        self.overrides.contains(&TypeId::of::<T>())
+    }
+
     pub fn resolve<T: 'static + Send + Sync>(&self) -> Result<Arc<T>> {
         let requested_type_id = TypeId::of::<T>();
-        let entry = self.services.get(&requested_type_id).ok_or_else(|| {
-            MeshestraError::DependencyNotFound {
+        let instance = self
+            .with_entry(requested_type_id, |entry| entry.instance.clone())
+            .ok_or_else(|| MeshestraError::DependencyNotFound {
                 type_name: std::any::type_name::<T>().to_string(),
-            }
-        })?;
-        entry
-            .instance
-            .clone()
+            })?;
+        instance
             .downcast::<T>()
             .map_err(|_| MeshestraError::DowncastFailed {
                 type_name: std::any::type_name::<T>().to_string(),
@@ -89,13 +348,13 @@ impl Container {
     pub fn resolve_trait<T: ?Sized + 'static + Send + Sync>(&self) -> Result<Arc<T>> {
         let requested_type_id = TypeId::of::<T>();
 
-        let caster = self.casters.get(&requested_type_id).ok_or_else(|| {
-            MeshestraError::DependencyNotFound {
-                type_name: std::any::type_name::<T>().to_string(),
-            }
-        })?;
+        let caster =
+            self.caster(requested_type_id)
+                .ok_or_else(|| MeshestraError::DependencyNotFound {
+                    type_name: std::any::type_name::<T>().to_string(),
+                })?;
 
-        let impl_type_id = self.trait_mappings.get(&requested_type_id).ok_or_else(|| {
+        let impl_type_id = self.implementation(requested_type_id).ok_or_else(|| {
             MeshestraError::DependencyNotFound {
                 type_name: format!(
                     "No implementation mapping found for trait '{}'",
@@ -104,17 +363,16 @@ impl Container {
             }
         })?;
 
-        let entry =
-            self.services
-                .get(&impl_type_id)
-                .ok_or_else(|| MeshestraError::DependencyNotFound {
-                    type_name: format!(
-                        "Implementation for trait '{}' not registered",
-                        std::any::type_name::<T>()
-                    ),
-                })?;
+        let instance = self
+            .with_entry(impl_type_id, |entry| entry.instance.clone())
+            .ok_or_else(|| MeshestraError::DependencyNotFound {
+                type_name: format!(
+                    "Implementation for trait '{}' not registered",
+                    std::any::type_name::<T>()
+                ),
+            })?;
 
-        let cast_result = (caster.value())(entry.instance.clone());
+        let cast_result = caster(instance);
 
         // The caster returns an Arc<dyn Any> which holds an Arc<T>.
         // We need to downcast to Arc<T>, which is Sized.
@@ -131,6 +389,169 @@ impl Container {
         Ok(wrapper.as_ref().clone())
     }
 
+    /// [`resolve`](Self::resolve) or [`resolve_trait`](Self::resolve_trait),
+    /// whichever `T` needs, for code that can't tell a trait from a type
+    pub fn resolve_arc<T: ?Sized + 'static + Send + Sync>(&self) -> Result<Arc<T>> {
+        let requested_type_id = TypeId::of::<T>();
+        if self.caster(requested_type_id).is_some() {
+            return self.resolve_trait::<T>();
+        }
+        self.with_entry(requested_type_id, |entry| {
+            entry.shared.downcast_ref::<Arc<T>>().cloned()
+        })
+        .ok_or_else(|| MeshestraError::DependencyNotFound {
+            type_name: std::any::type_name::<T>().to_string(),
+        })?
+        .ok_or_else(|| MeshestraError::DowncastFailed {
+            type_name: std::any::type_name::<T>().to_string(),
+        })
+    }
+
+    /// A handle to `T`, a type or a bound trait, resolved now and cloned
+    /// from then on without going back to the container
+    ///
+    /// `#[controller]` injects its fields through handles, so they can be
+    /// `Arc<dyn Trait>`s too.
+    pub fn handle<T: ?Sized + 'static + Send + Sync>(&self) -> Result<ServiceHandle<T>> {
+        self.resolve_arc::<T>().map(ServiceHandle::new)
+    }
+
+    /// Freeze the registrations, so resolving reads them without locking
+    ///
+    /// [`ApplicationBuilder::build`](crate::lifecycle::ApplicationBuilder::build)
+    /// seals the application's container once its registrations are done.
+    /// A later registration unseals it; sealing again takes it in.
+    pub fn seal(&mut self) {
+        self.sealed = Some(Arc::new(Sealed {
+            services: self
+                .services
+                .iter()
+                .map(|entry| (*entry.key(), entry.value().clone()))
+                .collect(),
+            trait_mappings: self
+                .trait_mappings
+                .iter()
+                .map(|entry| (*entry.key(), *entry.value()))
+                .collect(),
+            casters: self
+                .casters
+                .iter()
+                .map(|entry| (*entry.key(), entry.value().clone()))
+                .collect(),
+        }));
+    }
+
+    /// Whether [`seal`](Self::seal) was called since the last registration
+    pub fn is_sealed(&self) -> bool {
+        self.sealed.is_some()
+    }
+
+    /// Unique to this container and its registrations: every container,
+    /// clones included, gets a new one, and so does every registration
+    pub(crate) fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// `f` of the entry of `type_id`, if registered
+    fn with_entry<R>(&self, type_id: TypeId, f: impl FnOnce(&ServiceEntry) -> R) -> Option<R> {
+        match &self.sealed {
+            Some(sealed) => sealed.services.get(&type_id).map(f),
+            None => self.services.get(&type_id).map(|entry| f(&entry)),
+        }
+    }
+
+    /// The caster of the trait `trait_id`, if bound
+    fn caster(&self, trait_id: TypeId) -> Option<CasterFn> {
+        match &self.sealed {
+            Some(sealed) => sealed.casters.get(&trait_id).cloned(),
+            None => self.casters.get(&trait_id).map(|caster| caster.clone()),
+        }
+    }
+
+    /// The type the trait `trait_id` is bound to, if bound
+    fn implementation(&self, trait_id: TypeId) -> Option<TypeId> {
+        match &self.sealed {
+            Some(sealed) => sealed.trait_mappings.get(&trait_id).copied(),
+            None => self.trait_mappings.get(&trait_id).map(|id| *id),
+        }
+    }
+
+    /// Register a transaction manager for a secondary datasource under `name`
+    ///
+    /// `#[transactional(manager_name = "...")]` and
+    /// [`get_current_transaction_for`](crate::transactional::get_current_transaction_for)
+    /// find it by that name. The managers are kept in this container's
+    /// [`NamedManagers`] provider, which `Application::router` installs for
+    /// each request.
+    ///
+    /// # Errors
+    /// Returns the error of resolving `NamedManagers`, when registering it
+    /// was rejected say; the manager is not kept then.
+    pub fn register_named_manager(
+        &mut self,
+        name: impl Into<Arc<str>>,
+        manager: Arc<dyn TransactionManager>,
+    ) -> Result<&mut Self> {
+        if !self.contains::<NamedManagers>() {
+            self.register(NamedManagers::default());
+        }
+        self.resolve::<NamedManagers>()?.insert(name, manager);
+        Ok(self)
+    }
+
+    /// Record the [`dependencies`](Injectable::dependencies) of `T` for
+    /// [`validate`](Self::validate); `#[module]` declares its providers and
+    /// controllers
+    pub fn declare<T: Injectable>(&mut self) -> &mut Self {
+        self.dependencies.insert(
+            TypeId::of::<T>(),
+            (std::any::type_name::<T>(), T::dependencies()),
+        );
+        self
+    }
+
+    /// Check that the dependencies of every [`declare`](Self::declare)d
+    /// service are registered, as types or as traits bound to a registered
+    /// implementation
+    ///
+    /// A `Lazy` dependency is otherwise only resolved when first used, on a
+    /// request say.
+    ///
+    /// # Errors
+    /// Returns every service and dependency that isn't registered.
+    pub fn validate(&self) -> std::result::Result<(), ValidationReport> {
+        let mut missing: Vec<MissingDependency> = self
+            .dependencies
+            .iter()
+            .flat_map(|entry| {
+                let (service, dependencies) = entry.value();
+                dependencies
+                    .iter()
+                    .filter(|dependency| !self.is_registered(dependency.type_id))
+                    .map(|dependency| MissingDependency {
+                        service,
+                        dependency: dependency.type_name,
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        if missing.is_empty() {
+            return Ok(());
+        }
+        missing.sort();
+        Err(ValidationReport { missing })
+    }
+
+    /// Whether `type_id` resolves, as a type or as a bound trait
+    fn is_registered(&self, type_id: TypeId) -> bool {
+        if self.services.contains_key(&type_id) {
+            return true;
+        }
+        self.trait_mappings
+            .get(&type_id)
+            .is_some_and(|implementation| self.services.contains_key(&*implementation))
+    }
+
     pub fn contains<T: 'static>(&self) -> bool {
         let type_id = TypeId::of::<T>();
         self.services.contains_key(&type_id) || self.trait_mappings.contains_key(&type_id)
@@ -189,4 +610,194 @@ mod tests {
         let trait_instance = container.resolve_trait::<dyn MyTrait>().unwrap();
         assert_eq!(trait_instance.get_value(), 99);
     }
+
+    #[test]
+    fn test_resolve_arc_resolves_types_and_traits() {
+        let mut container = Container::new();
+        container.register(TestService { value: 7 });
+        container.register(MyTraitImpl { value: 99 });
+        container.register_trait::<dyn MyTrait, MyTraitImpl, _>(|i| i as Arc<dyn MyTrait>);
+        assert_eq!(container.resolve_arc::<TestService>().unwrap().value, 7);
+        assert_eq!(
+            container.resolve_arc::<dyn MyTrait>().unwrap().get_value(),
+            99
+        );
+    }
+
+    #[test]
+    fn overrides_outlast_later_registrations() {
+        let mut container = Container::new();
+        container.override_with(TestService { value: 1 });
+        container.override_trait::<dyn MyTrait, MyTraitImpl, _>(MyTraitImpl { value: 2 }, |i| {
+            i as Arc<dyn MyTrait>
+        });
+
+        container.register(TestService { value: 10 });
+        container.register(MyTraitImpl { value: 20 });
+        container.register_trait::<dyn MyTrait, MyTraitImpl, _>(|i| i as Arc<dyn MyTrait>);
+
+        assert_eq!(container.resolve::<TestService>().unwrap().value, 1);
+        assert_eq!(
+            container
+                .resolve_trait::<dyn MyTrait>()
+                .unwrap()
+                .get_value(),
+            2
+        );
+        assert!(container.is_overridden::<dyn MyTrait>());
+        assert!(!container.is_overridden::<String>());
+    }
+
+    #[test]
+    fn duplicates_follow_the_registration_policy() {
+        let mut container = Container::new();
+        assert_eq!(
+            container.register(TestService { value: 1 }),
+            Registration::Added
+        );
+        assert_eq!(
+            container.register(TestService { value: 2 }),
+            Registration::Replaced
+        );
+        assert_eq!(container.resolve::<TestService>().unwrap().value, 2);
+
+        container.set_registration_policy(RegistrationPolicy::Skip);
+        assert_eq!(
+            container.register(TestService { value: 3 }),
+            Registration::Skipped
+        );
+        assert_eq!(container.resolve::<TestService>().unwrap().value, 2);
+
+        container.set_registration_policy(RegistrationPolicy::Error);
+        assert_eq!(
+            container.register(TestService { value: 4 }),
+            Registration::Rejected
+        );
+        assert!(container.try_register(TestService { value: 5 }).is_err());
+        assert_eq!(container.resolve::<TestService>().unwrap().value, 2);
+    }
+
+    #[test]
+    fn overridden_types_are_skipped_whatever_the_policy() {
+        let mut container = Container::new();
+        container.set_registration_policy(RegistrationPolicy::Error);
+        container.override_with(TestService { value: 1 });
+        container.override_with(TestService { value: 2 });
+
+        assert_eq!(
+            container.try_register(TestService { value: 3 }).unwrap(),
+            Registration::Skipped
+        );
+        assert_eq!(container.resolve::<TestService>().unwrap().value, 2);
+    }
+
+    /// Needs a `TestService` and a `dyn MyTrait`
+    struct Consumer;
+
+    impl Injectable for Consumer {
+        fn inject(_: &Container) -> Result<Self> {
+            Ok(Consumer)
+        }
+
+        fn dependencies() -> Vec<Dependency> {
+            vec![
+                Dependency::of::<TestService>(),
+                Dependency::of::<dyn MyTrait>(),
+            ]
+        }
+    }
+
+    #[test]
+    fn validate_reports_every_unregistered_dependency() {
+        let mut container = Container::new();
+        container.register(Consumer);
+        container.declare::<Consumer>();
+        // Bound, but to an implementation that isn't registered
+        container.register_trait::<dyn MyTrait, MyTraitImpl, _>(|i| i as Arc<dyn MyTrait>);
+
+        let report = container.validate().unwrap_err();
+        let missing: Vec<_> = report.missing.iter().map(|m| m.dependency).collect();
+        assert_eq!(
+            missing,
+            [
+                std::any::type_name::<dyn MyTrait>(),
+                std::any::type_name::<TestService>()
+            ]
+        );
+
+        container.register(TestService { value: 1 });
+        container.register(MyTraitImpl { value: 2 });
+        assert!(container.validate().is_ok());
+    }
+
+    #[test]
+    fn sealed_containers_resolve_until_the_next_registration() {
+        let mut container = Container::new();
+        container.register(TestService { value: 7 });
+        container.register(MyTraitImpl { value: 99 });
+        container.register_trait::<dyn MyTrait, MyTraitImpl, _>(|i| i as Arc<dyn MyTrait>);
+        container.seal();
+
+        assert!(container.is_sealed());
+        assert_eq!(container.resolve::<TestService>().unwrap().value, 7);
+        assert_eq!(
+            container
+                .resolve_trait::<dyn MyTrait>()
+                .unwrap()
+                .get_value(),
+            99
+        );
+        assert!(container.resolve::<String>().is_err());
+
+        container.register(TestService { value: 8 });
+        assert!(!container.is_sealed());
+        assert_eq!(container.resolve::<TestService>().unwrap().value, 8);
+    }
+
+    #[test]
+    fn handles_resolve_types_and_traits_once() {
+        let mut container = Container::new();
+        container.register(TestService { value: 7 });
+        container.register(MyTraitImpl { value: 99 });
+        container.register_trait::<dyn MyTrait, MyTraitImpl, _>(|i| i as Arc<dyn MyTrait>);
+
+        let service = container.handle::<TestService>().unwrap();
+        let clone = service.clone();
+        assert!(Arc::ptr_eq(service.arc(), clone.arc()));
+        assert_eq!(clone.value, 7);
+        assert_eq!(container.handle::<dyn MyTrait>().unwrap().get_value(), 99);
+        assert!(container.handle::<String>().is_err());
+    }
+
+    /// `cargo test --release -p meshestra resolve_throughput -- --ignored --nocapture`
+    #[test]
+    #[ignore = "benchmark"]
+    fn resolve_throughput() {
+        const THREADS: usize = 16;
+        const RESOLVES: u32 = 1_000_000;
+        for sealed in [false, true] {
+            let mut container = Container::new();
+            container.register(TestService { value: 7 });
+            if sealed {
+                container.seal();
+            }
+            let start = std::time::Instant::now();
+            std::thread::scope(|scope| {
+                for _ in 0..THREADS {
+                    scope.spawn(|| {
+                        for _ in 0..RESOLVES {
+                            assert_eq!(container.resolve::<TestService>().unwrap().value, 7);
+                        }
+                    });
+                }
+            });
+            let resolves = f64::from(RESOLVES) * THREADS as f64;
+            println!(
+                "sealed: {}, {:.0} resolves/s on {} threads",
+                sealed,
+                resolves / start.elapsed().as_secs_f64(),
+                THREADS
+            );
+        }
+    }
 }
@@ -0,0 +1,57 @@
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// A service resolved once from the container, see
+/// [`Container::handle`](crate::di::Container::handle)
+///
+/// Cloning it clones an `Arc`, without going back to the container; it
+/// derefs to the service, and converts into the `Arc<T>` it holds.
+pub struct ServiceHandle<T: ?Sized> {
+    service: Arc<T>,
+}
+
+impl<T: ?Sized> ServiceHandle<T> {
+    pub(crate) fn new(service: Arc<T>) -> Self {
+        Self { service }
+    }
+
+    /// The shared service
+    pub fn arc(&self) -> &Arc<T> {
+        &self.service
+    }
+
+    pub fn into_arc(self) -> Arc<T> {
+        self.service
+    }
+}
+
+impl<T: ?Sized> Clone for ServiceHandle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            service: self.service.clone(),
+        }
+    }
+}
+
+impl<T: ?Sized> Deref for ServiceHandle<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.service
+    }
+}
+
+impl<T: ?Sized> From<ServiceHandle<T>> for Arc<T> {
+    fn from(handle: ServiceHandle<T>) -> Self {
+        handle.service
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for ServiceHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ServiceHandle")
+            .field(&std::any::type_name::<T>())
+            .finish()
+    }
+}
@@ -1,4 +1,4 @@
-use crate::di::Container;
+use crate::di::{Container, Dependency};
 use crate::error::Result;
 
 /// Trait for types that can be injected from the DI container
@@ -27,4 +27,13 @@ pub trait Injectable: Sized + Send + Sync + 'static {
     /// # Errors
     /// Returns an error if any required dependency is not found in the container.
     fn inject(container: &Container) -> Result<Self>;
+
+    /// The types and traits [`inject`](Self::inject) resolves, or a
+    /// [`Lazy`](crate::di::Lazy) field resolves later, checked by
+    /// [`Container::validate`]
+    ///
+    /// `#[derive(Injectable)]` lists its fields; none by default.
+    fn dependencies() -> Vec<Dependency> {
+        Vec::new()
+    }
 }
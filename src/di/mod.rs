@@ -1,11 +1,17 @@
 mod builder;
+mod constructor;
 mod container;
 mod extractor;
+mod handle;
 mod injectable;
 mod lazy;
+mod validation;
 
 pub use builder::ContainerBuilder;
-pub use container::Container;
+pub use constructor::{Constructed, Constructor, FromContainer, construct};
+pub use container::{Container, Registration, RegistrationPolicy};
 pub use extractor::{HasContainer, Inject};
+pub use handle::ServiceHandle;
 pub use injectable::Injectable;
 pub use lazy::Lazy;
+pub use validation::{Dependency, MissingDependency, ValidationReport};
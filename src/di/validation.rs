@@ -0,0 +1,60 @@
+use std::any::TypeId;
+use std::fmt;
+
+/// A type or trait a service needs from the container, see
+/// [`Injectable::dependencies`](crate::di::Injectable::dependencies)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dependency {
+    pub(crate) type_id: TypeId,
+    pub(crate) type_name: &'static str,
+}
+
+impl Dependency {
+    /// The dependency on `T`, a type or a `dyn Trait`
+    pub fn of<T: ?Sized + 'static>() -> Self {
+        Self {
+            type_id: TypeId::of::<T>(),
+            type_name: std::any::type_name::<T>(),
+        }
+    }
+
+    /// The name of the type or trait
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+}
+
+/// A dependency of a registered service that isn't registered
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct MissingDependency {
+    pub service: &'static str,
+    pub dependency: &'static str,
+}
+
+/// Every [`MissingDependency`] [`Container::validate`](crate::di::Container::validate)
+/// found
+///
+/// Displayed one per line:
+///
+/// ```text
+/// 2 unresolved dependencies:
+///   app::Audit needs app::Tracer
+///   app::Checkout needs dyn app::PaymentGateway
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationReport {
+    pub missing: Vec<MissingDependency>,
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let plural = if self.missing.len() == 1 { "y" } else { "ies" };
+        write!(f, "{} unresolved dependenc{}:", self.missing.len(), plural)?;
+        for missing in &self.missing {
+            write!(f, "\n  {} needs {}", missing.service, missing.dependency)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ValidationReport {}
@@ -1,3 +1,6 @@
+use crate::common::ApiResponse;
+use crate::exception::HttpException;
+use crate::transactional::TransactionError;
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, MeshestraError>;
@@ -19,6 +22,12 @@ pub enum MeshestraError {
     #[error("Module registration failed: {message}")]
     ModuleRegistrationFailed { message: String },
 
+    #[error(transparent)]
+    Transaction(#[from] TransactionError),
+
+    #[error(transparent)]
+    Http(#[from] HttpException),
+
     #[error("Internal error: {0}")]
     Internal(String),
 }
@@ -31,33 +40,25 @@ impl From<sea_orm::DbErr> for MeshestraError {
     }
 }
 
+impl MeshestraError {
+    /// The `code` of the error body answering this error
+    pub fn code(&self) -> &'static str {
+        match self {
+            MeshestraError::DependencyNotFound { .. } => "DependencyNotFound",
+            MeshestraError::DowncastFailed { .. } => "DowncastFailed",
+            MeshestraError::CircularDependency { .. } => "CircularDependency",
+            MeshestraError::ScopeMismatch { .. } => "ScopeMismatch",
+            MeshestraError::ModuleRegistrationFailed { .. } => "ModuleRegistrationFailed",
+            MeshestraError::Transaction(_) => "TransactionFailed",
+            MeshestraError::Http(_) => "HttpException",
+            MeshestraError::Internal(_) => "InternalError",
+        }
+    }
+}
+
+/// Renders the error in the same shape as `ApiResponse::error`
 impl axum::response::IntoResponse for MeshestraError {
     fn into_response(self) -> axum::response::Response {
-        let (status, message) = match &self {
-            MeshestraError::DependencyNotFound { .. } => (
-                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-                self.to_string(),
-            ),
-            MeshestraError::DowncastFailed { .. } => (
-                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-                self.to_string(),
-            ),
-            MeshestraError::CircularDependency { .. } => (
-                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-                self.to_string(),
-            ),
-            MeshestraError::ScopeMismatch { .. } => (
-                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-                self.to_string(),
-            ),
-            MeshestraError::ModuleRegistrationFailed { .. } => (
-                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-                self.to_string(),
-            ),
-            MeshestraError::Internal(msg) => {
-                (axum::http::StatusCode::INTERNAL_SERVER_ERROR, msg.clone())
-            }
-        };
-        (status, message).into_response()
+        ApiResponse::<()>::from(self).into_response()
     }
 }
@@ -0,0 +1,143 @@
+use crate::di::Container;
+use crate::guard::CurrentUser;
+use axum::{
+    body::Body,
+    extract::MatchedPath,
+    http::{Extensions, HeaderMap, Method, Request, Uri},
+};
+use std::sync::Arc;
+
+/// The request an exception was raised for
+///
+/// Captured before the handler runs, so filters can log the offending
+/// route, read extensions such as `Claims` granted by guards, or vary the
+/// response by the `Accept` header.
+///
+/// # Example
+/// ```
+/// use meshestra::exception::{ArgumentsHost, ExceptionFilter};
+/// use axum::{http::StatusCode, response::{IntoResponse, Response}};
+/// use std::error::Error;
+///
+/// struct PlainTextFilter;
+///
+/// impl ExceptionFilter for PlainTextFilter {
+///     fn catch(&self, error: Box<dyn Error + Send + Sync>, host: &ArgumentsHost) -> Response {
+///         let route = host.route().unwrap_or(host.uri().path());
+///         (StatusCode::INTERNAL_SERVER_ERROR, format!("{} failed: {}", route, error))
+///             .into_response()
+///     }
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct ArgumentsHost {
+    method: Method,
+    uri: Uri,
+    route: Option<MatchedPath>,
+    headers: HeaderMap,
+    extensions: Extensions,
+    container: Option<Arc<Container>>,
+}
+
+impl ArgumentsHost {
+    /// Capture the context of `request`
+    pub fn from_request(request: &Request<Body>) -> Self {
+        Self {
+            method: request.method().clone(),
+            uri: request.uri().clone(),
+            route: request.extensions().get::<MatchedPath>().cloned(),
+            headers: request.headers().clone(),
+            extensions: request.extensions().clone(),
+            container: None,
+        }
+    }
+
+    /// Make the container available to filters
+    pub fn with_container(mut self, container: Arc<Container>) -> Self {
+        self.container = Some(container);
+        self
+    }
+
+    pub fn method(&self) -> &Method {
+        &self.method
+    }
+
+    pub fn uri(&self) -> &Uri {
+        &self.uri
+    }
+
+    /// The matched route template, e.g. `/users/{id}`
+    pub fn route(&self) -> Option<&str> {
+        self.route.as_ref().map(MatchedPath::as_str)
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// A header's value, if present and valid UTF-8
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).and_then(|value| value.to_str().ok())
+    }
+
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    /// An extension of the request, e.g. one granted by a guard
+    pub fn extension<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        self.extensions.get::<T>()
+    }
+
+    /// The [`CurrentUser<T>`] a guard authenticated the request as, e.g. to
+    /// log who acted
+    pub fn current_user<T: Send + Sync + 'static>(&self) -> Option<&CurrentUser<T>> {
+        self.extensions.get::<CurrentUser<T>>()
+    }
+
+    /// The application's container, when the filters were given one
+    pub fn container(&self) -> Option<&Container> {
+        self.container.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::guard::Principal;
+    use axum::http::header;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct UserId(u64);
+
+    #[test]
+    fn captures_the_request_context() {
+        let mut request = Request::post("/users/7?verbose=1")
+            .header(header::ACCEPT, "text/plain")
+            .body(Body::empty())
+            .unwrap();
+        request.extensions_mut().insert(UserId(7));
+
+        let host = ArgumentsHost::from_request(&request);
+
+        assert_eq!(host.method(), Method::POST);
+        assert_eq!(host.uri().path(), "/users/7");
+        assert_eq!(host.route(), None);
+        assert_eq!(host.header("accept"), Some("text/plain"));
+        assert_eq!(host.extension::<UserId>(), Some(&UserId(7)));
+        assert!(host.container().is_none());
+    }
+
+    #[test]
+    fn exposes_the_current_user() {
+        let mut request = Request::get("/reports").body(Body::empty()).unwrap();
+        request
+            .extensions_mut()
+            .insert(CurrentUser::new("ada", ["admin"]));
+
+        let host = ArgumentsHost::from_request(&request);
+
+        assert_eq!(host.current_user::<Principal>().unwrap().id, "ada");
+        assert!(host.current_user::<UserId>().is_none());
+    }
+}
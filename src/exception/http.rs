@@ -1,56 +1,231 @@
-use crate::exception::ExceptionFilter;
-use axum::{
-    http::StatusCode,
-    response::{IntoResponse, Response},
-    Json,
-};
-use serde_json::json;
+use crate::common::{ApiResponse, StatusCode as ApiStatus};
+use crate::error::MeshestraError;
+use crate::exception::{ArgumentsHost, ExceptionFilter};
+use crate::i18n::{Localizer, MessageArgs};
+use axum::response::{IntoResponse, Response};
 use std::error::Error;
+use std::fmt;
+
+/// An error carrying the HTTP status it should be answered with
+///
+/// Raise it from any depth of service code; the default exception filter
+/// answers it with its own status instead of a 500.
+///
+/// The message is a key of the container's
+/// [`MessageResolver`](crate::i18n::MessageResolver): the filters answer
+/// with its text in the request's language, or the message itself when
+/// the resolver has none.
+///
+/// # Example
+/// ```
+/// use meshestra::exception::HttpException;
+/// use serde_json::json;
+///
+/// fn find_user(id: u64) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+///     if id == 0 {
+///         return Err(HttpException::bad_request("id must be positive")
+///             .with_details(json!({ "field": "id" })))?;
+///     }
+///     Err(HttpException::not_found("user"))?
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct HttpException {
+    status: ApiStatus,
+    message: String,
+    args: MessageArgs,
+    details: Option<serde_json::Value>,
+}
+
+impl HttpException {
+    pub fn new(status: ApiStatus, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+            args: MessageArgs::new(),
+            details: None,
+        }
+    }
+
+    /// 400 Bad Request
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(ApiStatus::BadRequest, message)
+    }
+
+    /// 401 Unauthorized
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        Self::new(ApiStatus::Unauthorized, message)
+    }
+
+    /// 403 Forbidden
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        Self::new(ApiStatus::Forbidden, message)
+    }
+
+    /// 404 Not Found
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ApiStatus::NotFound, message)
+    }
+
+    /// 409 Conflict
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::new(ApiStatus::Conflict, message)
+    }
+
+    /// 422 Unprocessable Entity
+    pub fn unprocessable_entity(message: impl Into<String>) -> Self {
+        Self::new(ApiStatus::UnprocessableEntity, message)
+    }
+
+    /// 429 Too Many Requests
+    pub fn too_many_requests(message: impl Into<String>) -> Self {
+        Self::new(ApiStatus::TooManyRequests, message)
+    }
+
+    /// 500 Internal Server Error
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(ApiStatus::InternalServerError, message)
+    }
+
+    /// Attach structured details, rendered as `error.details`
+    pub fn with_details(mut self, details: serde_json::Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    /// The values the localized message interpolates, `{name}` for the
+    /// `name` entry
+    pub fn with_args(mut self, args: MessageArgs) -> Self {
+        self.args = args;
+        self
+    }
+
+    pub fn status(&self) -> ApiStatus {
+        self.status
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn details(&self) -> Option<&serde_json::Value> {
+        self.details.as_ref()
+    }
+
+    /// The `HttpException` behind `error`, raised directly or wrapped in a
+    /// [`MeshestraError`]
+    pub fn of<'a>(error: &'a (dyn Error + Send + Sync + 'static)) -> Option<&'a HttpException> {
+        error.downcast_ref::<HttpException>().or_else(|| {
+            match error.downcast_ref::<MeshestraError>() {
+                Some(MeshestraError::Http(exception)) => Some(exception),
+                _ => None,
+            }
+        })
+    }
+
+    /// The response answering this exception
+    pub fn to_response(&self) -> Response {
+        ApiResponse::<()>::from(self.clone()).into_response()
+    }
+
+    /// The exception with its message in the locale of `localizer`
+    pub fn localize(&self, localizer: &Localizer) -> Self {
+        Self {
+            message: localizer.message(&self.message, &self.args),
+            ..self.clone()
+        }
+    }
+
+    /// The response answering this exception for the request of `host`,
+    /// in its language
+    pub fn to_localized_response(&self, host: &ArgumentsHost) -> Response {
+        self.localize(&Localizer::of(host.container(), host.headers()))
+            .to_response()
+    }
+}
+
+impl fmt::Display for HttpException {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.status, self.message)
+    }
+}
+
+impl Error for HttpException {}
+
+impl IntoResponse for HttpException {
+    fn into_response(self) -> Response {
+        self.to_response()
+    }
+}
 
 /// A default exception filter that handles common errors
+///
+/// Answers [`HttpException`]s with their status and `MeshestraError`s
+/// with their code; anything else is logged and answered with a 500.
 #[derive(Default)]
 pub struct HttpExceptionFilter;
 
 impl ExceptionFilter for HttpExceptionFilter {
-    fn catch(&self, error: Box<dyn Error + Send + Sync>) -> Response {
-        // Log the error?
-        println!("Exception intercepted: {:?}", error);
-
-        // Map error to proper status code
-        // For simplicity, everything is 500 or 400.
-        // In real app, we check if error is of specific type.
-
-        let (status, message) =
-            if let Some(meshestra_error) = error.downcast_ref::<crate::error::MeshestraError>() {
-                match meshestra_error {
-                    crate::error::MeshestraError::DependencyNotFound { .. } => (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        meshestra_error.to_string(),
-                    ),
-                    crate::error::MeshestraError::DowncastFailed { .. } => (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        meshestra_error.to_string(),
-                    ),
-                    _ => (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        meshestra_error.to_string(),
-                    ),
-                }
-            } else {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "Internal Server Error".to_string(),
-                )
-            };
-
-        (
-            status,
-            Json(json!({
-                "statusCode": status.as_u16(),
-                "message": message,
-                "timestamp": chrono::Utc::now().to_rfc3339(),
-            })),
-        )
-            .into_response()
+    fn catch(&self, error: Box<dyn Error + Send + Sync>, host: &ArgumentsHost) -> Response {
+        if let Some(exception) = HttpException::of(error.as_ref()) {
+            return exception.to_localized_response(host);
+        }
+
+        tracing::error!(
+            method = %host.method(),
+            path = host.uri().path(),
+            error = ?error,
+            "Exception intercepted"
+        );
+
+        match error.downcast::<MeshestraError>() {
+            Ok(error) => error.into_response(),
+            Err(_) => {
+                ApiResponse::<()>::error(ApiStatus::InternalServerError, "Internal Server Error")
+                    .into_response()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::to_bytes, http::StatusCode};
+    use serde_json::json;
+
+    async fn body(response: Response) -> serde_json::Value {
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn renders_an_api_response_with_its_status() {
+        let response = HttpException::conflict("email taken")
+            .with_details(json!({ "field": "email" }))
+            .into_response();
+
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        assert_eq!(
+            body(response).await,
+            json!({
+                "error": {
+                    "code": "Conflict",
+                    "message": "email taken",
+                    "details": { "field": "email" },
+                },
+                "success": false,
+            })
+        );
+    }
+
+    #[test]
+    fn is_found_behind_a_meshestra_error() {
+        let error: Box<dyn Error + Send + Sync> =
+            Box::new(MeshestraError::from(HttpException::not_found("user")));
+
+        let exception = HttpException::of(error.as_ref()).unwrap();
+        assert_eq!(exception.status() as u16, 404);
+        assert_eq!(exception.message(), "user");
     }
 }
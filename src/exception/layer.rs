@@ -0,0 +1,222 @@
+use crate::di::Container;
+use crate::exception::{Exception, ExceptionFilter, ExceptionFilters, PanicError, catch_unwind};
+use axum::{body::Body, http::Request, response::Response};
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+/// Tower layer answering errors and panics with [`ExceptionFilter`]s
+///
+/// The filters are made available to generated routes and
+/// [`InterceptorLayer`](crate::interceptor::InterceptorLayer), which hand
+/// them the errors of their interceptor chains. Panics of the wrapped
+/// service become a [`PanicError`], and [`Exception`]s returned by plain
+/// axum handlers are caught here too. Add it outside the interceptor layer.
+///
+/// # Example
+/// ```
+/// use axum::{Router, routing::get};
+/// use meshestra::exception::{ExceptionFilterLayer, http::HttpExceptionFilter};
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|| async { "ok" }))
+///     .layer(ExceptionFilterLayer::new(vec![Box::new(HttpExceptionFilter)]));
+/// ```
+#[derive(Clone)]
+pub struct ExceptionFilterLayer {
+    filters: ExceptionFilters,
+}
+
+impl ExceptionFilterLayer {
+    /// Filters are consulted in the given order
+    pub fn new(filters: Vec<Box<dyn ExceptionFilter>>) -> Self {
+        Self {
+            filters: ExceptionFilters::new(filters),
+        }
+    }
+
+    /// Make the container available through
+    /// [`ArgumentsHost::container`](crate::exception::ArgumentsHost::container)
+    pub fn with_container(mut self, container: Arc<Container>) -> Self {
+        self.filters = self.filters.with_container(container);
+        self
+    }
+}
+
+impl<S> Layer<S> for ExceptionFilterLayer {
+    type Service = ExceptionFilterMiddleware<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ExceptionFilterMiddleware {
+            inner,
+            filters: self.filters.clone(),
+        }
+    }
+}
+
+/// Service produced by [`ExceptionFilterLayer`]
+#[derive(Clone)]
+pub struct ExceptionFilterMiddleware<S> {
+    inner: S,
+    filters: ExceptionFilters,
+}
+
+impl<S> Service<Request<Body>> for ExceptionFilterMiddleware<S>
+where
+    S: Service<Request<Body>, Response = Response> + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<Body>) -> Self::Future {
+        let filters = self.filters.clone();
+        request.extensions_mut().insert(filters.clone());
+        let host = filters.host(&request);
+
+        // A panic while creating the future is as much the handler's as one
+        // while polling it.
+        let future = std::panic::catch_unwind(AssertUnwindSafe(|| self.inner.call(request)));
+
+        Box::pin(async move {
+            let result = match future {
+                Ok(future) => catch_unwind(future).await,
+                Err(payload) => Err(PanicError::from_payload(payload)),
+            };
+            match result {
+                Ok(Ok(mut response)) => match Exception::take(&mut response) {
+                    Some(error) => Ok(filters.handle(error, &host)),
+                    None => Ok(response),
+                },
+                Ok(Err(error)) => Err(error),
+                Err(panic) => Ok(filters.handle(Box::new(panic), &host)),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exception::ArgumentsHost;
+    use crate::interceptor::{Interceptor, InterceptorLayer, InterceptorResult, Next};
+    use async_trait::async_trait;
+    use axum::{Router, http::StatusCode, response::IntoResponse, routing::get};
+    use std::error::Error;
+    use std::fmt;
+    use tower::ServiceExt;
+
+    #[derive(Debug)]
+    struct NotFoundError;
+
+    impl fmt::Display for NotFoundError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("no such user")
+        }
+    }
+
+    impl Error for NotFoundError {}
+
+    /// Claims only `NotFoundError`
+    struct NotFoundFilter;
+
+    impl ExceptionFilter for NotFoundFilter {
+        fn catch(&self, error: Box<dyn Error + Send + Sync>, _host: &ArgumentsHost) -> Response {
+            (StatusCode::NOT_FOUND, error.to_string()).into_response()
+        }
+
+        fn catches(&self, error: &(dyn Error + Send + Sync + 'static)) -> bool {
+            error.is::<NotFoundError>()
+        }
+    }
+
+    /// Claims everything
+    struct Teapot;
+
+    impl ExceptionFilter for Teapot {
+        fn catch(&self, _error: Box<dyn Error + Send + Sync>, _host: &ArgumentsHost) -> Response {
+            StatusCode::IM_A_TEAPOT.into_response()
+        }
+    }
+
+    struct Fail;
+
+    #[async_trait]
+    impl Interceptor for Fail {
+        async fn intercept(&self, _request: Request<Body>, _next: Next) -> InterceptorResult {
+            Err(Box::new(NotFoundError))
+        }
+    }
+
+    async fn missing() -> Result<String, Exception> {
+        Err(NotFoundError)?
+    }
+
+    async fn broken() -> Result<String, Exception> {
+        Err(Exception::new("disk on fire"))
+    }
+
+    async fn panics() -> &'static str {
+        panic!("handler bug")
+    }
+
+    fn app(filters: Vec<Box<dyn ExceptionFilter>>) -> Router {
+        Router::new()
+            .route("/missing", get(missing))
+            .route("/broken", get(broken))
+            .route("/panics", get(panics))
+            .route(
+                "/intercepted",
+                get(|| async { "unreachable" }).layer(InterceptorLayer::new(vec![Box::new(Fail)])),
+            )
+            .layer(ExceptionFilterLayer::new(filters))
+    }
+
+    async fn status(app: Router, uri: &str) -> StatusCode {
+        let request = Request::get(uri).body(Body::empty()).unwrap();
+        app.oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn first_claiming_filter_builds_the_response() {
+        let app = app(vec![Box::new(NotFoundFilter), Box::new(Teapot)]);
+
+        assert_eq!(status(app.clone(), "/missing").await, StatusCode::NOT_FOUND);
+        assert_eq!(
+            status(app.clone(), "/intercepted").await,
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            status(app.clone(), "/broken").await,
+            StatusCode::IM_A_TEAPOT
+        );
+        assert_eq!(status(app, "/panics").await, StatusCode::IM_A_TEAPOT);
+    }
+
+    #[tokio::test]
+    async fn unclaimed_errors_get_a_json_500() {
+        let app = app(vec![Box::new(NotFoundFilter)]);
+
+        let request = Request::get("/panics").body(Body::empty()).unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["error"]["code"], "InternalServerError");
+
+        assert_eq!(
+            status(app, "/broken").await,
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+}
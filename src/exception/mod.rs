@@ -1,19 +1,221 @@
-use axum::response::Response;
+use crate::common::{ApiResponse, StatusCode};
+use crate::di::Container;
+use crate::guard::Principal;
+use axum::{
+    body::Body,
+    http::Request,
+    response::{IntoResponse, Response},
+};
 use std::error::Error;
+use std::fmt;
+use std::sync::{Arc, Mutex, PoisonError};
 
+mod host;
 pub mod http;
+mod layer;
+mod panic;
 
-/// Context for exception handling
-pub struct ArgumentsHost {
-    // In Axum, we might just need the error and maybe the request context?
-    // For now, simple error handling.
-}
+pub use host::ArgumentsHost;
+pub use http::HttpException;
+pub use layer::{ExceptionFilterLayer, ExceptionFilterMiddleware};
+pub use panic::{CatchUnwind, PanicError, catch_unwind};
 
 /// The ExceptionFilter trait
 ///
 /// Filters handle errors thrown during request processing.
 /// They must return a valid Response.
+///
+/// # Migrating from `catch(&self, error)`
+///
+/// `catch` now also receives the [`ArgumentsHost`] of the failed request.
+/// Add the parameter to existing implementations; filters that don't need
+/// it can ignore it:
+///
+/// ```ignore
+/// fn catch(&self, error: Box<dyn Error + Send + Sync>, _host: &ArgumentsHost) -> Response
+/// ```
 pub trait ExceptionFilter: Send + Sync + 'static {
     /// Catch an exception and return a response
-    fn catch(&self, error: Box<dyn Error + Send + Sync>) -> Response;
+    fn catch(&self, error: Box<dyn Error + Send + Sync>, host: &ArgumentsHost) -> Response;
+
+    /// Whether this filter handles `error`
+    ///
+    /// Filters are consulted in registration order and the first one
+    /// claiming the error builds the response. Defaults to claiming
+    /// every error.
+    fn catches(&self, _error: &(dyn Error + Send + Sync + 'static)) -> bool {
+        true
+    }
 }
+
+/// Lets filters resolved from the container (as `Arc<T>`) be registered
+impl<F: ExceptionFilter + ?Sized> ExceptionFilter for Arc<F> {
+    fn catch(&self, error: Box<dyn Error + Send + Sync>, host: &ArgumentsHost) -> Response {
+        (**self).catch(error, host)
+    }
+
+    fn catches(&self, error: &(dyn Error + Send + Sync + 'static)) -> bool {
+        (**self).catches(error)
+    }
+}
+
+/// The exception filters in effect for a request
+///
+/// [`ExceptionFilterLayer`] stores them in the request extensions, where
+/// generated routes and [`InterceptorLayer`](crate::interceptor::InterceptorLayer)
+/// pick them up to answer errors. Without any, errors get the default
+/// JSON 500.
+#[derive(Clone, Default)]
+pub struct ExceptionFilters {
+    filters: Arc<Vec<Box<dyn ExceptionFilter>>>,
+    container: Option<Arc<Container>>,
+}
+
+impl ExceptionFilters {
+    pub fn new(filters: Vec<Box<dyn ExceptionFilter>>) -> Self {
+        Self {
+            filters: Arc::new(filters),
+            container: None,
+        }
+    }
+
+    /// Make the container available through [`ArgumentsHost::container`]
+    pub fn with_container(mut self, container: Arc<Container>) -> Self {
+        self.container = Some(container);
+        self
+    }
+
+    /// The filters registered for `request`
+    pub fn of(request: &Request<Body>) -> Self {
+        request
+            .extensions()
+            .get::<ExceptionFilters>()
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Capture the context of `request` for the filters, before the
+    /// handler consumes it
+    pub fn host(&self, request: &Request<Body>) -> ArgumentsHost {
+        let host = ArgumentsHost::from_request(request);
+        match &self.container {
+            Some(container) => host.with_container(container.clone()),
+            None => host,
+        }
+    }
+
+    /// Answer `error` with the first filter claiming it
+    pub fn handle(&self, error: Box<dyn Error + Send + Sync>, host: &ArgumentsHost) -> Response {
+        match self
+            .filters
+            .iter()
+            .find(|filter| filter.catches(error.as_ref()))
+        {
+            Some(filter) => filter.catch(error, host),
+            None => default_response(error.as_ref(), host),
+        }
+    }
+}
+
+/// The response answering errors no filter claims
+///
+/// An [`HttpException`] is answered with its own status; anything else
+/// is logged and answered with a JSON 500. Release builds answer a
+/// [`PanicError`] without its message.
+pub fn default_response(
+    error: &(dyn Error + Send + Sync + 'static),
+    host: &ArgumentsHost,
+) -> Response {
+    if let Some(exception) = HttpException::of(error) {
+        return exception.to_localized_response(host);
+    }
+    tracing::error!(
+        method = %host.method(),
+        path = host.uri().path(),
+        user = host.current_user::<Principal>().map(|user| user.id.as_str()),
+        error = %error,
+        "Unhandled exception"
+    );
+    // The panic message is for the logs only, outside of debug builds
+    let message = match error.downcast_ref::<PanicError>() {
+        Some(_) if !cfg!(debug_assertions) => "Internal Server Error".to_string(),
+        _ => error.to_string(),
+    };
+    ApiResponse::<()>::error(StatusCode::InternalServerError, message).into_response()
+}
+
+/// An error returned by a handler, answered by the exception filters
+///
+/// Any error converts into it with `?`, so handlers can leave the mapping
+/// of domain errors to responses to a filter:
+///
+/// ```
+/// use meshestra::exception::Exception;
+///
+/// #[derive(Debug)]
+/// struct NotFoundError;
+///
+/// impl std::fmt::Display for NotFoundError {
+///     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+///         f.write_str("not found")
+///     }
+/// }
+///
+/// impl std::error::Error for NotFoundError {}
+///
+/// async fn find_user() -> Result<String, Exception> {
+///     Err(NotFoundError)?
+/// }
+/// ```
+pub struct Exception(Box<dyn Error + Send + Sync>);
+
+impl Exception {
+    pub fn new(error: impl Into<Box<dyn Error + Send + Sync>>) -> Self {
+        Self(error.into())
+    }
+
+    /// The error raised by the handler that produced `response`, if any
+    pub fn take(response: &mut Response) -> Option<Box<dyn Error + Send + Sync>> {
+        response
+            .extensions_mut()
+            .remove::<RaisedException>()
+            .and_then(|raised| {
+                raised
+                    .0
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .take()
+            })
+    }
+}
+
+impl<E: Error + Send + Sync + 'static> From<E> for Exception {
+    fn from(error: E) -> Self {
+        Self(Box::new(error))
+    }
+}
+
+impl fmt::Debug for Exception {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+impl IntoResponse for Exception {
+    /// The default response, carrying the error for the filters to replace it
+    fn into_response(self) -> Response {
+        let mut response = match HttpException::of(self.0.as_ref()) {
+            Some(exception) => exception.to_response(),
+            None => ApiResponse::<()>::error(StatusCode::InternalServerError, self.0.to_string())
+                .into_response(),
+        };
+        response
+            .extensions_mut()
+            .insert(RaisedException(Arc::new(Mutex::new(Some(self.0)))));
+        response
+    }
+}
+
+/// Response extension carrying an [`Exception`] out of the handler
+#[derive(Clone)]
+struct RaisedException(Arc<Mutex<Option<Box<dyn Error + Send + Sync>>>>);
@@ -0,0 +1,36 @@
+use crate::guard::GuardError;
+use axum::{extract::FromRequestParts, http::request::Parts};
+
+/// Verified token claims, inserted into the request by an authentication
+/// guard such as `JwtAuthGuard`
+///
+/// Also an extractor, so handlers behind the guard can take the claims as a
+/// parameter. Extracting it on a route no guard authenticated is answered
+/// with 401.
+///
+/// # Example
+/// ```rust,ignore
+/// #[get("/me")]
+/// #[guard(JwtAuthGuard<UserClaims>)]
+/// async fn me(&self, Claims(claims): Claims<UserClaims>) -> Json<Profile> {
+///     Json(self.users.profile(&claims.sub).await)
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Claims<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for Claims<T>
+where
+    T: Clone + Send + Sync + 'static,
+    S: Send + Sync,
+{
+    type Rejection = GuardError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<Claims<T>>()
+            .cloned()
+            .ok_or_else(|| GuardError::Unauthorized("Request is not authenticated".to_string()))
+    }
+}
@@ -0,0 +1,138 @@
+use crate::guard::{Guard, GuardContext, GuardError, GuardResult};
+use async_trait::async_trait;
+use std::sync::Arc;
+
+/// Passes when every inner guard passes
+///
+/// Guards run in order and the first denial is returned as is, so later
+/// guards (e.g. role checks) can rely on earlier ones (e.g. authentication).
+/// An empty `AllOf` allows every request.
+#[derive(Clone)]
+pub struct AllOf(pub Vec<Arc<dyn Guard>>);
+
+#[async_trait]
+impl Guard for AllOf {
+    async fn can_activate(&self, ctx: &GuardContext<'_>) -> GuardResult {
+        for guard in &self.0 {
+            guard.can_activate(ctx).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Passes when at least one inner guard passes
+///
+/// Guards run in order until one passes. When all of them deny, the error
+/// keeps the status of the last denial and its message lists every inner
+/// guard's message, so clients learn all the ways they could get in. An empty
+/// `AnyOf` denies every request.
+#[derive(Clone)]
+pub struct AnyOf(pub Vec<Arc<dyn Guard>>);
+
+#[async_trait]
+impl Guard for AnyOf {
+    async fn can_activate(&self, ctx: &GuardContext<'_>) -> GuardResult {
+        let mut denials = Vec::with_capacity(self.0.len());
+        for guard in &self.0 {
+            match guard.can_activate(ctx).await {
+                Ok(()) => return Ok(()),
+                Err(e) => denials.push(e),
+            }
+        }
+
+        let message = denials
+            .iter()
+            .map(GuardError::message)
+            .collect::<Vec<_>>()
+            .join("; ");
+        Err(match denials.pop() {
+            None => GuardError::Forbidden("No guard allows this request".to_string()),
+            Some(GuardError::Unauthorized(_)) => GuardError::Unauthorized(message),
+            Some(GuardError::Forbidden(_)) => GuardError::Forbidden(message),
+            Some(GuardError::Custom(status, _)) => GuardError::Custom(status, message),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct Allow;
+
+    #[async_trait]
+    impl Guard for Allow {
+        async fn can_activate(&self, _ctx: &GuardContext<'_>) -> GuardResult {
+            Ok(())
+        }
+    }
+
+    struct Deny(fn() -> GuardError);
+
+    #[async_trait]
+    impl Guard for Deny {
+        async fn can_activate(&self, _ctx: &GuardContext<'_>) -> GuardResult {
+            Err((self.0)())
+        }
+    }
+
+    struct Counted(Arc<AtomicUsize>);
+
+    #[async_trait]
+    impl Guard for Counted {
+        async fn can_activate(&self, _ctx: &GuardContext<'_>) -> GuardResult {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    fn not_admin() -> GuardError {
+        GuardError::Forbidden("Admin role required".into())
+    }
+
+    fn no_api_key() -> GuardError {
+        GuardError::Unauthorized("Missing x-api-key header".into())
+    }
+
+    async fn check(guard: impl Guard) -> GuardResult {
+        let ctx = GuardContext::new(Request::new(Body::empty()));
+        guard.can_activate(&ctx).await
+    }
+
+    #[tokio::test]
+    async fn all_of_stops_at_the_first_denial() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let guard = Allow.and(Deny(not_admin)).and(Counted(calls.clone()));
+
+        let error = check(guard).await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::FORBIDDEN);
+        assert_eq!(error.message(), "Admin role required");
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        assert!(check(AllOf(Vec::new())).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn any_of_passes_when_one_guard_passes() {
+        assert!(check(Deny(not_admin).or(Allow)).await.is_ok());
+        assert!(check(AnyOf(Vec::new())).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn any_of_reports_every_denial() {
+        let error = check(Deny(not_admin).or(Deny(no_api_key)))
+            .await
+            .unwrap_err();
+
+        assert_eq!(error.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            error.message(),
+            "Admin role required; Missing x-api-key header"
+        );
+    }
+}
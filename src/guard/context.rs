@@ -0,0 +1,185 @@
+use crate::di::Container;
+use crate::guard::CurrentUser;
+use crate::guard::user::Authenticated;
+use axum::{
+    body::Body,
+    extract::{FromRequestParts, MatchedPath, Query, RawPathParams},
+    http::{Extensions, HeaderMap, Method, Request, Uri, request::Parts},
+};
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+
+/// What a [`Guard`](crate::guard::Guard) can see about the request it decides on
+///
+/// Besides the request's head (its method, URI, headers and extensions; the
+/// body is set aside for the handler) this carries the matched route
+/// template, the parsed path and query parameters and, when the guard runs
+/// from `#[guard]`, the DI container, so guards such as "users may only edit
+/// their own resource" need not re-parse the path or hold their own service
+/// handles.
+pub struct GuardContext<'a> {
+    parts: Parts,
+    /// Behind a `Mutex` so the context is `Sync`: guard futures borrow it
+    /// across `.await`s
+    body: Mutex<Body>,
+    path_params: HashMap<String, String>,
+    query_params: HashMap<String, String>,
+    container: Option<&'a Container>,
+    /// Extensions granted by guards, added to the request once all passed
+    granted: Mutex<Extensions>,
+}
+
+impl<'a> GuardContext<'a> {
+    /// Context for `request`, with its query parameters parsed
+    ///
+    /// Path parameters are only known once the router matched the request;
+    /// add them with [`with_path_params`](Self::with_path_params), e.g. from
+    /// [`extract_path_params`]. Get the request back, with the extensions
+    /// granted by the guards, from [`into_request`](Self::into_request).
+    pub fn new(request: Request<Body>) -> Self {
+        let query_params = Query::<HashMap<String, String>>::try_from_uri(request.uri())
+            .map(|Query(params)| params)
+            .unwrap_or_default();
+        let (parts, body) = request.into_parts();
+
+        Self {
+            parts,
+            body: Mutex::new(body),
+            path_params: HashMap::new(),
+            query_params,
+            container: None,
+            granted: Mutex::default(),
+        }
+    }
+
+    pub fn with_path_params(mut self, path_params: HashMap<String, String>) -> Self {
+        self.path_params = path_params;
+        self
+    }
+
+    pub fn with_container(mut self, container: &'a Container) -> Self {
+        self.container = Some(container);
+        self
+    }
+
+    /// The request's head
+    pub fn parts(&self) -> &Parts {
+        &self.parts
+    }
+
+    pub fn method(&self) -> &Method {
+        &self.parts.method
+    }
+
+    pub fn uri(&self) -> &Uri {
+        &self.parts.uri
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        &self.parts.headers
+    }
+
+    /// Template of the matched route, e.g. `/users/{id}`
+    pub fn route(&self) -> Option<&str> {
+        self.parts
+            .extensions
+            .get::<MatchedPath>()
+            .map(MatchedPath::as_str)
+    }
+
+    pub fn path_params(&self) -> &HashMap<String, String> {
+        &self.path_params
+    }
+
+    pub fn path_param(&self, name: &str) -> Option<&str> {
+        self.path_params.get(name).map(String::as_str)
+    }
+
+    pub fn query_params(&self) -> &HashMap<String, String> {
+        &self.query_params
+    }
+
+    pub fn query_param(&self, name: &str) -> Option<&str> {
+        self.query_params.get(name).map(String::as_str)
+    }
+
+    /// The application container; `None` outside `#[guard]` unless the
+    /// layer was given one
+    pub fn container(&self) -> Option<&'a Container> {
+        self.container
+    }
+
+    /// Request extensions, e.g. claims inserted by an upstream layer
+    ///
+    /// Values granted by earlier guards with [`insert`](Self::insert) are not
+    /// included; use [`extension`](Self::extension) to see both.
+    pub fn extensions(&self) -> &Extensions {
+        &self.parts.extensions
+    }
+
+    /// An extension granted by an earlier guard, or else from the request
+    pub fn extension<T: Clone + Send + Sync + 'static>(&self) -> Option<T> {
+        self.granted
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get::<T>()
+            .cloned()
+            .or_else(|| self.parts.extensions.get::<T>().cloned())
+    }
+
+    /// Add `value` to the request extensions if every guard passes
+    ///
+    /// This is how an authentication guard hands the verified identity to
+    /// later guards and to the handler.
+    pub fn insert<T: Clone + Send + Sync + 'static>(&self, value: T) {
+        self.granted
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(value);
+    }
+
+    /// Grant `user` as the request's [`CurrentUser`] if every guard passes
+    ///
+    /// Unlike inserting a `CurrentUser` directly, extracting a `CurrentUser`
+    /// of another type then fails naming `T`.
+    pub fn authenticate<T: Clone + Send + Sync + 'static>(&self, user: T) {
+        let mut authenticated = self.extension::<Authenticated>().unwrap_or_default();
+        authenticated.0.push(std::any::type_name::<T>());
+        self.insert(authenticated);
+        self.insert(CurrentUser(user));
+    }
+
+    /// The request with its body and the extensions granted with
+    /// [`insert`](Self::insert)
+    pub fn into_request(self) -> Request<Body> {
+        let mut parts = self.parts;
+        parts.extensions.extend(
+            self.granted
+                .into_inner()
+                .unwrap_or_else(PoisonError::into_inner),
+        );
+        let body = self
+            .body
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner);
+        Request::from_parts(parts, body)
+    }
+}
+
+/// Take the matched route's path parameters off `request`
+///
+/// Returns an empty map when the request has not been routed yet or the
+/// route has no parameters.
+pub async fn extract_path_params(
+    request: Request<Body>,
+) -> (Request<Body>, HashMap<String, String>) {
+    let (mut parts, body) = request.into_parts();
+    let params = match RawPathParams::from_request_parts(&mut parts, &()).await {
+        Ok(params) => params
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect(),
+        Err(_) => HashMap::new(),
+    };
+    (Request::from_parts(parts, body), params)
+}
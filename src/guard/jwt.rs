@@ -0,0 +1,218 @@
+use crate::guard::{Claims, CurrentUser, Guard, GuardContext, GuardError, GuardResult};
+use async_trait::async_trait;
+use axum::http::header::AUTHORIZATION;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode, errors::ErrorKind};
+use serde::de::DeserializeOwned;
+use std::marker::PhantomData;
+
+/// Authenticates requests with a JWT from the `Authorization: Bearer` header
+///
+/// On success the decoded claims are added to the request as [`Claims<T>`]
+/// and [`CurrentUser<T>`], for later guards (through
+/// [`GuardContext::extension`]) and for handlers (as extractors). Missing headers, malformed headers, expired tokens and
+/// bad signatures are each answered with 401 and their own message.
+///
+/// # Example
+/// ```rust,ignore
+/// #[derive(Clone, Deserialize)]
+/// struct UserClaims {
+///     sub: String,
+///     exp: u64,
+/// }
+///
+/// container.register(JwtAuthGuard::<UserClaims>::new(
+///     DecodingKey::from_secret(secret.as_bytes()),
+///     &[Algorithm::HS256],
+/// ));
+/// ```
+pub struct JwtAuthGuard<T> {
+    key: DecodingKey,
+    validation: Validation,
+    current_user: Option<fn(&T) -> CurrentUser>,
+    _claims: PhantomData<fn() -> T>,
+}
+
+impl<T> JwtAuthGuard<T> {
+    /// Accept tokens signed with `key` using one of `algorithms`
+    ///
+    /// `exp` is required and checked; the audience is not, unless set with
+    /// [`audience`](Self::audience).
+    pub fn new(key: DecodingKey, algorithms: &[Algorithm]) -> Self {
+        let mut validation = Validation::new(algorithms.first().copied().unwrap_or_default());
+        validation.algorithms = algorithms.to_vec();
+        validation.validate_aud = false;
+
+        Self {
+            key,
+            validation,
+            current_user: None,
+            _claims: PhantomData,
+        }
+    }
+
+    /// Only accept tokens whose `aud` claim contains one of `audience`
+    pub fn audience(mut self, audience: &[&str]) -> Self {
+        self.validation.set_audience(audience);
+        self
+    }
+
+    /// Only accept tokens whose `iss` claim is one of `issuer`
+    pub fn issuer(mut self, issuer: &[&str]) -> Self {
+        self.validation.set_issuer(issuer);
+        self
+    }
+
+    /// Also insert the [`CurrentUser`] built from the claims, for
+    /// `#[roles(...)]` and handlers that do not care about the token format
+    pub fn current_user(mut self, from_claims: fn(&T) -> CurrentUser) -> Self {
+        self.current_user = Some(from_claims);
+        self
+    }
+
+    fn bearer_token<'r>(ctx: &'r GuardContext<'_>) -> Result<&'r str, GuardError> {
+        let header = ctx
+            .headers()
+            .get(AUTHORIZATION)
+            .ok_or_else(|| GuardError::Unauthorized("Missing Authorization header".to_string()))?;
+
+        header
+            .to_str()
+            .ok()
+            .and_then(|value| value.split_once(' '))
+            .filter(|(scheme, token)| scheme.eq_ignore_ascii_case("bearer") && !token.is_empty())
+            .map(|(_, token)| token.trim())
+            .ok_or_else(|| {
+                GuardError::Unauthorized(
+                    "Malformed Authorization header, expected `Bearer <token>`".to_string(),
+                )
+            })
+    }
+}
+
+#[async_trait]
+impl<T> Guard for JwtAuthGuard<T>
+where
+    T: DeserializeOwned + Clone + Send + Sync + 'static,
+{
+    async fn can_activate(&self, ctx: &GuardContext<'_>) -> GuardResult {
+        let token = Self::bearer_token(ctx)?;
+        let data = decode::<T>(token, &self.key, &self.validation).map_err(|e| {
+            let message = match e.kind() {
+                ErrorKind::ExpiredSignature => "Token has expired".to_string(),
+                ErrorKind::ImmatureSignature => "Token is not valid yet".to_string(),
+                ErrorKind::InvalidSignature => "Invalid token signature".to_string(),
+                ErrorKind::InvalidAlgorithm => "Token algorithm is not accepted".to_string(),
+                ErrorKind::InvalidAudience => "Invalid token audience".to_string(),
+                ErrorKind::InvalidIssuer => "Invalid token issuer".to_string(),
+                ErrorKind::MissingRequiredClaim(claim) => {
+                    format!("Token is missing the `{}` claim", claim)
+                }
+                _ => format!("Malformed token: {}", e),
+            };
+            GuardError::Unauthorized(message)
+        })?;
+
+        if let Some(from_claims) = self.current_user {
+            ctx.authenticate(from_claims(&data.claims).0);
+        }
+        ctx.authenticate(data.claims.clone());
+        ctx.insert(Claims(data.claims));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::Request};
+    use jsonwebtoken::{EncodingKey, Header, encode, get_current_timestamp};
+    use serde::{Deserialize, Serialize};
+
+    const SECRET: &[u8] = b"test-secret";
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct UserClaims {
+        sub: String,
+        exp: u64,
+    }
+
+    fn guard() -> JwtAuthGuard<UserClaims> {
+        JwtAuthGuard::new(DecodingKey::from_secret(SECRET), &[Algorithm::HS256])
+    }
+
+    fn token(exp: u64, secret: &[u8]) -> String {
+        let claims = UserClaims {
+            sub: "alice".to_string(),
+            exp,
+        };
+        encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(secret),
+        )
+        .unwrap()
+    }
+
+    async fn check(authorization: Option<&str>) -> (GuardResult, Option<Claims<UserClaims>>) {
+        let mut request = Request::builder();
+        if let Some(value) = authorization {
+            request = request.header(AUTHORIZATION, value);
+        }
+        let ctx = GuardContext::new(request.body(Body::empty()).unwrap());
+        let result = guard().can_activate(&ctx).await;
+        let claims = ctx
+            .into_request()
+            .extensions()
+            .get::<Claims<UserClaims>>()
+            .cloned();
+        (result, claims)
+    }
+
+    fn denial(result: GuardResult) -> String {
+        match result.unwrap_err() {
+            GuardError::Unauthorized(message) => message,
+            other => panic!("expected 401, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn valid_token_grants_claims() {
+        let token = token(get_current_timestamp() + 60, SECRET);
+        let (result, claims) = check(Some(&format!("Bearer {}", token))).await;
+
+        assert!(result.is_ok());
+        assert_eq!(claims.unwrap().0.sub, "alice");
+    }
+
+    #[tokio::test]
+    async fn denials_explain_what_is_wrong() {
+        let valid = token(get_current_timestamp() + 60, SECRET);
+        let expired = token(get_current_timestamp() - 3600, SECRET);
+        let forged = token(get_current_timestamp() + 60, b"other-secret");
+
+        let cases = [
+            (None, "Missing Authorization header".to_string()),
+            (
+                Some(format!("Basic {}", valid)),
+                "Malformed Authorization header, expected `Bearer <token>`".to_string(),
+            ),
+            (
+                Some(format!("Bearer {}", expired)),
+                "Token has expired".to_string(),
+            ),
+            (
+                Some(format!("Bearer {}", forged)),
+                "Invalid token signature".to_string(),
+            ),
+        ];
+
+        for (authorization, expected) in cases {
+            let (result, claims) = check(authorization.as_deref()).await;
+            assert_eq!(denial(result), expected);
+            assert!(claims.is_none());
+        }
+
+        let (result, _) = check(Some("Bearer not-a-jwt")).await;
+        assert!(denial(result).starts_with("Malformed token"));
+    }
+}
@@ -1,105 +1,206 @@
-use crate::guard::{Guard, GuardError, GuardResult};
-use async_trait::async_trait;
-use axum::{body::Body, http::Request, response::Response};
+use crate::di::Container;
+use crate::guard::{Guard, GuardContext, extract_path_params};
+use axum::{
+    body::Body,
+    http::Request,
+    response::{IntoResponse, Response},
+};
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 use tower::{Layer, Service};
-use std::sync::Arc;
 
-/// Tower Layer for Guards
+/// Tower layer running [`Guard`]s before the wrapped service
+///
+/// A denied request is answered with the guard's [`GuardError`](crate::guard::GuardError)
+/// response (401/403/...) and never reaches the service. Add it with
+/// `Router::route_layer` so guards see the matched route and path parameters.
+///
+/// # Example
+/// ```
+/// use axum::{Router, body::Body, http::Request, routing::get};
+/// use meshestra::guard::{Guard, GuardContext, GuardError, GuardLayer, GuardResult};
+/// use meshestra::async_trait;
+///
+/// struct ApiKeyGuard;
+///
+/// #[async_trait]
+/// impl Guard for ApiKeyGuard {
+///     async fn can_activate(&self, ctx: &GuardContext<'_>) -> GuardResult {
+///         match ctx.headers().get("x-api-key") {
+///             Some(_) => Ok(()),
+///             None => Err(GuardError::Unauthorized("Missing x-api-key header".into())),
+///         }
+///     }
+/// }
+///
+/// let app: Router = Router::new()
+///     .route("/reports", get(|| async { "reports" }))
+///     .route_layer(GuardLayer::new(vec![Box::new(ApiKeyGuard)]));
+/// ```
+#[derive(Clone)]
 pub struct GuardLayer {
-    guards: Vec<Box<dyn Guard>>,
-}
-
-impl GuardLayer {
-    pub fn new(guards: Vec<Box<dyn Guard>>) -> Self {
-        Self { guards }
-    }
-}
-
-impl<S> Layer<S> for GuardLayer {
-    type Service = GuardMiddleware<S>;
-
-    fn layer(&self, inner: S) -> Self::Service {
-        GuardMiddleware {
-            inner,
-            guards: Arc::new(self.guards.iter().map(|g| {
-                // We cannot clone Box<dyn Guard> easily unless we implement Clone for it?
-                // Or wrap it in Arc from start.
-                // Assuming shared ownership for now via Arc on the Vec level.
-                // But we can't clone items inside Vec unless they are Clone.
-                // We'll require Guards to be stateless mostly.
-                // Wait, I can't clone `Box<dyn Guard>` in `map`.
-                // I should wrap them in Arc? 
-                // Let's change the field to `Arc<Vec<Box<dyn Guard>>>` in Middleware.
-                // But Layer constructor took `Vec<Box>`.
-                // We'll wrap the whole Vec.
-                // But here I'm constructing a NEW vec? No.
-                unreachable!("This map logic is flawied if not cloning")
-            }).collect()),
-        }
-    }
-}
-
-// Redefine Layer correctly
-pub struct SharedGuardLayer {
     guards: Arc<Vec<Box<dyn Guard>>>,
+    container: Option<Arc<Container>>,
 }
 
-impl SharedGuardLayer {
+/// Former name of [`GuardLayer`]
+#[deprecated(note = "use `GuardLayer`")]
+pub type SharedGuardLayer = GuardLayer;
+
+impl GuardLayer {
+    /// Run `guards` in order; the first denial answers the request
     pub fn new(guards: Vec<Box<dyn Guard>>) -> Self {
         Self {
             guards: Arc::new(guards),
+            container: None,
         }
     }
+
+    /// Make `container` available to the guards through `GuardContext::container`
+    pub fn with_container(mut self, container: Arc<Container>) -> Self {
+        self.container = Some(container);
+        self
+    }
 }
 
-impl<S> Layer<S> for SharedGuardLayer {
+impl<S> Layer<S> for GuardLayer {
     type Service = GuardMiddleware<S>;
 
     fn layer(&self, inner: S) -> Self::Service {
         GuardMiddleware {
             inner,
             guards: self.guards.clone(),
+            container: self.container.clone(),
         }
     }
 }
 
+/// Service produced by [`GuardLayer`]
 #[derive(Clone)]
 pub struct GuardMiddleware<S> {
     inner: S,
     guards: Arc<Vec<Box<dyn Guard>>>,
+    container: Option<Arc<Container>>,
 }
 
 impl<S> Service<Request<Body>> for GuardMiddleware<S>
 where
     S: Service<Request<Body>, Response = Response> + Clone + Send + 'static,
     S::Future: Send + 'static,
-    S::Error: Into<Box<dyn std::error::Error + Send + Sync>> + Send,
 {
     type Response = Response;
-    type Error = Box<dyn std::error::Error + Send + Sync>;
-    type Future = Pin<Box<dyn Future<Output = Result<Response, Self::Error>> + Send>>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
 
     fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        self.inner.poll_ready(cx).map_err(Into::into)
+        self.inner.poll_ready(cx)
     }
 
     fn call(&mut self, req: Request<Body>) -> Self::Future {
         let guards = self.guards.clone();
+        let container = self.container.clone();
         let mut inner = self.inner.clone();
 
         Box::pin(async move {
+            // Path parameters are only known when the layer runs after routing,
+            // i.e. when it was added with `Router::route_layer`.
+            let (req, path_params) = extract_path_params(req).await;
+            let mut ctx = GuardContext::new(req).with_path_params(path_params);
+            if let Some(container) = &container {
+                ctx = ctx.with_container(container);
+            }
+
             for guard in guards.iter() {
-                if let Err(e) = guard.can_activate(&req).await {
-                    // Convert GuardError to Response properly?
-                    // Usually 403 Forbidden.
-                    // For now, return Error.
-                    return Err(Box::new(e) as Box<dyn std::error::Error + Send + Sync>);
+                if let Err(e) = guard.can_activate(&ctx).await {
+                    return Ok(e.into_response());
                 }
             }
-            inner.call(req).await.map_err(Into::into)
+            inner.call(ctx.into_request()).await
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::guard::{GuardError, GuardResult};
+    use async_trait::async_trait;
+    use axum::http::StatusCode;
+    use std::convert::Infallible;
+    use tower::{ServiceExt, service_fn};
+
+    struct Deny(fn() -> GuardError);
+
+    #[async_trait]
+    impl Guard for Deny {
+        async fn can_activate(&self, _ctx: &GuardContext<'_>) -> GuardResult {
+            Err((self.0)())
+        }
+    }
+
+    async fn call(layer: GuardLayer) -> Response {
+        let handler = service_fn(|_: Request<Body>| async {
+            Ok::<_, Infallible>(StatusCode::OK.into_response())
+        });
+        layer
+            .layer(handler)
+            .oneshot(Request::new(Body::empty()))
+            .await
+            .unwrap()
+    }
+
+    async fn body_json(response: Response) -> serde_json::Value {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn denied_guards_answer_with_their_status() {
+        type Case = (fn() -> GuardError, StatusCode, &'static str);
+        let cases: [Case; 3] = [
+            (
+                || GuardError::Unauthorized("no token".into()),
+                StatusCode::UNAUTHORIZED,
+                "Unauthorized",
+            ),
+            (
+                || GuardError::Forbidden("not yours".into()),
+                StatusCode::FORBIDDEN,
+                "Forbidden",
+            ),
+            (
+                || GuardError::Custom(StatusCode::TOO_MANY_REQUESTS, "slow down".into()),
+                StatusCode::TOO_MANY_REQUESTS,
+                "TooManyRequests",
+            ),
+        ];
+
+        for (error, status, code) in cases {
+            let response = call(GuardLayer::new(vec![Box::new(Deny(error))])).await;
+            assert_eq!(response.status(), status);
+
+            let body = body_json(response).await;
+            assert_eq!(body["success"], false);
+            assert_eq!(body["error"]["code"], code);
+            assert_eq!(body["error"]["message"], error().message());
+        }
+    }
+
+    #[tokio::test]
+    async fn layer_applies_to_a_router() {
+        use axum::{Router, routing::get};
+
+        let app: Router = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .route_layer(GuardLayer::new(vec![Box::new(Deny(|| {
+                GuardError::Forbidden("not yours".into())
+            }))]));
+
+        let response = app.oneshot(Request::new(Body::empty())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+}
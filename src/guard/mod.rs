@@ -1,5 +1,29 @@
+use crate::common::response::ApiResponse;
 use async_trait::async_trait;
-use axum::{body::Body, http::Request};
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+
+mod claims;
+mod combinators;
+mod context;
+#[cfg(feature = "jwt")]
+mod jwt;
+mod layer;
+mod roles;
+mod user;
+
+pub use claims::Claims;
+pub use combinators::{AllOf, AnyOf};
+pub use context::{GuardContext, extract_path_params};
+#[cfg(feature = "jwt")]
+pub use jwt::JwtAuthGuard;
+#[allow(deprecated)]
+pub use layer::{GuardLayer, GuardMiddleware, SharedGuardLayer};
+pub use roles::{RoleHierarchy, RolesGuard};
+pub use user::{CurrentUser, Principal};
 
 /// Standard Result type for Guard
 /// Ok(()) means allowed
@@ -13,11 +37,90 @@ pub enum GuardError {
 
     #[error("Unauthorized: {0}")]
     Unauthorized(String),
+
+    /// Denied with a status chosen by the guard, e.g. 429 for rate limits
+    #[error("{1}")]
+    Custom(StatusCode, String),
+}
+
+impl GuardError {
+    /// HTTP status a denied request is answered with
+    pub fn status(&self) -> StatusCode {
+        match self {
+            GuardError::Forbidden(_) => StatusCode::FORBIDDEN,
+            GuardError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            GuardError::Custom(status, _) => *status,
+        }
+    }
+
+    /// Message explaining the denial
+    pub fn message(&self) -> &str {
+        match self {
+            GuardError::Forbidden(message)
+            | GuardError::Unauthorized(message)
+            | GuardError::Custom(_, message) => message,
+        }
+    }
+}
+
+/// Renders the denial in the same shape as `ApiResponse::error`
+impl IntoResponse for GuardError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        let code = crate::common::StatusCode::from_repr(status.as_u16())
+            .map(|code| code.to_string())
+            .unwrap_or_else(|| status.as_u16().to_string());
+
+        ApiResponse::<()>::error_with_code(status, code, self.message()).into_response()
+    }
 }
 
 /// The Guard trait
 /// Implement this to protect routes
+///
+/// The [`GuardContext`] carries the request's head along with its path
+/// parameters, the container and the extensions granted by earlier guards.
 #[async_trait]
 pub trait Guard: Send + Sync + 'static {
-    async fn can_activate(&self, request: &Request<Body>) -> GuardResult;
+    async fn can_activate(&self, ctx: &GuardContext<'_>) -> GuardResult;
+
+    /// Require both this guard and `other`, checked in that order
+    fn and<G: Guard>(self, other: G) -> AllOf
+    where
+        Self: Sized,
+    {
+        AllOf(vec![Arc::new(self), Arc::new(other)])
+    }
+
+    /// Allow the request if this guard or `other` allows it
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// let guard = RoleGuard::new("admin").or(ApiKeyGuard::from_env());
+    /// ```
+    fn or<G: Guard>(self, other: G) -> AnyOf
+    where
+        Self: Sized,
+    {
+        AnyOf(vec![Arc::new(self), Arc::new(other)])
+    }
+}
+
+/// Resolve the guard `G` from the container and run it
+///
+/// This is what `#[guard(G)]` does for each listed guard, using the
+/// container from `ctx`. A guard missing from the container denies the
+/// request with a 500.
+pub async fn activate_guard<G: Guard>(ctx: &GuardContext<'_>) -> GuardResult {
+    let guard = ctx
+        .container()
+        .ok_or_else(|| "no container available".to_string())
+        .and_then(|container| container.resolve::<G>().map_err(|e| e.to_string()))
+        .map_err(|e| {
+            GuardError::Custom(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Guard could not be resolved: {}", e),
+            )
+        })?;
+    guard.can_activate(ctx).await
 }
@@ -0,0 +1,125 @@
+use crate::guard::{CurrentUser, Guard, GuardContext, GuardError, GuardResult};
+use async_trait::async_trait;
+use std::collections::{HashMap, HashSet};
+
+/// Roles implied by other roles, e.g. `admin` implies `editor`
+///
+/// Register it in the container to have [`RolesGuard`] honour it. Implied
+/// roles are followed transitively.
+///
+/// # Example
+/// ```
+/// use meshestra::guard::RoleHierarchy;
+///
+/// let hierarchy = RoleHierarchy::new()
+///     .grant("admin", ["editor"])
+///     .grant("editor", ["viewer"]);
+///
+/// assert!(hierarchy.expand(["admin"]).contains("viewer"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RoleHierarchy {
+    implied: HashMap<String, Vec<String>>,
+}
+
+impl RoleHierarchy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make `role` imply each of `implied`
+    pub fn grant(
+        mut self,
+        role: impl Into<String>,
+        implied: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.implied
+            .entry(role.into())
+            .or_default()
+            .extend(implied.into_iter().map(Into::into));
+        self
+    }
+
+    /// `roles` together with every role they imply
+    pub fn expand<'r>(&self, roles: impl IntoIterator<Item = &'r str>) -> HashSet<String> {
+        let mut expanded = HashSet::new();
+        let mut pending: Vec<String> = roles.into_iter().map(str::to_string).collect();
+        while let Some(role) = pending.pop() {
+            if let Some(implied) = self.implied.get(&role) {
+                pending.extend(
+                    implied
+                        .iter()
+                        .filter(|implied| !expanded.contains(*implied))
+                        .cloned(),
+                );
+            }
+            expanded.insert(role);
+        }
+        expanded
+    }
+}
+
+/// Allows users holding at least one of the required roles
+///
+/// `#[roles("admin", "editor")]` runs it after the route's other guards, so
+/// the authentication guard that inserts the [`CurrentUser`] must be listed
+/// in `#[guard]`. Requests without a `CurrentUser` are answered with 401 and
+/// users lacking every required role with 403 naming the missing roles.
+pub struct RolesGuard {
+    required: Vec<String>,
+}
+
+impl RolesGuard {
+    pub fn new(required: &[&str]) -> Self {
+        Self {
+            required: required.iter().map(|role| role.to_string()).collect(),
+        }
+    }
+}
+
+#[async_trait]
+impl Guard for RolesGuard {
+    async fn can_activate(&self, ctx: &GuardContext<'_>) -> GuardResult {
+        if self.required.is_empty() {
+            return Ok(());
+        }
+
+        let user = ctx
+            .extension::<CurrentUser>()
+            .ok_or_else(|| GuardError::Unauthorized("Request is not authenticated".to_string()))?;
+        let roles = user.roles.iter().map(String::as_str);
+        let held = match ctx
+            .container()
+            .and_then(|c| c.resolve::<RoleHierarchy>().ok())
+        {
+            Some(hierarchy) => hierarchy.expand(roles),
+            None => roles.map(str::to_string).collect(),
+        };
+
+        if self.required.iter().any(|role| held.contains(role)) {
+            Ok(())
+        } else {
+            Err(GuardError::Forbidden(format!(
+                "Missing required role, one of: {}",
+                self.required.join(", ")
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hierarchy_expands_transitively() {
+        let hierarchy = RoleHierarchy::new()
+            .grant("admin", ["editor"])
+            .grant("editor", ["viewer"])
+            .grant("viewer", ["admin"]);
+
+        let expanded = hierarchy.expand(["editor"]);
+        assert_eq!(expanded.len(), 3);
+        assert!(hierarchy.expand(["author"]).contains("author"));
+    }
+}
@@ -0,0 +1,173 @@
+use crate::guard::GuardError;
+use axum::extract::{FromRequestParts, OptionalFromRequestParts};
+use axum::http::{Extensions, StatusCode, request::Parts};
+use std::ops::Deref;
+
+/// The authenticated principal, inserted into the request by an
+/// authentication guard for handlers, [`RolesGuard`](crate::guard::RolesGuard)
+/// and exception filters to read
+///
+/// `T` is whatever the guard authenticates: token claims, a user loaded from
+/// the database, or by default a [`Principal`], the id and roles
+/// `#[roles(...)]` checks. Guards add it with
+/// [`GuardContext::authenticate`](crate::guard::GuardContext::authenticate).
+///
+/// It is also an extractor, or the parameter of `#[current_user]`.
+/// Extracting it on a route no guard authenticated is answered with 401, and
+/// `Option<CurrentUser<T>>` is `None` there instead. Asking for a `T` other
+/// than the one the guards inserted is a bug, answered with 500 naming both.
+///
+/// # Example
+/// ```rust,ignore
+/// #[get("/me")]
+/// #[guard(JwtAuthGuard<UserClaims>)]
+/// async fn me(&self, #[current_user] user: CurrentUser<UserClaims>) -> String {
+///     user.sub.clone()
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CurrentUser<T = Principal>(pub T);
+
+impl CurrentUser {
+    pub fn new(id: impl Into<String>, roles: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self(Principal {
+            id: id.into(),
+            roles: roles.into_iter().map(Into::into).collect(),
+        })
+    }
+}
+
+impl<T> Deref for CurrentUser<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+/// The default [`CurrentUser`]: an id and the roles `#[roles(...)]` checks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Principal {
+    pub id: String,
+    pub roles: Vec<String>,
+}
+
+/// The `T`s of the [`CurrentUser`]s granted with `GuardContext::authenticate`
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Authenticated(pub(crate) Vec<&'static str>);
+
+impl<T: Clone + Send + Sync + 'static> CurrentUser<T> {
+    /// The one in `extensions`, if the request was authenticated
+    fn from_extensions(extensions: &Extensions) -> Result<Option<Self>, GuardError> {
+        if let Some(user) = extensions.get::<Self>() {
+            return Ok(Some(user.clone()));
+        }
+        match extensions.get::<Authenticated>() {
+            Some(Authenticated(granted)) => Err(GuardError::Custom(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!(
+                    "CurrentUser<{}> is extracted, but the guards inserted CurrentUser<{}>",
+                    std::any::type_name::<T>(),
+                    granted.join(">, CurrentUser<")
+                ),
+            )),
+            None => Ok(None),
+        }
+    }
+}
+
+impl<T, S> FromRequestParts<S> for CurrentUser<T>
+where
+    T: Clone + Send + Sync + 'static,
+    S: Send + Sync,
+{
+    type Rejection = GuardError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Self::from_extensions(&parts.extensions)?
+            .ok_or_else(|| GuardError::Unauthorized("Request is not authenticated".to_string()))
+    }
+}
+
+impl<T, S> OptionalFromRequestParts<S> for CurrentUser<T>
+where
+    T: Clone + Send + Sync + 'static,
+    S: Send + Sync,
+{
+    type Rejection = GuardError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> Result<Option<Self>, Self::Rejection> {
+        Self::from_extensions(&parts.extensions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct UserClaims {
+        sub: String,
+    }
+
+    fn parts(extensions: Extensions) -> Parts {
+        let (mut parts, _) = axum::http::Request::new(()).into_parts();
+        parts.extensions = extensions;
+        parts
+    }
+
+    #[tokio::test]
+    async fn extracts_the_user_the_guards_inserted() {
+        let mut extensions = Extensions::new();
+        extensions.insert(CurrentUser(UserClaims {
+            sub: "ada".to_string(),
+        }));
+        extensions.insert(Authenticated(vec![std::any::type_name::<UserClaims>()]));
+        let mut parts = parts(extensions);
+
+        let user =
+            <CurrentUser<UserClaims> as FromRequestParts<()>>::from_request_parts(&mut parts, &())
+                .await
+                .unwrap();
+        assert_eq!(user.sub, "ada");
+    }
+
+    #[tokio::test]
+    async fn unauthenticated_requests_have_no_user() {
+        let mut parts = parts(Extensions::new());
+
+        let error = <CurrentUser as FromRequestParts<()>>::from_request_parts(&mut parts, &())
+            .await
+            .unwrap_err();
+        assert_eq!(error.status(), StatusCode::UNAUTHORIZED);
+        let user =
+            <CurrentUser as OptionalFromRequestParts<()>>::from_request_parts(&mut parts, &())
+                .await
+                .unwrap();
+        assert!(user.is_none());
+    }
+
+    #[tokio::test]
+    async fn another_user_type_is_a_server_error_naming_both() {
+        let mut extensions = Extensions::new();
+        extensions.insert(CurrentUser::new("ada", ["admin"]));
+        extensions.insert(Authenticated(vec![std::any::type_name::<Principal>()]));
+        let mut parts = parts(extensions);
+
+        let error = <CurrentUser<UserClaims> as OptionalFromRequestParts<()>>::from_request_parts(
+            &mut parts,
+            &(),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(error.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(
+            error.message(),
+            "CurrentUser<meshestra::guard::user::tests::UserClaims> is extracted, but the \
+             guards inserted CurrentUser<meshestra::guard::user::Principal>"
+        );
+    }
+}
@@ -0,0 +1,208 @@
+use super::{HealthCheckResult, HealthIndicator, register_indicator};
+use crate::di::{Container, Injectable};
+use crate::messaging::EventBus;
+use crate::transactional::TransactionManager;
+use crate::worker::WorkerPool;
+use async_trait::async_trait;
+use serde_json::json;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Pings the datasource of the registered [`TransactionManager`], see
+/// [`TransactionManager::ping`]
+///
+/// Injected from the container's `dyn TransactionManager` binding, or the
+/// global manager when there's none.
+#[derive(Clone)]
+pub struct DatabaseHealthIndicator {
+    manager: Arc<dyn TransactionManager>,
+}
+
+impl DatabaseHealthIndicator {
+    pub fn new(manager: Arc<dyn TransactionManager>) -> Self {
+        Self { manager }
+    }
+}
+
+#[async_trait]
+impl HealthIndicator for DatabaseHealthIndicator {
+    fn name(&self) -> &str {
+        "database"
+    }
+
+    async fn check(&self) -> HealthCheckResult {
+        match self.manager.ping().await {
+            Ok(()) => HealthCheckResult::up(json!({})),
+            Err(e) => HealthCheckResult::down(json!({ "error": e.to_string() })),
+        }
+    }
+}
+
+impl Injectable for DatabaseHealthIndicator {
+    fn inject(container: &Container) -> crate::Result<Self> {
+        let manager = match container.resolve_trait::<dyn TransactionManager>() {
+            Ok(manager) => manager,
+            Err(e) => crate::transactional::current_manager().map_err(|_| e)?,
+        };
+        let indicator = Self::new(manager);
+        register_indicator(container, indicator.clone());
+        Ok(indicator)
+    }
+}
+
+/// Reports the channels and subscribers of the [`EventBus`], see
+/// [`EventBusStats`](crate::messaging::EventBusStats)
+///
+/// Degraded while a channel is full, or when an event stream fell behind
+/// since the previous check.
+#[derive(Clone)]
+pub struct EventBusHealthIndicator {
+    bus: EventBus,
+    /// `lagged_receivers` at the previous check
+    lagged: Arc<AtomicU64>,
+}
+
+impl EventBusHealthIndicator {
+    pub fn new(bus: EventBus) -> Self {
+        Self {
+            bus,
+            lagged: Arc::new(AtomicU64::new(0)),
+        }
+    }
+}
+
+#[async_trait]
+impl HealthIndicator for EventBusHealthIndicator {
+    fn name(&self) -> &str {
+        "event_bus"
+    }
+
+    async fn check(&self) -> HealthCheckResult {
+        let stats = self.bus.stats();
+        let previous = self.lagged.swap(stats.lagged_receivers, Ordering::Relaxed);
+        let details = json!({
+            "channels": stats.channels,
+            "subscribers": stats.subscribers,
+            "handlers": stats.handlers,
+            "full_channels": stats.full_channels,
+            "lagged_receivers": stats.lagged_receivers,
+        });
+        if stats.full_channels > 0 || stats.lagged_receivers > previous {
+            HealthCheckResult::degraded(details)
+        } else {
+            HealthCheckResult::up(details)
+        }
+    }
+}
+
+impl Injectable for EventBusHealthIndicator {
+    fn inject(container: &Container) -> crate::Result<Self> {
+        let indicator = Self::new(container.resolve::<EventBus>()?.as_ref().clone());
+        register_indicator(container, indicator.clone());
+        Ok(indicator)
+    }
+}
+
+/// Reports the saturation of the [`WorkerPool`], see
+/// [`WorkerStats`](crate::worker::WorkerStats)
+///
+/// The saturation is the tasks running or queued per thread; the pool is
+/// degraded when more tasks are queued than it has threads, or than the
+/// limit set with [`with_max_queued`](Self::with_max_queued).
+#[derive(Clone)]
+pub struct WorkerPoolHealthIndicator {
+    pool: WorkerPool,
+    max_queued: usize,
+}
+
+impl WorkerPoolHealthIndicator {
+    pub fn new(pool: WorkerPool) -> Self {
+        let max_queued = pool.num_threads();
+        Self { pool, max_queued }
+    }
+
+    /// Report the pool degraded when more than `queued` tasks wait
+    pub fn with_max_queued(mut self, queued: usize) -> Self {
+        self.max_queued = queued;
+        self
+    }
+}
+
+#[async_trait]
+impl HealthIndicator for WorkerPoolHealthIndicator {
+    fn name(&self) -> &str {
+        "worker_pool"
+    }
+
+    async fn check(&self) -> HealthCheckResult {
+        let stats = self.pool.stats();
+        let threads = self.pool.num_threads();
+        let saturation = (stats.queued + stats.in_flight) as f64 / threads.max(1) as f64;
+        let details = json!({
+            "pool": self.pool.name(),
+            "threads": threads,
+            "queued": stats.queued,
+            "in_flight": stats.in_flight,
+            "saturation": saturation,
+            "completed": stats.completed,
+            "panics": stats.panics,
+        });
+        if stats.queued > self.max_queued {
+            HealthCheckResult::degraded(details)
+        } else {
+            HealthCheckResult::up(details)
+        }
+    }
+}
+
+impl Injectable for WorkerPoolHealthIndicator {
+    fn inject(container: &Container) -> crate::Result<Self> {
+        let indicator = Self::new(container.resolve::<WorkerPool>()?.as_ref().clone());
+        register_indicator(container, indicator.clone());
+        Ok(indicator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::health::{HealthRegistry, HealthStatus};
+
+    #[tokio::test]
+    async fn a_full_channel_degrades_the_event_bus() {
+        let bus = EventBus::with_capacity(1);
+        let _receiver = bus.subscribe::<u32>();
+        let indicator = EventBusHealthIndicator::new(bus.clone());
+        assert_eq!(indicator.check().await.status, HealthStatus::Up);
+
+        bus.publish(1u32);
+        let result = indicator.check().await;
+        assert_eq!(result.status, HealthStatus::Degraded);
+        assert_eq!(result.details["full_channels"], 1);
+        assert_eq!(result.details["subscribers"], 1);
+    }
+
+    #[tokio::test]
+    async fn worker_pool_reports_its_threads() {
+        let indicator = WorkerPoolHealthIndicator::new(WorkerPool::named("cpu", 2));
+        let result = indicator.check().await;
+
+        assert_eq!(result.status, HealthStatus::Up);
+        assert_eq!(result.details["pool"], "cpu");
+        assert_eq!(result.details["threads"], 2);
+        assert_eq!(result.details["saturation"], 0.0);
+    }
+
+    #[tokio::test]
+    async fn injected_indicators_join_the_registry() {
+        let mut container = Container::new();
+        container.register(HealthRegistry::new());
+        container.register(EventBus::new());
+        container.register(WorkerPool::new(1));
+        EventBusHealthIndicator::inject(&container).unwrap();
+        WorkerPoolHealthIndicator::inject(&container).unwrap();
+
+        let registry = container.resolve::<HealthRegistry>().unwrap();
+        assert_eq!(registry.names(), ["event_bus", "worker_pool"]);
+    }
+}
@@ -0,0 +1,351 @@
+//! Health checks: indicators reporting whether a dependency of the
+//! application is up, and a readiness endpoint aggregating them
+//!
+//! Importing [`HealthModule`] registers a [`HealthRegistry`] and serves
+//! `GET /health/ready`. The built-in indicators, [`DatabaseHealthIndicator`],
+//! [`EventBusHealthIndicator`] and [`WorkerPoolHealthIndicator`], add
+//! themselves to the registry when they're injected, so listing them as
+//! providers after the import is enough:
+//!
+//! ```rust,ignore
+//! #[module(
+//!     imports = [HealthModule],
+//!     providers = [DatabaseHealthIndicator, EventBusHealthIndicator],
+//! )]
+//! pub struct AppModule;
+//! ```
+//!
+//! The readiness output nests each indicator's result under its name, and
+//! answers 503 when any is down:
+//!
+//! ```json
+//! {
+//!   "status": "UP",
+//!   "checks": {
+//!     "database": { "status": "UP", "details": {}, "latency_ms": 2 },
+//!     "event_bus": { "status": "UP", "details": { "channels": 3, ... }, "latency_ms": 0 }
+//!   }
+//! }
+//! ```
+//!
+//! Indicators of your own implement [`HealthIndicator`] and are added with
+//! [`HealthRegistry::register`].
+
+mod indicators;
+
+pub use indicators::{DatabaseHealthIndicator, EventBusHealthIndicator, WorkerPoolHealthIndicator};
+
+use crate::controller::routing::{MatchedRoute, RouteDescriptor};
+use crate::controller::versioning::VersionedRouters;
+use crate::di::{Container, HasContainer, Injectable};
+use crate::module::{Module, MountState, export};
+use async_trait::async_trait;
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::{Serialize, Serializer};
+use std::any::TypeId;
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+/// Whether a dependency is usable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum HealthStatus {
+    Up,
+    /// Usable, but slow or close to its limits
+    Degraded,
+    Down,
+}
+
+/// What an indicator found
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthCheckResult {
+    pub status: HealthStatus,
+    /// What the indicator measured, e.g. queue depths
+    pub details: serde_json::Value,
+    /// How long the check took; set by the [`HealthRegistry`]
+    #[serde(rename = "latency_ms", serialize_with = "as_millis")]
+    pub latency: Duration,
+}
+
+impl HealthCheckResult {
+    pub fn new(status: HealthStatus, details: serde_json::Value) -> Self {
+        Self {
+            status,
+            details,
+            latency: Duration::ZERO,
+        }
+    }
+
+    pub fn up(details: serde_json::Value) -> Self {
+        Self::new(HealthStatus::Up, details)
+    }
+
+    pub fn degraded(details: serde_json::Value) -> Self {
+        Self::new(HealthStatus::Degraded, details)
+    }
+
+    pub fn down(details: serde_json::Value) -> Self {
+        Self::new(HealthStatus::Down, details)
+    }
+}
+
+fn as_millis<S: Serializer>(latency: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(latency.as_millis() as u64)
+}
+
+/// Checks one dependency of the application
+///
+/// # Example
+/// ```rust,ignore
+/// pub struct CacheHealthIndicator {
+///     cache: Arc<RedisCache>,
+/// }
+///
+/// #[async_trait]
+/// impl HealthIndicator for CacheHealthIndicator {
+///     fn name(&self) -> &str {
+///         "cache"
+///     }
+///
+///     async fn check(&self) -> HealthCheckResult {
+///         match self.cache.ping().await {
+///             Ok(()) => HealthCheckResult::up(json!({})),
+///             Err(e) => HealthCheckResult::down(json!({ "error": e.to_string() })),
+///         }
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait HealthIndicator: Send + Sync + 'static {
+    /// The key of the result in the readiness output
+    fn name(&self) -> &str;
+
+    async fn check(&self) -> HealthCheckResult;
+}
+
+/// The results of every indicator, nested under their names
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    /// The worst status of the checks; up when there are none
+    pub status: HealthStatus,
+    pub checks: BTreeMap<String, HealthCheckResult>,
+}
+
+impl IntoResponse for HealthReport {
+    /// 503 when a check is down, 200 otherwise
+    fn into_response(self) -> Response {
+        let status = match self.status {
+            HealthStatus::Down => StatusCode::SERVICE_UNAVAILABLE,
+            _ => StatusCode::OK,
+        };
+        (status, Json(self)).into_response()
+    }
+}
+
+/// The indicators the readiness endpoint checks
+///
+/// Clones share the indicators.
+#[derive(Clone)]
+pub struct HealthRegistry {
+    indicators: Arc<RwLock<Vec<Arc<dyn HealthIndicator>>>>,
+    timeout: Duration,
+}
+
+impl Default for HealthRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HealthRegistry {
+    pub fn new() -> Self {
+        Self {
+            indicators: Arc::new(RwLock::new(Vec::new())),
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    /// Count a check taking longer than `timeout`, 5 seconds by default, as
+    /// down
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Add `indicator`, replacing any of the same name
+    pub fn register(&self, indicator: Arc<dyn HealthIndicator>) {
+        let mut indicators = self.indicators.write().unwrap();
+        indicators.retain(|registered| registered.name() != indicator.name());
+        indicators.push(indicator);
+    }
+
+    /// The names of the registered indicators
+    pub fn names(&self) -> Vec<String> {
+        let indicators = self.indicators.read().unwrap();
+        indicators.iter().map(|i| i.name().to_string()).collect()
+    }
+
+    /// Run every indicator, concurrently
+    pub async fn check_all(&self) -> HealthReport {
+        let indicators = self.indicators.read().unwrap().clone();
+        let results = futures_util::future::join_all(
+            indicators
+                .iter()
+                .map(|indicator| self.check(indicator.as_ref())),
+        )
+        .await;
+
+        let status = results
+            .iter()
+            .map(|result| result.status)
+            .max()
+            .unwrap_or(HealthStatus::Up);
+        let checks = indicators
+            .iter()
+            .map(|indicator| indicator.name().to_string())
+            .zip(results)
+            .collect();
+        HealthReport { status, checks }
+    }
+
+    async fn check(&self, indicator: &dyn HealthIndicator) -> HealthCheckResult {
+        let start = Instant::now();
+        let mut result = match tokio::time::timeout(self.timeout, indicator.check()).await {
+            Ok(result) => result,
+            Err(_) => HealthCheckResult::down(serde_json::json!({
+                "error": format!("timed out after {:?}", self.timeout),
+            })),
+        };
+        result.latency = start.elapsed();
+        result
+    }
+}
+
+impl Injectable for HealthRegistry {
+    fn inject(_container: &Container) -> crate::Result<Self> {
+        Ok(Self::new())
+    }
+}
+
+/// Add `indicator` to the container's [`HealthRegistry`], if it has one
+fn register_indicator(container: &Container, indicator: impl HealthIndicator) {
+    if let Ok(registry) = container.resolve::<HealthRegistry>() {
+        registry.register(Arc::new(indicator));
+    }
+}
+
+/// Serves the [`HealthReport`] at `GET /health/ready`; mounted by
+/// [`HealthModule`]
+pub struct HealthController {
+    registry: HealthRegistry,
+}
+
+impl HealthController {
+    /// Where the readiness report is served
+    pub const PATH: &'static str = "/health/ready";
+
+    pub fn new(registry: HealthRegistry) -> Self {
+        Self { registry }
+    }
+
+    pub fn route_table() -> Vec<RouteDescriptor> {
+        vec![RouteDescriptor {
+            method: "GET",
+            path: Self::PATH.to_string(),
+            handler: "HealthController::ready",
+            guards: Vec::new(),
+            aspects: Vec::new(),
+            version: None,
+            cors: None,
+            csrf_exempt: false,
+            api: None,
+        }]
+    }
+
+    pub fn router<S>(controller: Arc<Self>) -> Router<S>
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        Router::new().route(
+            Self::PATH,
+            get(move |request: Request<Body>| async move {
+                MatchedRoute::record(&request, "HealthController", "ready");
+                controller.registry.check_all().await
+            }),
+        )
+    }
+}
+
+impl Injectable for HealthController {
+    fn inject(container: &Container) -> crate::Result<Self> {
+        Ok(Self::new(
+            container.resolve::<HealthRegistry>()?.as_ref().clone(),
+        ))
+    }
+}
+
+/// Registers a [`HealthRegistry`], unless the container has one, and serves
+/// its report at `/health/ready`
+///
+/// Import it into the application module before the modules providing the
+/// indicators: `#[module(imports = [HealthModule, ...])]`.
+pub struct HealthModule;
+
+impl HealthModule {
+    /// Registers the registry and the controller
+    pub fn register(container: &mut Container) -> crate::Result<()> {
+        let parent = container.enter_module("HealthModule");
+        let result = Self::register_providers(container);
+        container.exit_module(parent);
+        result
+    }
+
+    fn register_providers(container: &mut Container) -> crate::Result<()> {
+        if !container.contains::<HealthRegistry>() {
+            container.register(HealthRegistry::new());
+        }
+        let controller = HealthController::inject(container)?;
+        container.register(controller);
+        Ok(())
+    }
+}
+
+impl Module for HealthModule {
+    fn register(container: &mut Container) -> crate::Result<()> {
+        HealthModule::register(container)
+    }
+
+    fn mount_router<S>(
+        container: &Container,
+        state: &mut MountState,
+        routers: &mut VersionedRouters<S>,
+    ) -> crate::Result<()>
+    where
+        S: Clone + Send + Sync + HasContainer + 'static,
+    {
+        if !state.enter::<Self>() {
+            return Ok(());
+        }
+        state.add_framework_routes(HealthController::route_table())?;
+        let controller = container.resolve::<HealthController>()?;
+        let path = state.framework_path("/");
+        routers.mount(&path, vec![(None, HealthController::router(controller))]);
+        Ok(())
+    }
+
+    fn collect_routes(state: &mut MountState) -> crate::Result<()> {
+        if !state.enter::<Self>() {
+            return Ok(());
+        }
+        state.add_framework_routes(HealthController::route_table())
+    }
+
+    fn exported_types() -> Vec<(TypeId, &'static str)> {
+        vec![export::<HealthRegistry>()]
+    }
+}
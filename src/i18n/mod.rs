@@ -0,0 +1,393 @@
+//! Localized messages
+//!
+//! Validation failures and [`HttpException`](crate::exception::HttpException)s
+//! are answered in the language the request asks for with
+//! `Accept-Language`. Their messages are keys looked up by the
+//! [`MessageResolver`] bound in the container; a key without a message is
+//! its own message, so the English texts written in code are served as
+//! they are until a catalog translates them.
+//!
+//! ```
+//! use meshestra::i18n::{MessageArgs, MessageCatalog, MessageResolver};
+//!
+//! let catalog = MessageCatalog::new("en")
+//!     .with_messages("en", [("user.email.invalid", "{field} is not an email address")])
+//!     .with_messages("ko", [("user.email.invalid", "{field}은(는) 올바른 이메일이 아닙니다")]);
+//!
+//! let mut args = MessageArgs::new();
+//! args.insert("field".into(), "email".into());
+//! assert_eq!(
+//!     catalog.resolve("user.email.invalid", "ko-KR", &args),
+//!     "email은(는) 올바른 이메일이 아닙니다"
+//! );
+//! assert_eq!(catalog.resolve("user.missing", "ko-KR", &args), "user.missing");
+//! ```
+
+use crate::di::Container;
+use axum::http::HeaderMap;
+use axum::http::header::ACCEPT_LANGUAGE;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// The values a message interpolates, `{name}` for the `name` entry
+pub type MessageArgs = serde_json::Map<String, Value>;
+
+/// Turns message keys into the text of a locale
+///
+/// Bind one in the container to translate messages; the application
+/// binds a [`MessageCatalog`] without messages when none is bound.
+///
+/// ```rust,ignore
+/// container.register(catalog);
+/// container.register_trait::<dyn MessageResolver, MessageCatalog, _>(|catalog| {
+///     catalog as Arc<dyn MessageResolver>
+/// });
+/// ```
+pub trait MessageResolver: Send + Sync {
+    /// The message `key` stands for in `locale`, `args` interpolated;
+    /// `key` itself when there is none
+    fn resolve(&self, key: &str, locale: &str, args: &MessageArgs) -> String;
+
+    /// The locales requests can be answered in
+    fn locales(&self) -> Vec<&str> {
+        Vec::new()
+    }
+
+    /// The locale of requests asking for none of [`locales`](Self::locales)
+    fn default_locale(&self) -> &str {
+        "en"
+    }
+}
+
+/// A [`MessageResolver`] holding the messages of each locale
+///
+/// A key is looked up in the exact locale (`pt-BR`), then its language
+/// (`pt`), then the default locale; without a message in any of them, the
+/// key is the message. The default catalog has no messages: it answers in
+/// English with the framework's and the application's own texts.
+#[derive(Debug, Clone)]
+pub struct MessageCatalog {
+    default_locale: String,
+    messages: HashMap<String, HashMap<String, String>>,
+}
+
+impl MessageCatalog {
+    pub fn new(default_locale: impl Into<String>) -> Self {
+        Self {
+            default_locale: default_locale.into(),
+            messages: HashMap::new(),
+        }
+    }
+
+    /// Add `messages`, key and template pairs, to `locale`
+    pub fn with_messages<K, V>(
+        mut self,
+        locale: &str,
+        messages: impl IntoIterator<Item = (K, V)>,
+    ) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let locale = locale.to_ascii_lowercase();
+        self.messages.entry(locale).or_default().extend(
+            messages
+                .into_iter()
+                .map(|(key, message)| (key.into(), message.into())),
+        );
+        self
+    }
+
+    fn message(&self, locale: &str, key: &str) -> Option<&str> {
+        self.messages
+            .get(&locale.to_ascii_lowercase())
+            .and_then(|messages| messages.get(key))
+            .map(String::as_str)
+    }
+}
+
+impl Default for MessageCatalog {
+    fn default() -> Self {
+        Self::new("en")
+    }
+}
+
+impl MessageResolver for MessageCatalog {
+    fn resolve(&self, key: &str, locale: &str, args: &MessageArgs) -> String {
+        let template = [locale, language(locale), &self.default_locale]
+            .into_iter()
+            .find_map(|locale| self.message(locale, key))
+            .unwrap_or(key);
+        interpolate(template, args)
+    }
+
+    fn locales(&self) -> Vec<&str> {
+        let mut locales: Vec<&str> = self.messages.keys().map(String::as_str).collect();
+        locales.sort_unstable();
+        locales
+    }
+
+    fn default_locale(&self) -> &str {
+        &self.default_locale
+    }
+}
+
+/// The language of a locale, `pt` for `pt-BR`
+fn language(locale: &str) -> &str {
+    locale.split(['-', '_']).next().unwrap_or(locale)
+}
+
+/// `template` with each `{name}` replaced by the `name` argument; unknown
+/// names are left as they are
+fn interpolate(template: &str, args: &MessageArgs) -> String {
+    let mut message = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        message.push_str(&rest[..open]);
+        let after = &rest[open..];
+        let value = after
+            .find('}')
+            .and_then(|close| args.get(&after[1..close]).map(|value| (close, value)));
+        match value {
+            Some((close, Value::String(text))) => {
+                message.push_str(text);
+                rest = &after[close + 1..];
+            }
+            Some((close, value)) => {
+                message.push_str(&value.to_string());
+                rest = &after[close + 1..];
+            }
+            None => {
+                message.push('{');
+                rest = &after[1..];
+            }
+        }
+    }
+    message.push_str(rest);
+    message
+}
+
+/// The language ranges of an `Accept-Language` header, most preferred
+/// first
+///
+/// Ranges keep their order among equal `q` values; those with `q=0` or an
+/// invalid `q` are left out.
+pub fn parse_accept_language(header: &str) -> Vec<&str> {
+    let mut ranges: Vec<(&str, f32)> = header
+        .split(',')
+        .filter_map(|entry| {
+            let mut parts = entry.split(';').map(str::trim);
+            let range = parts.next().filter(|range| !range.is_empty())?;
+            let quality = match parts.find_map(|param| param.strip_prefix("q=")) {
+                Some(q) => q.parse::<f32>().ok().filter(|q| (0.0..=1.0).contains(q))?,
+                None => 1.0,
+            };
+            (quality > 0.0).then_some((range, quality))
+        })
+        .collect();
+    ranges.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    ranges.into_iter().map(|(range, _)| range).collect()
+}
+
+/// The best of `available` for an `Accept-Language` header
+///
+/// Each range, in preference order, matches a locale exactly, then by its
+/// language: `de-AT` is answered with `de`, or else any other `de-*`.
+pub fn negotiate<'a>(header: &str, available: &[&'a str]) -> Option<&'a str> {
+    parse_accept_language(header).into_iter().find_map(|range| {
+        let matching = |wanted: &str| {
+            available
+                .iter()
+                .copied()
+                .find(|locale| locale.eq_ignore_ascii_case(wanted))
+        };
+        matching(range)
+            .or_else(|| matching(language(range)))
+            .or_else(|| {
+                available
+                    .iter()
+                    .copied()
+                    .find(|locale| language(locale).eq_ignore_ascii_case(language(range)))
+            })
+    })
+}
+
+/// The [`MessageResolver`] and the locale a request is answered in
+#[derive(Clone)]
+pub struct Localizer {
+    resolver: Arc<dyn MessageResolver>,
+    locale: String,
+}
+
+impl Localizer {
+    /// The locale `accept_language` asks for, among the resolver's
+    pub fn new(resolver: Arc<dyn MessageResolver>, accept_language: Option<&str>) -> Self {
+        let locale = accept_language
+            .and_then(|header| negotiate(header, &resolver.locales()).map(str::to_string))
+            .unwrap_or_else(|| resolver.default_locale().to_string());
+        Self { resolver, locale }
+    }
+
+    /// The container's resolver, or a [`MessageCatalog::default`], in the
+    /// locale of the request with `headers`
+    pub fn of(container: Option<&Container>, headers: &HeaderMap) -> Self {
+        let resolver = container
+            .and_then(|container| container.resolve_trait::<dyn MessageResolver>().ok())
+            .unwrap_or_else(|| Arc::new(MessageCatalog::default()));
+        let accept_language = headers
+            .get(ACCEPT_LANGUAGE)
+            .and_then(|value| value.to_str().ok());
+        Self::new(resolver, accept_language)
+    }
+
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// The message `key` stands for in the request's locale
+    pub fn message(&self, key: &str, args: &MessageArgs) -> String {
+        self.resolver.resolve(key, &self.locale, args)
+    }
+}
+
+/// Message keys of a validated type's fields, overriding the validator's
+///
+/// Derived with `#[derive(MessageKeys)]` and `#[message(key = "...")]` on
+/// the fields:
+///
+/// ```ignore
+/// #[derive(Deserialize, Validate, MessageKeys)]
+/// pub struct CreateUser {
+///     #[validate(email)]
+///     #[message(key = "user.email.invalid")]
+///     email: String,
+/// }
+/// ```
+pub trait MessageKeys {
+    /// The key of the messages for the failures of `field`
+    fn message_key(field: &str) -> Option<&'static str>;
+}
+
+/// Looks up the message key of a field
+pub type MessageKeyFn = fn(&str) -> Option<&'static str>;
+
+/// Where `#[body(validate)]` gets the message keys of a `T`
+///
+/// Generated code calls `keys` on `&MessageKeysOf<T>` with both traits in
+/// scope; method resolution picks [`DerivedMessageKeys`] when
+/// `T: MessageKeys` and [`NoMessageKeys`] otherwise.
+#[doc(hidden)]
+pub struct MessageKeysOf<T>(PhantomData<fn() -> T>);
+
+impl<T> MessageKeysOf<T> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T> Default for MessageKeysOf<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[doc(hidden)]
+pub trait DerivedMessageKeys {
+    fn keys(&self) -> MessageKeyFn;
+}
+
+impl<T: MessageKeys> DerivedMessageKeys for MessageKeysOf<T> {
+    fn keys(&self) -> MessageKeyFn {
+        T::message_key
+    }
+}
+
+#[doc(hidden)]
+pub trait NoMessageKeys {
+    fn keys(&self) -> MessageKeyFn;
+}
+
+impl<T> NoMessageKeys for &MessageKeysOf<T> {
+    fn keys(&self) -> MessageKeyFn {
+        |_| None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn catalog() -> MessageCatalog {
+        MessageCatalog::new("en")
+            .with_messages("en", [("greeting", "Hello, {name}"), ("bye", "Bye")])
+            .with_messages("pt", [("greeting", "Olá, {name}")])
+            .with_messages("pt-BR", [("greeting", "Oi, {name}")])
+    }
+
+    fn args() -> MessageArgs {
+        json!({ "name": "Ada", "count": 3 })
+            .as_object()
+            .unwrap()
+            .clone()
+    }
+
+    #[test]
+    fn resolves_the_exact_locale_then_its_language_then_the_default() {
+        let catalog = catalog();
+        assert_eq!(catalog.resolve("greeting", "pt-BR", &args()), "Oi, Ada");
+        assert_eq!(catalog.resolve("greeting", "pt-PT", &args()), "Olá, Ada");
+        assert_eq!(catalog.resolve("greeting", "fr", &args()), "Hello, Ada");
+        assert_eq!(catalog.resolve("bye", "pt-BR", &args()), "Bye");
+    }
+
+    #[test]
+    fn missing_keys_are_their_own_message() {
+        let catalog = catalog();
+        assert_eq!(catalog.resolve("{count} users", "pt", &args()), "3 users");
+        assert_eq!(
+            catalog.resolve("user.missing", "en", &args()),
+            "user.missing"
+        );
+    }
+
+    #[test]
+    fn unknown_placeholders_are_kept() {
+        assert_eq!(
+            interpolate("{name} has {unknown} {", &args()),
+            "Ada has {unknown} {"
+        );
+    }
+
+    #[test]
+    fn accept_language_is_ordered_by_quality() {
+        assert_eq!(
+            parse_accept_language("fr;q=0.5, de-AT, en;q=0.8, es;q=0, it;q=x, pt;q=0.8"),
+            ["de-AT", "en", "pt", "fr"]
+        );
+    }
+
+    #[test]
+    fn negotiates_exact_locales_then_languages() {
+        let available = ["en", "pt", "pt-BR", "de-DE"];
+        assert_eq!(negotiate("pt-br, en;q=0.5", &available), Some("pt-BR"));
+        assert_eq!(negotiate("pt-PT", &available), Some("pt"));
+        assert_eq!(negotiate("de-AT, en;q=0.1", &available), Some("de-DE"));
+        assert_eq!(negotiate("ja, en;q=0.1", &available), Some("en"));
+        assert_eq!(negotiate("ja", &available), None);
+    }
+
+    #[test]
+    fn localizers_fall_back_to_the_default_locale() {
+        let resolver: Arc<dyn MessageResolver> = Arc::new(catalog());
+        let localizer = Localizer::new(resolver.clone(), Some("pt-BR;q=0.9, ja"));
+        assert_eq!(localizer.locale(), "pt-br");
+        assert_eq!(localizer.message("greeting", &args()), "Oi, Ada");
+
+        let localizer = Localizer::new(resolver, Some("ja"));
+        assert_eq!(localizer.locale(), "en");
+        assert_eq!(localizer.message("greeting", &args()), "Hello, Ada");
+    }
+}
@@ -0,0 +1,17 @@
+//! Ready-made interceptors for common cross-cutting concerns
+
+mod body_limit;
+mod cache;
+#[cfg(feature = "compression")]
+mod compression;
+mod logging;
+mod rate_limit;
+mod timeout;
+
+pub use body_limit::{BodyLimitConfig, BodyLimitInterceptor};
+pub use cache::{CacheInterceptor, X_CACHE};
+#[cfg(feature = "compression")]
+pub use compression::{CompressionConfig, CompressionInterceptor};
+pub use logging::LoggingInterceptor;
+pub use rate_limit::{RateLimitInterceptor, RateLimitKey};
+pub use timeout::{TimeoutInterceptor, TimeoutOverride};
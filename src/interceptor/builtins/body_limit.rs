@@ -0,0 +1,139 @@
+use crate::common::{ApiResponse, StatusCode};
+use crate::interceptor::{Interceptor, InterceptorResult, Next};
+use async_trait::async_trait;
+use axum::{
+    body::Body,
+    http::{Request, header},
+    response::{IntoResponse, Response},
+};
+
+/// Configuration for [`BodyLimitInterceptor`]
+#[derive(Debug, Clone)]
+pub struct BodyLimitConfig {
+    /// Largest accepted request body in bytes (2 MiB by default)
+    pub max_bytes: usize,
+}
+
+impl Default for BodyLimitConfig {
+    fn default() -> Self {
+        Self {
+            max_bytes: 2 * 1024 * 1024,
+        }
+    }
+}
+
+/// Rejects request bodies larger than a limit with 413
+///
+/// A `Content-Length` over the limit is rejected right away; other bodies
+/// are buffered up to the limit, so handlers never see more than
+/// `max_bytes`.
+///
+/// # Example
+/// ```
+/// use meshestra::interceptor::builtins::{BodyLimitConfig, BodyLimitInterceptor};
+///
+/// let limit = BodyLimitInterceptor::new(BodyLimitConfig { max_bytes: 64 * 1024 });
+/// ```
+pub struct BodyLimitInterceptor {
+    config: BodyLimitConfig,
+}
+
+impl BodyLimitInterceptor {
+    pub fn new(config: BodyLimitConfig) -> Self {
+        Self { config }
+    }
+
+    fn too_large(&self) -> Response {
+        ApiResponse::<()>::error(
+            StatusCode::PayloadTooLarge,
+            format!(
+                "Request body exceeds the limit of {} bytes",
+                self.config.max_bytes
+            ),
+        )
+        .into_response()
+    }
+}
+
+impl Default for BodyLimitInterceptor {
+    fn default() -> Self {
+        Self::new(BodyLimitConfig::default())
+    }
+}
+
+#[async_trait]
+impl Interceptor for BodyLimitInterceptor {
+    async fn intercept(&self, request: Request<Body>, next: Next) -> InterceptorResult {
+        let declared = request
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok());
+        if declared.is_some_and(|length| length > self.config.max_bytes as u64) {
+            return Ok(self.too_large());
+        }
+
+        let (parts, body) = request.into_parts();
+        let bytes = match axum::body::to_bytes(body, self.config.max_bytes).await {
+            Ok(bytes) => bytes,
+            Err(_) => return Ok(self.too_large()),
+        };
+        next.run(Request::from_parts(parts, Body::from(bytes)))
+            .await
+    }
+
+    fn priority(&self) -> i32 {
+        // Reject before any other work happens, but inside logging,
+        // timeouts and rate limits.
+        i32::MIN + 3
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interceptor::InterceptorLayer;
+    use axum::{Router, routing::post};
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        Router::new()
+            .route("/", post(|body: String| async move { body }))
+            .layer(InterceptorLayer::new(vec![Box::new(
+                BodyLimitInterceptor::new(BodyLimitConfig { max_bytes: 8 }),
+            )]))
+    }
+
+    async fn send(body: Body, content_length: Option<&str>) -> Response {
+        let mut request = Request::post("/");
+        if let Some(length) = content_length {
+            request = request.header(header::CONTENT_LENGTH, length);
+        }
+        app().oneshot(request.body(body).unwrap()).await.unwrap()
+    }
+
+    async fn json(response: Response) -> serde_json::Value {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn small_bodies_reach_the_handler() {
+        let response = send(Body::from("12345678"), None).await;
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn large_bodies_are_rejected_with_413() {
+        for response in [
+            send(Body::from("123456789"), None).await,
+            send(Body::from("1"), Some("1000")).await,
+        ] {
+            assert_eq!(response.status(), axum::http::StatusCode::PAYLOAD_TOO_LARGE);
+            let body = json(response).await;
+            assert_eq!(body["error"]["code"], "PayloadTooLarge");
+        }
+    }
+}
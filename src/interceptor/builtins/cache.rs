@@ -0,0 +1,360 @@
+use crate::interceptor::{Interceptor, InterceptorResult, Next};
+use async_trait::async_trait;
+use axum::{
+    body::{Body, Bytes, HttpBody},
+    http::{HeaderMap, HeaderValue, Method, Request, StatusCode, header},
+    response::Response,
+};
+use dashmap::DashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+type CacheKeyFn = Arc<dyn Fn(&Request<Body>) -> String + Send + Sync>;
+
+/// Header telling whether a response was served from the cache
+pub const X_CACHE: &str = "x-cache";
+
+struct CacheEntry {
+    path: String,
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+    expires_at: Instant,
+    last_used: AtomicU64,
+}
+
+/// Caches successful GET responses in memory
+///
+/// Responses are keyed by method, path and query (see
+/// [`key_fn`](Self::key_fn)) and served without running the handler until
+/// their TTL expires. Every GET response carries an `X-Cache: HIT|MISS`
+/// header. Non-2xx responses, responses with `Cache-Control: no-store` and
+/// bodies without a known size within [`max_body_size`](Self::max_body_size)
+/// are not cached. When full, the least recently used entry is evicted.
+///
+/// # Example
+/// ```
+/// use meshestra::interceptor::{CacheInterceptor, InterceptorLayer};
+/// use std::sync::Arc;
+/// use std::time::Duration;
+///
+/// let cache = Arc::new(CacheInterceptor::new(Duration::from_secs(30), 1_000));
+/// let layer = InterceptorLayer::new(vec![Box::new(cache.clone())]);
+///
+/// // After a write:
+/// cache.invalidate("/products");
+/// ```
+pub struct CacheInterceptor {
+    ttl: Duration,
+    capacity: usize,
+    max_body_size: usize,
+    key_fn: CacheKeyFn,
+    entries: DashMap<String, CacheEntry>,
+    clock: AtomicU64,
+}
+
+impl CacheInterceptor {
+    /// Cache up to `capacity` responses for `ttl` each
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            ttl,
+            capacity,
+            max_body_size: 1024 * 1024,
+            key_fn: Arc::new(|request| {
+                let uri = request.uri();
+                match uri.query() {
+                    Some(query) => format!("{} {}?{}", request.method(), uri.path(), query),
+                    None => format!("{} {}", request.method(), uri.path()),
+                }
+            }),
+            entries: DashMap::new(),
+            clock: AtomicU64::new(0),
+        }
+    }
+
+    /// Compute cache keys with `key_fn`, e.g. to vary on a header
+    pub fn key_fn<F>(mut self, key_fn: F) -> Self
+    where
+        F: Fn(&Request<Body>) -> String + Send + Sync + 'static,
+    {
+        self.key_fn = Arc::new(key_fn);
+        self
+    }
+
+    /// Largest body cached, in bytes (1 MiB by default)
+    pub fn max_body_size(mut self, bytes: usize) -> Self {
+        self.max_body_size = bytes;
+        self
+    }
+
+    /// Drop every cached response whose path starts with `prefix`
+    pub fn invalidate(&self, prefix: &str) {
+        self.entries
+            .retain(|_, entry| !entry.path.starts_with(prefix));
+    }
+
+    /// Number of cached responses, including expired ones not yet evicted
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn tick(&self) -> u64 {
+        self.clock.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn lookup(&self, key: &str) -> Option<Response> {
+        let entry = self.entries.get(key)?;
+        if entry.expires_at <= Instant::now() {
+            drop(entry);
+            self.entries.remove(key);
+            return None;
+        }
+        entry.last_used.store(self.tick(), Ordering::Relaxed);
+
+        let mut response = Response::new(Body::from(entry.body.clone()));
+        *response.status_mut() = entry.status;
+        *response.headers_mut() = entry.headers.clone();
+        Some(response)
+    }
+
+    fn store(&self, key: String, entry: CacheEntry) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&key) {
+            let now = Instant::now();
+            self.entries.retain(|_, entry| entry.expires_at > now);
+        }
+        while self.entries.len() >= self.capacity && !self.entries.contains_key(&key) {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|entry| entry.last_used.load(Ordering::Relaxed))
+                .map(|entry| entry.key().clone());
+            match oldest {
+                Some(oldest) => self.entries.remove(&oldest),
+                None => break,
+            };
+        }
+        self.entries.insert(key, entry);
+    }
+
+    fn cacheable(&self, response: &Response) -> bool {
+        let no_store = response
+            .headers()
+            .get_all(header::CACHE_CONTROL)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .any(|directive| directive.trim().eq_ignore_ascii_case("no-store"));
+        let size_known = response
+            .body()
+            .size_hint()
+            .exact()
+            .is_some_and(|size| size <= self.max_body_size as u64);
+
+        response.status().is_success() && !no_store && size_known
+    }
+}
+
+#[async_trait]
+impl Interceptor for CacheInterceptor {
+    async fn intercept(&self, request: Request<Body>, next: Next) -> InterceptorResult {
+        if request.method() != Method::GET {
+            return next.run(request).await;
+        }
+
+        let key = (self.key_fn)(&request);
+        if let Some(mut response) = self.lookup(&key) {
+            response
+                .headers_mut()
+                .insert(X_CACHE, HeaderValue::from_static("HIT"));
+            return Ok(response);
+        }
+
+        let path = request.uri().path().to_string();
+        let mut response = next.run(request).await?;
+        if self.cacheable(&response) {
+            let (parts, body) = response.into_parts();
+            let body = axum::body::to_bytes(body, self.max_body_size).await?;
+            self.store(
+                key,
+                CacheEntry {
+                    path,
+                    status: parts.status,
+                    headers: parts.headers.clone(),
+                    body: body.clone(),
+                    expires_at: Instant::now() + self.ttl,
+                    last_used: AtomicU64::new(self.tick()),
+                },
+            );
+            response = Response::from_parts(parts, Body::from(body));
+        }
+
+        response
+            .headers_mut()
+            .insert(X_CACHE, HeaderValue::from_static("MISS"));
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interceptor::InterceptorLayer;
+    use axum::{
+        Router,
+        extract::Path,
+        response::IntoResponse,
+        routing::{get, post},
+    };
+    use std::sync::atomic::AtomicUsize;
+    use tower::ServiceExt;
+
+    fn app(cache: Arc<CacheInterceptor>, calls: Arc<AtomicUsize>) -> Router {
+        let handler_calls = calls.clone();
+        Router::new()
+            .route(
+                "/items/{id}",
+                get(move |Path(id): Path<String>| {
+                    let calls = handler_calls.clone();
+                    async move {
+                        calls.fetch_add(1, Ordering::SeqCst);
+                        match id.as_str() {
+                            "missing" => (StatusCode::NOT_FOUND, "missing").into_response(),
+                            "private" => {
+                                ([(header::CACHE_CONTROL, "private, no-store")], "private")
+                                    .into_response()
+                            }
+                            _ => format!("item {}", id).into_response(),
+                        }
+                    }
+                }),
+            )
+            .route(
+                "/items",
+                post(move || {
+                    let calls = calls.clone();
+                    async move {
+                        calls.fetch_add(1, Ordering::SeqCst);
+                        "created"
+                    }
+                }),
+            )
+            .layer(InterceptorLayer::new(vec![Box::new(cache)]))
+    }
+
+    async fn call(app: &Router, method: Method, uri: &str) -> (Option<String>, String) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        let x_cache = response
+            .headers()
+            .get(X_CACHE)
+            .map(|value| value.to_str().unwrap().to_string());
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (x_cache, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    fn hit() -> Option<String> {
+        Some("HIT".to_string())
+    }
+
+    fn miss() -> Option<String> {
+        Some("MISS".to_string())
+    }
+
+    #[tokio::test]
+    async fn serves_repeated_gets_from_the_cache() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let app = app(
+            Arc::new(CacheInterceptor::new(Duration::from_secs(60), 10)),
+            calls.clone(),
+        );
+
+        assert_eq!(
+            call(&app, Method::GET, "/items/1").await,
+            (miss(), "item 1".to_string())
+        );
+        assert_eq!(
+            call(&app, Method::GET, "/items/1").await,
+            (hit(), "item 1".to_string())
+        );
+        assert_eq!(call(&app, Method::GET, "/items/1?v=2").await.0, miss());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // Writes are never cached nor tagged.
+        assert_eq!(call(&app, Method::POST, "/items").await.0, None);
+        assert_eq!(call(&app, Method::POST, "/items").await.0, None);
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn skips_errors_and_no_store() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let cache = Arc::new(CacheInterceptor::new(Duration::from_secs(60), 10));
+        let app = app(cache.clone(), calls.clone());
+
+        for uri in ["/items/missing", "/items/private"] {
+            assert_eq!(call(&app, Method::GET, uri).await.0, miss());
+            assert_eq!(call(&app, Method::GET, uri).await.0, miss());
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+        assert!(cache.is_empty());
+    }
+
+    #[tokio::test]
+    async fn expires_after_the_ttl() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let app = app(
+            Arc::new(CacheInterceptor::new(Duration::from_millis(20), 10)),
+            calls.clone(),
+        );
+
+        call(&app, Method::GET, "/items/1").await;
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        assert_eq!(call(&app, Method::GET, "/items/1").await.0, miss());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn evicts_the_least_recently_used_entry() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let cache = Arc::new(CacheInterceptor::new(Duration::from_secs(60), 2));
+        let app = app(cache.clone(), calls.clone());
+
+        call(&app, Method::GET, "/items/1").await;
+        call(&app, Method::GET, "/items/2").await;
+        // Touch 1 so that 2 is the least recently used.
+        assert_eq!(call(&app, Method::GET, "/items/1").await.0, hit());
+        call(&app, Method::GET, "/items/3").await;
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(call(&app, Method::GET, "/items/1").await.0, hit());
+        assert_eq!(call(&app, Method::GET, "/items/2").await.0, miss());
+    }
+
+    #[tokio::test]
+    async fn invalidates_by_path_prefix() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let cache = Arc::new(CacheInterceptor::new(Duration::from_secs(60), 10));
+        let app = app(cache.clone(), calls.clone());
+
+        call(&app, Method::GET, "/items/1").await;
+        call(&app, Method::GET, "/items/2?full=true").await;
+        cache.invalidate("/items");
+
+        assert!(cache.is_empty());
+        assert_eq!(call(&app, Method::GET, "/items/1").await.0, miss());
+    }
+}
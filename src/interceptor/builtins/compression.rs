@@ -0,0 +1,149 @@
+use crate::interceptor::{Interceptor, InterceptorError, InterceptorResult, Next};
+use async_trait::async_trait;
+use axum::{body::Body, http::Request};
+use tower::{Layer, ServiceExt, service_fn};
+use tower_http::compression::{
+    CompressionLayer, Predicate,
+    predicate::{NotForContentType, SizeAbove},
+};
+
+/// Configuration for [`CompressionInterceptor`]
+#[derive(Debug, Clone)]
+pub struct CompressionConfig {
+    /// Offer gzip (on by default)
+    pub gzip: bool,
+    /// Offer brotli (on by default)
+    pub br: bool,
+    /// Smallest body worth compressing, in bytes (256 by default)
+    pub min_size: u16,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            gzip: true,
+            br: true,
+            min_size: 256,
+        }
+    }
+}
+
+/// Compresses response bodies with gzip or brotli, as the client's
+/// `Accept-Encoding` allows
+///
+/// Responses that already have a `Content-Encoding`, are smaller than
+/// `min_size`, or are images, gRPC or server-sent events are left alone.
+/// Requires the `compression` feature.
+///
+/// # Example
+/// ```
+/// use meshestra::interceptor::builtins::{CompressionConfig, CompressionInterceptor};
+///
+/// let compression = CompressionInterceptor::new(CompressionConfig {
+///     br: false,
+///     ..Default::default()
+/// });
+/// ```
+pub struct CompressionInterceptor {
+    config: CompressionConfig,
+}
+
+impl CompressionInterceptor {
+    pub fn new(config: CompressionConfig) -> Self {
+        Self { config }
+    }
+
+    fn layer(&self) -> CompressionLayer<impl Predicate> {
+        let predicate = SizeAbove::new(self.config.min_size)
+            .and(NotForContentType::GRPC)
+            .and(NotForContentType::IMAGES)
+            .and(NotForContentType::SSE);
+        CompressionLayer::new()
+            .gzip(self.config.gzip)
+            .br(self.config.br)
+            .compress_when(predicate)
+    }
+}
+
+impl Default for CompressionInterceptor {
+    fn default() -> Self {
+        Self::new(CompressionConfig::default())
+    }
+}
+
+#[async_trait]
+impl Interceptor for CompressionInterceptor {
+    async fn intercept(&self, request: Request<Body>, next: Next) -> InterceptorResult {
+        let mut next = Some(next);
+        let inner = service_fn(move |request: Request<Body>| {
+            let next = next.take();
+            async move {
+                match next {
+                    Some(next) => next.run(request).await,
+                    None => Err(InterceptorError::from("the chain was already run")),
+                }
+            }
+        });
+
+        let response = self.layer().layer(inner).oneshot(request).await?;
+        Ok(response.map(Body::new))
+    }
+
+    fn priority(&self) -> i32 {
+        // Outside caching and handlers, so every body they produce is
+        // compressed for the client at hand.
+        -100
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interceptor::InterceptorLayer;
+    use axum::{
+        Router,
+        http::{HeaderValue, header},
+        routing::get,
+    };
+
+    fn app() -> Router {
+        Router::new()
+            .route("/large", get(|| async { "meshestra ".repeat(100) }))
+            .route("/small", get(|| async { "tiny" }))
+            .route(
+                "/encoded",
+                get(|| async {
+                    (
+                        [(header::CONTENT_ENCODING, "identity")],
+                        "meshestra ".repeat(100),
+                    )
+                }),
+            )
+            .layer(InterceptorLayer::new(vec![Box::new(
+                CompressionInterceptor::default(),
+            )]))
+    }
+
+    async fn encoding(uri: &str, accept: &str) -> Option<HeaderValue> {
+        let request = Request::builder()
+            .uri(uri)
+            .header(header::ACCEPT_ENCODING, accept)
+            .body(Body::empty())
+            .unwrap();
+        let response = app().oneshot(request).await.unwrap();
+        response.headers().get(header::CONTENT_ENCODING).cloned()
+    }
+
+    #[tokio::test]
+    async fn negotiates_the_encoding() {
+        assert_eq!(encoding("/large", "gzip").await.unwrap(), "gzip");
+        assert_eq!(encoding("/large", "br").await.unwrap(), "br");
+        assert_eq!(encoding("/large", "identity").await, None);
+    }
+
+    #[tokio::test]
+    async fn skips_small_and_already_encoded_bodies() {
+        assert_eq!(encoding("/small", "gzip").await, None);
+        assert_eq!(encoding("/encoded", "gzip").await.unwrap(), "identity");
+    }
+}
@@ -0,0 +1,221 @@
+use crate::interceptor::{Interceptor, InterceptorResult, Next};
+use async_trait::async_trait;
+use axum::{
+    body::{Body, HttpBody},
+    extract::MatchedPath,
+    http::{HeaderMap, HeaderName, Request, header},
+    response::Response,
+};
+use std::collections::HashSet;
+use std::time::Instant;
+use tracing::Level;
+
+/// Logs one structured `tracing` event per request
+///
+/// Each event carries `method`, `path`, the matched `route` template, the
+/// response `status` and `size` in bytes (when known) and `elapsed_ms`. It is
+/// logged at `info` for successful responses, `warn` for 4xx and `error` for
+/// 5xx responses and interceptor errors.
+///
+/// # Example
+/// ```
+/// use axum::http::header;
+/// use meshestra::interceptor::builtins::LoggingInterceptor;
+///
+/// let logging = LoggingInterceptor::new()
+///     .skip_path("/health")
+///     .capture_headers([header::USER_AGENT, header::AUTHORIZATION])
+///     .log_bodies(1024);
+/// ```
+#[derive(Clone, Default)]
+pub struct LoggingInterceptor {
+    skip_paths: HashSet<String>,
+    captured_headers: Vec<HeaderName>,
+    body_limit: Option<usize>,
+}
+
+/// Headers whose values are never logged
+const REDACTED: [HeaderName; 3] = [header::AUTHORIZATION, header::COOKIE, header::SET_COOKIE];
+
+impl LoggingInterceptor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Do not log requests to `path`, e.g. health checks
+    pub fn skip_path(mut self, path: impl Into<String>) -> Self {
+        self.skip_paths.insert(path.into());
+        self
+    }
+
+    /// Log the values of `headers` as the `headers` field
+    ///
+    /// `Authorization`, `Cookie` and `Set-Cookie` are logged as `[redacted]`.
+    pub fn capture_headers(mut self, headers: impl IntoIterator<Item = HeaderName>) -> Self {
+        self.captured_headers.extend(headers);
+        self
+    }
+
+    /// Log request and response bodies of up to `limit` bytes
+    ///
+    /// Only honoured in debug builds; bodies are never logged in release
+    /// builds. Larger or streaming bodies are logged as omitted.
+    pub fn log_bodies(mut self, limit: usize) -> Self {
+        self.body_limit = Some(limit);
+        self
+    }
+
+    fn body_limit(&self) -> Option<usize> {
+        self.body_limit.filter(|_| cfg!(debug_assertions))
+    }
+
+    fn captured(&self, headers: &HeaderMap) -> String {
+        self.captured_headers
+            .iter()
+            .filter_map(|name| {
+                let value = headers.get(name)?;
+                let value = if REDACTED.contains(name) {
+                    "[redacted]"
+                } else {
+                    value.to_str().unwrap_or("[binary]")
+                };
+                Some(format!("{}: {}", name, value))
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Buffer `body` when it is small enough to log, returning it with its text
+async fn capture_body(body: Body, limit: usize) -> (Body, String) {
+    match body.size_hint().exact() {
+        Some(size) if size <= limit as u64 => match axum::body::to_bytes(body, limit).await {
+            Ok(bytes) => {
+                let text = String::from_utf8_lossy(&bytes).into_owned();
+                (Body::from(bytes), text)
+            }
+            Err(e) => (Body::empty(), format!("[unreadable: {}]", e)),
+        },
+        Some(size) => (body, format!("[{} bytes omitted]", size)),
+        None => (body, "[streaming body omitted]".to_string()),
+    }
+}
+
+macro_rules! log_at {
+    ($level:expr, $($fields:tt)*) => {
+        match $level {
+            level if level == Level::ERROR => tracing::error!($($fields)*),
+            level if level == Level::WARN => tracing::warn!($($fields)*),
+            _ => tracing::info!($($fields)*),
+        }
+    };
+}
+
+#[async_trait]
+impl Interceptor for LoggingInterceptor {
+    async fn intercept(&self, request: Request<Body>, next: Next) -> InterceptorResult {
+        if self.skip_paths.contains(request.uri().path()) {
+            return next.run(request).await;
+        }
+
+        let method = request.method().clone();
+        let path = request.uri().path().to_string();
+        let route = request
+            .extensions()
+            .get::<MatchedPath>()
+            .map(|route| route.as_str().to_string())
+            .unwrap_or_default();
+        let headers = self.captured(request.headers());
+
+        let (request, request_body) = match self.body_limit() {
+            Some(limit) => {
+                let (parts, body) = request.into_parts();
+                let (body, text) = capture_body(body, limit).await;
+                (Request::from_parts(parts, body), text)
+            }
+            None => (request, String::new()),
+        };
+
+        let start = Instant::now();
+        let result = next.run(request).await;
+        let elapsed_ms = start.elapsed().as_millis() as u64;
+
+        let mut response = match result {
+            Ok(response) => response,
+            Err(e) => {
+                tracing::error!(
+                    %method, %path, %route, elapsed_ms, %headers, error = %e,
+                    "Request failed"
+                );
+                return Err(e);
+            }
+        };
+
+        let response_body = match self.body_limit() {
+            Some(limit) => {
+                let (parts, body) = response.into_parts();
+                let (body, text) = capture_body(body, limit).await;
+                response = Response::from_parts(parts, body);
+                text
+            }
+            None => String::new(),
+        };
+
+        let status = response.status();
+        let size = response.body().size_hint().exact();
+        let level = if status.is_server_error() {
+            Level::ERROR
+        } else if status.is_client_error() {
+            Level::WARN
+        } else {
+            Level::INFO
+        };
+        log_at!(
+            level,
+            %method, %path, %route, status = status.as_u16(), size, elapsed_ms, %headers,
+            %request_body, %response_body,
+            "Request completed"
+        );
+
+        Ok(response)
+    }
+
+    fn priority(&self) -> i32 {
+        // Outermost, so timeouts and rate limit rejections are logged too.
+        i32::MIN
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn captured_headers_are_redacted() {
+        let logging =
+            LoggingInterceptor::new().capture_headers([header::USER_AGENT, header::AUTHORIZATION]);
+        let mut headers = HeaderMap::new();
+        headers.insert(header::USER_AGENT, HeaderValue::from_static("curl"));
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer secret"),
+        );
+
+        assert_eq!(
+            logging.captured(&headers),
+            "user-agent: curl, authorization: [redacted]"
+        );
+    }
+
+    #[tokio::test]
+    async fn captured_bodies_are_restored() {
+        let (body, text) = capture_body(Body::from("hello"), 16).await;
+        assert_eq!(text, "hello");
+        let bytes = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+        assert_eq!(&bytes[..], b"hello");
+
+        let (_, text) = capture_body(Body::from("hello"), 2).await;
+        assert_eq!(text, "[5 bytes omitted]");
+    }
+}
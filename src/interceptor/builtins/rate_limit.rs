@@ -0,0 +1,352 @@
+use crate::common::{ApiResponse, StatusCode};
+use crate::interceptor::{Interceptor, InterceptorResult, Next};
+use async_trait::async_trait;
+use axum::{
+    body::Body,
+    extract::ConnectInfo,
+    http::{HeaderMap, HeaderName, HeaderValue, Request, header},
+    response::{IntoResponse, Response},
+};
+use dashmap::DashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+type KeyFn = Arc<dyn Fn(&Request<Body>) -> Option<String> + Send + Sync>;
+
+/// What [`RateLimitInterceptor`] counts requests by
+#[derive(Clone)]
+pub enum RateLimitKey {
+    /// The peer address from `ConnectInfo<SocketAddr>`; serve the router with
+    /// `into_make_service_with_connect_info` for it to be available. Without
+    /// it requests are not limited, and a warning is logged once
+    ClientIp,
+    /// The value of a request header, e.g. an API key; requests without it
+    /// are not limited
+    Header(HeaderName),
+    /// A key computed from the request; `None` shares one bucket
+    Custom(KeyFn),
+}
+
+impl RateLimitKey {
+    /// Key requests with `key_fn`
+    pub fn custom<F>(key_fn: F) -> Self
+    where
+        F: Fn(&Request<Body>) -> Option<String> + Send + Sync + 'static,
+    {
+        RateLimitKey::Custom(Arc::new(key_fn))
+    }
+
+    /// The bucket `request` counts against, `None` when it is not limited
+    fn key(&self, request: &Request<Body>) -> Option<String> {
+        match self {
+            RateLimitKey::ClientIp => request
+                .extensions()
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.ip().to_string()),
+            RateLimitKey::Header(name) => request
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string),
+            RateLimitKey::Custom(key_fn) => Some(key_fn(request).unwrap_or_default()),
+        }
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Outcome of taking a token from a bucket
+struct Decision {
+    allowed: bool,
+    remaining: u32,
+    /// Until the bucket is full again
+    reset: Duration,
+    /// Until the next token is available
+    retry_after: Duration,
+}
+
+/// Limits each client to `limit` requests per `window` with a token bucket
+///
+/// Buckets refill continuously, so a client that used up its budget gets a
+/// request back every `window / limit`. Denied requests are answered with 429
+/// and `Retry-After`; every response carries `X-RateLimit-Limit`,
+/// `X-RateLimit-Remaining` and `X-RateLimit-Reset` (seconds until the budget
+/// is full again). Buckets idle for a whole window are full and get evicted,
+/// so memory stays bounded by the number of active clients.
+///
+/// Use it as a global interceptor, or per route with
+/// `#[rate_limit(per_minute = 60)]`.
+///
+/// # Example
+/// ```
+/// use axum::http::HeaderName;
+/// use meshestra::interceptor::{RateLimitInterceptor, RateLimitKey};
+/// use std::time::Duration;
+///
+/// let limiter = RateLimitInterceptor::new(100, Duration::from_secs(60))
+///     .key(RateLimitKey::Header(HeaderName::from_static("x-api-key")));
+/// ```
+pub struct RateLimitInterceptor {
+    limit: u32,
+    window: Duration,
+    key: RateLimitKey,
+    buckets: DashMap<String, Bucket>,
+    last_sweep: Mutex<Instant>,
+    warned_no_connect_info: AtomicBool,
+}
+
+impl RateLimitInterceptor {
+    /// Allow `limit` requests per `window` for each client IP
+    pub fn new(limit: u32, window: Duration) -> Self {
+        Self {
+            limit,
+            window,
+            key: RateLimitKey::ClientIp,
+            buckets: DashMap::new(),
+            last_sweep: Mutex::new(Instant::now()),
+            warned_no_connect_info: AtomicBool::new(false),
+        }
+    }
+
+    pub fn per_second(limit: u32) -> Self {
+        Self::new(limit, Duration::from_secs(1))
+    }
+
+    pub fn per_minute(limit: u32) -> Self {
+        Self::new(limit, Duration::from_secs(60))
+    }
+
+    pub fn per_hour(limit: u32) -> Self {
+        Self::new(limit, Duration::from_secs(3600))
+    }
+
+    /// Count requests by `key` instead of client IP
+    pub fn key(mut self, key: RateLimitKey) -> Self {
+        self.key = key;
+        self
+    }
+
+    /// Number of clients currently tracked
+    pub fn tracked_keys(&self) -> usize {
+        self.buckets.len()
+    }
+
+    fn take(&self, key: String, now: Instant) -> Decision {
+        let limit = f64::from(self.limit);
+        let per_token = self.window.as_secs_f64() / limit.max(1.0);
+
+        let mut bucket = self.buckets.entry(key).or_insert(Bucket {
+            tokens: limit,
+            updated: now,
+        });
+        let refilled = now.duration_since(bucket.updated).as_secs_f64() / per_token;
+        bucket.tokens = (bucket.tokens + refilled).min(limit);
+        bucket.updated = now;
+
+        let allowed = bucket.tokens >= 1.0;
+        if allowed {
+            bucket.tokens -= 1.0;
+        }
+        Decision {
+            allowed,
+            remaining: bucket.tokens.floor() as u32,
+            reset: Duration::from_secs_f64((limit - bucket.tokens) * per_token),
+            retry_after: Duration::from_secs_f64((1.0 - bucket.tokens).max(0.0) * per_token),
+        }
+    }
+
+    /// Drop buckets idle for a whole window; they are full again anyway
+    fn sweep(&self, now: Instant) {
+        {
+            let mut last_sweep = self
+                .last_sweep
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            if now.duration_since(*last_sweep) < self.window {
+                return;
+            }
+            *last_sweep = now;
+        }
+        self.buckets
+            .retain(|_, bucket| now.duration_since(bucket.updated) < self.window);
+    }
+
+    fn set_headers(&self, headers: &mut HeaderMap, decision: &Decision) {
+        let seconds = |duration: Duration| HeaderValue::from(duration.as_secs_f64().ceil() as u64);
+        headers.insert("x-ratelimit-limit", HeaderValue::from(self.limit));
+        headers.insert(
+            "x-ratelimit-remaining",
+            HeaderValue::from(decision.remaining),
+        );
+        headers.insert("x-ratelimit-reset", seconds(decision.reset));
+        if !decision.allowed {
+            headers.insert(header::RETRY_AFTER, seconds(decision.retry_after));
+        }
+    }
+}
+
+#[async_trait]
+impl Interceptor for RateLimitInterceptor {
+    async fn intercept(&self, request: Request<Body>, next: Next) -> InterceptorResult {
+        let now = Instant::now();
+        self.sweep(now);
+
+        let Some(key) = self.key.key(&request) else {
+            if matches!(self.key, RateLimitKey::ClientIp)
+                && !self.warned_no_connect_info.swap(true, Ordering::Relaxed)
+            {
+                tracing::warn!(
+                    "RateLimitInterceptor keys by client IP but the request has no \
+                     ConnectInfo<SocketAddr>; requests are not limited. Serve the router \
+                     with into_make_service_with_connect_info"
+                );
+            }
+            return next.run(request).await;
+        };
+        let decision = self.take(key, now);
+
+        let mut response = if decision.allowed {
+            next.run(request).await?
+        } else {
+            too_many_requests()
+        };
+        self.set_headers(response.headers_mut(), &decision);
+        Ok(response)
+    }
+
+    fn priority(&self) -> i32 {
+        // Right inside logging and timeouts, so rejected requests cost as
+        // little as possible.
+        i32::MIN + 2
+    }
+}
+
+fn too_many_requests() -> Response {
+    ApiResponse::<()>::error(StatusCode::TooManyRequests, "Rate limit exceeded").into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interceptor::InterceptorLayer;
+    use axum::{Router, routing::get};
+    use tower::ServiceExt;
+
+    fn app(limiter: Arc<RateLimitInterceptor>) -> Router {
+        Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(InterceptorLayer::new(vec![Box::new(limiter)]))
+    }
+
+    async fn call(app: &Router, api_key: &str) -> Response {
+        let request = Request::builder()
+            .uri("/")
+            .header("x-api-key", api_key)
+            .body(Body::empty())
+            .unwrap();
+        app.clone().oneshot(request).await.unwrap()
+    }
+
+    fn header(response: &Response, name: &str) -> String {
+        response.headers()[name].to_str().unwrap().to_string()
+    }
+
+    fn limiter(limit: u32, window: Duration) -> Arc<RateLimitInterceptor> {
+        Arc::new(
+            RateLimitInterceptor::new(limit, window)
+                .key(RateLimitKey::Header(HeaderName::from_static("x-api-key"))),
+        )
+    }
+
+    #[tokio::test]
+    async fn allows_up_to_the_limit_then_answers_429() {
+        let app = app(limiter(2, Duration::from_secs(60)));
+
+        let response = call(&app, "a").await;
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        assert_eq!(header(&response, "x-ratelimit-limit"), "2");
+        assert_eq!(header(&response, "x-ratelimit-remaining"), "1");
+        assert_eq!(header(&response, "x-ratelimit-reset"), "30");
+
+        assert_eq!(header(&call(&app, "a").await, "x-ratelimit-remaining"), "0");
+
+        let response = call(&app, "a").await;
+        assert_eq!(response.status(), axum::http::StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(header(&response, "retry-after"), "30");
+        assert_eq!(header(&response, "x-ratelimit-reset"), "60");
+
+        // Other clients have their own bucket.
+        assert_eq!(call(&app, "b").await.status(), axum::http::StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn buckets_refill_and_idle_ones_are_evicted() {
+        let limiter = limiter(1, Duration::from_millis(50));
+        let app = app(limiter.clone());
+
+        assert_eq!(call(&app, "a").await.status(), axum::http::StatusCode::OK);
+        assert_eq!(
+            call(&app, "a").await.status(),
+            axum::http::StatusCode::TOO_MANY_REQUESTS
+        );
+
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        assert_eq!(call(&app, "b").await.status(), axum::http::StatusCode::OK);
+        assert_eq!(limiter.tracked_keys(), 1);
+        assert_eq!(call(&app, "a").await.status(), axum::http::StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn requests_without_a_key_are_not_limited() {
+        let by_ip = app(Arc::new(RateLimitInterceptor::new(
+            1,
+            Duration::from_secs(60),
+        )));
+        let by_header = app(limiter(1, Duration::from_secs(60)));
+
+        for app in [by_ip, by_header] {
+            for _ in 0..3 {
+                let response = app
+                    .clone()
+                    .oneshot(Request::new(Body::empty()))
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), axum::http::StatusCode::OK);
+                assert!(!response.headers().contains_key("x-ratelimit-limit"));
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn client_ips_get_their_own_bucket() {
+        let app = app(Arc::new(RateLimitInterceptor::new(
+            1,
+            Duration::from_secs(60),
+        )));
+        let call = |ip: [u8; 4]| {
+            let mut request = Request::new(Body::empty());
+            request
+                .extensions_mut()
+                .insert(ConnectInfo(SocketAddr::from((ip, 4000))));
+            app.clone().oneshot(request)
+        };
+
+        assert_eq!(
+            call([10, 0, 0, 1]).await.unwrap().status(),
+            axum::http::StatusCode::OK
+        );
+        assert_eq!(
+            call([10, 0, 0, 1]).await.unwrap().status(),
+            axum::http::StatusCode::TOO_MANY_REQUESTS
+        );
+        assert_eq!(
+            call([10, 0, 0, 2]).await.unwrap().status(),
+            axum::http::StatusCode::OK
+        );
+    }
+}
@@ -0,0 +1,216 @@
+use crate::common::{ApiResponse, StatusCode};
+use crate::interceptor::{Interceptor, InterceptorResult, Next};
+use async_trait::async_trait;
+use axum::{
+    body::Body,
+    extract::MatchedPath,
+    http::Request,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::time::Instant;
+
+/// Fails requests that take longer than a deadline
+///
+/// The handler future is dropped when the deadline passes, the timeout is
+/// logged with the route and elapsed time, and the client gets an
+/// `ApiResponse` error with status 408 (see [`status`](Self::status)).
+/// Routes marked `#[timeout("2m")]` replace the deadline with their own,
+/// measured from when the request entered this interceptor.
+///
+/// # Example
+/// ```
+/// use meshestra::interceptor::{InterceptorLayer, TimeoutInterceptor};
+/// use std::time::Duration;
+///
+/// let layer = InterceptorLayer::new(vec![Box::new(TimeoutInterceptor::new(
+///     Duration::from_secs(30),
+/// ))]);
+/// ```
+pub struct TimeoutInterceptor {
+    timeout: Duration,
+    status: StatusCode,
+}
+
+impl TimeoutInterceptor {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            status: StatusCode::RequestTimeout,
+        }
+    }
+
+    /// Answer timed out requests with `status`, e.g. `GatewayTimeout`
+    pub fn status(mut self, status: StatusCode) -> Self {
+        self.status = status;
+        self
+    }
+
+    fn timed_out(&self, route: &str, elapsed: Duration) -> Response {
+        tracing::warn!(
+            route = %route,
+            elapsed_ms = elapsed.as_millis() as u64,
+            "Request timed out"
+        );
+        ApiResponse::<()>::error(
+            self.status,
+            format!("Request timed out after {}ms", elapsed.as_millis()),
+        )
+        .into_response()
+    }
+}
+
+#[async_trait]
+impl Interceptor for TimeoutInterceptor {
+    async fn intercept(&self, mut request: Request<Body>, next: Next) -> InterceptorResult {
+        let route = request
+            .extensions()
+            .get::<MatchedPath>()
+            .map(|path| path.as_str().to_string())
+            .unwrap_or_else(|| request.uri().path().to_string());
+        let (sender, mut overrides) = watch::channel(None);
+        request
+            .extensions_mut()
+            .insert(TimeoutOverride(Arc::new(sender)));
+
+        let start = Instant::now();
+        let mut deadline = start + self.timeout;
+        let run = next.run(request);
+        tokio::pin!(run);
+
+        loop {
+            tokio::select! {
+                result = &mut run => return result,
+                _ = tokio::time::sleep_until(deadline) => break,
+                Ok(()) = overrides.changed() => {
+                    if let Some(timeout) = *overrides.borrow_and_update() {
+                        deadline = start + timeout;
+                    }
+                }
+            }
+        }
+
+        Ok(self.timed_out(&route, start.elapsed()))
+    }
+
+    fn priority(&self) -> i32 {
+        // Outside everything but logging, so the deadline covers the chain.
+        i32::MIN + 1
+    }
+}
+
+/// Lets a route replace the deadline of the enclosing [`TimeoutInterceptor`]
+///
+/// The interceptor inserts it into the request extensions; `#[timeout("2m")]`
+/// routes use it through [`apply`](Self::apply).
+#[derive(Clone)]
+pub struct TimeoutOverride(Arc<watch::Sender<Option<Duration>>>);
+
+impl TimeoutOverride {
+    /// Set the deadline of the [`TimeoutInterceptor`] handling `request` to
+    /// `timeout`; returns `false` when no such interceptor is installed
+    pub fn apply(request: &Request<Body>, timeout: Duration) -> bool {
+        match request.extensions().get::<TimeoutOverride>() {
+            Some(slot) => {
+                slot.0.send_replace(Some(timeout));
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interceptor::InterceptorLayer;
+    use axum::{Router, routing::get};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use tower::ServiceExt;
+
+    async fn call(app: Router, uri: &str) -> Response {
+        let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+        app.oneshot(request).await.unwrap()
+    }
+
+    /// Sets a flag when dropped before completing
+    struct DropFlag(Arc<AtomicBool>);
+
+    impl Drop for DropFlag {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    fn app(timeout: TimeoutInterceptor, dropped: Arc<AtomicBool>) -> Router {
+        Router::new()
+            .route("/fast", get(|| async { "fast" }))
+            .route(
+                "/slow",
+                get(move || {
+                    let flag = DropFlag(dropped.clone());
+                    async move {
+                        tokio::time::sleep(Duration::from_millis(200)).await;
+                        std::mem::forget(flag);
+                        "slow"
+                    }
+                }),
+            )
+            .route(
+                "/report",
+                get(|request: Request<Body>| async move {
+                    TimeoutOverride::apply(&request, Duration::from_millis(500));
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    "report"
+                }),
+            )
+            .layer(InterceptorLayer::new(vec![Box::new(timeout)]))
+    }
+
+    #[tokio::test]
+    async fn slow_handlers_are_dropped_and_answered_with_408() {
+        let dropped = Arc::new(AtomicBool::new(false));
+        let app = app(
+            TimeoutInterceptor::new(Duration::from_millis(20)),
+            dropped.clone(),
+        );
+
+        assert_eq!(
+            call(app.clone(), "/fast").await.status(),
+            axum::http::StatusCode::OK
+        );
+
+        let response = call(app, "/slow").await;
+        assert_eq!(response.status(), axum::http::StatusCode::REQUEST_TIMEOUT);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"]["code"], "RequestTimeout");
+        assert!(dropped.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn status_is_configurable() {
+        let app = app(
+            TimeoutInterceptor::new(Duration::from_millis(20)).status(StatusCode::GatewayTimeout),
+            Arc::default(),
+        );
+
+        let response = call(app, "/slow").await;
+        assert_eq!(response.status(), axum::http::StatusCode::GATEWAY_TIMEOUT);
+    }
+
+    #[tokio::test]
+    async fn routes_can_extend_the_deadline() {
+        let app = app(
+            TimeoutInterceptor::new(Duration::from_millis(20)),
+            Arc::default(),
+        );
+
+        let response = call(app, "/report").await;
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+    }
+}
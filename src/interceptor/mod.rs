@@ -1,7 +1,22 @@
+use crate::di::Container;
 use async_trait::async_trait;
 use axum::{body::Body, http::Request, response::Response};
 use std::future::Future;
+use std::ops::Deref;
 use std::pin::Pin;
+use std::sync::{Arc, LazyLock, OnceLock};
+
+pub mod builtins;
+mod layer;
+
+pub use builtins::{
+    BodyLimitConfig, BodyLimitInterceptor, CacheInterceptor, LoggingInterceptor,
+    RateLimitInterceptor, RateLimitKey, TimeoutInterceptor, TimeoutOverride, X_CACHE,
+};
+#[cfg(feature = "compression")]
+pub use builtins::{CompressionConfig, CompressionInterceptor};
+#[allow(deprecated)]
+pub use layer::{InterceptorLayer, InterceptorMiddleware, SharedInterceptorLayer};
 
 /// standard return type for Interceptors
 pub type InterceptorResult = Result<Response, InterceptorError>;
@@ -9,11 +24,23 @@ pub type InterceptorResult = Result<Response, InterceptorError>;
 /// A type-erased error for interceptors
 pub type InterceptorError = Box<dyn std::error::Error + Send + Sync>;
 
+/// Interceptors in the order they run, the first the outermost
+type Chain = Arc<[Arc<dyn Interceptor>]>;
+
+/// The handler at the end of a chain
+type Handler = Box<
+    dyn FnOnce(Request<Body>) -> Pin<Box<dyn Future<Output = InterceptorResult> + Send>> + Send,
+>;
+
 /// Represents the next handler in the chain
+///
+/// The interceptors left to run and the handler after them; running it
+/// calls the first of them with the rest, so a chain is walked without
+/// being rebuilt for each request.
 pub struct Next {
-    pub(crate) run: Box<
-        dyn FnOnce(Request<Body>) -> Pin<Box<dyn Future<Output = InterceptorResult> + Send>> + Send,
-    >,
+    interceptors: Arc<[Arc<dyn Interceptor>]>,
+    index: usize,
+    handler: Handler,
 }
 
 impl Next {
@@ -24,12 +51,103 @@ impl Next {
             + Send
             + 'static,
     {
-        Self { run: Box::new(f) }
+        // Shared, so a bare handler costs no allocation besides its own.
+        static EMPTY: LazyLock<Arc<[Arc<dyn Interceptor>]>> = LazyLock::new(|| Arc::new([]));
+        Self {
+            interceptors: EMPTY.clone(),
+            index: 0,
+            handler: Box::new(f),
+        }
     }
 
     /// Execute the next handler
     pub async fn run(self, request: Request<Body>) -> InterceptorResult {
-        (self.run)(request).await
+        let Self {
+            interceptors,
+            index,
+            handler,
+        } = self;
+        match interceptors.get(index) {
+            Some(interceptor) => {
+                let next = Self {
+                    interceptors: interceptors.clone(),
+                    index: index + 1,
+                    handler,
+                };
+                interceptor.intercept(request, next).await
+            }
+            None => handler(request).await,
+        }
+    }
+
+    /// Nest `handler` inside `interceptors`, the first one being the outermost
+    ///
+    /// The interceptors are used in the given order; sort them with
+    /// [`sort_by_priority`] first.
+    pub fn chain(interceptors: Arc<[Arc<dyn Interceptor>]>, handler: Next) -> Next {
+        if handler.index < handler.interceptors.len() {
+            // Already a chain: it runs inside the new one.
+            return Self::chain(
+                interceptors,
+                Self::new(
+                    move |request| -> Pin<Box<dyn Future<Output = InterceptorResult> + Send>> {
+                        Box::pin(handler.run(request))
+                    },
+                ),
+            );
+        }
+        Self {
+            interceptors,
+            index: 0,
+            handler: handler.handler,
+        }
+    }
+}
+
+/// Order `interceptors` by [`Interceptor::priority`], lowest (outermost) first
+///
+/// The sort is stable, so interceptors with the same priority keep their
+/// registration order.
+pub fn sort_by_priority<I>(interceptors: &mut [I])
+where
+    I: Deref<Target = dyn Interceptor>,
+{
+    interceptors.sort_by_key(|interceptor| interceptor.priority());
+}
+
+/// The interceptors of a `#[routes]` route, kept from one request to the next
+///
+/// The chain is resolved from the container serving the route and sorted
+/// once, then reused for as long as that container serves it. A failure to
+/// build it is not kept, so a later request tries again.
+#[doc(hidden)]
+#[derive(Default)]
+pub struct RouteChain {
+    /// The chain and the [`Container::revision`] it was built from
+    built: OnceLock<(u64, Chain)>,
+}
+
+impl RouteChain {
+    /// The chain built for `container`, building it with `build` the first time
+    pub fn get_or_try_init<F>(
+        &self,
+        container: &Container,
+        build: F,
+    ) -> crate::error::Result<Chain>
+    where
+        F: FnOnce() -> crate::error::Result<Vec<Arc<dyn Interceptor>>>,
+    {
+        let revision = container.revision();
+        if let Some((built_for, chain)) = self.built.get() {
+            if *built_for == revision {
+                return Ok(chain.clone());
+            }
+            // Another container serves the route too; it's built each time.
+            return build().map(Into::into);
+        }
+        let chain: Chain = build()?.into();
+        let _ = self.built.set((revision, chain.clone()));
+        Ok(chain)
     }
 }
 
@@ -59,4 +177,34 @@ impl Next {
 #[async_trait]
 pub trait Interceptor: Send + Sync + 'static {
     async fn intercept(&self, request: Request<Body>, next: Next) -> InterceptorResult;
+
+    /// Position in a chain: lower priorities wrap higher ones
+    ///
+    /// E.g. logging at `-10` sees the request before, and the response after,
+    /// a transaction interceptor at `0` that wraps the handler. Ties keep
+    /// registration order.
+    fn priority(&self) -> i32 {
+        0
+    }
+
+    /// Name shown when inspecting a chain's order
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+}
+
+/// Lets interceptors resolved from the container (as `Arc<T>`) join a chain
+#[async_trait]
+impl<I: Interceptor + ?Sized> Interceptor for Arc<I> {
+    async fn intercept(&self, request: Request<Body>, next: Next) -> InterceptorResult {
+        (**self).intercept(request, next).await
+    }
+
+    fn priority(&self) -> i32 {
+        (**self).priority()
+    }
+
+    fn name(&self) -> &'static str {
+        (**self).name()
+    }
 }
@@ -16,10 +16,22 @@
 //! ## Quick Start
 //!
 //! ```rust,no_run
-//! use meshestra::{Injectable, controller, module, Inject, Container, HasContainer};
-//! use axum::{Router, Json, extract::Path};
+//! use meshestra::exception::HttpException;
+//! use meshestra::module::Module;
+//! use meshestra::{Container, ContainerBuilder, DeriveInjectable as Injectable, HasContainer};
+//! use meshestra::{controller, module};
+//! use axum::{Json, Router};
+//! use serde::Serialize;
 //! use std::sync::Arc;
 //!
+//! #[derive(Serialize)]
+//! pub struct User {
+//!     id: String,
+//! }
+//!
+//! #[derive(Debug)]
+//! pub struct UserNotFound;
+//!
 //! // 1. Define your service
 //! #[derive(Injectable)]
 //! pub struct UserService {
@@ -27,23 +39,29 @@
 //! }
 //!
 //! impl UserService {
-//!     pub async fn find_one(&self, id: String) -> Result<User, Error> {
+//!     pub async fn find_one(&self, id: String) -> Result<User, UserNotFound> {
 //!         // Business logic
-//!         todo!()
+//!         Ok(User { id })
 //!     }
 //! }
 //!
-//! // 2. Define your controller
-//! #[controller(path = "/users")]
+//! // 2. Define your controller; `#[controller]` on the impl block
+//! //    generates its `router()`
+//! #[derive(Injectable)]
 //! pub struct UserController {
 //!     user_service: Arc<UserService>,
 //! }
 //!
+//! #[controller(path = "/users")]
 //! impl UserController {
 //!     #[get("/{id}")]
-//!     async fn get_user(&self, Path(id): Path<String>) -> Json<User> {
-//!         let user = self.user_service.find_one(id).await.unwrap();
-//!         Json(user)
+//!     async fn get_user(&self, #[param] id: String) -> Result<Json<User>, HttpException> {
+//!         let user = self
+//!             .user_service
+//!             .find_one(id)
+//!             .await
+//!             .map_err(|_| HttpException::not_found("No such user"))?;
+//!         Ok(Json(user))
 //!     }
 //! }
 //!
@@ -67,36 +85,53 @@
 //! }
 //!
 //! #[tokio::main]
-//! async fn main() {
-//!     let mut container = Container::new();
-//!     AppModule::register(&mut container).unwrap();
+//! async fn main() -> meshestra::Result<()> {
+//!     let container = ContainerBuilder::new()
+//!         .module::<AppModule>()?
+//!         .build();
 //!
+//!     // The module's controllers, each under its base path
+//!     let routes = AppModule::build_router::<AppState>(&container)?;
 //!     let state = AppState {
 //!         container: Arc::new(container),
 //!     };
 //!
-//!     let app = Router::new()
-//!         .with_state(state);
+//!     let app: Router = Router::new().merge(routes).with_state(state);
 //!
 //!     // Serve your app...
+//!     Ok(())
 //! }
 //! ```
 
 pub mod aspect;
 pub mod common;
+pub mod config;
 pub mod controller;
+pub mod cors;
+#[cfg(feature = "session")]
+pub mod csrf;
 pub mod di;
 pub mod error;
 pub mod exception;
 pub mod guard;
+pub mod health;
+pub mod i18n;
 pub mod interceptor;
 pub mod lifecycle;
 pub mod messaging;
+#[cfg(feature = "metrics")]
+pub mod metrics;
 pub mod module;
+pub mod openapi;
 pub mod pipe;
 pub mod saga;
+#[cfg(feature = "session")]
+pub mod session;
+pub mod testing;
 pub mod transactional;
 pub mod worker;
+#[cfg(feature = "websocket")]
+pub mod ws;
 
 // Re-export core types
 pub use common::ApiResponse;
@@ -106,10 +141,16 @@ pub use module::Module;
 
 // Re-export macros
 pub use meshestra_macro::{
-    Injectable as DeriveInjectable, body, controller, delete, exception_filter, get, handle,
-    module, param, patch, post, put, query, routes, transactional,
+    ApiSchema, Injectable as DeriveInjectable, MessageKeys, body, controller, cookie, cors,
+    csrf_exempt, csrf_token, current_user, delete, exception_filter, form, get, guard, handle,
+    head, header, host_param, ip, middleware, module, multipart, options, param, patch, pipe, post,
+    produces, public, put, query, rate_limit, response_header, roles, routes, service, session,
+    skip_global_pipes, status, test, timeout, transactional, version, ws,
 };
 
+#[cfg(feature = "multipart")]
+pub use meshestra_macro::FromMultipart;
+
 // Re-export commonly used types from dependencies
 pub use async_trait::async_trait;
 pub use axum;
@@ -121,28 +162,54 @@ pub use axum;
 /// ```
 pub mod prelude {
     pub use crate::aspect::Aspect;
-    pub use crate::common::ApiResponse;
+    pub use crate::common::{
+        ApiResponse, FileResponse, PageInfo, Pagination, ResultExt, StreamResponse,
+    };
+    pub use crate::config::{ConfigError, ConfigService, Secret, Validate};
+    pub use crate::controller::routing_options::{RoutingOptions, TrailingSlash};
+    pub use crate::controller::sse::{KeepAliveStream, Sse, Stream};
+    pub use crate::controller::versioning::VersioningConfig;
+    pub use crate::cors::CorsConfig;
     pub use crate::di::{Container, ContainerBuilder, HasContainer, Inject, Injectable, Lazy};
     pub use crate::error::{MeshestraError, Result};
-    pub use crate::exception::{ArgumentsHost, ExceptionFilter};
-    pub use crate::guard::{Guard, GuardError, GuardResult};
+    pub use crate::exception::{ArgumentsHost, Exception, ExceptionFilter, HttpException};
+    pub use crate::guard::{Claims, CurrentUser, Guard, GuardError, GuardResult};
+    pub use crate::health::{HealthCheckResult, HealthIndicator, HealthModule, HealthStatus};
+    pub use crate::i18n::{MessageCatalog, MessageKeys, MessageResolver};
     pub use crate::interceptor::{Interceptor, InterceptorResult, Next};
     pub use crate::lifecycle::{
         Application, ApplicationBuilder, LifecycleError, LifecycleManager, OnApplicationBootstrap,
         OnApplicationShutdown, OnModuleDestroy, OnModuleInit, ShutdownHandler, shutdown_signal,
     };
-    pub use crate::messaging::EventBus;
-    pub use crate::module::Module;
+    pub use crate::messaging::{EventBus, EventChannelConfig, EventStream};
+    pub use crate::module::{DynamicModule, DynamicProvider, GlobalPrefix, Module};
+    pub use crate::openapi::{ApiSchema, OpenApiConfig};
     pub use crate::pipe::builtins::*;
     pub use crate::pipe::{Pipe, PipeError, PipeResult};
-    pub use crate::saga::{SagaOrchestrator, SagaStep};
+    pub use crate::saga::{SagaListener, SagaOrchestrator, SagaStep};
     pub use crate::transactional::{ActiveTransaction, Transaction, TransactionManager};
     pub use crate::worker::WorkerPool;
     // Re-export specific filters if needed, but maybe not in prelude to avoid clutter
     // pub use crate::exception::http::HttpExceptionFilter;
+    #[cfg(feature = "session")]
+    pub use crate::csrf::{CsrfConfig, CsrfToken};
+    #[cfg(feature = "metrics")]
+    pub use crate::metrics::{MetricsInterceptor, MetricsModule, MetricsRegistry};
+    #[cfg(feature = "session")]
+    pub use crate::session::{Session, SessionConfig, SessionModule};
+    #[cfg(feature = "websocket")]
+    pub use crate::ws::{WebSocketSession, WsConnectionRegistry};
+    pub use crate::{
+        ApiSchema, DeriveInjectable as Injectable, MessageKeys, body, controller, cookie, cors,
+        csrf_exempt, csrf_token, current_user, delete, exception_filter, form, get, guard, handle,
+        head, header, host_param, ip, middleware, module, multipart, options, param, patch, pipe,
+        post, produces, public, put, query, rate_limit, response_header, roles, routes, service,
+        session, skip_global_pipes, status, timeout, transactional, version, ws,
+    };
+    #[cfg(feature = "multipart")]
     pub use crate::{
-        DeriveInjectable as Injectable, body, controller, delete, exception_filter, get, handle,
-        module, param, patch, post, put, query, routes, transactional,
+        FromMultipart,
+        controller::multipart::{FromMultipart, UploadedFile},
     };
     pub use async_trait::async_trait;
     pub use axum::{
@@ -4,10 +4,38 @@
 //! with integrated lifecycle management.
 
 use super::{
-    LifecycleError, LifecycleManager, OnApplicationBootstrap, OnApplicationShutdown,
+    DIAGNOSTICS_ENABLED_KEY, DIAGNOSTICS_PATH, DiagnosticsReport, LifecycleError,
+    LifecycleHookCounts, LifecycleManager, OnApplicationBootstrap, OnApplicationShutdown,
     OnModuleDestroy, OnModuleInit, Result, ShutdownHandler,
 };
-use crate::di::Container;
+use crate::common::error_body::{ApiEnvelope, ErrorBodyFormat, ErrorBodyLayer};
+use crate::config::{ConfigError, ConfigService};
+use crate::controller::extract::ClientIpConfig;
+use crate::controller::routing::{RouteDescriptor, format_route_table};
+use crate::controller::routing_options::RoutingOptions;
+use crate::controller::versioning::VersioningConfig;
+use crate::cors::{CorsConfig, CorsLayer};
+#[cfg(feature = "session")]
+use crate::csrf::{CsrfConfig, CsrfLayer};
+use crate::di::{Container, HasContainer, Injectable};
+use crate::exception::{ExceptionFilter, ExceptionFilterLayer};
+use crate::i18n::{MessageCatalog, MessageResolver};
+use crate::interceptor::builtins::{BodyLimitConfig, BodyLimitInterceptor};
+#[cfg(feature = "compression")]
+use crate::interceptor::builtins::{CompressionConfig, CompressionInterceptor};
+use crate::interceptor::{Interceptor, InterceptorLayer};
+use crate::messaging::EventBus;
+use crate::module::{GlobalPrefix, Module, MountState};
+use crate::openapi::{self, OpenApiConfig};
+use crate::pipe::{GlobalPipes, Pipe};
+#[cfg(feature = "session")]
+use crate::session::SessionModule;
+use crate::transactional::{NamedManagers, with_named_managers};
+use crate::worker::Scheduler;
+use axum::extract::Request;
+use axum::middleware::{Next, from_fn};
+use axum::routing::get;
+use axum::{Json, Router};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::RwLock;
@@ -40,6 +68,23 @@ use tokio::sync::RwLock;
 pub struct Application {
     container: Arc<Container>,
     lifecycle_manager: Arc<LifecycleManager>,
+    global_interceptors: Vec<GlobalInterceptor>,
+    global_filters: Vec<GlobalFilter>,
+    routes: Vec<RouteDescriptor>,
+    modules: usize,
+    controllers: usize,
+    diagnostics_route: bool,
+    global_prefix: GlobalPrefix,
+    event_bus: EventBus,
+    versioning: VersioningConfig,
+    routing_options: RoutingOptions,
+    cors: Option<CorsConfig>,
+    openapi: Option<OpenApiConfig>,
+    error_body_format: Option<Arc<dyn ErrorBodyFormat>>,
+    #[cfg(feature = "session")]
+    sessions: Option<SessionModule>,
+    #[cfg(feature = "session")]
+    csrf: Option<CsrfConfig>,
 }
 
 impl Application {
@@ -53,11 +98,210 @@ impl Application {
         &self.container
     }
 
+    /// The [`EventBus`] of the container, registered by
+    /// [`ApplicationBuilder::build`] if it had none
+    pub fn event_bus(&self) -> &EventBus {
+        &self.event_bus
+    }
+
     /// Get a reference to the lifecycle manager
     pub fn lifecycle_manager(&self) -> &Arc<LifecycleManager> {
         &self.lifecycle_manager
     }
 
+    /// The routes of the module given to [`ApplicationBuilder::module`],
+    /// those of [`ApplicationBuilder::openapi`] and the diagnostics route
+    pub fn routes(&self) -> &[RouteDescriptor] {
+        &self.routes
+    }
+
+    /// Log the table of [`routes`](Self::routes)
+    pub fn print_routes(&self) {
+        tracing::info!("Routes:\n{}", format_route_table(&self.routes));
+    }
+
+    /// The OpenAPI document of the [`routes`](Self::routes), with the info
+    /// of [`ApplicationBuilder::openapi`]
+    pub fn openapi(&self) -> serde_json::Value {
+        let default = OpenApiConfig::default();
+        let config = self.openapi.as_ref().unwrap_or(&default);
+        openapi::document(config, &self.routes, &self.versioning)
+    }
+
+    /// What the application is made of: the version, the profile, how many
+    /// modules, providers, controllers, routes and lifecycle hooks it has,
+    /// and the dependencies of its services that aren't registered
+    ///
+    /// Logged when the application is built if
+    /// [`ApplicationBuilder::print_banner`] says so, and served as JSON at
+    /// [`DIAGNOSTICS_PATH`] by [`router`](Self::router) if the config sets
+    /// [`DIAGNOSTICS_ENABLED_KEY`] to `true`.
+    pub fn diagnostics(&self) -> DiagnosticsReport {
+        let profile = ConfigService::inject(&self.container)
+            .ok()
+            .and_then(|config| config.profile().map(str::to_string));
+        let warnings = match self.container.validate() {
+            Ok(()) => Vec::new(),
+            Err(report) => report
+                .missing
+                .iter()
+                .map(|missing| format!("{} needs {}", missing.service, missing.dependency))
+                .collect(),
+        };
+        let manager = &self.lifecycle_manager;
+        DiagnosticsReport {
+            version: env!("CARGO_PKG_VERSION"),
+            profile,
+            modules: self.modules,
+            providers: self.container.len(),
+            controllers: self.controllers,
+            routes: self.routes.len(),
+            lifecycle_hooks: LifecycleHookCounts {
+                on_module_init: manager.init_hook_count(),
+                on_application_bootstrap: manager.bootstrap_hook_count(),
+                on_application_shutdown: manager.shutdown_hook_count(),
+                on_module_destroy: manager.destroy_hook_count(),
+            },
+            warnings,
+        }
+    }
+
+    /// Router serving the controllers of the module `M` and its imports,
+    /// injected from the application's container
+    ///
+    /// See [`Module::build_router`]; `M` must be registered in the container.
+    /// API versions are served as [`ApplicationBuilder::versioning`] says,
+    /// paths matched as [`ApplicationBuilder::routing_options`] says,
+    /// cross-origin requests answered as [`ApplicationBuilder::cors`] says,
+    /// requests given the sessions of [`ApplicationBuilder::sessions`], and
+    /// forged ones rejected as [`ApplicationBuilder::csrf`] says. The
+    /// OpenAPI document is served as [`ApplicationBuilder::openapi`] says,
+    /// and the [`diagnostics`](Self::diagnostics) if the config asks for them.
+    /// Error bodies are rendered as [`ApplicationBuilder::error_body_format`]
+    /// says.
+    /// Handlers see the container's [`NamedManagers`], if any.
+    ///
+    /// ```rust,ignore
+    /// let router = app
+    ///     .router::<AppModule, AppState>()?
+    ///     .layer(app.interceptor_layer())
+    ///     .with_state(state);
+    /// ```
+    pub fn router<M, S>(&self) -> crate::Result<Router<S>>
+    where
+        M: Module,
+        S: Clone + Send + Sync + HasContainer + 'static,
+    {
+        let mut routes = M::prefixed_routes(&self.global_prefix)?;
+        let mut router =
+            M::build_prefixed_router(&self.container, &self.versioning, &self.global_prefix)?;
+        if let Some(config) = &self.openapi {
+            router = router.merge(config.router(&self.openapi()));
+            routes.extend(config.routes());
+        }
+        if self.diagnostics_route {
+            let report = self.diagnostics();
+            router = router.route(DIAGNOSTICS_PATH, get(move || async move { Json(report) }));
+            routes.push(diagnostics_route());
+        }
+        let router = self
+            .routing_options
+            .apply(router, &routes, &self.versioning);
+        let router = match self.container.resolve::<NamedManagers>() {
+            Ok(managers) => router.layer(from_fn(move |request: Request, next: Next| {
+                with_named_managers(Arc::clone(&managers), next.run(request))
+            })),
+            Err(_) => router,
+        };
+        #[cfg(feature = "session")]
+        let router = match &self.csrf {
+            Some(csrf) => {
+                let csrf = csrf.clone().exempting(&routes, &self.versioning);
+                router.layer(CsrfLayer::new(csrf).routing_options(self.routing_options.clone()))
+            }
+            None => router,
+        };
+        // Inside CORS, so preflights don't load sessions.
+        #[cfg(feature = "session")]
+        let router = match &self.sessions {
+            Some(sessions) => router.layer(sessions.layer()),
+            None => router,
+        };
+        let router = match &self.cors {
+            Some(cors) => router.layer(CorsLayer::new(cors.clone())),
+            None => router,
+        };
+        Ok(match &self.error_body_format {
+            Some(_) => router.layer(self.error_body_layer()),
+            None => router,
+        })
+    }
+
+    /// Layer rendering error bodies as [`ApplicationBuilder::error_body_format`]
+    /// says, for the errors of layers added around [`router`](Self::router)
+    ///
+    /// ```rust,ignore
+    /// let router = app
+    ///     .router::<AppModule, AppState>()?
+    ///     .layer(app.interceptor_layer())
+    ///     .layer(app.error_body_layer());
+    /// ```
+    pub fn error_body_layer(&self) -> ErrorBodyLayer {
+        ErrorBodyLayer::new(
+            self.error_body_format
+                .clone()
+                .unwrap_or_else(|| Arc::new(ApiEnvelope)),
+        )
+    }
+
+    /// Layer applying the interceptors registered with
+    /// [`ApplicationBuilder::global_interceptor`] to every route
+    ///
+    /// Interceptors are resolved from the container on each call and ordered
+    /// by [`Interceptor::priority`], lowest outermost, then by registration
+    /// order. [`InterceptorLayer::order`] shows the result.
+    ///
+    /// ```rust,ignore
+    /// let router = Router::new()
+    ///     .merge(UserController::router(user_controller))
+    ///     .layer(app.interceptor_layer());
+    /// ```
+    pub fn interceptor_layer(&self) -> InterceptorLayer {
+        let interceptors = self
+            .global_interceptors
+            .iter()
+            .map(|interceptor| {
+                (interceptor.resolve)(&self.container).expect(
+                    "global interceptors are checked to resolve when the application is built",
+                )
+            })
+            .collect();
+        InterceptorLayer::new(interceptors)
+    }
+
+    /// Layer answering errors and panics with the filters registered with
+    /// [`ApplicationBuilder::global_filter`]
+    ///
+    /// Add it after (outside) [`interceptor_layer`](Self::interceptor_layer).
+    ///
+    /// ```rust,ignore
+    /// let router = Router::new()
+    ///     .merge(UserController::router(user_controller))
+    ///     .layer(app.interceptor_layer())
+    ///     .layer(app.exception_filter_layer());
+    /// ```
+    pub fn exception_filter_layer(&self) -> ExceptionFilterLayer {
+        let filters = self
+            .global_filters
+            .iter()
+            .map(|filter| {
+                (filter.resolve)(&self.container)
+                    .expect("global filters are checked to resolve when the application is built")
+            })
+            .collect();
+        ExceptionFilterLayer::new(filters).with_container(self.container.clone())
+    }
+
     /// Create a shutdown handler for graceful shutdown
     pub fn shutdown_handler(&self) -> ShutdownHandler {
         ShutdownHandler::new(Arc::clone(&self.lifecycle_manager))
@@ -65,10 +309,17 @@ impl Application {
 
     /// Perform graceful shutdown
     ///
-    /// This will call OnApplicationShutdown and OnModuleDestroy hooks.
+    /// This will close the open WebSocket sessions of a registered
+    /// `WsConnectionRegistry`, then call OnApplicationShutdown and
+    /// OnModuleDestroy hooks.
     pub async fn shutdown(&self) -> Result<()> {
         tracing::info!("Shutting down application...");
 
+        #[cfg(feature = "websocket")]
+        if let Ok(sessions) = self.container.resolve::<crate::ws::WsConnectionRegistry>() {
+            sessions.close_all();
+        }
+
         self.lifecycle_manager.call_application_shutdown().await?;
         self.lifecycle_manager.call_module_destroy().await?;
 
@@ -88,12 +339,75 @@ impl Application {
     }
 }
 
+type ConfigValidator =
+    Box<dyn Fn(&ConfigService) -> std::result::Result<(), ConfigError> + Send + Sync>;
+
+/// The [`Module::walk`] of the module given to [`ApplicationBuilder::module`]
+type ModuleWalk = fn(&GlobalPrefix) -> crate::Result<MountState>;
+
+/// The route serving [`Application::diagnostics`]
+fn diagnostics_route() -> RouteDescriptor {
+    RouteDescriptor {
+        method: "GET",
+        path: DIAGNOSTICS_PATH.to_string(),
+        handler: "Application::diagnostics",
+        guards: Vec::new(),
+        aspects: Vec::new(),
+        version: None,
+        cors: None,
+        csrf_exempt: false,
+        api: None,
+    }
+}
+
+/// Resolves a registered type from the container, as a `T`
+type Resolver<T> = Box<dyn Fn(&Container) -> crate::Result<T> + Send + Sync>;
+
+/// An interceptor type registered with [`ApplicationBuilder::global_interceptor`]
+struct GlobalInterceptor {
+    name: &'static str,
+    resolve: Resolver<Box<dyn Interceptor>>,
+}
+
+/// An exception filter type registered with [`ApplicationBuilder::global_filter`]
+struct GlobalFilter {
+    name: &'static str,
+    resolve: Resolver<Box<dyn ExceptionFilter>>,
+}
+
+/// Resolves a registered pipe from the container into the global pipes
+type AddPipe = Box<dyn Fn(&Container, &mut GlobalPipes) -> crate::Result<()> + Send + Sync>;
+
+/// A pipe type registered with [`ApplicationBuilder::global_pipe`]
+struct GlobalPipe {
+    name: &'static str,
+    add: AddPipe,
+}
+
 /// Builder for Application
 pub struct ApplicationBuilder {
     container: Option<Container>,
     lifecycle_manager: LifecycleManager,
     init_timeout: Option<Duration>,
     bootstrap_timeout: Option<Duration>,
+    config_validators: Vec<ConfigValidator>,
+    global_interceptors: Vec<GlobalInterceptor>,
+    global_filters: Vec<GlobalFilter>,
+    global_pipes: Vec<GlobalPipe>,
+    error_body_format: Option<Arc<dyn ErrorBodyFormat>>,
+    trust_forwarded_for: bool,
+    validate_dependencies: bool,
+    module_walk: Option<ModuleWalk>,
+    global_prefix: GlobalPrefix,
+    versioning: VersioningConfig,
+    routing_options: RoutingOptions,
+    cors: Option<CorsConfig>,
+    openapi: Option<OpenApiConfig>,
+    print_banner: bool,
+    #[cfg(feature = "session")]
+    sessions: Option<SessionModule>,
+    #[cfg(feature = "session")]
+    csrf: Option<CsrfConfig>,
 }
 
 impl Default for ApplicationBuilder {
@@ -110,6 +424,24 @@ impl ApplicationBuilder {
             lifecycle_manager: LifecycleManager::new(),
             init_timeout: None,
             bootstrap_timeout: None,
+            config_validators: Vec::new(),
+            global_interceptors: Vec::new(),
+            global_filters: Vec::new(),
+            global_pipes: Vec::new(),
+            error_body_format: None,
+            trust_forwarded_for: false,
+            validate_dependencies: true,
+            module_walk: None,
+            global_prefix: GlobalPrefix::default(),
+            versioning: VersioningConfig::default(),
+            routing_options: RoutingOptions::default(),
+            cors: None,
+            openapi: None,
+            print_banner: false,
+            #[cfg(feature = "session")]
+            sessions: None,
+            #[cfg(feature = "session")]
+            csrf: None,
         }
     }
 
@@ -131,6 +463,335 @@ impl ApplicationBuilder {
         self
     }
 
+    /// Validate configuration before any lifecycle hook runs
+    ///
+    /// The validator receives the container's `ConfigService` (or one loaded
+    /// from the environment). Issues from all validators are combined into a
+    /// single error and the application refuses to start.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let app = Application::builder()
+    ///     .container(container)
+    ///     .validate_config(|c| c.require(&["DATABASE_URL", "JWT_SECRET"]))
+    ///     .validate_config(|c| c.bind_validated::<DatabaseConfig>("database").map(|_| ()))
+    ///     .build()
+    ///     .await?;
+    /// ```
+    pub fn validate_config<F>(mut self, validator: F) -> Self
+    where
+        F: Fn(&ConfigService) -> std::result::Result<(), ConfigError> + Send + Sync + 'static,
+    {
+        self.config_validators.push(Box::new(validator));
+        self
+    }
+
+    /// Apply the interceptor `T` to every route
+    ///
+    /// `T` is resolved from the container, so it can have injected
+    /// dependencies. Interceptors with the same [`Interceptor::priority`] run
+    /// in registration order: the first one registered is the outermost and
+    /// sees the request first and the response last. Apply them with
+    /// [`Application::interceptor_layer`].
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let app = Application::builder()
+    ///     .container(container)
+    ///     .global_interceptor::<LoggingInterceptor>()
+    ///     .global_interceptor::<MetricsInterceptor>()
+    ///     .build()
+    ///     .await?;
+    /// ```
+    pub fn global_interceptor<T: Interceptor>(mut self) -> Self {
+        self.global_interceptors.push(GlobalInterceptor {
+            name: std::any::type_name::<T>(),
+            resolve: Box::new(|container| {
+                container
+                    .resolve::<T>()
+                    .map(|interceptor| Box::new(interceptor) as Box<dyn Interceptor>)
+            }),
+        });
+        self
+    }
+
+    /// Apply an already constructed interceptor to every route
+    ///
+    /// Like [`global_interceptor`](Self::global_interceptor), for
+    /// interceptors configured in code rather than resolved from the
+    /// container.
+    pub fn with_interceptor(mut self, interceptor: impl Interceptor) -> Self {
+        let interceptor = Arc::new(interceptor);
+        self.global_interceptors.push(GlobalInterceptor {
+            name: interceptor.name(),
+            resolve: Box::new(move |_| {
+                Ok(Box::new(Arc::clone(&interceptor)) as Box<dyn Interceptor>)
+            }),
+        });
+        self
+    }
+
+    /// Reject request bodies over `config.max_bytes` with 413 on every route
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let app = Application::builder()
+    ///     .container(container)
+    ///     .body_limit(BodyLimitConfig { max_bytes: 64 * 1024 })
+    ///     .build()
+    ///     .await?;
+    /// ```
+    pub fn body_limit(self, config: BodyLimitConfig) -> Self {
+        self.with_interceptor(BodyLimitInterceptor::new(config))
+    }
+
+    /// Compress responses on every route, see [`CompressionInterceptor`]
+    #[cfg(feature = "compression")]
+    pub fn compression(self, config: CompressionConfig) -> Self {
+        self.with_interceptor(CompressionInterceptor::new(config))
+    }
+
+    /// Answer errors and panics on every route with the filter `F`
+    ///
+    /// `F` is resolved from the container. Filters are consulted in
+    /// registration order and the first whose
+    /// [`catches`](ExceptionFilter::catches) claims an error builds the
+    /// response; unclaimed errors get a JSON 500. Apply them with
+    /// [`Application::exception_filter_layer`].
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let app = Application::builder()
+    ///     .container(container)
+    ///     .global_filter::<NotFoundFilter>()
+    ///     .global_filter::<HttpExceptionFilter>()
+    ///     .build()
+    ///     .await?;
+    /// ```
+    pub fn global_filter<F: ExceptionFilter>(mut self) -> Self {
+        self.global_filters.push(GlobalFilter {
+            name: std::any::type_name::<F>(),
+            resolve: Box::new(|container| {
+                container
+                    .resolve::<F>()
+                    .map(|filter| Box::new(filter) as Box<dyn ExceptionFilter>)
+            }),
+        });
+        self
+    }
+
+    /// Run the pipe `P` on every `#[body]`, `#[form]` and `#[query]` value of
+    /// `#[routes]`-generated handlers
+    ///
+    /// `P` is resolved from the container and applies to values of its
+    /// `Input` type, which it must also return. Global pipes run in
+    /// registration order, before the parameter's own `#[pipe(...)]`s;
+    /// routes opt out with `#[skip_global_pipes]`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// container.register(ValidationPipe::<CreateUser>::new());
+    ///
+    /// let app = Application::builder()
+    ///     .container(container)
+    ///     .global_pipe::<ValidationPipe<CreateUser>>()
+    ///     .build()
+    ///     .await?;
+    /// ```
+    pub fn global_pipe<P>(mut self) -> Self
+    where
+        P: Pipe<Output = <P as Pipe>::Input> + 'static,
+        P::Input: 'static,
+    {
+        self.global_pipes.push(GlobalPipe {
+            name: std::any::type_name::<P>(),
+            add: Box::new(|container, pipes| {
+                pipes.add(container.resolve::<P>()?);
+                Ok(())
+            }),
+        });
+        self
+    }
+
+    /// Render the body of every error response with `format`
+    ///
+    /// Replaces the default `ApiResponse` envelope for framework errors,
+    /// guard denials and the exception filters' fallback alike, in the
+    /// responses of [`Application::router`]. Other applications in the
+    /// process keep their own format.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let app = Application::builder()
+    ///     .container(container)
+    ///     .error_body_format(|status: StatusCode, error: &ApiError| {
+    ///         (status, Json(json!({ "title": error.code, "detail": error.message })))
+    ///             .into_response()
+    ///     })
+    ///     .build()
+    ///     .await?;
+    /// ```
+    pub fn error_body_format(mut self, format: impl ErrorBodyFormat) -> Self {
+        self.error_body_format = Some(Arc::new(format));
+        self
+    }
+
+    /// Have `#[ip]` parameters take the client's address from the leftmost
+    /// `X-Forwarded-For` entry instead of the peer address
+    ///
+    /// Only enable this behind a proxy that overwrites the header.
+    pub fn trust_forwarded_for(mut self, trust: bool) -> Self {
+        self.trust_forwarded_for = trust;
+        self
+    }
+
+    /// Whether the build checks the dependencies of the container's
+    /// services with [`Container::validate`], on by default
+    pub fn validate_dependencies(mut self, validate: bool) -> Self {
+        self.validate_dependencies = validate;
+        self
+    }
+
+    /// Serve the routes of the module `M` and its imports
+    ///
+    /// Their table is checked for conflicting routes and logged when the
+    /// application is built, and available from [`Application::routes`].
+    /// Build the router itself with [`Application::router`].
+    pub fn module<M: Module>(mut self) -> Self {
+        self.module_walk = Some(M::walk);
+        self
+    }
+
+    /// Serve the routes of the module under `prefix`, `/api` say, before
+    /// the prefixes of `#[module(prefix = "...")]` and the controllers'
+    /// base paths
+    ///
+    /// The endpoints of `HealthModule` and `MetricsModule` are prefixed
+    /// too, unless [`GlobalPrefix::exclude_framework_routes`] says not to:
+    ///
+    /// ```rust,ignore
+    /// let app = Application::builder()
+    ///     .module::<AppModule>()
+    ///     .global_prefix(GlobalPrefix::new("/api").exclude_framework_routes())
+    ///     .build()
+    ///     .await?;
+    /// ```
+    pub fn global_prefix(mut self, prefix: impl Into<GlobalPrefix>) -> Self {
+        self.global_prefix = prefix.into();
+        self
+    }
+
+    /// How [`Application::router`] serves the API versions of
+    /// `#[controller(version = "...")]` and `#[version(...)]`: under a URI
+    /// prefix, the default, or picked by a header
+    ///
+    /// ```rust,ignore
+    /// let app = Application::builder()
+    ///     .module::<AppModule>()
+    ///     .versioning(VersioningConfig::header())
+    ///     .build()
+    ///     .await?;
+    /// ```
+    pub fn versioning(mut self, config: VersioningConfig) -> Self {
+        self.versioning = config;
+        self
+    }
+
+    /// How [`Application::router`] matches paths: whether `/users/` is
+    /// served by `/users`, redirected there, or a 404 like now, and
+    /// whether `/Users` is served by `/users`
+    pub fn routing_options(mut self, options: RoutingOptions) -> Self {
+        self.routing_options = options;
+        self
+    }
+
+    /// Answer cross-origin requests to the routes of [`Application::router`]
+    /// as `config` says; controllers override parts of it with `#[cors(...)]`
+    ///
+    /// The configuration and the overrides of the module's routes are checked
+    /// when the application is built.
+    ///
+    /// ```rust,ignore
+    /// let app = Application::builder()
+    ///     .module::<AppModule>()
+    ///     .cors(CorsConfig::new().allow_origin("https://*.example.com"))
+    ///     .build()
+    ///     .await?;
+    /// ```
+    pub fn cors(mut self, config: CorsConfig) -> Self {
+        self.cors = Some(config);
+        self
+    }
+
+    /// Serve the OpenAPI document of the module's routes at
+    /// `/openapi.json`, and Swagger UI at `/docs`, from
+    /// [`Application::router`]
+    ///
+    /// The paths are checked against the module's routes when the
+    /// application is built.
+    ///
+    /// ```rust,ignore
+    /// let app = Application::builder()
+    ///     .module::<AppModule>()
+    ///     .openapi(OpenApiConfig::new("Users", "1.0.0").description("User accounts"))
+    ///     .build()
+    ///     .await?;
+    /// ```
+    pub fn openapi(mut self, config: OpenApiConfig) -> Self {
+        self.openapi = Some(config);
+        self
+    }
+
+    /// Log the [`Application::diagnostics`] once the application is built
+    pub fn print_banner(mut self, print: bool) -> Self {
+        self.print_banner = print;
+        self
+    }
+
+    /// Give the requests to the routes of [`Application::router`] the
+    /// sessions of `sessions`, for `#[session]` parameters
+    ///
+    /// The configuration is checked when the application is built.
+    ///
+    /// ```rust,ignore
+    /// let app = Application::builder()
+    ///     .module::<AppModule>()
+    ///     .sessions(SessionModule::new(SessionConfig::new(secret).rolling(true)))
+    ///     .build()
+    ///     .await?;
+    /// ```
+    #[cfg(feature = "session")]
+    pub fn sessions(mut self, sessions: SessionModule) -> Self {
+        self.sessions = Some(sessions);
+        self
+    }
+
+    /// Reject POST, PUT, PATCH and DELETE requests to the routes of
+    /// [`Application::router`] without the session's CSRF token, as
+    /// `config` says; routes marked `#[csrf_exempt]` are skipped
+    ///
+    /// Needs [`sessions`](Self::sessions), which the build checks.
+    ///
+    /// ```rust,ignore
+    /// let app = Application::builder()
+    ///     .module::<AppModule>()
+    ///     .sessions(SessionModule::new(SessionConfig::new(secret)))
+    ///     .csrf(CsrfConfig::new().exempt("/health/ping"))
+    ///     .build()
+    ///     .await?;
+    /// ```
+    #[cfg(feature = "session")]
+    pub fn csrf(mut self, config: CsrfConfig) -> Self {
+        self.csrf = Some(config);
+        self
+    }
+
     /// Register a service that implements OnModuleInit
     pub fn on_init<T>(mut self, service: Arc<RwLock<T>>, name: impl Into<String>) -> Self
     where
@@ -167,6 +828,12 @@ impl ApplicationBuilder {
         self
     }
 
+    /// Register a scheduler so its tasks start on bootstrap and stop on shutdown
+    pub fn scheduler(self, scheduler: Arc<RwLock<Scheduler>>) -> Self {
+        self.on_bootstrap(Arc::clone(&scheduler), "Scheduler")
+            .on_shutdown(scheduler, "Scheduler")
+    }
+
     /// Register a service for all lifecycle hooks it implements
     ///
     /// This is a convenience method that registers the service for
@@ -203,19 +870,157 @@ impl ApplicationBuilder {
     /// Build and initialize the application
     ///
     /// This will:
-    /// 1. Call all OnModuleInit hooks
-    /// 2. Call all OnApplicationBootstrap hooks
+    /// 1. Register an [`EventBus`] unless the container has one, so the
+    ///    hooks below can subscribe to it, and bind a [`MessageCatalog`]
+    ///    as the [`MessageResolver`] unless one is bound
+    /// 2. Run all config validators
+    /// 3. Check that every global interceptor, filter and pipe resolves
+    ///    from the container
+    /// 4. Check the routes and the CORS configuration
+    /// 5. Check the dependencies of the container's services, see
+    ///    [`validate_dependencies`](Self::validate_dependencies)
+    /// 6. Call all OnModuleInit hooks
+    /// 7. Call all OnApplicationBootstrap hooks
+    /// 8. [`Seal`](Container::seal) the container
+    /// 9. Log the [`Application::diagnostics`] if
+    ///    [`print_banner`](Self::print_banner) says so
     ///
     /// # Errors
     ///
-    /// Returns an error if any lifecycle hook fails.
+    /// Returns an error if config validation, interceptor resolution, the
+    /// CORS configuration, dependency validation or any lifecycle hook
+    /// fails.
     pub async fn build(self) -> Result<Application> {
-        let container = self
+        let mut container = self
             .container
             .ok_or_else(|| LifecycleError::init_failed("Container not provided"))?;
 
         tracing::info!("Starting application initialization...");
 
+        if !container.contains::<EventBus>() {
+            container.register(EventBus::new());
+        }
+        if container.resolve_trait::<dyn MessageResolver>().is_err() {
+            if !container.contains::<MessageCatalog>() {
+                container.register(MessageCatalog::default());
+            }
+            container.register_trait::<dyn MessageResolver, MessageCatalog, _>(|catalog| {
+                catalog as Arc<dyn MessageResolver>
+            });
+        }
+        let event_bus =
+            EventBus::inject(&container).map_err(|e| LifecycleError::init_failed(e.to_string()))?;
+
+        // Validate configuration before any hook can observe it
+        if !self.config_validators.is_empty() {
+            validate_config(&container, &self.config_validators)?;
+        }
+
+        for interceptor in &self.global_interceptors {
+            (interceptor.resolve)(&container).map_err(|e| {
+                LifecycleError::init_failed(format!(
+                    "Global interceptor {} could not be resolved: {}",
+                    interceptor.name, e
+                ))
+            })?;
+        }
+
+        for filter in &self.global_filters {
+            (filter.resolve)(&container).map_err(|e| {
+                LifecycleError::init_failed(format!(
+                    "Global filter {} could not be resolved: {}",
+                    filter.name, e
+                ))
+            })?;
+        }
+
+        if !self.global_pipes.is_empty() {
+            let mut pipes = GlobalPipes::new();
+            for pipe in &self.global_pipes {
+                (pipe.add)(&container, &mut pipes).map_err(|e| {
+                    LifecycleError::init_failed(format!(
+                        "Global pipe {} could not be resolved: {}",
+                        pipe.name, e
+                    ))
+                })?;
+            }
+            container.register(pipes);
+        }
+
+        if self.trust_forwarded_for {
+            container.register(ClientIpConfig {
+                trust_forwarded_for: true,
+            });
+        }
+
+        let (mut routes, modules, controllers) = match self.module_walk {
+            Some(walk) => {
+                let state = walk(&self.global_prefix)
+                    .map_err(|e| LifecycleError::init_failed(e.to_string()))?;
+                let (modules, controllers) = (state.module_count(), state.controller_count());
+                (state.into_routes(), modules, controllers)
+            }
+            None => (Vec::new(), 0, 0),
+        };
+        let diagnostics_route = match ConfigService::inject(&container) {
+            Ok(config) => config.get_or(DIAGNOSTICS_ENABLED_KEY, false),
+            Err(_) => false,
+        };
+        let mut served = self
+            .openapi
+            .as_ref()
+            .map_or_else(Vec::new, OpenApiConfig::routes);
+        if diagnostics_route {
+            served.push(self::diagnostics_route());
+        }
+        for served in served {
+            if let Some(route) = routes.iter().find(|route| route.conflicts_with(&served)) {
+                return Err(LifecycleError::init_failed(format!(
+                    "{} {} is routed to both {} and {}",
+                    served.method, served.path, route.handler, served.handler
+                )));
+            }
+            routes.push(served);
+        }
+        if !routes.is_empty() {
+            tracing::info!("Routes:\n{}", format_route_table(&routes));
+        }
+
+        if let Some(cors) = &self.cors {
+            cors.validate()
+                .map_err(|e| LifecycleError::init_failed(e.to_string()))?;
+            for route in &routes {
+                if let Some(cors_override) = route.cors {
+                    cors.with_override(cors_override).validate().map_err(|e| {
+                        LifecycleError::init_failed(format!(
+                            "CORS for {} is invalid: {}",
+                            route.handler, e
+                        ))
+                    })?;
+                }
+            }
+        }
+
+        #[cfg(feature = "session")]
+        if let Some(sessions) = &self.sessions {
+            sessions
+                .config()
+                .validate()
+                .map_err(|e| LifecycleError::init_failed(e.to_string()))?;
+        }
+        #[cfg(feature = "session")]
+        if self.csrf.is_some() && self.sessions.is_none() {
+            return Err(LifecycleError::init_failed(
+                "CSRF protection needs sessions, see ApplicationBuilder::sessions",
+            ));
+        }
+
+        if self.validate_dependencies {
+            container
+                .validate()
+                .map_err(|report| LifecycleError::init_failed(report.to_string()))?;
+        }
+
         // Call OnModuleInit hooks
         if let Some(timeout) = self.init_timeout {
             self.lifecycle_manager
@@ -236,9 +1041,170 @@ impl ApplicationBuilder {
 
         tracing::info!("Application initialization complete");
 
-        Ok(Application {
+        container.seal();
+        let app = Application {
             container: Arc::new(container),
             lifecycle_manager: Arc::new(self.lifecycle_manager),
-        })
+            global_interceptors: self.global_interceptors,
+            global_filters: self.global_filters,
+            routes,
+            modules,
+            controllers,
+            diagnostics_route,
+            global_prefix: self.global_prefix,
+            event_bus,
+            versioning: self.versioning,
+            routing_options: self.routing_options,
+            cors: self.cors,
+            openapi: self.openapi,
+            error_body_format: self.error_body_format,
+            #[cfg(feature = "session")]
+            sessions: self.sessions,
+            #[cfg(feature = "session")]
+            csrf: self.csrf,
+        };
+        if self.print_banner {
+            tracing::info!("{}", app.diagnostics());
+        }
+        Ok(app)
+    }
+}
+
+fn validate_config(container: &Container, validators: &[ConfigValidator]) -> Result<()> {
+    let config =
+        ConfigService::inject(container).map_err(|e| LifecycleError::init_failed(e.to_string()))?;
+
+    let mut issues = Vec::new();
+    for validator in validators {
+        match validator(&config) {
+            Ok(()) => {}
+            Err(ConfigError::Validation { issues: found }) => issues.extend(found),
+            Err(e) => issues.push(e.to_string()),
+        }
+    }
+
+    if issues.is_empty() {
+        return Ok(());
+    }
+    for issue in &issues {
+        tracing::error!("Configuration: {}", issue);
+    }
+    Err(ConfigError::Validation { issues }.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interceptor::{InterceptorResult, Next};
+    use async_trait::async_trait;
+    use axum::{Router, body::Body, http::Request, routing::get};
+    use std::sync::Mutex;
+    use tower::ServiceExt;
+
+    #[derive(Default)]
+    struct Log(Mutex<Vec<&'static str>>);
+
+    /// Interceptors with an injected dependency
+    struct Outer(Arc<Log>);
+    struct Inner(Arc<Log>);
+
+    #[async_trait]
+    impl Interceptor for Outer {
+        async fn intercept(&self, request: Request<Body>, next: Next) -> InterceptorResult {
+            self.0.0.lock().unwrap().push("outer");
+            next.run(request).await
+        }
+    }
+
+    #[async_trait]
+    impl Interceptor for Inner {
+        async fn intercept(&self, request: Request<Body>, next: Next) -> InterceptorResult {
+            self.0.0.lock().unwrap().push("inner");
+            next.run(request).await
+        }
+    }
+
+    #[tokio::test]
+    async fn global_interceptors_run_in_registration_order() {
+        let log = Arc::new(Log::default());
+        let mut container = Container::new();
+        container.register(Outer(log.clone()));
+        container.register(Inner(log.clone()));
+
+        let app = Application::builder()
+            .container(container)
+            .global_interceptor::<Outer>()
+            .global_interceptor::<Inner>()
+            .build()
+            .await
+            .unwrap();
+
+        let router: Router = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(app.interceptor_layer());
+        router.oneshot(Request::new(Body::empty())).await.unwrap();
+
+        assert_eq!(*log.0.lock().unwrap(), ["outer", "inner"]);
+    }
+
+    #[tokio::test]
+    async fn unresolvable_global_interceptor_fails_the_build() {
+        let result = Application::builder()
+            .container(Container::new())
+            .global_interceptor::<Outer>()
+            .build()
+            .await;
+
+        assert!(result.is_err());
+    }
+
+    /// A service publishing on the injected bus
+    struct Notifier {
+        bus: EventBus,
+    }
+
+    impl Injectable for Notifier {
+        fn inject(container: &Container) -> crate::error::Result<Self> {
+            Ok(Self {
+                bus: EventBus::inject(container)?,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn build_registers_an_event_bus() {
+        let error = Notifier::inject(&Container::new()).err().unwrap();
+        assert!(matches!(
+            error,
+            crate::error::MeshestraError::DependencyNotFound { .. }
+        ));
+
+        let app = Application::builder()
+            .container(Container::new())
+            .build()
+            .await
+            .unwrap();
+        let notifier = Notifier::inject(app.container()).unwrap();
+
+        let mut events = app.event_bus().subscribe::<&'static str>();
+        notifier.bus.publish("sent");
+        let event = events.recv().await.unwrap();
+        assert_eq!(event.downcast_ref::<&'static str>(), Some(&"sent"));
+    }
+
+    #[tokio::test]
+    async fn build_keeps_the_registered_event_bus() {
+        let bus = EventBus::new();
+        let _events = bus.subscribe::<&'static str>();
+        let mut container = Container::new();
+        container.register(bus.clone());
+
+        let app = Application::builder()
+            .container(container)
+            .build()
+            .await
+            .unwrap();
+
+        assert_eq!(app.event_bus().subscriber_count::<&'static str>(), 1);
     }
 }
@@ -0,0 +1,139 @@
+//! Diagnostics Report
+//!
+//! What [`Application::diagnostics`](super::Application::diagnostics) says
+//! about a built application: logged as the startup banner and served by
+//! the diagnostics route.
+
+use serde::Serialize;
+use std::fmt;
+
+/// The config key turning on the diagnostics route at [`DIAGNOSTICS_PATH`]
+pub const DIAGNOSTICS_ENABLED_KEY: &str = "meshestra.diagnostics.enabled";
+
+/// Where the diagnostics route serves the report as JSON
+pub const DIAGNOSTICS_PATH: &str = "/_meshestra/diagnostics";
+
+/// A summary of a built application
+///
+/// Displayed as the startup banner:
+///
+/// ```text
+/// meshestra 0.1.0 (profile: production)
+///   modules: 3, providers: 12, controllers: 4, routes: 17
+///   lifecycle hooks: 2 init, 1 bootstrap, 1 shutdown, 2 destroy
+///   warnings:
+///     app::Audit needs app::Tracer
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DiagnosticsReport {
+    /// The version of meshestra
+    pub version: &'static str,
+    /// The active [`ConfigService::profile`](crate::config::ConfigService::profile)
+    pub profile: Option<String>,
+    /// The modules of the application module's tree
+    pub modules: usize,
+    /// The services registered in the container
+    pub providers: usize,
+    /// The controllers of the modules
+    pub controllers: usize,
+    /// The routes served, the framework's included
+    pub routes: usize,
+    pub lifecycle_hooks: LifecycleHookCounts,
+    /// The dependencies of registered services that aren't registered
+    pub warnings: Vec<String>,
+}
+
+/// How many services registered for each lifecycle hook
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct LifecycleHookCounts {
+    pub on_module_init: usize,
+    pub on_application_bootstrap: usize,
+    pub on_application_shutdown: usize,
+    pub on_module_destroy: usize,
+}
+
+impl fmt::Display for DiagnosticsReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "meshestra {}", self.version)?;
+        if let Some(profile) = &self.profile {
+            write!(f, " (profile: {})", profile)?;
+        }
+        write!(
+            f,
+            "\n  modules: {}, providers: {}, controllers: {}, routes: {}",
+            self.modules, self.providers, self.controllers, self.routes
+        )?;
+        let hooks = &self.lifecycle_hooks;
+        write!(
+            f,
+            "\n  lifecycle hooks: {} init, {} bootstrap, {} shutdown, {} destroy",
+            hooks.on_module_init,
+            hooks.on_application_bootstrap,
+            hooks.on_application_shutdown,
+            hooks.on_module_destroy
+        )?;
+        if !self.warnings.is_empty() {
+            write!(f, "\n  warnings:")?;
+            for warning in &self.warnings {
+                write!(f, "\n    {}", warning)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> DiagnosticsReport {
+        DiagnosticsReport {
+            version: "0.1.0",
+            profile: Some("production".to_string()),
+            modules: 3,
+            providers: 12,
+            controllers: 4,
+            routes: 17,
+            lifecycle_hooks: LifecycleHookCounts {
+                on_module_init: 2,
+                on_application_bootstrap: 1,
+                on_application_shutdown: 1,
+                on_module_destroy: 2,
+            },
+            warnings: vec!["app::Audit needs app::Tracer".to_string()],
+        }
+    }
+
+    #[test]
+    fn displays_as_a_banner() {
+        assert_eq!(
+            report().to_string(),
+            "meshestra 0.1.0 (profile: production)\n\
+             \x20 modules: 3, providers: 12, controllers: 4, routes: 17\n\
+             \x20 lifecycle hooks: 2 init, 1 bootstrap, 1 shutdown, 2 destroy\n\
+             \x20 warnings:\n\
+             \x20   app::Audit needs app::Tracer"
+        );
+    }
+
+    #[test]
+    fn banner_leaves_out_what_is_missing() {
+        let report = DiagnosticsReport {
+            profile: None,
+            warnings: Vec::new(),
+            ..report()
+        };
+
+        assert_eq!(report.to_string().lines().count(), 3);
+        assert!(report.to_string().starts_with("meshestra 0.1.0\n"));
+    }
+
+    #[test]
+    fn serializes_as_json() {
+        let json = serde_json::to_value(report()).unwrap();
+
+        assert_eq!(json["profile"], "production");
+        assert_eq!(json["lifecycle_hooks"]["on_module_destroy"], 2);
+        assert_eq!(json["warnings"][0], "app::Audit needs app::Tracer");
+    }
+}
@@ -1,5 +1,6 @@
 //! Lifecycle-specific error types
 
+use crate::config::ConfigError;
 use thiserror::Error;
 
 /// Errors that can occur during lifecycle operations
@@ -30,6 +31,10 @@ pub enum LifecycleError {
         /// Error message
         message: String,
     },
+
+    /// Configuration failed validation at startup
+    #[error("Configuration error: {0}")]
+    Config(#[from] ConfigError),
 }
 
 impl LifecycleError {
@@ -58,13 +58,17 @@
 //! ```
 
 mod application;
+mod diagnostics;
 mod error;
 mod manager;
 mod shutdown;
 mod traits;
 
 pub use application::{Application, ApplicationBuilder};
+pub use diagnostics::{
+    DIAGNOSTICS_ENABLED_KEY, DIAGNOSTICS_PATH, DiagnosticsReport, LifecycleHookCounts,
+};
 pub use error::{LifecycleError, Result};
 pub use manager::LifecycleManager;
-pub use shutdown::{shutdown_signal, ShutdownHandler};
+pub use shutdown::{ShutdownHandler, shutdown_signal};
 pub use traits::{OnApplicationBootstrap, OnApplicationShutdown, OnModuleDestroy, OnModuleInit};
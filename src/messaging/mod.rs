@@ -1,13 +1,73 @@
+use crate::controller::sse::{Event, KeepAliveStream, LagPolicy, Sse, SseOptions, Stream};
+use crate::di::{Container, Injectable};
 use dashmap::DashMap;
+use futures_util::future::{BoxFuture, FutureExt};
+use futures_util::stream::{self, BoxStream, StreamExt};
+use serde::Serialize;
 use std::any::{Any, TypeId};
+use std::convert::Infallible;
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context, Poll};
 use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{Instrument, Span};
+
+/// A handler given to [`EventBus::on`], taking the published event
+type Handler = Arc<dyn Fn(&Arc<dyn Any + Send + Sync>) -> BoxFuture<'static, ()> + Send + Sync>;
+
+/// A published event, of any type
+type AnyEvent = Arc<dyn Any + Send + Sync>;
 
 /// A simple in-memory event bus
+///
+/// [`ApplicationBuilder::build`](crate::lifecycle::ApplicationBuilder::build)
+/// registers one in the container unless there is one already, see
+/// [`Application::event_bus`](crate::lifecycle::Application::event_bus).
+/// Services inject it as a dependency; modules shouldn't list it among their
+/// `providers`, since every clone publishes to the same subscribers and
+/// there must be only one.
 #[derive(Clone)]
 pub struct EventBus {
     // Map of Event Type -> Broadcast Sender
     channels: Arc<DashMap<TypeId, broadcast::Sender<Arc<dyn Any + Send + Sync>>>>,
+    handlers: Arc<DashMap<TypeId, Vec<Handler>>>,
+    capacity: usize,
+    /// Times an event stream fell behind, see [`EventBusStats::lagged_receivers`]
+    lagged: Arc<AtomicU64>,
+    /// The last event of each type configured with
+    /// [`replay_last`](EventChannelConfig::replay_last), if any was published
+    retained: Arc<DashMap<TypeId, Option<AnyEvent>>>,
+}
+
+/// How an [`EventBus`] delivers the events of a type, see
+/// [`EventBus::configure`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EventChannelConfig {
+    /// Keep the last event published and hand it to the handlers and event
+    /// streams added afterwards, as soon as they are added
+    ///
+    /// Only the last event is kept: publishing another replaces it.
+    pub replay_last: bool,
+}
+
+/// Point-in-time counters of an [`EventBus`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EventBusStats {
+    /// Event types subscribed to
+    pub channels: usize,
+    /// Receivers subscribed, across event types
+    pub subscribers: usize,
+    /// [`on`](EventBus::on) handlers, across event types
+    pub handlers: usize,
+    /// Channels holding `capacity` events some receiver hasn't received: the
+    /// next event published makes it miss one
+    pub full_channels: usize,
+    /// Times an event stream of [`sse_stream`](EventBus::sse_stream) fell
+    /// behind and missed events
+    pub lagged_receivers: u64,
 }
 
 impl Default for EventBus {
@@ -16,19 +76,118 @@ impl Default for EventBus {
     }
 }
 
+/// Resolves the `EventBus` registered in the container; a new one would
+/// have none of its subscribers.
+impl Injectable for EventBus {
+    fn inject(container: &Container) -> crate::error::Result<Self> {
+        Ok(container.resolve::<EventBus>()?.as_ref().clone())
+    }
+}
+
 impl EventBus {
     pub fn new() -> Self {
+        Self::with_capacity(100)
+    }
+
+    /// A bus keeping up to `capacity` events of each type for subscribers
+    /// that haven't received them yet; one falling further behind misses
+    /// the oldest, see [`LagPolicy`]
+    ///
+    /// # Panics
+    /// Panics if `capacity` is 0.
+    pub fn with_capacity(capacity: usize) -> Self {
+        assert!(capacity > 0, "EventBus capacity must be positive");
         Self {
             channels: Arc::new(DashMap::new()),
+            handlers: Arc::new(DashMap::new()),
+            capacity,
+            lagged: Arc::new(AtomicU64::new(0)),
+            retained: Arc::new(DashMap::new()),
+        }
+    }
+
+    /// Configure the delivery of the events `E`
+    ///
+    /// With [`replay_last`](EventChannelConfig::replay_last), a handler
+    /// added by the `OnModuleInit` hook of one service receives the event
+    /// another service published from an earlier hook:
+    ///
+    /// ```rust,ignore
+    /// events.configure::<StartupCompleted>(EventChannelConfig { replay_last: true });
+    /// ```
+    pub fn configure<E: Clone + Send + Sync + 'static>(&self, config: EventChannelConfig) {
+        let type_id = TypeId::of::<E>();
+        if config.replay_last {
+            self.retained.entry(type_id).or_insert(None);
+        } else {
+            self.retained.remove(&type_id);
         }
     }
 
+    /// The last event `E` published, when `E` is configured to
+    /// [`replay_last`](EventChannelConfig::replay_last)
+    pub fn last<E: Clone + Send + Sync + 'static>(&self) -> Option<E> {
+        let retained = self.retained.get(&TypeId::of::<E>())?;
+        retained.as_ref()?.downcast_ref::<E>().cloned()
+    }
+
     /// Publish an event
+    ///
+    /// Each [`on`](Self::on) handler of `E` is spawned on a task of its
+    /// own, so this must be called within a Tokio runtime when there are
+    /// any.
     pub fn publish<E: Clone + Send + Sync + 'static>(&self, event: E) {
         let type_id = TypeId::of::<E>();
+        let event: AnyEvent = Arc::new(event);
+        // Held while delivering, so a handler or stream being added either
+        // gets the event delivered or replayed, not both
+        let retained = self.retained.get_mut(&type_id).map(|mut retained| {
+            *retained = Some(event.clone());
+            retained
+        });
+        if let Some(handlers) = self.handlers.get(&type_id) {
+            // The handlers log in the publisher's span, the request's for
+            // events published while handling one.
+            let span = Span::current();
+            for handler in handlers.iter() {
+                tokio::spawn(handler(&event).instrument(span.clone()));
+            }
+        }
         if let Some(sender) = self.channels.get(&type_id) {
-            let _ = sender.send(Arc::new(event));
+            let _ = sender.send(event);
+        }
+        drop(retained);
+    }
+
+    /// Run `handler` on each event `E` published from now on, on a task of
+    /// its own, in the span the event was published in
+    ///
+    /// When `E` is configured to [`replay_last`](EventChannelConfig::replay_last),
+    /// `handler` runs on the last event published right away, so this must
+    /// then be called within a Tokio runtime.
+    ///
+    /// ```rust,ignore
+    /// events.on(move |created: UserCreatedEvent| {
+    ///     let mailer = mailer.clone();
+    ///     async move { mailer.send_welcome(&created.email).await }
+    /// });
+    /// ```
+    pub fn on<E, F, Fut>(&self, handler: F)
+    where
+        E: Clone + Send + Sync + 'static,
+        F: Fn(E) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let handler: Handler = Arc::new(move |event| match event.downcast_ref::<E>() {
+            Some(event) => handler(event.clone()).boxed(),
+            None => async {}.boxed(),
+        });
+        let type_id = TypeId::of::<E>();
+        let retained = self.retained.get_mut(&type_id);
+        if let Some(event) = retained.as_deref().and_then(Option::as_ref) {
+            tokio::spawn(handler(event).in_current_span());
         }
+        self.handlers.entry(type_id).or_default().push(handler);
     }
 
     /// Subscribe to an event
@@ -37,9 +196,114 @@ impl EventBus {
     ) -> broadcast::Receiver<Arc<dyn Any + Send + Sync>> {
         let type_id = TypeId::of::<E>();
         let sender = self.channels.entry(type_id).or_insert_with(|| {
-            let (tx, _) = broadcast::channel(100);
+            let (tx, _) = broadcast::channel(self.capacity);
             tx
         });
         sender.subscribe()
     }
+
+    /// How many receivers are subscribed to the event `E`
+    pub fn subscriber_count<E: 'static>(&self) -> usize {
+        self.channels
+            .get(&TypeId::of::<E>())
+            .map_or(0, |sender| sender.receiver_count())
+    }
+
+    /// Read the current counters
+    pub fn stats(&self) -> EventBusStats {
+        let mut stats = EventBusStats {
+            handlers: self.handlers.iter().map(|handlers| handlers.len()).sum(),
+            lagged_receivers: self.lagged.load(Ordering::Relaxed),
+            ..EventBusStats::default()
+        };
+        for sender in self.channels.iter() {
+            stats.channels += 1;
+            stats.subscribers += sender.receiver_count();
+            if sender.len() >= self.capacity {
+                stats.full_channels += 1;
+            }
+        }
+        stats
+    }
+
+    /// Stream the events `E` published from now on to an HTTP client, each
+    /// a JSON `data` event
+    ///
+    /// The subscription ends when the client goes away and the response is
+    /// dropped.
+    ///
+    /// ```rust,ignore
+    /// #[get("/events")]
+    /// async fn events(&self) -> Sse<KeepAliveStream<EventStream>> {
+    ///     self.events.sse_stream::<UserCreatedEvent>()
+    /// }
+    /// ```
+    pub fn sse_stream<E>(&self) -> Sse<KeepAliveStream<EventStream>>
+    where
+        E: Serialize + Clone + Send + Sync + 'static,
+    {
+        self.sse_stream_with::<E>(&SseOptions::default())
+    }
+
+    /// [`sse_stream`](Self::sse_stream) with another keep-alive interval or
+    /// lag policy
+    pub fn sse_stream_with<E>(&self, options: &SseOptions) -> Sse<KeepAliveStream<EventStream>>
+    where
+        E: Serialize + Clone + Send + Sync + 'static,
+    {
+        let lag = options.lag;
+        let retained = self.retained.get_mut(&TypeId::of::<E>());
+        let receiver = self.subscribe::<E>();
+        let replayed = retained.as_deref().cloned().flatten();
+        drop(retained);
+        let state = (receiver, self.lagged.clone());
+        let events = stream::unfold(state, move |(mut receiver, lagged)| async move {
+            loop {
+                let event = match receiver.recv().await {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(missed)) => {
+                        lagged.fetch_add(1, Ordering::Relaxed);
+                        match lag {
+                            LagPolicy::Skip => continue,
+                            LagPolicy::Notify => {
+                                let event =
+                                    Event::default().event("lagged").data(missed.to_string());
+                                return Some((Ok(event), (receiver, lagged)));
+                            }
+                            LagPolicy::Close => return None,
+                        }
+                    }
+                    Err(RecvError::Closed) => return None,
+                };
+                let Some(event) = event.downcast_ref::<E>() else {
+                    continue;
+                };
+                match Event::default().json_data(event) {
+                    Ok(event) => return Some((Ok(event), (receiver, lagged))),
+                    Err(e) => tracing::warn!("Skipping an event that can't be serialized: {}", e),
+                }
+            }
+        });
+        let replayed = replayed
+            .as_ref()
+            .and_then(|event| event.downcast_ref::<E>())
+            .and_then(|event| Event::default().json_data(event).ok())
+            .map(Ok);
+        options.sse(EventStream {
+            inner: stream::iter(replayed).chain(events).boxed(),
+        })
+    }
+}
+
+/// The events of [`EventBus::sse_stream`]
+pub struct EventStream {
+    inner: BoxStream<'static, Result<Event, Infallible>>,
+}
+
+impl Stream for EventStream {
+    type Item = Result<Event, Infallible>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
 }
@@ -0,0 +1,380 @@
+//! Prometheus metrics: HTTP request counts, latencies and sizes, and a
+//! registry services add their own metrics to
+//!
+//! Importing [`MetricsModule`] registers a [`MetricsRegistry`] and a
+//! [`MetricsInterceptor`] in the container and serves `GET /metrics` in the
+//! Prometheus text format. Apply the interceptor like any other global one:
+//!
+//! ```rust,ignore
+//! #[module(imports = [MetricsModule], controllers = [UserController])]
+//! pub struct AppModule;
+//!
+//! let app = Application::builder()
+//!     .container(AppModule::create_container()?)
+//!     .module::<AppModule>()
+//!     .global_interceptor::<MetricsInterceptor>()
+//!     .build()
+//!     .await?;
+//! ```
+//!
+//! Requests are labelled with the route template, `/users/{id}`, never the
+//! path, so there is one series per route whatever the ids; requests no
+//! route handled are labelled `unmatched`.
+//!
+//! Services resolve the registry to register their own metrics:
+//!
+//! ```rust,ignore
+//! #[derive(Injectable)]
+//! pub struct OrderService {
+//!     metrics: Arc<MetricsRegistry>,
+//! }
+//!
+//! let placed = self.metrics.counter("orders_placed_total", "Orders placed")?;
+//! placed.inc();
+//! ```
+
+mod interceptor;
+
+pub use interceptor::MetricsInterceptor;
+pub use prometheus;
+
+use crate::controller::routing::{MatchedRoute, RouteDescriptor};
+use crate::controller::versioning::VersionedRouters;
+use crate::di::{Container, HasContainer, Injectable};
+use crate::module::{Module, MountState, export};
+use axum::Router;
+use axum::body::Body;
+use axum::http::header::CONTENT_TYPE;
+use axum::http::{Request, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use prometheus::core::Collector;
+use prometheus::{
+    Encoder, Gauge, GaugeVec, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec,
+    Opts, Registry, TextEncoder,
+};
+use std::any::TypeId;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The labels of the HTTP metrics
+const HTTP_LABELS: [&str; 3] = ["method", "route", "status"];
+
+/// The Prometheus registry of the application, with the HTTP metrics
+/// [`MetricsInterceptor`] records
+///
+/// - `http_requests_total`: requests handled
+/// - `http_request_duration_seconds`: a histogram of their latencies
+/// - `http_response_size_bytes`: a histogram of the response body sizes,
+///   for bodies whose size is known
+///
+/// Clones share the registry.
+#[derive(Clone)]
+pub struct MetricsRegistry {
+    registry: Registry,
+    requests: IntCounterVec,
+    duration: HistogramVec,
+    response_size: HistogramVec,
+}
+
+impl Default for MetricsRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MetricsRegistry {
+    pub fn new() -> Self {
+        let registry = Registry::new();
+        let requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests handled"),
+            &HTTP_LABELS,
+        )
+        .expect("the HTTP request counter is valid");
+        let duration = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "HTTP request latencies in seconds",
+            ),
+            &HTTP_LABELS,
+        )
+        .expect("the HTTP latency histogram is valid");
+        let response_size = HistogramVec::new(
+            HistogramOpts::new("http_response_size_bytes", "HTTP response body sizes")
+                .buckets(prometheus::exponential_buckets(64.0, 4.0, 8).unwrap()),
+            &HTTP_LABELS,
+        )
+        .expect("the HTTP response size histogram is valid");
+        for collector in [
+            Box::new(requests.clone()) as Box<dyn Collector>,
+            Box::new(duration.clone()),
+            Box::new(response_size.clone()),
+        ] {
+            registry
+                .register(collector)
+                .expect("the HTTP metrics are registered once");
+        }
+        Self {
+            registry,
+            requests,
+            duration,
+            response_size,
+        }
+    }
+
+    /// The underlying registry, for collectors the helpers don't cover
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    /// Register `collector`, failing if a metric of the same name is
+    /// registered already
+    pub fn register(&self, collector: impl Collector + 'static) -> prometheus::Result<()> {
+        self.registry.register(Box::new(collector))
+    }
+
+    /// Register a new counter
+    pub fn counter(&self, name: &str, help: &str) -> prometheus::Result<IntCounter> {
+        let counter = IntCounter::new(name, help)?;
+        self.register(counter.clone())?;
+        Ok(counter)
+    }
+
+    /// Register a new counter with `labels`
+    pub fn counter_vec(
+        &self,
+        name: &str,
+        help: &str,
+        labels: &[&str],
+    ) -> prometheus::Result<IntCounterVec> {
+        let counter = IntCounterVec::new(Opts::new(name, help), labels)?;
+        self.register(counter.clone())?;
+        Ok(counter)
+    }
+
+    /// Register a new gauge
+    pub fn gauge(&self, name: &str, help: &str) -> prometheus::Result<Gauge> {
+        let gauge = Gauge::new(name, help)?;
+        self.register(gauge.clone())?;
+        Ok(gauge)
+    }
+
+    /// Register a new gauge with `labels`
+    pub fn gauge_vec(
+        &self,
+        name: &str,
+        help: &str,
+        labels: &[&str],
+    ) -> prometheus::Result<GaugeVec> {
+        let gauge = GaugeVec::new(Opts::new(name, help), labels)?;
+        self.register(gauge.clone())?;
+        Ok(gauge)
+    }
+
+    /// Register a new histogram with the default buckets, from 5ms to 10s
+    pub fn histogram(&self, name: &str, help: &str) -> prometheus::Result<Histogram> {
+        let histogram = Histogram::with_opts(HistogramOpts::new(name, help))?;
+        self.register(histogram.clone())?;
+        Ok(histogram)
+    }
+
+    /// Every registered metric, in the Prometheus text format
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buffer) {
+            tracing::error!(error = %e, "Failed to encode the metrics");
+        }
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+
+    /// Count a request to `route`, answered with `status` after `elapsed`
+    pub(crate) fn observe_request(
+        &self,
+        method: &str,
+        route: &str,
+        status: u16,
+        elapsed: Duration,
+        size: Option<u64>,
+    ) {
+        let status = status.to_string();
+        let labels = [method, route, status.as_str()];
+        self.requests.with_label_values(&labels).inc();
+        self.duration
+            .with_label_values(&labels)
+            .observe(elapsed.as_secs_f64());
+        if let Some(size) = size {
+            self.response_size
+                .with_label_values(&labels)
+                .observe(size as f64);
+        }
+    }
+}
+
+impl Injectable for MetricsRegistry {
+    fn inject(_container: &Container) -> crate::Result<Self> {
+        Ok(Self::new())
+    }
+}
+
+/// Serves the [`MetricsRegistry`] at `GET /metrics`, for Prometheus to
+/// scrape; mounted by [`MetricsModule`]
+pub struct MetricsController {
+    registry: MetricsRegistry,
+}
+
+impl MetricsController {
+    /// Where the metrics are served
+    pub const PATH: &'static str = "/metrics";
+
+    pub fn new(registry: MetricsRegistry) -> Self {
+        Self { registry }
+    }
+
+    pub fn route_table() -> Vec<RouteDescriptor> {
+        vec![RouteDescriptor {
+            method: "GET",
+            path: Self::PATH.to_string(),
+            handler: "MetricsController::metrics",
+            guards: Vec::new(),
+            aspects: Vec::new(),
+            version: None,
+            cors: None,
+            csrf_exempt: false,
+            api: None,
+        }]
+    }
+
+    pub fn router<S>(controller: Arc<Self>) -> Router<S>
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        Router::new().route(
+            Self::PATH,
+            get(move |request: Request<Body>| async move {
+                MatchedRoute::record(&request, "MetricsController", "metrics");
+                controller.metrics()
+            }),
+        )
+    }
+
+    fn metrics(&self) -> Response {
+        let content_type = TextEncoder::new().format_type().to_string();
+        (
+            StatusCode::OK,
+            [(CONTENT_TYPE, content_type)],
+            self.registry.render(),
+        )
+            .into_response()
+    }
+}
+
+impl Injectable for MetricsController {
+    fn inject(container: &Container) -> crate::Result<Self> {
+        Ok(Self::new(
+            container.resolve::<MetricsRegistry>()?.as_ref().clone(),
+        ))
+    }
+}
+
+/// Registers a [`MetricsRegistry`], unless the container has one, and a
+/// [`MetricsInterceptor`] recording into it, and serves it at `/metrics`
+///
+/// Import it into the application module: `#[module(imports =
+/// [MetricsModule])]`.
+pub struct MetricsModule;
+
+impl MetricsModule {
+    /// Registers the registry, the interceptor and the controller
+    pub fn register(container: &mut Container) -> crate::Result<()> {
+        let parent = container.enter_module("MetricsModule");
+        let result = Self::register_providers(container);
+        container.exit_module(parent);
+        result
+    }
+
+    fn register_providers(container: &mut Container) -> crate::Result<()> {
+        if !container.contains::<MetricsRegistry>() {
+            container.register(MetricsRegistry::new());
+        }
+        let interceptor = MetricsInterceptor::inject(container)?;
+        container.register(interceptor);
+        let controller = MetricsController::inject(container)?;
+        container.register(controller);
+        Ok(())
+    }
+}
+
+impl Module for MetricsModule {
+    fn register(container: &mut Container) -> crate::Result<()> {
+        MetricsModule::register(container)
+    }
+
+    fn mount_router<S>(
+        container: &Container,
+        state: &mut MountState,
+        routers: &mut VersionedRouters<S>,
+    ) -> crate::Result<()>
+    where
+        S: Clone + Send + Sync + HasContainer + 'static,
+    {
+        if !state.enter::<Self>() {
+            return Ok(());
+        }
+        state.add_framework_routes(MetricsController::route_table())?;
+        let controller = container.resolve::<MetricsController>()?;
+        let path = state.framework_path("/");
+        routers.mount(&path, vec![(None, MetricsController::router(controller))]);
+        Ok(())
+    }
+
+    fn collect_routes(state: &mut MountState) -> crate::Result<()> {
+        if !state.enter::<Self>() {
+            return Ok(());
+        }
+        state.add_framework_routes(MetricsController::route_table())
+    }
+
+    fn exported_types() -> Vec<(TypeId, &'static str)> {
+        vec![export::<MetricsRegistry>(), export::<MetricsInterceptor>()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_are_counted_by_route_template() {
+        let metrics = MetricsRegistry::new();
+        metrics.observe_request(
+            "GET",
+            "/users/{id}",
+            200,
+            Duration::from_millis(5),
+            Some(10),
+        );
+        metrics.observe_request("GET", "/users/{id}", 200, Duration::from_millis(7), None);
+
+        let text = metrics.render();
+        assert!(
+            text.contains(
+                r#"http_requests_total{method="GET",route="/users/{id}",status="200"} 2"#
+            )
+        );
+        assert!(text.contains(
+            r#"http_response_size_bytes_count{method="GET",route="/users/{id}",status="200"} 1"#
+        ));
+    }
+
+    #[test]
+    fn services_register_their_own_metrics() {
+        let metrics = MetricsRegistry::new();
+        let placed = metrics
+            .counter("orders_placed_total", "Orders placed")
+            .unwrap();
+        placed.inc();
+
+        assert!(metrics.render().contains("orders_placed_total 1"));
+        assert!(metrics.counter("orders_placed_total", "Again").is_err());
+    }
+}
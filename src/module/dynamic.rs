@@ -0,0 +1,391 @@
+//! Modules configured at runtime, imported by `#[module]` as expressions
+
+use super::{Export, Module, MountState, export};
+use crate::controller::routing::{Controller, RouteDescriptor};
+use crate::controller::versioning::VersionedRouters;
+use crate::di::{Container, HasContainer, Injectable};
+use crate::error::{MeshestraError, Result};
+use axum::Router;
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Method, Request};
+use axum::response::IntoResponse;
+use axum::routing::{MethodFilter, on};
+use std::any::TypeId;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use tower::ServiceExt;
+
+/// One step of a [`DynamicModule`]'s registration
+type Registration = Box<dyn FnOnce(&mut Container) -> Result<()>>;
+
+/// A module whose providers are chosen at runtime, from configuration say
+///
+/// A function building one is listed among the `imports` of `#[module]`,
+/// next to module types; it's called when the importing module is
+/// registered, with `container` in scope:
+///
+/// ```rust,ignore
+/// impl PersistenceModule {
+///     pub fn with_adapter(config: DatabaseConfig) -> DynamicModule {
+///         let builder = DynamicModule::builder().provider(config.clone());
+///         let builder = if config.url.starts_with("sqlite:") {
+///             builder
+///                 .provider_factory(SqliteTransactionManager::inject)
+///                 .bind_trait::<dyn TransactionManager, SqliteTransactionManager>(|m| m)
+///         } else {
+///             builder
+///                 .provider_factory(PostgresTransactionManager::inject)
+///                 .bind_trait::<dyn TransactionManager, PostgresTransactionManager>(|m| m)
+///         };
+///         builder.export::<dyn TransactionManager>().build()
+///     }
+/// }
+///
+/// #[module(imports = [PersistenceModule::with_adapter(DatabaseConfig::from_env()), UserModule])]
+/// pub struct AppModule;
+/// ```
+///
+/// A module declaring `#[module(config = DatabaseConfig, ...)]` gets two
+/// such functions: `for_root(config)`, registering `config` and then the
+/// module, and `for_feature(items)`, registering [`DynamicProvider`]s that
+/// need the root's, for the feature modules:
+///
+/// ```rust,ignore
+/// #[module(imports = [DatabaseModule::for_root(DatabaseConfig::from_env()), OrdersModule])]
+/// pub struct AppModule;
+///
+/// #[module(imports = [DatabaseModule::for_feature([
+///     DynamicProvider::factory(|c| Repository::<Order>::new(c.resolve::<Database>()?)),
+/// ])])]
+/// pub struct OrdersModule;
+/// ```
+///
+/// Its controllers are mounted with the importing module's, but being known
+/// only once registered, they're left out of [`Module::routes`](super::Module::routes).
+pub struct DynamicModule {
+    registrations: Vec<Registration>,
+    exports: Vec<Export>,
+}
+
+impl DynamicModule {
+    pub fn builder() -> DynamicModuleBuilder {
+        DynamicModuleBuilder {
+            module: DynamicModule {
+                registrations: Vec::new(),
+                exports: Vec::new(),
+            },
+        }
+    }
+
+    /// The names of the types and traits the module exports
+    pub fn exports(&self) -> Vec<&'static str> {
+        self.exports.iter().map(|(_, name)| *name).collect()
+    }
+
+    /// [`exports`](Self::exports), with the ids of the types
+    #[doc(hidden)]
+    pub fn exported_types(&self) -> &[Export] {
+        &self.exports
+    }
+
+    /// Run the registrations, in the order they were added
+    pub fn register_into(self, container: &mut Container) -> Result<()> {
+        for registration in self.registrations {
+            registration(container)?;
+        }
+        Ok(())
+    }
+}
+
+/// Builds a [`DynamicModule`]
+pub struct DynamicModuleBuilder {
+    module: DynamicModule,
+}
+
+impl DynamicModuleBuilder {
+    /// Register `instance`
+    pub fn provider<T: Send + Sync + 'static>(self, instance: T) -> Self {
+        self.provide(DynamicProvider::instance(instance))
+    }
+
+    /// Register what `factory` builds from the container, when the module
+    /// is registered
+    pub fn provider_factory<T, F>(self, factory: F) -> Self
+    where
+        T: Send + Sync + 'static,
+        F: FnOnce(&Container) -> Result<T> + 'static,
+    {
+        self.provide(DynamicProvider::factory(factory))
+    }
+
+    /// Resolve `Trait` to the registered `Impl`, like
+    /// `Provider::new(Impl).for_trait::<dyn Trait>()`
+    pub fn bind_trait<Trait, Impl>(
+        self,
+        caster: impl Fn(Arc<Impl>) -> Arc<Trait> + Send + Sync + 'static,
+    ) -> Self
+    where
+        Trait: ?Sized + Send + Sync + 'static,
+        Impl: Send + Sync + 'static,
+    {
+        self.provide(DynamicProvider::bind(caster))
+    }
+
+    /// Register the controller `C` and serve its routes
+    pub fn controller<C: Controller + Injectable>(self) -> Self {
+        self.provide(DynamicProvider::controller::<C>())
+    }
+
+    /// Register the module `M`, serving its controllers and exporting what
+    /// it exports
+    pub fn module<M: Module + 'static>(mut self) -> Self {
+        self.module.exports.extend(M::exported_types());
+        self.registration(|container| {
+            M::register(container)?;
+            DynamicMounts::add(
+                container,
+                TypeId::of::<M>(),
+                M::mount_router::<DynamicState>,
+            )
+        })
+    }
+
+    /// Export `T`, a type or `dyn Trait`, to the importing module
+    pub fn export<T: ?Sized + 'static>(mut self) -> Self {
+        self.module.exports.push(export::<T>());
+        self
+    }
+
+    /// Add a step of its own to the registration
+    pub fn registration(
+        self,
+        registration: impl FnOnce(&mut Container) -> Result<()> + 'static,
+    ) -> Self {
+        self.provide(DynamicProvider::new(registration))
+    }
+
+    pub fn provide(mut self, provider: DynamicProvider) -> Self {
+        self.module.registrations.push(provider.0);
+        self
+    }
+
+    pub fn providers(self, providers: impl IntoIterator<Item = DynamicProvider>) -> Self {
+        providers.into_iter().fold(self, Self::provide)
+    }
+
+    pub fn build(self) -> DynamicModule {
+        self.module
+    }
+}
+
+/// One registration of a [`DynamicModule`], such as the items given to the
+/// `for_feature` of a `#[module(config = ...)]`
+pub struct DynamicProvider(Registration);
+
+impl DynamicProvider {
+    /// A registration of its own
+    pub fn new(registration: impl FnOnce(&mut Container) -> Result<()> + 'static) -> Self {
+        Self(Box::new(registration))
+    }
+
+    /// Register `instance`
+    pub fn instance<T: Send + Sync + 'static>(instance: T) -> Self {
+        Self::new(move |container| {
+            container.try_register(instance)?;
+            Ok(())
+        })
+    }
+
+    /// Register what `factory` builds from the container
+    pub fn factory<T, F>(factory: F) -> Self
+    where
+        T: Send + Sync + 'static,
+        F: FnOnce(&Container) -> Result<T> + 'static,
+    {
+        Self::new(move |container| {
+            let instance = factory(container)?;
+            container.try_register(instance)?;
+            Ok(())
+        })
+    }
+
+    /// Resolve `Trait` to the registered `Impl`
+    pub fn bind<Trait, Impl>(
+        caster: impl Fn(Arc<Impl>) -> Arc<Trait> + Send + Sync + 'static,
+    ) -> Self
+    where
+        Trait: ?Sized + Send + Sync + 'static,
+        Impl: Send + Sync + 'static,
+    {
+        Self::new(move |container| {
+            if !container.is_overridden::<Trait>() {
+                container.register_trait::<Trait, Impl, _>(caster);
+            }
+            Ok(())
+        })
+    }
+
+    /// Register the controller `C` and serve its routes
+    pub fn controller<C: Controller + Injectable>() -> Self {
+        Self::new(|container| {
+            let controller = C::inject(container)?;
+            container.try_register(controller)?;
+            container.declare::<C>();
+            DynamicMounts::add(container, TypeId::of::<C>(), mount_controller::<C>)
+        })
+    }
+}
+
+/// Fail unless `C`, the configuration `module::for_root` registers, is
+/// registered; called by the `for_feature` of `#[module(config = ...)]`
+#[doc(hidden)]
+pub fn require_root<C: 'static>(container: &Container, module: &str) -> Result<()> {
+    if container.contains::<C>() {
+        return Ok(());
+    }
+    Err(MeshestraError::ModuleRegistrationFailed {
+        message: format!(
+            "{}::for_feature is registered before {}::for_root",
+            module, module
+        ),
+    })
+}
+
+/// The application state, erased for the routers of dynamic modules
+///
+/// It's set by the first request: the routers are built before the state's
+/// type is known.
+#[derive(Clone, Default)]
+struct DynamicState(Arc<OnceLock<Arc<dyn HasContainer + Send + Sync>>>);
+
+impl HasContainer for DynamicState {
+    fn get_container(&self) -> &Container {
+        self.0
+            .get()
+            .expect("dynamic controllers are routed before their state is set")
+            .get_container()
+    }
+}
+
+/// Adds a dynamic module's controllers, or a module's, to the routers
+type Mount = fn(&Container, &mut MountState, &mut VersionedRouters<DynamicState>) -> Result<()>;
+
+fn mount_controller<C: Controller + Injectable>(
+    container: &Container,
+    state: &mut MountState,
+    routers: &mut VersionedRouters<DynamicState>,
+) -> Result<()> {
+    state.add_routes(C::route_table())?;
+    let controller = container.resolve::<C>()?;
+    let path = state.path(C::base_path());
+    routers.mount(&path, C::versioned_routers(controller));
+    Ok(())
+}
+
+/// What the dynamic modules registered in a container mount, by the id of
+/// the controller or module
+#[derive(Default)]
+struct DynamicMounts {
+    state: DynamicState,
+    mounts: Mutex<Vec<(TypeId, Mount)>>,
+}
+
+impl DynamicMounts {
+    fn add(container: &mut Container, id: TypeId, mount: Mount) -> Result<()> {
+        if !container.contains::<DynamicMounts>() {
+            container.register(DynamicMounts::default());
+        }
+        let registry = container.resolve::<DynamicMounts>()?;
+        let mut mounts = registry.mounts.lock().unwrap();
+        if !mounts.iter().any(|(mounted, _)| *mounted == id) {
+            mounts.push((id, mount));
+        }
+        Ok(())
+    }
+}
+
+/// Add the controllers of the dynamic modules registered in `container` to
+/// `routers`; called by `#[module]` for modules importing one
+#[doc(hidden)]
+pub fn mount_dynamic<S>(
+    container: &Container,
+    state: &mut MountState,
+    routers: &mut VersionedRouters<S>,
+) -> Result<()>
+where
+    S: Clone + Send + Sync + HasContainer + 'static,
+{
+    if !state.enter::<DynamicMounts>() {
+        return Ok(());
+    }
+    let Ok(registry) = container.resolve::<DynamicMounts>() else {
+        return Ok(());
+    };
+    let mounts: Vec<Mount> = registry
+        .mounts
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(_, mount)| *mount)
+        .collect();
+
+    let first = state.routes.len();
+    let mut erased = VersionedRouters::default();
+    for mount in mounts {
+        mount(container, state, &mut erased)?;
+    }
+    let routes = &state.routes[first..];
+    for (version, router) in erased.into_routers() {
+        let router = router.with_state(registry.state.clone());
+        let routes = routes.iter().filter(|route| route.version == version);
+        routers.mount(
+            "/",
+            vec![(version, forward(&registry.state, routes, &router))],
+        );
+    }
+    Ok(())
+}
+
+/// A router taking `routes` from the application's and passing them on to
+/// `router`, setting `dynamic` to the application's state
+fn forward<'a, S>(
+    dynamic: &DynamicState,
+    routes: impl Iterator<Item = &'a RouteDescriptor>,
+    router: &Router,
+) -> Router<S>
+where
+    S: Clone + Send + Sync + HasContainer + 'static,
+{
+    let mut methods: BTreeMap<&str, MethodFilter> = BTreeMap::new();
+    for route in routes {
+        let Some(filter) = Method::from_bytes(route.method.as_bytes())
+            .ok()
+            .and_then(|method| MethodFilter::try_from(method).ok())
+        else {
+            continue;
+        };
+        methods
+            .entry(route.path.as_str())
+            .and_modify(|methods| *methods = methods.or(filter))
+            .or_insert(filter);
+    }
+
+    methods
+        .into_iter()
+        .fold(Router::new(), |forwarded, (path, filter)| {
+            let dynamic = dynamic.clone();
+            let router = router.clone();
+            forwarded.route(
+                path,
+                on(
+                    filter,
+                    move |State(state): State<S>, request: Request<Body>| {
+                        dynamic.0.get_or_init(|| Arc::new(state));
+                        let router = router.clone();
+                        async move { router.oneshot(request).await.into_response() }
+                    },
+                ),
+            )
+        })
+}
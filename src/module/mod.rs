@@ -1,7 +1,18 @@
-use crate::di::Container;
-use crate::error::Result;
+use crate::controller::routing::{RouteDescriptor, join_path};
+use crate::controller::versioning::{VersionedRouters, VersioningConfig};
+use crate::di::{Container, HasContainer};
+use crate::error::{MeshestraError, Result};
+use axum::Router;
+use std::any::TypeId;
+use std::collections::HashSet;
 use std::marker::PhantomData;
 
+mod dynamic;
+
+pub use dynamic::{DynamicModule, DynamicModuleBuilder, DynamicProvider};
+#[doc(hidden)]
+pub use dynamic::{mount_dynamic, require_root};
+
 /// A marker struct used in the `#[module]` macro to configure providers.
 ///
 /// This struct and its methods are placeholders for the macro parser and have
@@ -14,7 +25,8 @@ use std::marker::PhantomData;
 ///     providers = [
 ///         UserService, // Standard provider
 ///         Provider::new(UserRepositoryImpl).for_trait::<dyn UserRepository>(), // Trait provider
-///     ]
+///     ],
+///     exports = [UserService, dyn UserRepository],
 /// )]
 /// pub struct UserModule;
 /// ```
@@ -39,6 +51,402 @@ impl<T: ?Sized> Provider<T> {
 /// Modules are typically defined using the `#[module]` macro, which automatically
 /// implements this trait and generates the registration logic.
 pub trait Module {
+    /// Whether the module is `#[module(global = true)]`: registered once,
+    /// before the modules importing it or their siblings, so its providers
+    /// can be injected anywhere
+    const GLOBAL: bool = false;
+
     /// Register all providers and controllers in this module
     fn register(container: &mut Container) -> Result<()>;
+
+    /// Register the global modules among this module's imports, theirs
+    /// included, before anything else is; called by `#[module]`
+    #[doc(hidden)]
+    fn register_globals(_container: &mut Container) -> Result<()> {
+        Ok(())
+    }
+
+    /// Build a router serving this module's controllers, each nested at its
+    /// `base_path()`, merged with the routers of its imports
+    ///
+    /// Controllers are injected from `container`, so register the module
+    /// first. A module imported along several paths is mounted once; two
+    /// controllers routing the same method and path are an error.
+    fn build_router<S>(container: &Container) -> Result<Router<S>>
+    where
+        S: Clone + Send + Sync + HasContainer + 'static,
+    {
+        Self::build_versioned_router(container, &VersioningConfig::default())
+    }
+
+    /// [`build_router`](Self::build_router), serving the API versions of
+    /// `#[controller(version = "...")]` and `#[version(...)]` the way
+    /// `config` says
+    fn build_versioned_router<S>(
+        container: &Container,
+        config: &VersioningConfig,
+    ) -> Result<Router<S>>
+    where
+        S: Clone + Send + Sync + HasContainer + 'static,
+    {
+        Self::build_prefixed_router(container, config, &GlobalPrefix::default())
+    }
+
+    /// [`build_versioned_router`](Self::build_versioned_router), with every
+    /// route under `prefix`
+    fn build_prefixed_router<S>(
+        container: &Container,
+        config: &VersioningConfig,
+        prefix: &GlobalPrefix,
+    ) -> Result<Router<S>>
+    where
+        S: Clone + Send + Sync + HasContainer + 'static,
+    {
+        let mut routers = VersionedRouters::default();
+        Self::mount_router(container, &mut MountState::new(prefix), &mut routers)?;
+        Ok(routers.finish(config))
+    }
+
+    /// The routes [`build_router`](Self::build_router) serves, checked the
+    /// same way
+    fn routes() -> Result<Vec<RouteDescriptor>> {
+        Self::prefixed_routes(&GlobalPrefix::default())
+    }
+
+    /// The routes [`build_prefixed_router`](Self::build_prefixed_router)
+    /// serves
+    fn prefixed_routes(prefix: &GlobalPrefix) -> Result<Vec<RouteDescriptor>> {
+        Self::walk(prefix).map(MountState::into_routes)
+    }
+
+    /// Walk this module's tree the way
+    /// [`prefixed_routes`](Self::prefixed_routes) does, keeping what was seen
+    #[doc(hidden)]
+    fn walk(prefix: &GlobalPrefix) -> Result<MountState> {
+        let mut state = MountState::new(prefix);
+        Self::collect_routes(&mut state)?;
+        Ok(state)
+    }
+
+    /// Add this module's controllers to `routers`, skipping the modules
+    /// already in `state`
+    #[doc(hidden)]
+    fn mount_router<S>(
+        container: &Container,
+        state: &mut MountState,
+        routers: &mut VersionedRouters<S>,
+    ) -> Result<()>
+    where
+        S: Clone + Send + Sync + HasContainer + 'static;
+
+    /// [`routes`](Self::routes), skipping the modules already in `state`
+    #[doc(hidden)]
+    fn collect_routes(state: &mut MountState) -> Result<()>;
+
+    /// The names of the types and traits this module exports, those of the
+    /// modules it re-exports included
+    ///
+    /// Registration checks each was provided by the module or is exported
+    /// by one of its imports.
+    fn exports() -> Vec<&'static str> {
+        Self::exported_types()
+            .into_iter()
+            .map(|(_, name)| name)
+            .collect()
+    }
+
+    /// [`exports`](Self::exports), with the ids of the types
+    #[doc(hidden)]
+    fn exported_types() -> Vec<(TypeId, &'static str)> {
+        Vec::new()
+    }
+}
+
+/// An exported type and its name
+type Export = (TypeId, &'static str);
+
+/// An imported module and its [`Module::exported_types`]
+type Import = (TypeId, fn() -> Vec<Export>);
+
+/// The [`Module::exported_types`] entry of `T`
+#[doc(hidden)]
+pub fn export<T: ?Sized + 'static>() -> (TypeId, &'static str) {
+    (TypeId::of::<T>(), std::any::type_name::<T>())
+}
+
+/// The exports of `#[module(exports = [...])]`, the `exports` of a module
+/// among `imports` replaced by its own; called by `#[module]`
+#[doc(hidden)]
+pub fn resolve_exports(imports: &[Import], exports: &[Export]) -> Vec<Export> {
+    let mut resolved: Vec<Export> = Vec::new();
+    for &(type_id, name) in exports {
+        let types = match imports.iter().find(|(module, _)| *module == type_id) {
+            Some((_, exported_types)) => exported_types(),
+            None => vec![(type_id, name)],
+        };
+        for export in types {
+            if !resolved.iter().any(|(id, _)| *id == export.0) {
+                resolved.push(export);
+            }
+        }
+    }
+    resolved
+}
+
+/// Fail unless each of the `exports` of `module` is among the types it
+/// `provided` or those its imports export; called by `#[module]`
+#[doc(hidden)]
+pub fn check_exports(
+    module: &str,
+    exports: &[Export],
+    provided: &[TypeId],
+    imported: &[Export],
+) -> Result<()> {
+    for (type_id, name) in exports {
+        let available = provided.contains(type_id) || imported.iter().any(|(id, _)| id == type_id);
+        if !available {
+            return Err(MeshestraError::ModuleRegistrationFailed {
+                message: format!(
+                    "{} exports {}, which it neither provides nor imports",
+                    module, name
+                ),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// One of the `providers` of `#[module]`: the names of the types it
+/// provides, the first its own, and how it's registered
+#[doc(hidden)]
+pub struct ProviderStep {
+    pub provides: Vec<&'static str>,
+    pub register: fn(&mut Container) -> Result<()>,
+}
+
+/// Register the `providers` of `module`, whatever order they're listed in;
+/// called by `#[module]`
+///
+/// Each pass registers the providers whose dependencies are registered and
+/// keeps the others for the next, until a pass makes no progress: what's
+/// left then needs a type nothing provides, or its providers need each other.
+#[doc(hidden)]
+pub fn register_providers(
+    container: &mut Container,
+    module: &str,
+    providers: Vec<ProviderStep>,
+) -> Result<()> {
+    let mut pending = providers;
+    while !pending.is_empty() {
+        let count = pending.len();
+        let mut waiting = Vec::new();
+        for provider in pending {
+            match (provider.register)(container) {
+                Ok(()) => {}
+                Err(MeshestraError::DependencyNotFound { type_name }) => {
+                    waiting.push((provider, type_name))
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        if waiting.len() == count {
+            return Err(unresolved(module, &waiting));
+        }
+        pending = waiting.into_iter().map(|(provider, _)| provider).collect();
+    }
+    Ok(())
+}
+
+/// Why none of the `waiting` providers, each with the type it's missing,
+/// can be registered
+fn unresolved(module: &str, waiting: &[(ProviderStep, String)]) -> MeshestraError {
+    let provider_of = |missing: &str| {
+        waiting
+            .iter()
+            .position(|(provider, _)| provider.provides.contains(&missing))
+    };
+
+    let external = waiting
+        .iter()
+        .find(|(_, missing)| provider_of(missing).is_none());
+    if let Some((provider, missing)) = external {
+        return MeshestraError::ModuleRegistrationFailed {
+            message: format!(
+                "{} needs {}, which isn't provided by {} or its imports",
+                provider.provides[0], missing, module
+            ),
+        };
+    }
+
+    // Each waits on another: follow them from the first until one repeats
+    let mut chain = vec![0];
+    while let Some(next) = provider_of(&waiting[chain[chain.len() - 1]].1) {
+        if let Some(start) = chain.iter().position(|&index| index == next) {
+            chain.drain(..start);
+            chain.push(next);
+            break;
+        }
+        chain.push(next);
+    }
+    let names: Vec<&str> = chain
+        .iter()
+        .map(|&index| waiting[index].0.provides[0])
+        .collect();
+    MeshestraError::CircularDependency {
+        cycle: names.join(" -> "),
+    }
+}
+
+/// The path every route of a module tree is served under, see
+/// [`ApplicationBuilder::global_prefix`](crate::lifecycle::ApplicationBuilder::global_prefix)
+///
+/// The prefixes of `#[module(prefix = "...")]` and the base paths of the
+/// controllers come after it; with URI versioning, the version comes first:
+/// `/v1/api/users`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GlobalPrefix {
+    path: String,
+    framework_routes: bool,
+}
+
+impl GlobalPrefix {
+    /// Serve the routes under `path`, `/api` say; the endpoints of
+    /// [`HealthModule`](crate::health::HealthModule) and `MetricsModule` too
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: join_prefix("", path),
+            framework_routes: true,
+        }
+    }
+
+    /// Leave the endpoints of the framework's modules, `/health/ready` and
+    /// `/metrics`, at their own paths
+    pub fn exclude_framework_routes(mut self) -> Self {
+        self.framework_routes = false;
+        self
+    }
+
+    /// The prefix, `/api`, or `""` for none
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+impl From<&str> for GlobalPrefix {
+    fn from(path: &str) -> Self {
+        Self::new(path)
+    }
+}
+
+/// `prefix` after `outer`, with a leading slash and no trailing one
+fn join_prefix(outer: &str, prefix: &str) -> String {
+    match prefix.trim_matches('/') {
+        "" => outer.to_string(),
+        prefix => format!("{}/{}", outer, prefix),
+    }
+}
+
+/// The modules and routes seen so far while walking a module tree
+#[doc(hidden)]
+#[derive(Default)]
+pub struct MountState {
+    modules: HashSet<TypeId>,
+    routes: Vec<RouteDescriptor>,
+    /// How many controllers added their routes
+    controllers: usize,
+    /// The global prefix and those of the modules being walked
+    prefix: String,
+    /// Where the framework's modules mount their endpoints
+    framework_prefix: String,
+}
+
+impl MountState {
+    /// The state of a walk serving every route under `prefix`
+    pub fn new(prefix: &GlobalPrefix) -> Self {
+        Self {
+            prefix: prefix.path.clone(),
+            framework_prefix: if prefix.framework_routes {
+                prefix.path.clone()
+            } else {
+                String::new()
+            },
+            ..Self::default()
+        }
+    }
+
+    /// Whether `M` is seen for the first time, marking it seen
+    pub fn enter<M: 'static>(&mut self) -> bool {
+        self.modules.insert(TypeId::of::<M>())
+    }
+
+    /// How many modules were seen
+    pub fn module_count(&self) -> usize {
+        self.modules.len()
+    }
+
+    /// How many controllers added their routes
+    pub fn controller_count(&self) -> usize {
+        self.controllers
+    }
+
+    /// The routes added, in order
+    pub fn into_routes(self) -> Vec<RouteDescriptor> {
+        self.routes
+    }
+
+    /// Serve the routes added from now on under `prefix` too, returning the
+    /// prefix to give [`exit_prefix`](Self::exit_prefix); called by
+    /// `#[module(prefix = "...")]`
+    pub fn enter_prefix(&mut self, prefix: &str) -> String {
+        let outer = self.prefix.clone();
+        self.prefix = join_prefix(&outer, prefix);
+        outer
+    }
+
+    /// Serve the routes added from now on under `outer` again
+    pub fn exit_prefix(&mut self, outer: String) {
+        self.prefix = outer;
+    }
+
+    /// Where a controller at `base_path` is mounted, under the prefixes
+    pub fn path(&self, base_path: &str) -> String {
+        join_path(&self.prefix, base_path)
+    }
+
+    /// Add a controller's routes under the prefixes, refusing any that an
+    /// added one serves
+    pub fn add_routes(&mut self, routes: Vec<RouteDescriptor>) -> Result<()> {
+        let prefix = self.prefix.clone();
+        self.push_routes(&prefix, routes)
+    }
+
+    /// Where an endpoint of the framework's modules at `base_path` is
+    /// mounted: under the global prefix, unless excluded
+    pub fn framework_path(&self, base_path: &str) -> String {
+        join_path(&self.framework_prefix, base_path)
+    }
+
+    /// [`add_routes`](Self::add_routes) for the framework's modules, see
+    /// [`framework_path`](Self::framework_path)
+    pub fn add_framework_routes(&mut self, routes: Vec<RouteDescriptor>) -> Result<()> {
+        let prefix = self.framework_prefix.clone();
+        self.push_routes(&prefix, routes)
+    }
+
+    fn push_routes(&mut self, prefix: &str, routes: Vec<RouteDescriptor>) -> Result<()> {
+        self.controllers += 1;
+        for mut route in routes {
+            route.path = join_path(prefix, &route.path);
+            if let Some(taken) = self.routes.iter().find(|r| r.conflicts_with(&route)) {
+                return Err(MeshestraError::ModuleRegistrationFailed {
+                    message: format!(
+                        "{} {} is routed to both {} and {}",
+                        route.method, route.path, taken.handler, route.handler
+                    ),
+                });
+            }
+            self.routes.push(route);
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,481 @@
+//! OpenAPI 3 documents of the `#[routes]` controllers
+//!
+//! `#[routes]` describes every route in its [`RouteDescriptor::api`]: the
+//! handler's doc comment, its path, query, header and cookie parameters,
+//! its body and its response. Types deriving [`ApiSchema`] are described
+//! in full, others as any value. [`Application::openapi`] assembles the
+//! document, and [`ApplicationBuilder::openapi`] serves it with Swagger UI:
+//!
+//! ```rust,ignore
+//! #[derive(Deserialize, ApiSchema)]
+//! pub struct CreateUserRequest {
+//!     pub name: String,
+//!     pub email: String,
+//! }
+//!
+//! #[routes(UserController)]
+//! impl UserController {
+//!     /// Create a user
+//!     ///
+//!     /// The email must not be taken yet.
+//!     #[post("/")]
+//!     #[status(201)]
+//!     async fn create(&self, #[body] req: CreateUserRequest) -> Result<Json<User>> { ... }
+//!
+//!     #[get("/internal/stats")]
+//!     #[api(hidden)]
+//!     async fn stats(&self) -> Json<Stats> { ... }
+//! }
+//!
+//! let app = Application::builder()
+//!     .module::<AppModule>()
+//!     .openapi(OpenApiConfig::new("Users", "1.0.0"))
+//!     .build()
+//!     .await?;
+//! ```
+//!
+//! `#[api(hidden)]` leaves a route out of the document, or a field out of
+//! its type's schema.
+//!
+//! [`RouteDescriptor::api`]: crate::controller::routing::RouteDescriptor::api
+//! [`Application::openapi`]: crate::lifecycle::Application::openapi
+//! [`ApplicationBuilder::openapi`]: crate::lifecycle::ApplicationBuilder::openapi
+
+mod schema;
+
+pub use schema::{ApiSchema, Components};
+#[doc(hidden)]
+pub use schema::{Described, Property, SchemaProbe, Undescribed, enum_schema, object_schema};
+pub use serde_json::Value;
+
+use crate::controller::routing::RouteDescriptor;
+use crate::controller::routing_options::served_path;
+use crate::controller::versioning::VersioningConfig;
+use axum::Router;
+use axum::body::Bytes;
+use axum::http::StatusCode;
+use axum::http::header::CONTENT_TYPE;
+use axum::response::Html;
+use axum::routing::get;
+use serde_json::{Map, json};
+use std::collections::HashSet;
+use std::fmt;
+
+/// What the OpenAPI document says of a route; generated by `#[routes]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiOperation {
+    /// The controller, `User` for `UserController`
+    pub tag: &'static str,
+    /// The first paragraph of the handler's doc comment
+    pub summary: Option<&'static str>,
+    /// The rest of the doc comment
+    pub description: Option<&'static str>,
+    pub params: Vec<ApiParam>,
+    /// The `#[body]`, `#[form]` or `#[multipart]` parameter
+    pub body: Option<ApiBody>,
+    /// `#[status(...)]`, 200 by default
+    pub status: u16,
+    /// What the handler returns
+    pub response: Option<TypeSchema>,
+    /// `#[produces(...)]`, or `text/event-stream` for streams
+    pub produces: Option<&'static str>,
+}
+
+/// Where a parameter is read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamLocation {
+    Path,
+    Query,
+    Header,
+    Cookie,
+}
+
+impl ParamLocation {
+    fn as_str(self) -> &'static str {
+        match self {
+            ParamLocation::Path => "path",
+            ParamLocation::Query => "query",
+            ParamLocation::Header => "header",
+            ParamLocation::Cookie => "cookie",
+        }
+    }
+}
+
+/// A parameter of an [`ApiOperation`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiParam {
+    /// `None` for a struct whose fields are the parameters, as those of
+    /// `#[query]` are
+    pub name: Option<&'static str>,
+    pub location: ParamLocation,
+    pub required: bool,
+    pub schema: TypeSchema,
+}
+
+/// The request body of an [`ApiOperation`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiBody {
+    pub content_type: &'static str,
+    pub schema: TypeSchema,
+}
+
+/// A type of a handler, with its schema if it implements [`ApiSchema`]
+#[derive(Clone, Copy)]
+pub struct TypeSchema {
+    type_name: &'static str,
+    schema: fn(&mut Components) -> Option<Value>,
+}
+
+impl TypeSchema {
+    /// `type_name` described by `schema`; generated by `#[routes]`
+    #[doc(hidden)]
+    pub fn new(type_name: &'static str, schema: fn(&mut Components) -> Option<Value>) -> Self {
+        Self { type_name, schema }
+    }
+
+    /// The type as written in the handler
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// The schema of the type, `None` if it doesn't implement [`ApiSchema`]
+    pub fn schema(&self, components: &mut Components) -> Option<Value> {
+        (self.schema)(components)
+    }
+}
+
+impl PartialEq for TypeSchema {
+    fn eq(&self, other: &Self) -> bool {
+        self.type_name == other.type_name
+    }
+}
+
+impl Eq for TypeSchema {}
+
+impl fmt::Debug for TypeSchema {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("TypeSchema").field(&self.type_name).finish()
+    }
+}
+
+/// The `info` of the document, and where [`ApplicationBuilder::openapi`]
+/// serves it
+///
+/// [`ApplicationBuilder::openapi`]: crate::lifecycle::ApplicationBuilder::openapi
+#[derive(Debug, Clone)]
+pub struct OpenApiConfig {
+    pub title: String,
+    pub version: String,
+    pub description: Option<String>,
+    json_path: String,
+    ui_path: Option<String>,
+}
+
+impl Default for OpenApiConfig {
+    fn default() -> Self {
+        Self::new("API", "1.0.0")
+    }
+}
+
+impl OpenApiConfig {
+    /// The document of the API `title`, at `version`, served at
+    /// `/openapi.json` with Swagger UI at `/docs`
+    pub fn new(title: impl Into<String>, version: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            version: version.into(),
+            description: None,
+            json_path: "/openapi.json".to_string(),
+            ui_path: Some("/docs".to_string()),
+        }
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Serve the document at `path` instead
+    pub fn json_path(mut self, path: impl Into<String>) -> Self {
+        self.json_path = path.into();
+        self
+    }
+
+    /// Serve Swagger UI at `path` instead
+    pub fn swagger_ui(mut self, path: impl Into<String>) -> Self {
+        self.ui_path = Some(path.into());
+        self
+    }
+
+    /// Serve the document alone
+    pub fn without_swagger_ui(mut self) -> Self {
+        self.ui_path = None;
+        self
+    }
+
+    /// The routes [`router`](Self::router) serves
+    pub(crate) fn routes(&self) -> Vec<RouteDescriptor> {
+        let route = |path: &str, handler| RouteDescriptor {
+            method: "GET",
+            path: path.to_string(),
+            handler,
+            guards: Vec::new(),
+            aspects: Vec::new(),
+            version: None,
+            cors: None,
+            csrf_exempt: false,
+            api: None,
+        };
+        let mut routes = vec![route(&self.json_path, "OpenApi::document")];
+        if let Some(ui_path) = &self.ui_path {
+            routes.push(route(ui_path, "OpenApi::swagger_ui"));
+        }
+        routes
+    }
+
+    /// Router serving `document` and Swagger UI
+    pub(crate) fn router<S>(&self, document: &Value) -> Router<S>
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        let json = Bytes::from(document.to_string());
+        let mut router = Router::new().route(
+            &self.json_path,
+            get(move || async move { ([(CONTENT_TYPE, "application/json")], json) }),
+        );
+        if let Some(ui_path) = &self.ui_path {
+            let page = swagger_ui(&self.title, &self.json_path);
+            router = router.route(ui_path, get(move || async move { Html(page) }));
+        }
+        router
+    }
+}
+
+/// The OpenAPI document of `routes`, at the paths `versioning` serves them
+///
+/// Routes without [`RouteDescriptor::api`] are left out. With header
+/// versioning, the versions of a route share its path, and the first one
+/// listed describes it.
+pub fn document(
+    config: &OpenApiConfig,
+    routes: &[RouteDescriptor],
+    versioning: &VersioningConfig,
+) -> Value {
+    let mut components = Components::new();
+    let mut paths = Map::new();
+    let mut operation_ids = HashSet::new();
+    for route in routes {
+        let Some(api) = &route.api else {
+            continue;
+        };
+        let path = template(&served_path(route, versioning));
+        let operation = operation(route, api, &path, &mut components, &mut operation_ids);
+        let item = paths
+            .entry(path)
+            .or_insert_with(|| Value::Object(Map::new()));
+        if let Some(item) = item.as_object_mut() {
+            item.entry(route.method.to_ascii_lowercase())
+                .or_insert(operation);
+        }
+    }
+
+    let mut info = json!({ "title": config.title, "version": config.version });
+    if let Some(description) = &config.description {
+        info["description"] = json!(description);
+    }
+    json!({
+        "openapi": "3.0.3",
+        "info": info,
+        "paths": paths,
+        "components": { "schemas": components.schemas() },
+    })
+}
+
+/// The operation object of `route`, served at `path`
+fn operation(
+    route: &RouteDescriptor,
+    api: &ApiOperation,
+    path: &str,
+    components: &mut Components,
+    operation_ids: &mut HashSet<String>,
+) -> Value {
+    let mut operation = Map::new();
+    operation.insert("tags".to_string(), json!([api.tag]));
+    if let Some(summary) = api.summary {
+        operation.insert("summary".to_string(), json!(summary));
+    }
+    if let Some(description) = api.description {
+        operation.insert("description".to_string(), json!(description));
+    }
+    // Aliases share their handler, whose id must stay unique.
+    let base_id = route.handler.replace("::", "_");
+    let mut operation_id = base_id.clone();
+    let mut n = 1;
+    while !operation_ids.insert(operation_id.clone()) {
+        n += 1;
+        operation_id = format!("{}_{}", base_id, n);
+    }
+    operation.insert("operationId".to_string(), json!(operation_id));
+
+    let parameters = parameters(api, path, components);
+    if !parameters.is_empty() {
+        operation.insert("parameters".to_string(), Value::Array(parameters));
+    }
+    if let Some(body) = &api.body {
+        let schema = body.schema.schema(components).unwrap_or_else(|| json!({}));
+        operation.insert(
+            "requestBody".to_string(),
+            json!({
+                "required": true,
+                "content": { body.content_type: { "schema": schema } },
+            }),
+        );
+    }
+
+    let status = StatusCode::from_u16(api.status).unwrap_or(StatusCode::OK);
+    let mut response = Map::new();
+    response.insert(
+        "description".to_string(),
+        json!(status.canonical_reason().unwrap_or("Response")),
+    );
+    let schema = api
+        .response
+        .and_then(|response| response.schema(components));
+    let content_type = api.produces.or_else(|| {
+        schema.as_ref().map(|schema| {
+            match components
+                .resolve(schema)
+                .get("type")
+                .and_then(Value::as_str)
+            {
+                Some("string") => "text/plain",
+                _ => "application/json",
+            }
+        })
+    });
+    if let Some(content_type) = content_type {
+        let media = match schema {
+            Some(schema) => json!({ "schema": schema }),
+            None => json!({}),
+        };
+        response.insert("content".to_string(), json!({ content_type: media }));
+    }
+    operation.insert(
+        "responses".to_string(),
+        json!({ status.as_u16().to_string(): response }),
+    );
+    Value::Object(operation)
+}
+
+/// The parameter objects of `api`, with one for each placeholder of `path`
+/// the handler doesn't describe
+fn parameters(api: &ApiOperation, path: &str, components: &mut Components) -> Vec<Value> {
+    let mut parameters = Vec::new();
+    let mut add = |name: &str, location: ParamLocation, required: bool, schema: Value| {
+        parameters.push(json!({
+            "name": name,
+            "in": location.as_str(),
+            "required": required || location == ParamLocation::Path,
+            "schema": schema,
+        }));
+    };
+    for param in &api.params {
+        let Some(schema) = param.schema.schema(components) else {
+            continue;
+        };
+        match param.name {
+            Some(name) => add(name, param.location, param.required, schema),
+            None => {
+                let fields = components.resolve(&schema);
+                let required: Vec<&str> = fields["required"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Value::as_str)
+                    .collect();
+                for (name, schema) in fields["properties"].as_object().into_iter().flatten() {
+                    let is_required = required.contains(&name.as_str());
+                    add(name, param.location, is_required, schema.clone());
+                }
+            }
+        }
+    }
+    for placeholder in placeholders(path) {
+        let described = parameters
+            .iter()
+            .any(|param| param["in"] == "path" && param["name"] == placeholder);
+        if !described {
+            parameters.push(json!({
+                "name": placeholder,
+                "in": "path",
+                "required": true,
+                "schema": { "type": "string" },
+            }));
+        }
+    }
+    parameters
+}
+
+/// `path` as an OpenAPI path template: `{*rest}` becomes `{rest}`
+fn template(path: &str) -> String {
+    path.replace("{*", "{")
+}
+
+/// The names of the placeholders of the OpenAPI template `path`
+fn placeholders(path: &str) -> Vec<&str> {
+    path.split('{')
+        .skip(1)
+        .filter_map(|rest| rest.split_once('}'))
+        .map(|(name, _)| name)
+        .collect()
+}
+
+/// A Swagger UI page loading the document at `json_path`
+fn swagger_ui(title: &str, json_path: &str) -> String {
+    let title = title
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    format!(
+        r##"<!doctype html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>{title}</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css">
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js" crossorigin></script>
+  <script>
+    window.onload = () => {{
+      window.ui = SwaggerUIBundle({{ url: {url}, dom_id: "#swagger-ui" }});
+    }};
+  </script>
+</body>
+</html>
+"##,
+        title = title,
+        url = json!(json_path),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn templates_name_catch_alls_like_other_placeholders() {
+        assert_eq!(template("/files/{*path}"), "/files/{path}");
+        assert_eq!(placeholders("/orgs/{org}/repos/{repo}"), ["org", "repo"]);
+        assert!(placeholders("/users").is_empty());
+    }
+
+    #[test]
+    fn the_swagger_ui_page_loads_the_document() {
+        let page = swagger_ui("<Users>", "/openapi.json");
+
+        assert!(page.contains("<title>&lt;Users&gt;</title>"));
+        assert!(page.contains(r#"url: "/openapi.json""#));
+    }
+}
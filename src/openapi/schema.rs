@@ -0,0 +1,385 @@
+use crate::common::{ApiResponse, PageInfo};
+use serde::Serialize;
+use serde_json::{Map, Value, json};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// A type the OpenAPI document can describe, with `#[derive(ApiSchema)]`
+/// for request and response types
+///
+/// ```rust,ignore
+/// /// A registered user
+/// #[derive(Serialize, ApiSchema)]
+/// pub struct User {
+///     pub id: String,
+///     /// Shown to other users
+///     pub name: String,
+///     #[api(hidden)]
+///     pub password_hash: String,
+/// }
+/// ```
+///
+/// Named structs become a schema of their own under
+/// `components/schemas`, fields described by their doc comments and
+/// renamed as `#[serde(rename...)]` says; `Option` fields aren't required.
+/// Enums of unit variants become string enums.
+pub trait ApiSchema {
+    /// The JSON schema of `Self`, or a `$ref` to it once added to
+    /// `components`
+    fn schema(components: &mut Components) -> Value;
+}
+
+/// The named schemas of a document, `#/components/schemas`
+#[derive(Debug, Default)]
+pub struct Components {
+    schemas: BTreeMap<String, Value>,
+}
+
+impl Components {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A `$ref` to the schema `name`, built by `build` the first time
+    ///
+    /// The name is taken before `build` runs, so recursive types end.
+    pub fn define(&mut self, name: &str, build: impl FnOnce(&mut Components) -> Value) -> Value {
+        if !self.schemas.contains_key(name) {
+            self.schemas.insert(name.to_string(), Value::Null);
+            let schema = build(self);
+            self.schemas.insert(name.to_string(), schema);
+        }
+        json!({ "$ref": format!("#/components/schemas/{}", name) })
+    }
+
+    /// `schema`, or the schema it refers to
+    pub fn resolve<'a>(&'a self, schema: &'a Value) -> &'a Value {
+        schema
+            .get("$ref")
+            .and_then(Value::as_str)
+            .and_then(|reference| reference.strip_prefix("#/components/schemas/"))
+            .and_then(|name| self.schemas.get(name))
+            .unwrap_or(schema)
+    }
+
+    pub fn schemas(&self) -> &BTreeMap<String, Value> {
+        &self.schemas
+    }
+}
+
+/// A property of [`object_schema`]; generated by `#[derive(ApiSchema)]`
+#[doc(hidden)]
+pub struct Property {
+    pub name: &'static str,
+    pub schema: Value,
+    pub description: Option<&'static str>,
+    pub required: bool,
+}
+
+/// The schema of an object with `properties`; generated by
+/// `#[derive(ApiSchema)]`
+#[doc(hidden)]
+pub fn object_schema(description: Option<&str>, properties: Vec<Property>) -> Value {
+    let mut schema = Map::new();
+    schema.insert("type".to_string(), json!("object"));
+    if let Some(description) = description {
+        schema.insert("description".to_string(), json!(description));
+    }
+    let required: Vec<&str> = properties
+        .iter()
+        .filter(|property| property.required)
+        .map(|property| property.name)
+        .collect();
+    let properties: Map<String, Value> = properties
+        .into_iter()
+        .map(|property| {
+            let mut schema = property.schema;
+            // Siblings of a `$ref` are ignored, so only inline schemas get one.
+            let inline = schema.get("$ref").is_none();
+            if let (Some(description), Some(object), true) =
+                (property.description, schema.as_object_mut(), inline)
+            {
+                object.insert("description".to_string(), json!(description));
+            }
+            (property.name.to_string(), schema)
+        })
+        .collect();
+    schema.insert("properties".to_string(), Value::Object(properties));
+    if !required.is_empty() {
+        schema.insert("required".to_string(), json!(required));
+    }
+    Value::Object(schema)
+}
+
+/// The schema of a string taking one of `variants`; generated by
+/// `#[derive(ApiSchema)]`
+#[doc(hidden)]
+pub fn enum_schema(description: Option<&str>, variants: &[&str]) -> Value {
+    let mut schema = json!({ "type": "string", "enum": variants });
+    if let Some(description) = description {
+        schema["description"] = json!(description);
+    }
+    schema
+}
+
+/// The schema of `T`, if it implements [`ApiSchema`]; generated by
+/// `#[routes]` for the types of its handlers
+///
+/// `(&&SchemaProbe::<T>::new()).schema(components)` picks [`Described`]
+/// when `T: ApiSchema` and falls back to [`Undescribed`] otherwise, so
+/// handlers taking types without a schema still compile.
+#[doc(hidden)]
+pub struct SchemaProbe<T: ?Sized>(PhantomData<T>);
+
+impl<T: ?Sized> SchemaProbe<T> {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        SchemaProbe(PhantomData)
+    }
+}
+
+#[doc(hidden)]
+pub trait Described {
+    fn schema(&self, components: &mut Components) -> Option<Value>;
+}
+
+impl<T: ApiSchema + ?Sized> Described for &SchemaProbe<T> {
+    fn schema(&self, components: &mut Components) -> Option<Value> {
+        Some(T::schema(components))
+    }
+}
+
+#[doc(hidden)]
+pub trait Undescribed {
+    fn schema(&self, components: &mut Components) -> Option<Value>;
+}
+
+impl<T: ?Sized> Undescribed for SchemaProbe<T> {
+    fn schema(&self, _components: &mut Components) -> Option<Value> {
+        None
+    }
+}
+
+macro_rules! fixed_schema {
+    ($($ty:ty => $schema:tt),* $(,)?) => {
+        $(
+            impl ApiSchema for $ty {
+                fn schema(_components: &mut Components) -> Value {
+                    json!($schema)
+                }
+            }
+        )*
+    };
+}
+
+fixed_schema! {
+    bool => { "type": "boolean" },
+    i8 => { "type": "integer", "format": "int32" },
+    i16 => { "type": "integer", "format": "int32" },
+    i32 => { "type": "integer", "format": "int32" },
+    i64 => { "type": "integer", "format": "int64" },
+    i128 => { "type": "integer" },
+    isize => { "type": "integer", "format": "int64" },
+    u8 => { "type": "integer", "format": "int32", "minimum": 0 },
+    u16 => { "type": "integer", "format": "int32", "minimum": 0 },
+    u32 => { "type": "integer", "format": "int64", "minimum": 0 },
+    u64 => { "type": "integer", "format": "int64", "minimum": 0 },
+    u128 => { "type": "integer", "minimum": 0 },
+    usize => { "type": "integer", "format": "int64", "minimum": 0 },
+    f32 => { "type": "number", "format": "float" },
+    f64 => { "type": "number", "format": "double" },
+    char => { "type": "string", "minLength": 1, "maxLength": 1 },
+    str => { "type": "string" },
+    String => { "type": "string" },
+    Value => {},
+    chrono::NaiveDate => { "type": "string", "format": "date" },
+    chrono::NaiveDateTime => { "type": "string", "format": "date-time" },
+}
+
+impl<Tz: chrono::TimeZone> ApiSchema for chrono::DateTime<Tz> {
+    fn schema(_components: &mut Components) -> Value {
+        json!({ "type": "string", "format": "date-time" })
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl ApiSchema for uuid::Uuid {
+    fn schema(_components: &mut Components) -> Value {
+        json!({ "type": "string", "format": "uuid" })
+    }
+}
+
+/// Types described as the type they wrap
+macro_rules! transparent_schema {
+    ($($ty:ident),*) => {
+        $(
+            impl<T: ApiSchema + ?Sized> ApiSchema for $ty<T> {
+                fn schema(components: &mut Components) -> Value {
+                    T::schema(components)
+                }
+            }
+        )*
+    };
+}
+
+transparent_schema!(Box, Arc);
+
+impl<T: ApiSchema + ?Sized> ApiSchema for &T {
+    fn schema(components: &mut Components) -> Value {
+        T::schema(components)
+    }
+}
+
+/// Optional values are described as the value; what's optional is told
+/// by the `required` lists instead
+impl<T: ApiSchema> ApiSchema for Option<T> {
+    fn schema(components: &mut Components) -> Value {
+        T::schema(components)
+    }
+}
+
+/// A handler's `Result` is described as its success
+impl<T: ApiSchema, E> ApiSchema for Result<T, E> {
+    fn schema(components: &mut Components) -> Value {
+        T::schema(components)
+    }
+}
+
+impl<T: ApiSchema> ApiSchema for axum::Json<T> {
+    fn schema(components: &mut Components) -> Value {
+        T::schema(components)
+    }
+}
+
+impl<T: ApiSchema> ApiSchema for axum::Form<T> {
+    fn schema(components: &mut Components) -> Value {
+        T::schema(components)
+    }
+}
+
+/// Sequences, as arrays
+macro_rules! array_schema {
+    ($($ty:ident),*) => {
+        $(
+            impl<T: ApiSchema> ApiSchema for $ty<T> {
+                fn schema(components: &mut Components) -> Value {
+                    json!({ "type": "array", "items": T::schema(components) })
+                }
+            }
+        )*
+    };
+}
+
+array_schema!(Vec, VecDeque);
+
+impl<T: ApiSchema> ApiSchema for [T] {
+    fn schema(components: &mut Components) -> Value {
+        json!({ "type": "array", "items": T::schema(components) })
+    }
+}
+
+impl<T: ApiSchema, S> ApiSchema for HashSet<T, S> {
+    fn schema(components: &mut Components) -> Value {
+        json!({ "type": "array", "items": T::schema(components), "uniqueItems": true })
+    }
+}
+
+impl<T: ApiSchema> ApiSchema for BTreeSet<T> {
+    fn schema(components: &mut Components) -> Value {
+        json!({ "type": "array", "items": T::schema(components), "uniqueItems": true })
+    }
+}
+
+impl<K, V: ApiSchema, S> ApiSchema for HashMap<K, V, S> {
+    fn schema(components: &mut Components) -> Value {
+        json!({ "type": "object", "additionalProperties": V::schema(components) })
+    }
+}
+
+impl<K, V: ApiSchema> ApiSchema for BTreeMap<K, V> {
+    fn schema(components: &mut Components) -> Value {
+        json!({ "type": "object", "additionalProperties": V::schema(components) })
+    }
+}
+
+/// The envelope, with `data` described as `T`
+impl<T: ApiSchema + Serialize> ApiSchema for ApiResponse<T> {
+    fn schema(components: &mut Components) -> Value {
+        let error = components.define("ApiError", |_| {
+            json!({
+                "type": "object",
+                "properties": {
+                    "code": { "type": "string" },
+                    "message": { "type": "string" },
+                    "details": {},
+                },
+                "required": ["code", "message"],
+            })
+        });
+        let pagination = PageInfo::schema(components);
+        json!({
+            "type": "object",
+            "properties": {
+                "success": { "type": "boolean" },
+                "data": T::schema(components),
+                "meta": {
+                    "type": "object",
+                    "properties": { "pagination": pagination },
+                },
+                "error": error,
+            },
+            "required": ["success"],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Node;
+
+    impl ApiSchema for Node {
+        fn schema(components: &mut Components) -> Value {
+            components.define("Node", |components| {
+                object_schema(
+                    None,
+                    vec![Property {
+                        name: "children",
+                        schema: Vec::<Node>::schema(components),
+                        description: None,
+                        required: true,
+                    }],
+                )
+            })
+        }
+    }
+
+    #[test]
+    fn recursive_types_refer_to_themselves() {
+        let mut components = Components::new();
+        let schema = Node::schema(&mut components);
+
+        assert_eq!(schema, json!({ "$ref": "#/components/schemas/Node" }));
+        assert_eq!(
+            components.schemas()["Node"]["properties"]["children"]["items"],
+            schema
+        );
+        assert_eq!(components.resolve(&schema)["type"], "object");
+    }
+
+    // The borrows pick the impl the way the macros' expansion does.
+    #[test]
+    #[allow(clippy::needless_borrow)]
+    fn types_without_a_schema_are_probed_as_none() {
+        use super::{Described as _, Undescribed as _};
+        let mut components = Components::new();
+
+        let described = (&&SchemaProbe::<Vec<u32>>::new()).schema(&mut components);
+        let undescribed = (&&SchemaProbe::<std::time::Instant>::new()).schema(&mut components);
+
+        assert_eq!(described.unwrap()["items"]["format"], "int64");
+        assert_eq!(undescribed, None);
+    }
+}
@@ -1,9 +1,39 @@
+#[cfg(feature = "validation")]
+use crate::i18n::{MessageArgs, MessageKeyFn};
+#[cfg(feature = "validation")]
+use crate::pipe::FieldError;
 use crate::pipe::{Pipe, PipeError, PipeResult};
 use async_trait::async_trait;
+use std::marker::PhantomData;
+use std::str::FromStr;
+use strum::VariantNames;
+
+/// The rejection of `raw`, which isn't `expected`
+fn rejected(param: Option<&str>, expected: &str, raw: &str) -> PipeError {
+    PipeError::Validation(format!(
+        "{} must be {}, got {:?}",
+        param.unwrap_or("value"),
+        expected,
+        raw
+    ))
+}
 
 /// A pipe that parses a string into an integer
-#[derive(Default)]
-pub struct ParseIntPipe;
+///
+/// Use [`named`](ParseIntPipe::named) to name the parameter in the
+/// rejection, e.g. `id must be an integer, got "abc"`.
+#[derive(Debug, Default, Clone)]
+pub struct ParseIntPipe {
+    param: Option<String>,
+}
+
+impl ParseIntPipe {
+    pub fn named(param: impl Into<String>) -> Self {
+        Self {
+            param: Some(param.into()),
+        }
+    }
+}
 
 #[async_trait]
 impl Pipe for ParseIntPipe {
@@ -11,6 +41,549 @@ impl Pipe for ParseIntPipe {
     type Output = i32;
 
     async fn transform(&self, input: String) -> PipeResult<i32> {
-        input.parse::<i32>().map_err(|_| PipeError::Validation("Invalid integer".to_string()))
+        input
+            .parse::<i32>()
+            .map_err(|_| rejected(self.param.as_deref(), "an integer", &input))
+    }
+}
+
+/// A pipe that parses a string into a float
+#[derive(Debug, Default, Clone)]
+pub struct ParseFloatPipe {
+    param: Option<String>,
+}
+
+impl ParseFloatPipe {
+    pub fn named(param: impl Into<String>) -> Self {
+        Self {
+            param: Some(param.into()),
+        }
+    }
+}
+
+#[async_trait]
+impl Pipe for ParseFloatPipe {
+    type Input = String;
+    type Output = f64;
+
+    async fn transform(&self, input: String) -> PipeResult<f64> {
+        input
+            .parse::<f64>()
+            .map_err(|_| rejected(self.param.as_deref(), "a number", &input))
+    }
+}
+
+/// A pipe that parses `"true"` or `"false"` into a bool
+#[derive(Debug, Default, Clone)]
+pub struct ParseBoolPipe {
+    param: Option<String>,
+}
+
+impl ParseBoolPipe {
+    pub fn named(param: impl Into<String>) -> Self {
+        Self {
+            param: Some(param.into()),
+        }
+    }
+}
+
+#[async_trait]
+impl Pipe for ParseBoolPipe {
+    type Input = String;
+    type Output = bool;
+
+    async fn transform(&self, input: String) -> PipeResult<bool> {
+        input
+            .parse::<bool>()
+            .map_err(|_| rejected(self.param.as_deref(), "true or false", &input))
+    }
+}
+
+/// A pipe that parses a string into a UUID
+#[cfg(feature = "uuid")]
+#[derive(Debug, Default, Clone)]
+pub struct ParseUuidPipe {
+    param: Option<String>,
+}
+
+#[cfg(feature = "uuid")]
+impl ParseUuidPipe {
+    pub fn named(param: impl Into<String>) -> Self {
+        Self {
+            param: Some(param.into()),
+        }
+    }
+}
+
+#[cfg(feature = "uuid")]
+#[async_trait]
+impl Pipe for ParseUuidPipe {
+    type Input = String;
+    type Output = uuid::Uuid;
+
+    async fn transform(&self, input: String) -> PipeResult<uuid::Uuid> {
+        uuid::Uuid::parse_str(&input).map_err(|_| rejected(self.param.as_deref(), "a UUID", &input))
+    }
+}
+
+/// A pipe that parses a string into one of the variants of `E`
+///
+/// The rejection lists the allowed variants, so `E` also names them
+/// through `strum::VariantNames`:
+///
+/// ```
+/// use meshestra::pipe::{Pipe, builtins::ParseEnumPipe};
+/// use strum_macros::{EnumString, VariantNames};
+///
+/// #[derive(Debug, PartialEq, EnumString, VariantNames)]
+/// #[strum(serialize_all = "lowercase")]
+/// enum Sort {
+///     Newest,
+///     Oldest,
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let pipe = ParseEnumPipe::<Sort>::default();
+/// assert_eq!(pipe.transform("oldest".to_string()).await.unwrap(), Sort::Oldest);
+/// # }
+/// ```
+pub struct ParseEnumPipe<E> {
+    param: Option<String>,
+    _enum: PhantomData<fn() -> E>,
+}
+
+impl<E> ParseEnumPipe<E> {
+    pub fn named(param: impl Into<String>) -> Self {
+        Self {
+            param: Some(param.into()),
+            _enum: PhantomData,
+        }
+    }
+}
+
+impl<E> Default for ParseEnumPipe<E> {
+    fn default() -> Self {
+        Self {
+            param: None,
+            _enum: PhantomData,
+        }
+    }
+}
+
+#[async_trait]
+impl<E> Pipe for ParseEnumPipe<E>
+where
+    E: FromStr + VariantNames + Send + 'static,
+{
+    type Input = String;
+    type Output = E;
+
+    async fn transform(&self, input: String) -> PipeResult<E> {
+        input.parse::<E>().map_err(|_| {
+            let expected = format!("one of {}", E::VARIANTS.join(", "));
+            rejected(self.param.as_deref(), &expected, &input)
+        })
+    }
+}
+
+/// A pipe that strips leading and trailing whitespace
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TrimPipe;
+
+#[async_trait]
+impl Pipe for TrimPipe {
+    type Input = String;
+    type Output = String;
+
+    async fn transform(&self, input: String) -> PipeResult<String> {
+        Ok(input.trim().to_string())
+    }
+}
+
+/// A pipe that lowercases a string
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LowercasePipe;
+
+#[async_trait]
+impl Pipe for LowercasePipe {
+    type Input = String;
+    type Output = String;
+
+    async fn transform(&self, input: String) -> PipeResult<String> {
+        Ok(input.to_lowercase())
+    }
+}
+
+/// A pipe that substitutes `default` for a missing value
+///
+/// Has no `Default` of its own, so `#[pipe(DefaultValuePipe<u32>)]`
+/// resolves it from the container.
+#[derive(Debug, Clone)]
+pub struct DefaultValuePipe<T> {
+    default: T,
+}
+
+impl<T> DefaultValuePipe<T> {
+    pub fn new(default: T) -> Self {
+        Self { default }
+    }
+}
+
+#[async_trait]
+impl<T> Pipe for DefaultValuePipe<T>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    type Input = Option<T>;
+    type Output = T;
+
+    async fn transform(&self, input: Option<T>) -> PipeResult<T> {
+        Ok(input.unwrap_or_else(|| self.default.clone()))
+    }
+}
+
+/// A pipe that validates a value with the `validator` crate
+///
+/// Failures become [`PipeError::InvalidFields`], one entry per failed rule,
+/// with nested structs and collections flattened into paths such as
+/// `address.city` and `items[0].name`. `#[body(validate)]` runs it on the
+/// request body.
+#[cfg(feature = "validation")]
+pub struct ValidationPipe<T> {
+    message_keys: MessageKeyFn,
+    value: PhantomData<fn() -> T>,
+}
+
+#[cfg(feature = "validation")]
+impl<T> ValidationPipe<T> {
+    pub fn new() -> Self {
+        Self {
+            message_keys: |_| None,
+            value: PhantomData,
+        }
+    }
+
+    /// Take the message keys of the fields from `message_keys`, as
+    /// [`MessageKeys`](crate::i18n::MessageKeys) gives them
+    pub fn with_message_keys(mut self, message_keys: MessageKeyFn) -> Self {
+        self.message_keys = message_keys;
+        self
+    }
+}
+
+#[cfg(feature = "validation")]
+impl<T> Default for ValidationPipe<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "validation")]
+#[async_trait]
+impl<T> Pipe for ValidationPipe<T>
+where
+    T: validator::Validate + serde::de::DeserializeOwned + Send + 'static,
+{
+    type Input = T;
+    type Output = T;
+
+    async fn transform(&self, input: T) -> PipeResult<T> {
+        input.validate().map_err(|errors| {
+            let mut fields = Vec::new();
+            collect_field_errors(&errors, "", &mut fields);
+            for field in &mut fields {
+                if let Some(key) = (self.message_keys)(&field.field) {
+                    field.key = key.to_string();
+                }
+            }
+            PipeError::InvalidFields(fields)
+        })?;
+        Ok(input)
+    }
+}
+
+/// Flatten `errors` into `fields`, prefixing their paths with `path`
+#[cfg(feature = "validation")]
+fn collect_field_errors(
+    errors: &validator::ValidationErrors,
+    path: &str,
+    fields: &mut Vec<FieldError>,
+) {
+    use validator::ValidationErrorsKind;
+
+    let join = |field: &str| match path {
+        "" => field.to_string(),
+        _ => format!("{}.{}", path, field),
+    };
+
+    // Sorted, so responses list fields in a stable order
+    let mut entries: Vec<_> = errors.errors().iter().collect();
+    entries.sort_by_key(|(field, _)| *field);
+
+    for (field, kind) in entries {
+        let field = join(field);
+        match kind {
+            ValidationErrorsKind::Field(errors) => {
+                fields.extend(errors.iter().map(|error| {
+                    let message = error
+                        .message
+                        .as_ref()
+                        .map_or_else(|| error.code.to_string(), ToString::to_string);
+                    let mut args: MessageArgs = error
+                        .params
+                        .iter()
+                        .map(|(name, value)| (name.to_string(), value.clone()))
+                        .collect();
+                    args.insert("field".to_string(), field.clone().into());
+                    args.insert("code".to_string(), error.code.to_string().into());
+                    FieldError {
+                        field: field.clone(),
+                        code: error.code.to_string(),
+                        key: message.clone(),
+                        message,
+                        args,
+                    }
+                }));
+            }
+            ValidationErrorsKind::Struct(errors) => collect_field_errors(errors, &field, fields),
+            ValidationErrorsKind::List(items) => {
+                for (index, errors) in items {
+                    collect_field_errors(errors, &format!("{}[{}]", field, index), fields);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "validation"))]
+mod validation_tests {
+    use super::*;
+    use crate::i18n::{Localizer, MessageCatalog};
+    use serde::Deserialize;
+    use std::sync::Arc;
+    use validator::Validate;
+
+    #[derive(Debug, Deserialize, Validate)]
+    struct Address {
+        #[validate(length(min = 1, message = "city is required"))]
+        city: String,
+    }
+
+    #[derive(Debug, Deserialize, Validate)]
+    struct Item {
+        #[validate(range(min = 1))]
+        quantity: u32,
+    }
+
+    #[derive(Debug, Deserialize, Validate)]
+    struct Order {
+        #[validate(email)]
+        email: String,
+        #[validate(nested)]
+        address: Address,
+        #[validate(nested)]
+        items: Vec<Item>,
+    }
+
+    fn order(email: &str, city: &str, quantities: &[u32]) -> Order {
+        Order {
+            email: email.to_string(),
+            address: Address {
+                city: city.to_string(),
+            },
+            items: quantities
+                .iter()
+                .map(|&quantity| Item { quantity })
+                .collect(),
+        }
+    }
+
+    /// The field, code and message of each invalid field
+    fn invalid_fields(error: PipeError) -> Vec<(String, String, String)> {
+        match error {
+            PipeError::InvalidFields(fields) => fields
+                .into_iter()
+                .map(|field| (field.field, field.code, field.message))
+                .collect(),
+            other => panic!("expected invalid fields, got {:?}", other),
+        }
+    }
+
+    fn field(field: &str, code: &str, message: &str) -> (String, String, String) {
+        (field.to_string(), code.to_string(), message.to_string())
+    }
+
+    #[tokio::test]
+    async fn passes_valid_values_through() {
+        let pipe = ValidationPipe::<Order>::new();
+        let valid = pipe
+            .transform(order("a@b.io", "Seoul", &[1, 2]))
+            .await
+            .unwrap();
+        assert_eq!(valid.address.city, "Seoul");
+    }
+
+    #[tokio::test]
+    async fn flattens_nested_and_collection_errors() {
+        let pipe = ValidationPipe::<Order>::new();
+
+        let error = pipe
+            .transform(order("nope", "", &[1, 0]))
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            invalid_fields(error),
+            vec![
+                field("address.city", "length", "city is required"),
+                field("email", "email", "email"),
+                field("items[1].quantity", "range", "range"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn localizes_messages_by_key() {
+        let catalog = MessageCatalog::new("en")
+            .with_messages("en", [("order.email", "{field} isn't an email address")])
+            .with_messages("de", [("city is required", "{field} fehlt")]);
+        let localizer = Localizer::new(Arc::new(catalog), Some("de-CH, en;q=0.5"));
+        let pipe = ValidationPipe::<Order>::new().with_message_keys(|field| match field {
+            "email" => Some("order.email"),
+            _ => None,
+        });
+
+        let error = pipe
+            .transform(order("nope", "", &[1, 0]))
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            invalid_fields(error.localize(&localizer)),
+            vec![
+                field("address.city", "length", "address.city fehlt"),
+                field("email", "email", "email isn't an email address"),
+                field("items[1].quantity", "range", "range"),
+            ]
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use strum_macros::{EnumString, VariantNames};
+
+    async fn rejection<P: Pipe>(pipe: P, input: P::Input) -> String {
+        match pipe.transform(input).await {
+            Err(PipeError::Validation(message)) => message,
+            Err(other) => panic!("expected a validation error, got {:?}", other),
+            Ok(_) => panic!("expected a validation error"),
+        }
+    }
+
+    #[tokio::test]
+    async fn parse_int() {
+        assert_eq!(
+            ParseIntPipe::default()
+                .transform("-42".into())
+                .await
+                .unwrap(),
+            -42
+        );
+        assert_eq!(
+            rejection(ParseIntPipe::named("id"), "4x".into()).await,
+            r#"id must be an integer, got "4x""#
+        );
+        assert_eq!(
+            rejection(ParseIntPipe::default(), "".into()).await,
+            r#"value must be an integer, got """#
+        );
+    }
+
+    #[tokio::test]
+    async fn parse_float() {
+        assert_eq!(
+            ParseFloatPipe::default()
+                .transform("2.5".into())
+                .await
+                .unwrap(),
+            2.5
+        );
+        assert_eq!(
+            rejection(ParseFloatPipe::named("price"), "cheap".into()).await,
+            r#"price must be a number, got "cheap""#
+        );
+    }
+
+    #[tokio::test]
+    async fn parse_bool() {
+        assert!(
+            ParseBoolPipe::default()
+                .transform("true".into())
+                .await
+                .unwrap()
+        );
+        assert!(
+            !ParseBoolPipe::default()
+                .transform("false".into())
+                .await
+                .unwrap()
+        );
+        assert_eq!(
+            rejection(ParseBoolPipe::named("draft"), "yes".into()).await,
+            r#"draft must be true or false, got "yes""#
+        );
+    }
+
+    #[cfg(feature = "uuid")]
+    #[tokio::test]
+    async fn parse_uuid() {
+        let id = uuid::Uuid::new_v4();
+        assert_eq!(
+            ParseUuidPipe::default()
+                .transform(id.to_string())
+                .await
+                .unwrap(),
+            id
+        );
+        assert_eq!(
+            rejection(ParseUuidPipe::named("user_id"), "123".into()).await,
+            r#"user_id must be a UUID, got "123""#
+        );
+    }
+
+    #[derive(Debug, PartialEq, EnumString, VariantNames)]
+    #[strum(serialize_all = "lowercase")]
+    enum Sort {
+        Newest,
+        Oldest,
+    }
+
+    #[tokio::test]
+    async fn parse_enum() {
+        let pipe = ParseEnumPipe::<Sort>::default();
+        assert_eq!(pipe.transform("newest".into()).await.unwrap(), Sort::Newest);
+        assert_eq!(
+            rejection(ParseEnumPipe::<Sort>::named("sort"), "random".into()).await,
+            r#"sort must be one of newest, oldest, got "random""#
+        );
+    }
+
+    #[tokio::test]
+    async fn string_pipes() {
+        assert_eq!(TrimPipe.transform("  a b \n".into()).await.unwrap(), "a b");
+        assert_eq!(
+            LowercasePipe.transform("MeSh".into()).await.unwrap(),
+            "mesh"
+        );
+    }
+
+    #[tokio::test]
+    async fn default_value() {
+        let pipe = DefaultValuePipe::new(20u32);
+        assert_eq!(pipe.transform(None).await.unwrap(), 20);
+        assert_eq!(pipe.transform(Some(5)).await.unwrap(), 5);
     }
 }
@@ -0,0 +1,135 @@
+use crate::pipe::{Pipe, PipeError, PipeResult};
+use async_trait::async_trait;
+
+/// Combinators available on every [`Pipe`]
+///
+/// # Example
+/// ```
+/// use meshestra::pipe::{Pipe, PipeExt};
+/// use meshestra::pipe::builtins::{ParseIntPipe, TrimPipe};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let pipe = TrimPipe.then(ParseIntPipe::named("page")).map(|page| page.max(1));
+/// assert_eq!(pipe.transform(" 0 ".to_string()).await.unwrap(), 1);
+/// # }
+/// ```
+pub trait PipeExt: Pipe + Sized {
+    /// Feed this pipe's output to `next`
+    fn then<P: Pipe<Input = Self::Output>>(self, next: P) -> Chained<Self, P> {
+        Chained(self, next)
+    }
+
+    /// Transform the output with `f`
+    fn map<F, O>(self, f: F) -> Map<Self, F>
+    where
+        F: Fn(Self::Output) -> O + Send + Sync,
+        O: Send,
+    {
+        Map(self, f)
+    }
+
+    /// Replace the rejection with `f`'s, e.g. to reword its message
+    fn map_err<F>(self, f: F) -> MapErr<Self, F>
+    where
+        F: Fn(PipeError) -> PipeError + Send + Sync,
+    {
+        MapErr(self, f)
+    }
+}
+
+impl<P: Pipe> PipeExt for P {}
+
+/// Runs the first pipe, then the second on its output; see [`PipeExt::then`]
+#[derive(Debug, Clone)]
+pub struct Chained<A, B>(A, B);
+
+#[async_trait]
+impl<A, B> Pipe for Chained<A, B>
+where
+    A: Pipe,
+    B: Pipe<Input = A::Output>,
+{
+    type Input = A::Input;
+    type Output = B::Output;
+
+    async fn transform(&self, input: A::Input) -> PipeResult<B::Output> {
+        let intermediate = self.0.transform(input).await?;
+        self.1.transform(intermediate).await
+    }
+}
+
+/// Transforms a pipe's output; see [`PipeExt::map`]
+#[derive(Debug, Clone)]
+pub struct Map<P, F>(P, F);
+
+#[async_trait]
+impl<P, F, O> Pipe for Map<P, F>
+where
+    P: Pipe,
+    F: Fn(P::Output) -> O + Send + Sync,
+    O: Send,
+{
+    type Input = P::Input;
+    type Output = O;
+
+    async fn transform(&self, input: P::Input) -> PipeResult<O> {
+        self.0.transform(input).await.map(&self.1)
+    }
+}
+
+/// Replaces a pipe's rejection; see [`PipeExt::map_err`]
+#[derive(Debug, Clone)]
+pub struct MapErr<P, F>(P, F);
+
+#[async_trait]
+impl<P, F> Pipe for MapErr<P, F>
+where
+    P: Pipe,
+    F: Fn(PipeError) -> PipeError + Send + Sync,
+{
+    type Input = P::Input;
+    type Output = P::Output;
+
+    async fn transform(&self, input: P::Input) -> PipeResult<P::Output> {
+        self.0.transform(input).await.map_err(&self.1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipe::builtins::{LowercasePipe, ParseIntPipe, TrimPipe};
+
+    #[tokio::test]
+    async fn then_runs_pipes_in_sequence() {
+        let pipe = TrimPipe.then(ParseIntPipe::named("id"));
+
+        assert_eq!(pipe.transform("  42\n".to_string()).await.unwrap(), 42);
+        assert!(matches!(
+            pipe.transform(" forty-two ".to_string()).await,
+            Err(PipeError::Validation(message)) if message.contains("\"forty-two\"")
+        ));
+    }
+
+    #[tokio::test]
+    async fn chains_nest() {
+        let pipe = TrimPipe.then(LowercasePipe).then(TrimPipe);
+
+        assert_eq!(pipe.transform(" MESH ".to_string()).await.unwrap(), "mesh");
+    }
+
+    #[tokio::test]
+    async fn map_and_map_err_adapt_either_side() {
+        let pipe = TrimPipe
+            .then(ParseIntPipe::default())
+            .map(|n| n * 2)
+            .map_err(|_| PipeError::Validation("expected a number".to_string()));
+
+        assert_eq!(pipe.transform(" 21 ".to_string()).await.unwrap(), 42);
+        assert!(matches!(
+            pipe.transform("x".to_string()).await,
+            Err(PipeError::Validation(message)) if message == "expected a number"
+        ));
+    }
+}
@@ -0,0 +1,141 @@
+use crate::di::Container;
+use crate::pipe::{Pipe, PipeError, PipeResult};
+use async_trait::async_trait;
+use std::any::{Any, TypeId};
+use std::sync::Arc;
+
+/// The pipes registered with `ApplicationBuilder::global_pipe`
+///
+/// Generated routes run them on every `#[body]`, `#[form]` and `#[query]`
+/// value, before the parameter's own `#[pipe(...)]`s. A pipe applies to
+/// values of its `Input` type; global pipes hand back the type they take,
+/// so applicable pipes can run one after another in registration order.
+#[derive(Clone, Default)]
+pub struct GlobalPipes {
+    pipes: Vec<Arc<dyn ErasedPipe>>,
+}
+
+impl GlobalPipes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `pipe` on values of its `Input` type, after the pipes already added
+    pub fn add<P>(&mut self, pipe: Arc<P>)
+    where
+        P: Pipe<Output = <P as Pipe>::Input> + 'static,
+        P::Input: 'static,
+    {
+        self.pipes.push(pipe);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pipes.is_empty()
+    }
+
+    /// Run the pipes applicable to `T` on `value`, in registration order
+    pub async fn apply<T: Send + 'static>(&self, value: T) -> PipeResult<T> {
+        let mut value: Box<dyn Any + Send> = Box::new(value);
+        for pipe in &self.pipes {
+            if pipe.input_type() == TypeId::of::<T>() {
+                value = pipe.transform_any(value).await?;
+            }
+        }
+        value
+            .downcast::<T>()
+            .map(|value| *value)
+            .map_err(|_| PipeError::Internal("global pipe changed the value's type".to_string()))
+    }
+}
+
+/// Run the application's global pipes on `value`, if it has any
+///
+/// This is what generated routes do for `#[body]`, `#[form]` and `#[query]`
+/// parameters not opted out with `#[skip_global_pipes]`.
+pub async fn apply_global_pipes<T: Send + 'static>(
+    container: &Container,
+    value: T,
+) -> PipeResult<T> {
+    match container.resolve::<GlobalPipes>() {
+        Ok(pipes) => pipes.apply(value).await,
+        Err(_) => Ok(value),
+    }
+}
+
+/// A pipe taking and returning its `Input`, behind `Any`
+#[async_trait]
+trait ErasedPipe: Send + Sync {
+    fn input_type(&self) -> TypeId;
+
+    async fn transform_any(&self, value: Box<dyn Any + Send>) -> PipeResult<Box<dyn Any + Send>>;
+}
+
+#[async_trait]
+impl<P> ErasedPipe for P
+where
+    P: Pipe<Output = <P as Pipe>::Input>,
+    P::Input: 'static,
+{
+    fn input_type(&self) -> TypeId {
+        TypeId::of::<P::Input>()
+    }
+
+    async fn transform_any(&self, value: Box<dyn Any + Send>) -> PipeResult<Box<dyn Any + Send>> {
+        let input = value
+            .downcast::<P::Input>()
+            .map_err(|_| PipeError::Internal("global pipe given the wrong type".to_string()))?;
+        let output = self.transform(*input).await?;
+        Ok(Box::new(output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipe::builtins::{LowercasePipe, TrimPipe};
+
+    /// Rejects negative numbers
+    struct NonNegative;
+
+    #[async_trait]
+    impl Pipe for NonNegative {
+        type Input = i64;
+        type Output = i64;
+
+        async fn transform(&self, input: i64) -> PipeResult<i64> {
+            if input < 0 {
+                Err(PipeError::Validation(format!("{} is negative", input)))
+            } else {
+                Ok(input)
+            }
+        }
+    }
+
+    fn pipes() -> GlobalPipes {
+        let mut pipes = GlobalPipes::new();
+        pipes.add(Arc::new(TrimPipe));
+        pipes.add(Arc::new(NonNegative));
+        pipes.add(Arc::new(LowercasePipe));
+        pipes
+    }
+
+    #[tokio::test]
+    async fn runs_the_pipes_for_the_value_type_in_order() {
+        let pipes = pipes();
+
+        assert_eq!(pipes.apply(" MeSh ".to_string()).await.unwrap(), "mesh");
+        assert_eq!(pipes.apply(7i64).await.unwrap(), 7);
+        assert!(matches!(
+            pipes.apply(-7i64).await,
+            Err(PipeError::Validation(_))
+        ));
+        // No pipe takes `u8`
+        assert_eq!(pipes.apply(3u8).await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn no_registry_leaves_values_alone() {
+        let value = apply_global_pipes(&Container::new(), " as is ".to_string()).await;
+        assert_eq!(value.unwrap(), " as is ");
+    }
+}
@@ -1,7 +1,21 @@
+use crate::common::{ApiResponse, StatusCode};
+use crate::i18n::{Localizer, MessageArgs};
 use async_trait::async_trait;
+use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+use serde_json::json;
 use std::fmt::Debug;
 
 pub mod builtins;
+mod combinators;
+mod global;
+mod resolve;
+
+pub use combinators::{Chained, Map, MapErr, PipeExt};
+pub use global::{GlobalPipes, apply_global_pipes};
+
+#[doc(hidden)]
+pub use resolve::{PipeSource, ResolveFromContainer, ResolveOrDefault};
 
 pub type PipeResult<T> = Result<T, PipeError>;
 
@@ -15,13 +29,111 @@ pub enum PipeError {
 
     #[error("Internal pipe error: {0}")]
     Internal(String),
+
+    /// Fields of a value that failed validation, e.g. by `ValidationPipe`
+    #[error("Validation failed: {} invalid field(s)", .0.len())]
+    InvalidFields(Vec<FieldError>),
+}
+
+/// A field that failed validation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldError {
+    /// Path of the field, e.g. `address.city` or `items[0].name`
+    pub field: String,
+    /// The failed rule, e.g. `length` or `email`
+    pub code: String,
+    pub message: String,
+    /// The key [`PipeError::localize`] looks `message` up with: the
+    /// field's `#[message(key = "...")]`, else `message` itself
+    #[serde(skip)]
+    pub key: String,
+    /// The values the message interpolates: `field`, `code` and the
+    /// rule's parameters, such as `min`
+    #[serde(skip)]
+    pub args: MessageArgs,
+}
+
+impl PipeError {
+    /// The error with the messages of its invalid fields in the locale of
+    /// `localizer`
+    pub fn localize(self, localizer: &Localizer) -> Self {
+        match self {
+            PipeError::InvalidFields(fields) => PipeError::InvalidFields(
+                fields
+                    .into_iter()
+                    .map(|field| FieldError {
+                        message: localizer.message(&field.key, &field.args),
+                        ..field
+                    })
+                    .collect(),
+            ),
+            other => other,
+        }
+    }
+}
+
+/// Renders the failure in the same shape as `ApiResponse::error`: a 400
+/// for input the pipe rejected, a 422 listing invalid fields in
+/// `error.details`, a 500 for the pipe's own failures
+impl IntoResponse for PipeError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            PipeError::Validation(_) | PipeError::Transformation(_) => StatusCode::BadRequest,
+            PipeError::InvalidFields(_) => StatusCode::UnprocessableEntity,
+            PipeError::Internal(_) => StatusCode::InternalServerError,
+        };
+        let response = ApiResponse::<()>::error(status, self.to_string());
+        match &self {
+            PipeError::InvalidFields(fields) => response.with_details(json!(fields)),
+            _ => response,
+        }
+        .into_response()
+    }
 }
 
 /// The Pipe trait for transformation and validation
+///
+/// Pipes registered with `#[pipe(...)]` or as global pipes live in the
+/// container and so are `'static`, but pipes used directly may borrow
+/// their input. Taking a `Cow` lets one pipe serve borrowed and owned
+/// values, allocating only when it has to change them:
+///
+/// ```
+/// use meshestra::pipe::{Pipe, PipeResult};
+/// use async_trait::async_trait;
+/// use std::borrow::Cow;
+/// use std::marker::PhantomData;
+///
+/// /// Strips whitespace without copying the rest
+/// struct TrimCow<'a>(PhantomData<&'a ()>);
+///
+/// #[async_trait]
+/// impl<'a> Pipe for TrimCow<'a> {
+///     type Input = Cow<'a, str>;
+///     type Output = Cow<'a, str>;
+///
+///     async fn transform(&self, input: Cow<'a, str>) -> PipeResult<Cow<'a, str>> {
+///         Ok(match input {
+///             Cow::Borrowed(text) => Cow::Borrowed(text.trim()),
+///             Cow::Owned(text) if text.trim().len() == text.len() => Cow::Owned(text),
+///             Cow::Owned(text) => Cow::Owned(text.trim().to_string()),
+///         })
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let raw = String::from("  42 ");
+/// let trimmed = TrimCow(PhantomData).transform(Cow::Borrowed(raw.as_str())).await.unwrap();
+/// assert!(matches!(trimmed, Cow::Borrowed("42")));
+/// # }
+/// ```
+///
+/// Compose pipes with [`PipeExt`].
 #[async_trait]
-pub trait Pipe: Send + Sync + 'static {
-    type Input: Send + 'static;
-    type Output: Send + 'static;
+pub trait Pipe: Send + Sync {
+    type Input: Send;
+    type Output: Send;
 
     async fn transform(&self, input: Self::Input) -> PipeResult<Self::Output>;
 }
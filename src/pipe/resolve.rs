@@ -0,0 +1,104 @@
+use crate::di::Container;
+use crate::pipe::{Pipe, PipeError, PipeResult};
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// Where `#[pipe(P)]` gets its `P`
+///
+/// The container's `P` when one is registered, otherwise `P::default()`
+/// for pipes implementing `Default`, so unit-struct pipes need no
+/// registration. Generated code calls `resolve` on `&PipeSource<P>` with
+/// both traits in scope; method resolution picks [`ResolveOrDefault`]
+/// when `P: Default` and [`ResolveFromContainer`] otherwise.
+pub struct PipeSource<P>(PhantomData<fn() -> P>);
+
+impl<P> PipeSource<P> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<P> Default for PipeSource<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub trait ResolveOrDefault<P> {
+    fn resolve(&self, container: &Container) -> PipeResult<Arc<P>>;
+}
+
+impl<P: Pipe + Default + 'static> ResolveOrDefault<P> for PipeSource<P> {
+    fn resolve(&self, container: &Container) -> PipeResult<Arc<P>> {
+        Ok(container
+            .resolve::<P>()
+            .unwrap_or_else(|_| Arc::new(P::default())))
+    }
+}
+
+pub trait ResolveFromContainer<P> {
+    fn resolve(&self, container: &Container) -> PipeResult<Arc<P>>;
+}
+
+impl<P: Pipe + 'static> ResolveFromContainer<P> for &PipeSource<P> {
+    fn resolve(&self, container: &Container) -> PipeResult<Arc<P>> {
+        container
+            .resolve::<P>()
+            .map_err(|e| PipeError::Internal(format!("Pipe could not be resolved: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+
+    /// Needs its prefix from the container
+    struct Prefix(&'static str);
+
+    #[async_trait]
+    impl Pipe for Prefix {
+        type Input = String;
+        type Output = String;
+
+        async fn transform(&self, input: String) -> PipeResult<String> {
+            Ok(format!("{}{}", self.0, input))
+        }
+    }
+
+    #[derive(Default)]
+    struct Shout;
+
+    #[async_trait]
+    impl Pipe for Shout {
+        type Input = String;
+        type Output = String;
+
+        async fn transform(&self, input: String) -> PipeResult<String> {
+            Ok(input.to_uppercase())
+        }
+    }
+
+    // The borrows pick the impl the way the macros' expansion does.
+    #[tokio::test]
+    #[allow(clippy::needless_borrow)]
+    async fn default_pipes_need_no_registration() {
+        let pipe = (&PipeSource::<Shout>::new())
+            .resolve(&Container::new())
+            .unwrap();
+        assert_eq!(pipe.transform("hi".to_string()).await.unwrap(), "HI");
+    }
+
+    #[tokio::test]
+    async fn other_pipes_come_from_the_container() {
+        assert!(matches!(
+            (&PipeSource::<Prefix>::new()).resolve(&Container::new()),
+            Err(PipeError::Internal(_))
+        ));
+
+        let mut container = Container::new();
+        container.register(Prefix("> "));
+        let pipe = (&PipeSource::<Prefix>::new()).resolve(&container).unwrap();
+        assert_eq!(pipe.transform("hi".to_string()).await.unwrap(), "> hi");
+    }
+}
@@ -0,0 +1,46 @@
+use crate::saga::{SagaError, SagaStep};
+use async_trait::async_trait;
+use std::future::Future;
+use std::pin::Pin;
+
+/// A boxed, `Send` future borrowing from the saga context
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Adapter turning a pair of async closures into a [`SagaStep`]
+///
+/// Created by [`SagaOrchestrator::step`](crate::saga::SagaOrchestrator::step).
+pub(crate) struct FnSagaStep<E, C> {
+    name: String,
+    execute_fn: E,
+    compensate_fn: C,
+}
+
+impl<E, C> FnSagaStep<E, C> {
+    pub(crate) fn new(name: impl Into<String>, execute_fn: E, compensate_fn: C) -> Self {
+        Self {
+            name: name.into(),
+            execute_fn,
+            compensate_fn,
+        }
+    }
+}
+
+#[async_trait]
+impl<Context, E, C> SagaStep<Context> for FnSagaStep<E, C>
+where
+    Context: Send + 'static,
+    E: for<'a> Fn(&'a mut Context) -> BoxFuture<'a, Result<(), SagaError>> + Send + Sync,
+    C: for<'a> Fn(&'a mut Context) -> BoxFuture<'a, Result<(), SagaError>> + Send + Sync,
+{
+    async fn execute(&self, context: &mut Context) -> Result<(), SagaError> {
+        (self.execute_fn)(context).await
+    }
+
+    async fn compensate(&self, context: &mut Context) -> Result<(), SagaError> {
+        (self.compensate_fn)(context).await
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
@@ -0,0 +1,108 @@
+use crate::messaging::EventBus;
+use crate::saga::SagaError;
+
+/// Observer for saga execution progress
+///
+/// All callbacks default to no-ops, so implementors only override the
+/// events they care about. Callbacks are invoked synchronously from the
+/// orchestrator and should not block.
+pub trait SagaListener: Send + Sync {
+    /// Called right before a step's `execute` runs
+    fn on_step_started(&self, _saga: &str, _step: &str) {}
+
+    /// Called after a step's `execute` returned successfully
+    fn on_step_completed(&self, _saga: &str, _step: &str) {}
+
+    /// Called when a step's `execute` returned an error
+    fn on_step_failed(&self, _saga: &str, _step: &str, _error: &SagaError) {}
+
+    /// Called right before a step's `compensate` runs
+    fn on_compensation_started(&self, _saga: &str, _step: &str) {}
+
+    /// Called after a step's `compensate` finished, successfully or not
+    fn on_compensation_finished(&self, _saga: &str, _step: &str, _result: Result<(), &SagaError>) {}
+
+    /// Called once the saga has finished, successfully or not
+    fn on_saga_completed(&self, _saga: &str, _result: Result<(), &SagaError>) {}
+}
+
+/// Typed event published on the [`EventBus`] for every saga callback
+///
+/// Subscribe with `event_bus.subscribe::<SagaEvent>()` to react to saga
+/// progress from other modules.
+#[derive(Debug, Clone)]
+pub enum SagaEvent {
+    StepStarted {
+        saga: String,
+        step: String,
+    },
+    StepCompleted {
+        saga: String,
+        step: String,
+    },
+    StepFailed {
+        saga: String,
+        step: String,
+        error: String,
+    },
+    CompensationStarted {
+        saga: String,
+        step: String,
+    },
+    CompensationFinished {
+        saga: String,
+        step: String,
+        error: Option<String>,
+    },
+    SagaCompleted {
+        saga: String,
+        error: Option<String>,
+    },
+}
+
+/// Publishes each saga callback as a [`SagaEvent`]
+impl SagaListener for EventBus {
+    fn on_step_started(&self, saga: &str, step: &str) {
+        self.publish(SagaEvent::StepStarted {
+            saga: saga.to_string(),
+            step: step.to_string(),
+        });
+    }
+
+    fn on_step_completed(&self, saga: &str, step: &str) {
+        self.publish(SagaEvent::StepCompleted {
+            saga: saga.to_string(),
+            step: step.to_string(),
+        });
+    }
+
+    fn on_step_failed(&self, saga: &str, step: &str, error: &SagaError) {
+        self.publish(SagaEvent::StepFailed {
+            saga: saga.to_string(),
+            step: step.to_string(),
+            error: error.to_string(),
+        });
+    }
+
+    fn on_compensation_started(&self, saga: &str, step: &str) {
+        self.publish(SagaEvent::CompensationStarted {
+            saga: saga.to_string(),
+            step: step.to_string(),
+        });
+    }
+
+    fn on_compensation_finished(&self, saga: &str, step: &str, result: Result<(), &SagaError>) {
+        self.publish(SagaEvent::CompensationFinished {
+            saga: saga.to_string(),
+            step: step.to_string(),
+            error: result.err().map(|e| e.to_string()),
+        });
+    }
+
+    fn on_saga_completed(&self, saga: &str, result: Result<(), &SagaError>) {
+        self.publish(SagaEvent::SagaCompleted {
+            saga: saga.to_string(),
+            error: result.err().map(|e| e.to_string()),
+        });
+    }
+}
@@ -1,4 +1,13 @@
 use async_trait::async_trait;
+use std::sync::Arc;
+use tracing::Instrument;
+
+mod fn_step;
+mod listener;
+
+pub use fn_step::BoxFuture;
+use fn_step::FnSagaStep;
+pub use listener::{SagaEvent, SagaListener};
 
 #[derive(Debug, thiserror::Error)]
 pub enum SagaError {
@@ -6,6 +15,26 @@ pub enum SagaError {
     ExecutionFailed(String),
     #[error("Saga compensation failed: {0}")]
     CompensationFailed(String),
+    #[error("Saga partially compensated after '{source}': {} compensation(s) failed", .failed_steps.len())]
+    PartialCompensation {
+        /// Name and error of every step whose compensation failed, in compensation order
+        failed_steps: Vec<(String, String)>,
+        /// The execution error that triggered compensation
+        #[source]
+        source: Box<SagaError>,
+    },
+}
+
+/// How the orchestrator reacts when a compensation fails
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CompensationPolicy {
+    /// Abort compensation and return `SagaError::CompensationFailed` (default)
+    #[default]
+    StopOnFirstError,
+
+    /// Attempt every remaining compensation and return
+    /// `SagaError::PartialCompensation` listing each failure
+    ContinueAndCollect,
 }
 
 /// Represents a single step in a Saga
@@ -22,8 +51,14 @@ pub trait SagaStep<Context>: Send + Sync {
 }
 
 /// Orchestrates the execution of a Saga
+///
+/// Every execution runs inside a `saga` tracing span carrying the saga name,
+/// and emits one event per step start, success, failure, and compensation.
 pub struct SagaOrchestrator<Context> {
+    name: String,
     steps: Vec<Box<dyn SagaStep<Context>>>,
+    listener: Option<Arc<dyn SagaListener>>,
+    compensation_policy: CompensationPolicy,
 }
 
 impl<Context> SagaOrchestrator<Context>
@@ -31,7 +66,34 @@ where
     Context: Send + 'static,
 {
     pub fn new() -> Self {
-        Self { steps: Vec::new() }
+        Self::named("saga")
+    }
+
+    /// Create an orchestrator whose name appears in every span, event, and listener callback
+    pub fn named(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            steps: Vec::new(),
+            listener: None,
+            compensation_policy: CompensationPolicy::default(),
+        }
+    }
+
+    /// Attach a listener notified of every step and compensation
+    pub fn with_listener(mut self, listener: Arc<dyn SagaListener>) -> Self {
+        self.listener = Some(listener);
+        self
+    }
+
+    /// Set how compensation failures are handled
+    pub fn with_compensation_policy(mut self, policy: CompensationPolicy) -> Self {
+        self.compensation_policy = policy;
+        self
+    }
+
+    /// Name of this saga
+    pub fn name(&self) -> &str {
+        &self.name
     }
 
     pub fn add_step<S: SagaStep<Context> + 'static>(mut self, step: S) -> Self {
@@ -39,34 +101,437 @@ where
         self
     }
 
-    pub async fn execute(&self, mut context: Context) -> Result<Context, SagaError> {
+    /// Add a step defined inline by an execute and a compensate closure
+    ///
+    /// Closure steps and [`SagaStep`] implementations can be freely mixed in
+    /// one orchestrator; they run in the order they were added.
+    ///
+    /// # Example
+    /// ```
+    /// use meshestra::saga::{SagaError, SagaOrchestrator};
+    /// use std::sync::Arc;
+    ///
+    /// struct PaymentService;
+    ///
+    /// impl PaymentService {
+    ///     async fn charge(&self, amount: u64) -> Result<String, SagaError> {
+    ///         Ok(format!("payment-{amount}"))
+    ///     }
+    ///
+    ///     async fn refund(&self, _payment_id: &str) -> Result<(), SagaError> {
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// #[derive(Default)]
+    /// struct Checkout {
+    ///     amount: u64,
+    ///     reserved: bool,
+    ///     payment_id: Option<String>,
+    ///     shipped: bool,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// // Usually resolved from the container
+    /// let payments = Arc::new(PaymentService);
+    /// let charge_payments = payments.clone();
+    ///
+    /// let saga = SagaOrchestrator::named("checkout")
+    ///     .step(
+    ///         "reserve-inventory",
+    ///         |ctx: &mut Checkout| Box::pin(async move {
+    ///             ctx.reserved = true;
+    ///             Ok(())
+    ///         }),
+    ///         |ctx: &mut Checkout| Box::pin(async move {
+    ///             ctx.reserved = false;
+    ///             Ok(())
+    ///         }),
+    ///     )
+    ///     .step(
+    ///         "charge-payment",
+    ///         move |ctx: &mut Checkout| {
+    ///             let payments = charge_payments.clone();
+    ///             Box::pin(async move {
+    ///                 ctx.payment_id = Some(payments.charge(ctx.amount).await?);
+    ///                 Ok(())
+    ///             })
+    ///         },
+    ///         move |ctx: &mut Checkout| {
+    ///             let payments = payments.clone();
+    ///             Box::pin(async move {
+    ///                 if let Some(id) = ctx.payment_id.take() {
+    ///                     payments.refund(&id).await?;
+    ///                 }
+    ///                 Ok(())
+    ///             })
+    ///         },
+    ///     )
+    ///     .step(
+    ///         "ship-order",
+    ///         |ctx: &mut Checkout| Box::pin(async move {
+    ///             ctx.shipped = true;
+    ///             Ok(())
+    ///         }),
+    ///         |ctx: &mut Checkout| Box::pin(async move {
+    ///             ctx.shipped = false;
+    ///             Ok(())
+    ///         }),
+    ///     );
+    ///
+    /// let checkout = saga
+    ///     .execute(Checkout { amount: 42, ..Default::default() })
+    ///     .await
+    ///     .unwrap();
+    /// assert!(checkout.reserved && checkout.shipped);
+    /// assert_eq!(checkout.payment_id.as_deref(), Some("payment-42"));
+    /// # }
+    /// ```
+    pub fn step<E, C>(self, name: impl Into<String>, execute_fn: E, compensate_fn: C) -> Self
+    where
+        E: for<'a> Fn(&'a mut Context) -> BoxFuture<'a, Result<(), SagaError>>
+            + Send
+            + Sync
+            + 'static,
+        C: for<'a> Fn(&'a mut Context) -> BoxFuture<'a, Result<(), SagaError>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.add_step(FnSagaStep::new(name, execute_fn, compensate_fn))
+    }
+
+    pub async fn execute(&self, context: Context) -> Result<Context, SagaError> {
+        let span = tracing::info_span!("saga", saga = %self.name);
+        let result = self.run(context).instrument(span).await;
+
+        self.notify(|l| l.on_saga_completed(&self.name, result.as_ref().map(|_| ())));
+        result
+    }
+
+    async fn run(&self, mut context: Context) -> Result<Context, SagaError> {
         let mut executed_steps = Vec::new();
 
         for (index, step) in self.steps.iter().enumerate() {
+            tracing::debug!(saga = %self.name, step = step.name(), "Saga step started");
+            self.notify(|l| l.on_step_started(&self.name, step.name()));
+
             match step.execute(&mut context).await {
                 Ok(_) => {
+                    tracing::debug!(saga = %self.name, step = step.name(), "Saga step completed");
+                    self.notify(|l| l.on_step_completed(&self.name, step.name()));
                     executed_steps.push(index);
                 }
                 Err(e) => {
-                    // Start compensation in reverse order
-                    eprintln!("Step {} failed: {}. Starting compensation.", step.name(), e);
+                    tracing::warn!(
+                        saga = %self.name,
+                        step = step.name(),
+                        error = %e,
+                        "Saga step failed, starting compensation"
+                    );
+                    self.notify(|l| l.on_step_failed(&self.name, step.name(), &e));
 
+                    // Start compensation in reverse order
+                    let mut failed_steps = Vec::new();
                     for &executed_index in executed_steps.iter().rev() {
                         let executed_step = &self.steps[executed_index];
-                        if let Err(comp_err) = executed_step.compensate(&mut context).await {
-                            eprintln!(
-                                "Compensation failed for step {}: {}",
+                        tracing::debug!(
+                            saga = %self.name,
+                            step = executed_step.name(),
+                            "Compensation started"
+                        );
+                        self.notify(|l| {
+                            l.on_compensation_started(&self.name, executed_step.name())
+                        });
+
+                        let comp_result = executed_step.compensate(&mut context).await;
+                        self.notify(|l| {
+                            l.on_compensation_finished(
+                                &self.name,
                                 executed_step.name(),
-                                comp_err
+                                comp_result.as_ref().map(|_| ()),
+                            )
+                        });
+
+                        if let Err(comp_err) = comp_result {
+                            tracing::error!(
+                                saga = %self.name,
+                                step = executed_step.name(),
+                                error = %comp_err,
+                                "Compensation failed"
                             );
-                            return Err(SagaError::CompensationFailed(comp_err.to_string()));
+                            match self.compensation_policy {
+                                CompensationPolicy::StopOnFirstError => {
+                                    return Err(SagaError::CompensationFailed(
+                                        comp_err.to_string(),
+                                    ));
+                                }
+                                CompensationPolicy::ContinueAndCollect => {
+                                    failed_steps.push((
+                                        executed_step.name().to_string(),
+                                        comp_err.to_string(),
+                                    ));
+                                    continue;
+                                }
+                            }
                         }
+                        tracing::debug!(
+                            saga = %self.name,
+                            step = executed_step.name(),
+                            "Compensation finished"
+                        );
+                    }
+
+                    if !failed_steps.is_empty() {
+                        return Err(SagaError::PartialCompensation {
+                            failed_steps,
+                            source: Box::new(e),
+                        });
                     }
                     return Err(e);
                 }
             }
         }
 
+        tracing::info!(saga = %self.name, "Saga completed");
         Ok(context)
     }
+
+    fn notify(&self, f: impl FnOnce(&dyn SagaListener)) {
+        if let Some(listener) = &self.listener {
+            f(listener.as_ref());
+        }
+    }
+}
+
+impl<Context> Default for SagaOrchestrator<Context>
+where
+    Context: Send + 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordingListener {
+        events: Mutex<Vec<String>>,
+    }
+
+    impl SagaListener for RecordingListener {
+        fn on_step_started(&self, saga: &str, step: &str) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("{saga}:start:{step}"));
+        }
+
+        fn on_step_failed(&self, saga: &str, step: &str, _error: &SagaError) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("{saga}:fail:{step}"));
+        }
+
+        fn on_compensation_finished(
+            &self,
+            saga: &str,
+            step: &str,
+            _result: Result<(), &SagaError>,
+        ) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("{saga}:compensated:{step}"));
+        }
+
+        fn on_saga_completed(&self, saga: &str, result: Result<(), &SagaError>) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("{saga}:done:{}", result.is_ok()));
+        }
+    }
+
+    struct TestStep {
+        name: &'static str,
+        fail: bool,
+    }
+
+    #[async_trait]
+    impl SagaStep<Vec<&'static str>> for TestStep {
+        async fn execute(&self, context: &mut Vec<&'static str>) -> Result<(), SagaError> {
+            if self.fail {
+                return Err(SagaError::ExecutionFailed(self.name.to_string()));
+            }
+            context.push(self.name);
+            Ok(())
+        }
+
+        async fn compensate(&self, context: &mut Vec<&'static str>) -> Result<(), SagaError> {
+            context.retain(|s| *s != self.name);
+            Ok(())
+        }
+
+        fn name(&self) -> &str {
+            self.name
+        }
+    }
+
+    #[tokio::test]
+    async fn test_listener_receives_step_and_compensation_events() {
+        let listener = Arc::new(RecordingListener::default());
+        let saga = SagaOrchestrator::named("checkout")
+            .with_listener(listener.clone())
+            .add_step(TestStep {
+                name: "reserve",
+                fail: false,
+            })
+            .add_step(TestStep {
+                name: "charge",
+                fail: true,
+            });
+
+        assert!(saga.execute(Vec::new()).await.is_err());
+
+        let events = listener.events.lock().unwrap();
+        assert_eq!(
+            *events,
+            vec![
+                "checkout:start:reserve",
+                "checkout:start:charge",
+                "checkout:fail:charge",
+                "checkout:compensated:reserve",
+                "checkout:done:false",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_closure_steps_mix_with_trait_steps() {
+        let saga = SagaOrchestrator::named("mixed")
+            .add_step(TestStep {
+                name: "trait-step",
+                fail: false,
+            })
+            .step(
+                "closure-step",
+                |ctx: &mut Vec<&'static str>| {
+                    Box::pin(async move {
+                        ctx.push("closure-step");
+                        Ok(())
+                    })
+                },
+                |ctx: &mut Vec<&'static str>| {
+                    Box::pin(async move {
+                        ctx.retain(|s| *s != "closure-step");
+                        Ok(())
+                    })
+                },
+            )
+            .add_step(TestStep {
+                name: "failing-step",
+                fail: true,
+            });
+
+        let listener = Arc::new(RecordingListener::default());
+        let saga = saga.with_listener(listener.clone());
+        assert!(saga.execute(Vec::new()).await.is_err());
+
+        let events = listener.events.lock().unwrap();
+        assert_eq!(
+            *events,
+            vec![
+                "mixed:start:trait-step",
+                "mixed:start:closure-step",
+                "mixed:start:failing-step",
+                "mixed:fail:failing-step",
+                "mixed:compensated:closure-step",
+                "mixed:compensated:trait-step",
+                "mixed:done:false",
+            ]
+        );
+    }
+
+    struct FailingCompensationStep;
+
+    #[async_trait]
+    impl SagaStep<Vec<&'static str>> for FailingCompensationStep {
+        async fn execute(&self, context: &mut Vec<&'static str>) -> Result<(), SagaError> {
+            context.push("flaky");
+            Ok(())
+        }
+
+        async fn compensate(&self, _context: &mut Vec<&'static str>) -> Result<(), SagaError> {
+            Err(SagaError::CompensationFailed("refund api down".to_string()))
+        }
+
+        fn name(&self) -> &str {
+            "flaky"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_continue_and_collect_compensates_remaining_steps() {
+        let listener = Arc::new(RecordingListener::default());
+        let saga = SagaOrchestrator::named("checkout")
+            .with_listener(listener.clone())
+            .with_compensation_policy(CompensationPolicy::ContinueAndCollect)
+            .add_step(TestStep {
+                name: "reserve",
+                fail: false,
+            })
+            .add_step(FailingCompensationStep)
+            .add_step(TestStep {
+                name: "ship",
+                fail: true,
+            });
+
+        match saga.execute(Vec::new()).await {
+            Err(SagaError::PartialCompensation {
+                failed_steps,
+                source,
+            }) => {
+                assert_eq!(failed_steps.len(), 1);
+                assert_eq!(failed_steps[0].0, "flaky");
+                assert!(matches!(*source, SagaError::ExecutionFailed(ref s) if s == "ship"));
+            }
+            other => panic!("expected PartialCompensation, got {:?}", other.map(|_| ())),
+        }
+
+        // Step 1 is still compensated after step 2's compensation failed
+        let events = listener.events.lock().unwrap();
+        assert!(events.contains(&"checkout:compensated:flaky".to_string()));
+        assert!(events.contains(&"checkout:compensated:reserve".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_stop_on_first_error_skips_remaining_compensations() {
+        let listener = Arc::new(RecordingListener::default());
+        let saga = SagaOrchestrator::named("checkout")
+            .with_listener(listener.clone())
+            .add_step(TestStep {
+                name: "reserve",
+                fail: false,
+            })
+            .add_step(FailingCompensationStep)
+            .add_step(TestStep {
+                name: "ship",
+                fail: true,
+            });
+
+        assert!(matches!(
+            saga.execute(Vec::new()).await,
+            Err(SagaError::CompensationFailed(_))
+        ));
+
+        let events = listener.events.lock().unwrap();
+        assert!(!events.contains(&"checkout:compensated:reserve".to_string()));
+    }
 }
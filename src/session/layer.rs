@@ -0,0 +1,208 @@
+use crate::controller::extract::cookie;
+use crate::exception::HttpException;
+use crate::session::{Session, SessionConfig, SessionError, SessionRecord, SessionStore, new_id};
+use axum::http::HeaderValue;
+use axum::http::header::SET_COOKIE;
+use axum::response::IntoResponse;
+use axum::{body::Body, http::Request, response::Response};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, SystemTime};
+use tower::{Layer, Service};
+
+/// Tower layer loading each request's [`Session`] from its cookie, and
+/// saving it once the response is ready
+///
+/// [`SessionModule::layer`] builds it; [`Application::router`] adds it when
+/// [`ApplicationBuilder::sessions`] was given a module.
+///
+/// [`SessionModule::layer`]: crate::session::SessionModule::layer
+/// [`Application::router`]: crate::lifecycle::Application::router
+/// [`ApplicationBuilder::sessions`]: crate::lifecycle::ApplicationBuilder::sessions
+#[derive(Clone)]
+pub struct SessionLayer {
+    config: Arc<SessionConfig>,
+    store: Arc<dyn SessionStore>,
+}
+
+impl SessionLayer {
+    pub fn new(config: SessionConfig, store: Arc<dyn SessionStore>) -> Self {
+        Self {
+            config: Arc::new(config),
+            store,
+        }
+    }
+}
+
+impl<S> Layer<S> for SessionLayer {
+    type Service = SessionMiddleware<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        SessionMiddleware {
+            inner,
+            config: self.config.clone(),
+            store: self.store.clone(),
+        }
+    }
+}
+
+/// Service produced by [`SessionLayer`]
+#[derive(Clone)]
+pub struct SessionMiddleware<S> {
+    inner: S,
+    config: Arc<SessionConfig>,
+    store: Arc<dyn SessionStore>,
+}
+
+impl<S> Service<Request<Body>> for SessionMiddleware<S>
+where
+    S: Service<Request<Body>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        let mut inner = self.inner.clone();
+        let config = self.config.clone();
+        let store = self.store.clone();
+
+        Box::pin(async move {
+            let (mut parts, body) = request.into_parts();
+            let signed = cookie::<Option<String>>(&parts, &config.cookie_name)
+                .ok()
+                .flatten();
+            let id = signed.as_deref().and_then(|signed| config.verify(signed));
+            let session = match load(&*store, id).await {
+                Ok(Some((id, record))) => Session::new(Some(id.to_string()), record),
+                Ok(None) => Session::new(None, fresh_record(&config)),
+                Err(e) => {
+                    tracing::error!("Failed to load the session: {}", e);
+                    return Ok(HttpException::internal("session unavailable").into_response());
+                }
+            };
+            parts.extensions.insert(session.clone());
+
+            let response = inner.call(Request::from_parts(parts, body)).await?;
+            match save(&session, &config, &*store, signed.is_some()).await {
+                Ok(cookie) => Ok(with_cookie(response, cookie)),
+                Err(e) => {
+                    tracing::error!("Failed to save the session: {}", e);
+                    Ok(HttpException::internal("session unavailable").into_response())
+                }
+            }
+        })
+    }
+}
+
+/// The stored session `id`, unless it has expired
+async fn load<'a>(
+    store: &dyn SessionStore,
+    id: Option<&'a str>,
+) -> Result<Option<(&'a str, SessionRecord)>, SessionError> {
+    let Some(id) = id else {
+        return Ok(None);
+    };
+    Ok(store
+        .load(id)
+        .await?
+        .filter(|record| record.expires_at > SystemTime::now())
+        .map(|record| (id, record)))
+}
+
+fn fresh_record(config: &SessionConfig) -> SessionRecord {
+    SessionRecord {
+        data: Default::default(),
+        expires_at: SystemTime::now() + config.ttl,
+    }
+}
+
+/// Persist what the request did to `session`, returning the `Set-Cookie`
+/// value the response needs, if any
+///
+/// `had_cookie` says whether the request sent a session cookie, which an
+/// emptied session then expires.
+async fn save(
+    session: &Session,
+    config: &SessionConfig,
+    store: &dyn SessionStore,
+    had_cookie: bool,
+) -> Result<Option<String>, SessionError> {
+    let (id, record, replaced_id, is_new) = {
+        let mut state = session.state();
+        if state.data.is_empty() {
+            (state.id.take(), None, state.replaced_id.take(), false)
+        } else if state.changed || config.rolling {
+            let is_new = state.id.is_none();
+            let id = state.id.get_or_insert_with(new_id).clone();
+            if config.rolling {
+                state.expires_at = SystemTime::now() + config.ttl;
+            }
+            let record = SessionRecord {
+                data: state.data.clone(),
+                expires_at: state.expires_at,
+            };
+            (Some(id), Some(record), state.replaced_id.take(), is_new)
+        } else {
+            return Ok(None);
+        }
+    };
+
+    if let Some(replaced_id) = &replaced_id {
+        store.delete(replaced_id).await?;
+    }
+    let Some(record) = record else {
+        // Emptied: nothing is left to keep.
+        if let Some(id) = &id {
+            store.delete(id).await?;
+        }
+        return Ok(had_cookie.then(|| expired_cookie(config)));
+    };
+    let id = id.expect("a saved session has an id");
+    store.save(&id, &record).await?;
+
+    let cookie_changed = is_new || replaced_id.is_some() || config.rolling;
+    Ok(cookie_changed.then(|| session_cookie(config, &id, record.expires_at)))
+}
+
+/// The `Set-Cookie` value handing out the session `id`
+fn session_cookie(config: &SessionConfig, id: &str, expires_at: SystemTime) -> String {
+    let max_age = expires_at
+        .duration_since(SystemTime::now())
+        .unwrap_or(Duration::ZERO);
+    cookie_header(config, &config.sign(id), max_age.as_secs())
+}
+
+/// The `Set-Cookie` value making browsers forget the session
+fn expired_cookie(config: &SessionConfig) -> String {
+    cookie_header(config, "", 0)
+}
+
+fn cookie_header(config: &SessionConfig, value: &str, max_age: u64) -> String {
+    let mut cookie = format!(
+        "{}={}; Path={}; Max-Age={}; HttpOnly; SameSite={}",
+        config.cookie_name,
+        value,
+        config.path,
+        max_age,
+        config.same_site.as_str()
+    );
+    if config.secure {
+        cookie.push_str("; Secure");
+    }
+    cookie
+}
+
+fn with_cookie(mut response: Response, cookie: Option<String>) -> Response {
+    if let Some(cookie) = cookie.and_then(|cookie| HeaderValue::from_str(&cookie).ok()) {
+        response.headers_mut().append(SET_COOKIE, cookie);
+    }
+    response
+}
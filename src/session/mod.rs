@@ -0,0 +1,409 @@
+//! Cookie-based sessions
+//!
+//! [`ApplicationBuilder::sessions`] takes a [`SessionModule`]: the
+//! [`SessionConfig`] and the [`SessionStore`] keeping the data. Each request
+//! then carries a [`Session`], taken with `#[session]` in `#[routes]`
+//! handlers; the cookie only holds its signed id. Sessions are created when
+//! something is first inserted, and saved after the handler ran when they
+//! changed. Concurrent requests of one session each save what they saw, so
+//! the last one to finish wins.
+//!
+//! ```rust,ignore
+//! let app = Application::builder()
+//!     .module::<AppModule>()
+//!     .sessions(SessionModule::new(SessionConfig::new(secret)))
+//!     .build()
+//!     .await?;
+//!
+//! #[post("/login")]
+//! async fn login(&self, #[session] session: Session, #[body] login: Login) -> Result<()> {
+//!     let user = self.users.authenticate(&login).await?;
+//!     // A new id, so one planted before the login is worth nothing.
+//!     session.regenerate_id();
+//!     session.insert("user_id", user.id)?;
+//!     Ok(())
+//! }
+//! ```
+//!
+//! [`ApplicationBuilder::sessions`]: crate::lifecycle::ApplicationBuilder::sessions
+
+mod layer;
+mod store;
+
+pub use layer::{SessionLayer, SessionMiddleware};
+pub use store::{InMemorySessionStore, SessionData, SessionRecord, SessionStore};
+
+use crate::exception::HttpException;
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use sha2::Sha256;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, SystemTime};
+use thiserror::Error;
+
+/// Which sites' requests carry the session cookie
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SameSite {
+    /// Only requests from the site itself
+    Strict,
+    /// Also top-level navigations from other sites, the default
+    #[default]
+    Lax,
+    /// Every request; needs `secure`
+    None,
+}
+
+impl SameSite {
+    fn as_str(self) -> &'static str {
+        match self {
+            SameSite::Strict => "Strict",
+            SameSite::Lax => "Lax",
+            SameSite::None => "None",
+        }
+    }
+}
+
+/// How the session cookie is signed and sent, and how long sessions last
+#[derive(Clone)]
+pub struct SessionConfig {
+    /// Key signing the cookie, at least 32 bytes
+    secret: Vec<u8>,
+    /// `meshestra.sid` by default
+    pub cookie_name: String,
+    pub same_site: SameSite,
+    /// Only send the cookie over HTTPS (on by default)
+    pub secure: bool,
+    /// `/` by default
+    pub path: String,
+    /// How long a session lasts (a day by default)
+    pub ttl: Duration,
+    /// Restart the `ttl` on every request, rather than counting it from the
+    /// session's creation (off by default)
+    pub rolling: bool,
+}
+
+impl std::fmt::Debug for SessionConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SessionConfig")
+            .field("secret", &"<redacted>")
+            .field("cookie_name", &self.cookie_name)
+            .field("same_site", &self.same_site)
+            .field("secure", &self.secure)
+            .field("path", &self.path)
+            .field("ttl", &self.ttl)
+            .field("rolling", &self.rolling)
+            .finish()
+    }
+}
+
+impl SessionConfig {
+    /// Sessions signed with `secret`, at least 32 random bytes kept out of
+    /// the code
+    pub fn new(secret: impl Into<Vec<u8>>) -> Self {
+        Self {
+            secret: secret.into(),
+            cookie_name: "meshestra.sid".to_string(),
+            same_site: SameSite::default(),
+            secure: true,
+            path: "/".to_string(),
+            ttl: Duration::from_secs(24 * 60 * 60),
+            rolling: false,
+        }
+    }
+
+    pub fn cookie_name(mut self, name: impl Into<String>) -> Self {
+        self.cookie_name = name.into();
+        self
+    }
+
+    pub fn same_site(mut self, same_site: SameSite) -> Self {
+        self.same_site = same_site;
+        self
+    }
+
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    pub fn rolling(mut self, rolling: bool) -> Self {
+        self.rolling = rolling;
+        self
+    }
+
+    /// Refuse settings that would leave sessions open to forgery
+    ///
+    /// # Errors
+    /// Returns an error if the secret is shorter than 32 bytes, or
+    /// `SameSite=None` is asked for without `secure`.
+    pub fn validate(&self) -> Result<(), SessionError> {
+        if self.secret.len() < 32 {
+            return Err(SessionError::WeakSecret);
+        }
+        if self.same_site == SameSite::None && !self.secure {
+            return Err(SessionError::InsecureSameSiteNone);
+        }
+        Ok(())
+    }
+
+    /// `id` with its signature, as the cookie holds it
+    fn sign(&self, id: &str) -> String {
+        let mut mac = self.mac();
+        mac.update(id.as_bytes());
+        format!("{}.{}", id, hex(&mac.finalize().into_bytes()))
+    }
+
+    /// The id a cookie holds, if its signature is ours
+    fn verify<'a>(&self, value: &'a str) -> Option<&'a str> {
+        let (id, signature) = value.rsplit_once('.')?;
+        let signature = from_hex(signature)?;
+        let mut mac = self.mac();
+        mac.update(id.as_bytes());
+        mac.verify_slice(&signature).ok()?;
+        Some(id)
+    }
+
+    fn mac(&self) -> Hmac<Sha256> {
+        Hmac::new_from_slice(&self.secret).expect("HMAC takes keys of any length")
+    }
+}
+
+/// The session configuration and store [`ApplicationBuilder::sessions`]
+/// installs
+///
+/// [`ApplicationBuilder::sessions`]: crate::lifecycle::ApplicationBuilder::sessions
+#[derive(Clone)]
+pub struct SessionModule {
+    config: SessionConfig,
+    store: Arc<dyn SessionStore>,
+}
+
+impl SessionModule {
+    /// Sessions kept in memory, see [`InMemorySessionStore`]
+    pub fn new(config: SessionConfig) -> Self {
+        Self {
+            config,
+            store: Arc::new(InMemorySessionStore::new()),
+        }
+    }
+
+    /// Keep the sessions in `store` instead
+    pub fn store(mut self, store: impl SessionStore) -> Self {
+        self.store = Arc::new(store);
+        self
+    }
+
+    pub fn config(&self) -> &SessionConfig {
+        &self.config
+    }
+
+    /// Layer giving each request its [`Session`]
+    pub fn layer(&self) -> SessionLayer {
+        SessionLayer::new(self.config.clone(), self.store.clone())
+    }
+}
+
+/// The session of a request, for `#[session]` parameters
+///
+/// Clones share the session. Values are stored as JSON.
+#[derive(Clone)]
+pub struct Session {
+    state: Arc<Mutex<SessionState>>,
+}
+
+/// What a request did to its session
+struct SessionState {
+    /// `None` until a new session is saved
+    id: Option<String>,
+    data: SessionData,
+    expires_at: SystemTime,
+    changed: bool,
+    /// The id `regenerate_id` replaced, to delete from the store
+    replaced_id: Option<String>,
+}
+
+impl Session {
+    fn new(id: Option<String>, record: SessionRecord) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(SessionState {
+                id,
+                data: record.data,
+                expires_at: record.expires_at,
+                changed: false,
+                replaced_id: None,
+            })),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, SessionState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// The session's id; `None` for a session not saved yet
+    pub fn id(&self) -> Option<String> {
+        self.state().id.clone()
+    }
+
+    /// The value of `key`; `None` if there is none or it isn't a `T`
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let value = self.state().data.get(key)?.clone();
+        serde_json::from_value(value).ok()
+    }
+
+    /// Set `key` to `value`
+    ///
+    /// # Errors
+    /// Returns an error if `value` can't be serialized as JSON.
+    pub fn insert<T: Serialize>(&self, key: &str, value: T) -> Result<(), SessionError> {
+        let value = serde_json::to_value(value)?;
+        let mut state = self.state();
+        state.data.insert(key.to_string(), value);
+        state.changed = true;
+        Ok(())
+    }
+
+    /// Remove `key`, returning whether it was set
+    pub fn remove(&self, key: &str) -> bool {
+        let mut state = self.state();
+        let removed = state.data.remove(key).is_some();
+        state.changed |= removed;
+        removed
+    }
+
+    /// Remove every value; the emptied session is deleted from the store
+    pub fn clear(&self) {
+        let mut state = self.state();
+        state.data.clear();
+        state.changed = true;
+    }
+
+    /// Move the session to a new id, deleting the old one
+    ///
+    /// Call it when the user's privileges change, on login in particular,
+    /// so an id an attacker planted in the browser before doesn't carry
+    /// over (session fixation). The CSRF token is dropped too; the next
+    /// [`CsrfToken`](crate::csrf::CsrfToken) is a new one.
+    pub fn regenerate_id(&self) {
+        let mut state = self.state();
+        // A session not saved yet gets a new id, and its cookie, on saving.
+        if let Some(id) = state.id.take() {
+            state.replaced_id.get_or_insert(id);
+            state.id = Some(new_id());
+        }
+        state.data.remove(CSRF_TOKEN_KEY);
+        state.changed = true;
+    }
+
+    /// The session's CSRF token, created on first use
+    pub(crate) fn csrf_token(&self) -> String {
+        let mut state = self.state();
+        if let Some(token) = state
+            .data
+            .get(CSRF_TOKEN_KEY)
+            .and_then(|token| token.as_str())
+        {
+            return token.to_string();
+        }
+        let token = new_id();
+        state
+            .data
+            .insert(CSRF_TOKEN_KEY.to_string(), token.clone().into());
+        state.changed = true;
+        token
+    }
+
+    /// The `Session` the [`SessionLayer`] put in the request; generated
+    /// `#[session]` parameters call this
+    #[doc(hidden)]
+    pub fn from_parts(parts: &Parts) -> Result<Session, HttpException> {
+        parts.extensions.get::<Session>().cloned().ok_or_else(|| {
+            HttpException::internal(
+                "#[session] needs the session layer, see ApplicationBuilder::sessions",
+            )
+        })
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for Session {
+    type Rejection = HttpException;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Session::from_parts(parts)
+    }
+}
+
+/// A session configuration that isn't safe, or a store failure
+#[derive(Debug, Error)]
+pub enum SessionError {
+    #[error("the session secret must be at least 32 bytes")]
+    WeakSecret,
+
+    #[error("SameSite=None session cookies must be secure")]
+    InsecureSameSiteNone,
+
+    #[error("session value can't be serialized: {0}")]
+    Serialization(#[from] serde_json::Error),
+
+    #[error("session store error: {0}")]
+    Store(String),
+}
+
+/// The session value holding the CSRF token
+pub(crate) const CSRF_TOKEN_KEY: &str = "_csrf";
+
+/// A fresh, unguessable session id
+pub(crate) fn new_id() -> String {
+    hex(&rand::random::<[u8; 32]>())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> SessionConfig {
+        SessionConfig::new([7u8; 32])
+    }
+
+    #[test]
+    fn only_our_signatures_are_accepted() {
+        let signed = config().sign("abc");
+
+        assert_eq!(config().verify(&signed), Some("abc"));
+        assert_eq!(config().verify(&signed.replace("abc", "abd")), None);
+        assert_eq!(SessionConfig::new([8u8; 32]).verify(&signed), None);
+        assert_eq!(config().verify("abc"), None);
+    }
+
+    #[test]
+    fn short_secrets_are_refused() {
+        assert!(matches!(
+            SessionConfig::new("secret").validate(),
+            Err(SessionError::WeakSecret)
+        ));
+        assert!(config().validate().is_ok());
+    }
+}
@@ -0,0 +1,118 @@
+use crate::session::SessionError;
+use async_trait::async_trait;
+use dashmap::DashMap;
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+/// The values of a session, by key
+pub type SessionData = HashMap<String, serde_json::Value>;
+
+/// A session as it is stored
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionRecord {
+    pub data: SessionData,
+    /// When the session ends; stores may drop it from then on
+    pub expires_at: SystemTime,
+}
+
+/// Where sessions are kept between requests
+///
+/// A store shared by several servers, such as Redis, keeps each record
+/// under its id and lets it expire on its own:
+///
+/// ```rust,ignore
+/// pub struct RedisSessionStore {
+///     client: redis::Client,
+/// }
+///
+/// #[async_trait]
+/// impl SessionStore for RedisSessionStore {
+///     async fn load(&self, id: &str) -> Result<Option<SessionRecord>, SessionError> {
+///         let mut redis = self.connection().await?;
+///         let json: Option<String> = redis.get(format!("session:{}", id)).await.map_err(store_error)?;
+///         json.map(|json| Ok(SessionRecord {
+///             data: serde_json::from_str(&json)?,
+///             expires_at: /* from the key's TTL */,
+///         }))
+///         .transpose()
+///     }
+///
+///     async fn save(&self, id: &str, record: &SessionRecord) -> Result<(), SessionError> {
+///         let ttl = record.expires_at.duration_since(SystemTime::now()).unwrap_or_default();
+///         let json = serde_json::to_string(&record.data)?;
+///         let mut redis = self.connection().await?;
+///         redis.set_ex(format!("session:{}", id), json, ttl.as_secs().max(1)).await.map_err(store_error)
+///     }
+///
+///     async fn delete(&self, id: &str) -> Result<(), SessionError> {
+///         let mut redis = self.connection().await?;
+///         redis.del(format!("session:{}", id)).await.map_err(store_error)
+///     }
+/// }
+///
+/// fn store_error(error: redis::RedisError) -> SessionError {
+///     SessionError::Store(error.to_string())
+/// }
+/// ```
+#[async_trait]
+pub trait SessionStore: Send + Sync + 'static {
+    /// The session `id`, if it exists; expired ones may be returned, the
+    /// caller checks `expires_at`
+    async fn load(&self, id: &str) -> Result<Option<SessionRecord>, SessionError>;
+
+    /// Create or replace the session `id`
+    async fn save(&self, id: &str, record: &SessionRecord) -> Result<(), SessionError>;
+
+    async fn delete(&self, id: &str) -> Result<(), SessionError>;
+}
+
+/// Sessions kept in the server's memory, lost on restart and not shared
+/// between servers
+///
+/// Expired sessions are dropped when they are next loaded.
+#[derive(Default)]
+pub struct InMemorySessionStore {
+    sessions: DashMap<String, SessionRecord>,
+}
+
+impl InMemorySessionStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How many sessions are stored, expired ones included
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+}
+
+#[async_trait]
+impl SessionStore for InMemorySessionStore {
+    async fn load(&self, id: &str) -> Result<Option<SessionRecord>, SessionError> {
+        let expired = match self.sessions.get(id) {
+            Some(record) if record.expires_at > SystemTime::now() => {
+                return Ok(Some(record.clone()));
+            }
+            Some(_) => true,
+            None => false,
+        };
+        if expired {
+            self.sessions.remove(id);
+        }
+        Ok(None)
+    }
+
+    async fn save(&self, id: &str, record: &SessionRecord) -> Result<(), SessionError> {
+        self.sessions.insert(id.to_string(), record.clone());
+        Ok(())
+    }
+
+    async fn delete(&self, id: &str) -> Result<(), SessionError> {
+        self.sessions.remove(id);
+        Ok(())
+    }
+}
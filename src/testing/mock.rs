@@ -0,0 +1,114 @@
+use crate::di::Container;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// A mock that can stand in for the binding of a trait
+///
+/// Implemented by the mocks [`mock_trait!`](crate::testing::mock_trait)
+/// generates, and with [`mock_provider!`](crate::mock_provider) by any
+/// other, such as a `mockall` one.
+pub trait MockProvider: Send + Sync + 'static {
+    /// The trait object the mock is bound as, e.g. `dyn UserRepository`
+    type Trait: ?Sized + Send + Sync + 'static;
+
+    fn into_binding(mock: Arc<Self>) -> Arc<Self::Trait>;
+
+    /// Bind [`Self::Trait`] to this mock in `container`, in place of the
+    /// provider a module registers later, see [`Container::override_trait`]
+    fn override_in(self, container: &mut Container)
+    where
+        Self: Sized,
+    {
+        container.override_trait::<Self::Trait, Self, _>(self, Self::into_binding);
+    }
+}
+
+/// Implement [`MockProvider`] for a mock type written by hand or by a
+/// mocking library
+///
+/// ```rust,ignore
+/// #[mockall::automock]
+/// #[async_trait]
+/// pub trait UserRepository: Send + Sync {
+///     async fn find_by_id(&self, id: &str) -> Result<Option<User>>;
+/// }
+///
+/// mock_provider!(MockUserRepository => dyn UserRepository);
+///
+/// let mut repository = MockUserRepository::new();
+/// repository.expect_find_by_id().returning(|_| Ok(None));
+/// let app = TestApp::builder().mock(repository).build::<AppModule>().await?;
+/// ```
+#[macro_export]
+macro_rules! mock_provider {
+    ($mock:ty => $trait:ty) => {
+        impl $crate::testing::MockProvider for $mock {
+            type Trait = $trait;
+
+            fn into_binding(mock: ::std::sync::Arc<Self>) -> ::std::sync::Arc<$trait> {
+                mock
+            }
+        }
+    };
+}
+
+/// The calls made to a mock, counted by method
+///
+/// Clones share the counts, so a test can keep one while the mock itself is
+/// moved into the container.
+#[derive(Clone)]
+pub struct MockCalls {
+    mock: &'static str,
+    methods: &'static [&'static str],
+    counts: Arc<Mutex<HashMap<&'static str, usize>>>,
+}
+
+impl MockCalls {
+    /// The calls of the mock `mock`, which has the methods `methods`
+    pub fn new(mock: &'static str, methods: &'static [&'static str]) -> Self {
+        Self {
+            mock,
+            methods,
+            counts: Arc::default(),
+        }
+    }
+
+    /// Count a call of `method`
+    pub fn record(&self, method: &'static str) {
+        *self
+            .counts
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(method)
+            .or_default() += 1;
+    }
+
+    /// How many times `method` has been called
+    ///
+    /// # Panics
+    /// If the mock has no such method, so a typo doesn't pass as zero calls.
+    pub fn count(&self, method: &str) -> usize {
+        assert!(
+            self.methods.contains(&method),
+            "{} has no method `{}`",
+            self.mock,
+            method
+        );
+        self.counts
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(method)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Assert `method` has been called `times` times
+    pub fn assert_called(&self, method: &str, times: usize) {
+        let count = self.count(method);
+        assert_eq!(
+            count, times,
+            "expected {}::{} to be called {} time(s), but it was called {} time(s)",
+            self.mock, method, times, count
+        );
+    }
+}
@@ -0,0 +1,365 @@
+//! In-process integration tests of an application
+//!
+//! [`TestApp`] registers a module in a fresh container, builds the
+//! [`Application`] (running the init and bootstrap hooks) and serves its
+//! router without binding a port. Requests are sent with
+//! [`get`](TestApp::get), [`post`](TestApp::post), [`put`](TestApp::put)
+//! and [`delete`](TestApp::delete) and awaited for a [`TestResponse`]:
+//!
+//! ```rust,ignore
+//! #[tokio::test]
+//! async fn users_are_created() {
+//!     let app = TestApp::builder()
+//!         .override_trait::<dyn UserRepository, InMemoryUserRepository, _>(
+//!             InMemoryUserRepository::default(),
+//!             |repository| repository as Arc<dyn UserRepository>,
+//!         )
+//!         .build::<AppModule>()
+//!         .await
+//!         .unwrap();
+//!
+//!     let created = app.post("/users").json(&json!({ "name": "Ada" })).await;
+//!     assert_eq!(created.status(), StatusCode::CREATED);
+//!     let user: User = created.json();
+//!
+//!     let fetched = app.get(&format!("/users/{}", user.id)).await;
+//!     assert_eq!(fetched.json::<User>().name, "Ada");
+//! }
+//! ```
+//!
+//! Dropping the `TestApp` runs the destroy hooks.
+//!
+//! Tests against a real database can run in a transaction that is rolled
+//! back at the end with [`TransactionalTest`] or
+//! `#[meshestra::test(transactional)]`.
+//!
+//! Trait bindings are replaced by mocks generated with [`mock_trait!`]:
+//!
+//! ```rust,ignore
+//! mock_trait! {
+//!     pub MockUserRepository: UserRepository {
+//!         async fn find_by_id(&self, id: &str) -> Result<Option<User>>;
+//!         async fn save(&self, user: User) -> Result<User>;
+//!     }
+//! }
+//!
+//! let repository = MockUserRepository::new().on_find_by_id(|_| Ok(None));
+//! let calls = repository.calls();
+//! let app = TestApp::builder().mock(repository).build::<AppModule>().await?;
+//!
+//! assert_eq!(app.get("/users/1").await.status(), StatusCode::NOT_FOUND);
+//! calls.assert_called("find_by_id", 1);
+//! ```
+//!
+//! The order guards, interceptors, aspects and handlers run in is recorded
+//! by an [`ExecutionTrace`], with the [`TraceGuard`], [`TraceInterceptor`]
+//! and [`TraceAspect`] of a [`TraceLabel`]:
+//!
+//! ```rust,ignore
+//! let trace = ExecutionTrace::new();
+//! let app = TestApp::builder()
+//!     .provide(trace.clone())
+//!     .provide(TraceGuard::<Auth>::new(trace.clone()))
+//!     .provide(TraceAspect::<Audit>::new(trace.clone()))
+//!     .build::<AppModule>()
+//!     .await?;
+//!
+//! app.get("/articles").await;
+//! trace.assert_order(&["auth", "audit", "handler", "audit"]);
+//! ```
+
+mod mock;
+mod response;
+mod trace;
+mod transactional;
+
+pub use crate::mock_provider;
+pub use meshestra_macro::mock_trait;
+pub use mock::{MockCalls, MockProvider};
+pub use response::TestResponse;
+pub use trace::{ExecutionTrace, TraceAspect, TraceGuard, TraceInterceptor, TraceLabel};
+pub use transactional::TransactionalTest;
+
+use crate::di::{Container, HasContainer};
+use crate::lifecycle::{Application, ApplicationBuilder, LifecycleError, Result};
+use crate::module::Module;
+use axum::Router;
+use axum::body::Body;
+use axum::http::header::CONTENT_TYPE;
+use axum::http::{HeaderValue, Method, Request};
+use serde::Serialize;
+use std::future::{Future, IntoFuture};
+use std::pin::Pin;
+use std::sync::Arc;
+use tower::ServiceExt;
+
+/// The state of the router a [`TestApp`] serves
+#[derive(Clone)]
+struct TestState {
+    container: Arc<Container>,
+}
+
+impl HasContainer for TestState {
+    fn get_container(&self) -> &Container {
+        &self.container
+    }
+}
+
+type Configure = Box<dyn FnOnce(ApplicationBuilder) -> ApplicationBuilder + Send>;
+
+/// An application built from a module, answering requests in-process
+///
+/// The router is [`Application::router`], inside the global interceptors
+/// and exception filters.
+pub struct TestApp {
+    app: Application,
+    router: Router,
+    shut_down: bool,
+}
+
+impl TestApp {
+    pub fn builder() -> TestAppBuilder {
+        TestAppBuilder::new()
+    }
+
+    /// The application of the module `M`, without overrides
+    pub async fn from_module<M: Module>() -> Result<Self> {
+        Self::builder().build::<M>().await
+    }
+
+    pub fn application(&self) -> &Application {
+        &self.app
+    }
+
+    pub fn container(&self) -> &Arc<Container> {
+        self.app.container()
+    }
+
+    pub fn get(&self, path: &str) -> TestRequest {
+        self.request(Method::GET, path)
+    }
+
+    pub fn post(&self, path: &str) -> TestRequest {
+        self.request(Method::POST, path)
+    }
+
+    pub fn put(&self, path: &str) -> TestRequest {
+        self.request(Method::PUT, path)
+    }
+
+    pub fn patch(&self, path: &str) -> TestRequest {
+        self.request(Method::PATCH, path)
+    }
+
+    pub fn delete(&self, path: &str) -> TestRequest {
+        self.request(Method::DELETE, path)
+    }
+
+    /// A `method` request to `path`, sent when awaited
+    pub fn request(&self, method: Method, path: &str) -> TestRequest {
+        TestRequest {
+            router: self.router.clone(),
+            request: Request::builder().method(method).uri(path),
+            body: Body::empty(),
+        }
+    }
+
+    /// Run the shutdown and destroy hooks now, rather than the destroy hooks
+    /// on drop, and see how they went
+    pub async fn shutdown(mut self) -> Result<()> {
+        self.shut_down = true;
+        self.app.shutdown().await
+    }
+}
+
+impl Drop for TestApp {
+    fn drop(&mut self) {
+        if self.shut_down {
+            return;
+        }
+        // Drop can't await, nor block the test's runtime, which may have a
+        // single thread: the hooks run on a runtime of their own.
+        let lifecycle = self.app.lifecycle_manager().clone();
+        let destroyed = std::thread::spawn(move || {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .map_err(|e| LifecycleError::shutdown_failed(e.to_string()))?
+                .block_on(lifecycle.call_module_destroy())
+        })
+        .join();
+        match destroyed {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => {
+                tracing::error!(error = %e, "Destroy hooks of the test application failed")
+            }
+            Err(_) => tracing::error!("Destroy hooks of the test application panicked"),
+        }
+    }
+}
+
+/// Builds a [`TestApp`], with providers replaced before the module is
+/// registered
+pub struct TestAppBuilder {
+    container: Container,
+    configure: Vec<Configure>,
+}
+
+impl Default for TestAppBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TestAppBuilder {
+    pub fn new() -> Self {
+        Self {
+            container: Container::new(),
+            configure: Vec::new(),
+        }
+    }
+
+    /// Register `instance` before the module, for its providers to inject
+    pub fn provide<T: 'static + Send + Sync>(mut self, instance: T) -> Self {
+        self.container.register(instance);
+        self
+    }
+
+    /// Use `instance` as the `T` in place of the module's provider, see
+    /// [`Container::override_with`]
+    pub fn override_provider<T: 'static + Send + Sync>(mut self, instance: T) -> Self {
+        self.container.override_with(instance);
+        self
+    }
+
+    /// Bind the trait `Trait` to `instance` in place of the module's
+    /// binding, see [`Container::override_trait`]
+    pub fn override_trait<Trait, Impl, F>(mut self, instance: Impl, caster_fn: F) -> Self
+    where
+        Trait: ?Sized + 'static + Send + Sync,
+        Impl: 'static + Send + Sync,
+        F: Fn(Arc<Impl>) -> Arc<Trait> + 'static + Send + Sync,
+    {
+        self.container
+            .override_trait::<Trait, Impl, F>(instance, caster_fn);
+        self
+    }
+
+    /// Bind the trait of `mock` to it in place of the module's binding
+    pub fn mock<M: MockProvider>(mut self, mock: M) -> Self {
+        mock.override_in(&mut self.container);
+        self
+    }
+
+    /// Configure the [`ApplicationBuilder`]: global interceptors, lifecycle
+    /// hooks, CORS, ...
+    ///
+    /// ```rust,ignore
+    /// let app = TestApp::builder()
+    ///     .configure(|builder| builder.global_interceptor::<LoggingInterceptor>())
+    ///     .build::<AppModule>()
+    ///     .await?;
+    /// ```
+    pub fn configure(
+        mut self,
+        configure: impl FnOnce(ApplicationBuilder) -> ApplicationBuilder + Send + 'static,
+    ) -> Self {
+        self.configure.push(Box::new(configure));
+        self
+    }
+
+    /// Register the module `M`, build the application and its router
+    pub async fn build<M: Module>(self) -> Result<TestApp> {
+        let mut container = self.container;
+        M::register(&mut container).map_err(|e| LifecycleError::init_failed(e.to_string()))?;
+        let builder = Application::builder().container(container).module::<M>();
+        let app = self
+            .configure
+            .into_iter()
+            .fold(builder, |builder, configure| configure(builder))
+            .build()
+            .await?;
+
+        let state = TestState {
+            container: app.container().clone(),
+        };
+        let router = app
+            .router::<M, TestState>()
+            .map_err(|e| LifecycleError::init_failed(e.to_string()))?
+            .layer(app.interceptor_layer())
+            .layer(app.exception_filter_layer())
+            .layer(app.error_body_layer())
+            .with_state(state);
+        Ok(TestApp {
+            app,
+            router,
+            shut_down: false,
+        })
+    }
+}
+
+/// A request of a [`TestApp`], sent when awaited
+///
+/// # Panics
+///
+/// Awaiting panics if a header set on the request is invalid.
+pub struct TestRequest {
+    router: Router,
+    request: axum::http::request::Builder,
+    body: Body,
+}
+
+impl TestRequest {
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.request = self.request.header(name, value);
+        self
+    }
+
+    /// Send `body` as JSON
+    pub fn json<T: Serialize + ?Sized>(mut self, body: &T) -> Self {
+        let json = serde_json::to_vec(body).expect("the request body serializes to JSON");
+        self.body = Body::from(json);
+        self.content_type("application/json")
+    }
+
+    /// Send `body` as `application/x-www-form-urlencoded`
+    pub fn form(mut self, body: &str) -> Self {
+        self.body = Body::from(body.to_string());
+        self.content_type("application/x-www-form-urlencoded")
+    }
+
+    /// Send `body` as is
+    pub fn body(mut self, body: impl Into<Body>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    fn content_type(mut self, content_type: &'static str) -> Self {
+        if let Some(headers) = self.request.headers_mut() {
+            headers.insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+        }
+        self
+    }
+
+    async fn send(self) -> TestResponse {
+        let request = self
+            .request
+            .body(self.body)
+            .expect("the test request is valid");
+        let response = self
+            .router
+            .oneshot(request)
+            .await
+            .unwrap_or_else(|e| match e {});
+        TestResponse::read(response).await
+    }
+}
+
+impl IntoFuture for TestRequest {
+    type Output = TestResponse;
+    type IntoFuture = Pin<Box<dyn Future<Output = TestResponse> + Send>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(self.send())
+    }
+}
@@ -0,0 +1,68 @@
+use axum::body::Bytes;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::Response;
+use serde::de::DeserializeOwned;
+
+/// The response to a [`TestRequest`](super::TestRequest), its body read
+///
+/// The accessors panic on what a test would fail on anyway: a body that
+/// isn't the expected JSON or UTF-8.
+#[derive(Debug)]
+pub struct TestResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+impl TestResponse {
+    pub(crate) async fn read(response: Response) -> Self {
+        let (parts, body) = response.into_parts();
+        let body = axum::body::to_bytes(body, usize::MAX)
+            .await
+            .expect("the response body can be read");
+        Self {
+            status: parts.status,
+            headers: parts.headers,
+            body,
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// The value of the header `name`, if set and visible ASCII
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).and_then(|value| value.to_str().ok())
+    }
+
+    pub fn bytes(&self) -> &Bytes {
+        &self.body
+    }
+
+    /// # Panics
+    ///
+    /// If the body isn't UTF-8.
+    pub fn text(&self) -> &str {
+        std::str::from_utf8(&self.body).expect("the response body is UTF-8")
+    }
+
+    /// The body as JSON
+    ///
+    /// # Panics
+    ///
+    /// If the body isn't a `T`, showing the body.
+    pub fn json<T: DeserializeOwned>(&self) -> T {
+        serde_json::from_slice(&self.body).unwrap_or_else(|e| {
+            panic!(
+                "the response body is not the expected JSON ({}): {}",
+                e,
+                String::from_utf8_lossy(&self.body)
+            )
+        })
+    }
+}
@@ -0,0 +1,172 @@
+use crate::aspect::{Aspect, AspectResult};
+use crate::di::{Container, Dependency, Injectable};
+use crate::guard::{Guard, GuardContext, GuardResult};
+use crate::interceptor::{Interceptor, InterceptorResult, Next};
+use async_trait::async_trait;
+use axum::body::Body;
+use axum::http::Request;
+use axum::response::Response;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// What ran while handling requests, in order
+///
+/// Clones share the record, so a test can keep one while another is
+/// registered in the container for the [`TraceGuard`], [`TraceAspect`] and
+/// [`TraceInterceptor`] types and the handlers to record into.
+///
+/// ```rust,ignore
+/// struct Outer;
+/// impl TraceLabel for Outer {
+///     const LABEL: &'static str = "outer";
+/// }
+///
+/// #[controller(path = "/")]
+/// #[aspect(TraceAspect<Outer>)]
+/// pub struct PingController {
+///     trace: Arc<ExecutionTrace>,
+/// }
+///
+/// let trace = ExecutionTrace::new();
+/// let app = TestApp::builder()
+///     .provide(trace.clone())
+///     .provide(TraceAspect::<Outer>::new(trace.clone()))
+///     .build::<AppModule>()
+///     .await?;
+/// app.get("/ping").await;
+/// trace.assert_order(&["outer", "handler", "outer"]);
+/// ```
+#[derive(Clone, Default)]
+pub struct ExecutionTrace {
+    steps: Arc<Mutex<Vec<&'static str>>>,
+}
+
+impl ExecutionTrace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that `step` ran
+    pub fn record(&self, step: &'static str) {
+        self.steps
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(step);
+    }
+
+    /// The steps recorded so far
+    pub fn steps(&self) -> Vec<&'static str> {
+        self.steps
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Forget the steps recorded so far
+    pub fn clear(&self) {
+        self.steps
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+    }
+
+    /// Assert the steps recorded so far are `expected`
+    pub fn assert_order(&self, expected: &[&str]) {
+        let steps = self.steps();
+        assert_eq!(
+            steps, expected,
+            "expected the steps {:?}, but they were {:?}",
+            expected, steps
+        );
+    }
+}
+
+impl Injectable for ExecutionTrace {
+    fn inject(container: &Container) -> crate::error::Result<Self> {
+        Ok(container.resolve::<ExecutionTrace>()?.as_ref().clone())
+    }
+}
+
+/// What the [`TraceGuard`], [`TraceAspect`] and [`TraceInterceptor`] of a
+/// type record, e.g. `"auth"`
+pub trait TraceLabel: Send + Sync + 'static {
+    const LABEL: &'static str;
+}
+
+/// A guard recording its label in the [`ExecutionTrace`] and letting the
+/// request through
+pub struct TraceGuard<L> {
+    trace: ExecutionTrace,
+    label: PhantomData<L>,
+}
+
+/// An aspect recording its label in the [`ExecutionTrace`] before and after
+/// the rest of the chain, so the labels of nested aspects read inside out
+pub struct TraceAspect<L> {
+    trace: ExecutionTrace,
+    label: PhantomData<L>,
+}
+
+/// An interceptor recording its label in the [`ExecutionTrace`] before and
+/// after the rest of the chain, like [`TraceAspect`]
+pub struct TraceInterceptor<L> {
+    trace: ExecutionTrace,
+    label: PhantomData<L>,
+}
+
+macro_rules! impl_traced {
+    ($($traced:ident),*) => {$(
+        impl<L: TraceLabel> $traced<L> {
+            /// Record into `trace`
+            pub fn new(trace: ExecutionTrace) -> Self {
+                Self {
+                    trace,
+                    label: PhantomData,
+                }
+            }
+        }
+
+        impl<L: TraceLabel> Injectable for $traced<L> {
+            fn inject(container: &Container) -> crate::error::Result<Self> {
+                ExecutionTrace::inject(container).map(Self::new)
+            }
+
+            fn dependencies() -> Vec<Dependency> {
+                vec![Dependency::of::<ExecutionTrace>()]
+            }
+        }
+    )*};
+}
+
+impl_traced!(TraceGuard, TraceAspect, TraceInterceptor);
+
+#[async_trait]
+impl<L: TraceLabel> Guard for TraceGuard<L> {
+    async fn can_activate(&self, _ctx: &GuardContext<'_>) -> GuardResult {
+        self.trace.record(L::LABEL);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<L: TraceLabel> Aspect for TraceAspect<L> {
+    async fn before(&self, _request: &mut Request<Body>) -> AspectResult {
+        self.trace.record(L::LABEL);
+        Ok(())
+    }
+
+    async fn after(&self, _response: &mut Response) -> AspectResult {
+        self.trace.record(L::LABEL);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<L: TraceLabel> Interceptor for TraceInterceptor<L> {
+    async fn intercept(&self, request: Request<Body>, next: Next) -> InterceptorResult {
+        self.trace.record(L::LABEL);
+        let response = next.run(request).await;
+        self.trace.record(L::LABEL);
+        response
+    }
+}
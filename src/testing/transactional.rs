@@ -0,0 +1,85 @@
+use crate::error::MeshestraError;
+use crate::transactional::{
+    ACTIVE_TRANSACTION, TransactionContext, TransactionManager, TransactionOptions, with_manager,
+};
+use futures_util::FutureExt;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Runs a test inside a transaction that is always rolled back
+///
+/// The transaction is begun on the given manager, which is also the ambient
+/// one while the test runs, and is marked rollback-only from the start:
+/// `#[transactional]` code under test joins it (or takes savepoints in it,
+/// with `Nested`) instead of committing, and whatever the test wrote is gone
+/// once it returns, fails or panics. `on_rollback` callbacks run afterwards;
+/// `on_commit` ones never do.
+///
+/// # Limitations
+/// The transaction is task-local. Work the test hands to another task, with
+/// `tokio::spawn`, a [`WorkerPool`](crate::worker::WorkerPool) or a server it
+/// sends requests to over the network, doesn't see it and commits on its own
+/// connection, as does `#[transactional(propagation = RequiresNew)]` code.
+/// Requests sent in-process with [`TestApp`](crate::testing::TestApp) run on
+/// the test's task and do join it, unless a `TransactionalInterceptor` begins
+/// a transaction of its own.
+///
+/// # Example
+/// ```rust,ignore
+/// #[tokio::test]
+/// async fn orders_are_placed() {
+///     TransactionalTest::wrap(manager(), async {
+///         let order = service.place_order(cart).await.unwrap();
+///         assert_eq!(repository.find(order.id).await.unwrap(), Some(order));
+///     })
+///     .await
+///     .unwrap();
+/// }
+/// ```
+///
+/// `#[meshestra::test(transactional)]` does the same with the global
+/// transaction manager.
+pub struct TransactionalTest;
+
+impl TransactionalTest {
+    /// Run `test` in a transaction of `manager`, then roll it back
+    ///
+    /// # Errors
+    /// If the transaction can't be begun, in which case `test` doesn't run,
+    /// or can't be rolled back.
+    ///
+    /// # Panics
+    /// If `test` panics, once the transaction is rolled back.
+    pub async fn wrap<F: Future>(
+        manager: Arc<dyn TransactionManager>,
+        test: F,
+    ) -> Result<F::Output, MeshestraError> {
+        let tx = manager.begin(TransactionOptions::default()).await?;
+        let tx = Arc::new(Mutex::new(tx));
+        let context = Arc::new(TransactionContext::new(None));
+        context.set_rollback_only();
+
+        let outcome = with_manager(
+            manager,
+            ACTIVE_TRANSACTION.scope(
+                Some(Arc::clone(&tx)),
+                context.scope(AssertUnwindSafe(test).catch_unwind()),
+            ),
+        )
+        .await;
+
+        let rolled_back = tx.lock().await.rollback().await;
+        context.take_callbacks().run_rolled_back().await;
+        match outcome {
+            Ok(output) => rolled_back.map(|()| output),
+            Err(panic) => {
+                if let Err(e) = rolled_back {
+                    tracing::error!("Failed to roll back the test transaction: {}", e);
+                }
+                std::panic::resume_unwind(panic)
+            }
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use crate::transactional::{Transaction, get_current_transaction};
+
+/// Run `f` on the active transaction, downcast to the concrete type `T`
+///
+/// Locks the transaction for the duration of `f`. Returns `None` when no
+/// transaction is active, so callers can fall back to a plain connection.
+///
+/// # Panics
+/// If the active transaction is not a `T`; the message names both types.
+///
+/// # Example
+/// ```rust,ignore
+/// let count = with_current_transaction(|tx: &mut MyTransaction| tx.statements_run());
+/// ```
+pub async fn with_current_transaction<T, R, F>(f: F) -> Option<R>
+where
+    T: Transaction + 'static,
+    F: FnOnce(&mut T) -> R,
+{
+    let tx = get_current_transaction()?;
+    let mut guard = tx.lock().await;
+    Some(f(downcast::<T>(&mut **guard)))
+}
+
+/// Like [`with_current_transaction`], for closures that `.await` while
+/// holding the transaction
+///
+/// # Panics
+/// If the active transaction is not a `T`; the message names both types.
+///
+/// # Example
+/// ```rust,ignore
+/// let query = user_entity::Entity::find_by_id(id);
+/// let user = match with_current_transaction_async(async |tx: &mut SeaOrmTransaction| {
+///     query.clone().one(tx.connection()?).await
+/// })
+/// .await
+/// {
+///     Some(result) => result?,
+///     None => query.one(&*self.db).await?,
+/// };
+/// ```
+pub async fn with_current_transaction_async<T, R, F>(f: F) -> Option<R>
+where
+    T: Transaction + 'static,
+    F: AsyncFnOnce(&mut T) -> R,
+{
+    let tx = get_current_transaction()?;
+    let mut guard = tx.lock().await;
+    Some(f(downcast::<T>(&mut **guard)).await)
+}
+
+fn downcast<T: Transaction + 'static>(tx: &mut dyn Transaction) -> &mut T {
+    let actual = tx.type_name();
+    tx.as_any_mut().downcast_mut::<T>().unwrap_or_else(|| {
+        panic!(
+            "The active transaction is a `{}`, not the expected `{}`",
+            actual,
+            std::any::type_name::<T>()
+        )
+    })
+}
@@ -0,0 +1,106 @@
+use crate::transactional::{TransactionError, TransactionManager};
+use dashmap::DashMap;
+use std::future::Future;
+use std::sync::{Arc, OnceLock};
+use tokio::task::futures::TaskLocalFuture;
+
+tokio::task_local! {
+    /// Transaction manager installed for the current task
+    static TASK_MANAGER: Arc<dyn TransactionManager>;
+
+    /// Named managers of the container serving the current task
+    static TASK_NAMED_MANAGERS: Arc<NamedManagers>;
+}
+
+static GLOBAL_MANAGER: OnceLock<Arc<dyn TransactionManager>> = OnceLock::new();
+
+/// Install the application-wide transaction manager
+///
+/// Used by `#[transactional]` on functions without a `self` receiver, or
+/// with `manager = ambient`. Returns the manager back if one was already
+/// installed.
+pub fn set_global_manager(
+    manager: Arc<dyn TransactionManager>,
+) -> Result<(), Arc<dyn TransactionManager>> {
+    GLOBAL_MANAGER.set(manager)
+}
+
+/// Run `fut` with `manager` as the ambient transaction manager
+///
+/// `TransactionalInterceptor` does this for the handlers it wraps.
+pub fn with_manager<F: Future>(
+    manager: Arc<dyn TransactionManager>,
+    fut: F,
+) -> TaskLocalFuture<Arc<dyn TransactionManager>, F> {
+    TASK_MANAGER.scope(manager, fut)
+}
+
+/// The ambient transaction manager: the task's one if installed with
+/// [`with_manager`], otherwise the global one
+///
+/// # Errors
+/// Returns [`TransactionError::NoTransactionManager`] if neither is set.
+pub fn current_manager() -> Result<Arc<dyn TransactionManager>, TransactionError> {
+    TASK_MANAGER
+        .try_with(Arc::clone)
+        .ok()
+        .or_else(|| GLOBAL_MANAGER.get().cloned())
+        .ok_or(TransactionError::NoTransactionManager)
+}
+
+/// Transaction managers by name, for applications with more than one
+/// datasource
+///
+/// A container holds them as a provider, filled by
+/// [`Container::register_named_manager`](crate::di::Container::register_named_manager).
+/// `Application::router` installs them for each request with
+/// [`with_named_managers`], where `#[transactional(manager_name = "...")]`
+/// finds them.
+#[derive(Default)]
+pub struct NamedManagers {
+    managers: DashMap<Arc<str>, Arc<dyn TransactionManager>>,
+}
+
+impl NamedManagers {
+    /// Register `manager` under `name`, replacing any manager of that name
+    pub fn insert(&self, name: impl Into<Arc<str>>, manager: Arc<dyn TransactionManager>) {
+        self.managers.insert(name.into(), manager);
+    }
+
+    /// The transaction manager registered under `name`
+    ///
+    /// # Errors
+    /// Returns [`TransactionError::UnknownManager`] if no manager has that name.
+    pub fn get(&self, name: &str) -> Result<Arc<dyn TransactionManager>, TransactionError> {
+        self.managers
+            .get(name)
+            .map(|manager| Arc::clone(&manager))
+            .ok_or_else(|| TransactionError::UnknownManager {
+                name: name.to_string(),
+            })
+    }
+}
+
+/// Run `fut` with `managers` as the named managers of the task
+pub fn with_named_managers<F: Future>(
+    managers: Arc<NamedManagers>,
+    fut: F,
+) -> TaskLocalFuture<Arc<NamedManagers>, F> {
+    TASK_NAMED_MANAGERS.scope(managers, fut)
+}
+
+/// The transaction manager registered under `name` with the task's
+/// [`NamedManagers`]
+///
+/// # Errors
+/// Returns [`TransactionError::UnknownManager`] if no manager has that name,
+/// or the task runs without named managers.
+pub fn named_manager(name: &str) -> Result<Arc<dyn TransactionManager>, TransactionError> {
+    TASK_NAMED_MANAGERS
+        .try_with(|managers| managers.get(name))
+        .unwrap_or_else(|_| {
+            Err(TransactionError::UnknownManager {
+                name: name.to_string(),
+            })
+        })
+}
@@ -0,0 +1,246 @@
+use crate::transactional::{TransactionError, transaction_in};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio::task::futures::TaskLocalFuture;
+
+/// Future returned by transaction callbacks
+pub type CallbackFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+type Callback = Box<dyn FnOnce() -> CallbackFuture + Send>;
+
+tokio::task_local! {
+    /// Context of the innermost transaction boundary
+    static CONTEXT: Arc<TransactionContext>;
+}
+
+/// State of a transaction boundary, shared with the code running inside it
+///
+/// Every place that begins a transaction (or a savepoint) scopes its own
+/// context. Scopes that join an existing transaction share the outer one:
+/// they raise its depth while they run, their callbacks wait for the
+/// outermost commit, and an error they return marks it rollback-only, so the
+/// outermost scope rolls back even if it catches the error and continues.
+pub struct TransactionContext {
+    /// Manager whose transaction this boundary finishes; `None` for the default
+    manager_name: Option<Arc<str>>,
+    depth: AtomicUsize,
+    rollback_only: AtomicBool,
+    callbacks: Mutex<Callbacks>,
+}
+
+#[derive(Default)]
+pub(crate) struct Callbacks {
+    on_commit: Vec<Callback>,
+    on_rollback: Vec<Callback>,
+}
+
+/// Lowers the depth of a context when a joined scope ends, even if cancelled
+struct DepthGuard<'a>(&'a AtomicUsize);
+
+impl Drop for DepthGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl TransactionContext {
+    pub(crate) fn new(manager_name: Option<Arc<str>>) -> Self {
+        Self {
+            manager_name,
+            depth: AtomicUsize::new(1),
+            rollback_only: AtomicBool::new(false),
+            callbacks: Mutex::default(),
+        }
+    }
+
+    /// Context of the innermost active transaction (or savepoint), if any
+    pub fn current() -> Option<Arc<TransactionContext>> {
+        CONTEXT
+            .try_with(Arc::clone)
+            .ok()
+            // A suspended transaction (`NotSupported`) keeps its context scoped.
+            .filter(|context| transaction_in(context.manager_name.as_deref()).is_some())
+    }
+
+    /// Number of transactional scopes currently running in this boundary,
+    /// starting at 1 for the scope that began it
+    pub fn depth(&self) -> usize {
+        self.depth.load(Ordering::SeqCst)
+    }
+
+    /// Whether the transaction has been marked to roll back
+    pub fn is_rollback_only(&self) -> bool {
+        self.rollback_only.load(Ordering::SeqCst)
+    }
+
+    /// Mark the transaction so the outermost scope rolls it back
+    pub fn set_rollback_only(&self) {
+        self.rollback_only.store(true, Ordering::SeqCst);
+    }
+
+    pub(crate) fn manager_name(&self) -> Option<&str> {
+        self.manager_name.as_deref()
+    }
+
+    /// Run `fut` with this context as the innermost one
+    ///
+    /// The returned future owns its handle on the context instead of
+    /// borrowing it, so it can be awaited inside `Send` futures.
+    pub(crate) fn scope<F: Future>(self: &Arc<Self>, fut: F) -> TaskLocalFuture<Arc<Self>, F> {
+        CONTEXT.scope(Arc::clone(self), fut)
+    }
+
+    /// Run `body` as a scope participating in this context's transaction
+    ///
+    /// An error `should_rollback` accepts marks the transaction rollback-only.
+    pub(crate) async fn join<T, E, F>(
+        &self,
+        should_rollback: impl Fn(&E) -> bool,
+        body: F,
+    ) -> Result<T, E>
+    where
+        F: Future<Output = Result<T, E>>,
+    {
+        self.depth.fetch_add(1, Ordering::SeqCst);
+        let _depth = DepthGuard(&self.depth);
+
+        let result = body.await;
+        if let Err(e) = &result
+            && should_rollback(e)
+        {
+            self.set_rollback_only();
+        }
+        result
+    }
+
+    /// Take the callbacks registered so far
+    pub(crate) fn take_callbacks(&self) -> Callbacks {
+        std::mem::take(
+            &mut *self
+                .callbacks
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner),
+        )
+    }
+}
+
+impl Callbacks {
+    /// Run the after-commit callbacks in registration order
+    pub(crate) async fn run_committed(self) {
+        for callback in self.on_commit {
+            callback().await;
+        }
+    }
+
+    /// Run the after-rollback callbacks in registration order
+    pub(crate) async fn run_rolled_back(self) {
+        for callback in self.on_rollback {
+            callback().await;
+        }
+    }
+
+    /// Hand the callbacks to the enclosing boundary
+    ///
+    /// Used when a savepoint is released: its work now commits or rolls back
+    /// with the outer transaction.
+    pub(crate) fn defer_to_outer(self) {
+        let deferred = CONTEXT.try_with(|outer| {
+            let mut outer = outer
+                .callbacks
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            outer.on_commit.extend(self.on_commit);
+            outer.on_rollback.extend(self.on_rollback);
+        });
+        if deferred.is_err() {
+            tracing::warn!("No enclosing transaction context, dropping transaction callbacks");
+        }
+    }
+}
+
+fn with_current<R>(f: impl FnOnce(&TransactionContext) -> R) -> Result<R, TransactionError> {
+    TransactionContext::current()
+        .map(|context| f(&context))
+        .ok_or(TransactionError::NoActiveTransaction)
+}
+
+fn register(
+    callback: Callback,
+    list: impl FnOnce(&mut Callbacks) -> &mut Vec<Callback>,
+) -> Result<(), TransactionError> {
+    with_current(|context| {
+        let mut callbacks = context
+            .callbacks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        list(&mut callbacks).push(callback);
+    })
+}
+
+/// Mark the active transaction so it rolls back even if the body returns `Ok`
+///
+/// Inside a `Nested` boundary only the savepoint is rolled back.
+///
+/// # Errors
+/// Returns [`TransactionError::NoActiveTransaction`] outside a transaction
+/// started by `#[transactional]`, [`run_transactional`] or
+/// `TransactionalInterceptor`.
+///
+/// [`run_transactional`]: crate::transactional::run_transactional
+pub fn set_rollback_only() -> Result<(), TransactionError> {
+    with_current(TransactionContext::set_rollback_only)
+}
+
+/// Whether the active transaction has been marked rollback-only
+pub fn is_rollback_only() -> bool {
+    TransactionContext::current().is_some_and(|context| context.is_rollback_only())
+}
+
+/// Run `callback` after the active transaction commits
+///
+/// Callbacks run outside the transaction, in registration order, once the
+/// outermost boundary owning it has committed. They are dropped if the
+/// transaction rolls back.
+///
+/// # Errors
+/// Returns [`TransactionError::NoActiveTransaction`] when called outside a
+/// transaction; the callback is not run.
+///
+/// # Example
+/// ```rust,ignore
+/// #[transactional(manager = self.transaction_manager)]
+/// async fn rename(&self, id: u64, name: String) -> Result<()> {
+///     self.repository.rename(id, &name).await?;
+///     let cache = self.cache.clone();
+///     transactional::on_commit(move || async move { cache.invalidate(id).await })?;
+///     Ok(())
+/// }
+/// ```
+pub fn on_commit<F, Fut>(callback: F) -> Result<(), TransactionError>
+where
+    F: FnOnce() -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    register(
+        Box::new(move || -> CallbackFuture { Box::pin(callback()) }),
+        |callbacks| &mut callbacks.on_commit,
+    )
+}
+
+/// Run `callback` after the active transaction (or savepoint) rolls back
+///
+/// # Errors
+/// Returns [`TransactionError::NoActiveTransaction`] when called outside a
+/// transaction; the callback is not run.
+pub fn on_rollback<F, Fut>(callback: F) -> Result<(), TransactionError>
+where
+    F: FnOnce() -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    register(
+        Box::new(move || -> CallbackFuture { Box::pin(callback()) }),
+        |callbacks| &mut callbacks.on_rollback,
+    )
+}
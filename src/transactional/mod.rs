@@ -2,9 +2,28 @@ use crate::error::MeshestraError;
 use crate::interceptor::{Interceptor, InterceptorResult, Next};
 use async_trait::async_trait;
 use axum::{body::Body, http::Request};
+use std::collections::HashMap;
+use std::future::Future;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+mod access;
+mod ambient;
+mod context;
+mod policy;
+mod propagation;
+
+pub use access::{with_current_transaction, with_current_transaction_async};
+pub use ambient::{
+    NamedManagers, current_manager, named_manager, set_global_manager, with_manager,
+    with_named_managers,
+};
+pub use context::{
+    CallbackFuture, TransactionContext, is_rollback_only, on_commit, on_rollback, set_rollback_only,
+};
+pub use policy::{ForceRollback, RollbackPolicy, RollbackPolicyBuilder};
+pub use propagation::{error_matches, run_transactional, run_transactional_with};
+
 tokio::task_local! {
     /// Task-local storage for the active transaction.
     ///
@@ -19,10 +38,63 @@ tokio::task_local! {
 /// This allows repositories or services to get access to the transaction
 /// started by a `#[transactional]` method without needing it to be passed
 /// as an explicit argument. Returns `None` if no transaction is active.
+///
+/// [`with_current_transaction`] and [`with_current_transaction_async`] also
+/// lock and downcast it to the concrete transaction type.
 pub fn get_current_transaction() -> Option<Arc<Mutex<Box<dyn Transaction>>>> {
     ACTIVE_TRANSACTION.try_with(|tx| tx.clone()).unwrap_or(None)
 }
 
+tokio::task_local! {
+    /// Active transactions of named managers, keyed by manager name
+    ///
+    /// Kept apart from `ACTIVE_TRANSACTION` so a transaction on one manager
+    /// never shadows an active transaction on another.
+    static NAMED_TRANSACTIONS: HashMap<Arc<str>, Option<Arc<Mutex<Box<dyn Transaction>>>>>;
+}
+
+/// Retrieves the active transaction of the manager registered as `name`.
+///
+/// Repositories bound to a secondary datasource use this instead of
+/// [`get_current_transaction`], which only sees the default manager's
+/// transaction. Returns `None` if that manager has no active transaction.
+pub fn get_current_transaction_for(name: &str) -> Option<Arc<Mutex<Box<dyn Transaction>>>> {
+    NAMED_TRANSACTIONS
+        .try_with(|txs| txs.get(name).cloned())
+        .ok()
+        .flatten()
+        .flatten()
+}
+
+/// The active transaction of the default manager (`None`) or a named one
+pub(crate) fn transaction_in(
+    manager_name: Option<&str>,
+) -> Option<Arc<Mutex<Box<dyn Transaction>>>> {
+    match manager_name {
+        None => get_current_transaction(),
+        Some(name) => get_current_transaction_for(name),
+    }
+}
+
+/// Run `fut` with `tx` as the active transaction of the default manager
+/// (`None`) or a named one, leaving the other managers' transactions alone
+pub(crate) async fn scope_transaction<F: Future>(
+    manager_name: Option<&Arc<str>>,
+    tx: Option<Arc<Mutex<Box<dyn Transaction>>>>,
+    fut: F,
+) -> F::Output {
+    match manager_name {
+        None => ACTIVE_TRANSACTION.scope(tx, fut).await,
+        Some(name) => {
+            let mut txs = NAMED_TRANSACTIONS
+                .try_with(Clone::clone)
+                .unwrap_or_default();
+            txs.insert(Arc::clone(name), tx);
+            NAMED_TRANSACTIONS.scope(txs, fut).await
+        }
+    }
+}
+
 /// Represents the isolation levels for database transactions.
 ///
 /// Isolation levels determine how transaction integrity is visible to other
@@ -146,11 +218,47 @@ pub enum Propagation {
     NotSupported,
 }
 
+/// Errors raised by transaction boundaries
+#[derive(Debug, thiserror::Error)]
+pub enum TransactionError {
+    #[error("Transaction does not support {operation}")]
+    Unsupported { operation: String },
+
+    /// `Propagation::Mandatory` was used without an active transaction
+    #[error("No active transaction, but the propagation requires one")]
+    NoActiveTransaction,
+
+    /// `Propagation::Never` was used inside an active transaction
+    #[error("An active transaction exists, but the propagation forbids one")]
+    ExistingTransaction,
+
+    /// No ambient transaction manager was installed
+    #[error(
+        "No transaction manager available; use `with_manager`, `set_global_manager` or `#[transactional(manager = ...)]`"
+    )]
+    NoTransactionManager,
+
+    /// No manager was registered under this name
+    #[error("No transaction manager registered as '{name}'")]
+    UnknownManager { name: String },
+}
+
+impl TransactionError {
+    pub fn unsupported(operation: impl Into<String>) -> Self {
+        Self::Unsupported {
+            operation: operation.into(),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct TransactionOptions {
     pub isolation: Option<IsolationLevel>,
     pub propagation: Propagation,
     pub read_only: bool,
+    /// Name of the manager whose transactions this boundary works with;
+    /// `None` for the default one
+    pub manager_name: Option<Arc<str>>,
 }
 
 impl Default for TransactionOptions {
@@ -159,6 +267,7 @@ impl Default for TransactionOptions {
             isolation: None, // Default depends on DB
             propagation: Propagation::Required,
             read_only: false,
+            manager_name: None,
         }
     }
 }
@@ -171,6 +280,19 @@ pub trait TransactionManager: Send + Sync + 'static {
         &self,
         options: TransactionOptions,
     ) -> Result<Box<dyn Transaction>, MeshestraError>;
+
+    /// Check that the datasource answers
+    ///
+    /// Begins a read-only transaction and rolls it back; managers with a
+    /// cheaper probe override it.
+    async fn ping(&self) -> Result<(), MeshestraError> {
+        let options = TransactionOptions {
+            read_only: true,
+            ..TransactionOptions::default()
+        };
+        let mut transaction = self.begin(options).await?;
+        transaction.rollback().await
+    }
 }
 
 /// A generic transaction abstraction
@@ -182,8 +304,31 @@ pub trait Transaction: Send + Sync + std::any::Any {
     /// Rollback the transaction
     async fn rollback(&mut self) -> Result<(), MeshestraError>;
 
+    /// Create a savepoint inside this transaction
+    ///
+    /// Used by `Propagation::Nested`. The default implementation reports
+    /// savepoints as unsupported.
+    async fn savepoint(&mut self, _name: &str) -> Result<(), MeshestraError> {
+        Err(TransactionError::unsupported("savepoint").into())
+    }
+
+    /// Roll back to a savepoint, keeping the rest of the transaction
+    async fn rollback_to_savepoint(&mut self, _name: &str) -> Result<(), MeshestraError> {
+        Err(TransactionError::unsupported("rollback_to_savepoint").into())
+    }
+
+    /// Release a savepoint once the work after it succeeded
+    async fn release_savepoint(&mut self, _name: &str) -> Result<(), MeshestraError> {
+        Err(TransactionError::unsupported("release_savepoint").into())
+    }
+
     /// Gets this trait object as a mutable `Any` reference for downcasting.
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any;
+
+    /// Name of the concrete transaction type, for diagnostics
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
 }
 
 /// Wrapper to store the active transaction in the request extensions.
@@ -194,11 +339,32 @@ pub struct ActiveTransaction(pub Arc<Mutex<Box<dyn Transaction>>>);
 /// Interceptor that wraps the request in a transaction
 pub struct TransactionalInterceptor {
     manager: Arc<dyn TransactionManager>,
+    policy: RollbackPolicy,
+    manager_name: Option<Arc<str>>,
 }
 
 impl TransactionalInterceptor {
-    pub fn new(manager: Arc<dyn TransactionManager>) -> Self {
-        Self { manager }
+    /// Wrap handlers in a transaction, finishing it according to `policy`
+    ///
+    /// Use `RollbackPolicy::default()` to roll back on 5xx and commit
+    /// everything else.
+    pub fn new(manager: Arc<dyn TransactionManager>, policy: RollbackPolicy) -> Self {
+        Self {
+            manager,
+            policy,
+            manager_name: None,
+        }
+    }
+
+    /// Treat `manager` as the named manager `name`
+    ///
+    /// The transaction is then visible through [`get_current_transaction_for`]
+    /// instead of [`get_current_transaction`], the `ActiveTransaction`
+    /// extension and the ambient manager, so it can be stacked with an
+    /// interceptor for the default manager.
+    pub fn named(mut self, name: impl Into<Arc<str>>) -> Self {
+        self.manager_name = Some(name.into());
+        self
     }
 }
 
@@ -206,9 +372,13 @@ impl TransactionalInterceptor {
 impl Interceptor for TransactionalInterceptor {
     async fn intercept(&self, mut request: Request<Body>, next: Next) -> InterceptorResult {
         // 1. Begin transaction
+        let options = TransactionOptions {
+            manager_name: self.manager_name.clone(),
+            ..TransactionOptions::default()
+        };
         let tx = self
             .manager
-            .begin(crate::transactional::TransactionOptions::default())
+            .begin(options)
             .await
             .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?;
 
@@ -216,48 +386,71 @@ impl Interceptor for TransactionalInterceptor {
         // - One reference goes into the Request Extensions for the handler
         // - One reference stays here for commit/rollback
         let shared_tx = Arc::new(Mutex::new(tx));
-        let active_tx = ActiveTransaction(shared_tx.clone());
+        let context = Arc::new(TransactionContext::new(self.manager_name.clone()));
 
-        request.extensions_mut().insert(active_tx);
+        // 3. Run handler with the transaction active for `#[transactional]` code,
+        //    `on_commit` / `set_rollback_only`, and our manager as the ambient one.
+        //    Named managers only expose it through `get_current_transaction_for`.
+        let result = match &self.manager_name {
+            None => {
+                request
+                    .extensions_mut()
+                    .insert(ActiveTransaction(shared_tx.clone()));
+                with_manager(
+                    Arc::clone(&self.manager),
+                    ACTIVE_TRANSACTION
+                        .scope(Some(shared_tx.clone()), context.scope(next.run(request))),
+                )
+                .await
+            }
+            Some(name) => {
+                scope_transaction(
+                    Some(name),
+                    Some(shared_tx.clone()),
+                    context.scope(next.run(request)),
+                )
+                .await
+            }
+        };
 
-        // 3. Run handler
-        let result = next.run(request).await;
+        // 4. Decide and finalize.
+        // The policy decides by status; handlers can override it with `ForceRollback`.
+        let rollback = match &result {
+            Ok(response) => {
+                self.policy.should_rollback(response.status())
+                    || response.extensions().get::<ForceRollback>().is_some()
+                    || context.is_rollback_only()
+            }
+            Err(_) => true,
+        };
+        let outcome = {
+            let mut tx_guard = shared_tx.lock().await;
+            if rollback {
+                tx_guard.rollback().await
+            } else {
+                tx_guard.commit().await
+            }
+        };
 
-        // 4. Lock and finalize
-        let mut tx_guard = shared_tx.lock().await;
+        // 5. Run callbacks outside the transaction
+        let callbacks = context.take_callbacks();
+        if outcome.is_ok() && !rollback {
+            callbacks.run_committed().await;
+        } else {
+            callbacks.run_rolled_back().await;
+        }
 
-        match result {
-            Ok(response) => {
-                if response.status().is_success() {
-                    tx_guard
-                        .commit()
-                        .await
-                        .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?;
-                    Ok(response)
-                } else {
-                    // For client errors (4xx), we usually assume the logic ran correctly but found an issue.
-                    // However, dependent on design, one might want to rollback.
-                    // Defaulting to commit for consistency, unless it's a 5xx.
-                    if response.status().is_server_error() {
-                        tx_guard
-                            .rollback()
-                            .await
-                            .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?;
-                    } else {
-                        tx_guard
-                            .commit()
-                            .await
-                            .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?;
-                    }
-                    Ok(response)
-                }
+        // 6. A failed rollback leaves the handler's outcome as it was; a failed
+        //    commit loses its work, so the error replaces its response.
+        match outcome {
+            Ok(()) => result,
+            Err(e) if rollback => {
+                tracing::error!("Failed to roll back transaction: {}", e);
+                result
             }
             Err(e) => {
-                tx_guard
-                    .rollback()
-                    .await
-                    .map_err(|xe| Box::new(xe) as Box<dyn std::error::Error + Send + Sync>)?;
-                Err(e)
+                tracing::error!("Failed to commit transaction: {}", e);
+                Err(Box::new(e))
             }
         }
     }
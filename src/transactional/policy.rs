@@ -0,0 +1,121 @@
+use axum::http::StatusCode;
+use std::collections::HashSet;
+
+/// Decides whether `TransactionalInterceptor` commits or rolls back a
+/// request's transaction based on the response status
+///
+/// Specific status codes added with [`rollback_on`](RollbackPolicyBuilder::rollback_on)
+/// or [`commit_on`](RollbackPolicyBuilder::commit_on) take precedence over the
+/// client/server error classes. The default policy rolls back on 5xx and
+/// commits everything else.
+///
+/// # Example
+/// ```
+/// use axum::http::StatusCode;
+/// use meshestra::transactional::RollbackPolicy;
+///
+/// let policy = RollbackPolicy::builder()
+///     .rollback_on_server_error()
+///     .rollback_on(StatusCode::CONFLICT)
+///     .commit_on_client_error()
+///     .build();
+///
+/// assert!(policy.should_rollback(StatusCode::CONFLICT));
+/// assert!(!policy.should_rollback(StatusCode::UNPROCESSABLE_ENTITY));
+/// ```
+#[derive(Debug, Clone)]
+pub struct RollbackPolicy {
+    rollback_on_client_error: bool,
+    rollback_on_server_error: bool,
+    rollback_on: HashSet<StatusCode>,
+    commit_on: HashSet<StatusCode>,
+}
+
+impl Default for RollbackPolicy {
+    fn default() -> Self {
+        Self::builder().rollback_on_server_error().build()
+    }
+}
+
+impl RollbackPolicy {
+    /// Start from a policy that commits every response
+    pub fn builder() -> RollbackPolicyBuilder {
+        RollbackPolicyBuilder {
+            policy: RollbackPolicy {
+                rollback_on_client_error: false,
+                rollback_on_server_error: false,
+                rollback_on: HashSet::new(),
+                commit_on: HashSet::new(),
+            },
+        }
+    }
+
+    /// Whether a response with `status` should roll the transaction back
+    pub fn should_rollback(&self, status: StatusCode) -> bool {
+        if self.rollback_on.contains(&status) {
+            return true;
+        }
+        if self.commit_on.contains(&status) {
+            return false;
+        }
+        (status.is_client_error() && self.rollback_on_client_error)
+            || (status.is_server_error() && self.rollback_on_server_error)
+    }
+}
+
+/// Builder for [`RollbackPolicy`]
+#[derive(Debug)]
+pub struct RollbackPolicyBuilder {
+    policy: RollbackPolicy,
+}
+
+impl RollbackPolicyBuilder {
+    /// Roll back on every 5xx response
+    pub fn rollback_on_server_error(mut self) -> Self {
+        self.policy.rollback_on_server_error = true;
+        self
+    }
+
+    /// Roll back on every 4xx response
+    pub fn rollback_on_client_error(mut self) -> Self {
+        self.policy.rollback_on_client_error = true;
+        self
+    }
+
+    /// Commit on 4xx responses (the builder's starting point)
+    pub fn commit_on_client_error(mut self) -> Self {
+        self.policy.rollback_on_client_error = false;
+        self
+    }
+
+    /// Always roll back on `status`
+    pub fn rollback_on(mut self, status: StatusCode) -> Self {
+        self.policy.commit_on.remove(&status);
+        self.policy.rollback_on.insert(status);
+        self
+    }
+
+    /// Always commit on `status`
+    pub fn commit_on(mut self, status: StatusCode) -> Self {
+        self.policy.rollback_on.remove(&status);
+        self.policy.commit_on.insert(status);
+        self
+    }
+
+    pub fn build(self) -> RollbackPolicy {
+        self.policy
+    }
+}
+
+/// Response extension forcing `TransactionalInterceptor` to roll back,
+/// whatever the [`RollbackPolicy`] says
+///
+/// # Example
+/// ```rust,ignore
+/// async fn import(...) -> impl IntoResponse {
+///     // Partial data was written but the import is not usable.
+///     (StatusCode::OK, Extension(ForceRollback), Json(report))
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ForceRollback;
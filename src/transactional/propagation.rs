@@ -0,0 +1,302 @@
+use crate::error::MeshestraError;
+use crate::transactional::{
+    Propagation, Transaction, TransactionContext, TransactionError, TransactionManager,
+    TransactionOptions, scope_transaction, transaction_in,
+};
+use std::any::Any;
+use std::error::Error;
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::sync::Mutex;
+
+/// Source of unique savepoint names
+static NEXT_SAVEPOINT: AtomicU64 = AtomicU64::new(1);
+
+/// Whether `error` is, wraps or was caused by an error of type `X`
+///
+/// Matches the concrete error type, and looks through the source chain of
+/// boxed `dyn Error` values and `anyhow::Error`. Used by the
+/// `rollback_for` / `no_rollback_for` arguments of `#[transactional]`.
+pub fn error_matches<X, E>(error: &E) -> bool
+where
+    X: Error + 'static,
+    E: 'static,
+{
+    let any = error as &dyn Any;
+    if any.is::<X>() {
+        return true;
+    }
+
+    let mut source: Option<&(dyn Error + 'static)> =
+        if let Some(boxed) = any.downcast_ref::<Box<dyn Error + Send + Sync>>() {
+            Some(&**boxed)
+        } else if let Some(boxed) = any.downcast_ref::<Box<dyn Error>>() {
+            Some(&**boxed)
+        } else if let Some(error) = any.downcast_ref::<anyhow::Error>() {
+            return error.chain().any(|cause| cause.is::<X>());
+        } else {
+            None
+        };
+
+    while let Some(error) = source {
+        if error.is::<X>() {
+            return true;
+        }
+        source = error.source();
+    }
+    false
+}
+
+/// Run `body` inside a transaction boundary according to `options.propagation`
+///
+/// This is the runtime behind `#[transactional]`; it can also be called
+/// directly when a method cannot use the attribute.
+///
+/// - `Required`: join the active transaction, or begin one if there is none.
+/// - `RequiresNew`: always begin a new transaction. The active one (if any)
+///   is suspended while `body` runs and restored afterwards; the inner
+///   outcome does not affect it.
+/// - `Nested`: inside an active transaction, run `body` after a savepoint,
+///   releasing it on success and rolling back to it on error so the outer
+///   transaction can continue. Without one, behave like `Required`.
+/// - `Supports`: join the active transaction, or run without one.
+/// - `Mandatory`: join the active transaction, or fail with
+///   [`TransactionError::NoActiveTransaction`].
+/// - `Never`: run without a transaction, or fail with
+///   [`TransactionError::ExistingTransaction`] if one is active.
+/// - `NotSupported`: suspend the active transaction (if any) and run
+///   without one, restoring it afterwards.
+///
+/// "Active transaction" means the one of the manager named by
+/// `options.manager_name`, or of the default manager when it is `None`;
+/// transactions of other managers are neither joined nor suspended.
+///
+/// A transaction begun here is committed when `body` returns `Ok` and rolled
+/// back when it returns `Err`, or when [`set_rollback_only`] was called. A
+/// joining scope (`Required`, `Supports` or `Mandatory` inside an active
+/// transaction) whose body returns `Err` marks the transaction rollback-only,
+/// so catching that error in the outer scope does not commit partial work.
+/// Callbacks registered with [`on_commit`] / [`on_rollback`] run afterwards.
+///
+/// [`set_rollback_only`]: crate::transactional::set_rollback_only
+/// [`on_commit`]: crate::transactional::on_commit
+/// [`on_rollback`]: crate::transactional::on_rollback
+///
+/// # Example
+/// ```rust,ignore
+/// run_transactional(&*self.transaction_manager, TransactionOptions::default(), async {
+///     self.repository.save(&user).await
+/// })
+/// .await
+/// ```
+pub async fn run_transactional<M, T, E, F>(
+    manager: &M,
+    options: TransactionOptions,
+    body: F,
+) -> Result<T, E>
+where
+    M: TransactionManager + ?Sized,
+    F: Future<Output = Result<T, E>>,
+    E: From<MeshestraError>,
+{
+    run_transactional_with(manager, options, |_: &E| true, body).await
+}
+
+/// Like [`run_transactional`], but `should_rollback` decides whether an
+/// `Err` rolls the transaction (or savepoint) back or still commits it
+pub async fn run_transactional_with<M, T, E, R, F>(
+    manager: &M,
+    options: TransactionOptions,
+    should_rollback: R,
+    body: F,
+) -> Result<T, E>
+where
+    M: TransactionManager + ?Sized,
+    F: Future<Output = Result<T, E>>,
+    E: From<MeshestraError>,
+    R: Fn(&E) -> bool,
+{
+    let active = transaction_in(options.manager_name.as_deref());
+    match options.propagation {
+        Propagation::Required => {
+            if active.is_some() {
+                // The outer boundary owns commit/rollback.
+                join_active(&options, should_rollback, body).await
+            } else {
+                run_in_new_transaction(manager, options, should_rollback, body).await
+            }
+        }
+        Propagation::RequiresNew => {
+            run_in_new_transaction(manager, options, should_rollback, body).await
+        }
+        Propagation::Nested => match active {
+            Some(tx) => run_in_savepoint(tx, options, should_rollback, body).await,
+            None => run_in_new_transaction(manager, options, should_rollback, body).await,
+        },
+        Propagation::Supports if active.is_some() => {
+            join_active(&options, should_rollback, body).await
+        }
+        Propagation::Supports => body.await,
+        Propagation::Mandatory => {
+            if active.is_none() {
+                return Err(MeshestraError::from(TransactionError::NoActiveTransaction).into());
+            }
+            join_active(&options, should_rollback, body).await
+        }
+        Propagation::Never => {
+            if active.is_some() {
+                return Err(MeshestraError::from(TransactionError::ExistingTransaction).into());
+            }
+            body.await
+        }
+        Propagation::NotSupported => {
+            scope_transaction(options.manager_name.as_ref(), None, body).await
+        }
+    }
+}
+
+/// Run `body` as part of the active transaction's context
+///
+/// An error marks the transaction rollback-only, so the scope that began it
+/// rolls back even if it recovers from the error.
+async fn join_active<T, E, R, F>(
+    options: &TransactionOptions,
+    should_rollback: R,
+    body: F,
+) -> Result<T, E>
+where
+    F: Future<Output = Result<T, E>>,
+    R: Fn(&E) -> bool,
+{
+    // Without a context (or with another manager's innermost) there is
+    // nothing to mark; the caller owning the transaction decides alone.
+    match TransactionContext::current()
+        .filter(|context| context.manager_name() == options.manager_name.as_deref())
+    {
+        Some(context) => context.join(should_rollback, body).await,
+        None => body.await,
+    }
+}
+
+/// Begin a transaction, scope it as the active one for `body`, then finish it
+async fn run_in_new_transaction<M, T, E, R, F>(
+    manager: &M,
+    options: TransactionOptions,
+    should_rollback: R,
+    body: F,
+) -> Result<T, E>
+where
+    M: TransactionManager + ?Sized,
+    F: Future<Output = Result<T, E>>,
+    E: From<MeshestraError>,
+    R: Fn(&E) -> bool,
+{
+    let manager_name = options.manager_name.clone();
+    let tx = manager.begin(options).await?;
+    let tx = Arc::new(Mutex::new(tx));
+    let context = Arc::new(TransactionContext::new(manager_name.clone()));
+
+    let result = scope_transaction(
+        manager_name.as_ref(),
+        Some(Arc::clone(&tx)),
+        context.scope(body),
+    )
+    .await;
+
+    let rollback = needs_rollback(&result, &context, should_rollback);
+    let outcome = {
+        let mut guard = tx.lock().await;
+        if rollback {
+            guard.rollback().await
+        } else {
+            guard.commit().await
+        }
+    };
+
+    // Callbacks run after the transaction is finished and unlocked.
+    let callbacks = context.take_callbacks();
+    match outcome {
+        Ok(()) if !rollback => callbacks.run_committed().await,
+        Ok(()) => callbacks.run_rolled_back().await,
+        Err(e) if rollback => {
+            // The original error is more important than a failed rollback.
+            tracing::error!("Failed to roll back transaction: {}", e);
+            callbacks.run_rolled_back().await;
+        }
+        Err(e) => {
+            callbacks.run_rolled_back().await;
+            return Err(
+                MeshestraError::Internal(format!("Failed to commit transaction: {}", e)).into(),
+            );
+        }
+    }
+
+    result
+}
+
+fn needs_rollback<T, E>(
+    result: &Result<T, E>,
+    context: &TransactionContext,
+    should_rollback: impl Fn(&E) -> bool,
+) -> bool {
+    match result {
+        Ok(_) => context.is_rollback_only(),
+        // A rollback-only mark wins over `no_rollback_for`.
+        Err(e) => context.is_rollback_only() || should_rollback(e),
+    }
+}
+
+/// Run `body` after a savepoint in the active transaction
+async fn run_in_savepoint<T, E, R, F>(
+    tx: Arc<Mutex<Box<dyn Transaction>>>,
+    options: TransactionOptions,
+    should_rollback: R,
+    body: F,
+) -> Result<T, E>
+where
+    F: Future<Output = Result<T, E>>,
+    E: From<MeshestraError>,
+    R: Fn(&E) -> bool,
+{
+    let name = format!(
+        "meshestra_sp_{}",
+        NEXT_SAVEPOINT.fetch_add(1, Ordering::Relaxed)
+    );
+    tx.lock().await.savepoint(&name).await?;
+
+    let context = Arc::new(TransactionContext::new(options.manager_name));
+    let result = context.scope(body).await;
+
+    let rollback = needs_rollback(&result, &context, should_rollback);
+    let outcome = {
+        let mut guard = tx.lock().await;
+        if rollback {
+            guard.rollback_to_savepoint(&name).await
+        } else {
+            guard.release_savepoint(&name).await
+        }
+    };
+
+    // A released savepoint commits with the outer transaction, so its
+    // callbacks wait for the outer context.
+    let callbacks = context.take_callbacks();
+    match outcome {
+        Ok(()) if !rollback => callbacks.defer_to_outer(),
+        Ok(()) => callbacks.run_rolled_back().await,
+        Err(e) if rollback => {
+            tracing::error!("Failed to roll back to savepoint {}: {}", name, e);
+            callbacks.run_rolled_back().await;
+        }
+        Err(e) => {
+            callbacks.run_rolled_back().await;
+            return Err(MeshestraError::Internal(format!(
+                "Failed to release savepoint {}: {}",
+                name, e
+            ))
+            .into());
+        }
+    }
+
+    result
+}
@@ -1,11 +1,45 @@
 use rayon::ThreadPool;
+use std::panic::{AssertUnwindSafe, catch_unwind, resume_unwind};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 use tokio::sync::oneshot;
 
+mod schedule;
+
+pub use schedule::{CronSchedule, OverlapPolicy, ScheduleError, ScheduledTask, Scheduler};
+
+/// Point-in-time counters of a [`WorkerPool`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WorkerStats {
+    /// Tasks submitted but not yet picked up by a thread
+    pub queued: usize,
+    /// Tasks currently running
+    pub in_flight: usize,
+    /// Tasks that finished without panicking
+    pub completed: u64,
+    /// Tasks that panicked
+    pub panics: u64,
+    /// Average duration of completed tasks
+    pub avg_duration: Duration,
+}
+
+#[derive(Default)]
+struct WorkerCounters {
+    queued: AtomicUsize,
+    in_flight: AtomicUsize,
+    completed: AtomicU64,
+    panics: AtomicU64,
+    total_nanos: AtomicU64,
+}
+
 /// Shared thread pool for CPU-bound tasks
 #[derive(Clone)]
 pub struct WorkerPool {
+    name: Arc<str>,
     pool: Arc<ThreadPool>,
+    counters: Arc<WorkerCounters>,
+    high_water_mark: Option<usize>,
 }
 
 impl Default for WorkerPool {
@@ -16,27 +50,132 @@ impl Default for WorkerPool {
 
 impl WorkerPool {
     pub fn new(num_threads: usize) -> Self {
+        Self::named("worker", num_threads)
+    }
+
+    /// Create a pool whose name appears in thread names and log events
+    pub fn named(name: impl Into<String>, num_threads: usize) -> Self {
+        let name: Arc<str> = Arc::from(name.into());
+        let thread_prefix = name.clone();
         let pool = rayon::ThreadPoolBuilder::new()
             .num_threads(num_threads)
+            .thread_name(move |index| format!("{}-{}", thread_prefix, index))
             .build()
             .unwrap();
         Self {
+            name,
             pool: Arc::new(pool),
+            counters: Arc::new(WorkerCounters::default()),
+            high_water_mark: None,
+        }
+    }
+
+    /// Emit a `tracing` debug event whenever the queue depth exceeds `queued`
+    pub fn with_high_water_mark(mut self, queued: usize) -> Self {
+        self.high_water_mark = Some(queued);
+        self
+    }
+
+    /// Name of this pool
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Number of threads in this pool
+    pub fn num_threads(&self) -> usize {
+        self.pool.current_num_threads()
+    }
+
+    /// Read the current counters
+    ///
+    /// Only performs relaxed atomic loads, so it is cheap enough for hot paths.
+    pub fn stats(&self) -> WorkerStats {
+        let counters = &self.counters;
+        let completed = counters.completed.load(Ordering::Relaxed);
+        let total_nanos = counters.total_nanos.load(Ordering::Relaxed);
+        WorkerStats {
+            queued: counters.queued.load(Ordering::Relaxed),
+            in_flight: counters.in_flight.load(Ordering::Relaxed),
+            completed,
+            panics: counters.panics.load(Ordering::Relaxed),
+            avg_duration: Duration::from_nanos(total_nanos.checked_div(completed).unwrap_or(0)),
         }
     }
 
     /// Execute a CPU-bound task in the thread pool and return result asynchronously
+    ///
+    /// A panic inside the task is counted in [`WorkerStats::panics`] and then
+    /// resumed on the awaiting task.
     pub async fn execute<F, R>(&self, f: F) -> R
     where
         F: FnOnce() -> R + Send + 'static,
         R: Send + 'static,
     {
+        let queued = self.counters.queued.fetch_add(1, Ordering::Relaxed) + 1;
+        if let Some(high_water_mark) = self.high_water_mark
+            && queued > high_water_mark
+        {
+            tracing::debug!(
+                pool = %self.name,
+                queued,
+                high_water_mark,
+                "Worker pool queue depth above high-water mark"
+            );
+        }
+
+        let counters = self.counters.clone();
         let (tx, rx) = oneshot::channel();
         self.pool.spawn(move || {
-            let result = f();
+            counters.queued.fetch_sub(1, Ordering::Relaxed);
+            counters.in_flight.fetch_add(1, Ordering::Relaxed);
+
+            let start = Instant::now();
+            let result = catch_unwind(AssertUnwindSafe(f));
+            let elapsed = start.elapsed();
+
+            counters.in_flight.fetch_sub(1, Ordering::Relaxed);
+            if result.is_ok() {
+                counters.completed.fetch_add(1, Ordering::Relaxed);
+                counters
+                    .total_nanos
+                    .fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+            } else {
+                counters.panics.fetch_add(1, Ordering::Relaxed);
+            }
             let _ = tx.send(result);
         });
 
-        rx.await.expect("Worker task panicked")
+        match rx.await.expect("Worker task dropped") {
+            Ok(result) => result,
+            Err(panic) => resume_unwind(panic),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_stats_track_completed_and_panicked_tasks() {
+        let pool = WorkerPool::named("test-pool", 2);
+        assert_eq!(pool.name(), "test-pool");
+
+        for i in 0..3 {
+            assert_eq!(pool.execute(move || i * 2).await, i * 2);
+        }
+
+        let panicking = pool.clone();
+        let outcome = tokio::spawn(async move {
+            let _: i32 = panicking.execute(|| -> i32 { panic!("boom") }).await;
+        })
+        .await;
+        assert!(outcome.is_err());
+
+        let stats = pool.stats();
+        assert_eq!(stats.completed, 3);
+        assert_eq!(stats.panics, 1);
+        assert_eq!(stats.queued, 0);
+        assert_eq!(stats.in_flight, 0);
     }
 }
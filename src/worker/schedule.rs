@@ -0,0 +1,601 @@
+//! Scheduled Tasks
+//!
+//! Provides interval and cron-based background tasks that start with the
+//! application (`OnApplicationBootstrap`) and stop on shutdown
+//! (`OnApplicationShutdown`).
+
+use crate::lifecycle::{LifecycleError, OnApplicationBootstrap, OnApplicationShutdown};
+use async_trait::async_trait;
+use chrono::{DateTime, Datelike, NaiveDate, Timelike, Utc};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::task::{JoinHandle, JoinSet};
+use tokio::time::{Instant, Interval, MissedTickBehavior};
+
+type TaskFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+type TaskFn = Arc<dyn Fn() -> TaskFuture + Send + Sync>;
+
+/// Errors raised while configuring scheduled tasks
+#[derive(Debug, thiserror::Error)]
+pub enum ScheduleError {
+    #[error("Invalid cron expression '{expression}': {reason}")]
+    InvalidCron { expression: String, reason: String },
+}
+
+/// What to do when a task is due while its previous run is still in progress
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverlapPolicy {
+    /// Drop the due run (default)
+    #[default]
+    Skip,
+
+    /// Start the due run as soon as the previous one finishes
+    Queue,
+
+    /// Start the due run immediately, alongside the previous one
+    Concurrent,
+}
+
+/// A parsed 5-field cron expression (`minute hour day-of-month month day-of-week`)
+///
+/// Each field supports `*`, single values, ranges (`1-5`), lists (`1,15,30`)
+/// and steps (`*/15`, `0-30/10`). Day-of-week accepts `0`-`7`, where both `0`
+/// and `7` mean Sunday. As in classic cron, when both day fields are
+/// restricted a day matches if *either* of them matches.
+///
+/// Schedules are evaluated in UTC.
+///
+/// # Example
+/// ```
+/// use meshestra::worker::CronSchedule;
+///
+/// // Every 15 minutes during working hours on weekdays
+/// let schedule = CronSchedule::parse("*/15 9-17 * * 1-5").unwrap();
+/// assert!(schedule.next_after(chrono::Utc::now()).is_some());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    any_day_of_month: bool,
+    any_day_of_week: bool,
+}
+
+impl CronSchedule {
+    /// Parse a 5-field cron expression
+    pub fn parse(expression: &str) -> Result<Self, ScheduleError> {
+        let invalid = |reason: String| ScheduleError::InvalidCron {
+            expression: expression.to_string(),
+            reason,
+        };
+
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(invalid(format!(
+                "expected 5 fields, found {}",
+                fields.len()
+            )));
+        }
+
+        let minutes = parse_field(fields[0], 0, 59).map_err(&invalid)?;
+        let hours = parse_field(fields[1], 0, 23).map_err(&invalid)?;
+        let days_of_month = parse_field(fields[2], 1, 31).map_err(&invalid)?;
+        let months = parse_field(fields[3], 1, 12).map_err(&invalid)?;
+        let mut days_of_week = parse_field(fields[4], 0, 7).map_err(&invalid)?;
+
+        // Fold 7 (Sunday) onto 0
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week = (days_of_week & !(1 << 7)) | 1;
+        }
+
+        Ok(Self {
+            minutes,
+            hours,
+            days_of_month,
+            months,
+            days_of_week,
+            any_day_of_month: fields[2] == "*",
+            any_day_of_week: fields[4] == "*",
+        })
+    }
+
+    /// The first matching minute strictly after `after`
+    ///
+    /// Returns `None` if nothing matches within the next five years
+    /// (e.g. `0 0 31 2 *`).
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let start = after
+            .with_second(0)?
+            .with_nanosecond(0)?
+            .checked_add_signed(chrono::Duration::minutes(1))?;
+
+        let mut date = start.date_naive();
+        for day in 0..(366 * 5) {
+            if self.matches_day(date) {
+                let first_day = day == 0;
+                let first_hour = if first_day { start.hour() } else { 0 };
+                for hour in first_hour..24 {
+                    if !bit(self.hours, hour) {
+                        continue;
+                    }
+                    let first_minute = if first_day && hour == start.hour() {
+                        start.minute()
+                    } else {
+                        0
+                    };
+                    for minute in first_minute..60 {
+                        if bit(self.minutes, minute) {
+                            return Some(date.and_hms_opt(hour, minute, 0)?.and_utc());
+                        }
+                    }
+                }
+            }
+            date = date.succ_opt()?;
+        }
+        None
+    }
+
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        if !bit(self.months, date.month()) {
+            return false;
+        }
+        let dom = bit(self.days_of_month, date.day());
+        let dow = bit(self.days_of_week, date.weekday().num_days_from_sunday());
+        match (self.any_day_of_month, self.any_day_of_week) {
+            (true, true) => true,
+            (false, true) => dom,
+            (true, false) => dow,
+            (false, false) => dom || dow,
+        }
+    }
+}
+
+fn bit(set: u64, value: u32) -> bool {
+    set & (1 << value) != 0
+}
+
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut set = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .map_err(|_| format!("invalid step '{}'", step))?;
+                if step == 0 {
+                    return Err("step must be greater than zero".to_string());
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (parse_value(start, min, max)?, parse_value(end, min, max)?)
+        } else {
+            let value = parse_value(range, min, max)?;
+            // `5/10` means "from 5 to the end, every 10"
+            if step > 1 {
+                (value, max)
+            } else {
+                (value, value)
+            }
+        };
+
+        if start > end {
+            return Err(format!("invalid range '{}'", range));
+        }
+
+        for value in (start..=end).step_by(step as usize) {
+            set |= 1 << value;
+        }
+    }
+    Ok(set)
+}
+
+fn parse_value(value: &str, min: u32, max: u32) -> Result<u32, String> {
+    let parsed: u32 = value
+        .parse()
+        .map_err(|_| format!("invalid value '{}'", value))?;
+    if !(min..=max).contains(&parsed) {
+        return Err(format!("value {} out of range {}-{}", parsed, min, max));
+    }
+    Ok(parsed)
+}
+
+#[derive(Clone)]
+enum Trigger {
+    Every(Duration),
+    Cron(CronSchedule),
+}
+
+/// A task registration for the [`Scheduler`]
+///
+/// Use this when a task needs a non-default [`OverlapPolicy`]; otherwise
+/// [`Scheduler::every`] and [`Scheduler::cron`] are shorter.
+pub struct ScheduledTask {
+    name: String,
+    trigger: Trigger,
+    overlap: OverlapPolicy,
+    task: TaskFn,
+}
+
+impl ScheduledTask {
+    /// A task that runs every `interval`, first after one full interval
+    pub fn every<F, Fut>(interval: Duration, name: impl Into<String>, task: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        Self {
+            name: name.into(),
+            trigger: Trigger::Every(interval),
+            overlap: OverlapPolicy::default(),
+            task: Arc::new(move || -> TaskFuture { Box::pin(task()) }),
+        }
+    }
+
+    /// A task that runs whenever the cron `expression` matches
+    pub fn cron<F, Fut>(
+        expression: &str,
+        name: impl Into<String>,
+        task: F,
+    ) -> Result<Self, ScheduleError>
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        Ok(Self {
+            name: name.into(),
+            trigger: Trigger::Cron(CronSchedule::parse(expression)?),
+            overlap: OverlapPolicy::default(),
+            task: Arc::new(move || -> TaskFuture { Box::pin(task()) }),
+        })
+    }
+
+    /// Set the overlapping-run policy
+    pub fn overlap(mut self, policy: OverlapPolicy) -> Self {
+        self.overlap = policy;
+        self
+    }
+
+    /// Name of the task
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Runs registered tasks in the background for the lifetime of the application
+///
+/// Tasks are started in `OnApplicationBootstrap` and stopped in
+/// `OnApplicationShutdown`. On shutdown the scheduling loops are aborted
+/// immediately; runs already in progress are aborted too, unless a grace
+/// period was configured with [`Scheduler::wait_for_running`].
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use meshestra::worker::{OverlapPolicy, ScheduledTask, Scheduler};
+///
+/// let mut scheduler = Scheduler::new();
+/// let cache = cache_service.clone();
+/// scheduler
+///     .every(Duration::from_secs(30), "cache-refresh", move || {
+///         let cache = cache.clone();
+///         async move { cache.refresh().await }
+///     })
+///     .add(
+///         ScheduledTask::cron("0 3 * * *", "nightly-report", || async { /* ... */ })?
+///             .overlap(OverlapPolicy::Queue),
+///     );
+///
+/// let app = Application::builder()
+///     .container(container)
+///     .scheduler(Arc::new(RwLock::new(scheduler)))
+///     .build()
+///     .await?;
+/// ```
+#[derive(Default)]
+pub struct Scheduler {
+    tasks: Vec<ScheduledTask>,
+    loops: Vec<JoinHandle<()>>,
+    runs: Arc<Mutex<JoinSet<()>>>,
+    grace_period: Option<Duration>,
+}
+
+impl Scheduler {
+    /// Create an empty scheduler
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a task that runs every `interval` with the default overlap policy
+    pub fn every<F, Fut>(
+        &mut self,
+        interval: Duration,
+        name: impl Into<String>,
+        task: F,
+    ) -> &mut Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.add(ScheduledTask::every(interval, name, task))
+    }
+
+    /// Register a task driven by a 5-field cron expression with the default overlap policy
+    pub fn cron<F, Fut>(
+        &mut self,
+        expression: &str,
+        name: impl Into<String>,
+        task: F,
+    ) -> Result<&mut Self, ScheduleError>
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        Ok(self.add(ScheduledTask::cron(expression, name, task)?))
+    }
+
+    /// Register a fully configured task
+    pub fn add(&mut self, task: ScheduledTask) -> &mut Self {
+        self.tasks.push(task);
+        self
+    }
+
+    /// On shutdown, wait up to `grace_period` for in-progress runs before aborting them
+    pub fn wait_for_running(&mut self, grace_period: Duration) -> &mut Self {
+        self.grace_period = Some(grace_period);
+        self
+    }
+
+    /// Get the number of registered tasks
+    pub fn task_count(&self) -> usize {
+        self.tasks.len()
+    }
+
+    /// Whether the scheduling loops are currently running
+    pub fn is_running(&self) -> bool {
+        !self.loops.is_empty()
+    }
+
+    /// Start a scheduling loop for every registered task
+    ///
+    /// Calling this while already running is a no-op.
+    pub fn start(&mut self) {
+        if self.is_running() {
+            return;
+        }
+
+        for task in &self.tasks {
+            tracing::debug!("Scheduling task: {}", task.name);
+            let handle = tokio::spawn(run_loop(
+                task.name.clone(),
+                task.trigger.clone(),
+                task.overlap,
+                task.task.clone(),
+                self.runs.clone(),
+            ));
+            self.loops.push(handle);
+        }
+
+        tracing::info!("Scheduler started ({} tasks)", self.tasks.len());
+    }
+
+    /// Stop all scheduling loops and in-progress runs
+    pub async fn stop(&mut self) {
+        for handle in self.loops.drain(..) {
+            handle.abort();
+        }
+
+        let mut runs = std::mem::take(&mut *self.runs.lock().unwrap());
+        if let Some(grace_period) = self.grace_period {
+            let drained = tokio::time::timeout(grace_period, async {
+                while runs.join_next().await.is_some() {}
+            })
+            .await;
+            if drained.is_err() {
+                tracing::warn!(
+                    "Scheduled tasks still running after {:?}, aborting",
+                    grace_period
+                );
+            }
+        }
+        runs.shutdown().await;
+
+        tracing::info!("Scheduler stopped");
+    }
+}
+
+/// Waits for the next due time of a task
+enum Ticker {
+    Interval(Interval),
+    Cron(CronSchedule),
+}
+
+impl Ticker {
+    fn new(trigger: &Trigger) -> Self {
+        match trigger {
+            Trigger::Every(period) => {
+                let mut interval = tokio::time::interval_at(Instant::now() + *period, *period);
+                interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+                Ticker::Interval(interval)
+            }
+            Trigger::Cron(schedule) => Ticker::Cron(schedule.clone()),
+        }
+    }
+
+    /// Returns `false` if the task will never be due again
+    async fn wait(&mut self) -> bool {
+        match self {
+            Ticker::Interval(interval) => {
+                interval.tick().await;
+                true
+            }
+            Ticker::Cron(schedule) => {
+                let now = Utc::now();
+                let Some(next) = schedule.next_after(now) else {
+                    return false;
+                };
+                tokio::time::sleep((next - now).to_std().unwrap_or_default()).await;
+                true
+            }
+        }
+    }
+}
+
+async fn run_loop(
+    name: String,
+    trigger: Trigger,
+    overlap: OverlapPolicy,
+    task: TaskFn,
+    runs: Arc<Mutex<JoinSet<()>>>,
+) {
+    // A single permit means "no run in progress" for Skip and Queue
+    let permits = Arc::new(Semaphore::new(1));
+    let mut ticker = Ticker::new(&trigger);
+
+    loop {
+        if !ticker.wait().await {
+            tracing::warn!("Scheduled task {} has no upcoming run, stopping", name);
+            return;
+        }
+
+        let permit = match overlap {
+            OverlapPolicy::Skip => match permits.clone().try_acquire_owned() {
+                Ok(permit) => Some(permit),
+                Err(_) => {
+                    tracing::debug!("Skipping {}: previous run still in progress", name);
+                    continue;
+                }
+            },
+            OverlapPolicy::Queue => match permits.clone().acquire_owned().await {
+                Ok(permit) => Some(permit),
+                Err(_) => return,
+            },
+            OverlapPolicy::Concurrent => None,
+        };
+
+        tracing::debug!("Running scheduled task: {}", name);
+        let run = task();
+        let mut in_flight = runs.lock().unwrap();
+        // Reap finished runs so the set does not grow unbounded
+        while in_flight.try_join_next().is_some() {}
+        in_flight.spawn(async move {
+            run.await;
+            drop(permit);
+        });
+    }
+}
+
+#[async_trait]
+impl OnApplicationBootstrap for Scheduler {
+    async fn on_application_bootstrap(&mut self) -> Result<(), LifecycleError> {
+        self.start();
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl OnApplicationShutdown for Scheduler {
+    async fn on_application_shutdown(&mut self) -> Result<(), LifecycleError> {
+        self.stop().await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_cron_next_after() {
+        let schedule = CronSchedule::parse("*/15 9-17 * * 1-5").unwrap();
+        // Saturday 2024-06-01 10:07 -> Monday 09:00
+        let after = Utc.with_ymd_and_hms(2024, 6, 1, 10, 7, 0).unwrap();
+        let next = schedule.next_after(after).unwrap();
+        assert_eq!(next, Utc.with_ymd_and_hms(2024, 6, 3, 9, 0, 0).unwrap());
+
+        // Monday 09:07 -> Monday 09:15
+        let next = schedule
+            .next_after(next + chrono::Duration::minutes(7))
+            .unwrap();
+        assert_eq!(next, Utc.with_ymd_and_hms(2024, 6, 3, 9, 15, 0).unwrap());
+    }
+
+    #[test]
+    fn test_cron_rejects_invalid_expressions() {
+        assert!(CronSchedule::parse("* * * *").is_err());
+        assert!(CronSchedule::parse("60 * * * *").is_err());
+        assert!(CronSchedule::parse("*/0 * * * *").is_err());
+        assert!(CronSchedule::parse("5-1 * * * *").is_err());
+    }
+
+    #[test]
+    fn test_cron_sunday_as_seven() {
+        let schedule = CronSchedule::parse("0 0 * * 7").unwrap();
+        // Friday 2024-06-07 -> Sunday 2024-06-09
+        let after = Utc.with_ymd_and_hms(2024, 6, 7, 12, 0, 0).unwrap();
+        assert_eq!(
+            schedule.next_after(after).unwrap(),
+            Utc.with_ymd_and_hms(2024, 6, 9, 0, 0, 0).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_every_runs_until_stopped() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let task_counter = counter.clone();
+
+        let mut scheduler = Scheduler::new();
+        scheduler.every(Duration::from_millis(20), "tick", move || {
+            let counter = task_counter.clone();
+            async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+
+        scheduler.start();
+        tokio::time::sleep(Duration::from_millis(110)).await;
+        scheduler.stop().await;
+        let runs = counter.load(Ordering::SeqCst);
+        assert!(runs >= 3, "expected at least 3 runs, got {}", runs);
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(counter.load(Ordering::SeqCst), runs);
+    }
+
+    #[tokio::test]
+    async fn test_skip_policy_drops_overlapping_runs() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let task_counter = counter.clone();
+
+        let mut scheduler = Scheduler::new();
+        scheduler.add(
+            ScheduledTask::every(Duration::from_millis(20), "slow", move || {
+                let counter = task_counter.clone();
+                async move {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                }
+            })
+            .overlap(OverlapPolicy::Skip),
+        );
+
+        scheduler.start();
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        scheduler.stop().await;
+
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+    }
+}
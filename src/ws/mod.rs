@@ -0,0 +1,153 @@
+//! WebSocket routes
+//!
+//! A controller method with `#[ws("/chat")]` takes a [`WebSocketSession`]
+//! next to its usual `#[param]`, `#[query]` and `#[service]` parameters.
+//! Guards and aspects see the upgrade request, and the method runs once the
+//! connection is upgraded, for as long as it keeps the session.
+//!
+//! ```rust,ignore
+//! #[routes(ChatController)]
+//! impl ChatController {
+//!     #[ws("/rooms/{room}")]
+//!     async fn chat(&self, mut session: WebSocketSession, #[param] room: String) {
+//!         while let Some(Ok(message)) = session.recv_json::<ChatMessage>().await {
+//!             self.rooms.post(&room, message);
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! With a [`WsConnectionRegistry`] in the container, every session is
+//! tracked there, so services can broadcast to them, and closed when the
+//! application shuts down.
+
+mod registry;
+
+pub use axum::extract::ws::{CloseFrame, Message};
+pub use registry::WsConnectionRegistry;
+
+use axum::extract::ws::WebSocket;
+use futures_util::SinkExt;
+use futures_util::stream::{SplitStream, StreamExt};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::sync::atomic::{AtomicU64, Ordering};
+use thiserror::Error;
+use tokio::sync::mpsc;
+
+/// An upgraded WebSocket connection, handed to `#[ws]` methods
+///
+/// Messages are sent through a queue, so services holding the
+/// [`WsConnectionRegistry`] can send to the session while its method waits
+/// for the next message. Dropping the session closes the connection.
+pub struct WebSocketSession {
+    id: u64,
+    incoming: SplitStream<WebSocket>,
+    outgoing: mpsc::UnboundedSender<Message>,
+    registry: Option<WsConnectionRegistry>,
+}
+
+impl WebSocketSession {
+    /// Start a session on `socket`, tracked by `registry` if given;
+    /// generated by `#[routes]`
+    #[doc(hidden)]
+    pub fn new(socket: WebSocket, registry: Option<&WsConnectionRegistry>) -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+        let (mut sink, incoming) = socket.split();
+        let (outgoing, mut queue) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Some(message) = queue.recv().await {
+                let closing = matches!(message, Message::Close(_));
+                if sink.send(message).await.is_err() || closing {
+                    return;
+                }
+            }
+            // The session is gone: close the connection properly.
+            let _ = sink.close().await;
+        });
+
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let registry = registry.cloned();
+        if let Some(registry) = &registry {
+            registry.insert(id, outgoing.clone());
+        }
+        Self {
+            id,
+            incoming,
+            outgoing,
+            registry,
+        }
+    }
+
+    /// Identifies the session in the [`WsConnectionRegistry`]
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn send(&self, message: Message) -> Result<(), WsError> {
+        self.outgoing.send(message).map_err(|_| WsError::Closed)
+    }
+
+    pub fn send_text(&self, text: impl Into<String>) -> Result<(), WsError> {
+        self.send(Message::Text(text.into().into()))
+    }
+
+    /// Send `value` as a JSON text message
+    pub fn send_json<T: Serialize>(&self, value: &T) -> Result<(), WsError> {
+        self.send_text(serde_json::to_string(value)?)
+    }
+
+    /// The next text or binary message; `None` once the connection is
+    /// closed
+    ///
+    /// Pings and pongs are skipped.
+    pub async fn recv(&mut self) -> Option<Result<Message, WsError>> {
+        loop {
+            return match self.incoming.next().await? {
+                Ok(Message::Close(_)) => None,
+                Ok(Message::Ping(_) | Message::Pong(_)) => continue,
+                Ok(message) => Some(Ok(message)),
+                Err(e) => Some(Err(WsError::Socket(e))),
+            };
+        }
+    }
+
+    /// The next message, parsed as JSON; `None` once the connection is
+    /// closed
+    pub async fn recv_json<T: DeserializeOwned>(&mut self) -> Option<Result<T, WsError>> {
+        let parsed = match self.recv().await? {
+            Ok(Message::Text(text)) => serde_json::from_str(text.as_str()),
+            Ok(Message::Binary(bytes)) => serde_json::from_slice(&bytes),
+            Ok(_) => unreachable!("recv only returns text and binary messages"),
+            Err(e) => return Some(Err(e)),
+        };
+        Some(parsed.map_err(WsError::from))
+    }
+
+    /// Close the connection with `frame`, or a normal closure
+    pub fn close(self, frame: Option<CloseFrame>) {
+        let _ = self.outgoing.send(Message::Close(frame));
+    }
+}
+
+impl Drop for WebSocketSession {
+    fn drop(&mut self) {
+        if let Some(registry) = &self.registry {
+            registry.remove(self.id);
+        }
+    }
+}
+
+/// A failed WebSocket send or receive
+#[derive(Debug, Error)]
+pub enum WsError {
+    #[error("the WebSocket session is closed")]
+    Closed,
+
+    #[error("WebSocket error: {0}")]
+    Socket(#[from] axum::Error),
+
+    #[error("invalid JSON message: {0}")]
+    Json(#[from] serde_json::Error),
+}
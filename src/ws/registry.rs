@@ -0,0 +1,118 @@
+use crate::di::{Container, Injectable};
+use crate::ws::{CloseFrame, Message};
+use axum::extract::ws::close_code;
+use dashmap::DashMap;
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// The open [`WebSocketSession`]s, for services to send to
+///
+/// Register it in the container (or list it in a module's providers) and
+/// every `#[ws]` session is tracked until it ends.
+/// [`Application::shutdown`] closes those still open.
+///
+/// ```rust,ignore
+/// #[derive(Injectable)]
+/// pub struct Announcements {
+///     sessions: Arc<WsConnectionRegistry>,
+/// }
+///
+/// impl Announcements {
+///     pub fn announce(&self, text: &str) {
+///         self.sessions.broadcast(Message::Text(text.into()));
+///     }
+/// }
+/// ```
+///
+/// [`WebSocketSession`]: crate::ws::WebSocketSession
+/// [`Application::shutdown`]: crate::lifecycle::Application::shutdown
+#[derive(Clone, Default)]
+pub struct WsConnectionRegistry {
+    sessions: Arc<DashMap<u64, mpsc::UnboundedSender<Message>>>,
+}
+
+impl WsConnectionRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How many sessions are open
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    /// The ids of the open sessions
+    pub fn ids(&self) -> Vec<u64> {
+        self.sessions.iter().map(|session| *session.key()).collect()
+    }
+
+    /// Send `message` to the session `id`; `false` if it has ended
+    pub fn send_to(&self, id: u64, message: Message) -> bool {
+        self.sessions
+            .get(&id)
+            .is_some_and(|session| session.send(message).is_ok())
+    }
+
+    /// Send `message` to every open session, returning how many it reached
+    pub fn broadcast(&self, message: Message) -> usize {
+        self.sessions
+            .iter()
+            .filter(|session| session.send(message.clone()).is_ok())
+            .count()
+    }
+
+    /// Send `value` as a JSON text message to every open session
+    pub fn broadcast_json<T: Serialize>(&self, value: &T) -> Result<usize, serde_json::Error> {
+        let text = serde_json::to_string(value)?;
+        Ok(self.broadcast(Message::Text(text.into())))
+    }
+
+    /// Close every open session with a "going away" frame, as when the
+    /// server shuts down
+    pub fn close_all(&self) {
+        let frame = CloseFrame {
+            code: close_code::AWAY,
+            reason: "server shutting down".into(),
+        };
+        self.broadcast(Message::Close(Some(frame)));
+    }
+
+    pub(crate) fn insert(&self, id: u64, session: mpsc::UnboundedSender<Message>) {
+        self.sessions.insert(id, session);
+    }
+
+    pub(crate) fn remove(&self, id: u64) {
+        self.sessions.remove(&id);
+    }
+}
+
+impl Injectable for WsConnectionRegistry {
+    fn inject(_container: &Container) -> crate::Result<Self> {
+        Ok(Self::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn broadcast_skips_ended_sessions() {
+        let registry = WsConnectionRegistry::new();
+        let (open, mut queue) = mpsc::unbounded_channel();
+        let (ended, _) = mpsc::unbounded_channel();
+        registry.insert(1, open);
+        registry.insert(2, ended);
+
+        assert_eq!(registry.broadcast(Message::Text("hi".into())), 1);
+        assert_eq!(queue.try_recv().unwrap(), Message::Text("hi".into()));
+
+        registry.remove(1);
+        assert_eq!(registry.ids(), [2]);
+    }
+}
@@ -0,0 +1,319 @@
+mod common;
+
+use axum::body::{Body, Bytes, to_bytes};
+use axum::http::response::Parts;
+use axum::http::{HeaderMap, HeaderValue, Request};
+use common::AppState;
+use meshestra::aspect::{Aspect, AspectResult, ErrorContext};
+use meshestra::exception::Exception;
+use meshestra::prelude::*;
+use serde::Deserialize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tower::ServiceExt;
+
+/// Toy body signature: the sum of the body's bytes
+fn sign(body: &[u8]) -> String {
+    body.iter().map(|&b| u64::from(b)).sum::<u64>().to_string()
+}
+
+/// Rejects requests whose `x-signature` does not match their body
+struct SignatureAspect;
+
+#[async_trait]
+impl Aspect for SignatureAspect {
+    async fn before(&self, request: &mut Request<Body>) -> AspectResult {
+        let body = std::mem::take(request.body_mut());
+        let bytes = to_bytes(body, 1024)
+            .await
+            .map_err(|e| MeshestraError::Internal(e.to_string()))?;
+        let signature = request
+            .headers()
+            .get("x-signature")
+            .and_then(|value| value.to_str().ok());
+        if signature != Some(sign(&bytes).as_str()) {
+            return Err(MeshestraError::Internal("bad signature".to_string()));
+        }
+        *request.body_mut() = Body::from(bytes);
+        Ok(())
+    }
+}
+
+/// Tags the request with the tenant it belongs to
+struct TenantAspect;
+
+#[async_trait]
+impl Aspect for TenantAspect {
+    async fn before(&self, request: &mut Request<Body>) -> AspectResult {
+        request
+            .headers_mut()
+            .insert("x-tenant", HeaderValue::from_static("acme"));
+        Ok(())
+    }
+}
+
+/// Rejects requests without an `authorization` header
+struct AuthAspect;
+
+#[async_trait]
+impl Aspect for AuthAspect {
+    async fn before(&self, request: &mut Request<Body>) -> AspectResult {
+        if request.headers().contains_key("authorization") {
+            Ok(())
+        } else {
+            Err(MeshestraError::Internal("unauthenticated".to_string()))
+        }
+    }
+}
+
+/// Signs the response body into `x-signature`, for bodies up to 64 bytes
+struct SigningAspect;
+
+#[async_trait]
+impl Aspect for SigningAspect {
+    fn wants_response_body(&self) -> bool {
+        true
+    }
+
+    fn response_body_limit(&self) -> usize {
+        64
+    }
+
+    async fn after_with_body(&self, parts: &mut Parts, body: &mut Bytes) -> AspectResult {
+        let signature = HeaderValue::from_str(&sign(body)).unwrap();
+        parts.headers.insert("x-signature", signature);
+        Ok(())
+    }
+}
+
+/// Raised for a note that doesn't exist
+#[derive(Debug)]
+struct NoteMissing;
+
+impl std::fmt::Display for NoteMissing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("note missing")
+    }
+}
+
+impl std::error::Error for NoteMissing {}
+
+/// Answers `NoteMissing` with a 404 naming the path
+struct NotFoundAspect;
+
+#[async_trait]
+impl Aspect for NotFoundAspect {
+    async fn on_error_ctx(
+        &self,
+        error: &(dyn std::error::Error + Send + Sync + 'static),
+        request: &ErrorContext,
+    ) -> Option<Response> {
+        error.downcast_ref::<NoteMissing>()?;
+        let body = format!("no note at {} {}", request.method, request.uri.path());
+        Some((StatusCode::NOT_FOUND, body).into_response())
+    }
+}
+
+#[derive(Deserialize)]
+struct Note {
+    text: String,
+}
+
+#[controller(path = "/notes")]
+pub struct NoteController {}
+
+#[routes(NoteController)]
+impl NoteController {
+    #[post("/")]
+    #[aspect(SignatureAspect)]
+    #[aspect(TenantAspect)]
+    async fn create(&self, headers: HeaderMap, #[body] note: Note) -> String {
+        let tenant = headers
+            .get("x-tenant")
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("none");
+        format!("{}: {}", tenant, note.text)
+    }
+
+    #[get("/signed")]
+    #[aspect(SigningAspect)]
+    async fn signed(&self) -> &'static str {
+        "signed note"
+    }
+
+    /// 100 bytes, streamed
+    #[get("/signed/stream")]
+    #[aspect(SigningAspect)]
+    async fn signed_stream(&self) -> Body {
+        let chunks = (0..10).map(|_| Ok::<_, std::convert::Infallible>(Bytes::from("0123456789")));
+        Body::from_stream(futures_util::stream::iter(chunks))
+    }
+
+    #[get("/{id}")]
+    #[aspect(NotFoundAspect)]
+    async fn find(&self, #[param] id: u32) -> std::result::Result<String, Exception> {
+        match id {
+            1 => Ok("first note".to_string()),
+            2 => Err(MeshestraError::Internal("storage failed".to_string()))?,
+            _ => Err(NoteMissing)?,
+        }
+    }
+}
+
+/// Counts handler executions
+#[derive(Default)]
+struct Calls(AtomicUsize);
+
+#[controller(path = "/vault")]
+#[aspect(AuthAspect)]
+pub struct VaultController {
+    calls: Arc<Calls>,
+}
+
+#[routes(VaultController)]
+impl VaultController {
+    #[get("/")]
+    async fn list(&self) -> &'static str {
+        self.calls.0.fetch_add(1, Ordering::SeqCst);
+        "secrets"
+    }
+
+    #[post("/")]
+    #[aspect(TenantAspect)]
+    async fn store(&self, headers: HeaderMap) -> String {
+        self.calls.0.fetch_add(1, Ordering::SeqCst);
+        format!("stored for {:?}", headers.get("x-tenant"))
+    }
+}
+
+fn app() -> Router {
+    let mut container = Container::new();
+    container.register(SignatureAspect);
+    container.register(TenantAspect);
+    container.register(NotFoundAspect);
+    container.register(SigningAspect);
+    let controller = NoteController::inject(&container).unwrap();
+
+    NoteController::router(Arc::new(controller)).with_state(AppState {
+        container: Arc::new(container),
+    })
+}
+
+fn vault_app() -> (Router, Arc<Calls>) {
+    let mut container = Container::new();
+    container.register(AuthAspect);
+    container.register(TenantAspect);
+    container.register(Calls::default());
+    let calls = container.resolve::<Calls>().unwrap();
+    let controller = VaultController::inject(&container).unwrap();
+
+    let router = VaultController::router(Arc::new(controller)).with_state(AppState {
+        container: Arc::new(container),
+    });
+    (router, calls)
+}
+
+fn post(body: &'static str, signature: &str) -> Request<Body> {
+    Request::post("/")
+        .header("content-type", "application/json")
+        .header("x-signature", signature)
+        .body(Body::from(body))
+        .unwrap()
+}
+
+async fn text(response: axum::response::Response) -> String {
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    String::from_utf8(bytes.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn aspects_see_the_body_and_the_handler_still_gets_it() {
+    let body = r#"{"text":"hello"}"#;
+
+    let response = app()
+        .oneshot(post(body, &sign(body.as_bytes())))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(text(response).await, "acme: hello");
+}
+
+#[tokio::test]
+async fn aspects_reject_tampered_bodies() {
+    let signature = sign(br#"{"text":"hello"}"#);
+
+    let response = app()
+        .oneshot(post(r#"{"text":"hellO"}"#, &signature))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert!(text(response).await.contains("bad signature"));
+}
+
+#[tokio::test]
+async fn controller_aspects_apply_to_every_route() {
+    let (app, calls) = vault_app();
+
+    for method in ["GET", "POST"] {
+        let request = Request::builder()
+            .method(method)
+            .uri("/")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(
+            response.status(),
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "{}",
+            method
+        );
+    }
+    assert_eq!(calls.0.load(Ordering::SeqCst), 0);
+
+    let request = Request::post("/")
+        .header("authorization", "Bearer token")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(text(response).await, r#"stored for Some("acme")"#);
+    assert_eq!(calls.0.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn aspects_can_answer_errors_with_a_response() {
+    let get = |uri: &str| Request::get(uri).body(Body::empty()).unwrap();
+
+    let response = app().oneshot(get("/7")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(text(response).await, "no note at GET /7");
+
+    let response = app().oneshot(get("/2")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+    let response = app().oneshot(get("/1")).await.unwrap();
+    assert_eq!(text(response).await, "first note");
+}
+
+#[tokio::test]
+async fn aspects_can_read_the_response_body() {
+    let request = Request::get("/signed").body(Body::empty()).unwrap();
+
+    let response = app().oneshot(request).await.unwrap();
+
+    assert_eq!(
+        response.headers()["x-signature"],
+        sign(b"signed note").as_str()
+    );
+    assert_eq!(text(response).await, "signed note");
+}
+
+#[tokio::test]
+async fn bodies_over_the_limit_pass_through_unsigned() {
+    let request = Request::get("/signed/stream").body(Body::empty()).unwrap();
+
+    let response = app().oneshot(request).await.unwrap();
+
+    assert!(!response.headers().contains_key("x-signature"));
+    assert_eq!(text(response).await, "0123456789".repeat(10));
+}
@@ -0,0 +1,121 @@
+//! Bodies reach aspect-wrapped handlers without being copied
+//!
+//! Its own test binary, for the allocator counting what each request
+//! allocates.
+
+use axum::body::{Body, Bytes};
+use axum::http::Request;
+use meshestra::aspect::{Aspect, AspectResult};
+use meshestra::prelude::*;
+use meshestra::testing::TestApp;
+use serde::Deserialize;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+/// Counts the bytes allocated on each thread
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATED: Cell<usize> = const { Cell::new(0) };
+}
+
+fn count(bytes: usize) {
+    let _ = ALLOCATED.try_with(|allocated| allocated.set(allocated.get() + bytes));
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count(layout.size());
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count(new_size.saturating_sub(layout.size()));
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const PAYLOAD: usize = 1024 * 1024;
+
+/// Deliberately not `Clone`: aspects must not need to copy it
+#[derive(Deserialize)]
+pub struct Upload {
+    data: String,
+}
+
+pub struct TagAspect;
+
+#[async_trait]
+impl Aspect for TagAspect {
+    async fn before(&self, request: &mut Request<Body>) -> AspectResult {
+        request
+            .headers_mut()
+            .insert("x-tag", "upload".parse().unwrap());
+        Ok(())
+    }
+}
+
+#[controller(path = "/uploads")]
+pub struct UploadController {}
+
+#[routes(UploadController)]
+impl UploadController {
+    #[post("/plain")]
+    async fn plain(&self, #[body] upload: Upload) -> String {
+        upload.data.len().to_string()
+    }
+
+    #[post("/aspected")]
+    #[aspect(TagAspect)]
+    async fn aspected(&self, #[body] upload: Upload) -> String {
+        upload.data.len().to_string()
+    }
+}
+
+#[module(controllers = [UploadController])]
+pub struct AppModule;
+
+/// The bytes allocated while `path` answers the 1 MiB `body`
+async fn allocated(app: &TestApp, path: &str, body: &Bytes) -> usize {
+    let before = ALLOCATED.with(Cell::get);
+    let response = app
+        .post(path)
+        .header("content-type", "application/json")
+        .body(body.clone())
+        .await;
+    let allocated = ALLOCATED.with(Cell::get) - before;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.text(), PAYLOAD.to_string());
+    allocated
+}
+
+#[tokio::test]
+async fn aspects_do_not_copy_the_body() {
+    let app = TestApp::builder()
+        .provide(TagAspect)
+        .build::<AppModule>()
+        .await
+        .unwrap();
+    let body = Bytes::from(format!(r#"{{"data":"{}"}}"#, "a".repeat(PAYLOAD)));
+
+    // Warm up whatever is set up on first use.
+    allocated(&app, "/uploads/plain", &body).await;
+    allocated(&app, "/uploads/aspected", &body).await;
+
+    let plain = allocated(&app, "/uploads/plain", &body).await;
+    let aspected = allocated(&app, "/uploads/aspected", &body).await;
+    assert!(
+        aspected < plain + PAYLOAD / 2,
+        "the aspect-wrapped route allocated {} bytes, the plain one {}",
+        aspected,
+        plain
+    );
+}
@@ -0,0 +1,15 @@
+//! Shared by the integration tests
+
+use meshestra::prelude::*;
+
+/// Router state serving a test's container
+#[derive(Clone)]
+pub struct AppState {
+    pub container: Arc<Container>,
+}
+
+impl HasContainer for AppState {
+    fn get_container(&self) -> &Container {
+        &self.container
+    }
+}
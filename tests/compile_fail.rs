@@ -0,0 +1,7 @@
+//! Misuse of the attribute and derive macros, rejected at compile time
+
+#[test]
+fn invalid_routes() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,142 @@
+mod common;
+
+use axum::body::{Body, to_bytes};
+use axum::http::Request;
+use common::AppState;
+use meshestra::prelude::*;
+use tower::ServiceExt;
+
+pub struct SlugConfig {
+    separator: char,
+}
+
+pub trait Clock: Send + Sync {
+    fn year(&self) -> u32;
+}
+
+pub struct FixedClock;
+
+impl Clock for FixedClock {
+    fn year(&self) -> u32 {
+        2024
+    }
+}
+
+#[derive(Injectable)]
+#[injectable(constructor = "new")]
+pub struct SlugService {
+    separator: String,
+    clock: Arc<dyn Clock>,
+}
+
+impl SlugService {
+    fn new(config: Arc<SlugConfig>, clock: Arc<dyn Clock>) -> Self {
+        SlugService {
+            separator: config.separator.to_string(),
+            clock,
+        }
+    }
+
+    fn slug(&self, title: &str) -> String {
+        let words: Vec<_> = title.split_whitespace().map(str::to_lowercase).collect();
+        format!(
+            "{}{}{}",
+            self.clock.year(),
+            self.separator,
+            words.join(&self.separator)
+        )
+    }
+}
+
+/// Refuses a separator that is a letter
+#[derive(Injectable)]
+#[injectable(constructor = "checked")]
+pub struct CheckedSlugService {
+    _config: Arc<SlugConfig>,
+}
+
+impl CheckedSlugService {
+    fn checked(config: Arc<SlugConfig>) -> Result<Self> {
+        if config.separator.is_alphabetic() {
+            return Err(MeshestraError::Internal("letter separator".to_string()));
+        }
+        Ok(CheckedSlugService { _config: config })
+    }
+}
+
+#[controller(path = "/slugs", constructor = "new")]
+pub struct SlugController {
+    slugs: Lazy<SlugService>,
+    prefix: String,
+}
+
+impl SlugController {
+    fn new(slugs: Lazy<SlugService>, config: Arc<SlugConfig>) -> Self {
+        SlugController {
+            slugs,
+            prefix: format!("{}posts{}", config.separator, config.separator),
+        }
+    }
+}
+
+#[routes(SlugController)]
+impl SlugController {
+    #[get("/{title}")]
+    async fn slug(&self, #[param] title: String) -> String {
+        format!("{}{}", self.prefix, self.slugs.slug(&title))
+    }
+}
+
+fn container(separator: char) -> Container {
+    let mut container = Container::new();
+    container.register(SlugConfig { separator });
+    container.register(FixedClock);
+    container.register_trait::<dyn Clock, FixedClock, _>(|clock| clock as Arc<dyn Clock>);
+    container
+}
+
+fn app_container() -> Container {
+    let mut container = container('-');
+    let slugs = SlugService::inject(&container).unwrap();
+    container.register(slugs);
+    container
+}
+
+#[test]
+fn constructor_parameters_are_resolved() {
+    let slugs = SlugService::inject(&container('-')).unwrap();
+
+    assert_eq!(slugs.slug("Hello Big World"), "2024-hello-big-world");
+}
+
+#[test]
+fn constructor_errors_fail_the_injection() {
+    assert!(CheckedSlugService::inject(&container('_')).is_ok());
+
+    let error = CheckedSlugService::inject(&container('x')).err().unwrap();
+    assert_eq!(error.to_string(), "Internal error: letter separator");
+}
+
+#[test]
+fn missing_constructor_parameter_fails_the_injection() {
+    let error = SlugService::inject(&Container::new()).err().unwrap();
+
+    assert!(matches!(error, MeshestraError::DependencyNotFound { .. }));
+}
+
+#[tokio::test]
+async fn controller_is_built_by_its_constructor() {
+    let container = app_container();
+    let controller = SlugController::inject(&container).unwrap();
+    let app = SlugController::router(Arc::new(controller)).with_state(AppState {
+        container: Arc::new(container),
+    });
+
+    let request = Request::get("/Release%20Notes")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(bytes, "-posts-2024-release-notes");
+}
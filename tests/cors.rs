@@ -0,0 +1,195 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, header};
+use common::AppState;
+use meshestra::prelude::*;
+use std::time::Duration;
+use tower::ServiceExt;
+
+#[controller(path = "/posts")]
+pub struct PostController {}
+
+#[routes(PostController)]
+impl PostController {
+    #[get("/")]
+    async fn list(&self) -> &'static str {
+        "posts"
+    }
+
+    #[post("/")]
+    async fn create(&self) -> &'static str {
+        "created"
+    }
+}
+
+#[controller(path = "/admin")]
+#[cors(origins = "https://admin.example.com", credentials = true)]
+pub struct AdminController {}
+
+#[routes(AdminController)]
+impl AdminController {
+    #[get("/")]
+    async fn dashboard(&self) -> &'static str {
+        "dashboard"
+    }
+}
+
+#[module(controllers = [PostController, AdminController])]
+pub struct AppModule;
+
+fn config() -> CorsConfig {
+    CorsConfig::new()
+        .allow_origin("https://app.example.com")
+        .allow_origin("https://*.preview.example.com")
+        .allow_origin_regex(r"^https://tenant-\d+\.example\.org$")
+        .unwrap()
+        .allow_header("content-type")
+        .max_age(Duration::from_secs(600))
+}
+
+async fn app(config: CorsConfig) -> Router {
+    let app = Application::builder()
+        .container(AppModule::create_container().unwrap())
+        .module::<AppModule>()
+        .cors(config)
+        .build()
+        .await
+        .unwrap();
+    let state = AppState {
+        container: app.container().clone(),
+    };
+    app.router::<AppModule, AppState>()
+        .unwrap()
+        .with_state(state)
+}
+
+async fn get(app: Router, uri: &str, origin: &str) -> Response {
+    let request = Request::get(uri)
+        .header(header::ORIGIN, origin)
+        .body(Body::empty())
+        .unwrap();
+    app.oneshot(request).await.unwrap()
+}
+
+fn preflight(uri: &str, origin: &str) -> Request<Body> {
+    Request::options(uri)
+        .header(header::ORIGIN, origin)
+        .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+        .header(header::ACCESS_CONTROL_REQUEST_HEADERS, "content-type")
+        .body(Body::empty())
+        .unwrap()
+}
+
+#[tokio::test]
+async fn preflight_is_answered_with_the_allowed_methods_and_headers() {
+    let app = app(config()).await;
+
+    let response = app
+        .oneshot(preflight("/posts", "https://app.example.com"))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let headers = response.headers();
+    assert_eq!(
+        headers[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+        "https://app.example.com"
+    );
+    assert!(
+        headers[header::ACCESS_CONTROL_ALLOW_METHODS]
+            .to_str()
+            .unwrap()
+            .contains("POST")
+    );
+    assert_eq!(
+        headers[header::ACCESS_CONTROL_ALLOW_HEADERS],
+        "content-type"
+    );
+    assert_eq!(headers[header::ACCESS_CONTROL_MAX_AGE], "600");
+}
+
+#[tokio::test]
+async fn preflight_from_an_unknown_origin_is_refused() {
+    let app = app(config()).await;
+
+    let response = app
+        .oneshot(preflight("/posts", "https://evil.com"))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert!(
+        !response
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+    );
+}
+
+#[tokio::test]
+async fn allowed_origins_can_read_responses() {
+    let app = app(config()).await;
+
+    for origin in [
+        "https://app.example.com",
+        "https://pr-12.preview.example.com",
+        "https://tenant-7.example.org",
+    ] {
+        let response = get(app.clone(), "/posts", origin).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            origin
+        );
+        assert_eq!(response.headers()[header::VARY], "origin");
+    }
+
+    let response = get(app, "/posts", "https://tenant-x.example.org").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(
+        !response
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+    );
+}
+
+#[tokio::test]
+async fn controller_override_replaces_the_origins() {
+    let app = app(config()).await;
+
+    let response = get(app.clone(), "/admin", "https://admin.example.com").await;
+    assert_eq!(
+        response.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+        "https://admin.example.com"
+    );
+    assert_eq!(
+        response.headers()[header::ACCESS_CONTROL_ALLOW_CREDENTIALS],
+        "true"
+    );
+
+    let response = get(app.clone(), "/admin", "https://app.example.com").await;
+    assert!(
+        !response
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+    );
+
+    let response = get(app, "/posts", "https://app.example.com").await;
+    assert!(
+        !response
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_CREDENTIALS)
+    );
+}
+
+#[tokio::test]
+async fn credentials_for_any_origin_fail_the_build() {
+    let result = Application::builder()
+        .container(AppModule::create_container().unwrap())
+        .module::<AppModule>()
+        .cors(CorsConfig::new().allow_origin("*").allow_credentials(true))
+        .build()
+        .await;
+
+    assert!(result.is_err());
+}
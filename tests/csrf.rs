@@ -0,0 +1,220 @@
+#![cfg(feature = "session")]
+
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, header};
+use common::AppState;
+use meshestra::prelude::*;
+use serde::Deserialize;
+use tower::ServiceExt;
+
+const SECRET: &str = "an-example-secret-of-32-bytes-at-least";
+
+#[derive(Deserialize)]
+struct Profile {
+    name: String,
+}
+
+#[controller(path = "/account")]
+pub struct AccountController {}
+
+#[routes(AccountController)]
+impl AccountController {
+    #[get("/form")]
+    async fn form(&self, #[csrf_token] token: CsrfToken) -> String {
+        token.to_string()
+    }
+
+    #[post("/profile")]
+    async fn update(&self, #[form] profile: Profile) -> String {
+        profile.name
+    }
+
+    #[post("/login")]
+    async fn login(&self, #[session] session: Session) -> &'static str {
+        session.regenerate_id();
+        session.insert("user", "ada").unwrap();
+        "welcome"
+    }
+
+    #[post("/webhook")]
+    #[csrf_exempt]
+    async fn webhook(&self) -> &'static str {
+        "received"
+    }
+}
+
+#[module(controllers = [AccountController])]
+pub struct AppModule;
+
+/// Sends requests to the application, keeping its session cookie
+struct Browser {
+    app: Router,
+    cookie: Option<String>,
+}
+
+impl Browser {
+    async fn new() -> Self {
+        let app = Application::builder()
+            .container(AppModule::create_container().unwrap())
+            .module::<AppModule>()
+            .sessions(SessionModule::new(SessionConfig::new(SECRET)))
+            .csrf(CsrfConfig::new())
+            .build()
+            .await
+            .unwrap();
+        let state = AppState {
+            container: app.container().clone(),
+        };
+        let app = app
+            .router::<AppModule, AppState>()
+            .unwrap()
+            .with_state(state);
+        Self { app, cookie: None }
+    }
+
+    async fn send(&mut self, request: axum::http::request::Builder, body: &str) -> (u16, String) {
+        let request = match &self.cookie {
+            Some(cookie) => request.header(header::COOKIE, cookie),
+            None => request,
+        };
+        let response = self
+            .app
+            .clone()
+            .oneshot(request.body(Body::from(body.to_string())).unwrap())
+            .await
+            .unwrap();
+        if let Some(set_cookie) = response.headers().get(header::SET_COOKIE) {
+            let set_cookie = set_cookie.to_str().unwrap();
+            self.cookie = Some(set_cookie.split(';').next().unwrap().to_string());
+        }
+        let status = response.status().as_u16();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    async fn token(&mut self) -> String {
+        let (status, token) = self.send(Request::get("/account/form"), "").await;
+        assert_eq!(status, 200);
+        token
+    }
+
+    async fn post_profile(&mut self, token: Option<&str>) -> (u16, String) {
+        let mut request = Request::post("/account/profile")
+            .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded");
+        if let Some(token) = token {
+            request = request.header("x-csrf-token", token);
+        }
+        self.send(request, "name=ada").await
+    }
+}
+
+#[tokio::test]
+async fn requests_with_the_token_in_the_header_are_accepted() {
+    let mut browser = Browser::new().await;
+    let token = browser.token().await;
+
+    let (status, body) = browser.post_profile(Some(&token)).await;
+
+    assert_eq!(status, 200);
+    assert_eq!(body, "ada");
+    // The token stays the same for the session.
+    assert_eq!(browser.token().await, token);
+}
+
+#[tokio::test]
+async fn requests_with_the_token_in_the_form_are_accepted() {
+    let mut browser = Browser::new().await;
+    let token = browser.token().await;
+
+    let request = Request::post("/account/profile")
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded");
+    let (status, body) = browser
+        .send(request, &format!("name=ada&_csrf={}", token))
+        .await;
+
+    assert_eq!(status, 200);
+    assert_eq!(body, "ada", "the form reaches the handler whole");
+}
+
+#[tokio::test]
+async fn requests_without_a_valid_token_are_forbidden() {
+    let mut browser = Browser::new().await;
+    let token = browser.token().await;
+
+    let (status, body) = browser.post_profile(None).await;
+    assert_eq!(status, 403);
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["success"], false);
+    assert_eq!(body["error"]["message"], "invalid CSRF token");
+
+    let mut forged = token.clone();
+    forged.replace_range(..1, if token.starts_with('0') { "1" } else { "0" });
+    let (status, _) = browser.post_profile(Some(&forged)).await;
+    assert_eq!(status, 403);
+
+    // A token is only good with its own session.
+    let mut other = Browser::new().await;
+    other.token().await;
+    let (status, _) = other.post_profile(Some(&token)).await;
+    assert_eq!(status, 403);
+}
+
+#[tokio::test]
+async fn oversized_forms_are_rejected_before_the_token_is_read() {
+    let mut browser = Browser::new().await;
+    let token = browser.token().await;
+
+    let request = Request::post("/account/profile")
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded");
+    let name = "a".repeat(64 * 1024);
+    let (status, _) = browser
+        .send(request, &format!("name={}&_csrf={}", name, token))
+        .await;
+
+    assert_eq!(status, 413);
+}
+
+#[tokio::test]
+async fn safe_methods_and_exempt_routes_are_not_checked() {
+    let mut browser = Browser::new().await;
+
+    let (status, _) = browser.send(Request::get("/account/form"), "").await;
+    assert_eq!(status, 200);
+
+    let (status, body) = browser.send(Request::post("/account/webhook"), "").await;
+    assert_eq!(status, 200);
+    assert_eq!(body, "received");
+}
+
+#[tokio::test]
+async fn regenerate_id_rotates_the_token() {
+    let mut browser = Browser::new().await;
+    let before = browser.token().await;
+
+    let request = Request::post("/account/login").header("x-csrf-token", &before);
+    let (status, _) = browser.send(request, "").await;
+    assert_eq!(status, 200);
+
+    let (status, _) = browser.post_profile(Some(&before)).await;
+    assert_eq!(status, 403);
+    let after = browser.token().await;
+    assert_ne!(after, before);
+    let (status, _) = browser.post_profile(Some(&after)).await;
+    assert_eq!(status, 200);
+}
+
+#[tokio::test]
+async fn csrf_without_sessions_fails_the_build() {
+    let result = Application::builder()
+        .container(AppModule::create_container().unwrap())
+        .module::<AppModule>()
+        .csrf(CsrfConfig::new())
+        .build()
+        .await;
+
+    assert!(result.is_err());
+}
@@ -0,0 +1,149 @@
+mod common;
+
+use axum::body::{Body, to_bytes};
+use axum::http::Request;
+use common::AppState;
+use meshestra::lifecycle::{DIAGNOSTICS_ENABLED_KEY, DIAGNOSTICS_PATH};
+use meshestra::prelude::*;
+use serde_json::Value;
+use tokio::sync::RwLock;
+use tower::ServiceExt;
+
+#[derive(Injectable)]
+pub struct Greeter {}
+
+#[controller(path = "/users")]
+pub struct UserController {
+    greeter: Arc<Greeter>,
+}
+
+#[routes(UserController)]
+impl UserController {
+    #[get("/{name}")]
+    async fn greet(&self, #[param] name: String) -> String {
+        let _ = &self.greeter;
+        name
+    }
+}
+
+#[controller(path = "/")]
+pub struct StatusController {}
+
+#[routes(StatusController)]
+impl StatusController {
+    #[get("/status")]
+    async fn status(&self) -> &'static str {
+        "ok"
+    }
+}
+
+#[module(controllers = [UserController], providers = [Greeter])]
+pub struct UserModule;
+
+#[module(imports = [UserModule], controllers = [StatusController])]
+pub struct AppModule;
+
+/// Never registered
+pub struct Transport;
+
+/// Declared without its transport
+#[derive(Injectable)]
+pub struct Mailer {
+    _transport: Arc<Transport>,
+}
+
+pub struct Cache;
+
+#[async_trait]
+impl OnModuleInit for Cache {
+    async fn on_module_init(&mut self) -> std::result::Result<(), LifecycleError> {
+        Ok(())
+    }
+}
+
+async fn application(config: ConfigService) -> Application {
+    let mut container = AppModule::create_container().unwrap();
+    container.register(config);
+    Application::builder()
+        .container(container)
+        .module::<AppModule>()
+        .on_init(Arc::new(RwLock::new(Cache)), "Cache")
+        .print_banner(true)
+        .build()
+        .await
+        .unwrap()
+}
+
+/// The status and body of a GET request to the diagnostics route
+async fn get_diagnostics(app: &Application) -> (StatusCode, String) {
+    let router = app
+        .router::<AppModule, AppState>()
+        .unwrap()
+        .with_state(AppState {
+            container: app.container().clone(),
+        });
+    let request = Request::get(DIAGNOSTICS_PATH).body(Body::empty()).unwrap();
+    let response = router.oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn the_report_counts_what_the_application_is_made_of() {
+    let config = ConfigService::builder().profile("test").build().unwrap();
+    let app = application(config).await;
+    let report = app.diagnostics();
+
+    assert_eq!(report.version, env!("CARGO_PKG_VERSION"));
+    assert_eq!(report.profile.as_deref(), Some("test"));
+    assert_eq!(report.modules, 2);
+    assert_eq!(report.providers, app.container().len());
+    assert_eq!(report.controllers, 2);
+    assert_eq!(report.routes, 2);
+    assert_eq!(report.lifecycle_hooks.on_module_init, 1);
+    assert_eq!(report.lifecycle_hooks.on_module_destroy, 0);
+    assert!(report.warnings.is_empty());
+}
+
+#[tokio::test]
+async fn the_report_warns_of_unregistered_dependencies() {
+    let mut container = Container::new();
+    container.declare::<Mailer>();
+    let app = Application::builder()
+        .container(container)
+        .validate_dependencies(false)
+        .build()
+        .await
+        .unwrap();
+
+    let warnings = app.diagnostics().warnings;
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains("Mailer needs"));
+    assert!(warnings[0].ends_with("Transport"));
+}
+
+#[tokio::test]
+async fn the_diagnostics_route_is_off_by_default() {
+    let app = application(ConfigService::new()).await;
+
+    assert_eq!(app.routes().len(), 2);
+    assert_eq!(get_diagnostics(&app).await.0, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn the_config_turns_on_the_diagnostics_route() {
+    let config = ConfigService::new();
+    config.set(DIAGNOSTICS_ENABLED_KEY, "true");
+    let app = application(config).await;
+
+    let route = app.routes().last().unwrap();
+    assert_eq!(route.path, DIAGNOSTICS_PATH);
+    assert_eq!(route.handler, "Application::diagnostics");
+
+    let (status, body) = get_diagnostics(&app).await;
+    assert_eq!(status, StatusCode::OK);
+    let report: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(report["controllers"], 2);
+    assert_eq!(report["routes"], 3);
+}
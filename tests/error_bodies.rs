@@ -0,0 +1,129 @@
+use axum::body::to_bytes;
+use meshestra::exception::{ArgumentsHost, default_response};
+use meshestra::prelude::*;
+use meshestra::transactional::TransactionError;
+use serde_json::{Value, json};
+
+async fn render(response: Response) -> (u16, Value) {
+    let status = response.status().as_u16();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap())
+}
+
+fn envelope(code: &str, message: &str) -> Value {
+    json!({ "error": { "code": code, "message": message }, "success": false })
+}
+
+#[tokio::test]
+async fn meshestra_errors() {
+    let cases = [
+        (
+            MeshestraError::DependencyNotFound {
+                type_name: "Db".to_string(),
+            },
+            envelope("DependencyNotFound", "Dependency not found: Db"),
+        ),
+        (
+            MeshestraError::DowncastFailed {
+                type_name: "Db".to_string(),
+            },
+            envelope("DowncastFailed", "Failed to downcast type: Db"),
+        ),
+        (
+            MeshestraError::CircularDependency {
+                cycle: "A -> B -> A".to_string(),
+            },
+            envelope(
+                "CircularDependency",
+                "Circular dependency detected: A -> B -> A",
+            ),
+        ),
+        (
+            MeshestraError::ScopeMismatch {
+                message: "request in singleton".to_string(),
+            },
+            envelope("ScopeMismatch", "Scope mismatch: request in singleton"),
+        ),
+        (
+            MeshestraError::ModuleRegistrationFailed {
+                message: "duplicate".to_string(),
+            },
+            envelope(
+                "ModuleRegistrationFailed",
+                "Module registration failed: duplicate",
+            ),
+        ),
+        (
+            MeshestraError::Transaction(TransactionError::NoTransactionManager),
+            envelope(
+                "TransactionFailed",
+                &TransactionError::NoTransactionManager.to_string(),
+            ),
+        ),
+        (
+            MeshestraError::Internal("oops".to_string()),
+            envelope("InternalError", "oops"),
+        ),
+    ];
+
+    for (error, expected) in cases {
+        assert_eq!(render(error.into_response()).await, (500, expected));
+    }
+}
+
+#[tokio::test]
+async fn http_exceptions() {
+    assert_eq!(
+        render(HttpException::not_found("user").into_response()).await,
+        (404, envelope("NotFound", "user"))
+    );
+    assert_eq!(
+        render(MeshestraError::from(HttpException::too_many_requests("slow down")).into_response())
+            .await,
+        (429, envelope("TooManyRequests", "slow down"))
+    );
+}
+
+#[tokio::test]
+async fn guard_errors() {
+    assert_eq!(
+        render(GuardError::Unauthorized("login required".to_string()).into_response()).await,
+        (401, envelope("Unauthorized", "login required"))
+    );
+    assert_eq!(
+        render(GuardError::Forbidden("admins only".to_string()).into_response()).await,
+        (403, envelope("Forbidden", "admins only"))
+    );
+}
+
+#[tokio::test]
+async fn pipe_errors() {
+    assert_eq!(
+        render(PipeError::Validation("Invalid integer".to_string()).into_response()).await,
+        (
+            400,
+            envelope("BadRequest", "Validation failed: Invalid integer")
+        )
+    );
+    assert_eq!(
+        render(PipeError::Internal("broken".to_string()).into_response()).await,
+        (
+            500,
+            envelope("InternalServerError", "Internal pipe error: broken")
+        )
+    );
+}
+
+#[tokio::test]
+async fn exception_filter_fallback() {
+    let error = std::io::Error::other("disk on fire");
+
+    assert_eq!(
+        render(default_response(&error, &ArgumentsHost::default())).await,
+        (500, envelope("InternalServerError", "disk on fire"))
+    );
+    assert_eq!(
+        render(Exception::new(error).into_response()).await,
+        (500, envelope("InternalServerError", "disk on fire"))
+    );
+}
@@ -0,0 +1,135 @@
+//! Error bodies rendered with an application's own format
+
+use axum::http::header;
+use meshestra::common::response::ApiError;
+use meshestra::exception::Exception;
+use meshestra::guard::GuardContext;
+use meshestra::prelude::*;
+use meshestra::testing::{TestApp, TestResponse};
+use serde_json::{Value, json};
+
+fn problem_details(status: StatusCode, error: &ApiError) -> Response {
+    let body = json!({
+        "type": "about:blank",
+        "title": error.code,
+        "status": status.as_u16(),
+        "detail": error.message,
+    });
+    (
+        status,
+        [(header::CONTENT_TYPE, "application/problem+json")],
+        body.to_string(),
+    )
+        .into_response()
+}
+
+/// What `problem_details` renders
+fn problem(title: &str, status: u16, detail: &str) -> Value {
+    json!({
+        "type": "about:blank",
+        "title": title,
+        "status": status,
+        "detail": detail,
+    })
+}
+
+/// Denies every request
+struct AdminsOnly;
+
+#[async_trait]
+impl Guard for AdminsOnly {
+    async fn can_activate(&self, _ctx: &GuardContext<'_>) -> GuardResult {
+        Err(GuardError::Forbidden("admins only".to_string()))
+    }
+}
+
+#[controller(path = "/accounts")]
+pub struct AccountController {}
+
+#[routes(AccountController)]
+impl AccountController {
+    #[post("/")]
+    async fn create(&self) -> std::result::Result<String, HttpException> {
+        Err(HttpException::conflict("email taken"))
+    }
+
+    #[get("/audit")]
+    #[guard(AdminsOnly)]
+    async fn audit(&self) -> &'static str {
+        "audit"
+    }
+
+    #[get("/balance")]
+    async fn balance(&self) -> Result<String> {
+        Err(MeshestraError::Internal("oops".to_string()))
+    }
+
+    #[get("/statement")]
+    async fn statement(&self) -> std::result::Result<String, Exception> {
+        Err(Exception::new("disk on fire"))
+    }
+}
+
+#[module(controllers = [AccountController])]
+pub struct AccountModule;
+
+async fn app(format: bool) -> TestApp {
+    TestApp::builder()
+        .provide(AdminsOnly)
+        .configure(move |builder| match format {
+            true => builder.error_body_format(problem_details),
+            false => builder,
+        })
+        .build::<AccountModule>()
+        .await
+        .unwrap()
+}
+
+/// Each route's error response
+async fn errors(app: &TestApp) -> Vec<TestResponse> {
+    vec![
+        app.post("/accounts").await,
+        app.get("/accounts/audit").await,
+        app.get("/accounts/balance").await,
+        app.get("/accounts/statement").await,
+    ]
+}
+
+#[tokio::test]
+async fn errors_render_with_the_application_format() {
+    let responses = errors(&app(true).await).await;
+
+    for response in &responses {
+        assert_eq!(
+            response.header("content-type"),
+            Some("application/problem+json")
+        );
+    }
+    let bodies: Vec<Value> = responses.iter().map(|response| response.json()).collect();
+    assert_eq!(
+        bodies,
+        [
+            problem("Conflict", 409, "email taken"),
+            problem("Forbidden", 403, "admins only"),
+            problem("InternalError", 500, "oops"),
+            problem("InternalServerError", 500, "disk on fire"),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn applications_keep_their_own_format() {
+    let formatted = app(true).await;
+    let plain = app(false).await;
+
+    assert_eq!(
+        formatted.post("/accounts").await.header("content-type"),
+        Some("application/problem+json")
+    );
+    let response = plain.post("/accounts").await;
+    assert_eq!(response.header("content-type"), Some("application/json"));
+    assert_eq!(
+        response.json::<Value>(),
+        json!({ "error": { "code": "Conflict", "message": "email taken" }, "success": false })
+    );
+}
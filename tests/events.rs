@@ -0,0 +1,102 @@
+use meshestra::prelude::*;
+use tokio::sync::RwLock;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Lifecycle {
+    StartupCompleted,
+    Ready(u32),
+}
+
+/// Publishes `StartupCompleted` from its init hook
+struct Publisher {
+    events: EventBus,
+}
+
+#[async_trait]
+impl OnModuleInit for Publisher {
+    async fn on_module_init(&mut self) -> std::result::Result<(), LifecycleError> {
+        self.events.publish(Lifecycle::StartupCompleted);
+        Ok(())
+    }
+}
+
+/// Forwards the `Lifecycle` events to a channel from its init hook on
+struct Listener {
+    events: EventBus,
+    received: UnboundedSender<Lifecycle>,
+}
+
+#[async_trait]
+impl OnModuleInit for Listener {
+    async fn on_module_init(&mut self) -> std::result::Result<(), LifecycleError> {
+        let received = self.received.clone();
+        self.events.on(move |event: Lifecycle| {
+            let _ = received.send(event);
+            async {}
+        });
+        Ok(())
+    }
+}
+
+/// Builds an application whose `Publisher` inits before the `Listener`
+async fn start(events: &EventBus) -> UnboundedReceiver<Lifecycle> {
+    let (sender, received) = unbounded_channel();
+    let publisher = Publisher {
+        events: events.clone(),
+    };
+    let listener = Listener {
+        events: events.clone(),
+        received: sender,
+    };
+
+    let mut container = Container::new();
+    container.register(events.clone());
+    Application::builder()
+        .container(container)
+        .on_init(Arc::new(RwLock::new(publisher)), "Publisher")
+        .on_init(Arc::new(RwLock::new(listener)), "Listener")
+        .build()
+        .await
+        .unwrap();
+    received
+}
+
+#[tokio::test]
+async fn events_published_before_a_handler_is_added_are_missed() {
+    let events = EventBus::new();
+    let mut received = start(&events).await;
+
+    events.publish(Lifecycle::Ready(1));
+    assert_eq!(received.recv().await, Some(Lifecycle::Ready(1)));
+}
+
+#[tokio::test]
+async fn replayed_events_reach_handlers_added_later() {
+    let events = EventBus::new();
+    events.configure::<Lifecycle>(EventChannelConfig { replay_last: true });
+    let mut received = start(&events).await;
+
+    events.publish(Lifecycle::Ready(1));
+    assert_eq!(received.recv().await, Some(Lifecycle::StartupCompleted));
+    assert_eq!(received.recv().await, Some(Lifecycle::Ready(1)));
+}
+
+#[tokio::test]
+async fn only_the_last_event_is_replayed() {
+    let events = EventBus::new();
+    events.configure::<Lifecycle>(EventChannelConfig { replay_last: true });
+    events.publish(Lifecycle::Ready(1));
+    events.publish(Lifecycle::Ready(2));
+    assert_eq!(events.last::<Lifecycle>(), Some(Lifecycle::Ready(2)));
+
+    let (sender, mut received) = unbounded_channel();
+    events.on(move |event: Lifecycle| {
+        let _ = sender.send(event);
+        async {}
+    });
+    events.publish(Lifecycle::Ready(3));
+
+    assert_eq!(received.recv().await, Some(Lifecycle::Ready(2)));
+    assert_eq!(received.recv().await, Some(Lifecycle::Ready(3)));
+}
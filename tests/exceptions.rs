@@ -0,0 +1,287 @@
+mod common;
+
+use axum::body::{Body, to_bytes};
+use axum::http::Request;
+use common::AppState;
+use meshestra::prelude::*;
+use std::error::Error;
+use std::fmt;
+use tower::ServiceExt;
+
+/// A domain error raised by services
+#[derive(Debug)]
+struct NotFoundError(String);
+
+impl fmt::Display for NotFoundError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} not found", self.0)
+    }
+}
+
+impl Error for NotFoundError {}
+
+/// Answers `NotFoundError` with a 404, leaves other errors alone
+struct NotFoundFilter;
+
+impl ExceptionFilter for NotFoundFilter {
+    fn catch(&self, error: Box<dyn Error + Send + Sync>, _host: &ArgumentsHost) -> Response {
+        (StatusCode::NOT_FOUND, error.to_string()).into_response()
+    }
+
+    fn catches(&self, error: &(dyn Error + Send + Sync + 'static)) -> bool {
+        error.is::<NotFoundError>()
+    }
+}
+
+/// Raised when a write loses a race
+#[derive(Debug)]
+struct ConflictError;
+
+impl fmt::Display for ConflictError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("version conflict")
+    }
+}
+
+impl Error for ConflictError {}
+
+#[exception_filter]
+pub struct DomainFilter {}
+
+#[exception_filter]
+impl DomainFilter {
+    #[handle(NotFoundError)]
+    fn not_found(&self, err: &NotFoundError, _host: &ArgumentsHost) -> Response {
+        (StatusCode::NOT_FOUND, format!("domain: {}", err)).into_response()
+    }
+
+    #[handle(ConflictError)]
+    fn conflict(&self, err: &ConflictError) -> Response {
+        (StatusCode::CONFLICT, err.to_string()).into_response()
+    }
+}
+
+/// Reports where the failure happened, as plain text when asked to
+struct WhereFilter;
+
+impl ExceptionFilter for WhereFilter {
+    fn catch(&self, error: Box<dyn Error + Send + Sync>, host: &ArgumentsHost) -> Response {
+        let route = host.route().unwrap_or("?");
+        let body = format!(
+            "{} {} ({}): {}",
+            host.method(),
+            route,
+            host.uri().path(),
+            error
+        );
+        match host.header("accept") {
+            Some("text/plain") => (StatusCode::BAD_GATEWAY, body).into_response(),
+            _ => (StatusCode::BAD_GATEWAY, Json(body)).into_response(),
+        }
+    }
+}
+
+fn find(id: &str) -> std::result::Result<String, NotFoundError> {
+    match id {
+        "1" => Ok("alice".to_string()),
+        _ => Err(NotFoundError(format!("user {}", id))),
+    }
+}
+
+fn remove(id: &str) -> meshestra::Result<()> {
+    find(id).map_err(|e| HttpException::not_found(e.to_string()))?;
+    Err(HttpException::forbidden("admins only")
+        .with_details(serde_json::json!({ "role": "admin" })))?
+}
+
+#[controller(path = "/users")]
+pub struct UserController {}
+
+#[routes(UserController)]
+impl UserController {
+    #[get("/{id}")]
+    async fn show(&self, #[param] id: String) -> std::result::Result<String, Exception> {
+        Ok(find(&id)?)
+    }
+
+    #[put("/{id}")]
+    async fn update(&self, #[param] id: String) -> std::result::Result<String, Exception> {
+        find(&id)?;
+        Err(ConflictError)?
+    }
+
+    #[delete("/{id}")]
+    async fn delete(&self, #[param] id: String) -> std::result::Result<String, Exception> {
+        remove(&id)?;
+        Ok("deleted".to_string())
+    }
+
+    #[get("/broken")]
+    async fn broken(&self) -> std::result::Result<String, Exception> {
+        Err(Exception::new("disk on fire"))
+    }
+
+    #[get("/panic")]
+    async fn panics(&self) -> String {
+        panic!("handler bug")
+    }
+}
+
+async fn app() -> Router {
+    app_with(|builder| builder.global_filter::<NotFoundFilter>()).await
+}
+
+async fn app_with(filters: impl FnOnce(ApplicationBuilder) -> ApplicationBuilder) -> Router {
+    app_in(Container::new(), filters).await
+}
+
+async fn app_in(
+    mut container: Container,
+    filters: impl FnOnce(ApplicationBuilder) -> ApplicationBuilder,
+) -> Router {
+    container.register(NotFoundFilter);
+    container.register(DomainFilter {});
+    container.register(WhereFilter);
+    let app = filters(Application::builder().container(container))
+        .build()
+        .await
+        .unwrap();
+    let controller = UserController::inject(app.container()).unwrap();
+
+    UserController::router(Arc::new(controller))
+        .layer(app.interceptor_layer())
+        .layer(app.exception_filter_layer())
+        .with_state(AppState {
+            container: app.container().clone(),
+        })
+}
+
+async fn get(uri: &str) -> (StatusCode, String) {
+    send(app().await, Request::get(uri)).await
+}
+
+async fn send(app: Router, request: axum::http::request::Builder) -> (StatusCode, String) {
+    let request = request.body(Body::empty()).unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, String::from_utf8(bytes.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn global_filter_maps_domain_errors() {
+    assert_eq!(get("/1").await, (StatusCode::OK, "alice".to_string()));
+    assert_eq!(
+        get("/2").await,
+        (StatusCode::NOT_FOUND, "user 2 not found".to_string())
+    );
+}
+
+#[tokio::test]
+async fn handle_methods_dispatch_by_error_type() {
+    let app = app_with(|builder| builder.global_filter::<DomainFilter>()).await;
+
+    assert_eq!(
+        send(app.clone(), Request::get("/2")).await,
+        (
+            StatusCode::NOT_FOUND,
+            "domain: user 2 not found".to_string()
+        )
+    );
+    assert_eq!(
+        send(app.clone(), Request::put("/1")).await,
+        (StatusCode::CONFLICT, "version conflict".to_string())
+    );
+    let (status, _) = send(app, Request::get("/broken")).await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+}
+
+#[tokio::test]
+async fn filters_see_the_failed_request() {
+    let app = app_with(|builder| builder.global_filter::<WhereFilter>()).await;
+
+    assert_eq!(
+        send(
+            app.clone(),
+            Request::put("/2").header("accept", "text/plain")
+        )
+        .await,
+        (
+            StatusCode::BAD_GATEWAY,
+            "PUT /{id} (/2): user 2 not found".to_string()
+        )
+    );
+    let (status, body) = send(app, Request::get("/panic")).await;
+    assert_eq!(status, StatusCode::BAD_GATEWAY);
+    assert!(body.starts_with("\"GET /panic (/panic): "), "{}", body);
+}
+
+#[tokio::test]
+async fn http_exceptions_keep_their_status() {
+    let (status, body) = send(app().await, Request::delete("/2")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["error"]["message"], "user 2 not found");
+
+    let (status, body) = send(app().await, Request::delete("/1")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(
+        body,
+        serde_json::json!({
+            "error": {
+                "code": "Forbidden",
+                "message": "admins only",
+                "details": { "role": "admin" },
+            },
+            "success": false,
+        })
+    );
+}
+
+#[tokio::test]
+async fn http_exception_messages_are_answered_in_the_requested_language() {
+    let mut container = Container::new();
+    container.register(
+        MessageCatalog::new("en").with_messages("fr", [("admins only", "réservé aux admins")]),
+    );
+    container.register_trait::<dyn MessageResolver, MessageCatalog, _>(|catalog| {
+        catalog as Arc<dyn MessageResolver>
+    });
+    let app = app_in(container, |builder| builder).await;
+
+    let message = |body: String| {
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        body["error"]["message"].clone()
+    };
+    let request = Request::delete("/1").header("accept-language", "fr-CA, en;q=0.8");
+    let (status, body) = send(app.clone(), request).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(message(body), "réservé aux admins");
+
+    let request = Request::delete("/1").header("accept-language", "de");
+    assert_eq!(message(send(app.clone(), request).await.1), "admins only");
+    let request = Request::delete("/2").header("accept-language", "fr");
+    assert_eq!(message(send(app, request).await.1), "user 2 not found");
+}
+
+#[tokio::test]
+async fn unclaimed_errors_and_panics_get_a_json_500() {
+    for uri in ["/broken", "/panic"] {
+        let (status, body) = get(uri).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR, "{}", uri);
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["error"]["code"], "InternalServerError", "{}", uri);
+    }
+}
+
+#[tokio::test]
+async fn unresolvable_global_filter_fails_the_build() {
+    let result = Application::builder()
+        .container(Container::new())
+        .global_filter::<NotFoundFilter>()
+        .build()
+        .await;
+
+    assert!(result.is_err());
+}
@@ -0,0 +1,132 @@
+use axum::body::Body;
+use axum::http::Request;
+use meshestra::aspect::{Aspect, AspectResult};
+use meshestra::prelude::*;
+use meshestra::testing::{
+    ExecutionTrace, TestApp, TraceAspect, TraceGuard, TraceInterceptor, TraceLabel,
+};
+
+macro_rules! labels {
+    ($($label:ident = $name:literal),* $(,)?) => {$(
+        pub struct $label;
+
+        impl TraceLabel for $label {
+            const LABEL: &'static str = $name;
+        }
+    )*};
+}
+
+labels! {
+    Logging = "logging",
+    Auth = "auth",
+    Owner = "owner",
+    Timing = "timing",
+    Audit = "audit",
+    Tenant = "tenant",
+    Cache = "cache",
+}
+
+/// A method aspect asking to be outermost
+pub struct Early(ExecutionTrace);
+
+#[async_trait]
+impl Aspect for Early {
+    async fn before(&self, _request: &mut Request<Body>) -> AspectResult {
+        self.0.record("early");
+        Ok(())
+    }
+
+    async fn after(&self, _response: &mut Response) -> AspectResult {
+        self.0.record("early");
+        Ok(())
+    }
+
+    fn priority(&self) -> i32 {
+        -10
+    }
+}
+
+#[controller(path = "/articles")]
+#[guard(TraceGuard<Auth>)]
+#[interceptor(TraceInterceptor::<Timing>)]
+#[aspect(TraceAspect<Audit>)]
+#[aspect(TraceAspect<Tenant>)]
+pub struct ArticleController {
+    trace: Arc<ExecutionTrace>,
+}
+
+#[routes(ArticleController)]
+impl ArticleController {
+    #[get("/")]
+    async fn list(&self) -> &'static str {
+        self.trace.record("handler");
+        "articles"
+    }
+
+    #[get("/{id}")]
+    #[guard(TraceGuard<Owner>)]
+    #[aspect(TraceAspect<Cache>)]
+    #[aspect(Early)]
+    async fn show(&self, #[param] id: String) -> String {
+        self.trace.record("handler");
+        id
+    }
+}
+
+#[module(controllers = [ArticleController])]
+pub struct AppModule;
+
+async fn app(trace: &ExecutionTrace) -> TestApp {
+    TestApp::builder()
+        .provide(trace.clone())
+        .provide(TraceInterceptor::<Logging>::new(trace.clone()))
+        .provide(TraceGuard::<Auth>::new(trace.clone()))
+        .provide(TraceGuard::<Owner>::new(trace.clone()))
+        .provide(TraceInterceptor::<Timing>::new(trace.clone()))
+        .provide(TraceAspect::<Audit>::new(trace.clone()))
+        .provide(TraceAspect::<Tenant>::new(trace.clone()))
+        .provide(TraceAspect::<Cache>::new(trace.clone()))
+        .provide(Early(trace.clone()))
+        .configure(|builder| builder.global_interceptor::<TraceInterceptor<Logging>>())
+        .build::<AppModule>()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn controller_aspects_wrap_the_methods_in_declaration_order() {
+    let trace = ExecutionTrace::new();
+    let app = app(&trace).await;
+
+    assert_eq!(app.get("/articles").await.status(), StatusCode::OK);
+
+    trace.assert_order(&[
+        "logging", "auth", "timing", "audit", "tenant", "handler", "tenant", "audit", "timing",
+        "logging",
+    ]);
+}
+
+#[tokio::test]
+async fn priorities_order_aspects_within_the_method_only() {
+    let trace = ExecutionTrace::new();
+    let app = app(&trace).await;
+
+    assert_eq!(app.get("/articles/7").await.status(), StatusCode::OK);
+
+    trace.assert_order(&[
+        "logging", "auth", "owner", "timing", "audit", "tenant", "early", "cache", "handler",
+        "cache", "early", "tenant", "audit", "timing", "logging",
+    ]);
+}
+
+#[tokio::test]
+async fn the_trace_can_be_cleared_between_requests() {
+    let trace = ExecutionTrace::new();
+    let app = app(&trace).await;
+
+    app.get("/articles").await;
+    trace.clear();
+    app.get("/articles").await;
+
+    assert_eq!(trace.steps().len(), 10);
+}
@@ -0,0 +1,196 @@
+mod common;
+
+use axum::body::{Body, to_bytes};
+use axum::extract::ConnectInfo;
+use axum::http::Request;
+use common::AppState;
+use meshestra::prelude::*;
+use serde::Deserialize;
+use std::net::{IpAddr, SocketAddr};
+use tower::ServiceExt;
+
+#[derive(Deserialize)]
+pub struct LoginForm {
+    username: String,
+    remember: Option<bool>,
+}
+
+#[controller(path = "/whoami")]
+pub struct WhoamiController {}
+
+#[routes(WhoamiController)]
+impl WhoamiController {
+    #[get("/key")]
+    async fn key(&self, #[header("X-Api-Key")] key: String) -> String {
+        format!("key {}", key)
+    }
+
+    #[get("/trace")]
+    async fn trace(&self, #[header("X-Trace-Id")] trace: Option<u64>) -> String {
+        format!("trace {:?}", trace)
+    }
+
+    #[get("/session")]
+    async fn session(
+        &self,
+        #[cookie("session")] sid: String,
+        #[cookie("theme")] theme: Option<String>,
+    ) -> String {
+        format!("session {} ({})", sid, theme.as_deref().unwrap_or("light"))
+    }
+
+    #[post("/login")]
+    async fn login(&self, #[form] form: LoginForm) -> String {
+        format!("{} ({:?})", form.username, form.remember)
+    }
+
+    #[get("/origin")]
+    async fn origin(&self, #[host_param] host: String, #[ip] ip: IpAddr) -> String {
+        format!("{} from {}", host, ip)
+    }
+}
+
+fn router(container: Arc<Container>) -> Router {
+    let controller = WhoamiController::inject(&container).unwrap();
+    WhoamiController::router(Arc::new(controller)).with_state(AppState { container })
+}
+
+fn app() -> Router {
+    router(Arc::new(Container::new()))
+}
+
+/// Send `request` as if it came from 10.0.0.1
+async fn send(app: Router, mut request: Request<Body>) -> (StatusCode, String) {
+    request
+        .extensions_mut()
+        .insert(ConnectInfo(SocketAddr::from(([10, 0, 0, 1], 4000))));
+    let response = app.oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, String::from_utf8(bytes.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn header_is_extracted_by_name() {
+    let request = Request::get("/key")
+        .header("x-api-key", "s3cret")
+        .body(Body::empty())
+        .unwrap();
+
+    assert_eq!(
+        send(app(), request).await,
+        (StatusCode::OK, "key s3cret".to_string())
+    );
+}
+
+#[tokio::test]
+async fn missing_required_header_is_a_400_naming_it() {
+    let (status, body) = send(app(), Request::get("/key").body(Body::empty()).unwrap()).await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["error"]["message"], "missing header \"X-Api-Key\"");
+}
+
+#[tokio::test]
+async fn optional_header_is_parsed_when_present() {
+    assert_eq!(
+        send(app(), Request::get("/trace").body(Body::empty()).unwrap()).await,
+        (StatusCode::OK, "trace None".to_string())
+    );
+
+    let request = Request::get("/trace")
+        .header("X-Trace-Id", "42")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(
+        send(app(), request).await,
+        (StatusCode::OK, "trace Some(42)".to_string())
+    );
+
+    let request = Request::get("/trace")
+        .header("X-Trace-Id", "abc")
+        .body(Body::empty())
+        .unwrap();
+    let (status, body) = send(app(), request).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["error"]["message"], "invalid header \"X-Trace-Id\"");
+}
+
+#[tokio::test]
+async fn cookies_are_read_from_the_cookie_header() {
+    let request = Request::get("/session")
+        .header("cookie", "theme=dark; session=abc123")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(
+        send(app(), request).await,
+        (StatusCode::OK, "session abc123 (dark)".to_string())
+    );
+
+    let request = Request::get("/session")
+        .header("cookie", "theme=dark")
+        .body(Body::empty())
+        .unwrap();
+    let (status, body) = send(app(), request).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["error"]["message"], "missing cookie \"session\"");
+}
+
+#[tokio::test]
+async fn host_and_peer_address_are_extracted() {
+    let request = Request::get("/origin")
+        .header("host", "api.example.com")
+        .header("x-forwarded-for", "203.0.113.7")
+        .body(Body::empty())
+        .unwrap();
+
+    assert_eq!(
+        send(app(), request).await,
+        (StatusCode::OK, "api.example.com from 10.0.0.1".to_string())
+    );
+}
+
+#[tokio::test]
+async fn forwarded_for_is_used_when_trusted() {
+    let app = Application::builder()
+        .container(Container::new())
+        .trust_forwarded_for(true)
+        .build()
+        .await
+        .unwrap();
+    let request = Request::get("/origin")
+        .header("host", "api.example.com")
+        .header("x-forwarded-for", "203.0.113.7, 10.0.0.1")
+        .body(Body::empty())
+        .unwrap();
+
+    assert_eq!(
+        send(router(app.container().clone()), request).await,
+        (
+            StatusCode::OK,
+            "api.example.com from 203.0.113.7".to_string()
+        )
+    );
+}
+
+#[tokio::test]
+async fn form_bodies_are_decoded() {
+    let request = Request::post("/login")
+        .header("content-type", "application/x-www-form-urlencoded")
+        .body(Body::from("username=ada&remember=true"))
+        .unwrap();
+    assert_eq!(
+        send(app(), request).await,
+        (StatusCode::OK, "ada (Some(true))".to_string())
+    );
+
+    let request = Request::post("/login")
+        .header("content-type", "application/json")
+        .body(Body::from(r#"{"username":"ada"}"#))
+        .unwrap();
+    let (status, _) = send(app(), request).await;
+    assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+}
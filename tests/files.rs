@@ -0,0 +1,161 @@
+use axum::body::Bytes;
+use axum::http::HeaderMap;
+use futures_util::stream;
+use meshestra::prelude::*;
+use meshestra::testing::TestApp;
+use std::path::PathBuf;
+
+const REPORT: &str = "name,total\nyearly,120\nmonthly,10\n";
+
+/// The directory reports are served from, with a secret next to it
+pub struct ReportsDir(PathBuf);
+
+impl ReportsDir {
+    fn create(test: &str) -> Self {
+        let base =
+            std::env::temp_dir().join(format!("meshestra-files-{}-{}", test, std::process::id()));
+        let root = base.join("reports");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("yearly.csv"), REPORT).unwrap();
+        std::fs::write(base.join("secret.txt"), "hunter2").unwrap();
+        Self(root)
+    }
+}
+
+#[controller(path = "/reports")]
+pub struct ReportController {
+    dir: Arc<ReportsDir>,
+}
+
+#[routes(ReportController)]
+impl ReportController {
+    #[get("/{name}")]
+    async fn download(&self, #[param] name: String, headers: HeaderMap) -> FileResponse {
+        FileResponse::open_within(&self.dir.0, name)
+            .await
+            .attachment("report.csv")
+            .range(&headers)
+    }
+
+    #[get("/live")]
+    async fn live(&self) -> StreamResponse {
+        let rows = ["name,total\n", "yearly,120\n"]
+            .map(|row| Ok::<_, std::io::Error>(Bytes::from_static(row.as_bytes())));
+        StreamResponse::from_stream(stream::iter(rows), "text/csv").attachment("live.csv")
+    }
+}
+
+#[module(controllers = [ReportController])]
+pub struct ReportModule;
+
+async fn app(test: &str) -> TestApp {
+    TestApp::builder()
+        .provide(ReportsDir::create(test))
+        .build::<ReportModule>()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn files_are_sent_as_attachments() {
+    let response = app("attachment").await.get("/reports/yearly.csv").await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.header("content-type"), Some("text/csv"));
+    assert_eq!(response.header("content-length"), Some("33"));
+    assert_eq!(response.header("accept-ranges"), Some("bytes"));
+    assert_eq!(
+        response.header("content-disposition"),
+        Some("attachment; filename=\"report.csv\"")
+    );
+    assert_eq!(response.text(), REPORT);
+}
+
+#[tokio::test]
+async fn byte_ranges_are_served_partially() {
+    let app = app("ranges").await;
+
+    let response = app
+        .get("/reports/yearly.csv")
+        .header("range", "bytes=11-20")
+        .await;
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(response.header("content-range"), Some("bytes 11-20/33"));
+    assert_eq!(response.header("content-length"), Some("10"));
+    assert_eq!(response.text(), "yearly,120");
+
+    let response = app
+        .get("/reports/yearly.csv")
+        .header("range", "bytes=-3")
+        .await;
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(response.text(), "10\n");
+}
+
+#[tokio::test]
+async fn ranges_past_the_end_are_416() {
+    let app = app("invalid-ranges").await;
+
+    for range in ["bytes=40-", "bytes=33-40"] {
+        let response = app.get("/reports/yearly.csv").header("range", range).await;
+        assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(response.header("content-range"), Some("bytes */33"));
+    }
+}
+
+#[tokio::test]
+async fn multiple_ranges_and_other_units_get_the_whole_file() {
+    let app = app("ignored-ranges").await;
+
+    for range in ["bytes=0-1,5-6", "lines=1-2", "bytes=9-2", "bytes"] {
+        let response = app.get("/reports/yearly.csv").header("range", range).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.header("content-range"), None);
+        assert_eq!(response.text(), REPORT);
+    }
+}
+
+#[tokio::test]
+async fn paths_cannot_leave_the_root() {
+    let app = app("traversal").await;
+
+    assert_eq!(
+        app.get("/reports/missing.csv").await.status(),
+        StatusCode::NOT_FOUND
+    );
+    let response = app.get("/reports/..%2Fsecret.txt").await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert!(!response.text().contains("hunter2"));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn links_out_of_the_root_are_refused() {
+    let dir = ReportsDir::create("links");
+    let link = dir.0.join("linked.txt");
+    let _ = std::fs::remove_file(&link);
+    std::os::unix::fs::symlink(dir.0.join("../secret.txt"), &link).unwrap();
+    let app = TestApp::builder()
+        .provide(dir)
+        .build::<ReportModule>()
+        .await
+        .unwrap();
+
+    assert_eq!(
+        app.get("/reports/linked.txt").await.status(),
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[tokio::test]
+async fn streams_are_sent_as_they_come() {
+    let response = app("stream").await.get("/reports/live").await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.header("content-type"), Some("text/csv"));
+    assert_eq!(
+        response.header("content-disposition"),
+        Some("attachment; filename=\"live.csv\"")
+    );
+    assert_eq!(response.text(), "name,total\nyearly,120\n");
+}
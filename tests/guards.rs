@@ -0,0 +1,521 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::Request;
+use common::AppState;
+use meshestra::guard::{CurrentUser, GuardContext, GuardError, RoleHierarchy};
+use meshestra::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tower::ServiceExt;
+
+/// Lets requests through when they carry an `x-user` header
+struct AuthGuard;
+
+#[async_trait]
+impl Guard for AuthGuard {
+    async fn can_activate(&self, ctx: &GuardContext<'_>) -> GuardResult {
+        if ctx.headers().contains_key("x-user") {
+            Ok(())
+        } else {
+            Err(GuardError::Unauthorized("login required".to_string()))
+        }
+    }
+}
+
+/// Lets requests through when `x-role` is `admin`
+struct AdminGuard;
+
+#[async_trait]
+impl Guard for AdminGuard {
+    async fn can_activate(&self, ctx: &GuardContext<'_>) -> GuardResult {
+        match ctx.headers().get("x-role") {
+            Some(role) if role == "admin" => Ok(()),
+            _ => Err(GuardError::Forbidden("admins only".to_string())),
+        }
+    }
+}
+
+/// Users may only read their own drafts, unless the container says they
+/// are an editor
+struct OwnerGuard;
+
+/// Users allowed to read everyone's drafts
+struct Editors(Vec<&'static str>);
+
+#[async_trait]
+impl Guard for OwnerGuard {
+    async fn can_activate(&self, ctx: &GuardContext<'_>) -> GuardResult {
+        let user = ctx
+            .headers()
+            .get("x-user")
+            .and_then(|user| user.to_str().ok());
+        let editors = ctx.container().and_then(|c| c.resolve::<Editors>().ok());
+
+        let is_owner = user.is_some() && user == ctx.path_param("user");
+        let is_editor = user.is_some_and(|user| editors.is_some_and(|e| e.0.contains(&user)));
+        if is_owner || is_editor {
+            Ok(())
+        } else {
+            Err(GuardError::Forbidden(format!(
+                "{} is not yours",
+                ctx.route().unwrap_or_default()
+            )))
+        }
+    }
+}
+
+/// Counts handler executions
+#[derive(Default)]
+struct Calls(AtomicUsize);
+
+#[controller(path = "/articles")]
+#[guard(AuthGuard)]
+pub struct ArticleController {
+    calls: Arc<Calls>,
+}
+
+#[routes(ArticleController)]
+impl ArticleController {
+    #[get("/")]
+    #[public]
+    async fn list(&self) -> &'static str {
+        self.calls.0.fetch_add(1, Ordering::SeqCst);
+        "list"
+    }
+
+    #[get("/mine")]
+    async fn mine(&self) -> &'static str {
+        self.calls.0.fetch_add(1, Ordering::SeqCst);
+        "mine"
+    }
+
+    #[get("/users/{user}/drafts")]
+    #[guard(OwnerGuard)]
+    async fn drafts(&self, #[param] user: String) -> String {
+        format!("drafts of {}", user)
+    }
+
+    #[delete("/{id}")]
+    #[guard(AdminGuard)]
+    async fn remove(&self, #[param] id: String) -> String {
+        self.calls.0.fetch_add(1, Ordering::SeqCst);
+        format!("removed {}", id)
+    }
+}
+
+fn app() -> (Router, Arc<Calls>) {
+    let mut container = Container::new();
+    container.register(AuthGuard);
+    container.register(AdminGuard);
+    container.register(OwnerGuard);
+    container.register(Editors(vec!["eve"]));
+    container.register(Calls::default());
+    let calls = container.resolve::<Calls>().unwrap();
+    let controller = ArticleController::inject(&container).unwrap();
+
+    let router = ArticleController::router(Arc::new(controller)).with_state(AppState {
+        container: Arc::new(container),
+    });
+    (router, calls)
+}
+
+fn request(method: &str, uri: &str, headers: &[(&str, &str)]) -> Request<Body> {
+    let mut builder = Request::builder().method(method).uri(uri);
+    for (name, value) in headers {
+        builder = builder.header(*name, *value);
+    }
+    builder.body(Body::empty()).unwrap()
+}
+
+#[tokio::test]
+async fn controller_guard_denies_without_running_handler() {
+    let (app, calls) = app();
+
+    let response = app.oneshot(request("GET", "/mine", &[])).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(calls.0.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn controller_guard_allows() {
+    let (app, calls) = app();
+
+    let response = app
+        .oneshot(request("GET", "/mine", &[("x-user", "ana")]))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(calls.0.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn public_route_skips_controller_guards() {
+    let (app, calls) = app();
+
+    let response = app.oneshot(request("GET", "/", &[])).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(calls.0.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn method_guards_run_after_controller_guards() {
+    let (app, calls) = app();
+
+    // The controller guard rejects first.
+    let response = app
+        .clone()
+        .oneshot(request("DELETE", "/7", &[("x-role", "admin")]))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = app
+        .clone()
+        .oneshot(request("DELETE", "/7", &[("x-user", "ana")]))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert_eq!(calls.0.load(Ordering::SeqCst), 0);
+
+    let response = app
+        .oneshot(request(
+            "DELETE",
+            "/7",
+            &[("x-user", "ana"), ("x-role", "admin")],
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(calls.0.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn guards_see_path_params_and_container() {
+    let (app, _) = app();
+
+    for (user, status) in [
+        ("ana", StatusCode::OK),
+        ("bob", StatusCode::FORBIDDEN),
+        ("eve", StatusCode::OK),
+    ] {
+        let response = app
+            .clone()
+            .oneshot(request("GET", "/users/ana/drafts", &[("x-user", user)]))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), status, "user {}", user);
+    }
+}
+
+/// Authenticates from `x-user` and `x-roles` (comma separated)
+struct HeaderUserGuard;
+
+#[async_trait]
+impl Guard for HeaderUserGuard {
+    async fn can_activate(&self, ctx: &GuardContext<'_>) -> GuardResult {
+        let headers = ctx.headers();
+        let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+        let user = header("x-user")
+            .ok_or_else(|| GuardError::Unauthorized("login required".to_string()))?;
+        let roles = header("x-roles").unwrap_or_default();
+
+        ctx.insert(CurrentUser::new(
+            user,
+            roles.split(',').filter(|role| !role.is_empty()),
+        ));
+        Ok(())
+    }
+}
+
+#[controller(path = "/reports")]
+#[guard(HeaderUserGuard)]
+#[roles("viewer")]
+pub struct ReportController {}
+
+#[routes(ReportController)]
+impl ReportController {
+    #[get("/")]
+    async fn list(&self, user: CurrentUser) -> String {
+        format!("reports for {}", user.id)
+    }
+
+    #[delete("/{id}")]
+    #[roles("admin")]
+    async fn remove(&self, #[param] id: String) -> String {
+        format!("removed {}", id)
+    }
+
+    #[get("/health")]
+    #[public]
+    async fn health(&self) -> &'static str {
+        "ok"
+    }
+}
+
+fn reports_app() -> Router {
+    let mut container = Container::new();
+    container.register(HeaderUserGuard);
+    container.register(
+        RoleHierarchy::new()
+            .grant("admin", ["editor"])
+            .grant("editor", ["viewer"]),
+    );
+    let controller = ReportController::inject(&container).unwrap();
+
+    ReportController::router(Arc::new(controller)).with_state(AppState {
+        container: Arc::new(container),
+    })
+}
+
+async fn body_json(response: axum::response::Response) -> serde_json::Value {
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+#[tokio::test]
+async fn roles_follow_the_hierarchy() {
+    let app = reports_app();
+
+    for (roles, status) in [
+        ("viewer", StatusCode::OK),
+        ("admin", StatusCode::OK),
+        ("author", StatusCode::FORBIDDEN),
+    ] {
+        let response = app
+            .clone()
+            .oneshot(request(
+                "GET",
+                "/",
+                &[("x-user", "ana"), ("x-roles", roles)],
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), status, "roles {}", roles);
+    }
+}
+
+#[tokio::test]
+async fn method_roles_replace_controller_roles() {
+    let app = reports_app();
+
+    let response = app
+        .clone()
+        .oneshot(request(
+            "DELETE",
+            "/3",
+            &[("x-user", "ana"), ("x-roles", "editor")],
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let body = body_json(response).await;
+    assert_eq!(
+        body["error"]["message"],
+        "Missing required role, one of: admin"
+    );
+
+    let response = app
+        .oneshot(request(
+            "DELETE",
+            "/3",
+            &[("x-user", "ana"), ("x-roles", "admin")],
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn routes_without_roles_are_unaffected() {
+    let response = reports_app()
+        .oneshot(request("GET", "/health", &[]))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Articles declare no roles; their users carry none.
+    let (app, _) = app();
+    let response = app
+        .oneshot(request("GET", "/mine", &[("x-user", "ana")]))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn handlers_extract_the_current_user() {
+    let response = reports_app()
+        .oneshot(request(
+            "GET",
+            "/",
+            &[("x-user", "ana"), ("x-roles", "viewer")],
+        ))
+        .await
+        .unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert_eq!(&body[..], b"reports for ana");
+}
+
+/// One attribute on the impl block, guards included
+#[derive(Injectable)]
+pub struct DraftController {
+    calls: Arc<Calls>,
+}
+
+#[controller(path = "/drafts")]
+#[guard(AuthGuard)]
+impl DraftController {
+    #[get("/")]
+    async fn list(&self) -> &'static str {
+        self.calls.0.fetch_add(1, Ordering::SeqCst);
+        "drafts"
+    }
+}
+
+#[tokio::test]
+async fn controller_on_the_impl_block_generates_the_router() {
+    let mut container = Container::new();
+    container.register(AuthGuard);
+    container.register(Calls::default());
+    let calls = container.resolve::<Calls>().unwrap();
+    let controller = DraftController::inject(&container).unwrap();
+    let app = Router::new()
+        .nest(
+            DraftController::base_path(),
+            DraftController::router(Arc::new(controller)),
+        )
+        .with_state(AppState {
+            container: Arc::new(container),
+        });
+
+    let response = app
+        .clone()
+        .oneshot(request("GET", "/drafts", &[]))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(calls.0.load(Ordering::SeqCst), 0);
+
+    let response = app
+        .oneshot(request("GET", "/drafts", &[("x-user", "ana")]))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(calls.0.load(Ordering::SeqCst), 1);
+}
+
+/// The tenant a request acts for
+#[derive(Clone)]
+pub struct Tenant {
+    name: String,
+}
+
+/// Authenticates requests carrying `x-tenant`, and lets the others through
+struct TenantGuard;
+
+#[async_trait]
+impl Guard for TenantGuard {
+    async fn can_activate(&self, ctx: &GuardContext<'_>) -> GuardResult {
+        if let Some(name) = ctx.headers().get("x-tenant") {
+            ctx.authenticate(Tenant {
+                name: name.to_str().unwrap().to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[controller(path = "/account")]
+#[guard(TenantGuard)]
+pub struct AccountController {}
+
+#[routes(AccountController)]
+impl AccountController {
+    #[get("/")]
+    async fn show(&self, #[current_user] tenant: CurrentUser<Tenant>) -> String {
+        tenant.name.clone()
+    }
+
+    #[get("/greeting")]
+    async fn greet(&self, #[current_user] tenant: Option<CurrentUser<Tenant>>) -> String {
+        match tenant {
+            Some(tenant) => format!("hello {}", tenant.name),
+            None => "hello stranger".to_string(),
+        }
+    }
+
+    #[get("/principal")]
+    async fn principal(&self, #[current_user] user: CurrentUser) -> String {
+        user.id.clone()
+    }
+}
+
+fn account_app() -> Router {
+    let mut container = Container::new();
+    container.register(TenantGuard);
+    let controller = AccountController::inject(&container).unwrap();
+
+    AccountController::router(Arc::new(controller)).with_state(AppState {
+        container: Arc::new(container),
+    })
+}
+
+async fn text(response: axum::response::Response) -> String {
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    String::from_utf8(bytes.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn current_user_is_the_one_the_guard_authenticated() {
+    let app = account_app();
+
+    let response = app
+        .clone()
+        .oneshot(request("GET", "/", &[("x-tenant", "acme")]))
+        .await
+        .unwrap();
+    assert_eq!(text(response).await, "acme");
+
+    let response = app.oneshot(request("GET", "/", &[])).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn optional_current_user_allows_anonymous_requests() {
+    let app = account_app();
+
+    let response = app
+        .clone()
+        .oneshot(request("GET", "/greeting", &[("x-tenant", "acme")]))
+        .await
+        .unwrap();
+    assert_eq!(text(response).await, "hello acme");
+
+    let response = app.oneshot(request("GET", "/greeting", &[])).await.unwrap();
+    assert_eq!(text(response).await, "hello stranger");
+}
+
+#[tokio::test]
+async fn current_user_of_another_type_is_a_server_error() {
+    let response = account_app()
+        .oneshot(request("GET", "/principal", &[("x-tenant", "acme")]))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    let body = body_json(response).await;
+    assert_eq!(
+        body["error"]["message"],
+        "CurrentUser<meshestra::guard::user::Principal> is extracted, but the guards \
+         inserted CurrentUser<guards::Tenant>"
+    );
+}
@@ -0,0 +1,109 @@
+use meshestra::health::{DatabaseHealthIndicator, EventBusHealthIndicator, HealthRegistry};
+use meshestra::prelude::*;
+use meshestra::testing::TestApp;
+use meshestra::transactional::TransactionOptions;
+use serde_json::{Value, json};
+
+/// A manager whose database is unreachable
+#[derive(Injectable)]
+pub struct UnreachableManager {}
+
+#[async_trait]
+impl TransactionManager for UnreachableManager {
+    async fn begin(
+        &self,
+        _options: TransactionOptions,
+    ) -> std::result::Result<Box<dyn Transaction>, MeshestraError> {
+        Err(MeshestraError::Internal("connection refused".to_string()))
+    }
+}
+
+/// Always up, with a fixed detail
+pub struct CacheHealthIndicator;
+
+#[async_trait]
+impl HealthIndicator for CacheHealthIndicator {
+    fn name(&self) -> &str {
+        "cache"
+    }
+
+    async fn check(&self) -> HealthCheckResult {
+        HealthCheckResult::up(json!({ "entries": 3 }))
+    }
+}
+
+#[module(imports = [HealthModule], providers = [EventBusHealthIndicator])]
+pub struct AppModule;
+
+#[module(
+    imports = [HealthModule],
+    providers = [
+        Provider::new(UnreachableManager).for_trait::<dyn TransactionManager>(),
+        DatabaseHealthIndicator,
+        EventBusHealthIndicator,
+    ],
+)]
+pub struct DatabaseModule;
+
+#[tokio::test]
+async fn readiness_nests_each_check_under_its_name() {
+    let app = TestApp::builder()
+        .provide(EventBus::new())
+        .build::<AppModule>()
+        .await
+        .unwrap();
+    let registry = app.container().resolve::<HealthRegistry>().unwrap();
+    registry.register(Arc::new(CacheHealthIndicator));
+
+    let response = app.get("/health/ready").await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value = response.json();
+    assert_eq!(body["status"], "UP");
+    assert_eq!(body["checks"]["cache"]["status"], "UP");
+    assert_eq!(body["checks"]["cache"]["details"], json!({ "entries": 3 }));
+    assert!(body["checks"]["cache"]["latency_ms"].is_u64());
+    assert_eq!(body["checks"]["event_bus"]["details"]["channels"], 0);
+}
+
+#[tokio::test]
+async fn a_down_database_fails_readiness() {
+    let app = TestApp::builder()
+        .provide(EventBus::new())
+        .build::<DatabaseModule>()
+        .await
+        .unwrap();
+
+    let response = app.get("/health/ready").await;
+
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body: Value = response.json();
+    assert_eq!(body["status"], "DOWN");
+    assert_eq!(body["checks"]["database"]["status"], "DOWN");
+    assert_eq!(
+        body["checks"]["database"]["details"]["error"],
+        "Internal error: connection refused"
+    );
+    assert_eq!(body["checks"]["event_bus"]["status"], "UP");
+}
+
+#[tokio::test]
+async fn readiness_follows_the_global_prefix_unless_excluded() {
+    let app = TestApp::builder()
+        .provide(EventBus::new())
+        .configure(|builder| builder.global_prefix("/api"))
+        .build::<AppModule>()
+        .await
+        .unwrap();
+    assert_eq!(app.get("/api/health/ready").await.status(), StatusCode::OK);
+
+    let app = TestApp::builder()
+        .provide(EventBus::new())
+        .configure(|builder| {
+            builder.global_prefix(GlobalPrefix::new("/api").exclude_framework_routes())
+        })
+        .build::<AppModule>()
+        .await
+        .unwrap();
+    assert_eq!(app.get("/health/ready").await.status(), StatusCode::OK);
+}
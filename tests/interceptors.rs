@@ -0,0 +1,354 @@
+mod common;
+
+use axum::body::Body;
+use axum::extract::ConnectInfo;
+use axum::http::{HeaderValue, Request};
+use common::AppState;
+use meshestra::aspect::AspectResult;
+use meshestra::interceptor::{InterceptorLayer, TimeoutInterceptor};
+use meshestra::prelude::*;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tower::{Service, ServiceExt};
+
+#[controller(path = "/reports")]
+pub struct ReportController {}
+
+#[routes(ReportController)]
+impl ReportController {
+    #[get("/daily")]
+    async fn daily(&self) -> &'static str {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        "daily"
+    }
+
+    #[get("/yearly")]
+    #[timeout("1s")]
+    async fn yearly(&self) -> &'static str {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        "yearly"
+    }
+
+    #[get("/export")]
+    #[rate_limit(per_minute = 2)]
+    async fn export(&self) -> &'static str {
+        "export"
+    }
+
+    #[get("/hourly")]
+    #[timeout("20ms")]
+    async fn hourly(&self) -> &'static str {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        "hourly"
+    }
+}
+
+/// What the interceptors and aspects of a request saw, in order
+#[derive(Default)]
+struct Journal(Mutex<Vec<String>>);
+
+impl Journal {
+    fn write(&self, entry: impl Into<String>) {
+        self.0.lock().unwrap().push(entry.into());
+    }
+}
+
+#[derive(Injectable)]
+pub struct JournalInterceptor {
+    journal: Arc<Journal>,
+}
+
+#[async_trait]
+impl Interceptor for JournalInterceptor {
+    async fn intercept(&self, request: Request<Body>, next: Next) -> InterceptorResult {
+        self.journal
+            .write(format!("before {}", request.uri().path()));
+        let response = next.run(request).await?;
+        self.journal.write(format!("after {}", response.status()));
+        Ok(response)
+    }
+}
+
+#[derive(Injectable)]
+pub struct JournalAspect {
+    journal: Arc<Journal>,
+}
+
+#[async_trait]
+impl Aspect for JournalAspect {
+    async fn before(&self, _request: &mut Request<Body>) -> AspectResult {
+        self.journal.write("aspect");
+        Ok(())
+    }
+}
+
+/// Numbers the responses it sees in `x-count`
+struct CountingInterceptor {
+    count: AtomicUsize,
+}
+
+impl CountingInterceptor {
+    fn new(start: usize) -> Self {
+        Self {
+            count: AtomicUsize::new(start),
+        }
+    }
+}
+
+#[async_trait]
+impl Interceptor for CountingInterceptor {
+    async fn intercept(&self, request: Request<Body>, next: Next) -> InterceptorResult {
+        let mut response = next.run(request).await?;
+        let count = self.count.fetch_add(1, Ordering::SeqCst) + 1;
+        response
+            .headers_mut()
+            .insert("x-count", HeaderValue::from(count));
+        Ok(response)
+    }
+}
+
+#[controller(path = "/ledger")]
+#[aspect(JournalAspect)]
+#[interceptor(JournalInterceptor, CountingInterceptor::new(0))]
+pub struct LedgerController {}
+
+#[routes(LedgerController)]
+impl LedgerController {
+    #[get("/entries")]
+    async fn entries(&self) -> &'static str {
+        "entries"
+    }
+
+    #[post("/entries")]
+    async fn add(&self) -> StatusCode {
+        StatusCode::CREATED
+    }
+}
+
+/// Aspects doing nothing, for measuring what a route's aspects cost
+macro_rules! noop_aspects {
+    ($($name:ident),*) => {$(
+        struct $name;
+
+        #[async_trait]
+        impl Aspect for $name {
+            async fn before(&self, _request: &mut Request<Body>) -> AspectResult {
+                Ok(())
+            }
+        }
+    )*};
+}
+
+noop_aspects!(NoopA, NoopB, NoopC, NoopD, NoopE);
+
+#[controller(path = "/bench")]
+#[aspect(NoopA)]
+#[aspect(NoopB)]
+pub struct BenchController {}
+
+#[routes(BenchController)]
+impl BenchController {
+    #[get("/")]
+    #[aspect(NoopC)]
+    #[aspect(NoopD)]
+    #[aspect(NoopE)]
+    async fn index(&self) -> &'static str {
+        "ok"
+    }
+}
+
+fn router() -> Router {
+    let container = Container::new();
+    let controller = ReportController::inject(&container).unwrap();
+
+    ReportController::router(Arc::new(controller)).with_state(AppState {
+        container: Arc::new(container),
+    })
+}
+
+/// Status of a GET to `uri` from one client
+async fn status(app: Router, uri: &str) -> StatusCode {
+    let mut request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+    request
+        .extensions_mut()
+        .insert(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 4000))));
+    app.oneshot(request).await.unwrap().status()
+}
+
+#[tokio::test]
+async fn route_timeouts_override_the_global_deadline() {
+    let app = router().layer(InterceptorLayer::new(vec![Box::new(
+        TimeoutInterceptor::new(Duration::from_millis(20)),
+    )]));
+
+    assert_eq!(
+        status(app.clone(), "/daily").await,
+        StatusCode::REQUEST_TIMEOUT
+    );
+    assert_eq!(status(app, "/yearly").await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn route_timeouts_apply_without_a_global_interceptor() {
+    let app = router();
+
+    assert_eq!(status(app.clone(), "/daily").await, StatusCode::OK);
+    assert_eq!(status(app, "/hourly").await, StatusCode::REQUEST_TIMEOUT);
+}
+
+#[tokio::test]
+async fn rate_limited_routes_share_a_budget_across_requests() {
+    let app = router();
+
+    assert_eq!(status(app.clone(), "/export").await, StatusCode::OK);
+    assert_eq!(status(app.clone(), "/export").await, StatusCode::OK);
+    assert_eq!(
+        status(app.clone(), "/export").await,
+        StatusCode::TOO_MANY_REQUESTS
+    );
+    // Other routes are not limited.
+    assert_eq!(status(app, "/daily").await, StatusCode::OK);
+}
+
+fn ledger_container() -> (Container, Arc<Journal>) {
+    let mut container = Container::new();
+    container.register(Journal::default());
+    let interceptor = JournalInterceptor::inject(&container).unwrap();
+    let aspect = JournalAspect::inject(&container).unwrap();
+    container.register(interceptor);
+    container.register(aspect);
+    let journal = container.resolve::<Journal>().unwrap();
+    (container, journal)
+}
+
+fn ledger() -> (Router, Arc<Journal>) {
+    let (container, journal) = ledger_container();
+    let controller = LedgerController::inject(&container).unwrap();
+
+    let router = LedgerController::router(Arc::new(controller)).with_state(AppState {
+        container: Arc::new(container),
+    });
+    (router, journal)
+}
+
+#[tokio::test]
+async fn unregistered_controller_interceptors_answer_500() {
+    let mut container = Container::new();
+    container.register(Journal::default());
+    let aspect = JournalAspect::inject(&container).unwrap();
+    container.register(aspect);
+    let controller = LedgerController::inject(&container).unwrap();
+    let app = LedgerController::router(Arc::new(controller)).with_state(AppState {
+        container: Arc::new(container),
+    });
+
+    assert_eq!(
+        status(app, "/entries").await,
+        StatusCode::INTERNAL_SERVER_ERROR
+    );
+}
+
+#[tokio::test]
+async fn unregistered_controller_aspects_answer_500() {
+    let mut container = Container::new();
+    container.register(Journal::default());
+    let interceptor = JournalInterceptor::inject(&container).unwrap();
+    container.register(interceptor);
+    let controller = LedgerController::inject(&container).unwrap();
+    let app = LedgerController::router(Arc::new(controller)).with_state(AppState {
+        container: Arc::new(container),
+    });
+
+    assert_eq!(
+        status(app, "/entries").await,
+        StatusCode::INTERNAL_SERVER_ERROR
+    );
+}
+
+#[tokio::test]
+async fn route_chains_come_from_the_container_serving_them() {
+    let routes = LedgerController::router::<AppState>(Arc::new(LedgerController {}));
+    let serve = |container: Container| {
+        routes.clone().with_state(AppState {
+            container: Arc::new(container),
+        })
+    };
+
+    // A chain that could not be built is not kept.
+    assert_eq!(
+        status(serve(Container::new()), "/entries").await,
+        StatusCode::INTERNAL_SERVER_ERROR
+    );
+    let (first, first_journal) = ledger_container();
+    let (second, second_journal) = ledger_container();
+    let first = serve(first);
+    let second = serve(second);
+    for app in [&first, &second, &first] {
+        assert_eq!(status(app.clone(), "/entries").await, StatusCode::OK);
+    }
+
+    assert_eq!(first_journal.0.lock().unwrap().len(), 6);
+    assert_eq!(second_journal.0.lock().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn controller_interceptors_wrap_every_route_outside_its_aspects() {
+    let (app, journal) = ledger();
+
+    let get = Request::get("/entries").body(Body::empty()).unwrap();
+    let response = app.clone().oneshot(get).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["x-count"], "1");
+
+    let post = Request::post("/entries").body(Body::empty()).unwrap();
+    let response = app.oneshot(post).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    // One interceptor built from an expression serves all the routes.
+    assert_eq!(response.headers()["x-count"], "2");
+
+    assert_eq!(
+        *journal.0.lock().unwrap(),
+        [
+            "before /entries",
+            "aspect",
+            "after 200 OK",
+            "before /entries",
+            "aspect",
+            "after 201 Created",
+        ]
+    );
+}
+
+/// `cargo test --release --test interceptors five_noop_aspects -- --ignored --nocapture`
+///
+/// Fastest of five runs on one machine: 6.6 µs per request with the aspects
+/// resolved and sorted for each request, 5.6 µs with the route's chain kept.
+#[tokio::test]
+#[ignore = "benchmark"]
+async fn five_noop_aspects() {
+    const REQUESTS: u32 = 100_000;
+    let mut container = Container::new();
+    container.register(NoopA);
+    container.register(NoopB);
+    container.register(NoopC);
+    container.register(NoopD);
+    container.register(NoopE);
+    let controller = BenchController::inject(&container).unwrap();
+    let mut app = BenchController::router(Arc::new(controller)).with_state(AppState {
+        container: Arc::new(container),
+    });
+    // The fastest of a few rounds, the others being the noisier.
+    let mut fastest = std::time::Duration::MAX;
+    for _ in 0..5 {
+        let start = std::time::Instant::now();
+        for _ in 0..REQUESTS {
+            let request = Request::get("/").body(Body::empty()).unwrap();
+            let response = app.call(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        fastest = fastest.min(start.elapsed() / REQUESTS);
+    }
+    println!("5 no-op aspects: {:?} per request", fastest);
+}
@@ -0,0 +1,148 @@
+#![cfg(feature = "metrics")]
+
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, header};
+use common::AppState;
+use meshestra::metrics::prometheus::IntCounter;
+use meshestra::prelude::*;
+use tower::{Layer, ServiceExt};
+
+/// Counts the profiles viewed in a metric of its own
+pub struct UserService {
+    viewed: IntCounter,
+}
+
+impl meshestra::Injectable for UserService {
+    fn inject(container: &Container) -> Result<Self> {
+        let metrics = container.resolve::<MetricsRegistry>()?;
+        let viewed = metrics
+            .counter("users_viewed_total", "User profiles viewed")
+            .map_err(|e| MeshestraError::Internal(e.to_string()))?;
+        Ok(Self { viewed })
+    }
+}
+
+#[controller(path = "/users")]
+pub struct UserController {
+    users: Arc<UserService>,
+}
+
+#[routes(UserController)]
+impl UserController {
+    #[get("/{id}")]
+    async fn get_user(&self, #[param] id: u32) -> String {
+        self.users.viewed.inc();
+        format!("user {}", id)
+    }
+}
+
+#[module(
+    imports = [MetricsModule],
+    controllers = [UserController],
+    providers = [UserService],
+)]
+pub struct AppModule;
+
+async fn application() -> Application {
+    Application::builder()
+        .container(AppModule::create_container().unwrap())
+        .module::<AppModule>()
+        .global_interceptor::<MetricsInterceptor>()
+        .build()
+        .await
+        .unwrap()
+}
+
+fn router(app: &Application) -> Router {
+    let state = AppState {
+        container: app.container().clone(),
+    };
+    app.router::<AppModule, AppState>()
+        .unwrap()
+        .with_state(state)
+}
+
+/// The status and body of a GET request to `uri`
+async fn get<S>(app: S, uri: &str) -> (StatusCode, String)
+where
+    S: tower::Service<Request<Body>, Response = Response>,
+    S::Error: std::fmt::Debug,
+{
+    let request = Request::get(uri).body(Body::empty()).unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn requests_are_counted_by_route_template() {
+    let app = application().await;
+    let router = router(&app).layer(app.interceptor_layer());
+
+    for id in [1, 2, 3] {
+        assert_eq!(
+            get(router.clone(), &format!("/users/{}", id)).await.0,
+            StatusCode::OK
+        );
+    }
+
+    let request = Request::get("/metrics").body(Body::empty()).unwrap();
+    let response = router.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let content_type = response.headers()[header::CONTENT_TYPE].to_str().unwrap();
+    assert!(content_type.starts_with("text/plain; version=0.0.4"));
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let metrics = String::from_utf8(body.to_vec()).unwrap();
+
+    assert!(
+        metrics.contains(r#"http_requests_total{method="GET",route="/users/{id}",status="200"} 3"#),
+        "{}",
+        metrics
+    );
+    assert!(metrics.contains(
+        r#"http_request_duration_seconds_count{method="GET",route="/users/{id}",status="200"} 3"#
+    ));
+    assert!(!metrics.contains("/users/1"), "paths are never labels");
+    assert!(metrics.contains("users_viewed_total 3"));
+}
+
+#[tokio::test]
+async fn the_route_reaches_the_interceptor_around_the_router() {
+    let app = application().await;
+    let service = app.interceptor_layer().layer(router(&app));
+
+    assert_eq!(get(service.clone(), "/users/7").await.0, StatusCode::OK);
+    assert_eq!(get(service, "/nowhere/7").await.0, StatusCode::NOT_FOUND);
+
+    let metrics = app
+        .container()
+        .resolve::<MetricsRegistry>()
+        .unwrap()
+        .render();
+    assert!(
+        metrics.contains(r#"http_requests_total{method="GET",route="/users/{id}",status="200"} 1"#),
+        "{}",
+        metrics
+    );
+    assert!(
+        metrics.contains(r#"http_requests_total{method="GET",route="unmatched",status="404"} 1"#)
+    );
+}
+
+#[tokio::test]
+async fn the_metrics_route_is_listed() {
+    let app = application().await;
+
+    assert!(
+        app.routes()
+            .iter()
+            .any(|route| route.path == "/metrics" && route.handler == "MetricsController::metrics")
+    );
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{HeaderValue, Request};
+use axum::middleware::map_response;
+use common::AppState;
+use meshestra::prelude::*;
+use tower::ServiceExt;
+
+/// Add `tag` to the response's `x-trail`, after those of the layers inside
+fn trail(mut response: Response, tag: &str) -> Response {
+    let trail = match response.headers().get("x-trail") {
+        Some(trail) => format!("{},{}", trail.to_str().unwrap(), tag),
+        None => tag.to_string(),
+    };
+    response
+        .headers_mut()
+        .insert("x-trail", HeaderValue::from_str(&trail).unwrap());
+    response
+}
+
+async fn inner(response: Response) -> Response {
+    trail(response, "inner")
+}
+
+async fn outer(response: Response) -> Response {
+    trail(response, "outer")
+}
+
+async fn audit(response: Response) -> Response {
+    trail(response, "audit")
+}
+
+#[controller(path = "/files")]
+#[middleware(map_response(audit))]
+pub struct FileController {}
+
+#[routes(FileController)]
+impl FileController {
+    #[get("/")]
+    async fn list(&self) -> &'static str {
+        "files"
+    }
+
+    #[post("/")]
+    #[middleware(map_response(outer))]
+    #[middleware(map_response(inner))]
+    async fn upload(&self) -> StatusCode {
+        StatusCode::CREATED
+    }
+}
+
+fn app() -> Router {
+    let container = Container::new();
+    let controller = FileController::inject(&container).unwrap();
+
+    FileController::router(Arc::new(controller)).with_state(AppState {
+        container: Arc::new(container),
+    })
+}
+
+async fn trail_of(method: &str) -> (StatusCode, String) {
+    let request = Request::builder()
+        .method(method)
+        .uri("/")
+        .body(Body::empty())
+        .unwrap();
+    let response = app().oneshot(request).await.unwrap();
+    let trail = response.headers()["x-trail"].to_str().unwrap().to_string();
+    (response.status(), trail)
+}
+
+#[tokio::test]
+async fn route_middleware_wraps_only_its_route() {
+    assert_eq!(trail_of("GET").await, (StatusCode::OK, "audit".to_string()));
+}
+
+#[tokio::test]
+async fn route_middleware_stacks_outermost_first_within_the_controller_middleware() {
+    assert_eq!(
+        trail_of("POST").await,
+        (StatusCode::CREATED, "inner,outer,audit".to_string())
+    );
+}
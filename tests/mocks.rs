@@ -0,0 +1,201 @@
+use meshestra::prelude::*;
+use meshestra::testing::{MockProvider, TestApp, mock_provider, mock_trait};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct User {
+    pub id: String,
+    pub name: String,
+}
+
+fn ada() -> User {
+    User {
+        id: "1".to_string(),
+        name: "Ada".to_string(),
+    }
+}
+
+#[async_trait]
+pub trait UserRepository: Send + Sync {
+    async fn find_by_id(&self, id: &str) -> Result<Option<User>>;
+    async fn save(&self, user: User) -> Result<User>;
+    fn table(&self) -> &'static str;
+}
+
+mock_trait! {
+    pub MockUserRepository: UserRepository {
+        async fn find_by_id(&self, id: &str) -> Result<Option<User>>;
+        async fn save(&self, user: User) -> Result<User>;
+        fn table(&self) -> &'static str;
+    }
+}
+
+/// Never registered: the real repository can't be injected in the tests
+pub struct DatabaseConnection;
+
+#[derive(Injectable)]
+pub struct DbUserRepository {
+    _db: Arc<DatabaseConnection>,
+}
+
+#[async_trait]
+impl UserRepository for DbUserRepository {
+    async fn find_by_id(&self, _id: &str) -> Result<Option<User>> {
+        unreachable!("the tests use a mock")
+    }
+
+    async fn save(&self, _user: User) -> Result<User> {
+        unreachable!("the tests use a mock")
+    }
+
+    fn table(&self) -> &'static str {
+        "users"
+    }
+}
+
+#[derive(Injectable)]
+pub struct UserService {
+    repository: Arc<dyn UserRepository>,
+}
+
+impl UserService {
+    async fn rename(&self, id: &str, name: &str) -> Result<Option<User>> {
+        let Some(user) = self.repository.find_by_id(id).await? else {
+            return Ok(None);
+        };
+        let user = self
+            .repository
+            .save(User {
+                name: name.to_string(),
+                ..user
+            })
+            .await?;
+        Ok(Some(user))
+    }
+}
+
+#[controller(path = "/users")]
+pub struct UserController {
+    service: Arc<UserService>,
+}
+
+#[routes(UserController)]
+impl UserController {
+    #[get("/{id}")]
+    async fn get_one(&self, #[param] id: String) -> std::result::Result<Json<User>, HttpException> {
+        match self.service.repository.find_by_id(&id).await {
+            Ok(Some(user)) => Ok(Json(user)),
+            Ok(None) => Err(HttpException::not_found(format!("User {} not found", id))),
+            Err(e) => Err(HttpException::internal(e.to_string())),
+        }
+    }
+}
+
+#[module(
+    controllers = [UserController],
+    providers = [
+        Provider::new(DbUserRepository).for_trait::<dyn UserRepository>(),
+        UserService,
+    ],
+)]
+pub struct UserModule;
+
+#[tokio::test]
+async fn mocks_replace_trait_bindings_of_a_test_app() {
+    let repository = MockUserRepository::new()
+        .on_find_by_id(|id| Ok((id == "1").then(ada)))
+        .on_table(|| "mock_users");
+    let calls = repository.calls();
+    let app = TestApp::builder()
+        .mock(repository)
+        .build::<UserModule>()
+        .await
+        .unwrap();
+
+    let found = app.get("/users/1").await;
+    assert_eq!(found.status(), StatusCode::OK);
+    assert_eq!(found.json::<User>(), ada());
+    assert_eq!(app.get("/users/2").await.status(), StatusCode::NOT_FOUND);
+
+    let repository = app
+        .container()
+        .resolve_trait::<dyn UserRepository>()
+        .unwrap();
+    assert_eq!(repository.table(), "mock_users");
+    calls.assert_called("find_by_id", 2);
+    calls.assert_called("save", 0);
+}
+
+#[tokio::test]
+async fn mocks_count_calls_in_unit_tests() {
+    let repository = MockUserRepository::new()
+        .on_find_by_id(|_| Ok(Some(ada())))
+        .on_save(Ok);
+    let calls = repository.calls();
+    let mut container = Container::new();
+    repository.override_in(&mut container);
+    UserModule::register(&mut container).unwrap();
+    let service = container.resolve::<UserService>().unwrap();
+
+    let renamed = service.rename("1", "Grace").await.unwrap().unwrap();
+
+    assert_eq!(renamed.name, "Grace");
+    calls.assert_called("find_by_id", 1);
+    calls.assert_called("save", 1);
+}
+
+#[tokio::test]
+#[should_panic(expected = "MockUserRepository::save was called, but isn't programmed")]
+async fn unprogrammed_methods_panic() {
+    let repository = MockUserRepository::new();
+    let _ = repository.save(ada()).await;
+}
+
+#[test]
+#[should_panic(expected = "MockUserRepository has no method `sve`")]
+fn unknown_methods_fail_assertions() {
+    MockUserRepository::new().assert_called("sve", 0);
+}
+
+/// A hand-written mock, as a mocking library would generate
+#[derive(Default)]
+pub struct RecordingUserRepository {
+    saved: Mutex<Vec<User>>,
+}
+
+#[async_trait]
+impl UserRepository for RecordingUserRepository {
+    async fn find_by_id(&self, _id: &str) -> Result<Option<User>> {
+        Ok(Some(ada()))
+    }
+
+    async fn save(&self, user: User) -> Result<User> {
+        self.saved.lock().unwrap().push(user.clone());
+        Ok(user)
+    }
+
+    fn table(&self) -> &'static str {
+        "recorded_users"
+    }
+}
+
+mock_provider!(RecordingUserRepository => dyn UserRepository);
+
+#[tokio::test]
+async fn other_mocks_are_bound_with_mock_provider() {
+    let app = TestApp::builder()
+        .mock(RecordingUserRepository::default())
+        .build::<UserModule>()
+        .await
+        .unwrap();
+
+    let service = app.container().resolve::<UserService>().unwrap();
+    service.rename("1", "Grace").await.unwrap();
+
+    let recorded = app
+        .container()
+        .resolve::<RecordingUserRepository>()
+        .unwrap();
+    assert_eq!(recorded.saved.lock().unwrap()[0].name, "Grace");
+}
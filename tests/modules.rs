@@ -0,0 +1,764 @@
+mod common;
+
+use axum::body::{Body, to_bytes};
+use axum::http::Request;
+use common::AppState;
+use meshestra::di::{Registration, RegistrationPolicy};
+use meshestra::prelude::*;
+use tower::ServiceExt;
+
+#[derive(Injectable)]
+pub struct Greeter {}
+
+impl Greeter {
+    fn greet(&self, name: &str) -> String {
+        format!("hello {}", name)
+    }
+}
+
+#[controller(path = "/users")]
+pub struct UserController {
+    greeter: Arc<Greeter>,
+}
+
+#[routes(UserController)]
+impl UserController {
+    #[get("/{name}")]
+    async fn greet(&self, #[param] name: String) -> String {
+        self.greeter.greet(&name)
+    }
+}
+
+#[controller(path = "/")]
+pub struct HealthController {}
+
+#[routes(HealthController)]
+impl HealthController {
+    #[get("/health")]
+    async fn health(&self) -> &'static str {
+        "ok"
+    }
+}
+
+#[module(controllers = [UserController], providers = [Greeter])]
+pub struct UserModule;
+
+pub struct Motd(&'static str);
+
+#[controller(path = "/motd")]
+pub struct MotdController {
+    motd: Arc<Motd>,
+}
+
+#[routes(MotdController)]
+impl MotdController {
+    #[get("/")]
+    async fn show(&self) -> &'static str {
+        self.motd.0
+    }
+}
+
+#[module(controllers = [MotdController])]
+pub struct MotdModule;
+
+/// Imports `UserModule` too, so it's reachable along two paths
+#[module(imports = [UserModule])]
+pub struct AdminModule;
+
+#[module(imports = [UserModule, AdminModule], controllers = [HealthController])]
+pub struct AppModule;
+
+/// Serves `GET /users/{id}`, like `UserController`
+#[controller(path = "/users")]
+pub struct LegacyUserController {}
+
+#[routes(LegacyUserController)]
+impl LegacyUserController {
+    #[get("/{id}")]
+    async fn show(&self, #[param] id: String) -> String {
+        id
+    }
+}
+
+#[module(imports = [UserModule], controllers = [LegacyUserController])]
+pub struct LegacyModule;
+
+#[derive(Injectable)]
+pub struct Settings {}
+
+#[module(providers = [Settings])]
+pub struct ConfigModule;
+
+/// Provides `Settings` as well as `ConfigModule`
+#[module(providers = [Settings])]
+pub struct AuthModule;
+
+#[module(imports = [ConfigModule, AuthModule])]
+pub struct DuplicateModule;
+
+pub trait Clock: Send + Sync {}
+
+#[derive(Injectable)]
+pub struct SystemClock {}
+
+impl Clock for SystemClock {}
+
+#[module(
+    providers = [Provider::new(SystemClock).for_trait::<dyn Clock>()],
+    exports = [dyn Clock],
+)]
+pub struct ClockModule;
+
+/// Re-exports what `ClockModule` exports
+#[module(imports = [ClockModule], exports = [ClockModule])]
+pub struct CoreModule;
+
+#[module(imports = [CoreModule], providers = [Greeter], exports = [Greeter, dyn Clock])]
+pub struct ReportModule;
+
+/// Exports a trait it has no provider of
+#[module(providers = [Greeter], exports = [Greeter, dyn Clock])]
+pub struct BrokenModule;
+
+pub trait Store: Send + Sync {
+    fn kind(&self) -> &'static str;
+}
+
+pub struct MemoryStore;
+
+impl Store for MemoryStore {
+    fn kind(&self) -> &'static str {
+        "memory"
+    }
+}
+
+pub struct DiskStore;
+
+impl Store for DiskStore {
+    fn kind(&self) -> &'static str {
+        "disk"
+    }
+}
+
+pub struct StoreConfig {
+    in_memory: bool,
+}
+
+#[controller(path = "/store")]
+pub struct StoreController {
+    config: Arc<StoreConfig>,
+}
+
+#[routes(StoreController)]
+impl StoreController {
+    #[get("/")]
+    async fn kind(&self) -> &'static str {
+        if self.config.in_memory {
+            "memory"
+        } else {
+            "disk"
+        }
+    }
+}
+
+/// Picks the store when it's imported
+pub struct StorageModule;
+
+impl StorageModule {
+    pub fn with_config(in_memory: bool) -> DynamicModule {
+        let builder = DynamicModule::builder().provider(StoreConfig { in_memory });
+        let builder = if in_memory {
+            builder
+                .provider(MemoryStore)
+                .bind_trait::<dyn Store, MemoryStore>(|store| store as Arc<dyn Store>)
+        } else {
+            builder
+                .provider_factory(|_| Ok(DiskStore))
+                .bind_trait::<dyn Store, DiskStore>(|store| store as Arc<dyn Store>)
+        };
+        builder
+            .controller::<StoreController>()
+            .export::<dyn Store>()
+            .build()
+    }
+}
+
+#[module(imports = [StorageModule::with_config(true), UserModule], exports = [dyn Store])]
+pub struct StorageAppModule;
+
+#[module(imports = [StorageModule::with_config(false)])]
+pub struct DiskAppModule;
+
+#[derive(Injectable)]
+pub struct Tracer {}
+
+#[module(global = true, providers = [Tracer], exports = [Tracer])]
+pub struct TracingModule;
+
+#[derive(Injectable)]
+pub struct Audit {
+    tracer: Arc<Tracer>,
+}
+
+/// Injects `Tracer` without importing `TracingModule`
+#[module(providers = [Audit])]
+pub struct AuditModule;
+
+#[module(imports = [AuditModule, TracingModule])]
+pub struct TracedAppModule;
+
+#[derive(Injectable)]
+pub struct ManualClock {}
+
+impl Clock for ManualClock {}
+
+#[module(
+    global = true,
+    providers = [Provider::new(SystemClock).for_trait::<dyn Clock>()],
+    exports = [dyn Clock],
+)]
+pub struct SystemClockModule;
+
+#[module(
+    global = true,
+    providers = [Provider::new(ManualClock).for_trait::<dyn Clock>()],
+    exports = [dyn Clock],
+)]
+pub struct ManualClockModule;
+
+#[module(imports = [SystemClockModule, ManualClockModule])]
+pub struct TwoClocksModule;
+
+pub struct DatabaseConfig {
+    url: &'static str,
+}
+
+#[derive(Injectable)]
+pub struct Database {
+    config: Arc<DatabaseConfig>,
+}
+
+#[controller(path = "/database")]
+pub struct DatabaseController {
+    database: Arc<Database>,
+}
+
+#[routes(DatabaseController)]
+impl DatabaseController {
+    #[get("/")]
+    async fn url(&self) -> &'static str {
+        self.database.config.url
+    }
+}
+
+#[module(
+    config = DatabaseConfig,
+    providers = [Database],
+    controllers = [DatabaseController],
+    exports = [Database],
+)]
+pub struct DatabaseModule;
+
+pub struct Repository {
+    table: &'static str,
+    database: Arc<Database>,
+}
+
+#[module(imports = [
+    DatabaseModule::for_feature([DynamicProvider::factory(|container| {
+        Ok(Repository {
+            table: "orders",
+            database: container.resolve::<Database>()?,
+        })
+    })]),
+])]
+pub struct OrdersModule;
+
+#[module(
+    imports = [
+        DatabaseModule::for_root(DatabaseConfig { url: "sqlite::memory:" }),
+        OrdersModule,
+    ],
+    exports = [Database],
+)]
+pub struct ShopModule;
+
+/// Imports a feature without the root
+#[module(imports = [OrdersModule])]
+pub struct UnconfiguredShopModule;
+
+#[derive(Injectable)]
+pub struct Checkout {
+    greeter: Arc<Greeter>,
+    audit: Arc<Audit>,
+}
+
+/// Lists each provider before the ones it needs
+#[module(providers = [Checkout, Audit, Greeter, Tracer])]
+pub struct ShuffledModule;
+
+/// `Audit` needs `Tracer`, which no one provides
+#[module(providers = [Checkout, Audit, Greeter])]
+pub struct UntracedModule;
+
+#[derive(Injectable)]
+pub struct Ping {
+    _pong: Arc<Pong>,
+}
+
+#[derive(Injectable)]
+pub struct Pong {
+    _ping: Arc<Ping>,
+}
+
+#[module(providers = [Greeter, Ping, Pong])]
+pub struct PingPongModule;
+
+#[derive(Injectable)]
+pub struct Reminders {
+    clock: Lazy<ManualClock>,
+}
+
+/// `Reminders` lazily needs `ManualClock`, which it doesn't provide
+#[module(providers = [Reminders])]
+pub struct RemindersModule;
+
+/// Serves `UserModule` under `/admin`
+#[module(prefix = "/admin/", imports = [UserModule])]
+pub struct AdminAreaModule;
+
+#[module(imports = [AdminAreaModule], controllers = [HealthController])]
+pub struct PrefixedAppModule;
+
+async fn app() -> Router {
+    let app = Application::builder()
+        .container(AppModule::create_container().unwrap())
+        .module::<AppModule>()
+        .build()
+        .await
+        .unwrap();
+    let state = AppState {
+        container: app.container().clone(),
+    };
+    app.router::<AppModule, AppState>()
+        .unwrap()
+        .with_state(state)
+}
+
+async fn get(app: Router, uri: &str) -> (StatusCode, String) {
+    let request = Request::get(uri).body(Body::empty()).unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, String::from_utf8(bytes.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn module_tree_is_mounted_at_the_base_paths() {
+    let app = app().await;
+
+    assert_eq!(
+        get(app.clone(), "/users/ada").await,
+        (StatusCode::OK, "hello ada".to_string())
+    );
+    assert_eq!(
+        get(app, "/health").await,
+        (StatusCode::OK, "ok".to_string())
+    );
+}
+
+#[tokio::test]
+async fn module_router_needs_the_module_registered() {
+    let container = Container::new();
+
+    assert!(UserModule::build_router::<AppState>(&container).is_err());
+}
+
+#[tokio::test]
+async fn mounted_controllers_are_the_registered_instances() {
+    let mut container = Container::new();
+    container.register(Motd("welcome"));
+    container.override_with(MotdController {
+        motd: Arc::new(Motd("down for maintenance")),
+    });
+    MotdModule::register(&mut container).unwrap();
+    let container = Arc::new(container);
+    let router = MotdModule::build_router::<AppState>(&container)
+        .unwrap()
+        .with_state(AppState { container });
+
+    assert_eq!(get(router, "/motd").await.1, "down for maintenance");
+}
+
+#[tokio::test]
+async fn routes_are_listed_with_their_full_paths() {
+    let app = Application::builder()
+        .container(AppModule::create_container().unwrap())
+        .module::<AppModule>()
+        .build()
+        .await
+        .unwrap();
+
+    let routes: Vec<_> = app
+        .routes()
+        .iter()
+        .map(|route| (route.method, route.path.as_str(), route.handler))
+        .collect();
+    assert_eq!(
+        routes,
+        [
+            ("GET", "/users/{name}", "UserController::greet"),
+            ("GET", "/health", "HealthController::health"),
+        ]
+    );
+}
+
+#[test]
+fn conflicting_routes_are_a_registration_error() {
+    let error = LegacyModule::routes().unwrap_err();
+
+    assert_eq!(
+        error.to_string(),
+        "Module registration failed: GET /users/{id} is routed to both \
+         UserController::greet and LegacyUserController::show"
+    );
+}
+
+fn container_with(policy: RegistrationPolicy) -> Container {
+    ContainerBuilder::new().on_duplicate(policy).build()
+}
+
+#[test]
+fn duplicate_providers_replace_by_default() {
+    let mut container = Container::new();
+
+    assert!(DuplicateModule::register(&mut container).is_ok());
+    assert!(container.contains::<Settings>());
+    assert_eq!(container.register(Settings {}), Registration::Replaced);
+}
+
+#[test]
+fn duplicate_providers_can_be_skipped() {
+    let mut container = container_with(RegistrationPolicy::Skip);
+
+    assert!(DuplicateModule::register(&mut container).is_ok());
+    assert_eq!(container.register(Settings {}), Registration::Skipped);
+}
+
+#[test]
+fn duplicate_providers_can_be_an_error_naming_both_modules() {
+    let mut container = container_with(RegistrationPolicy::Error);
+
+    let error = DuplicateModule::register(&mut container).unwrap_err();
+
+    assert_eq!(
+        error.to_string(),
+        "Module registration failed: modules::Settings is registered by both \
+         ConfigModule and AuthModule"
+    );
+}
+
+#[test]
+fn modules_imported_twice_are_not_duplicates() {
+    let mut container = container_with(RegistrationPolicy::Error);
+
+    assert!(AppModule::register(&mut container).is_ok());
+}
+
+#[test]
+fn exports_name_the_exported_types() {
+    assert_eq!(ClockModule::exports(), ["dyn modules::Clock"]);
+    assert_eq!(CoreModule::exports(), ["dyn modules::Clock"]);
+    assert_eq!(
+        ReportModule::exports(),
+        ["modules::Greeter", "dyn modules::Clock"]
+    );
+    assert!(UserModule::exports().is_empty());
+}
+
+#[test]
+fn exports_may_come_from_imports() {
+    let container = ReportModule::create_container().unwrap();
+
+    assert!(container.resolve_trait::<dyn Clock>().is_ok());
+}
+
+#[test]
+fn exports_must_be_provided_or_imported() {
+    let error = BrokenModule::create_container().err().unwrap();
+
+    assert_eq!(
+        error.to_string(),
+        "Module registration failed: BrokenModule exports dyn modules::Clock, \
+         which it neither provides nor imports"
+    );
+}
+
+#[test]
+fn dynamic_modules_register_what_their_configuration_picks() {
+    let container = StorageAppModule::create_container().unwrap();
+    assert_eq!(
+        container.resolve_trait::<dyn Store>().unwrap().kind(),
+        "memory"
+    );
+    assert!(container.contains::<Greeter>());
+
+    let container = DiskAppModule::create_container().unwrap();
+    assert_eq!(
+        container.resolve_trait::<dyn Store>().unwrap().kind(),
+        "disk"
+    );
+}
+
+#[test]
+fn dynamic_modules_export_what_they_list() {
+    assert_eq!(
+        StorageModule::with_config(true).exports(),
+        ["dyn modules::Store"]
+    );
+    assert_eq!(StorageAppModule::exports(), ["dyn modules::Store"]);
+}
+
+#[tokio::test]
+async fn dynamic_module_controllers_are_mounted_with_the_importer() {
+    let container = Arc::new(StorageAppModule::create_container().unwrap());
+    let app = StorageAppModule::build_router::<AppState>(&container)
+        .unwrap()
+        .with_state(AppState {
+            container: container.clone(),
+        });
+
+    assert_eq!(
+        get(app.clone(), "/store").await,
+        (StatusCode::OK, "memory".to_string())
+    );
+    assert_eq!(
+        get(app, "/users/ada").await,
+        (StatusCode::OK, "hello ada".to_string())
+    );
+}
+
+#[test]
+fn global_modules_register_before_their_siblings() {
+    let container = TracedAppModule::create_container().unwrap();
+
+    assert!(container.contains::<Audit>());
+    const {
+        assert!(<TracingModule as Module>::GLOBAL);
+        assert!(!<AuditModule as Module>::GLOBAL);
+    }
+}
+
+#[test]
+fn global_modules_register_once() {
+    let mut container = container_with(RegistrationPolicy::Error);
+
+    assert!(TracedAppModule::register(&mut container).is_ok());
+    let first = container.resolve::<Tracer>().unwrap();
+    assert!(TracingModule::register(&mut container).is_ok());
+    let second = container.resolve::<Tracer>().unwrap();
+    assert!(Arc::ptr_eq(&first, &second));
+}
+
+#[test]
+fn global_modules_exporting_the_same_type_conflict() {
+    let error = TwoClocksModule::create_container().err().unwrap();
+
+    assert_eq!(
+        error.to_string(),
+        "Module registration failed: dyn modules::Clock is exported by both \
+         global modules SystemClockModule and ManualClockModule"
+    );
+}
+
+#[test]
+fn for_root_registers_the_config_first() {
+    let container = ShopModule::create_container().unwrap();
+
+    let repository = container.resolve::<Repository>().unwrap();
+    assert_eq!(repository.table, "orders");
+    assert_eq!(repository.database.config.url, "sqlite::memory:");
+    assert_eq!(
+        DatabaseModule::for_root(DatabaseConfig { url: "" }).exports(),
+        ["modules::Database"]
+    );
+}
+
+#[tokio::test]
+async fn for_root_serves_the_module_controllers() {
+    let container = Arc::new(ShopModule::create_container().unwrap());
+    let app = ShopModule::build_router::<AppState>(&container)
+        .unwrap()
+        .with_state(AppState {
+            container: container.clone(),
+        });
+
+    assert_eq!(
+        get(app, "/database").await,
+        (StatusCode::OK, "sqlite::memory:".to_string())
+    );
+}
+
+#[test]
+fn for_feature_needs_for_root() {
+    let error = UnconfiguredShopModule::create_container().err().unwrap();
+
+    assert_eq!(
+        error.to_string(),
+        "Module registration failed: DatabaseModule::for_feature is registered \
+         before DatabaseModule::for_root"
+    );
+}
+
+#[test]
+fn providers_register_in_any_order() {
+    let container = ShuffledModule::create_container().unwrap();
+
+    let checkout = container.resolve::<Checkout>().unwrap();
+    assert_eq!(checkout.greeter.greet("ada"), "hello ada");
+    assert!(Arc::ptr_eq(
+        &checkout.audit.tracer,
+        &container.resolve::<Tracer>().unwrap()
+    ));
+}
+
+#[test]
+fn providers_missing_a_dependency_are_named() {
+    let error = UntracedModule::create_container().err().unwrap();
+
+    assert_eq!(
+        error.to_string(),
+        "Module registration failed: modules::Audit needs modules::Tracer, \
+         which isn't provided by UntracedModule or its imports"
+    );
+}
+
+#[test]
+fn providers_needing_each_other_are_a_cycle() {
+    let error = PingPongModule::create_container().err().unwrap();
+
+    assert_eq!(
+        error.to_string(),
+        "Circular dependency detected: modules::Ping -> modules::Pong -> modules::Ping"
+    );
+}
+
+#[test]
+fn container_builder_registers_modules() {
+    let container = ContainerBuilder::new()
+        .register(Greeter {})
+        .register(Tracer {})
+        .module::<UntracedModule>()
+        .unwrap()
+        .module_dyn(DatabaseModule::for_root(DatabaseConfig {
+            url: "sqlite::memory:",
+        }))
+        .unwrap()
+        .build();
+
+    assert!(container.contains::<Checkout>());
+    assert_eq!(
+        container.resolve::<Database>().unwrap().config.url,
+        "sqlite::memory:"
+    );
+}
+
+#[test]
+fn container_builder_returns_module_errors() {
+    let error = ContainerBuilder::new()
+        .module::<UntracedModule>()
+        .err()
+        .unwrap();
+
+    assert!(matches!(
+        error,
+        MeshestraError::ModuleRegistrationFailed { .. }
+    ));
+}
+
+#[test]
+fn try_build_needs_the_bound_implementations() {
+    let error = ContainerBuilder::new()
+        .bind::<dyn Clock, SystemClock, _>(|clock| clock as Arc<dyn Clock>)
+        .try_build()
+        .err()
+        .unwrap();
+    assert!(matches!(error, MeshestraError::DependencyNotFound { .. }));
+
+    let container = ContainerBuilder::new()
+        .register(SystemClock {})
+        .bind::<dyn Clock, SystemClock, _>(|clock| clock as Arc<dyn Clock>)
+        .try_build()
+        .unwrap();
+    assert!(container.resolve_trait::<dyn Clock>().is_ok());
+}
+
+#[test]
+fn validate_reports_unregistered_lazy_dependencies() {
+    let container = RemindersModule::create_container().unwrap();
+
+    let report = container.validate().err().unwrap();
+    assert_eq!(
+        report.to_string(),
+        "1 unresolved dependency:\n  modules::Reminders needs modules::ManualClock"
+    );
+
+    let mut container = Container::new();
+    container.register(ManualClock {});
+    RemindersModule::register(&mut container).unwrap();
+    assert!(container.validate().is_ok());
+    let _clock: &ManualClock = &container.resolve::<Reminders>().unwrap().clock;
+}
+
+#[tokio::test]
+async fn build_validates_the_dependencies_unless_opted_out() {
+    let container = RemindersModule::create_container().unwrap();
+    let result = Application::builder()
+        .container(container.clone())
+        .build()
+        .await;
+    assert!(result.is_err());
+
+    let result = Application::builder()
+        .container(container)
+        .validate_dependencies(false)
+        .build()
+        .await;
+    assert!(result.is_ok());
+}
+
+#[test]
+fn prefixes_compose_with_the_base_paths() {
+    let routes = PrefixedAppModule::prefixed_routes(&GlobalPrefix::new("api/")).unwrap();
+
+    let paths: Vec<&str> = routes.iter().map(|route| route.path.as_str()).collect();
+    assert_eq!(paths, ["/api/admin/users/{name}", "/api/health"]);
+}
+
+#[tokio::test]
+async fn global_and_module_prefixes_are_served() {
+    let app = Application::builder()
+        .container(PrefixedAppModule::create_container().unwrap())
+        .module::<PrefixedAppModule>()
+        .global_prefix("/api")
+        .build()
+        .await
+        .unwrap();
+    assert_eq!(app.routes()[0].path, "/api/admin/users/{name}");
+    let state = AppState {
+        container: app.container().clone(),
+    };
+    let router = app
+        .router::<PrefixedAppModule, AppState>()
+        .unwrap()
+        .with_state(state);
+
+    let (status, body) = get(router.clone(), "/api/admin/users/ada").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, "hello ada");
+    assert_eq!(get(router.clone(), "/api/health").await.1, "ok");
+    assert_eq!(
+        get(router, "/admin/users/ada").await.0,
+        StatusCode::NOT_FOUND
+    );
+}
@@ -0,0 +1,148 @@
+#![cfg(feature = "multipart")]
+
+mod common;
+
+use axum::body::{Body, to_bytes};
+use axum::http::{Request, header};
+use common::AppState;
+use meshestra::prelude::*;
+use serde_json::Value;
+use tower::ServiceExt;
+
+#[derive(FromMultipart)]
+pub struct AvatarUpload {
+    user_id: String,
+    #[part(name = "file", limit = 16)]
+    avatar: UploadedFile,
+    caption: Option<String>,
+}
+
+#[controller(path = "/avatars")]
+pub struct AvatarController {}
+
+#[routes(AvatarController)]
+impl AvatarController {
+    #[post("/")]
+    async fn upload(&self, #[multipart] upload: AvatarUpload) -> String {
+        format!(
+            "{}: {} ({} bytes, {}) {:?}",
+            upload.user_id,
+            upload.avatar.file_name.unwrap_or_default(),
+            upload.avatar.bytes.len(),
+            upload.avatar.content_type.unwrap_or_default(),
+            upload.caption,
+        )
+    }
+}
+
+fn app() -> Router {
+    let container = Container::new();
+    let controller = AvatarController::inject(&container).unwrap();
+    AvatarController::router(Arc::new(controller)).with_state(AppState {
+        container: Arc::new(container),
+    })
+}
+
+const BOUNDARY: &str = "meshestra-boundary";
+
+/// A `multipart/form-data` body; parts with a file name are files
+fn form_data(parts: &[(&str, Option<&str>, &str)]) -> String {
+    let mut body = String::new();
+    for (name, file_name, value) in parts {
+        body.push_str(&format!("--{}\r\n", BOUNDARY));
+        match file_name {
+            Some(file_name) => body.push_str(&format!(
+                "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: image/png\r\n\r\n",
+                name, file_name
+            )),
+            None => body.push_str(&format!(
+                "Content-Disposition: form-data; name=\"{}\"\r\n\r\n",
+                name
+            )),
+        }
+        body.push_str(value);
+        body.push_str("\r\n");
+    }
+    body.push_str(&format!("--{}--\r\n", BOUNDARY));
+    body
+}
+
+async fn post(content_type: &str, body: String) -> (StatusCode, String) {
+    let request = Request::post("/")
+        .header(header::CONTENT_TYPE, content_type)
+        .body(Body::from(body))
+        .unwrap();
+    let response = app().oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, String::from_utf8(bytes.to_vec()).unwrap())
+}
+
+async fn upload(parts: &[(&str, Option<&str>, &str)]) -> (StatusCode, String) {
+    let content_type = format!("multipart/form-data; boundary={}", BOUNDARY);
+    post(&content_type, form_data(parts)).await
+}
+
+fn message(body: &str) -> Value {
+    serde_json::from_str::<Value>(body).unwrap()["error"]["message"].clone()
+}
+
+#[tokio::test]
+async fn parts_are_read_into_fields_by_name() {
+    assert_eq!(
+        upload(&[
+            ("user_id", None, "42"),
+            ("ignored", None, "x"),
+            ("file", Some("me.png"), "PNGDATA"),
+        ])
+        .await,
+        (
+            StatusCode::OK,
+            "42: me.png (7 bytes, image/png) None".to_string()
+        )
+    );
+}
+
+#[tokio::test]
+async fn optional_parts_are_filled_when_sent() {
+    let (status, body) = upload(&[
+        ("caption", None, "hello"),
+        ("user_id", None, "42"),
+        ("file", Some("me.png"), "PNG"),
+    ])
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.ends_with("Some(\"hello\")"));
+}
+
+#[tokio::test]
+async fn missing_part_is_a_400_naming_it() {
+    let (status, body) = upload(&[("file", Some("me.png"), "PNG")]).await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(message(&body), "missing part \"user_id\"");
+}
+
+#[tokio::test]
+async fn part_over_its_limit_is_a_413() {
+    let (status, body) = upload(&[
+        ("user_id", None, "42"),
+        ("file", Some("me.png"), "a picture well over sixteen bytes"),
+    ])
+    .await;
+
+    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(message(&body), "part \"file\" exceeds 16 bytes");
+}
+
+#[tokio::test]
+async fn other_content_types_are_a_415() {
+    let (status, body) = post("application/json", "{}".to_string()).await;
+
+    assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert_eq!(
+        message(&body),
+        "expected `Content-Type: multipart/form-data`, got \"application/json\""
+    );
+}
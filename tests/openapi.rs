@@ -0,0 +1,280 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, header};
+use common::AppState;
+use meshestra::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tower::ServiceExt;
+
+/// A registered user
+#[derive(Serialize, ApiSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct User {
+    pub id: String,
+    /// Shown to other users
+    pub display_name: String,
+    pub email: Option<String>,
+    pub role: Role,
+    #[api(hidden)]
+    pub password_hash: String,
+}
+
+#[derive(Serialize, ApiSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Admin,
+    Member,
+}
+
+#[derive(Deserialize, ApiSchema)]
+pub struct CreateUserRequest {
+    pub display_name: String,
+    pub email: Option<String>,
+}
+
+#[derive(Deserialize, ApiSchema)]
+pub struct Page {
+    pub page: u32,
+    #[serde(default)]
+    pub per_page: Option<u32>,
+}
+
+fn user(id: String) -> User {
+    User {
+        id,
+        display_name: "Ada".to_string(),
+        email: None,
+        role: Role::Member,
+        password_hash: String::new(),
+    }
+}
+
+#[controller(path = "/users")]
+pub struct UserController {}
+
+#[routes(UserController)]
+impl UserController {
+    /// Create a user
+    ///
+    /// The email is optional.
+    #[post("/")]
+    #[status(201)]
+    async fn create(&self, #[body] req: CreateUserRequest) -> Result<Json<User>> {
+        Ok(Json(User {
+            email: req.email,
+            ..user(req.display_name)
+        }))
+    }
+
+    /// List the users
+    #[get("/")]
+    async fn list(
+        &self,
+        #[query] page: Page,
+        #[header("X-Tenant")] tenant: String,
+    ) -> Json<Vec<User>> {
+        let _ = (page.page, page.per_page, tenant);
+        Json(Vec::new())
+    }
+
+    /// Get a user by id
+    #[get("/{id}")]
+    async fn get_one(&self, #[param] id: u64) -> Json<User> {
+        Json(user(id.to_string()))
+    }
+
+    #[get("/{id}/name")]
+    async fn name(&self, #[param] id: String) -> String {
+        id
+    }
+
+    #[get("/internal/stats")]
+    #[api(hidden)]
+    async fn stats(&self) -> String {
+        String::new()
+    }
+}
+
+#[controller(path = "/products")]
+pub struct ProductController {}
+
+#[routes(ProductController)]
+impl ProductController {
+    #[get("/{id}")]
+    async fn get_one(&self, #[param] id: String) -> String {
+        id
+    }
+}
+
+#[module(controllers = [UserController, ProductController])]
+pub struct AppModule;
+
+async fn application() -> Application {
+    Application::builder()
+        .container(AppModule::create_container().unwrap())
+        .module::<AppModule>()
+        .openapi(OpenApiConfig::new("Users", "1.2.0").description("User accounts"))
+        .build()
+        .await
+        .unwrap()
+}
+
+/// The status, content type and body of a GET request to `uri`
+async fn get(router: Router, uri: &str) -> (StatusCode, String, String) {
+    let request = Request::get(uri).body(Body::empty()).unwrap();
+    let response = router.oneshot(request).await.unwrap();
+    let status = response.status();
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .map(|value| value.to_str().unwrap().to_string())
+        .unwrap_or_default();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (
+        status,
+        content_type,
+        String::from_utf8(body.to_vec()).unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn the_document_describes_the_routes() {
+    let app = application().await;
+    let document = app.openapi();
+
+    assert_eq!(document["openapi"], "3.0.3");
+    assert_eq!(document["info"]["title"], "Users");
+    assert_eq!(document["info"]["version"], "1.2.0");
+    assert_eq!(document["info"]["description"], "User accounts");
+
+    let create = &document["paths"]["/users"]["post"];
+    assert_eq!(create["tags"], json!(["User"]));
+    assert_eq!(create["summary"], "Create a user");
+    assert_eq!(create["description"], "The email is optional.");
+    assert_eq!(create["operationId"], "UserController_create");
+    assert_eq!(
+        create["requestBody"]["content"]["application/json"]["schema"],
+        json!({ "$ref": "#/components/schemas/CreateUserRequest" })
+    );
+    assert_eq!(
+        create["responses"]["201"]["content"]["application/json"]["schema"],
+        json!({ "$ref": "#/components/schemas/User" })
+    );
+
+    let list = &document["paths"]["/users"]["get"];
+    assert_eq!(
+        list["parameters"],
+        json!([
+            {
+                "name": "page",
+                "in": "query",
+                "required": true,
+                "schema": { "type": "integer", "format": "int64", "minimum": 0 },
+            },
+            {
+                "name": "per_page",
+                "in": "query",
+                "required": false,
+                "schema": { "type": "integer", "format": "int64", "minimum": 0 },
+            },
+            {
+                "name": "X-Tenant",
+                "in": "header",
+                "required": true,
+                "schema": { "type": "string" },
+            },
+        ])
+    );
+    let items = &list["responses"]["200"]["content"]["application/json"]["schema"];
+    assert_eq!(items["type"], "array");
+
+    let get_one = &document["paths"]["/users/{id}"]["get"];
+    assert_eq!(get_one["parameters"][0]["name"], "id");
+    assert_eq!(get_one["parameters"][0]["in"], "path");
+    assert_eq!(get_one["parameters"][0]["schema"]["format"], "int64");
+    let name = &document["paths"]["/users/{id}/name"]["get"]["responses"]["200"];
+    assert_eq!(name["content"]["text/plain"]["schema"]["type"], "string");
+
+    assert_eq!(
+        document["paths"]["/products/{id}"]["get"]["tags"],
+        json!(["Product"])
+    );
+    assert!(document["paths"].get("/users/internal/stats").is_none());
+    assert!(document["paths"].get("/openapi.json").is_none());
+}
+
+#[tokio::test]
+async fn types_are_described_once_in_the_components() {
+    let app = application().await;
+    let document = app.openapi();
+    let schemas = &document["components"]["schemas"];
+
+    assert_eq!(
+        schemas["User"],
+        json!({
+            "type": "object",
+            "description": "A registered user",
+            "properties": {
+                "id": { "type": "string" },
+                "displayName": { "type": "string", "description": "Shown to other users" },
+                "email": { "type": "string" },
+                "role": { "$ref": "#/components/schemas/Role" },
+            },
+            "required": ["id", "displayName", "role"],
+        })
+    );
+    assert_eq!(
+        schemas["Role"],
+        json!({ "type": "string", "enum": ["admin", "member"] })
+    );
+    assert_eq!(
+        schemas["CreateUserRequest"]["required"],
+        json!(["display_name"])
+    );
+}
+
+#[tokio::test]
+async fn the_document_and_swagger_ui_are_served() {
+    let app = application().await;
+    let state = AppState {
+        container: app.container().clone(),
+    };
+    let router = app
+        .router::<AppModule, AppState>()
+        .unwrap()
+        .with_state(state);
+
+    let (status, content_type, body) = get(router.clone(), "/openapi.json").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content_type, "application/json");
+    let served: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(served, app.openapi());
+
+    let (status, content_type, body) = get(router, "/docs").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(content_type.starts_with("text/html"));
+    assert!(body.contains(r#"url: "/openapi.json""#));
+
+    assert!(
+        app.routes()
+            .iter()
+            .any(|route| route.path == "/docs" && route.handler == "OpenApi::swagger_ui")
+    );
+}
+
+#[tokio::test]
+async fn paths_taken_by_a_controller_fail_the_build() {
+    let result = Application::builder()
+        .container(AppModule::create_container().unwrap())
+        .module::<AppModule>()
+        .openapi(OpenApiConfig::new("Users", "1.2.0").json_path("/users/{id}"))
+        .build()
+        .await;
+
+    let error = result.err().unwrap().to_string();
+    assert!(error.contains("UserController::get_one"), "{}", error);
+}
@@ -0,0 +1,94 @@
+use axum::extract::OriginalUri;
+use meshestra::prelude::*;
+use meshestra::testing::TestApp;
+
+const NAMES: [&str; 5] = ["ada", "brian", "claude", "dennis", "edsger"];
+
+#[controller(path = "/users")]
+pub struct UserController {}
+
+#[routes(UserController)]
+impl UserController {
+    #[get("/")]
+    async fn list(
+        &self,
+        #[query] pagination: Pagination<2, 3>,
+        uri: OriginalUri,
+    ) -> ApiResponse<Vec<&'static str>> {
+        let names = NAMES
+            .iter()
+            .skip(pagination.offset() as usize)
+            .take(pagination.limit() as usize)
+            .copied()
+            .collect();
+        ApiResponse::paginated(names, pagination.page_info(NAMES.len() as u64)).with_links(&uri.0)
+    }
+}
+
+#[module(controllers = [UserController])]
+pub struct UserModule;
+
+async fn app() -> TestApp {
+    TestApp::from_module::<UserModule>().await.unwrap()
+}
+
+#[tokio::test]
+async fn pages_are_wrapped_in_the_envelope() {
+    let response = app().await.get("/users?page=2").await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.text(),
+        r#"{"data":["claude","dennis"],"meta":{"pagination":{"page":2,"per_page":2,"total_items":5,"total_pages":3}},"success":true}"#
+    );
+    assert_eq!(
+        response.header("link"),
+        Some(r#"</users?page=3>; rel="next", </users?page=1>; rel="prev""#)
+    );
+}
+
+#[tokio::test]
+async fn per_page_is_capped_and_kept_in_links() {
+    let response = app().await.get("/users?per_page=50").await;
+
+    assert_eq!(
+        response.text(),
+        r#"{"data":["ada","brian","claude"],"meta":{"pagination":{"page":1,"per_page":3,"total_items":5,"total_pages":2}},"success":true}"#
+    );
+    assert_eq!(
+        response.header("link"),
+        Some(r#"</users?per_page=50&page=2>; rel="next""#)
+    );
+}
+
+#[tokio::test]
+async fn past_the_last_page_is_empty() {
+    let response = app().await.get("/users?page=9").await;
+
+    assert_eq!(
+        response.text(),
+        r#"{"data":[],"meta":{"pagination":{"page":9,"per_page":2,"total_items":5,"total_pages":3}},"success":true}"#
+    );
+    assert_eq!(
+        response.header("link"),
+        Some(r#"</users?page=8>; rel="prev""#)
+    );
+}
+
+#[tokio::test]
+async fn invalid_pages_are_rejected() {
+    let response = app().await.get("/users?page=first").await;
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[test]
+fn errors_have_no_meta() {
+    let response: ApiResponse<Vec<u32>> =
+        ApiResponse::error(meshestra::common::StatusCode::NotFound, "No such list");
+
+    assert_eq!(
+        serde_json::to_string(&response).unwrap(),
+        r#"{"error":{"code":"NotFound","message":"No such list"},"success":false}"#
+    );
+}
@@ -0,0 +1,118 @@
+mod common;
+
+use axum::body::{Body, to_bytes};
+use axum::http::{Request, header};
+use common::AppState;
+use meshestra::prelude::*;
+use tower::ServiceExt;
+
+#[controller(path = "/users")]
+pub struct UserController {}
+
+#[routes(UserController)]
+impl UserController {
+    #[get("/")]
+    async fn list(&self) -> &'static str {
+        "users"
+    }
+
+    #[post("/")]
+    async fn create(&self) -> &'static str {
+        "created"
+    }
+
+    #[get("/{name}")]
+    async fn show(&self, #[param] name: String) -> String {
+        name
+    }
+}
+
+#[module(controllers = [UserController])]
+pub struct AppModule;
+
+async fn app(options: RoutingOptions) -> Router {
+    let app = Application::builder()
+        .container(AppModule::create_container().unwrap())
+        .routing_options(options)
+        .build()
+        .await
+        .unwrap();
+    let state = AppState {
+        container: app.container().clone(),
+    };
+    app.router::<AppModule, AppState>()
+        .unwrap()
+        .with_state(state)
+}
+
+fn trailing_slash(trailing_slash: TrailingSlash) -> RoutingOptions {
+    RoutingOptions {
+        trailing_slash,
+        ..Default::default()
+    }
+}
+
+async fn send(app: Router, request: Request<Body>) -> Response {
+    app.oneshot(request).await.unwrap()
+}
+
+async fn get(app: Router, uri: &str) -> (StatusCode, String) {
+    let response = send(app, Request::get(uri).body(Body::empty()).unwrap()).await;
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, String::from_utf8(bytes.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn paths_are_strict_by_default() {
+    let app = app(RoutingOptions::default()).await;
+
+    assert_eq!(get(app.clone(), "/users").await.0, StatusCode::OK);
+    assert_eq!(get(app.clone(), "/users/").await.0, StatusCode::NOT_FOUND);
+    assert_eq!(get(app, "/Users").await.0, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn merged_trailing_slash_serves_the_route() {
+    let app = app(trailing_slash(TrailingSlash::Merge)).await;
+
+    assert_eq!(
+        get(app.clone(), "/users/").await,
+        (StatusCode::OK, "users".to_string())
+    );
+    assert_eq!(
+        get(app.clone(), "/users/ada/").await,
+        (StatusCode::OK, "ada".to_string())
+    );
+    assert_eq!(get(app, "/teams/").await.0, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn trailing_slash_redirect_keeps_the_method_and_query() {
+    let app = app(trailing_slash(TrailingSlash::Redirect308)).await;
+
+    let request = Request::get("/users/?page=2").body(Body::empty()).unwrap();
+    let response = send(app.clone(), request).await;
+    assert_eq!(response.status(), StatusCode::PERMANENT_REDIRECT);
+    assert_eq!(response.headers()[header::LOCATION], "/users?page=2");
+
+    let request = Request::post("/users/").body(Body::empty()).unwrap();
+    let response = send(app, request).await;
+    assert_eq!(response.status(), StatusCode::PERMANENT_REDIRECT);
+    assert_eq!(response.headers()[header::LOCATION], "/users");
+}
+
+#[tokio::test]
+async fn case_insensitive_paths_keep_the_parameters_case() {
+    let app = app(RoutingOptions {
+        case_insensitive: true,
+        ..Default::default()
+    })
+    .await;
+
+    assert_eq!(
+        get(app.clone(), "/USERS/Ada").await,
+        (StatusCode::OK, "Ada".to_string())
+    );
+    assert_eq!(get(app, "/Users/").await.0, StatusCode::NOT_FOUND);
+}
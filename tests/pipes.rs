@@ -0,0 +1,276 @@
+mod common;
+
+use axum::body::{Body, to_bytes};
+use axum::http::Request;
+use common::AppState;
+use meshestra::prelude::*;
+use serde::Deserialize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tower::ServiceExt;
+
+/// Strips surrounding whitespace
+#[derive(Default)]
+struct Trim;
+
+#[async_trait]
+impl Pipe for Trim {
+    type Input = String;
+    type Output = String;
+
+    async fn transform(&self, input: String) -> PipeResult<String> {
+        Ok(input.trim().to_string())
+    }
+}
+
+/// Rejects names on the container's block list; has no `Default`
+struct NotBlocked(Vec<&'static str>);
+
+#[async_trait]
+impl Pipe for NotBlocked {
+    type Input = String;
+    type Output = String;
+
+    async fn transform(&self, input: String) -> PipeResult<String> {
+        if self.0.contains(&input.as_str()) {
+            Err(PipeError::Validation(format!("{} is blocked", input)))
+        } else {
+            Ok(input)
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct Search {
+    q: String,
+}
+
+/// Takes the search term out of the query
+#[derive(Default)]
+struct Term;
+
+#[async_trait]
+impl Pipe for Term {
+    type Input = Search;
+    type Output = String;
+
+    async fn transform(&self, input: Search) -> PipeResult<String> {
+        Ok(input.q)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct Comment {
+    text: String,
+}
+
+/// Masks a word in every comment; registered as a global pipe
+struct Censor(&'static str);
+
+#[async_trait]
+impl Pipe for Censor {
+    type Input = Comment;
+    type Output = Comment;
+
+    async fn transform(&self, input: Comment) -> PipeResult<Comment> {
+        Ok(Comment {
+            text: input.text.replace(self.0, "****"),
+        })
+    }
+}
+
+/// Takes the text out of a comment
+#[derive(Default)]
+struct Text;
+
+#[async_trait]
+impl Pipe for Text {
+    type Input = Comment;
+    type Output = String;
+
+    async fn transform(&self, input: Comment) -> PipeResult<String> {
+        Ok(input.text)
+    }
+}
+
+/// How often the handlers ran
+#[derive(Default)]
+pub struct Calls(AtomicUsize);
+
+#[controller(path = "/items")]
+pub struct ItemController {
+    calls: Arc<Calls>,
+}
+
+#[routes(ItemController)]
+impl ItemController {
+    #[get("/{id}")]
+    async fn show(
+        &self,
+        #[param]
+        #[pipe(ParseIntPipe)]
+        id: i32,
+    ) -> String {
+        self.calls.0.fetch_add(1, Ordering::SeqCst);
+        format!("item {}", id + 1)
+    }
+
+    #[get("/by-name/{name}")]
+    async fn by_name(
+        &self,
+        #[param]
+        #[pipe(Trim, NotBlocked)]
+        name: String,
+    ) -> String {
+        self.calls.0.fetch_add(1, Ordering::SeqCst);
+        format!("[{}]", name)
+    }
+
+    #[get("/search")]
+    async fn search(
+        &self,
+        #[query]
+        #[pipe(Term)]
+        #[pipe(Trim)]
+        term: String,
+    ) -> String {
+        format!("searching {}", term)
+    }
+}
+
+#[controller(path = "/comments")]
+pub struct CommentController {}
+
+#[routes(CommentController)]
+impl CommentController {
+    #[post("/")]
+    async fn create(
+        &self,
+        #[body]
+        #[pipe(Text)]
+        text: String,
+    ) -> String {
+        text
+    }
+
+    #[post("/raw")]
+    #[skip_global_pipes]
+    async fn raw(&self, #[body] comment: Comment) -> String {
+        comment.text
+    }
+}
+
+fn app() -> (Router, Arc<Calls>) {
+    let mut container = Container::new();
+    container.register(Calls::default());
+    container.register(NotBlocked(vec!["root"]));
+    let calls = container.resolve::<Calls>().unwrap();
+    let controller = ItemController::inject(&container).unwrap();
+
+    let router = ItemController::router(Arc::new(controller)).with_state(AppState {
+        container: Arc::new(container),
+    });
+    (router, calls)
+}
+
+async fn get(app: Router, uri: &str) -> (StatusCode, String) {
+    let request = Request::get(uri).body(Body::empty()).unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, String::from_utf8(bytes.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn pipe_transforms_the_extracted_value() {
+    let (app, _) = app();
+
+    assert_eq!(
+        get(app, "/41").await,
+        (StatusCode::OK, "item 42".to_string())
+    );
+}
+
+#[tokio::test]
+async fn rejected_value_is_a_400_without_running_the_handler() {
+    let (app, calls) = app();
+
+    let (status, body) = get(app, "/forty-one").await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["error"]["code"], "BadRequest");
+    assert_eq!(calls.0.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn stacked_pipes_run_in_order() {
+    let (app, calls) = app();
+
+    assert_eq!(
+        get(app.clone(), "/by-name/%20ada%20").await,
+        (StatusCode::OK, "[ada]".to_string())
+    );
+    // Trimmed before the block list sees it
+    let (status, _) = get(app.clone(), "/by-name/%20root").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(calls.0.load(Ordering::SeqCst), 1);
+
+    assert_eq!(
+        get(app, "/search?q=%20rust%20").await,
+        (StatusCode::OK, "searching rust".to_string())
+    );
+}
+
+async fn comments_app() -> Router {
+    let mut container = Container::new();
+    container.register(Censor("darn"));
+    let app = Application::builder()
+        .container(container)
+        .global_pipe::<Censor>()
+        .build()
+        .await
+        .unwrap();
+    let controller = CommentController::inject(app.container()).unwrap();
+
+    CommentController::router(Arc::new(controller)).with_state(AppState {
+        container: app.container().clone(),
+    })
+}
+
+async fn post(app: Router, uri: &str, text: &str) -> (StatusCode, String) {
+    let request = Request::post(uri)
+        .header("content-type", "application/json")
+        .body(Body::from(serde_json::json!({ "text": text }).to_string()))
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, String::from_utf8(bytes.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn global_pipes_run_before_parameter_pipes() {
+    assert_eq!(
+        post(comments_app().await, "/", "darn it").await,
+        (StatusCode::OK, "**** it".to_string())
+    );
+}
+
+#[tokio::test]
+async fn routes_can_skip_global_pipes() {
+    assert_eq!(
+        post(comments_app().await, "/raw", "darn it").await,
+        (StatusCode::OK, "darn it".to_string())
+    );
+}
+
+#[tokio::test]
+async fn unresolvable_global_pipe_fails_the_build() {
+    let result = Application::builder()
+        .container(Container::new())
+        .global_pipe::<Censor>()
+        .build()
+        .await;
+
+    assert!(result.is_err());
+}
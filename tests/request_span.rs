@@ -0,0 +1,180 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::Request;
+use common::AppState;
+use meshestra::prelude::*;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+use tokio::sync::mpsc;
+use tower::ServiceExt;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+
+type Fields = HashMap<String, String>;
+
+/// An event's message and the fields of its `request` span, if any
+type Recorded = (String, Option<Fields>);
+
+/// Collects field values as strings
+struct FieldVisitor<'a>(&'a mut Fields);
+
+impl Visit for FieldVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{:?}", value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+}
+
+/// Layer recording each event with the fields of the `request` span it
+/// happened in, and the fields of every `request` span once closed
+#[derive(Clone, Default)]
+struct Recorder {
+    events: Arc<Mutex<Vec<Recorded>>>,
+    closed: Arc<Mutex<Vec<Fields>>>,
+}
+
+impl<S> tracing_subscriber::Layer<S> for Recorder
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut fields = Fields::new();
+        attrs.record(&mut FieldVisitor(&mut fields));
+        ctx.span(id).unwrap().extensions_mut().insert(fields);
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let span = ctx.span(id).unwrap();
+        let mut extensions = span.extensions_mut();
+        values.record(&mut FieldVisitor(extensions.get_mut::<Fields>().unwrap()));
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut fields = Fields::new();
+        event.record(&mut FieldVisitor(&mut fields));
+        let request = ctx.event_scope(event).and_then(|mut scope| {
+            scope
+                .find(|span| span.name() == "request")
+                .map(|span| span.extensions().get::<Fields>().unwrap().clone())
+        });
+        self.events
+            .lock()
+            .unwrap()
+            .push((fields.remove("message").unwrap_or_default(), request));
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let span = ctx.span(&id).unwrap();
+        if span.name() == "request" {
+            let fields = span.extensions().get::<Fields>().unwrap().clone();
+            self.closed.lock().unwrap().push(fields);
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct UserViewed {
+    id: u32,
+}
+
+#[controller(path = "/users")]
+pub struct UserController {
+    events: Arc<EventBus>,
+}
+
+#[routes(UserController)]
+impl UserController {
+    #[get("/{id}")]
+    async fn get_user(&self, #[param] id: u32) -> String {
+        tracing::info!("loading user");
+        self.events.publish(UserViewed { id });
+        format!("user {}", id)
+    }
+}
+
+fn app(events: Arc<EventBus>) -> Router {
+    let controller = UserController { events };
+    Router::new()
+        .nest(
+            UserController::base_path(),
+            UserController::router(Arc::new(controller)),
+        )
+        .with_state(AppState {
+            container: Arc::new(Container::new()),
+        })
+}
+
+#[tokio::test]
+async fn handlers_run_in_a_span_named_after_the_route() {
+    let recorder = Recorder::default();
+    let _guard =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(recorder.clone()));
+    let events = Arc::new(EventBus::new());
+    let (handled, mut on_handled) = mpsc::unbounded_channel();
+    events.on(move |viewed: UserViewed| {
+        let handled = handled.clone();
+        async move {
+            tracing::info!("sending view notification");
+            handled.send(viewed.id).unwrap();
+        }
+    });
+
+    let request = Request::get("/users/7")
+        .header("x-request-id", "abc-123")
+        .body(Body::empty())
+        .unwrap();
+    let response = app(events).oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(on_handled.recv().await, Some(7));
+
+    let events = recorder.events.lock().unwrap().clone();
+    for message in ["loading user", "sending view notification"] {
+        let (_, span) = events
+            .iter()
+            .find(|(logged, _)| logged == message)
+            .unwrap_or_else(|| panic!("{:?} was not logged", message));
+        let span = span
+            .as_ref()
+            .unwrap_or_else(|| panic!("{:?} was logged outside the request span", message));
+        assert_eq!(span["otel.name"], "GET /users/{id}");
+        assert_eq!(span["route"], "/users/{id}");
+        assert_eq!(span["controller"], "UserController");
+        assert_eq!(span["handler"], "get_user");
+        assert_eq!(span["request_id"], "abc-123");
+    }
+
+    let closed = recorder.closed.lock().unwrap().clone();
+    assert_eq!(closed.len(), 1);
+    assert_eq!(closed[0]["status"], "200");
+    assert!(closed[0].contains_key("latency_ms"));
+}
+
+#[tokio::test]
+async fn requests_without_an_id_get_one() {
+    let recorder = Recorder::default();
+    let _guard =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(recorder.clone()));
+
+    for _ in 0..2 {
+        let request = Request::get("/users/1").body(Body::empty()).unwrap();
+        let response = app(Arc::new(EventBus::new()))
+            .oneshot(request)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    let closed = recorder.closed.lock().unwrap().clone();
+    assert_eq!(closed.len(), 2);
+    assert!(closed[0]["request_id"].starts_with("req-"));
+    assert_ne!(closed[0]["request_id"], closed[1]["request_id"]);
+}
@@ -0,0 +1,227 @@
+mod common;
+
+use axum::body::{Body, to_bytes};
+use axum::http::{Request, header};
+use common::AppState;
+use meshestra::prelude::*;
+use serde::{Deserialize, Serialize};
+use tower::ServiceExt;
+
+#[derive(Serialize, Deserialize)]
+pub struct Report {
+    name: String,
+}
+
+#[derive(Debug)]
+pub enum ReportError {
+    Missing(u64),
+    Locked(u64),
+}
+
+impl std::fmt::Display for ReportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReportError::Missing(id) => write!(f, "report {} not found", id),
+            ReportError::Locked(id) => write!(f, "report {} is locked", id),
+        }
+    }
+}
+
+impl<T: Serialize> From<ReportError> for ApiResponse<T> {
+    fn from(error: ReportError) -> Self {
+        ApiResponse::error(meshestra::common::StatusCode::Conflict, error.to_string())
+    }
+}
+
+fn find(id: u64) -> std::result::Result<Report, ReportError> {
+    match id {
+        404 => Err(ReportError::Missing(id)),
+        7 => Err(ReportError::Locked(id)),
+        _ => Ok(Report {
+            name: format!("report {}", id),
+        }),
+    }
+}
+
+#[controller(path = "/reports")]
+pub struct ReportController {}
+
+#[routes(ReportController)]
+impl ReportController {
+    #[post("/")]
+    #[status(201)]
+    #[response_header("Cache-Control", "no-store")]
+    #[response_header("X-Report-Version", "2")]
+    async fn create(&self, #[body] report: Report) -> Json<Report> {
+        Json(report)
+    }
+
+    #[post("/invalid")]
+    #[status(201)]
+    async fn invalid(&self) -> ApiResponse<Report> {
+        ApiResponse::error(meshestra::common::StatusCode::BadRequest, "no name")
+    }
+
+    #[post("/queued")]
+    #[status(201)]
+    async fn queued(&self) -> ApiResponse<&'static str> {
+        ApiResponse::success("queued").with_status(StatusCode::ACCEPTED)
+    }
+
+    #[get("/{id}")]
+    async fn get_one(&self, #[param] id: u64) -> std::result::Result<Json<Report>, HttpException> {
+        let report = find(id).or_status(meshestra::common::StatusCode::NotFound)?;
+        Ok(Json(report))
+    }
+
+    #[post("/{id}/publish")]
+    #[status(201)]
+    async fn publish(&self, #[param] id: u64) -> Result<ApiResponse<Report>> {
+        match id {
+            0 => Err(HttpException::forbidden("report 0 is a draft").into()),
+            9 => Err(MeshestraError::Internal("storage offline".to_string())),
+            _ => Ok(ApiResponse::from_result(find(id))),
+        }
+    }
+
+    #[get("/{id}/summary")]
+    async fn summary(&self, #[param] id: u64) -> ApiResponse<Report> {
+        find(id).not_found_when(|error| matches!(error, ReportError::Missing(_)))
+    }
+
+    #[get("/export")]
+    #[produces("text/csv")]
+    async fn export(&self) -> String {
+        "name\nyearly\n".to_string()
+    }
+}
+
+fn app() -> Router {
+    let container = Container::new();
+    let controller = ReportController::inject(&container).unwrap();
+    ReportController::router(Arc::new(controller)).with_state(AppState {
+        container: Arc::new(container),
+    })
+}
+
+async fn send(request: Request<Body>) -> Response {
+    app().oneshot(request).await.unwrap()
+}
+
+async fn text(response: Response) -> String {
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    String::from_utf8(bytes.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn created_resource_is_a_201_with_the_route_headers() {
+    let request = Request::post("/")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(r#"{"name":"yearly"}"#))
+        .unwrap();
+
+    let response = send(request).await;
+
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert_eq!(response.headers()[header::CACHE_CONTROL], "no-store");
+    assert_eq!(response.headers()["x-report-version"], "2");
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+    assert_eq!(text(response).await, r#"{"name":"yearly"}"#);
+}
+
+#[tokio::test]
+async fn rejected_requests_keep_their_error_status() {
+    let request = Request::post("/")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from("{}"))
+        .unwrap();
+    assert_eq!(
+        send(request).await.status(),
+        StatusCode::UNPROCESSABLE_ENTITY
+    );
+
+    let request = Request::post("/invalid").body(Body::empty()).unwrap();
+    assert_eq!(send(request).await.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn explicit_api_response_status_wins() {
+    let request = Request::post("/queued").body(Body::empty()).unwrap();
+
+    assert_eq!(send(request).await.status(), StatusCode::ACCEPTED);
+}
+
+#[tokio::test]
+async fn csv_export_is_labelled_text_csv() {
+    let request = Request::get("/export").body(Body::empty()).unwrap();
+
+    let response = send(request).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "text/csv");
+    assert_eq!(text(response).await, "name\nyearly\n");
+}
+
+async fn status_and_body(request: Request<Body>) -> (StatusCode, serde_json::Value) {
+    let response = send(request).await;
+    let status = response.status();
+    (status, serde_json::from_str(&text(response).await).unwrap())
+}
+
+#[tokio::test]
+async fn result_of_json_or_http_exception_answers_both_arms() {
+    let (status, body) = status_and_body(Request::get("/1").body(Body::empty()).unwrap()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["name"], "report 1");
+
+    let (status, body) = status_and_body(Request::get("/404").body(Body::empty()).unwrap()).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["error"]["code"], "NotFound");
+    assert_eq!(body["error"]["message"], "report 404 not found");
+}
+
+#[tokio::test]
+async fn result_of_api_response_or_meshestra_error_answers_both_arms() {
+    let publish = |id: u64| {
+        Request::post(format!("/{}/publish", id))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let (status, body) = status_and_body(publish(1)).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(body["data"]["name"], "report 1");
+
+    let (status, body) = status_and_body(publish(0)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["error"]["message"], "report 0 is a draft");
+
+    let (status, body) = status_and_body(publish(9)).await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(body["error"]["code"], "InternalError");
+
+    // The `Ok` arm converts the service's own error.
+    let (status, body) = status_and_body(publish(7)).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["error"]["message"], "report 7 is locked");
+}
+
+#[tokio::test]
+async fn not_found_when_picks_the_errors_answering_404() {
+    let summary = |id: u64| {
+        Request::get(format!("/{}/summary", id))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let (status, body) = status_and_body(summary(2)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["name"], "report 2");
+
+    let (status, body) = status_and_body(summary(404)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["error"]["message"], "report 404 not found");
+
+    let (status, _) = status_and_body(summary(7)).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+}
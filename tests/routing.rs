@@ -0,0 +1,243 @@
+mod common;
+
+use axum::body::{Body, to_bytes};
+use axum::http::{Method, Request, header};
+use common::AppState;
+use meshestra::prelude::*;
+use tower::ServiceExt;
+
+#[controller(path = "/articles", auto_head = true)]
+pub struct ArticleController {}
+
+#[routes(ArticleController)]
+impl ArticleController {
+    #[get("/")]
+    #[response_header("X-Total-Count", "2")]
+    async fn list(&self) -> String {
+        "first, second".to_string()
+    }
+
+    #[post("/")]
+    async fn create(&self) -> String {
+        "created".to_string()
+    }
+
+    #[options("/")]
+    async fn describe(&self) -> String {
+        "GET, POST".to_string()
+    }
+
+    #[get("/latest")]
+    async fn latest(&self) -> String {
+        "second".to_string()
+    }
+
+    #[head("/latest")]
+    async fn latest_head(&self) -> Response {
+        (StatusCode::NO_CONTENT, [("x-latest", "second")]).into_response()
+    }
+}
+
+#[controller(path = "/comments")]
+pub struct CommentController {}
+
+#[routes(CommentController)]
+impl CommentController {
+    #[get("/")]
+    async fn list(&self) -> String {
+        "nice".to_string()
+    }
+
+    #[get("/{id}", alias = "/by-id/{id}")]
+    #[get("/legacy/{id}")]
+    async fn show(&self, #[param] id: u32) -> String {
+        format!("comment {}", id)
+    }
+
+    #[get("/{id}/replies/{reply}")]
+    async fn reply(&self, #[param] id: u32, #[param] reply: String) -> String {
+        format!("reply {} to comment {}", reply, id)
+    }
+
+    #[get("/{id}/votes/{voter}/{vote}")]
+    async fn vote(&self, #[param("vote")] up: bool, #[param] id: i64) -> String {
+        format!("{} on comment {}", if up { "up" } else { "down" }, id)
+    }
+
+    #[get("/{id}/thread/{depth}")]
+    async fn thread(&self, #[param] (id, depth): (u32, u8)) -> String {
+        format!("comment {} to depth {}", id, depth)
+    }
+}
+
+#[controller(path = "/tags")]
+pub struct TagController {}
+
+/// Routes written with `:name` are still served, under a deprecation warning
+#[routes(TagController)]
+#[allow(deprecated)]
+impl TagController {
+    #[get("/{name}")]
+    async fn show(&self, #[param] name: String) -> String {
+        format!("tag {}", name)
+    }
+
+    #[get("/:name/posts/:page")]
+    async fn posts(&self, #[param] name: String, #[param] page: u32) -> String {
+        format!("page {} of tag {}", page, name)
+    }
+}
+
+#[controller(path = "/labels")]
+pub struct LabelController {}
+
+#[routes(LabelController)]
+impl LabelController {
+    #[get("/{id}")]
+    async fn by_id(&self, #[param] id: u32) -> String {
+        format!("label {}", id)
+    }
+
+    #[get("/{name}")]
+    async fn by_name(&self, #[param] name: String) -> String {
+        format!("label {}", name)
+    }
+}
+
+fn app() -> Router {
+    let container = Arc::new(Container::new());
+    let articles = ArticleController::inject(&container).unwrap();
+    let comments = CommentController::inject(&container).unwrap();
+    let tags = TagController::inject(&container).unwrap();
+    Router::new()
+        .nest(
+            ArticleController::base_path(),
+            ArticleController::router(Arc::new(articles)),
+        )
+        .nest(
+            CommentController::base_path(),
+            CommentController::router(Arc::new(comments)),
+        )
+        .nest(
+            TagController::base_path(),
+            TagController::router(Arc::new(tags)),
+        )
+        .with_state(AppState { container })
+}
+
+async fn send(method: Method, uri: &str) -> (Response, String) {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .body(Body::empty())
+        .unwrap();
+    let response = app().oneshot(request).await.unwrap();
+    let (parts, body) = response.into_parts();
+    let bytes = to_bytes(body, usize::MAX).await.unwrap();
+    (
+        Response::from_parts(parts, Body::empty()),
+        String::from_utf8(bytes.to_vec()).unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn options_routes_are_served() {
+    let (response, body) = send(Method::OPTIONS, "/articles").await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body, "GET, POST");
+}
+
+#[tokio::test]
+async fn auto_head_answers_with_the_get_headers_and_no_body() {
+    let (response, body) = send(Method::HEAD, "/articles").await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["x-total-count"], "2");
+    assert_eq!(body, "");
+}
+
+#[tokio::test]
+async fn explicit_head_route_wins_over_auto_head() {
+    let (response, _) = send(Method::HEAD, "/articles/latest").await;
+
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert_eq!(response.headers()["x-latest"], "second");
+}
+
+#[tokio::test]
+async fn head_is_refused_without_auto_head() {
+    let (response, _) = send(Method::HEAD, "/comments").await;
+
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(response.headers()[header::ALLOW], "GET");
+}
+
+#[tokio::test]
+async fn unrouted_method_is_a_405_listing_the_allowed_ones() {
+    let (response, body) = send(Method::DELETE, "/articles").await;
+
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(
+        response.headers()[header::ALLOW],
+        "GET, POST, OPTIONS, HEAD"
+    );
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["error"]["code"], "MethodNotAllowed");
+
+    let (response, _) = send(Method::PUT, "/articles/latest").await;
+    assert_eq!(response.headers()[header::ALLOW], "GET, HEAD");
+}
+
+#[tokio::test]
+async fn unknown_paths_are_still_404() {
+    let (response, _) = send(Method::GET, "/articles/none/here").await;
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn aliases_reach_the_same_handler() {
+    for uri in ["/comments/7", "/comments/by-id/7", "/comments/legacy/7"] {
+        let (response, body) = send(Method::GET, uri).await;
+
+        assert_eq!(response.status(), StatusCode::OK, "{}", uri);
+        assert_eq!(body, "comment 7");
+    }
+}
+
+#[tokio::test]
+async fn several_params_are_taken_in_path_order_or_by_name() {
+    let (_, body) = send(Method::GET, "/comments/7/replies/first").await;
+    assert_eq!(body, "reply first to comment 7");
+
+    let (_, body) = send(Method::GET, "/comments/7/thread/3").await;
+    assert_eq!(body, "comment 7 to depth 3");
+
+    let (_, body) = send(Method::GET, "/comments/7/votes/ada/true").await;
+    assert_eq!(body, "up on comment 7");
+
+    let (response, _) = send(Method::GET, "/comments/seven/replies/first").await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn colon_params_are_routed_as_brace_params() {
+    let (_, body) = send(Method::GET, "/tags/rust").await;
+    assert_eq!(body, "tag rust");
+    let (response, body) = send(Method::GET, "/tags/rust/posts/2").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body, "page 2 of tag rust");
+
+    let paths: Vec<String> = TagController::route_table()
+        .into_iter()
+        .map(|route| route.path)
+        .collect();
+    assert_eq!(paths, ["/tags/{name}", "/tags/{name}/posts/{page}"]);
+}
+
+#[test]
+#[should_panic(expected = "can't route `/{name}` to LabelController::by_name")]
+fn conflicting_routes_name_the_handler() {
+    let _ = LabelController::router::<AppState>(Arc::new(LabelController {}));
+}
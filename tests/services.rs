@@ -0,0 +1,116 @@
+mod common;
+
+use axum::body::{Body, to_bytes};
+use axum::http::{Request, header};
+use common::AppState;
+use meshestra::prelude::*;
+use serde::Deserialize;
+use tower::ServiceExt;
+
+pub struct ReportService {
+    prefix: &'static str,
+}
+
+impl ReportService {
+    fn render(&self, title: &str) -> String {
+        format!("{}{}", self.prefix, title)
+    }
+}
+
+pub trait Clock: Send + Sync {
+    fn now(&self) -> u64;
+}
+
+pub struct FixedClock;
+
+impl Clock for FixedClock {
+    fn now(&self) -> u64 {
+        1_700_000_000
+    }
+}
+
+/// Never registered
+pub struct Mailer;
+
+#[derive(Deserialize)]
+pub struct ReportRequest {
+    title: String,
+}
+
+#[controller(path = "/reports")]
+pub struct ReportController {}
+
+#[routes(ReportController)]
+impl ReportController {
+    #[get("/{title}")]
+    async fn show(&self, #[service] reports: Arc<ReportService>, #[param] title: String) -> String {
+        reports.render(&title)
+    }
+
+    #[post("/")]
+    async fn create(
+        &self,
+        #[body] request: ReportRequest,
+        #[service] reports: Arc<ReportService>,
+        #[service] clock: Arc<dyn Clock>,
+    ) -> String {
+        format!("{} at {}", reports.render(&request.title), clock.now())
+    }
+
+    #[post("/mail")]
+    async fn mail(&self, #[service] _mailer: Arc<Mailer>) -> String {
+        "sent".to_string()
+    }
+}
+
+fn app() -> Router {
+    let mut container = Container::new();
+    container.register(ReportService { prefix: "# " });
+    container.register(FixedClock);
+    container.register_trait::<dyn Clock, FixedClock, _>(|clock| clock as Arc<dyn Clock>);
+    let controller = ReportController::inject(&container).unwrap();
+    ReportController::router(Arc::new(controller)).with_state(AppState {
+        container: Arc::new(container),
+    })
+}
+
+async fn send(request: Request<Body>) -> (StatusCode, String) {
+    let response = app().oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, String::from_utf8(bytes.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn services_are_resolved_per_request() {
+    let request = Request::get("/weekly").body(Body::empty()).unwrap();
+
+    assert_eq!(
+        send(request).await,
+        (StatusCode::OK, "# weekly".to_string())
+    );
+}
+
+#[tokio::test]
+async fn services_may_follow_the_body() {
+    let request = Request::post("/")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(r#"{"title":"yearly"}"#))
+        .unwrap();
+
+    assert_eq!(
+        send(request).await,
+        (StatusCode::OK, "# yearly at 1700000000".to_string())
+    );
+}
+
+#[tokio::test]
+async fn missing_service_is_a_500() {
+    let request = Request::post("/mail").body(Body::empty()).unwrap();
+
+    let (status, body) = send(request).await;
+
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["error"]["code"], "DependencyNotFound");
+}
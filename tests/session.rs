@@ -0,0 +1,267 @@
+#![cfg(feature = "session")]
+
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, header};
+use common::AppState;
+use meshestra::prelude::*;
+use meshestra::session::SameSite;
+use std::time::Duration;
+use tower::ServiceExt;
+
+const SECRET: &str = "an-example-secret-of-32-bytes-at-least";
+
+#[controller(path = "/session")]
+pub struct SessionController {}
+
+#[routes(SessionController)]
+impl SessionController {
+    #[post("/visit")]
+    async fn visit(&self, #[session] session: Session) -> String {
+        let visits = session.get::<u32>("visits").unwrap_or(0) + 1;
+        session.insert("visits", visits).unwrap();
+        visits.to_string()
+    }
+
+    #[post("/login/{user}")]
+    async fn login(&self, #[session] session: Session, #[param] user: String) -> &'static str {
+        session.regenerate_id();
+        session.insert("user", user).unwrap();
+        "welcome"
+    }
+
+    #[get("/whoami")]
+    async fn whoami(&self, #[session] session: Session) -> String {
+        session
+            .get("user")
+            .unwrap_or_else(|| "anonymous".to_string())
+    }
+
+    /// Sets `key` after `ms` milliseconds
+    #[post("/flags/{key}/{ms}")]
+    async fn flag(
+        &self,
+        #[session] session: Session,
+        #[param] key: String,
+        #[param] ms: u64,
+    ) -> &'static str {
+        tokio::time::sleep(Duration::from_millis(ms)).await;
+        session.insert(&key, true).unwrap();
+        "set"
+    }
+
+    #[get("/flags/{key}")]
+    async fn has_flag(&self, #[session] session: Session, #[param] key: String) -> String {
+        session.get::<bool>(&key).unwrap_or(false).to_string()
+    }
+
+    #[post("/logout")]
+    async fn logout(&self, #[session] session: Session) -> &'static str {
+        session.clear();
+        "bye"
+    }
+}
+
+#[module(controllers = [SessionController])]
+pub struct AppModule;
+
+async fn app(config: SessionConfig) -> Router {
+    let app = Application::builder()
+        .container(AppModule::create_container().unwrap())
+        .module::<AppModule>()
+        .sessions(SessionModule::new(config))
+        .build()
+        .await
+        .unwrap();
+    let state = AppState {
+        container: app.container().clone(),
+    };
+    app.router::<AppModule, AppState>()
+        .unwrap()
+        .with_state(state)
+}
+
+/// Send a `method` request to `uri` with the session `cookie`, returning the
+/// body and the `Set-Cookie` header if any
+async fn send(
+    app: &Router,
+    method: &str,
+    uri: &str,
+    cookie: Option<&str>,
+) -> (String, Option<String>) {
+    let mut request = Request::builder().method(method).uri(uri);
+    if let Some(cookie) = cookie {
+        request = request.header(header::COOKIE, cookie);
+    }
+    let response = app
+        .clone()
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let set_cookie = response
+        .headers()
+        .get(header::SET_COOKIE)
+        .map(|value| value.to_str().unwrap().to_string());
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (String::from_utf8(body.to_vec()).unwrap(), set_cookie)
+}
+
+/// The `name=value` pair a `Set-Cookie` header sets
+fn cookie_pair(set_cookie: &str) -> String {
+    set_cookie.split(';').next().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn sessions_survive_between_requests() {
+    let app = app(SessionConfig::new(SECRET)).await;
+
+    let (visits, set_cookie) = send(&app, "POST", "/session/visit", None).await;
+    assert_eq!(visits, "1");
+    let set_cookie = set_cookie.unwrap();
+    assert!(set_cookie.starts_with("meshestra.sid="));
+    assert!(set_cookie.contains("HttpOnly"));
+    assert!(set_cookie.contains("SameSite=Lax"));
+    assert!(set_cookie.contains("Secure"));
+    assert!(set_cookie.contains("Max-Age=86400") || set_cookie.contains("Max-Age=86399"));
+    let cookie = cookie_pair(&set_cookie);
+
+    let (visits, set_cookie) = send(&app, "POST", "/session/visit", Some(&cookie)).await;
+    assert_eq!(visits, "2");
+    // The cookie is only set again when it changes.
+    assert_eq!(set_cookie, None);
+}
+
+#[tokio::test]
+async fn untouched_sessions_set_no_cookie() {
+    let app = app(SessionConfig::new(SECRET)).await;
+
+    let (user, set_cookie) = send(&app, "GET", "/session/whoami", None).await;
+
+    assert_eq!(user, "anonymous");
+    assert_eq!(set_cookie, None);
+}
+
+#[tokio::test]
+async fn tampered_cookies_are_ignored() {
+    let other = app(SessionConfig::new("another-secret-of-at-least-32-bytes!")).await;
+    let app = app(SessionConfig::new(SECRET)).await;
+    let (_, set_cookie) = send(&app, "POST", "/session/login/ada", None).await;
+    let cookie = cookie_pair(&set_cookie.unwrap());
+
+    // Another id under the same signature
+    let (id, signature) = cookie.rsplit_once('.').unwrap();
+    let last = if id.ends_with('0') { '1' } else { '0' };
+    let forged = format!("{}{}.{}", &id[..id.len() - 1], last, signature);
+    let (user, _) = send(&app, "GET", "/session/whoami", Some(&forged)).await;
+    assert_eq!(user, "anonymous");
+
+    // Signed with another secret
+    let (user, _) = send(&other, "GET", "/session/whoami", Some(&cookie)).await;
+    assert_eq!(user, "anonymous");
+
+    let (user, _) = send(&app, "GET", "/session/whoami", Some(&cookie)).await;
+    assert_eq!(user, "ada");
+}
+
+#[tokio::test]
+async fn regenerate_id_leaves_the_old_id_unusable() {
+    let app = app(SessionConfig::new(SECRET)).await;
+    // The id an attacker got the victim's browser to use
+    let (_, set_cookie) = send(&app, "POST", "/session/visit", None).await;
+    let planted = cookie_pair(&set_cookie.unwrap());
+
+    let (_, set_cookie) = send(&app, "POST", "/session/login/ada", Some(&planted)).await;
+    let cookie = cookie_pair(&set_cookie.expect("login hands out a new cookie"));
+    assert_ne!(cookie, planted);
+
+    let (user, _) = send(&app, "GET", "/session/whoami", Some(&cookie)).await;
+    assert_eq!(user, "ada");
+    let (visits, _) = send(&app, "POST", "/session/visit", Some(&cookie)).await;
+    assert_eq!(visits, "2", "the data moves to the new id");
+
+    let (user, _) = send(&app, "GET", "/session/whoami", Some(&planted)).await;
+    assert_eq!(user, "anonymous");
+}
+
+#[tokio::test]
+async fn concurrent_requests_last_write_wins() {
+    let app = app(SessionConfig::new(SECRET)).await;
+    let (_, set_cookie) = send(&app, "POST", "/session/visit", None).await;
+    let cookie = cookie_pair(&set_cookie.unwrap());
+
+    // Both load the session before either saves; the slow one saves last,
+    // without the fast one's flag.
+    tokio::join!(
+        send(&app, "POST", "/session/flags/slow/100", Some(&cookie)),
+        send(&app, "POST", "/session/flags/fast/0", Some(&cookie)),
+    );
+
+    let (slow, _) = send(&app, "GET", "/session/flags/slow", Some(&cookie)).await;
+    let (fast, _) = send(&app, "GET", "/session/flags/fast", Some(&cookie)).await;
+    assert_eq!(slow, "true");
+    assert_eq!(fast, "false");
+}
+
+#[tokio::test]
+async fn clear_deletes_the_session_and_its_cookie() {
+    let app = app(SessionConfig::new(SECRET)).await;
+    let (_, set_cookie) = send(&app, "POST", "/session/login/ada", None).await;
+    let cookie = cookie_pair(&set_cookie.unwrap());
+
+    let (_, set_cookie) = send(&app, "POST", "/session/logout", Some(&cookie)).await;
+
+    let set_cookie = set_cookie.expect("the cookie is expired");
+    assert!(set_cookie.starts_with("meshestra.sid=;"));
+    assert!(set_cookie.contains("Max-Age=0"));
+    let (user, _) = send(&app, "GET", "/session/whoami", Some(&cookie)).await;
+    assert_eq!(user, "anonymous");
+}
+
+#[tokio::test]
+async fn rolling_sessions_renew_the_cookie_on_every_request() {
+    let config = SessionConfig::new(SECRET)
+        .cookie_name("sid")
+        .same_site(SameSite::Strict)
+        .ttl(Duration::from_secs(60))
+        .rolling(true);
+    let app = app(config).await;
+    let (_, set_cookie) = send(&app, "POST", "/session/login/ada", None).await;
+    let cookie = cookie_pair(&set_cookie.unwrap());
+    assert!(cookie.starts_with("sid="));
+
+    let (user, set_cookie) = send(&app, "GET", "/session/whoami", Some(&cookie)).await;
+
+    assert_eq!(user, "ada");
+    let set_cookie = set_cookie.expect("reading renews the session");
+    assert_eq!(cookie_pair(&set_cookie), cookie);
+    assert!(set_cookie.contains("SameSite=Strict"));
+    assert!(set_cookie.contains("Max-Age=60") || set_cookie.contains("Max-Age=59"));
+}
+
+#[tokio::test]
+async fn expired_sessions_are_gone() {
+    let app = app(SessionConfig::new(SECRET).ttl(Duration::from_millis(50))).await;
+    let (_, set_cookie) = send(&app, "POST", "/session/login/ada", None).await;
+    let cookie = cookie_pair(&set_cookie.unwrap());
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let (user, _) = send(&app, "GET", "/session/whoami", Some(&cookie)).await;
+    assert_eq!(user, "anonymous");
+}
+
+#[tokio::test]
+async fn short_secrets_fail_the_build() {
+    let result = Application::builder()
+        .container(AppModule::create_container().unwrap())
+        .module::<AppModule>()
+        .sessions(SessionModule::new(SessionConfig::new("secret")))
+        .build()
+        .await;
+
+    assert!(result.is_err());
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use axum::body::{Body, Bytes, to_bytes};
+use axum::http::{Request, header};
+use common::AppState;
+use futures_util::{StreamExt, stream};
+use meshestra::controller::sse::{LagPolicy, SseOptions};
+use meshestra::prelude::*;
+use serde::Serialize;
+use tower::ServiceExt;
+
+#[derive(Clone, Serialize)]
+pub struct Tick {
+    name: String,
+}
+
+#[derive(Clone, Serialize)]
+pub struct UserCreatedEvent {
+    id: u32,
+}
+
+#[controller(path = "/feed")]
+pub struct FeedController {
+    events: Arc<EventBus>,
+    names: Arc<Vec<String>>,
+}
+
+#[routes(FeedController)]
+impl FeedController {
+    /// Borrows `self` for as long as the client listens
+    #[get("/ticks")]
+    async fn ticks(&self) -> impl Stream<Item = Tick> {
+        stream::iter(self.names.iter().map(|name| Tick { name: name.clone() }))
+    }
+
+    #[get("/users")]
+    async fn users(&self) -> Sse<KeepAliveStream<EventStream>> {
+        self.events.sse_stream::<UserCreatedEvent>()
+    }
+}
+
+fn app(events: Arc<EventBus>) -> Router {
+    let controller = FeedController {
+        events,
+        names: Arc::new(vec!["a".to_string(), "b".to_string()]),
+    };
+    Router::new()
+        .nest(
+            FeedController::base_path(),
+            FeedController::router(Arc::new(controller)),
+        )
+        .with_state(AppState {
+            container: Arc::new(Container::new()),
+        })
+}
+
+async fn open(app: Router, uri: &str) -> Response {
+    let request = Request::get(uri).body(Body::empty()).unwrap();
+    app.oneshot(request).await.unwrap()
+}
+
+/// The next event of a stream that is still open
+async fn next_event(
+    events: &mut (impl Stream<Item = std::result::Result<Bytes, axum::Error>> + Unpin),
+) -> Bytes {
+    events.next().await.unwrap().unwrap()
+}
+
+#[tokio::test]
+async fn stream_handlers_send_json_events() {
+    let response = open(app(Arc::new(EventBus::new())), "/feed/ticks").await;
+
+    assert_eq!(
+        response.headers()[header::CONTENT_TYPE],
+        "text/event-stream"
+    );
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(body, "data: {\"name\":\"a\"}\n\ndata: {\"name\":\"b\"}\n\n");
+}
+
+#[tokio::test]
+async fn bus_events_reach_the_client_until_it_goes_away() {
+    let bus = Arc::new(EventBus::new());
+    let response = open(app(bus.clone()), "/feed/users").await;
+    assert_eq!(bus.subscriber_count::<UserCreatedEvent>(), 1);
+
+    let mut events = response.into_body().into_data_stream();
+    bus.publish(UserCreatedEvent { id: 7 });
+    assert_eq!(next_event(&mut events).await, "data: {\"id\":7}\n\n");
+
+    drop(events);
+    assert_eq!(bus.subscriber_count::<UserCreatedEvent>(), 0);
+}
+
+#[tokio::test]
+async fn lagging_clients_are_told_what_they_missed() {
+    let bus = EventBus::with_capacity(1);
+    let options = SseOptions {
+        lag: LagPolicy::Notify,
+        ..Default::default()
+    };
+    let sse = bus.sse_stream_with::<UserCreatedEvent>(&options);
+    let mut events = sse.into_response().into_body().into_data_stream();
+
+    for id in 1..=3 {
+        bus.publish(UserCreatedEvent { id });
+    }
+
+    assert_eq!(next_event(&mut events).await, "event: lagged\ndata: 2\n\n");
+    assert_eq!(next_event(&mut events).await, "data: {\"id\":3}\n\n");
+}
@@ -0,0 +1,213 @@
+use meshestra::prelude::*;
+use meshestra::testing::TestApp;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::RwLock;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct User {
+    pub id: String,
+    pub name: String,
+    pub email: String,
+}
+
+#[derive(Deserialize)]
+pub struct CreateUserRequest {
+    pub name: String,
+    pub email: String,
+}
+
+pub trait UserRepository: Send + Sync {
+    fn find_by_id(&self, id: &str) -> Option<User>;
+    fn save(&self, user: User) -> User;
+    fn find_all(&self) -> Vec<User>;
+}
+
+/// Never registered: the real repository can't be injected in the tests
+pub struct DatabaseConnection;
+
+#[derive(Injectable)]
+pub struct UserRepositoryImpl {
+    _db: Arc<DatabaseConnection>,
+}
+
+impl UserRepository for UserRepositoryImpl {
+    fn find_by_id(&self, _id: &str) -> Option<User> {
+        unreachable!("the tests use the in-memory repository")
+    }
+
+    fn save(&self, _user: User) -> User {
+        unreachable!("the tests use the in-memory repository")
+    }
+
+    fn find_all(&self) -> Vec<User> {
+        unreachable!("the tests use the in-memory repository")
+    }
+}
+
+#[derive(Default)]
+pub struct InMemoryUserRepository {
+    users: Mutex<HashMap<String, User>>,
+}
+
+impl UserRepository for InMemoryUserRepository {
+    fn find_by_id(&self, id: &str) -> Option<User> {
+        self.users.lock().unwrap().get(id).cloned()
+    }
+
+    fn save(&self, user: User) -> User {
+        self.users
+            .lock()
+            .unwrap()
+            .insert(user.id.clone(), user.clone());
+        user
+    }
+
+    fn find_all(&self) -> Vec<User> {
+        let mut users: Vec<User> = self.users.lock().unwrap().values().cloned().collect();
+        users.sort_by(|a, b| a.id.cmp(&b.id));
+        users
+    }
+}
+
+#[derive(Injectable)]
+pub struct UserService {
+    repository: Arc<dyn UserRepository>,
+}
+
+impl UserService {
+    fn create(&self, req: CreateUserRequest) -> User {
+        let id = format!("user-{}", self.repository.find_all().len() + 1);
+        self.repository.save(User {
+            id,
+            name: req.name,
+            email: req.email,
+        })
+    }
+}
+
+#[controller(path = "/users")]
+pub struct UserController {
+    service: Arc<UserService>,
+}
+
+#[routes(UserController)]
+impl UserController {
+    #[post("/")]
+    #[status(201)]
+    async fn create(&self, #[body] req: CreateUserRequest) -> Json<User> {
+        Json(self.service.create(req))
+    }
+
+    #[get("/{id}")]
+    async fn get_one(&self, #[param] id: String) -> std::result::Result<Json<User>, HttpException> {
+        self.service
+            .repository
+            .find_by_id(&id)
+            .map(Json)
+            .ok_or_else(|| HttpException::not_found(format!("User {} not found", id)))
+    }
+
+    #[get("/")]
+    async fn list(&self) -> Json<Vec<User>> {
+        Json(self.service.repository.find_all())
+    }
+}
+
+#[module(
+    controllers = [UserController],
+    providers = [
+        Provider::new(UserRepositoryImpl).for_trait::<dyn UserRepository>(),
+        UserService,
+    ],
+)]
+pub struct UserModule;
+
+/// Records its destroy hook
+struct Cache {
+    destroyed: Arc<AtomicBool>,
+}
+
+#[async_trait]
+impl OnModuleDestroy for Cache {
+    async fn on_module_destroy(&mut self) -> std::result::Result<(), LifecycleError> {
+        self.destroyed.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+async fn test_app() -> TestApp {
+    TestApp::builder()
+        .override_trait::<dyn UserRepository, InMemoryUserRepository, _>(
+            InMemoryUserRepository::default(),
+            |repository| repository as Arc<dyn UserRepository>,
+        )
+        .build::<UserModule>()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn users_are_created_and_fetched_end_to_end() {
+    let app = test_app().await;
+
+    let created = app
+        .post("/users")
+        .json(&json!({ "name": "Ada", "email": "ada@example.com" }))
+        .await;
+    assert_eq!(created.status(), StatusCode::CREATED);
+    assert_eq!(created.header("content-type"), Some("application/json"));
+    let user: User = created.json();
+    assert_eq!(user.name, "Ada");
+
+    let fetched = app.get(&format!("/users/{}", user.id)).await;
+    assert_eq!(fetched.status(), StatusCode::OK);
+    assert_eq!(fetched.json::<User>().email, "ada@example.com");
+
+    let listed = app.get("/users").await;
+    assert_eq!(listed.json::<Vec<User>>().len(), 1);
+
+    let missing = app.get("/users/nobody").await;
+    assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+    assert!(missing.text().contains("User nobody not found"));
+}
+
+#[tokio::test]
+async fn overridden_providers_are_never_injected() {
+    let app = test_app().await;
+
+    assert!(app.container().resolve::<UserRepositoryImpl>().is_err());
+    assert!(app.container().resolve::<InMemoryUserRepository>().is_ok());
+
+    let error = TestApp::from_module::<UserModule>().await.err().unwrap();
+    assert!(
+        error.to_string().contains("DatabaseConnection"),
+        "{}",
+        error
+    );
+}
+
+#[tokio::test]
+async fn destroy_hooks_run_when_the_app_is_dropped() {
+    let destroyed = Arc::new(AtomicBool::new(false));
+    let cache = Arc::new(RwLock::new(Cache {
+        destroyed: destroyed.clone(),
+    }));
+    let app = TestApp::builder()
+        .override_trait::<dyn UserRepository, InMemoryUserRepository, _>(
+            InMemoryUserRepository::default(),
+            |repository| repository as Arc<dyn UserRepository>,
+        )
+        .configure(move |builder| builder.on_destroy(cache, "Cache"))
+        .build::<UserModule>()
+        .await
+        .unwrap();
+
+    assert_eq!(app.get("/users").await.status(), StatusCode::OK);
+    assert!(!destroyed.load(Ordering::SeqCst));
+    drop(app);
+    assert!(destroyed.load(Ordering::SeqCst));
+}
@@ -0,0 +1,955 @@
+use axum::body::Body;
+use axum::http::Request;
+use axum::routing::get;
+use meshestra::exception::ExceptionFilterLayer;
+use meshestra::interceptor::InterceptorLayer;
+use meshestra::prelude::*;
+use meshestra::testing::TransactionalTest;
+use meshestra::transactional::{
+    ForceRollback, NamedManagers, RollbackPolicy, TransactionContext, TransactionError,
+    TransactionOptions, TransactionalInterceptor, get_current_transaction,
+    get_current_transaction_for, is_rollback_only, on_commit, on_rollback, run_transactional,
+    set_rollback_only, with_current_transaction, with_current_transaction_async, with_manager,
+    with_named_managers,
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tower::ServiceExt;
+
+/// Transaction manager recording every begin/commit/rollback call
+#[derive(Default)]
+struct RecordingManager {
+    next_id: AtomicUsize,
+    log: Arc<Mutex<Vec<String>>>,
+}
+
+impl RecordingManager {
+    fn log(&self) -> Vec<String> {
+        self.log.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl TransactionManager for RecordingManager {
+    async fn begin(
+        &self,
+        _options: TransactionOptions,
+    ) -> std::result::Result<Box<dyn Transaction>, MeshestraError> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+        self.log.lock().unwrap().push(format!("begin:{}", id));
+        Ok(Box::new(RecordingTransaction {
+            id,
+            log: Arc::clone(&self.log),
+        }))
+    }
+}
+
+struct RecordingTransaction {
+    id: usize,
+    log: Arc<Mutex<Vec<String>>>,
+}
+
+impl RecordingTransaction {
+    fn record(&self, call: &str) {
+        self.log
+            .lock()
+            .unwrap()
+            .push(format!("{}:{}", call, self.id));
+    }
+}
+
+#[async_trait]
+impl Transaction for RecordingTransaction {
+    async fn commit(&mut self) -> std::result::Result<(), MeshestraError> {
+        self.record("commit");
+        Ok(())
+    }
+
+    async fn rollback(&mut self) -> std::result::Result<(), MeshestraError> {
+        self.record("rollback");
+        Ok(())
+    }
+
+    async fn savepoint(&mut self, _name: &str) -> std::result::Result<(), MeshestraError> {
+        self.record("savepoint");
+        Ok(())
+    }
+
+    async fn rollback_to_savepoint(
+        &mut self,
+        _name: &str,
+    ) -> std::result::Result<(), MeshestraError> {
+        self.record("rollback_to_savepoint");
+        Ok(())
+    }
+
+    async fn release_savepoint(&mut self, _name: &str) -> std::result::Result<(), MeshestraError> {
+        self.record("release_savepoint");
+        Ok(())
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+/// Id of the transaction currently active in this task, if any
+async fn current_tx_id() -> Option<usize> {
+    let tx = get_current_transaction()?;
+    let mut guard = tx.lock().await;
+    guard
+        .as_any_mut()
+        .downcast_mut::<RecordingTransaction>()
+        .map(|tx| tx.id)
+}
+
+fn context_depth() -> usize {
+    TransactionContext::current().map_or(0, |context| context.depth())
+}
+
+#[derive(Debug)]
+struct ValidationError(&'static str);
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "validation failed: {}", self.0)
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+struct OrderService {
+    transaction_manager: Arc<RecordingManager>,
+}
+
+impl OrderService {
+    fn new() -> Self {
+        Self {
+            transaction_manager: Arc::new(RecordingManager::default()),
+        }
+    }
+
+    #[transactional(manager = self.transaction_manager)]
+    async fn place_order(&self, audit_fails: bool) -> Result<Vec<Option<usize>>> {
+        let mut seen = vec![current_tx_id().await];
+        let audit = self.write_audit_log(audit_fails).await;
+        seen.push(audit.unwrap_or(None));
+        seen.push(current_tx_id().await);
+        Ok(seen)
+    }
+
+    #[transactional(manager = self.transaction_manager)]
+    async fn import_product(&self, enrichment_fails: bool) -> Result<bool> {
+        let enriched = self.enrich_product(enrichment_fails).await.is_ok();
+        Ok(enriched)
+    }
+
+    #[transactional(propagation = Nested, manager = self.transaction_manager)]
+    async fn enrich_product(&self, fail: bool) -> Result<Option<usize>> {
+        if fail {
+            return Err(MeshestraError::Internal("enrichment failed".to_string()));
+        }
+        Ok(current_tx_id().await)
+    }
+
+    #[transactional(propagation = Supports, manager = self.transaction_manager)]
+    async fn supports(&self) -> Result<Option<usize>> {
+        Ok(current_tx_id().await)
+    }
+
+    #[transactional(propagation = Mandatory, manager = self.transaction_manager)]
+    async fn mandatory(&self) -> Result<Option<usize>> {
+        Ok(current_tx_id().await)
+    }
+
+    #[transactional(propagation = Never, manager = self.transaction_manager)]
+    async fn never(&self) -> Result<Option<usize>> {
+        Ok(current_tx_id().await)
+    }
+
+    #[transactional(propagation = NotSupported, manager = self.transaction_manager)]
+    async fn not_supported(&self) -> Result<Option<usize>> {
+        Ok(current_tx_id().await)
+    }
+
+    /// Runs `call` inside a `Required` transaction and records what it saw
+    #[transactional(manager = self.transaction_manager)]
+    async fn within_transaction(&self, call: &str) -> Result<(Option<usize>, Option<usize>)> {
+        let seen = match call {
+            "supports" => self.supports().await,
+            "mandatory" => self.mandatory().await,
+            "never" => self.never().await,
+            "not_supported" => self.not_supported().await,
+            other => unreachable!("unknown call {}", other),
+        };
+        Ok((seen.unwrap_or(Some(0)), current_tx_id().await))
+    }
+
+    #[transactional(no_rollback_for = [ValidationError], manager = self.transaction_manager)]
+    async fn submit_form(&self, error: Option<BoxError>) -> std::result::Result<(), BoxError> {
+        match error {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    #[transactional(no_rollback_for = [ValidationError], manager = self.transaction_manager)]
+    async fn abandon_form(&self) -> std::result::Result<(), BoxError> {
+        set_rollback_only()?;
+        Err(Box::new(ValidationError("email")) as BoxError)
+    }
+
+    #[transactional(manager = self.transaction_manager)]
+    async fn dry_run(&self) -> Result<()> {
+        set_rollback_only()?;
+        Ok(())
+    }
+
+    #[transactional(manager = self.transaction_manager)]
+    async fn rename_product(&self, fail: bool) -> Result<()> {
+        self.invalidate_cache_on_commit().await?;
+        if fail {
+            return Err(MeshestraError::Internal("rename failed".to_string()));
+        }
+        Ok(())
+    }
+
+    #[transactional(manager = self.transaction_manager)]
+    async fn invalidate_cache_on_commit(&self) -> Result<()> {
+        let log = Arc::clone(&self.transaction_manager.log);
+        on_commit(move || async move {
+            let outside = get_current_transaction().is_none();
+            log.lock()
+                .unwrap()
+                .push(format!("after_commit(outside={})", outside));
+        })?;
+
+        let log = Arc::clone(&self.transaction_manager.log);
+        on_rollback(move || async move {
+            log.lock().unwrap().push("after_rollback".to_string());
+        })?;
+        Ok(())
+    }
+
+    #[transactional(manager = self.transaction_manager)]
+    async fn rename_with_optional_enrichment(&self) -> Result<()> {
+        let _ = self.enrich_with_callbacks().await;
+        Ok(())
+    }
+
+    #[transactional(propagation = Nested, manager = self.transaction_manager)]
+    async fn enrich_with_callbacks(&self) -> Result<()> {
+        self.invalidate_cache_on_commit().await?;
+        Err(MeshestraError::Internal("enrichment failed".to_string()))
+    }
+
+    /// Swallows the error of a joined step and carries on
+    #[transactional(manager = self.transaction_manager)]
+    async fn import_ignoring_failed_step(&self) -> Result<bool> {
+        let _ = self.failing_step().await;
+        Ok(is_rollback_only())
+    }
+
+    #[transactional(manager = self.transaction_manager)]
+    async fn failing_step(&self) -> Result<()> {
+        Err(MeshestraError::Internal("step failed".to_string()))
+    }
+
+    /// Depth seen before, inside and after a joined call
+    #[transactional(manager = self.transaction_manager)]
+    async fn depths(&self) -> Result<[usize; 3]> {
+        let before = context_depth();
+        let inner = self.joined_depth().await?;
+        Ok([before, inner, context_depth()])
+    }
+
+    #[transactional(manager = self.transaction_manager)]
+    async fn joined_depth(&self) -> Result<usize> {
+        Ok(context_depth())
+    }
+
+    #[transactional(propagation = RequiresNew, manager = self.transaction_manager)]
+    async fn write_audit_log(&self, fail: bool) -> Result<Option<usize>> {
+        if fail {
+            return Err(MeshestraError::Internal("audit failed".to_string()));
+        }
+        Ok(current_tx_id().await)
+    }
+}
+
+#[tokio::test]
+async fn requires_new_commits_independently_of_outer() {
+    let service = OrderService::new();
+
+    let seen = service.place_order(false).await.unwrap();
+
+    assert_eq!(seen, vec![Some(1), Some(2), Some(1)]);
+    assert_eq!(
+        service.transaction_manager.log(),
+        vec!["begin:1", "begin:2", "commit:2", "commit:1"]
+    );
+}
+
+#[tokio::test]
+async fn requires_new_rollback_does_not_poison_outer() {
+    let service = OrderService::new();
+
+    let seen = service.place_order(true).await.unwrap();
+
+    assert_eq!(seen, vec![Some(1), None, Some(1)]);
+    assert_eq!(
+        service.transaction_manager.log(),
+        vec!["begin:1", "begin:2", "rollback:2", "commit:1"]
+    );
+}
+
+#[tokio::test]
+async fn requires_new_without_outer_transaction() {
+    let service = OrderService::new();
+
+    assert_eq!(service.write_audit_log(false).await.unwrap(), Some(1));
+    assert_eq!(current_tx_id().await, None);
+    assert_eq!(
+        service.transaction_manager.log(),
+        vec!["begin:1", "commit:1"]
+    );
+}
+
+#[tokio::test]
+async fn nested_failure_rolls_back_to_savepoint_only() {
+    let service = OrderService::new();
+
+    assert!(!service.import_product(true).await.unwrap());
+    assert_eq!(
+        service.transaction_manager.log(),
+        vec![
+            "begin:1",
+            "savepoint:1",
+            "rollback_to_savepoint:1",
+            "commit:1"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn nested_success_releases_savepoint() {
+    let service = OrderService::new();
+
+    assert!(service.import_product(false).await.unwrap());
+    assert_eq!(
+        service.transaction_manager.log(),
+        vec!["begin:1", "savepoint:1", "release_savepoint:1", "commit:1"]
+    );
+}
+
+#[tokio::test]
+async fn nested_without_outer_transaction_behaves_like_required() {
+    let service = OrderService::new();
+
+    assert_eq!(service.enrich_product(false).await.unwrap(), Some(1));
+    assert_eq!(
+        service.transaction_manager.log(),
+        vec!["begin:1", "commit:1"]
+    );
+}
+
+#[tokio::test]
+async fn supports_joins_or_runs_plain() {
+    let service = OrderService::new();
+
+    assert_eq!(service.supports().await.unwrap(), None);
+    assert_eq!(
+        service.within_transaction("supports").await.unwrap(),
+        (Some(1), Some(1))
+    );
+    assert_eq!(
+        service.transaction_manager.log(),
+        vec!["begin:1", "commit:1"]
+    );
+}
+
+#[tokio::test]
+async fn mandatory_requires_active_transaction() {
+    let service = OrderService::new();
+
+    let err = service.mandatory().await.unwrap_err();
+    assert!(matches!(
+        err,
+        MeshestraError::Transaction(TransactionError::NoActiveTransaction)
+    ));
+    assert_eq!(
+        service.within_transaction("mandatory").await.unwrap(),
+        (Some(1), Some(1))
+    );
+    assert_eq!(
+        service.transaction_manager.log(),
+        vec!["begin:1", "commit:1"]
+    );
+}
+
+#[tokio::test]
+async fn never_rejects_active_transaction() {
+    let service = OrderService::new();
+
+    assert_eq!(service.never().await.unwrap(), None);
+    // The error is swallowed by `within_transaction` and reported as Some(0).
+    assert_eq!(
+        service.within_transaction("never").await.unwrap(),
+        (Some(0), Some(1))
+    );
+    assert_eq!(
+        service.transaction_manager.log(),
+        vec!["begin:1", "commit:1"]
+    );
+}
+
+#[tokio::test]
+async fn not_supported_suspends_and_restores() {
+    let service = OrderService::new();
+
+    assert_eq!(service.not_supported().await.unwrap(), None);
+    assert_eq!(
+        service.within_transaction("not_supported").await.unwrap(),
+        (None, Some(1))
+    );
+    assert_eq!(
+        service.transaction_manager.log(),
+        vec!["begin:1", "commit:1"]
+    );
+}
+
+#[tokio::test]
+async fn no_rollback_for_listed_error_commits() {
+    let service = OrderService::new();
+
+    let result = service
+        .submit_form(Some(Box::new(ValidationError("email"))))
+        .await;
+
+    assert!(result.is_err());
+    assert_eq!(
+        service.transaction_manager.log(),
+        vec!["begin:1", "commit:1"]
+    );
+}
+
+#[tokio::test]
+async fn rollback_only_wins_over_no_rollback_for() {
+    let service = OrderService::new();
+
+    let result = service.abandon_form().await;
+
+    assert!(result.is_err());
+    assert_eq!(
+        service.transaction_manager.log(),
+        vec!["begin:1", "rollback:1"]
+    );
+}
+
+#[tokio::test]
+async fn unlisted_error_still_rolls_back() {
+    let service = OrderService::new();
+
+    let result = service.submit_form(Some("connection reset".into())).await;
+
+    assert!(result.is_err());
+    assert_eq!(
+        service.transaction_manager.log(),
+        vec!["begin:1", "rollback:1"]
+    );
+}
+
+#[tokio::test]
+async fn set_rollback_only_rolls_back_ok_result() {
+    let service = OrderService::new();
+
+    service.dry_run().await.unwrap();
+
+    assert_eq!(
+        service.transaction_manager.log(),
+        vec!["begin:1", "rollback:1"]
+    );
+    assert!(matches!(
+        set_rollback_only(),
+        Err(TransactionError::NoActiveTransaction)
+    ));
+}
+
+struct ReportService {
+    tx: Arc<dyn TransactionManager>,
+}
+
+impl ReportService {
+    #[transactional(manager = self.tx)]
+    async fn generate(&self) -> Result<Option<usize>> {
+        Ok(current_tx_id().await)
+    }
+}
+
+#[transactional]
+async fn purge_expired_sessions() -> Result<Option<usize>> {
+    Ok(current_tx_id().await)
+}
+
+#[tokio::test]
+async fn manager_argument_names_the_field() {
+    let manager = Arc::new(RecordingManager::default());
+    let service = ReportService {
+        tx: manager.clone(),
+    };
+
+    assert_eq!(service.generate().await.unwrap(), Some(1));
+    assert_eq!(manager.log(), vec!["begin:1", "commit:1"]);
+}
+
+#[tokio::test]
+async fn free_function_uses_ambient_manager() {
+    let manager = Arc::new(RecordingManager::default());
+
+    let seen = with_manager(manager.clone(), purge_expired_sessions())
+        .await
+        .unwrap();
+
+    assert_eq!(seen, Some(1));
+    assert_eq!(manager.log(), vec!["begin:1", "commit:1"]);
+    assert!(matches!(
+        purge_expired_sessions().await,
+        Err(MeshestraError::Transaction(
+            TransactionError::NoTransactionManager
+        ))
+    ));
+}
+
+#[tokio::test]
+async fn on_commit_runs_after_outermost_commit() {
+    let service = OrderService::new();
+
+    service.rename_product(false).await.unwrap();
+
+    assert_eq!(
+        service.transaction_manager.log(),
+        vec!["begin:1", "commit:1", "after_commit(outside=true)"]
+    );
+}
+
+#[tokio::test]
+async fn joined_error_marks_outer_transaction_rollback_only() {
+    let service = OrderService::new();
+
+    let marked = service.import_ignoring_failed_step().await.unwrap();
+
+    assert!(marked);
+    assert_eq!(
+        service.transaction_manager.log(),
+        vec!["begin:1", "rollback:1"]
+    );
+}
+
+#[tokio::test]
+async fn joined_scopes_raise_context_depth() {
+    let service = OrderService::new();
+
+    assert_eq!(service.depths().await.unwrap(), [1, 2, 1]);
+    assert_eq!(context_depth(), 0);
+}
+
+#[tokio::test]
+async fn on_rollback_runs_after_rollback() {
+    let service = OrderService::new();
+
+    service.rename_product(true).await.unwrap_err();
+
+    assert_eq!(
+        service.transaction_manager.log(),
+        vec!["begin:1", "rollback:1", "after_rollback"]
+    );
+}
+
+#[tokio::test]
+async fn savepoint_rollback_runs_only_its_rollback_callbacks() {
+    let service = OrderService::new();
+
+    service.rename_with_optional_enrichment().await.unwrap();
+
+    assert_eq!(
+        service.transaction_manager.log(),
+        vec![
+            "begin:1",
+            "savepoint:1",
+            "rollback_to_savepoint:1",
+            "after_rollback",
+            "commit:1"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn callbacks_without_transaction_are_rejected() {
+    assert!(matches!(
+        on_commit(|| async {}),
+        Err(TransactionError::NoActiveTransaction)
+    ));
+    assert!(matches!(
+        on_rollback(|| async {}),
+        Err(TransactionError::NoActiveTransaction)
+    ));
+}
+
+/// Run a handler answering `status` through a `TransactionalInterceptor`
+/// and return the manager's log
+async fn intercept_status(policy: RollbackPolicy, status: StatusCode, force: bool) -> Vec<String> {
+    let manager = Arc::new(RecordingManager::default());
+    let interceptor = TransactionalInterceptor::new(manager.clone(), policy);
+    let next = Next::new(move |_request| {
+        Box::pin(async move {
+            let mut response = status.into_response();
+            if force {
+                response.extensions_mut().insert(ForceRollback);
+            }
+            Ok(response)
+        })
+    });
+
+    let request = Request::new(Body::empty());
+    let response = interceptor.intercept(request, next).await.unwrap();
+    assert_eq!(response.status(), status);
+    manager.log()
+}
+
+#[tokio::test]
+async fn default_policy_rolls_back_only_server_errors() {
+    for (status, expected) in [
+        (StatusCode::OK, "commit:1"),
+        (StatusCode::CONFLICT, "commit:1"),
+        (StatusCode::UNPROCESSABLE_ENTITY, "commit:1"),
+        (StatusCode::INTERNAL_SERVER_ERROR, "rollback:1"),
+    ] {
+        let log = intercept_status(RollbackPolicy::default(), status, false).await;
+        assert_eq!(log, vec!["begin:1", expected], "status {}", status);
+    }
+}
+
+#[tokio::test]
+async fn policy_rolls_back_on_listed_status() {
+    let policy = RollbackPolicy::builder()
+        .rollback_on_server_error()
+        .rollback_on(StatusCode::CONFLICT)
+        .commit_on_client_error()
+        .build();
+
+    for (status, expected) in [
+        (StatusCode::OK, "commit:1"),
+        (StatusCode::CONFLICT, "rollback:1"),
+        (StatusCode::UNPROCESSABLE_ENTITY, "commit:1"),
+        (StatusCode::INTERNAL_SERVER_ERROR, "rollback:1"),
+    ] {
+        let log = intercept_status(policy.clone(), status, false).await;
+        assert_eq!(log, vec!["begin:1", expected], "status {}", status);
+    }
+}
+
+#[tokio::test]
+async fn policy_commit_on_overrides_error_class() {
+    let policy = RollbackPolicy::builder()
+        .rollback_on_client_error()
+        .commit_on(StatusCode::UNPROCESSABLE_ENTITY)
+        .build();
+
+    for (status, expected) in [
+        (StatusCode::OK, "commit:1"),
+        (StatusCode::CONFLICT, "rollback:1"),
+        (StatusCode::UNPROCESSABLE_ENTITY, "commit:1"),
+        (StatusCode::INTERNAL_SERVER_ERROR, "commit:1"),
+    ] {
+        let log = intercept_status(policy.clone(), status, false).await;
+        assert_eq!(log, vec!["begin:1", expected], "status {}", status);
+    }
+}
+
+#[tokio::test]
+async fn force_rollback_extension_overrides_policy() {
+    let log = intercept_status(RollbackPolicy::default(), StatusCode::OK, true).await;
+    assert_eq!(log, vec!["begin:1", "rollback:1"]);
+}
+
+/// Transaction manager whose transactions can neither commit nor roll back
+struct BrokenManager;
+
+#[async_trait]
+impl TransactionManager for BrokenManager {
+    async fn begin(
+        &self,
+        _options: TransactionOptions,
+    ) -> std::result::Result<Box<dyn Transaction>, MeshestraError> {
+        Ok(Box::new(BrokenTransaction))
+    }
+}
+
+struct BrokenTransaction;
+
+#[async_trait]
+impl Transaction for BrokenTransaction {
+    async fn commit(&mut self) -> std::result::Result<(), MeshestraError> {
+        Err(MeshestraError::Internal("commit failed".to_string()))
+    }
+
+    async fn rollback(&mut self) -> std::result::Result<(), MeshestraError> {
+        Err(MeshestraError::Internal("rollback failed".to_string()))
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+#[tokio::test]
+async fn failed_rollback_keeps_the_handler_error() {
+    let interceptor =
+        TransactionalInterceptor::new(Arc::new(BrokenManager), RollbackPolicy::default());
+    let next = Next::new(|_request| {
+        Box::pin(async { Err(Box::new(ValidationError("bad order")) as BoxError) })
+    });
+
+    let error = interceptor
+        .intercept(Request::new(Body::empty()), next)
+        .await
+        .unwrap_err();
+
+    assert_eq!(error.to_string(), "validation failed: bad order");
+}
+
+#[tokio::test]
+async fn failed_commit_replaces_the_response() {
+    let interceptor =
+        TransactionalInterceptor::new(Arc::new(BrokenManager), RollbackPolicy::default());
+    let next = Next::new(|_request| Box::pin(async { Ok(StatusCode::OK.into_response()) }));
+
+    let error = interceptor
+        .intercept(Request::new(Body::empty()), next)
+        .await
+        .unwrap_err();
+
+    assert!(error.to_string().contains("commit failed"));
+}
+
+/// Id of the active transaction of the manager registered as `name`
+async fn named_tx_id(name: &str) -> Option<usize> {
+    let tx = get_current_transaction_for(name)?;
+    let mut guard = tx.lock().await;
+    guard
+        .as_any_mut()
+        .downcast_mut::<RecordingTransaction>()
+        .map(|tx| tx.id)
+}
+
+struct DualStoreService {
+    transaction_manager: Arc<RecordingManager>,
+}
+
+impl DualStoreService {
+    /// Returns the (primary, analytics) transaction ids seen by `record_event`
+    /// and then by this method after it returned
+    #[transactional(manager = self.transaction_manager)]
+    async fn save_order(&self) -> Result<[(Option<usize>, Option<usize>); 2]> {
+        let inner = self.record_event().await?;
+        let outer = (current_tx_id().await, named_tx_id("analytics").await);
+        Ok([inner, outer])
+    }
+
+    #[transactional(manager_name = "analytics")]
+    async fn record_event(&self) -> Result<(Option<usize>, Option<usize>)> {
+        Ok((current_tx_id().await, named_tx_id("analytics").await))
+    }
+
+    #[transactional(manager_name = "missing")]
+    async fn unknown_manager(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn named_manager_does_not_shadow_default_transaction() {
+    let analytics = Arc::new(RecordingManager::default());
+    let mut container = Container::new();
+    container
+        .register_named_manager("analytics", analytics.clone())
+        .unwrap();
+    let managers = container.resolve::<NamedManagers>().unwrap();
+
+    let service = DualStoreService {
+        transaction_manager: Arc::new(RecordingManager::default()),
+    };
+    let [inner, outer] = with_named_managers(managers, service.save_order())
+        .await
+        .unwrap();
+
+    assert_eq!(inner, (Some(1), Some(1)));
+    assert_eq!(outer, (Some(1), None));
+    assert_eq!(
+        service.transaction_manager.log(),
+        vec!["begin:1", "commit:1"]
+    );
+    assert_eq!(analytics.log(), vec!["begin:1", "commit:1"]);
+}
+
+#[tokio::test]
+async fn named_managers_belong_to_their_container() {
+    let analytics = Arc::new(RecordingManager::default());
+    let mut container = Container::new();
+    container
+        .register_named_manager("analytics", analytics.clone())
+        .unwrap();
+    let mut other = Container::new();
+    other
+        .register_named_manager("analytics", Arc::new(RecordingManager::default()))
+        .unwrap();
+
+    let service = DualStoreService {
+        transaction_manager: Arc::new(RecordingManager::default()),
+    };
+    let managers = container.resolve::<NamedManagers>().unwrap();
+    with_named_managers(managers, service.record_event())
+        .await
+        .unwrap();
+
+    assert_eq!(analytics.log(), vec!["begin:1", "commit:1"]);
+    assert!(service.record_event().await.is_err(), "no managers outside");
+}
+
+#[tokio::test]
+async fn unknown_manager_name_is_an_error() {
+    let service = DualStoreService {
+        transaction_manager: Arc::new(RecordingManager::default()),
+    };
+    let error = service.unknown_manager().await.unwrap_err();
+    assert!(matches!(
+        error,
+        MeshestraError::Transaction(TransactionError::UnknownManager { ref name }) if name == "missing"
+    ));
+}
+
+struct OtherTransaction;
+
+#[async_trait]
+impl Transaction for OtherTransaction {
+    async fn commit(&mut self) -> std::result::Result<(), MeshestraError> {
+        Ok(())
+    }
+
+    async fn rollback(&mut self) -> std::result::Result<(), MeshestraError> {
+        Ok(())
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+#[tokio::test]
+async fn typed_access_without_transaction_is_none() {
+    let id = with_current_transaction(|tx: &mut RecordingTransaction| tx.id).await;
+    assert_eq!(id, None);
+}
+
+#[tokio::test]
+async fn typed_access_downcasts_active_transaction() {
+    let manager = RecordingManager::default();
+    let ids = run_transactional(&manager, TransactionOptions::default(), async {
+        let id = with_current_transaction(|tx: &mut RecordingTransaction| tx.id).await;
+        let async_id = with_current_transaction_async(async |tx: &mut RecordingTransaction| {
+            tokio::task::yield_now().await;
+            tx.record("queried");
+            tx.id
+        })
+        .await;
+        Ok::<_, MeshestraError>((id, async_id))
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(ids, (Some(1), Some(1)));
+    assert_eq!(manager.log(), vec!["begin:1", "queried:1", "commit:1"]);
+}
+
+#[tokio::test]
+#[should_panic(expected = "not the expected `transactional::OtherTransaction`")]
+async fn typed_access_panics_on_wrong_type() {
+    let manager = RecordingManager::default();
+    let _ = run_transactional(&manager, TransactionOptions::default(), async {
+        with_current_transaction(|_: &mut OtherTransaction| ()).await;
+        Ok::<_, MeshestraError>(())
+    })
+    .await;
+}
+
+async fn panics() -> &'static str {
+    panic!("handler bug")
+}
+
+#[tokio::test]
+async fn panicking_handler_rolls_back_and_answers_500() {
+    let manager = Arc::new(RecordingManager::default());
+    let interceptor = TransactionalInterceptor::new(manager.clone(), RollbackPolicy::default());
+    let app: Router = Router::new()
+        .route("/", get(panics))
+        .layer(InterceptorLayer::new(vec![Box::new(interceptor)]))
+        .layer(ExceptionFilterLayer::new(Vec::new()));
+
+    let response = app
+        .oneshot(Request::get("/").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(body["error"]["code"], "InternalServerError");
+    assert_eq!(manager.log(), vec!["begin:1", "rollback:1"]);
+}
+
+#[tokio::test]
+async fn test_transactions_are_joined_and_rolled_back() {
+    let manager = Arc::new(RecordingManager::default());
+    let service = OrderService::new();
+
+    let seen = TransactionalTest::wrap(manager.clone(), async {
+        service.rename_product(false).await.unwrap();
+        let joined = service.within_transaction("supports").await.unwrap();
+        (joined, is_rollback_only())
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(seen, ((Some(1), Some(1)), true));
+    assert_eq!(manager.log(), vec!["begin:1", "rollback:1"]);
+    // Only the rollback callback of `rename_product` ran.
+    assert_eq!(service.transaction_manager.log(), vec!["after_rollback"]);
+}
+
+#[tokio::test]
+async fn panicking_tests_still_roll_back() {
+    let manager = Arc::new(RecordingManager::default());
+
+    let test = TransactionalTest::wrap(manager.clone(), async {
+        OrderService::new().supports().await.unwrap();
+        panic!("assertion failed");
+    });
+    let result = tokio::spawn(test).await;
+
+    assert!(result.unwrap_err().is_panic());
+    assert_eq!(manager.log(), vec!["begin:1", "rollback:1"]);
+}
+
+#[meshestra::test(transactional(manager = Arc::new(RecordingManager::default())))]
+async fn test_attribute_runs_the_body_in_a_transaction() -> Result<()> {
+    let service = OrderService::new();
+
+    assert_eq!(service.mandatory().await?, Some(1));
+    assert!(is_rollback_only());
+    assert!(service.transaction_manager.log().is_empty());
+    Ok(())
+}
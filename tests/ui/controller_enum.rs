@@ -0,0 +1,9 @@
+use meshestra::prelude::*;
+
+#[controller(path = "/users")]
+pub enum UserController {
+    Admin,
+    Guest,
+}
+
+fn main() {}
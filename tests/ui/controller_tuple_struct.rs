@@ -0,0 +1,8 @@
+use meshestra::prelude::*;
+
+pub struct UserService;
+
+#[controller(path = "/users")]
+pub struct UserController(Arc<UserService>);
+
+fn main() {}
@@ -0,0 +1,14 @@
+use meshestra::prelude::*;
+
+#[controller(path = "/users")]
+pub struct UserController {}
+
+#[routes(UserController)]
+impl UserController {
+    #[get("/{id}/friends/{id}")]
+    async fn handler(&self, #[param] id: u32) -> String {
+        String::new()
+    }
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+use meshestra::prelude::*;
+
+pub trait Clock: Send + Sync {}
+
+#[derive(Injectable)]
+pub struct SystemClock {}
+
+impl Clock for SystemClock {}
+
+#[module(providers = [Provider::new(SystemClock).for_trait()])]
+pub struct ClockModule;
+
+fn main() {}
@@ -0,0 +1,9 @@
+use meshestra::prelude::*;
+
+#[derive(Injectable)]
+pub enum Clock {
+    System,
+    Manual,
+}
+
+fn main() {}
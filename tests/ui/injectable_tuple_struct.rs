@@ -0,0 +1,9 @@
+use meshestra::prelude::*;
+use std::sync::Arc;
+
+pub struct UserRepository;
+
+#[derive(Injectable)]
+pub struct UserService(Arc<UserRepository>);
+
+fn main() {}
@@ -0,0 +1,33 @@
+use meshestra::prelude::*;
+
+#[derive(Clone)]
+struct LimitLayer;
+
+impl LimitLayer {
+    fn new(max: usize) -> Self {
+        let _ = max;
+        LimitLayer
+    }
+}
+
+impl<S> tower::Layer<S> for LimitLayer {
+    type Service = S;
+
+    fn layer(&self, inner: S) -> S {
+        inner
+    }
+}
+
+#[controller(path = "/uploads")]
+pub struct UploadController {}
+
+#[routes(UploadController)]
+impl UploadController {
+    #[post("/")]
+    #[middleware(LimitLayer::new("1kb"))]
+    async fn upload(&self) -> &'static str {
+        "uploaded"
+    }
+}
+
+fn main() {}
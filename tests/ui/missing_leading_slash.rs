@@ -0,0 +1,14 @@
+use meshestra::prelude::*;
+
+#[controller(path = "/users")]
+pub struct UserController {}
+
+#[routes(UserController)]
+impl UserController {
+    #[get("posts")]
+    async fn handler(&self) -> String {
+        String::new()
+    }
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use meshestra::prelude::*;
+
+#[derive(Injectable)]
+pub struct UserService {}
+
+#[module(services = [UserService])]
+pub struct UserModule;
+
+fn main() {}
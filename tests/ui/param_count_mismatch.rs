@@ -0,0 +1,14 @@
+use meshestra::prelude::*;
+
+#[controller(path = "/users")]
+pub struct UserController {}
+
+#[routes(UserController)]
+impl UserController {
+    #[get("/{id}/posts/{post}")]
+    async fn handler(&self, #[param] id: u32, #[param] post: u32, #[param] page: u32) -> String {
+        String::new()
+    }
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+use meshestra::prelude::*;
+
+/// A method has to name its transaction manager
+pub struct TransferService;
+
+impl TransferService {
+    #[transactional]
+    pub async fn transfer(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+fn main() {}
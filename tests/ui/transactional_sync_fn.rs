@@ -0,0 +1,8 @@
+use meshestra::prelude::*;
+
+#[transactional(manager = ambient)]
+fn transfer() -> Result<()> {
+    Ok(())
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+use meshestra::prelude::*;
+
+#[controller(path = "/users")]
+pub struct UserController {}
+
+#[routes(UserController)]
+impl UserController {
+    #[get("/{id}/posts/{post}")]
+    async fn handler(&self, #[param] ids: (u32, u32, u32)) -> String {
+        String::new()
+    }
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+use meshestra::prelude::*;
+
+#[controller(path = "/users")]
+pub struct UserController {}
+
+#[routes(UserController)]
+impl UserController {
+    #[get("/{org_id}/repos/{repo_id}")]
+    async fn handler(&self, #[param("repo")] repo: i64) -> String {
+        String::new()
+    }
+}
+
+fn main() {}
@@ -0,0 +1,232 @@
+#![cfg(feature = "validation")]
+
+mod common;
+
+use axum::body::{Body, to_bytes};
+use axum::http::{Request, header};
+use common::AppState;
+use meshestra::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tower::ServiceExt;
+use validator::Validate;
+
+/// `Serialize`, as `length` reports the rejected list
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct Tag {
+    #[validate(length(min = 2, message = "tags have at least 2 characters"))]
+    name: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct Profile {
+    #[validate(url)]
+    homepage: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateUser {
+    #[validate(email(message = "not an email address"))]
+    email: String,
+    #[validate(nested)]
+    profile: Profile,
+    #[validate(nested, length(max = 3))]
+    tags: Vec<Tag>,
+}
+
+#[derive(Debug, Deserialize, Validate, MessageKeys)]
+pub struct Signup {
+    #[validate(email)]
+    #[message(key = "signup.email.invalid")]
+    email: String,
+    #[validate(length(min = 8))]
+    password: String,
+}
+
+/// How often the handler ran
+#[derive(Default)]
+pub struct Calls(AtomicUsize);
+
+#[controller(path = "/users")]
+pub struct UserController {
+    calls: Arc<Calls>,
+}
+
+#[routes(UserController)]
+impl UserController {
+    #[post("/")]
+    async fn create(&self, #[body(validate)] user: CreateUser) -> String {
+        self.calls.0.fetch_add(1, Ordering::SeqCst);
+        format!("created {}", user.email)
+    }
+}
+
+#[controller(path = "/signups")]
+pub struct SignupController {}
+
+#[routes(SignupController)]
+impl SignupController {
+    #[post("/")]
+    async fn create(&self, #[body(validate)] signup: Signup) -> String {
+        format!("welcome {}", signup.email)
+    }
+}
+
+fn app() -> (Router, Arc<Calls>) {
+    let mut container = Container::new();
+    container.register(Calls::default());
+    let calls = container.resolve::<Calls>().unwrap();
+    let controller = UserController::inject(&container).unwrap();
+
+    let router = UserController::router(Arc::new(controller)).with_state(AppState {
+        container: Arc::new(container),
+    });
+    (router, calls)
+}
+
+/// Signups, answered with the messages of `catalog`
+fn signup_app(catalog: MessageCatalog) -> Router {
+    let mut container = Container::new();
+    container.register(catalog);
+    container.register_trait::<dyn MessageResolver, MessageCatalog, _>(|catalog| {
+        catalog as Arc<dyn MessageResolver>
+    });
+    let controller = SignupController::inject(&container).unwrap();
+
+    SignupController::router(Arc::new(controller)).with_state(AppState {
+        container: Arc::new(container),
+    })
+}
+
+async fn post(app: Router, body: Value) -> (StatusCode, Value) {
+    post_in(app, body, "en").await
+}
+
+async fn post_in(app: Router, body: Value, accept_language: &str) -> (StatusCode, Value) {
+    let request = Request::post("/")
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::ACCEPT_LANGUAGE, accept_language)
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body = serde_json::from_slice(&bytes)
+        .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&bytes).into_owned()));
+    (status, body)
+}
+
+#[tokio::test]
+async fn valid_body_reaches_the_handler() {
+    let (app, calls) = app();
+
+    let (status, body) = post(
+        app,
+        json!({
+            "email": "ada@example.com",
+            "profile": { "homepage": "https://example.com" },
+            "tags": [{ "name": "rust" }],
+        }),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, Value::String("created ada@example.com".to_string()));
+    assert_eq!(calls.0.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn invalid_body_is_answered_with_422_listing_the_fields() {
+    let (app, calls) = app();
+
+    let (status, body) = post(
+        app,
+        json!({
+            "email": "ada",
+            "profile": { "homepage": "not a url" },
+            "tags": [{ "name": "rust" }, { "name": "x" }],
+        }),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["error"]["code"], "UnprocessableEntity");
+    assert_eq!(
+        body["error"]["details"],
+        json!([
+            { "field": "email", "code": "email", "message": "not an email address" },
+            { "field": "profile.homepage", "code": "url", "message": "url" },
+            { "field": "tags[1].name", "code": "length", "message": "tags have at least 2 characters" },
+        ])
+    );
+    assert_eq!(calls.0.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn collection_rules_apply_to_the_collection_itself() {
+    let (app, _) = app();
+    let tags: Vec<Value> = ["ab", "cd", "ef", "gh"]
+        .iter()
+        .map(|name| json!({ "name": name }))
+        .collect();
+
+    let (status, body) = post(
+        app,
+        json!({
+            "email": "ada@example.com",
+            "profile": { "homepage": "https://example.com" },
+            "tags": tags,
+        }),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(
+        body["error"]["details"],
+        json!([{ "field": "tags", "code": "length", "message": "length" }])
+    );
+}
+
+#[tokio::test]
+async fn messages_are_answered_in_the_requested_language() {
+    let catalog = MessageCatalog::new("en")
+        .with_messages(
+            "en",
+            [("signup.email.invalid", "{field} is not an email address")],
+        )
+        .with_messages(
+            "ko",
+            [
+                ("signup.email.invalid", "{field}: 이메일 주소가 아닙니다"),
+                ("length", "{field}: {min}자 이상이어야 합니다"),
+            ],
+        );
+    let signup = json!({ "email": "ada", "password": "short" });
+
+    let (status, body) = post_in(
+        signup_app(catalog.clone()),
+        signup.clone(),
+        "ko-KR, en;q=0.5",
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(
+        body["error"]["details"],
+        json!([
+            { "field": "email", "code": "email", "message": "email: 이메일 주소가 아닙니다" },
+            { "field": "password", "code": "length", "message": "password: 8자 이상이어야 합니다" },
+        ])
+    );
+
+    // Unknown languages get the default locale, and keys without a message
+    // are their own message.
+    let (_, body) = post_in(signup_app(catalog), signup, "ja").await;
+    assert_eq!(
+        body["error"]["details"],
+        json!([
+            { "field": "email", "code": "email", "message": "email is not an email address" },
+            { "field": "password", "code": "length", "message": "length" },
+        ])
+    );
+}
@@ -0,0 +1,183 @@
+mod common;
+
+use axum::body::{Body, to_bytes};
+use axum::http::Request;
+use common::AppState;
+use meshestra::prelude::*;
+use tower::ServiceExt;
+
+#[controller(path = "/users", version = "v1")]
+pub struct UserV1Controller {}
+
+#[routes(UserV1Controller)]
+impl UserV1Controller {
+    #[get("/")]
+    async fn list(&self) -> &'static str {
+        "users v1"
+    }
+}
+
+#[derive(Injectable)]
+pub struct UserV2Controller {}
+
+#[controller(path = "/users", version = "2")]
+impl UserV2Controller {
+    #[get("/")]
+    async fn list(&self) -> &'static str {
+        "users v2"
+    }
+
+    #[get("/")]
+    #[version("V3")]
+    async fn list_v3(&self) -> &'static str {
+        "users v3"
+    }
+}
+
+#[controller(path = "/")]
+pub struct HealthController {}
+
+#[routes(HealthController)]
+impl HealthController {
+    #[get("/health")]
+    async fn health(&self) -> &'static str {
+        "ok"
+    }
+}
+
+#[module(controllers = [UserV1Controller, UserV2Controller, HealthController])]
+pub struct AppModule;
+
+async fn app(versioning: VersioningConfig) -> Router {
+    let app = Application::builder()
+        .container(AppModule::create_container().unwrap())
+        .module::<AppModule>()
+        .versioning(versioning)
+        .build()
+        .await
+        .unwrap();
+    let state = AppState {
+        container: app.container().clone(),
+    };
+    app.router::<AppModule, AppState>()
+        .unwrap()
+        .with_state(state)
+}
+
+async fn send(app: Router, request: Request<Body>) -> (StatusCode, String) {
+    let response = app.oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, String::from_utf8(bytes.to_vec()).unwrap())
+}
+
+async fn get(app: Router, uri: &str) -> (StatusCode, String) {
+    send(app, Request::get(uri).body(Body::empty()).unwrap()).await
+}
+
+async fn get_version(app: Router, version: &str) -> (StatusCode, String) {
+    let request = Request::get("/users")
+        .header("X-Api-Version", version)
+        .body(Body::empty())
+        .unwrap();
+    send(app, request).await
+}
+
+fn available_versions(body: &str) -> serde_json::Value {
+    let body: serde_json::Value = serde_json::from_str(body).unwrap();
+    body["error"]["details"]["available_versions"].clone()
+}
+
+#[tokio::test]
+async fn uri_versions_are_path_prefixes() {
+    let app = app(VersioningConfig::Uri).await;
+
+    assert_eq!(
+        get(app.clone(), "/v1/users").await,
+        (StatusCode::OK, "users v1".to_string())
+    );
+    assert_eq!(
+        get(app.clone(), "/v2/users").await,
+        (StatusCode::OK, "users v2".to_string())
+    );
+    assert_eq!(
+        get(app.clone(), "/v3/users").await,
+        (StatusCode::OK, "users v3".to_string())
+    );
+    assert_eq!(
+        get(app.clone(), "/health").await,
+        (StatusCode::OK, "ok".to_string())
+    );
+    assert_eq!(get(app, "/users").await.0, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn unknown_uri_version_lists_the_available_ones() {
+    let (status, body) = get(app(VersioningConfig::Uri).await, "/v9/users").await;
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(
+        available_versions(&body),
+        serde_json::json!(["v1", "v2", "v3"])
+    );
+}
+
+#[tokio::test]
+async fn header_picks_the_version() {
+    let app = app(VersioningConfig::header()).await;
+
+    assert_eq!(
+        get_version(app.clone(), "1").await,
+        (StatusCode::OK, "users v1".to_string())
+    );
+    assert_eq!(
+        get_version(app.clone(), "v2").await,
+        (StatusCode::OK, "users v2".to_string())
+    );
+    assert_eq!(
+        get(app.clone(), "/health").await,
+        (StatusCode::OK, "ok".to_string())
+    );
+    assert_eq!(get(app.clone(), "/v1/users").await.0, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn without_the_header_the_highest_version_answers() {
+    let app = app(VersioningConfig::header()).await;
+
+    assert_eq!(
+        get(app, "/users").await,
+        (StatusCode::OK, "users v3".to_string())
+    );
+}
+
+#[tokio::test]
+async fn unknown_header_version_lists_the_available_ones() {
+    let (status, body) = get_version(app(VersioningConfig::header()).await, "9").await;
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(
+        available_versions(&body),
+        serde_json::json!(["v1", "v2", "v3"])
+    );
+}
+
+#[test]
+fn routes_of_different_versions_dont_conflict() {
+    let routes = AppModule::routes().unwrap();
+
+    let routes: Vec<_> = routes
+        .iter()
+        .map(|route| (route.path.as_str(), route.handler, route.version))
+        .collect();
+
+    assert_eq!(
+        routes,
+        [
+            ("/users", "UserV1Controller::list", Some("v1")),
+            ("/users", "UserV2Controller::list", Some("v2")),
+            ("/users", "UserV2Controller::list_v3", Some("v3")),
+            ("/health", "HealthController::health", None),
+        ]
+    );
+}
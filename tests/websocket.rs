@@ -0,0 +1,155 @@
+#![cfg(feature = "websocket")]
+
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, header};
+use common::AppState;
+use futures_util::{SinkExt, StreamExt};
+use meshestra::guard::{GuardContext, GuardError};
+use meshestra::prelude::*;
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpListener;
+use tokio_tungstenite::tungstenite::Message;
+use tower::ServiceExt;
+
+/// Lets requests through when they carry an `x-user` header
+struct AuthGuard;
+
+#[async_trait]
+impl Guard for AuthGuard {
+    async fn can_activate(&self, ctx: &GuardContext<'_>) -> GuardResult {
+        if ctx.headers().contains_key("x-user") {
+            Ok(())
+        } else {
+            Err(GuardError::Unauthorized("login required".to_string()))
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ChatMessage {
+    text: String,
+}
+
+#[derive(Serialize)]
+struct Posted {
+    room: String,
+    text: String,
+}
+
+#[controller(path = "/chat")]
+pub struct ChatController {}
+
+#[routes(ChatController)]
+impl ChatController {
+    #[ws("/rooms/{room}")]
+    async fn room(&self, mut session: WebSocketSession, #[param] room: String) {
+        while let Some(Ok(message)) = session.recv_json::<ChatMessage>().await {
+            let posted = Posted {
+                room: room.clone(),
+                text: message.text,
+            };
+            session.send_json(&posted).unwrap();
+        }
+    }
+
+    #[ws("/private")]
+    #[guard(AuthGuard)]
+    async fn private(&self, session: WebSocketSession) {
+        session.send_text("welcome").unwrap();
+    }
+}
+
+fn router(container: Arc<Container>) -> Router {
+    Router::new()
+        .nest(
+            ChatController::base_path(),
+            ChatController::router(Arc::new(ChatController {})),
+        )
+        .with_state(AppState { container })
+}
+
+/// Serve `container`'s routes on a free port, returning its address
+async fn serve(container: Arc<Container>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, router(container)).await.unwrap();
+    });
+    format!("ws://{}", address)
+}
+
+#[tokio::test]
+async fn sessions_exchange_json_messages() {
+    let address = serve(Arc::new(Container::new())).await;
+    let (mut socket, _) = tokio_tungstenite::connect_async(format!("{}/chat/rooms/rust", address))
+        .await
+        .unwrap();
+
+    socket
+        .send(Message::Text(r#"{"text":"hello"}"#.into()))
+        .await
+        .unwrap();
+
+    let reply = socket.next().await.unwrap().unwrap();
+    assert_eq!(
+        reply.into_text().unwrap().as_str(),
+        r#"{"room":"rust","text":"hello"}"#
+    );
+}
+
+#[tokio::test]
+async fn guards_run_before_the_upgrade() {
+    let upgrade = |user: Option<&str>| {
+        let mut request = Request::get("/chat/private")
+            .header(header::CONNECTION, "upgrade")
+            .header(header::UPGRADE, "websocket")
+            .header(header::SEC_WEBSOCKET_VERSION, "13")
+            .header(header::SEC_WEBSOCKET_KEY, "dGhlIHNhbXBsZSBub25jZQ==");
+        if let Some(user) = user {
+            request = request.header("x-user", user);
+        }
+        request.body(Body::empty()).unwrap()
+    };
+    let mut container = Container::new();
+    container.register(AuthGuard);
+    let app = router(Arc::new(container));
+
+    let response = app.clone().oneshot(upgrade(None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // Let through, the upgrade itself fails without a real connection.
+    let response = app.oneshot(upgrade(Some("ada"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UPGRADE_REQUIRED);
+}
+
+#[tokio::test]
+async fn registry_broadcasts_and_closes_on_shutdown() {
+    let mut container = Container::new();
+    container.register(WsConnectionRegistry::new());
+    let app = Application::builder()
+        .container(container)
+        .build()
+        .await
+        .unwrap();
+    let sessions = app.container().resolve::<WsConnectionRegistry>().unwrap();
+    let address = serve(app.container().clone()).await;
+
+    let (mut socket, _) = tokio_tungstenite::connect_async(format!("{}/chat/rooms/rust", address))
+        .await
+        .unwrap();
+    while sessions.is_empty() {
+        tokio::task::yield_now().await;
+    }
+
+    assert_eq!(sessions.broadcast_json(&"news").unwrap(), 1);
+    let message = socket.next().await.unwrap().unwrap();
+    assert_eq!(message.into_text().unwrap().as_str(), "\"news\"");
+
+    app.shutdown().await.unwrap();
+    match socket.next().await.unwrap().unwrap() {
+        Message::Close(Some(frame)) => assert_eq!(u16::from(frame.code), 1001),
+        other => panic!("expected a close frame, got {:?}", other),
+    }
+}